@@ -0,0 +1,124 @@
+//! Example of answering an agent's questions from the terminal
+//!
+//! The model can ask for things only a person knows, like a 2FA code, with the
+//! `ask_human` action. The run waits until the answer is given through the
+//! agent's `AgentHandle`; here a task reads each question's answer from stdin.
+//!
+//! Usage:
+//!   cargo run --example ask_human
+//!
+//! Requirements:
+//!   - Chrome/Chromium browser installed
+
+use async_trait::async_trait;
+use browsing::agent::service::Agent;
+use browsing::browser::{Browser, BrowserProfile};
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::Result;
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use serde_json::json;
+
+/// Mock LLM that asks for a code and reports it back
+/// In production, implement your own ChatModel
+struct AskingLLM {
+    current_index: std::sync::Mutex<usize>,
+}
+
+#[async_trait]
+impl ChatModel for AskingLLM {
+    fn model(&self) -> &str {
+        "asking-llm"
+    }
+
+    fn provider(&self) -> &str {
+        "demo"
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        let mut index = self.current_index.lock().unwrap();
+        *index += 1;
+        let response = if *index == 1 {
+            json!({
+                "next_goal": "Get the login code from the user",
+                "action": [{
+                    "action_type": "ask_human",
+                    "params": { "question": "Which code was sent to your phone?" }
+                }]
+            })
+        } else {
+            // The reply is in the state message of the step after the question
+            let reply = messages
+                .last()
+                .and_then(|message| {
+                    message
+                        .content
+                        .lines()
+                        .find(|line| line.starts_with("Human's reply"))
+                })
+                .unwrap_or("no reply")
+                .to_string();
+            json!({
+                "next_goal": "Report the code",
+                "action": [{ "action_type": "done", "params": { "text": reply, "success": true } }]
+            })
+        };
+        Ok(ChatInvokeCompletion::new(response.to_string()))
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        let response = self.chat(messages).await?;
+        let stream = futures_util::stream::iter(vec![Ok(response.completion)]);
+        Ok(Box::new(stream))
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    println!("=== Ask Human Example ===\n");
+
+    let browser = Box::new(Browser::new(BrowserProfile::new().with_headless(true)));
+    let mut agent = Agent::new(
+        "Log in with the code sent to my phone".to_string(),
+        browser,
+        Box::new(DOMProcessorImpl::new()),
+        AskingLLM {
+            current_index: std::sync::Mutex::new(0),
+        },
+    )
+    .with_max_steps(5);
+
+    // Answer every question the run asks with a line from stdin
+    let handle = agent.handle();
+    tokio::spawn(async move {
+        loop {
+            let question = handle.next_question().await;
+            println!("🙋 {}", question.question);
+            let answer = tokio::task::spawn_blocking(|| {
+                let mut line = String::new();
+                std::io::stdin().read_line(&mut line).map(|_| line)
+            })
+            .await;
+            match answer {
+                Ok(Ok(line)) => {
+                    handle.provide_human_input(line.trim());
+                }
+                _ => break,
+            }
+        }
+    });
+
+    let history = agent.run().await?;
+    let result = history
+        .history
+        .last()
+        .and_then(|step| step.result.last())
+        .and_then(|result| result.extracted_content.clone());
+    match result {
+        Some(result) => println!("\n✓ {result}"),
+        None => println!("\n✗ The run did not finish"),
+    }
+    Ok(())
+}
@@ -0,0 +1,46 @@
+//! Example of attaching to a Chrome you started yourself
+//!
+//! Start Chrome with remote debugging enabled, e.g.
+//!
+//!   google-chrome --remote-debugging-port=9222 --user-data-dir=/tmp/chrome-debug
+//!
+//! This attaches to its first tab, reads the page and opens a new tab. When
+//! it finishes, Chrome keeps running with your tabs as they were.
+//!
+//! Usage:
+//!   cargo run --example attach_to_browser [port]
+//!
+//! Requirements:
+//!   - Chrome/Chromium running with --remote-debugging-port
+
+use browsing::browser::Browser;
+use browsing::error::Result;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    println!("=== Attach to Running Browser Example ===\n");
+
+    let port = std::env::args()
+        .nth(1)
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(9222);
+
+    let mut browser = Browser::attach("127.0.0.1", port).await?;
+    println!("✓ Attached to Chrome on port {port}: {}", browser.is_attached());
+
+    let url = browser.get_current_url().await?;
+    let title = browser.get_current_page_title().await?;
+    println!("  Current tab: {title} ({url})");
+
+    let tabs = browser.get_tabs(false).await?;
+    println!("  {} open tabs", tabs.len());
+
+    let target_id = browser.create_new_tab(Some("https://example.com")).await?;
+    println!("✓ Opened example.com in a new tab ({target_id})");
+
+    // Disconnects only; the browser and its tabs stay open
+    browser.stop().await?;
+    println!("\n✓ Detached; Chrome is still running");
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+//! Example of reading the browser state without an agent
+//!
+//! Builds the same state the agent gives its model each step and prints the
+//! indexed elements, so you can decide on actions yourself (or in another
+//! process, from the JSON) and run them with `Tools::act`.
+//!
+//! Usage:
+//!   cargo run --example browser_state [url]
+//!
+//! Requirements:
+//!   - Chrome/Chromium browser installed
+
+use browsing::browser::{Browser, BrowserProfile, BrowserStateOptions, build_browser_state};
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::Result;
+use browsing::traits::BrowserClient;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    println!("=== Browser State Example ===\n");
+
+    let url = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "https://example.com".to_string());
+
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await?;
+    browser.navigate(&url).await?;
+
+    let session = browser.get_session_info().await?;
+    let dom_processor = DOMProcessorImpl::new()
+        .with_cdp_client(browser.get_cdp_client()?, session.session_id)
+        .with_target_id(session.target_id);
+
+    let state = build_browser_state(&browser, &dom_processor, &BrowserStateOptions::new()).await?;
+    println!("✓ {} ({})", state.title, state.url);
+    println!("  {} open tabs\n", state.tabs.len());
+
+    println!("Interactive elements:");
+    for element in &state.dom_state.elements {
+        let label = element
+            .name
+            .as_deref()
+            .or(element.text.as_deref())
+            .unwrap_or_default();
+        println!("  [{}] <{}> {}", element.index, element.tag, label);
+    }
+
+    // The whole summary serializes for a decision maker elsewhere
+    let json = serde_json::to_string(&state)?;
+    println!("\n✓ State is {} bytes of JSON", json.len());
+
+    browser.stop().await?;
+    Ok(())
+}
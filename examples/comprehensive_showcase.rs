@@ -233,6 +233,9 @@ async fn main() -> Result<()> {
                 if let Some(ct) = usage.completion_tokens {
                     println!("   Completion tokens: {}", ct);
                 }
+                if let Some(avg) = usage.avg_completion_tokens_per_step {
+                    println!("   Completion tokens per step: {:.0}", avg);
+                }
                 if let Some(tt) = usage.total_tokens {
                     println!("   Total tokens: {}", tt);
                 }
@@ -0,0 +1,144 @@
+//! Example of a credentials provider backed by an encrypted file
+//!
+//! Secrets are stored per domain in a local file and read only when an action
+//! needs them. The model writes `<secret>password</secret>` and never sees the
+//! value; pass the provider to `Agent::with_credentials_provider` to use it in
+//! a run.
+//!
+//! The "encryption" here is a XOR keystream to keep the example free of extra
+//! dependencies. It only stands in for a real cipher: use an authenticated one
+//! (e.g. AES-GCM or age) or a real vault in practice.
+//!
+//! Usage:
+//!   VAULT_KEY=my-passphrase cargo run --example credentials_vault
+
+use async_trait::async_trait;
+use base64::Engine as _;
+use browsing::agent::{Credentials, CredentialsProvider, SecretString};
+use browsing::error::Result;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Secrets by domain and key, encrypted in a JSON file
+struct EncryptedFileProvider {
+    path: PathBuf,
+    key: SecretString,
+}
+
+impl EncryptedFileProvider {
+    fn new(path: impl Into<PathBuf>, key: SecretString) -> Self {
+        Self {
+            path: path.into(),
+            key,
+        }
+    }
+
+    /// Write `secrets` (domain -> key -> value) encrypted to `path`
+    fn create(path: &Path, key: &SecretString, secrets: &[(&str, &str, &str)]) -> Result<()> {
+        let mut vault: HashMap<String, HashMap<String, String>> = HashMap::new();
+        for (domain, name, value) in secrets {
+            let sealed = base64::engine::general_purpose::STANDARD
+                .encode(xor(value.as_bytes(), key.expose_secret()));
+            vault
+                .entry(domain.to_string())
+                .or_default()
+                .insert(name.to_string(), sealed);
+        }
+        std::fs::write(path, serde_json::to_vec_pretty(&vault)?)?;
+        Ok(())
+    }
+
+    fn read(&self) -> HashMap<String, HashMap<String, String>> {
+        std::fs::read(&self.path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl CredentialsProvider for EncryptedFileProvider {
+    async fn get(&self, domain: &str, key: &str) -> Option<SecretString> {
+        let sealed = self.read().get(domain)?.get(key)?.clone();
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(sealed)
+            .ok()?;
+        String::from_utf8(xor(&bytes, self.key.expose_secret()))
+            .ok()
+            .map(SecretString::from)
+    }
+
+    async fn keys(&self, domain: &str) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .read()
+            .get(domain)
+            .map(|secrets| secrets.keys().cloned().collect())
+            .unwrap_or_default();
+        keys.sort();
+        keys
+    }
+}
+
+/// XOR `data` with the repeated bytes of `key` (not real encryption)
+fn xor(data: &[u8], key: &str) -> Vec<u8> {
+    data.iter()
+        .zip(key.as_bytes().iter().cycle())
+        .map(|(byte, key)| byte ^ key)
+        .collect()
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    println!("=== Credentials Vault Example ===\n");
+
+    let key = SecretString::from(
+        std::env::var("VAULT_KEY").unwrap_or_else(|_| "example-passphrase".to_string()),
+    );
+    let path = std::env::temp_dir().join("browsing-example-vault.json");
+    EncryptedFileProvider::create(
+        &path,
+        &key,
+        &[
+            ("shop.example.com", "username", "ada"),
+            (
+                "shop.example.com",
+                "password",
+                "correct horse battery staple",
+            ),
+        ],
+    )?;
+    println!("✓ Wrote vault to {}", path.display());
+
+    let provider = Arc::new(EncryptedFileProvider::new(&path, key));
+    let credentials = Credentials::new().with_provider(provider);
+    println!(
+        "✓ Secrets for shop.example.com: {:?}",
+        credentials.keys("shop.example.com").await
+    );
+
+    // What the model writes, and what the action receives when it runs
+    let mut params = json!({ "index": 3, "text": "<secret>password</secret>" });
+    println!("\nModel wrote:    {}", params);
+    let filled = credentials
+        .substitute("shop.example.com", &mut params)
+        .await?;
+    println!(
+        "Action receives the secret filled in ({} bytes)",
+        params["text"].as_str().unwrap_or_default().len()
+    );
+    println!("Printed secret: {} / {:?}", filled[0], filled[0]);
+
+    // Unknown names fail instead of typing the placeholder into the page
+    let mut params = json!({ "text": "<secret>pin</secret>" });
+    if let Err(e) = credentials
+        .substitute("shop.example.com", &mut params)
+        .await
+    {
+        println!("\n✗ {}", e);
+    }
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
@@ -32,7 +32,7 @@ async fn main() -> Result<()> {
     println!("  Page title: {}", title);
 
     // Get list of tabs
-    let tabs = browser.get_tabs().await?;
+    let tabs = browser.get_tabs(false).await?;
     println!("  Open tabs: {}", tabs.len());
 
     // Take a screenshot
@@ -0,0 +1,160 @@
+//! Example of an agent that remembers facts across steps
+//!
+//! The model stores what it finds with the `remember` action and looks it up
+//! later with `recall`, by key or by keywords. Remembered facts are never
+//! evicted, unlike the summary of earlier steps.
+//!
+//! Usage:
+//!   cargo run --example memory_agent
+//!
+//! Requirements:
+//!   - Chrome/Chromium browser installed
+
+use async_trait::async_trait;
+use browsing::agent::service::Agent;
+use browsing::browser::{Browser, BrowserProfile};
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::Result;
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use serde_json::json;
+
+/// Mock LLM that remembers a fact, recalls it, and finishes
+/// In production, implement your own ChatModel
+struct MemoryDemoLLM {
+    responses: Vec<String>,
+    current_index: std::sync::Mutex<usize>,
+}
+
+impl MemoryDemoLLM {
+    fn new() -> Self {
+        let responses = vec![
+            // Step 1: Open the page
+            json!({
+                "thinking": "I need to read the heading of example.com",
+                "next_goal": "Navigate to example.com",
+                "action": [{ "action_type": "navigate", "params": { "url": "https://example.com" } }]
+            })
+            .to_string(),
+            // Step 2: Remember what was found
+            json!({
+                "thinking": "The heading is 'Example Domain'; I'll keep it for later",
+                "next_goal": "Remember the heading",
+                "action": [{
+                    "action_type": "remember",
+                    "params": { "key": "example_heading", "value": "Example Domain" }
+                }]
+            })
+            .to_string(),
+            // Step 3: Move on to another page
+            json!({
+                "thinking": "Now visit the Rust homepage",
+                "next_goal": "Navigate to rust-lang.org",
+                "action": [{ "action_type": "navigate", "params": { "url": "https://www.rust-lang.org" } }]
+            })
+            .to_string(),
+            // Step 4: Look the fact up again by keywords
+            json!({
+                "thinking": "What was the heading on the first page?",
+                "next_goal": "Recall the heading",
+                "action": [{ "action_type": "recall", "params": { "query": "example heading" } }]
+            })
+            .to_string(),
+            // Step 5: Finish
+            json!({
+                "thinking": "The recalled heading answers the task",
+                "next_goal": "Report the answer",
+                "action": [{
+                    "action_type": "done",
+                    "params": { "text": "The example.com heading is 'Example Domain'", "success": true }
+                }]
+            })
+            .to_string(),
+        ];
+
+        Self {
+            responses,
+            current_index: std::sync::Mutex::new(0),
+        }
+    }
+}
+
+#[async_trait]
+impl ChatModel for MemoryDemoLLM {
+    fn model(&self) -> &str {
+        "memory-demo-llm"
+    }
+
+    fn provider(&self) -> &str {
+        "demo"
+    }
+
+    async fn chat(&self, _messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        let mut index = self.current_index.lock().unwrap();
+        let response = self.responses[(*index).min(self.responses.len() - 1)].clone();
+        *index += 1;
+
+        Ok(ChatInvokeCompletion {
+            completion: response,
+            thinking: None,
+            redacted_thinking: None,
+            usage: None,
+            stop_reason: None,
+        })
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        let response = self.chat(messages).await?;
+        let stream = futures_util::stream::iter(vec![Ok(response.completion)]);
+        Ok(Box::new(stream))
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    println!("=== Agent Memory Example ===\n");
+
+    let headless = std::env::var("BROWSER_USE_HEADLESS")
+        .ok()
+        .and_then(|v| v.parse::<bool>().ok())
+        .unwrap_or(true);
+    let mut browser = Box::new(Browser::new(BrowserProfile {
+        headless: Some(headless),
+        ..Default::default()
+    }));
+    browser.start().await?;
+
+    let mut agent = Agent::new(
+        "Find the heading of example.com, then visit rust-lang.org and report the heading"
+            .to_string(),
+        browser,
+        Box::new(DOMProcessorImpl::new()),
+        MemoryDemoLLM::new(),
+    )
+    .with_max_steps(8);
+
+    // Facts can also be seeded before the run
+    agent.remember("preferred_language", "English");
+
+    let history = agent.run().await?;
+    println!("✓ Finished in {} steps", history.number_of_steps());
+    for result in history.history.iter().flat_map(|h| &h.result) {
+        if let Some(content) = &result.extracted_content {
+            println!("  {content}");
+        }
+    }
+
+    println!("\nWorking memory after the run:");
+    for (key, value) in &agent.memory().working_memory {
+        println!("  {key}: {value}");
+    }
+
+    println!("\nSearching for 'heading':");
+    for (key, value, score) in agent.recall_memory("heading") {
+        println!("  {key}: {value} (relevance {score:.2})");
+    }
+
+    Ok(())
+}
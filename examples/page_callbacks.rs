@@ -0,0 +1,82 @@
+//! Example of page JavaScript reporting events back to Rust
+//!
+//! An init script watches the DOM and reports bursts of added nodes, such as
+//! an infinite-scroll page appending items, through a function exposed with
+//! `Page::expose_binding`.
+//!
+//! Usage:
+//!   cargo run --example page_callbacks
+//!
+//! Requirements:
+//!   - Chrome/Chromium browser installed
+
+use anyhow::Result;
+use browsing::browser::{Browser, BrowserProfile};
+use serde_json::{Value, json};
+use std::time::Duration;
+
+/// Reports nodes added within 250ms of each other as one burst
+const MUTATION_BURST_JS: &str = r#"
+(() => {
+    let added = 0;
+    let timer = null;
+    const flush = () => {
+        window.reportMutations({ added, url: location.href });
+        added = 0;
+        timer = null;
+    };
+    const start = () => {
+        new MutationObserver(records => {
+            for (const record of records) added += record.addedNodes.length;
+            if (added > 0 && !timer) timer = setTimeout(flush, 250);
+        }).observe(document, { childList: true, subtree: true });
+    };
+    if (document.readyState === "loading") {
+        document.addEventListener("DOMContentLoaded", start);
+    } else {
+        start();
+    }
+})()
+"#;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    browsing::init();
+
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await?;
+    let page = browser.get_page()?;
+
+    // Called for every burst, in every document the tab loads from now on
+    let binding = page
+        .expose_binding("reportMutations", |payload: Value| async move {
+            println!(
+                "Mutation burst: {} nodes added on {}",
+                payload["added"], payload["url"]
+            );
+        })
+        .await?;
+
+    // Register the observer for documents loaded later
+    let client = browser.get_cdp_client()?;
+    let session_id = browser.get_session_id()?;
+    client
+        .send_command_with_session(
+            "Page.addScriptToEvaluateOnNewDocument",
+            json!({ "source": MUTATION_BURST_JS }),
+            Some(&session_id),
+        )
+        .await?;
+
+    browser.navigate("https://example.com").await?;
+    // Simulate an infinite-scroll page appending 20 items
+    page.evaluate(
+        "for (let i = 0; i < 20; i++) document.body.appendChild(document.createElement('p'))",
+    )
+    .await?;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    binding.remove().await?;
+    browser.stop().await?;
+    Ok(())
+}
@@ -70,7 +70,7 @@ async fn main() -> Result<()> {
 
     // Get all tabs
     println!("8. Getting all tabs...");
-    let tabs = browser.get_tabs().await?;
+    let tabs = browser.get_tabs(false).await?;
     println!("   Open tabs: {}", tabs.len());
     for (i, tab) in tabs.iter().enumerate() {
         println!("     Tab {}: {}", i, tab.url);
@@ -0,0 +1,153 @@
+//! Example of following an agent run step by step
+//!
+//! `Agent::run_stream` yields an event as each step finishes, so a UI can show
+//! progress live instead of waiting for the whole run. This prints a row per
+//! step and a summary once the run completes.
+//!
+//! Usage:
+//!   cargo run --example stream_steps
+//!
+//! Requirements:
+//!   - Chrome/Chromium browser installed
+
+use async_trait::async_trait;
+use browsing::agent::StepEvent;
+use browsing::agent::service::Agent;
+use browsing::browser::{Browser, BrowserProfile};
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::Result;
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use futures_util::StreamExt;
+use serde_json::json;
+
+/// Mock LLM that visits two pages and finishes
+/// In production, implement your own ChatModel
+struct StreamDemoLLM {
+    responses: Vec<String>,
+    current_index: std::sync::Mutex<usize>,
+}
+
+impl StreamDemoLLM {
+    fn new() -> Self {
+        let responses = vec![
+            json!({
+                "next_goal": "Open example.com",
+                "action": [{ "action_type": "navigate", "params": { "url": "https://example.com" } }]
+            })
+            .to_string(),
+            json!({
+                "next_goal": "Open the Rust homepage",
+                "action": [{ "action_type": "navigate", "params": { "url": "https://www.rust-lang.org" } }]
+            })
+            .to_string(),
+            json!({
+                "next_goal": "Report the pages visited",
+                "action": [{
+                    "action_type": "done",
+                    "params": { "text": "Visited example.com and rust-lang.org", "success": true }
+                }]
+            })
+            .to_string(),
+        ];
+
+        Self {
+            responses,
+            current_index: std::sync::Mutex::new(0),
+        }
+    }
+}
+
+#[async_trait]
+impl ChatModel for StreamDemoLLM {
+    fn model(&self) -> &str {
+        "stream-demo-llm"
+    }
+
+    fn provider(&self) -> &str {
+        "demo"
+    }
+
+    async fn chat(&self, _messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        let mut index = self.current_index.lock().unwrap();
+        let response = self.responses[(*index).min(self.responses.len() - 1)].clone();
+        *index += 1;
+
+        Ok(ChatInvokeCompletion::new(response))
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        let response = self.chat(messages).await?;
+        let stream = futures_util::stream::iter(vec![Ok(response.completion)]);
+        Ok(Box::new(stream))
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    println!("=== Streaming Agent Steps Example ===\n");
+
+    let headless = std::env::var("BROWSER_USE_HEADLESS")
+        .ok()
+        .and_then(|v| v.parse::<bool>().ok())
+        .unwrap_or(true);
+    let browser = Box::new(Browser::new(BrowserProfile {
+        headless: Some(headless),
+        ..Default::default()
+    }));
+
+    let agent = Agent::new(
+        "Visit example.com and rust-lang.org".to_string(),
+        browser,
+        Box::new(DOMProcessorImpl::new()),
+        StreamDemoLLM::new(),
+    )
+    .with_max_steps(5);
+
+    println!(
+        "{:<5} {:<28} {:<10} {:<7} URL",
+        "Step", "Goal", "Actions", "Tokens"
+    );
+    println!("{}", "-".repeat(80));
+
+    let mut events = Box::pin(agent.run_stream());
+    while let Some(event) = events.next().await {
+        match event {
+            StepEvent::Step(update) => {
+                let actions: Vec<&str> = update
+                    .actions
+                    .iter()
+                    .map(|action| action.action_type.as_str())
+                    .collect();
+                let tokens = update
+                    .usage
+                    .as_ref()
+                    .map(|usage| usage.total_tokens.to_string())
+                    .unwrap_or_else(|| "-".to_string());
+                let status = if update.is_success() { "" } else { " ✗" };
+                println!(
+                    "{:<5} {:<28} {:<10} {:<7} {}{status}",
+                    update.step,
+                    update.summary.chars().take(28).collect::<String>(),
+                    actions.join(","),
+                    tokens,
+                    update.url
+                );
+            }
+            StepEvent::HumanInputRequested(question) => {
+                println!("?     {}", question.question);
+            }
+            StepEvent::RunComplete { history, error } => {
+                println!("{}", "-".repeat(80));
+                match error {
+                    Some(error) => println!("✗ Run failed: {error}"),
+                    None => println!("✓ Finished in {} steps", history.number_of_steps()),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,405 @@
+//! Page audits from DevTools issues
+//!
+//! Chrome reports problems it detects while loading a page (unlabelled form
+//! fields, Content Security Policy violations, rejected cookies, ...) as
+//! issues in the `Audits` domain. There is no command to list them: enabling
+//! the domain replays the issues found so far as `Audits.issueAdded` events,
+//! which [`Page::run_audit`](crate::actor::Page::run_audit) collects.
+
+use crate::error::{BrowsingError, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+/// How long to wait for further issues after the last one arrived
+pub const AUDIT_SETTLE_MS: u64 = 500;
+
+/// Upper bound on the time spent collecting issues
+pub const AUDIT_TIMEOUT_MS: u64 = 3000;
+
+/// Builds a CSS selector for `this` element, preferring IDs
+pub(crate) const CSS_PATH_JS: &str = r#"
+function() {
+    const parts = [];
+    let el = this;
+    while (el && el.nodeType === Node.ELEMENT_NODE) {
+        if (el.id) {
+            parts.unshift('#' + CSS.escape(el.id));
+            break;
+        }
+        let part = el.localName;
+        const parent = el.parentElement;
+        if (parent) {
+            const same = Array.from(parent.children).filter((c) => c.localName === el.localName);
+            if (same.length > 1) part += ':nth-of-type(' + (same.indexOf(el) + 1) + ')';
+        }
+        parts.unshift(part);
+        el = parent;
+    }
+    return parts.join(' > ');
+}
+"#;
+
+/// Category of issues reported by [`Page::run_audit`](crate::actor::Page::run_audit)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditType {
+    /// Form labelling, ARIA references and text contrast
+    Accessibility,
+    /// Content Security Policy violations
+    Csp,
+    /// Cookies rejected or flagged by the browser
+    Cookies,
+    /// Every category above
+    All,
+}
+
+impl AuditType {
+    /// Parse an audit type name (`accessibility`, `csp`, `cookies` or `all`)
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "accessibility" | "a11y" => Ok(Self::Accessibility),
+            "csp" => Ok(Self::Csp),
+            "cookies" | "cookie" => Ok(Self::Cookies),
+            "all" => Ok(Self::All),
+            other => Err(BrowsingError::Validation(format!(
+                "Unknown audit type '{other}': expected accessibility, csp, cookies or all"
+            ))),
+        }
+    }
+
+    fn includes(self, category: AuditType) -> bool {
+        self == AuditType::All || self == category
+    }
+}
+
+/// Severity of an audit issue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditSeverity {
+    /// The browser blocked something
+    Error,
+    /// Likely a problem, nothing was blocked
+    Warning,
+}
+
+/// An issue found by an audit
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditIssue {
+    /// Issue code, e.g. `FormLabelForNameError` or `ContentSecurityPolicyIssue`
+    pub code: String,
+    /// Human-readable description
+    pub description: String,
+    /// CSS selector of the offending element, if the issue names one
+    pub element_selector: Option<String>,
+    /// Severity of the issue
+    pub severity: AuditSeverity,
+    /// Backend node ID of the offending element
+    #[serde(skip)]
+    pub(crate) violating_node_id: Option<u64>,
+}
+
+/// A Content Security Policy violation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CspIssue {
+    /// Violation type, e.g. `kInlineViolation` or `kURLViolation`
+    pub code: String,
+    /// Human-readable description
+    pub description: String,
+    /// CSS selector of the offending element, if the issue names one
+    pub element_selector: Option<String>,
+    /// Error when enforced, warning for report-only policies
+    pub severity: AuditSeverity,
+    /// Directive that was violated, e.g. `script-src-elem`
+    pub violated_directive: String,
+    /// URL that was blocked, for URL violations
+    pub blocked_url: Option<String>,
+    /// Whether the policy only reports violations
+    pub is_report_only: bool,
+}
+
+impl From<CspIssue> for AuditIssue {
+    fn from(issue: CspIssue) -> Self {
+        Self {
+            code: issue.code,
+            description: issue.description,
+            element_selector: issue.element_selector,
+            severity: issue.severity,
+            violating_node_id: None,
+        }
+    }
+}
+
+/// Generic issue types about form labelling and ARIA references
+fn is_accessibility_error(error_type: &str) -> bool {
+    error_type.starts_with("Form") || error_type.contains("Aria")
+}
+
+fn describe_generic(error_type: &str, attribute: Option<&str>) -> String {
+    let description = match error_type {
+        "FormLabelForNameError" => "A <label for> names a field by name instead of by id",
+        "FormDuplicateIdForInputError" => "Several form fields share the same id",
+        "FormInputWithNoLabelError" => "A form field has no associated label",
+        "FormAutocompleteAttributeEmptyError" => "A form field has an empty autocomplete attribute",
+        "FormEmptyIdAndNameAttributesForInputError" => "A form field has neither an id nor a name",
+        "FormAriaLabelledByToNonExistingId" => "aria-labelledby refers to an id that doesn't exist",
+        "FormInputAssignedAutocompleteValueToIdOrNameAttributeError" => {
+            "An autocomplete value is used as a field's id or name"
+        }
+        "FormLabelHasNeitherForNorNestedInput" => {
+            "A <label> has neither a for attribute nor a nested field"
+        }
+        "FormLabelForMatchesNonExistingIdError" => {
+            "A <label for> refers to an id that doesn't exist"
+        }
+        "FormInputHasWrongButWellIntendedAutocompleteValueError" => {
+            "A form field has a misspelled autocomplete value"
+        }
+        other => other,
+    };
+    match attribute {
+        Some(attribute) => format!("{description} (attribute: {attribute})"),
+        None => description.to_string(),
+    }
+}
+
+/// Read an `Audits.issueAdded` event's issue as an [`AuditIssue`] in `category`
+///
+/// Returns `None` for issues outside the requested category.
+pub(crate) fn parse_issue(issue: &Value, category: AuditType) -> Option<AuditIssue> {
+    let code = issue.get("code")?.as_str()?;
+    let details = &issue["details"];
+    match code {
+        "GenericIssue" if category.includes(AuditType::Accessibility) => {
+            let details = &details["genericIssueDetails"];
+            let error_type = details["errorType"].as_str()?;
+            if !is_accessibility_error(error_type) {
+                return None;
+            }
+            Some(AuditIssue {
+                code: error_type.to_string(),
+                description: describe_generic(
+                    error_type,
+                    details["violatingNodeAttribute"].as_str(),
+                ),
+                element_selector: None,
+                severity: AuditSeverity::Warning,
+                violating_node_id: details["violatingNodeId"].as_u64(),
+            })
+        }
+        "LowTextContrastIssue" if category.includes(AuditType::Accessibility) => {
+            let details = &details["lowTextContrastIssueDetails"];
+            let ratio = details["contrastRatio"].as_f64().unwrap_or_default();
+            let required = details["thresholdAA"].as_f64().unwrap_or(4.5);
+            Some(AuditIssue {
+                code: code.to_string(),
+                description: format!(
+                    "Text contrast ratio {ratio:.2} is below the required {required:.1}"
+                ),
+                element_selector: details["violatingNodeSelector"]
+                    .as_str()
+                    .map(str::to_string),
+                severity: AuditSeverity::Warning,
+                violating_node_id: details["violatingNodeId"].as_u64(),
+            })
+        }
+        "ContentSecurityPolicyIssue" if category.includes(AuditType::Csp) => {
+            let csp = parse_csp_issue(issue)?;
+            let violating_node_id =
+                details["contentSecurityPolicyIssueDetails"]["violatingNodeId"].as_u64();
+            Some(AuditIssue {
+                violating_node_id,
+                ..csp.into()
+            })
+        }
+        "CookieIssue" if category.includes(AuditType::Cookies) => {
+            let details = &details["cookieIssueDetails"];
+            let cookie = &details["cookie"];
+            let name = cookie["name"]
+                .as_str()
+                .or_else(|| details["rawCookieLine"].as_str())
+                .unwrap_or("<unknown>");
+            let reasons = |key: &str| -> Vec<&str> {
+                details[key]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|v| v.as_str())
+                    .collect()
+            };
+            let exclusions = reasons("cookieExclusionReasons");
+            let (severity, reasons) = if exclusions.is_empty() {
+                (AuditSeverity::Warning, reasons("cookieWarningReasons"))
+            } else {
+                (AuditSeverity::Error, exclusions)
+            };
+            let verb = if severity == AuditSeverity::Error {
+                "was rejected"
+            } else {
+                "was flagged"
+            };
+            Some(AuditIssue {
+                code: reasons.first().copied().unwrap_or(code).to_string(),
+                description: format!("Cookie \"{name}\" {verb}: {}", reasons.join(", ")),
+                element_selector: None,
+                severity,
+                violating_node_id: None,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Read a `ContentSecurityPolicyIssue` as a [`CspIssue`]
+pub(crate) fn parse_csp_issue(issue: &Value) -> Option<CspIssue> {
+    if issue.get("code")?.as_str()? != "ContentSecurityPolicyIssue" {
+        return None;
+    }
+    let details = &issue["details"]["contentSecurityPolicyIssueDetails"];
+    let violation = details["contentSecurityPolicyViolationType"]
+        .as_str()
+        .unwrap_or("ContentSecurityPolicyIssue");
+    let directive = details["violatedDirective"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    let blocked_url = details["blockedURL"].as_str().map(str::to_string);
+    let is_report_only = details["isReportOnly"].as_bool().unwrap_or(false);
+
+    let what = match (violation, &blocked_url) {
+        ("kInlineViolation", _) => "Inline script or style".to_string(),
+        ("kEvalViolation", _) => "eval()".to_string(),
+        ("kWasmEvalViolation", _) => "WebAssembly compilation".to_string(),
+        ("kTrustedTypesSinkViolation", _) => "Assignment to a Trusted Types sink".to_string(),
+        ("kTrustedTypesPolicyViolation", _) => "Trusted Types policy creation".to_string(),
+        (_, Some(url)) => url.clone(),
+        _ => "A resource".to_string(),
+    };
+    let outcome = if is_report_only {
+        "violates (report-only)"
+    } else {
+        "was blocked by"
+    };
+    let mut description = format!("{what} {outcome} directive '{directive}'");
+    if let Some(url) = details["sourceCodeLocation"]["url"].as_str() {
+        let line = details["sourceCodeLocation"]["lineNumber"]
+            .as_u64()
+            .unwrap_or_default();
+        description.push_str(&format!(" at {url}:{}", line + 1));
+    }
+
+    Some(CspIssue {
+        code: violation.to_string(),
+        description,
+        element_selector: None,
+        severity: if is_report_only {
+            AuditSeverity::Warning
+        } else {
+            AuditSeverity::Error
+        },
+        violated_directive: directive,
+        blocked_url,
+        is_report_only,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_generic_form_issue_is_accessibility() {
+        let issue = json!({
+            "code": "GenericIssue",
+            "details": { "genericIssueDetails": {
+                "errorType": "FormLabelForNameError",
+                "violatingNodeId": 12,
+                "violatingNodeAttribute": "for"
+            } }
+        });
+        let parsed = parse_issue(&issue, AuditType::Accessibility).unwrap();
+        assert_eq!(parsed.code, "FormLabelForNameError");
+        assert!(parsed.description.contains("attribute: for"));
+        assert_eq!(parsed.violating_node_id, Some(12));
+        assert_eq!(parsed.severity, AuditSeverity::Warning);
+
+        assert!(parse_issue(&issue, AuditType::Csp).is_none());
+        assert!(parse_issue(&issue, AuditType::All).is_some());
+
+        // Generic issues unrelated to accessibility are left out
+        let orb = json!({
+            "code": "GenericIssue",
+            "details": { "genericIssueDetails": { "errorType": "ResponseWasBlockedByORB" } }
+        });
+        assert!(parse_issue(&orb, AuditType::All).is_none());
+    }
+
+    #[test]
+    fn test_csp_issue() {
+        let issue = json!({
+            "code": "ContentSecurityPolicyIssue",
+            "details": { "contentSecurityPolicyIssueDetails": {
+                "blockedURL": "https://cdn.example/x.js",
+                "violatedDirective": "script-src-elem",
+                "isReportOnly": false,
+                "contentSecurityPolicyViolationType": "kURLViolation",
+                "sourceCodeLocation": { "url": "https://example.com/", "lineNumber": 4, "columnNumber": 2 }
+            } }
+        });
+        let csp = parse_csp_issue(&issue).unwrap();
+        assert_eq!(csp.code, "kURLViolation");
+        assert_eq!(csp.severity, AuditSeverity::Error);
+        assert_eq!(csp.blocked_url.as_deref(), Some("https://cdn.example/x.js"));
+        assert_eq!(
+            csp.description,
+            "https://cdn.example/x.js was blocked by directive 'script-src-elem' at https://example.com/:5"
+        );
+
+        let mut report_only = issue.clone();
+        report_only["details"]["contentSecurityPolicyIssueDetails"]["isReportOnly"] = json!(true);
+        report_only["details"]["contentSecurityPolicyIssueDetails"]["contentSecurityPolicyViolationType"] =
+            json!("kInlineViolation");
+        let csp = parse_csp_issue(&report_only).unwrap();
+        assert_eq!(csp.severity, AuditSeverity::Warning);
+        assert!(
+            csp.description
+                .starts_with("Inline script or style violates")
+        );
+    }
+
+    #[test]
+    fn test_cookie_issue_severity_follows_exclusion() {
+        let issue = json!({
+            "code": "CookieIssue",
+            "details": { "cookieIssueDetails": {
+                "cookie": { "name": "sid", "domain": "example.com", "path": "/" },
+                "cookieWarningReasons": [],
+                "cookieExclusionReasons": ["ExcludeSameSiteNoneInsecure"],
+                "operation": "SetCookie"
+            } }
+        });
+        let parsed = parse_issue(&issue, AuditType::Cookies).unwrap();
+        assert_eq!(parsed.code, "ExcludeSameSiteNoneInsecure");
+        assert_eq!(parsed.severity, AuditSeverity::Error);
+        assert_eq!(
+            parsed.description,
+            "Cookie \"sid\" was rejected: ExcludeSameSiteNoneInsecure"
+        );
+
+        let mut warning = issue.clone();
+        warning["details"]["cookieIssueDetails"]["cookieExclusionReasons"] = json!([]);
+        warning["details"]["cookieIssueDetails"]["cookieWarningReasons"] =
+            json!(["WarnSameSiteUnspecifiedLaxAllowUnsafe"]);
+        assert_eq!(
+            parse_issue(&warning, AuditType::Cookies).unwrap().severity,
+            AuditSeverity::Warning
+        );
+    }
+
+    #[test]
+    fn test_audit_type_from_name() {
+        assert_eq!(AuditType::from_name("CSP").unwrap(), AuditType::Csp);
+        assert_eq!(AuditType::from_name("all").unwrap(), AuditType::All);
+        assert!(AuditType::from_name("seo").is_err());
+    }
+}
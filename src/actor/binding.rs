@@ -0,0 +1,238 @@
+//! Callbacks from page JavaScript to Rust
+//!
+//! [`Page::expose_binding`](crate::actor::Page::expose_binding) defines a
+//! function on `window` that page scripts call with any JSON-serializable
+//! value. The value is serialized in the page, sent over `Runtime.addBinding`
+//! and handed to a Rust callback as a [`serde_json::Value`]. Only JSON crosses
+//! the boundary: payloads that do not parse, or are larger than
+//! [`MAX_BINDING_PAYLOAD_BYTES`], are dropped with a warning.
+
+use crate::browser::cdp::CdpClient;
+use crate::error::{BrowsingError, Result};
+use serde_json::{Value, json};
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// Largest payload, in bytes of serialized JSON, passed to a binding handler
+pub const MAX_BINDING_PAYLOAD_BYTES: usize = 1 << 20;
+
+/// Prefix of the raw CDP binding behind each exposed function
+const RAW_BINDING_PREFIX: &str = "__browsingBinding_";
+
+/// Name of the raw CDP binding behind the exposed function `name`
+fn raw_binding_name(name: &str) -> String {
+    format!("{RAW_BINDING_PREFIX}{name}")
+}
+
+/// Whether `name` can be used as a `window` property from page scripts
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Script defining `window[name]`, which serializes its argument to JSON
+///
+/// Registered as an init script so the function exists in every document,
+/// before page scripts run. The raw binding is looked up on each call since
+/// it may be installed after the script runs.
+fn install_script(name: &str) -> String {
+    let raw = raw_binding_name(name);
+    format!(
+        r#"(() => {{
+    window["{name}"] = (payload) => {{
+        const send = window["{raw}"];
+        if (typeof send !== "function") {{
+            throw new Error("Binding {name} was removed");
+        }}
+        send(JSON.stringify(payload === undefined ? null : payload));
+    }};
+}})()"#
+    )
+}
+
+/// Payload of a `Runtime.bindingCalled` event for `raw_name` in `session_id`
+fn binding_payload<'a>(event: &'a Value, session_id: &str, raw_name: &str) -> Option<&'a str> {
+    if event["method"] != "Runtime.bindingCalled"
+        || event["sessionId"] != session_id
+        || event["params"]["name"] != raw_name
+    {
+        return None;
+    }
+    event["params"]["payload"].as_str()
+}
+
+/// Parse a payload sent by page JavaScript, enforcing the JSON-only contract
+fn parse_payload(payload: &str) -> Result<Value> {
+    if payload.len() > MAX_BINDING_PAYLOAD_BYTES {
+        return Err(BrowsingError::Dom(format!(
+            "Binding payload of {} bytes exceeds {MAX_BINDING_PAYLOAD_BYTES}",
+            payload.len()
+        )));
+    }
+    serde_json::from_str(payload)
+        .map_err(|e| BrowsingError::Dom(format!("Binding payload is not JSON: {e}")))
+}
+
+/// A function exposed to page JavaScript
+///
+/// The handler runs until [`BindingHandle::remove`] is called or the handle is
+/// dropped, so keep the handle alive for as long as callbacks are wanted.
+pub struct BindingHandle {
+    name: String,
+    client: Arc<CdpClient>,
+    session_id: String,
+    script_identifier: Option<Value>,
+    task: JoinHandle<()>,
+}
+
+impl BindingHandle {
+    /// Name of the function on `window`
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Stop calling the handler and remove the function from the page
+    ///
+    /// Documents loaded afterwards no longer define the function; in the
+    /// current document it is deleted from `window`.
+    pub async fn remove(self) -> Result<()> {
+        self.task.abort();
+        let session_id = Some(self.session_id.as_str());
+        self.client
+            .send_command_with_session(
+                "Runtime.removeBinding",
+                json!({ "name": raw_binding_name(&self.name) }),
+                session_id,
+            )
+            .await?;
+        if let Some(identifier) = &self.script_identifier {
+            self.client
+                .send_command_with_session(
+                    "Page.removeScriptToEvaluateOnNewDocument",
+                    json!({ "identifier": identifier }),
+                    session_id,
+                )
+                .await?;
+        }
+        self.client
+            .send_command_with_session(
+                "Runtime.evaluate",
+                json!({ "expression": format!("delete window[\"{}\"]", self.name) }),
+                session_id,
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+impl Drop for BindingHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Expose `name` in the page behind `session_id`, routing calls to `handler`
+///
+/// Calls are handled one at a time, in the order the page made them.
+pub(crate) async fn expose<F, Fut>(
+    client: Arc<CdpClient>,
+    session_id: String,
+    name: &str,
+    handler: F,
+) -> Result<BindingHandle>
+where
+    F: Fn(Value) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    if !is_valid_name(name) {
+        return Err(BrowsingError::Dom(format!(
+            "Invalid binding name '{name}': use letters, digits, '_' and '$'"
+        )));
+    }
+    let raw = raw_binding_name(name);
+
+    // Subscribe first so calls made while installing are not missed
+    let mut events = client.subscribe_events();
+    let task = {
+        let session_id = session_id.clone();
+        let raw = raw.clone();
+        let name = name.to_string();
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Binding {} missed {} events", name, skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                let Some(payload) = binding_payload(&event, &session_id, &raw) else {
+                    continue;
+                };
+                match parse_payload(payload) {
+                    Ok(value) => handler(value).await,
+                    Err(e) => warn!("Dropped call to binding {}: {}", name, e),
+                }
+            }
+            debug!("Binding {} stopped: connection closed", name);
+        })
+    };
+    let mut handle = BindingHandle {
+        name: name.to_string(),
+        client: Arc::clone(&client),
+        session_id: session_id.clone(),
+        script_identifier: None,
+        task,
+    };
+
+    let session = Some(session_id.as_str());
+    client
+        .send_command_with_session("Runtime.addBinding", json!({ "name": raw }), session)
+        .await?;
+    let script = install_script(name);
+    let added = client
+        .send_command_with_session(
+            "Page.addScriptToEvaluateOnNewDocument",
+            json!({ "source": script }),
+            session,
+        )
+        .await?;
+    handle.script_identifier = added.get("identifier").cloned();
+    client
+        .send_command_with_session("Runtime.evaluate", json!({ "expression": script }), session)
+        .await?;
+    Ok(handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binding_names() {
+        assert!(is_valid_name("reportMutations"));
+        assert!(is_valid_name("_$cb2"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("2fast"));
+        assert!(!is_valid_name("x\"]; alert(1); //"));
+    }
+
+    #[test]
+    fn test_payload_contract() {
+        assert_eq!(
+            parse_payload(r#"{"added":20}"#).unwrap(),
+            json!({ "added": 20 })
+        );
+        assert_eq!(parse_payload("null").unwrap(), Value::Null);
+        assert!(parse_payload("not json").is_err());
+        let huge = format!("\"{}\"", "x".repeat(MAX_BINDING_PAYLOAD_BYTES));
+        assert!(parse_payload(&huge).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+//! Page state checkpoints
+//!
+//! A checkpoint records what the browser holds for a page — URL, cookies,
+//! `localStorage`, `sessionStorage` and scroll position — so an agent can try
+//! one branch of a flow and come back to try another. Restoring re-navigates
+//! to the URL with the storage seeded before any page script runs.
+//!
+//! Only client-side state is captured. Anything the server did in between
+//! (an order placed, a form submitted) is not undone.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Note attached to checkpoint results, since restoring cannot undo server-side effects
+pub const SERVER_STATE_NOTE: &str =
+    "server-side state (orders placed, forms submitted) is not rolled back";
+
+/// Reads URL, origin, storage and scroll position of the top document
+pub(crate) const CAPTURE_JS: &str = r#"
+(() => {
+    const read = (storage) => {
+        const items = {};
+        try {
+            const s = storage();
+            for (let i = 0; i < s.length; i++) {
+                const key = s.key(i);
+                items[key] = s.getItem(key);
+            }
+        } catch (e) {}
+        return items;
+    };
+    return {
+        url: location.href,
+        origin: location.origin,
+        local_storage: read(() => localStorage),
+        session_storage: read(() => sessionStorage),
+        scroll_x: window.scrollX,
+        scroll_y: window.scrollY,
+    };
+})()
+"#;
+
+/// Identifier of a checkpoint saved by [`Browser::checkpoint`](crate::browser::Browser::checkpoint)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CheckpointId(pub u32);
+
+impl fmt::Display for CheckpointId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Client-side state of a page at one point in time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageCheckpoint {
+    /// URL of the page
+    pub url: String,
+    /// Origin the storage belongs to (`"null"` for opaque origins)
+    pub origin: String,
+    /// Cookies as returned by `Network.getAllCookies`
+    #[serde(default)]
+    pub cookies: Vec<serde_json::Value>,
+    /// `localStorage` entries of the origin
+    #[serde(default)]
+    pub local_storage: BTreeMap<String, String>,
+    /// `sessionStorage` entries of the tab
+    #[serde(default)]
+    pub session_storage: BTreeMap<String, String>,
+    /// Horizontal scroll position
+    #[serde(default)]
+    pub scroll_x: f64,
+    /// Vertical scroll position
+    #[serde(default)]
+    pub scroll_y: f64,
+}
+
+impl PageCheckpoint {
+    /// Script that replaces the origin's storage with the checkpoint's before page scripts run
+    ///
+    /// Registered with `Page.addScriptToEvaluateOnNewDocument`; it only touches
+    /// the top document of the checkpoint's origin.
+    pub(crate) fn seed_storage_script(&self) -> String {
+        format!(
+            r#"(() => {{
+    if (window !== window.top || location.origin !== {origin}) return;
+    const seed = (storage, items) => {{
+        try {{
+            const s = storage();
+            s.clear();
+            for (const [key, value] of Object.entries(items)) s.setItem(key, value);
+        }} catch (e) {{}}
+    }};
+    seed(() => localStorage, {local});
+    seed(() => sessionStorage, {session});
+}})()"#,
+            origin = json_literal(&self.origin),
+            local = json_literal(&self.local_storage),
+            session = json_literal(&self.session_storage),
+        )
+    }
+
+    /// Script that scrolls back to the checkpoint's position
+    pub(crate) fn scroll_script(&self) -> String {
+        format!("window.scrollTo({}, {})", self.scroll_x, self.scroll_y)
+    }
+}
+
+/// JSON literal of a value, for embedding in scripts
+fn json_literal<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "null".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_result_parses() {
+        let value = serde_json::json!({
+            "url": "http://localhost:8080/cart",
+            "origin": "http://localhost:8080",
+            "local_storage": { "cart": "[1,2]" },
+            "session_storage": {},
+            "scroll_x": 0,
+            "scroll_y": 420.5
+        });
+        let checkpoint: PageCheckpoint = serde_json::from_value(value).unwrap();
+        assert_eq!(checkpoint.local_storage["cart"], "[1,2]");
+        assert!(checkpoint.cookies.is_empty());
+        assert_eq!(checkpoint.scroll_script(), "window.scrollTo(0, 420.5)");
+    }
+
+    #[test]
+    fn test_seed_script_escapes_values() {
+        let checkpoint = PageCheckpoint {
+            url: "http://localhost/".into(),
+            origin: "http://localhost".into(),
+            cookies: vec![],
+            local_storage: BTreeMap::from([("note".into(), "it's \"quoted\"\n".into())]),
+            session_storage: BTreeMap::new(),
+            scroll_x: 0.0,
+            scroll_y: 0.0,
+        };
+        let script = checkpoint.seed_storage_script();
+        assert!(script.contains(r#"location.origin !== "http://localhost""#));
+        assert!(
+            script.contains(r#"{"note":"it's \"quoted\"\n"}"#),
+            "{script}"
+        );
+        assert!(script.contains("seed(() => sessionStorage, {})"));
+    }
+}
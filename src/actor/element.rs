@@ -1,16 +1,433 @@
 //! Element operations for browser automation
 
+use crate::actor::ime;
 use crate::actor::mouse::MouseButton;
-use crate::browser::cdp::CdpClient;
+use crate::actor::pointer::{PointerEventType, PointerInput, PointerType};
+use crate::browser::cdp::{BatchCommand, CdpClient};
 use crate::error::{BrowsingError, Result};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::sync::Arc;
 
+/// Defines `roleOf(el)`: an element's ARIA role, explicit or implied by its tag
+macro_rules! role_of_js {
+    () => {
+        r#"
+    const implicitRoles = {
+        button: 'button', select: 'combobox', textarea: 'textbox', img: 'img',
+        h1: 'heading', h2: 'heading', h3: 'heading', h4: 'heading', h5: 'heading', h6: 'heading',
+        li: 'listitem', ul: 'list', ol: 'list', nav: 'navigation', form: 'form',
+        option: 'option', table: 'table', tr: 'row', td: 'cell', th: 'columnheader',
+    };
+    const inputRoles = {
+        button: 'button', submit: 'button', reset: 'button', image: 'button',
+        checkbox: 'checkbox', radio: 'radio', range: 'slider', search: 'searchbox',
+    };
+    const roleOf = (el) => {
+        const explicit = (el.getAttribute('role') || '').trim().split(/\s+/)[0];
+        if (explicit) return explicit.toLowerCase();
+        const tag = el.localName;
+        if (tag === 'a' || tag === 'area') return el.hasAttribute('href') ? 'link' : null;
+        if (tag === 'input') return inputRoles[(el.type || 'text').toLowerCase()] || 'textbox';
+        return implicitRoles[tag] || null;
+    };
+"#
+    };
+}
+
+/// Finds matching descendants of `this`, innermost and rendered only
+const FIND_DESCENDANTS_JS: &str = concat!(
+    "function(locator) {",
+    role_of_js!(),
+    r#"
+    const normalize = (s) => (s || '').replace(/\s+/g, ' ').trim().toLowerCase();
+    const textOf = (el) => normalize(
+        [el.innerText, el.value, el.getAttribute('aria-label'), el.getAttribute('title')]
+            .filter((s) => typeof s === 'string').join(' ')
+    );
+
+    let found = Array.from(this.querySelectorAll(locator.css || '*'));
+    found = found.filter((el) => el.getClientRects().length > 0);
+    if (locator.role) {
+        const role = locator.role.toLowerCase();
+        found = found.filter((el) => roleOf(el) === role);
+    }
+    if (locator.text) {
+        const needle = normalize(locator.text);
+        found = found.filter((el) => textOf(el).includes(needle));
+    }
+    // A match's ancestors contain its text too; keep the innermost
+    return found.filter((el) => !found.some((other) => other !== el && el.contains(other)));
+}
+"#
+);
+
+/// ARIA role of `this`, explicit or implied by its tag, or `null`
+const ROLE_JS: &str = concat!("function() {", role_of_js!(), "    return roleOf(this);\n}");
+
+/// Short visible label of each element in `this` array
+const DESCRIBE_MATCHES_JS: &str = r#"
+function() {
+    return this.map((el) => {
+        const text = el.innerText || el.value || el.getAttribute('aria-label') || el.getAttribute('title') || '';
+        return text.replace(/\s+/g, ' ').trim().slice(0, 80);
+    });
+}
+"#;
+
+/// Sets the value of `this` through the native setter, so frameworks tracking
+/// the property see the change, and fires `input` and `change`
+const FILL_JS: &str = r#"
+function(text) {
+    this.focus();
+    if (this.isContentEditable) {
+        this.textContent = text;
+    } else {
+        const setter = Object.getOwnPropertyDescriptor(Object.getPrototypeOf(this), 'value')?.set;
+        if (setter) setter.call(this, text); else this.value = text;
+    }
+    this.dispatchEvent(new Event('input', { bubbles: true }));
+    this.dispatchEvent(new Event('change', { bubbles: true }));
+}
+"#;
+
+/// Options of `this` as [`SelectOption`]s, `null` if it is not a `<select>`
+const SELECT_OPTIONS_JS: &str = r#"
+function() {
+    if (this.localName !== 'select') return null;
+    return Array.from(this.options).map(o => ({ value: o.value, text: o.text.trim(), selected: o.selected }));
+}
+"#;
+
+/// Selects the option of `this` whose text or value is `wanted`, or whose text
+/// contains it, firing `input` and `change`; returns it as a [`SelectOption`]
+const SELECT_OPTION_JS: &str = r#"
+function(wanted) {
+    if (this.localName !== 'select') return { error: 'not_select' };
+    const options = Array.from(this.options);
+    const option = options.find(o => o.text.trim() === wanted || o.value === wanted)
+        || options.find(o => o.text.includes(wanted));
+    if (!option) return { error: 'no_option' };
+    this.value = option.value;
+    this.dispatchEvent(new Event('input', { bubbles: true }));
+    this.dispatchEvent(new Event('change', { bubbles: true }));
+    return { option: { value: option.value, text: option.text.trim(), selected: true } };
+}
+"#;
+
+/// Value or checked state of `this` as a form control, as a [`ControlState`]
+const CONTROL_STATE_JS: &str = r#"
+function() {
+    const tag = this.localName;
+    if (tag === 'select') {
+        const option = this.options[this.selectedIndex];
+        return { kind: 'select', value: this.value, label: option ? option.text.trim() : '' };
+    }
+    if (tag === 'input' && this.type === 'checkbox') {
+        return { kind: 'checkbox', checked: this.checked };
+    }
+    if (this.isContentEditable) return { kind: 'text', value: this.textContent };
+    if ((tag === 'input' && this.type !== 'radio') || tag === 'textarea') {
+        return { kind: 'text', value: this.value, password: this.type === 'password' };
+    }
+    return { kind: 'other' };
+}
+"#;
+
+/// Puts `this` back into a [`ControlState`]: a checkbox is clicked if it
+/// differs, so the page's handlers run; other values are set through the
+/// native setter and announced with `input` and `change`
+const RESTORE_CONTROL_JS: &str = r#"
+function(state) {
+    if (state.kind === 'checkbox') {
+        if (this.checked !== state.checked) this.click();
+        return;
+    }
+    this.focus();
+    if (this.isContentEditable) {
+        this.textContent = state.value;
+    } else {
+        const setter = Object.getOwnPropertyDescriptor(Object.getPrototypeOf(this), 'value')?.set;
+        if (setter) setter.call(this, state.value); else this.value = state.value;
+    }
+    this.dispatchEvent(new Event('input', { bubbles: true }));
+    this.dispatchEvent(new Event('change', { bubbles: true }));
+}
+"#;
+
+/// Scrolls `this` by a number of its own heights and reports where it ended up
+const SCROLL_BY_PAGES_JS: &str = r#"
+function(pages) {
+    this.scrollBy({ top: pages * this.clientHeight, behavior: 'instant' });
+    return { top: this.scrollTop, height: this.scrollHeight, clientHeight: this.clientHeight };
+}
+"#;
+
+/// Whether `this` is what its frame shows at the center of its box, i.e. it
+/// is not clipped away by a scroll container or covered by another element
+const HIT_TEST_JS: &str = r#"
+function() {
+    const rect = this.getBoundingClientRect();
+    const hit = this.ownerDocument.elementFromPoint(rect.left + rect.width / 2, rect.top + rect.height / 2);
+    return !!hit && (hit === this || this.contains(hit));
+}
+"#;
+
+/// Submits the form `this` belongs to with `requestSubmit()`, which validates
+/// it and runs submit handlers like a click on its submit button would
+const SUBMIT_FORM_JS: &str = r#"
+function() {
+    const form = this.form || this.closest('form');
+    if (!form) return { status: 'no_form' };
+    const invalid = Array.from(form.elements).find((el) => el.willValidate && !el.checkValidity());
+    if (invalid) {
+        return {
+            status: 'invalid',
+            field: invalid.name || invalid.id || invalid.localName,
+            message: invalid.validationMessage,
+        };
+    }
+    if (typeof form.requestSubmit !== 'function') return { status: 'unsupported' };
+    try {
+        form.requestSubmit();
+        return { status: 'submitted' };
+    } catch (e) {
+        return { status: 'unsupported' };
+    }
+}
+"#;
+
+/// Submit button of the form `this` belongs to, or null
+const FIND_SUBMIT_BUTTON_JS: &str = r#"
+function() {
+    const form = this.form || this.closest('form');
+    if (!form) return null;
+    return Array.from(form.elements).find((el) =>
+        (el.localName === 'button' && el.type === 'submit') ||
+        (el.localName === 'input' && (el.type === 'submit' || el.type === 'image'))
+    ) || null;
+}
+"#;
+
+/// State of a form control, read by [`Element::control_state`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ControlState {
+    /// Text input, textarea or contenteditable element
+    Text {
+        /// Current text
+        value: String,
+        /// Whether the element is a password field, whose text is not displayed
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        password: bool,
+    },
+    /// Checkbox
+    Checkbox {
+        /// Whether it is checked
+        checked: bool,
+    },
+    /// `<select>`
+    Select {
+        /// Value of the selected option
+        value: String,
+        /// Text of the selected option
+        label: String,
+    },
+    /// Anything else, including radio buttons, which cannot be restored
+    Other,
+}
+
+impl std::fmt::Display for ControlState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text { password: true, .. } => f.write_str("[password]"),
+            Self::Text { value, .. } => write!(f, "{value:?}"),
+            Self::Checkbox { checked: true } => f.write_str("checked"),
+            Self::Checkbox { checked: false } => f.write_str("unchecked"),
+            Self::Select { label, .. } => write!(f, "{label:?}"),
+            Self::Other => f.write_str("unknown"),
+        }
+    }
+}
+
+/// How [`Element::submit_form`] submitted the element's form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormSubmission {
+    /// Called `form.requestSubmit()`
+    RequestSubmit,
+    /// Clicked the form's submit button
+    SubmitButton,
+    /// Pressed Enter in the element
+    Enter,
+}
+
+impl std::fmt::Display for FormSubmission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::RequestSubmit => "requestSubmit()",
+            Self::SubmitButton => "its submit button",
+            Self::Enter => "Enter",
+        })
+    }
+}
+
+/// An option of a `<select>`, read by [`Element::select_options`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelectOption {
+    /// Value submitted with the form
+    pub value: String,
+    /// Text shown to the user
+    pub text: String,
+    /// Whether the option is selected
+    pub selected: bool,
+}
+
+/// Scroll offset of a scroll container after [`Element::scroll_by_pages`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrollPosition {
+    /// `scrollTop`, in CSS pixels
+    pub top: f64,
+    /// `scrollHeight`, the height of the content
+    pub height: f64,
+    /// `clientHeight`, the height of the visible part
+    pub client_height: f64,
+}
+
+impl ScrollPosition {
+    /// Whether the container shows its first row
+    pub fn at_top(&self) -> bool {
+        self.top <= 1.0
+    }
+
+    /// Whether the container shows its last row
+    pub fn at_bottom(&self) -> bool {
+        self.top + self.client_height >= self.height - 1.0
+    }
+}
+
+/// Locator for elements inside another, by ARIA role, visible text and/or CSS selector
+///
+/// Every criterion that is set must match.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DescendantLocator {
+    /// ARIA role, explicit or implied by the tag (e.g. `button`, `link`)
+    pub role: Option<String>,
+    /// Case-insensitive substring of the element's visible text or label
+    pub text: Option<String>,
+    /// CSS selector, scoped to the container
+    pub css: Option<String>,
+}
+
+impl DescendantLocator {
+    /// Create an empty locator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match elements with this ARIA role
+    pub fn with_role(mut self, role: impl Into<String>) -> Self {
+        self.role = Some(role.into());
+        self
+    }
+
+    /// Match elements whose text contains this
+    pub fn with_text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    /// Match elements by CSS selector
+    pub fn with_css(mut self, css: impl Into<String>) -> Self {
+        self.css = Some(css.into());
+        self
+    }
+
+    /// Whether no criterion is set
+    pub fn is_empty(&self) -> bool {
+        self.role.is_none() && self.text.is_none() && self.css.is_none()
+    }
+}
+
+impl std::fmt::Display for DescendantLocator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(role) = &self.role {
+            parts.push(format!("role={role}"));
+        }
+        if let Some(text) = &self.text {
+            parts.push(format!("text=\"{text}\""));
+        }
+        if let Some(css) = &self.css {
+            parts.push(format!("css={css}"));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// Element found by [`Element::find_descendants`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescendantMatch {
+    /// Backend node ID of the match
+    pub backend_node_id: u32,
+    /// Visible text or label, truncated
+    pub text: String,
+}
+
+/// Key that activates a focused element, as keyboard users do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivationKey {
+    /// Activates links and buttons
+    Enter,
+    /// Activates buttons and toggles checkboxes, radios and switches
+    Space,
+}
+
+impl ActivationKey {
+    /// Keys that activate an element with ARIA `role`, in the order to try them
+    pub fn for_role(role: &str) -> &'static [ActivationKey] {
+        match role {
+            "link" => &[ActivationKey::Enter],
+            "checkbox" | "radio" | "switch" | "option" => &[ActivationKey::Space],
+            "button" | "menuitem" | "tab" | "treeitem" => {
+                &[ActivationKey::Enter, ActivationKey::Space]
+            }
+            _ => &[ActivationKey::Enter],
+        }
+    }
+
+    /// `key` value of the key's events
+    pub fn key(self) -> &'static str {
+        match self {
+            ActivationKey::Enter => "Enter",
+            ActivationKey::Space => " ",
+        }
+    }
+
+    /// Text the key types, which makes Chrome fire `keypress` and default actions
+    fn text(self) -> &'static str {
+        match self {
+            ActivationKey::Enter => "\r",
+            ActivationKey::Space => " ",
+        }
+    }
+}
+
+impl std::fmt::Display for ActivationKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ActivationKey::Enter => "Enter",
+            ActivationKey::Space => "Space",
+        })
+    }
+}
+
 /// Element operations using BackendNodeId
 pub struct Element {
     client: Arc<CdpClient>,
     session_id: String,
     backend_node_id: u32,
+    /// Frame the element is in, if not the top-level document
+    frame_id: Option<String>,
 }
 
 impl Element {
@@ -20,7 +437,303 @@ impl Element {
             client,
             session_id,
             backend_node_id,
+            frame_id: None,
+        }
+    }
+
+    /// Resolve the element in the execution context of the iframe `frame_id`
+    ///
+    /// Scripts called on the element then run with that frame's globals.
+    /// `None` keeps the default, which is right for top-level elements.
+    pub fn with_frame(mut self, frame_id: Option<String>) -> Self {
+        self.frame_id = frame_id;
+        self
+    }
+
+    /// Backend node ID of the element
+    pub fn backend_node_id(&self) -> u32 {
+        self.backend_node_id
+    }
+
+    /// Frame the element is in, `None` for the top-level document
+    pub fn frame_id(&self) -> Option<&str> {
+        self.frame_id.as_deref()
+    }
+
+    /// Params of `DOM.resolveNode` for the element, in its frame's context when known
+    async fn resolve_node_params(&self, object_group: &str) -> serde_json::Value {
+        let mut params =
+            json!({ "backendNodeId": self.backend_node_id, "objectGroup": object_group });
+        if let Some(context_id) = self.frame_context().await {
+            params["executionContextId"] = json!(context_id);
+        }
+        params
+    }
+
+    /// Main-world execution context of the element's frame
+    async fn frame_context(&self) -> Option<i64> {
+        let frame_id = self.frame_id.as_deref()?;
+        if let Some(context_id) = self.client.frame_execution_context(&self.session_id, frame_id) {
+            return Some(context_id);
+        }
+        // Enabling the domain makes Chrome report the contexts it already has
+        if let Err(e) = self.send("Runtime.enable", json!({})).await {
+            tracing::debug!("Runtime.enable failed, resolving in the default context: {}", e);
+            return None;
+        }
+        let context_id = self.client.frame_execution_context(&self.session_id, frame_id);
+        if context_id.is_none() {
+            tracing::debug!("No execution context for frame {}, resolving in the default context", frame_id);
+        }
+        context_id
+    }
+
+    /// Find rendered descendants matching `locator`
+    ///
+    /// When an element and one of its descendants both match, only the
+    /// innermost is returned, so a text match lands on the element showing it.
+    pub async fn find_descendants(
+        &self,
+        locator: &DescendantLocator,
+    ) -> Result<Vec<DescendantMatch>> {
+        if locator.is_empty() {
+            return Err(BrowsingError::Validation(
+                "Descendant locator needs a role, text or css".to_string(),
+            ));
+        }
+        let object_group = "browsing-descendants";
+        let found = self.query_descendants(locator, object_group).await;
+        let _ = self
+            .send(
+                "Runtime.releaseObjectGroup",
+                json!({ "objectGroup": object_group }),
+            )
+            .await;
+        found
+    }
+
+    async fn query_descendants(
+        &self,
+        locator: &DescendantLocator,
+        object_group: &str,
+    ) -> Result<Vec<DescendantMatch>> {
+        let params = self.resolve_node_params(object_group).await;
+        let resolved = self.send("DOM.resolveNode", params).await?;
+        let object_id = resolved
+            .get("object")
+            .and_then(|v| v.get("objectId"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| BrowsingError::Dom("Failed to resolve container element".to_string()))?;
+
+        let found = self
+            .send(
+                "Runtime.callFunctionOn",
+                json!({
+                    "functionDeclaration": FIND_DESCENDANTS_JS,
+                    "objectId": object_id,
+                    "arguments": [{ "value": {
+                        "role": locator.role,
+                        "text": locator.text,
+                        "css": locator.css,
+                    } }],
+                    "objectGroup": object_group,
+                }),
+            )
+            .await?;
+        if let Some(exception) = found.get("exceptionDetails") {
+            let description = exception
+                .get("exception")
+                .and_then(|v| v.get("description"))
+                .and_then(|v| v.as_str())
+                .unwrap_or("lookup failed");
+            return Err(BrowsingError::Dom(format!(
+                "Could not find {locator}: {description}"
+            )));
+        }
+        let array_id = found
+            .get("result")
+            .and_then(|v| v.get("objectId"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| BrowsingError::Dom("Descendant lookup returned nothing".to_string()))?;
+
+        let described = self
+            .send(
+                "Runtime.callFunctionOn",
+                json!({
+                    "functionDeclaration": DESCRIBE_MATCHES_JS,
+                    "objectId": array_id,
+                    "returnByValue": true,
+                }),
+            )
+            .await?;
+        let texts: Vec<String> = described
+            .get("result")
+            .and_then(|v| v.get("value"))
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .map(|v| v.as_str().unwrap_or_default().to_string())
+            .collect();
+
+        let properties = self
+            .send(
+                "Runtime.getProperties",
+                json!({ "objectId": array_id, "ownProperties": true }),
+            )
+            .await?;
+        let mut matches = Vec::new();
+        for property in properties
+            .get("result")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+        {
+            let Some(index) = property
+                .get("name")
+                .and_then(|v| v.as_str())
+                .and_then(|n| n.parse::<usize>().ok())
+            else {
+                continue;
+            };
+            let Some(match_id) = property
+                .get("value")
+                .and_then(|v| v.get("objectId"))
+                .and_then(|v| v.as_str())
+            else {
+                continue;
+            };
+            let node = self
+                .send("DOM.describeNode", json!({ "objectId": match_id }))
+                .await?;
+            if let Some(backend_node_id) = node
+                .get("node")
+                .and_then(|v| v.get("backendNodeId"))
+                .and_then(|v| v.as_u64())
+            {
+                matches.push((
+                    index,
+                    DescendantMatch {
+                        backend_node_id: backend_node_id as u32,
+                        text: texts.get(index).cloned().unwrap_or_default(),
+                    },
+                ));
+            }
         }
+        matches.sort_by_key(|(index, _)| *index);
+        Ok(matches.into_iter().map(|(_, m)| m).collect())
+    }
+
+    /// Submit the form this element belongs to (as a control or descendant)
+    ///
+    /// Uses `form.requestSubmit()`. Where that is unavailable, clicks the
+    /// form's submit button, or presses Enter in this element if it has none.
+    /// Fails without submitting if the element is not in a form or a field
+    /// does not pass validation.
+    pub async fn submit_form(&self) -> Result<FormSubmission> {
+        let object_group = "browsing-submit";
+        let submitted = self.request_submit(object_group).await;
+        let _ = self
+            .send(
+                "Runtime.releaseObjectGroup",
+                json!({ "objectGroup": object_group }),
+            )
+            .await;
+        submitted
+    }
+
+    async fn request_submit(&self, object_group: &str) -> Result<FormSubmission> {
+        let object_id = self.resolve(object_group).await?;
+        let submitted = self
+            .send(
+                "Runtime.callFunctionOn",
+                json!({
+                    "functionDeclaration": SUBMIT_FORM_JS,
+                    "objectId": object_id,
+                    "returnByValue": true,
+                }),
+            )
+            .await?;
+        let outcome = submitted
+            .get("result")
+            .and_then(|v| v.get("value"))
+            .cloned()
+            .unwrap_or_default();
+        match outcome["status"].as_str() {
+            Some("submitted") => return Ok(FormSubmission::RequestSubmit),
+            Some("no_form") => {
+                return Err(BrowsingError::Dom("Element is not in a form".to_string()));
+            }
+            Some("invalid") => {
+                return Err(BrowsingError::Validation(format!(
+                    "Form not submitted: field '{}' is invalid: {}",
+                    outcome["field"].as_str().unwrap_or_default(),
+                    outcome["message"].as_str().unwrap_or_default()
+                )));
+            }
+            _ => {}
+        }
+
+        let button = self
+            .send(
+                "Runtime.callFunctionOn",
+                json!({
+                    "functionDeclaration": FIND_SUBMIT_BUTTON_JS,
+                    "objectId": object_id,
+                    "objectGroup": object_group,
+                }),
+            )
+            .await?;
+        if let Some(button_id) = button
+            .get("result")
+            .and_then(|v| v.get("objectId"))
+            .and_then(|v| v.as_str())
+        {
+            let described = self
+                .send("DOM.describeNode", json!({ "objectId": button_id }))
+                .await?;
+            if let Some(backend_node_id) = described
+                .get("node")
+                .and_then(|v| v.get("backendNodeId"))
+                .and_then(|v| v.as_u64())
+            {
+                let button = Element::new(
+                    Arc::clone(&self.client),
+                    self.session_id.clone(),
+                    backend_node_id as u32,
+                );
+                button.click(MouseButton::Left, 1, None).await?;
+                return Ok(FormSubmission::SubmitButton);
+            }
+        }
+
+        self.send("DOM.focus", json!({ "backendNodeId": self.backend_node_id }))
+            .await?;
+        let (code, key_code) = crate::actor::keyboard::get_key_info("Enter");
+        for (event_type, text) in [("keyDown", Some("\r")), ("keyUp", None)] {
+            let mut params = json!({
+                "type": event_type,
+                "key": "Enter",
+                "code": code,
+                "windowsVirtualKeyCode": key_code.unwrap_or(13),
+            });
+            if let Some(text) = text {
+                params["text"] = json!(text);
+            }
+            self.send("Input.dispatchKeyEvent", params).await?;
+        }
+        Ok(FormSubmission::Enter)
+    }
+
+    /// Remote object ID of the element in `object_group`
+    async fn resolve(&self, object_group: &str) -> Result<String> {
+        let params = self.resolve_node_params(object_group).await;
+        let resolved = self.send("DOM.resolveNode", params).await?;
+        resolved
+            .get("object")
+            .and_then(|v| v.get("objectId"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| BrowsingError::Dom("Failed to resolve element".to_string()))
     }
 
     /// Get DOM node ID from backend node ID
@@ -44,81 +757,18 @@ impl Element {
     }
 
     /// Click the element
+    ///
+    /// The click point is the center of the element's content quad, falling
+    /// back to its box model, then to both again after scrolling it into view.
+    /// Returns [`BrowsingError::NotVisible`] rather than clicking elsewhere if
+    /// the element has no geometry (e.g. `display: none`, zero size, detached).
     pub async fn click(
         &self,
         button: MouseButton,
         click_count: u32,
         modifiers: Option<Vec<String>>,
     ) -> Result<()> {
-        // Get viewport dimensions
-        let layout_metrics = self
-            .client
-            .send_command("Page.getLayoutMetrics", json!({}))
-            .await?;
-
-        let viewport_width = layout_metrics
-            .get("layoutViewport")
-            .and_then(|v| v.get("clientWidth"))
-            .and_then(|v| v.as_f64())
-            .unwrap_or(1920.0);
-        let viewport_height = layout_metrics
-            .get("layoutViewport")
-            .and_then(|v| v.get("clientHeight"))
-            .and_then(|v| v.as_f64())
-            .unwrap_or(1080.0);
-
-        // Try to get element geometry
-        let mut center_x = viewport_width / 2.0;
-        let mut center_y = viewport_height / 2.0;
-
-        // Try DOM.getContentQuads first
-        let quads_result = self
-            .client
-            .send_command(
-                "DOM.getContentQuads",
-                json!({ "backendNodeId": self.backend_node_id }),
-            )
-            .await;
-
-        if let Ok(quads_result) = quads_result {
-            if let Some(quads) = quads_result.get("quads").and_then(|v| v.as_array()) {
-                if let Some(first_quad) = quads.first().and_then(|v| v.as_array()) {
-                    if first_quad.len() >= 8 {
-                        // Calculate center of quad
-                        let x_coords: Vec<f64> = first_quad
-                            .iter()
-                            .step_by(2)
-                            .filter_map(|v| v.as_f64())
-                            .collect();
-                        let y_coords: Vec<f64> = first_quad
-                            .iter()
-                            .skip(1)
-                            .step_by(2)
-                            .filter_map(|v| v.as_f64())
-                            .collect();
-
-                        if !x_coords.is_empty() && !y_coords.is_empty() {
-                            center_x = x_coords.iter().sum::<f64>() / x_coords.len() as f64;
-                            center_y = y_coords.iter().sum::<f64>() / y_coords.len() as f64;
-                        }
-                    }
-                }
-            }
-        }
-
-        // Ensure coordinates are within viewport
-        center_x = center_x.max(0.0).min(viewport_width - 1.0);
-        center_y = center_y.max(0.0).min(viewport_height - 1.0);
-
-        // Scroll element into view
-        let _ = self
-            .client
-            .send_command(
-                "DOM.scrollIntoViewIfNeeded",
-                json!({ "backendNodeId": self.backend_node_id }),
-            )
-            .await;
-        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+        let (center_x, center_y) = self.visible_center().await?;
 
         // Calculate modifier bitmask
         let mut modifier_value = 0u32;
@@ -140,10 +790,7 @@ impl Element {
             "x": center_x,
             "y": center_y,
         });
-        let _ = self
-            .client
-            .send_command("Input.dispatchMouseEvent", move_params)
-            .await;
+        let _ = self.send("Input.dispatchMouseEvent", move_params).await;
         tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
 
         // Mouse down
@@ -155,10 +802,7 @@ impl Element {
             "clickCount": click_count,
             "modifiers": modifier_value,
         });
-        let _ = self
-            .client
-            .send_command("Input.dispatchMouseEvent", press_params)
-            .await;
+        let _ = self.send("Input.dispatchMouseEvent", press_params).await;
         tokio::time::sleep(tokio::time::Duration::from_millis(80)).await;
 
         // Mouse up
@@ -170,47 +814,499 @@ impl Element {
             "clickCount": click_count,
             "modifiers": modifier_value,
         });
-        self.client
-            .send_command("Input.dispatchMouseEvent", release_params)
+        self.send("Input.dispatchMouseEvent", release_params).await?;
+
+        Ok(())
+    }
+
+    /// Click the element with a pointer event sequence from `pointer_type`
+    ///
+    /// Enters, moves onto, presses and releases the element center found as
+    /// in [`click`](Self::click), with the pressure and `pointerType` of a real
+    /// device. Suits pages that handle `pointerdown`/`pointerup` themselves,
+    /// or that show controls only after `pointerenter`.
+    pub async fn pointer_click(&self, pointer_type: PointerType) -> Result<()> {
+        let (x, y) = self.visible_center().await?;
+        let touch = pointer_type == PointerType::Touch;
+        let mut sequence = vec![(PointerEventType::Enter, 0.0)];
+        // A finger cannot hover, so it only touches down and lifts
+        if !touch {
+            sequence.push((PointerEventType::Move, 0.0));
+        }
+        sequence.extend([(PointerEventType::Down, 0.5), (PointerEventType::Up, 0.0)]);
+
+        for (event_type, pressure) in sequence {
+            PointerInput {
+                event_type,
+                x,
+                y,
+                pointer_id: 1,
+                pointer_type,
+                pressure,
+                tilt_x: 0.0,
+                tilt_y: 0.0,
+            }
+            .dispatch(&self.client, &self.session_id)
+            .await?;
+            if event_type != PointerEventType::Up {
+                tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Click the element with `HTMLElement.click()` instead of input events
+    ///
+    /// Reaches the element's listeners even when an overlay covers it, but
+    /// skips everything a real press does first (hover, `mousedown`, focus).
+    pub async fn js_click(&self) -> Result<()> {
+        let object_group = "browsing-js-click";
+        let clicked = self.call_click(object_group).await;
+        let _ = self
+            .send(
+                "Runtime.releaseObjectGroup",
+                json!({ "objectGroup": object_group }),
+            )
+            .await;
+        clicked
+    }
+
+    async fn call_click(&self, object_group: &str) -> Result<()> {
+        let object_id = self.resolve(object_group).await?;
+        let result = self
+            .send(
+                "Runtime.callFunctionOn",
+                json!({
+                    "functionDeclaration": "function() { this.click(); }",
+                    "objectId": object_id,
+                }),
+            )
             .await?;
+        if let Some(exception) = result.get("exceptionDetails") {
+            return Err(BrowsingError::Dom(format!("JavaScript click failed: {exception}")));
+        }
+        Ok(())
+    }
+
+    /// ARIA role of the element, explicit or implied by its tag (e.g. `link`
+    /// for `<a href>`), `None` if it has none
+    pub async fn role(&self) -> Result<Option<String>> {
+        let object_group = "browsing-role";
+        let role = self.call_role(object_group).await;
+        let _ = self
+            .send(
+                "Runtime.releaseObjectGroup",
+                json!({ "objectGroup": object_group }),
+            )
+            .await;
+        role
+    }
 
+    async fn call_role(&self, object_group: &str) -> Result<Option<String>> {
+        let object_id = self.resolve(object_group).await?;
+        let result = self
+            .send(
+                "Runtime.callFunctionOn",
+                json!({
+                    "functionDeclaration": ROLE_JS,
+                    "objectId": object_id,
+                    "returnByValue": true,
+                }),
+            )
+            .await?;
+        if let Some(exception) = result.get("exceptionDetails") {
+            return Err(BrowsingError::Dom(format!("Reading the role failed: {exception}")));
+        }
+        Ok(result
+            .get("result")
+            .and_then(|v| v.get("value"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string))
+    }
+
+    /// Move keyboard focus to the element
+    pub async fn focus(&self) -> Result<()> {
+        self.send("DOM.focus", json!({ "backendNodeId": self.backend_node_id }))
+            .await?;
         Ok(())
     }
 
+    /// Press `key` on the focused element, as keyboard users activate it
+    ///
+    /// Call [`Element::focus`] first; the key goes to whatever has focus.
+    pub async fn press_activation_key(&self, key: ActivationKey) -> Result<()> {
+        let (code, key_code) = crate::actor::keyboard::get_key_info(key.key());
+        for (event_type, text) in [("keyDown", Some(key.text())), ("keyUp", None)] {
+            let mut params = json!({
+                "type": event_type,
+                "key": key.key(),
+                "code": code,
+                "windowsVirtualKeyCode": key_code,
+            });
+            if let Some(text) = text {
+                params["text"] = json!(text);
+            }
+            self.send("Input.dispatchKeyEvent", params).await?;
+        }
+        Ok(())
+    }
+
+    /// Scroll the element's own content by `pages` of its visible height
+    ///
+    /// For scroll containers inside the page, which wheel events over the
+    /// window do not reach. Negative `pages` scroll up.
+    pub async fn scroll_by_pages(&self, pages: f64) -> Result<ScrollPosition> {
+        let object_group = "browsing-scroll";
+        let scrolled = self.call_scroll_by_pages(object_group, pages).await;
+        let _ = self
+            .send(
+                "Runtime.releaseObjectGroup",
+                json!({ "objectGroup": object_group }),
+            )
+            .await;
+        scrolled
+    }
+
+    async fn call_scroll_by_pages(&self, object_group: &str, pages: f64) -> Result<ScrollPosition> {
+        let object_id = self.resolve(object_group).await?;
+        let result = self
+            .send(
+                "Runtime.callFunctionOn",
+                json!({
+                    "functionDeclaration": SCROLL_BY_PAGES_JS,
+                    "objectId": object_id,
+                    "arguments": [{ "value": pages }],
+                    "returnByValue": true,
+                }),
+            )
+            .await?;
+        if let Some(exception) = result.get("exceptionDetails") {
+            return Err(BrowsingError::Dom(format!("Scrolling the element failed: {exception}")));
+        }
+        let value = &result["result"]["value"];
+        Ok(ScrollPosition {
+            top: value["top"].as_f64().unwrap_or_default(),
+            height: value["height"].as_f64().unwrap_or_default(),
+            client_height: value["clientHeight"].as_f64().unwrap_or_default(),
+        })
+    }
+
+    /// Scroll the page, and any containers around the element, until it is in view
+    ///
+    /// Always sends `DOM.scrollIntoViewIfNeeded`, which leaves a visible
+    /// element where it is. Returns whether the element was hidden before:
+    /// outside the viewport, or clipped by a scroll container or covered at
+    /// the center of its box (`document.elementFromPoint`). Elements in
+    /// iframes are scrolled into view of the top-level viewport. Returns
+    /// [`BrowsingError::NotVisible`] if the element has no geometry to scroll to.
+    pub async fn scroll_into_view(&self) -> Result<bool> {
+        let node = json!({ "backendNodeId": self.backend_node_id });
+        let session = Some(self.session_id.as_str());
+        let [metrics, quads, box_model] = self
+            .batch([
+                ("Page.getLayoutMetrics", json!({}), session),
+                ("DOM.getContentQuads", node.clone(), session),
+                ("DOM.getBoxModel", node.clone(), session),
+            ])
+            .await;
+        let (viewport_width, viewport_height) = viewport_size(&metrics?);
+        let in_viewport =
+            |(x, y): (f64, f64)| x >= 0.0 && y >= 0.0 && x < viewport_width && y < viewport_height;
+        let was_in_view = first_quad(quads, box_model)
+            .is_some_and(|quad| in_viewport(quad_center(&quad)))
+            && self.is_hit_at_center().await;
+
+        self.send("DOM.scrollIntoViewIfNeeded", node).await?;
+        if self.click_point().await.is_none() {
+            return Err(self.not_visible(
+                "has no size or layout (hidden, zero-size or detached from the document)",
+            ));
+        }
+        Ok(!was_in_view)
+    }
+
+    /// Whether the element is what its frame shows at the center of its box;
+    /// false if that cannot be told
+    async fn is_hit_at_center(&self) -> bool {
+        self.call_in_group("browsing-hit-test", HIT_TEST_JS, vec![])
+            .await
+            .is_ok_and(|hit| hit == json!(true))
+    }
+
+    /// Value, checked state or selected option of the element as a form control
+    pub async fn control_state(&self) -> Result<ControlState> {
+        let value = self
+            .call_in_group("browsing-control-state", CONTROL_STATE_JS, vec![])
+            .await?;
+        Ok(serde_json::from_value(value).unwrap_or(ControlState::Other))
+    }
+
+    /// Options of the element, which must be a `<select>`
+    pub async fn select_options(&self) -> Result<Vec<SelectOption>> {
+        let value = self
+            .call_in_group("browsing-select-options", SELECT_OPTIONS_JS, vec![])
+            .await?;
+        if value.is_null() {
+            return Err(BrowsingError::Dom("Element is not a select dropdown".to_string()));
+        }
+        serde_json::from_value(value)
+            .map_err(|e| BrowsingError::Dom(format!("Unexpected select options: {e}")))
+    }
+
+    /// Select the option whose text or value is `text`, or whose text contains it
+    ///
+    /// Fires `input` and `change` as a user's choice would. Fails if the
+    /// element is not a `<select>` or has no such option.
+    pub async fn select_option(&self, text: &str) -> Result<SelectOption> {
+        let value = self
+            .call_in_group(
+                "browsing-select-option",
+                SELECT_OPTION_JS,
+                vec![json!({ "value": text })],
+            )
+            .await?;
+        match value["error"].as_str() {
+            Some("not_select") => Err(BrowsingError::Dom(
+                "Element is not a select dropdown".to_string(),
+            )),
+            Some(_) => Err(BrowsingError::Dom(format!("Option \"{text}\" not found"))),
+            None => serde_json::from_value(value["option"].clone())
+                .map_err(|e| BrowsingError::Dom(format!("Unexpected select option: {e}"))),
+        }
+    }
+
+    /// Put the element back into `state`, firing the events a user's change would
+    pub async fn restore_control_state(&self, state: &ControlState) -> Result<()> {
+        if *state == ControlState::Other {
+            return Err(BrowsingError::Dom(
+                "The element's state cannot be restored".to_string(),
+            ));
+        }
+        self.call_in_group(
+            "browsing-restore-control",
+            RESTORE_CONTROL_JS,
+            vec![json!({ "value": state })],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Call `function` on the element with `arguments`, returning its result by value
+    async fn call_in_group(
+        &self,
+        object_group: &str,
+        function: &str,
+        arguments: Vec<serde_json::Value>,
+    ) -> Result<serde_json::Value> {
+        let called = async {
+            let object_id = self.resolve(object_group).await?;
+            let result = self
+                .send(
+                    "Runtime.callFunctionOn",
+                    json!({
+                        "functionDeclaration": function,
+                        "objectId": object_id,
+                        "arguments": arguments,
+                        "returnByValue": true,
+                    }),
+                )
+                .await?;
+            if let Some(exception) = result.get("exceptionDetails") {
+                return Err(BrowsingError::Dom(format!(
+                    "Calling a function on the element failed: {exception}"
+                )));
+            }
+            Ok(result["result"]["value"].clone())
+        }
+        .await;
+        let _ = self
+            .send(
+                "Runtime.releaseObjectGroup",
+                json!({ "objectGroup": object_group }),
+            )
+            .await;
+        called
+    }
+
     /// Fill the element with text (clears first, then types)
     pub async fn fill(&self, text: &str) -> Result<()> {
-        // Focus the element
-        let node_id = self.get_node_id().await?;
-        let focus_params = json!({ "nodeId": node_id });
-        let _ = self.client.send_command("DOM.focus", focus_params).await;
-
-        // Clear and set value using JavaScript
-        let script = format!(
-            r#"
-            (() => {{
-                const node = arguments[0];
-                node.value = '';
-                node.focus();
-                node.value = {};
-                node.dispatchEvent(new Event('input', {{ bubbles: true }}));
-                node.dispatchEvent(new Event('change', {{ bubbles: true }}));
-                return node.value;
-            }})
-            "#,
-            serde_json::to_string(text)?
-        );
+        let object_group = "browsing-fill";
+        let filled = self.call_fill(object_group, text).await;
+        let _ = self
+            .send(
+                "Runtime.releaseObjectGroup",
+                json!({ "objectGroup": object_group }),
+            )
+            .await;
+        filled
+    }
 
-        let eval_params = json!({
-            "expression": script,
-            "returnByValue": true,
-        });
-        self.client
-            .send_command("Runtime.evaluate", eval_params)
-            .await?;
+    async fn call_fill(&self, object_group: &str, text: &str) -> Result<()> {
+        let object_id = self.resolve(object_group).await?;
+        self.call_function(json!({
+            "functionDeclaration": FILL_JS,
+            "objectId": object_id,
+            "arguments": [{ "value": text }],
+        }))
+        .await
+    }
+
+    /// Replace the element's text by typing `text` through IME composition
+    ///
+    /// Each grapheme cluster is inserted with `Input.insertText` between
+    /// `compositionstart` and `compositionend`, as an input method editor
+    /// would, so frameworks tracking composition see the text arrive. For
+    /// Japanese, Chinese or Korean text, which users type through an IME.
+    pub async fn input_with_ime(&self, text: &str) -> Result<()> {
+        let object_group = "browsing-ime-input";
+        let typed = self.call_input_with_ime(object_group, text).await;
+        let _ = self
+            .send(
+                "Runtime.releaseObjectGroup",
+                json!({ "objectGroup": object_group }),
+            )
+            .await;
+        typed
+    }
+
+    async fn call_input_with_ime(&self, object_group: &str, text: &str) -> Result<()> {
+        let object_id = self.resolve(object_group).await?;
+        let call = |function: &str, arguments: Vec<serde_json::Value>| {
+            json!({
+                "functionDeclaration": function,
+                "objectId": object_id,
+                "arguments": arguments,
+            })
+        };
+        let composition = |event_type: &str, data: &str| {
+            call(
+                ime::COMPOSITION_EVENT_JS,
+                vec![json!({ "value": event_type }), json!({ "value": data })],
+            )
+        };
+
+        self.call_function(call(ime::CLEAR_FOR_INPUT_JS, vec![])).await?;
+        for cluster in ime::grapheme_clusters(text) {
+            self.call_function(composition("compositionstart", "")).await?;
+            self.call_function(composition("compositionupdate", cluster))
+                .await?;
+            self.send("Input.insertText", json!({ "text": cluster }))
+                .await?;
+            self.call_function(composition("compositionend", cluster))
+                .await?;
+        }
+        self.call_function(call(ime::DISPATCH_CHANGE_JS, vec![]))
+            .await
+    }
 
+    /// `Runtime.callFunctionOn` with `params`, failing if the function throws
+    async fn call_function(&self, params: serde_json::Value) -> Result<()> {
+        let result = self.send("Runtime.callFunctionOn", params).await?;
+        if let Some(exception) = result.get("exceptionDetails") {
+            return Err(BrowsingError::Dom(format!("Typing into the element failed: {exception}")));
+        }
         Ok(())
     }
 
+    /// Current value of `attribute`, `None` if the element does not have it
+    pub async fn attribute(&self, attribute: &str) -> Result<Option<String>> {
+        let node_id = self.push_node().await?;
+        self.attribute_of(node_id, attribute).await
+    }
+
+    /// Wait for `attribute` to change, returning its new value
+    ///
+    /// Resolves when the attribute is set to `target_value`, or on any change
+    /// if `target_value` is `None`. A removed attribute reads as an empty
+    /// string. Returns at once if the attribute already has `target_value`,
+    /// and fails with [`BrowsingError::Dom`] after `timeout_ms`.
+    pub async fn wait_for_attribute_change(
+        &self,
+        attribute: &str,
+        target_value: Option<&str>,
+        timeout_ms: u64,
+    ) -> Result<String> {
+        // Subscribe first, so a change while the node is looked up is not missed
+        let mut events = self.client.subscribe_events();
+        let deadline = tokio::time::Instant::now() + std::time::Duration::from_millis(timeout_ms);
+        let node_id = self.push_node().await?;
+        let initial = self.attribute_of(node_id, attribute).await?;
+        if let Some(target) = target_value
+            && initial.as_deref() == Some(target)
+        {
+            return Ok(target.to_string());
+        }
+
+        loop {
+            let event = match tokio::time::timeout_at(deadline, events.recv()).await {
+                Ok(Ok(event)) => event,
+                Ok(Err(tokio::sync::broadcast::error::RecvError::Lagged(_))) => continue,
+                Ok(Err(_)) => return Err(BrowsingError::Cdp("CDP connection closed".to_string())),
+                Err(_) => {
+                    return Err(BrowsingError::Dom(format!(
+                        "Attribute '{attribute}' of backend node {} did not change{} within {timeout_ms}ms",
+                        self.backend_node_id,
+                        target_value
+                            .map(|target| format!(" to '{target}'"))
+                            .unwrap_or_default()
+                    )));
+                }
+            };
+            let params = &event["params"];
+            if event["sessionId"].as_str() != Some(self.session_id.as_str())
+                || params["nodeId"].as_u64() != Some(node_id as u64)
+                || params["name"].as_str() != Some(attribute)
+            {
+                continue;
+            }
+            let value = match event["method"].as_str() {
+                Some("DOM.attributeModified") => params["value"].as_str().map(str::to_string),
+                Some("DOM.attributeRemoved") => None,
+                _ => continue,
+            };
+            let changed = match target_value {
+                Some(target) => value.as_deref().unwrap_or_default() == target,
+                None => value != initial,
+            };
+            if changed {
+                return Ok(value.unwrap_or_default());
+            }
+        }
+    }
+
+    /// Frontend node ID of the element, with DOM events turned on
+    ///
+    /// `DOM.attributeModified` is only sent for nodes pushed to the frontend.
+    async fn push_node(&self) -> Result<u32> {
+        self.send("DOM.enable", json!({})).await?;
+        self.send("DOM.getDocument", json!({ "depth": 0 })).await?;
+        let result = self
+            .send(
+                "DOM.pushNodesByBackendIdsToFrontend",
+                json!({ "backendNodeIds": [self.backend_node_id] }),
+            )
+            .await?;
+        result["nodeIds"]
+            .get(0)
+            .and_then(|v| v.as_u64())
+            .filter(|&id| id != 0)
+            .map(|id| id as u32)
+            .ok_or_else(|| BrowsingError::Dom("Element is no longer in the document".to_string()))
+    }
+
+    async fn attribute_of(&self, node_id: u32, attribute: &str) -> Result<Option<String>> {
+        let result = self
+            .send("DOM.getAttributes", json!({ "nodeId": node_id }))
+            .await?;
+        let attributes = result["attributes"].as_array().cloned().unwrap_or_default();
+        Ok(attributes
+            .chunks(2)
+            .find(|pair| pair[0].as_str() == Some(attribute))
+            .and_then(|pair| pair.get(1))
+            .and_then(|v| v.as_str())
+            .map(str::to_string))
+    }
+
     /// Get element text content
     pub async fn text(&self) -> Result<String> {
         let _node_id = self.get_node_id().await?;
@@ -242,49 +1338,89 @@ impl Element {
 
     /// Get element bounding box
     pub async fn get_bounding_box(&self) -> Result<Option<(f64, f64, f64, f64)>> {
-        // Try DOM.getContentQuads first
-        let quads_result = self
-            .client
-            .send_command(
-                "DOM.getContentQuads",
-                json!({ "backendNodeId": self.backend_node_id }),
-            )
+        Ok(self.content_quad().await.map(|quad| quad_bounds(&quad)))
+    }
+
+    /// Send a command to this element's session
+    async fn send(&self, method: &str, params: serde_json::Value) -> Result<serde_json::Value> {
+        self.client
+            .send_command_with_session(method, params, Some(&self.session_id))
+            .await
+    }
+
+    /// Send commands to the browser in one batch, returning their results in order
+    async fn batch<const N: usize>(
+        &self,
+        commands: [BatchCommand<'_>; N],
+    ) -> [Result<serde_json::Value>; N] {
+        let mut results = self.client.send_batch(commands.into()).await.into_iter();
+        std::array::from_fn(|_| {
+            results
+                .next()
+                .unwrap_or_else(|| Err(BrowsingError::Cdp("Missing batch result".to_string())))
+        })
+    }
+
+    /// First non-empty quad of the element, from its content quads or box model
+    async fn content_quad(&self) -> Option<Vec<f64>> {
+        let node = json!({ "backendNodeId": self.backend_node_id });
+        let session = Some(self.session_id.as_str());
+        let [quads, box_model] = self
+            .batch([
+                ("DOM.getContentQuads", node.clone(), session),
+                ("DOM.getBoxModel", node, session),
+            ])
             .await;
+        first_quad(quads, box_model)
+    }
 
-        if let Ok(quads_result) = quads_result {
-            if let Some(quads) = quads_result.get("quads").and_then(|v| v.as_array()) {
-                if let Some(first_quad) = quads.first().and_then(|v| v.as_array()) {
-                    if first_quad.len() >= 8 {
-                        // Calculate bounding box from quad
-                        let x_coords: Vec<f64> = first_quad
-                            .iter()
-                            .step_by(2)
-                            .filter_map(|v| v.as_f64())
-                            .collect();
-                        let y_coords: Vec<f64> = first_quad
-                            .iter()
-                            .skip(1)
-                            .step_by(2)
-                            .filter_map(|v| v.as_f64())
-                            .collect();
-
-                        if !x_coords.is_empty() && !y_coords.is_empty() {
-                            let min_x = x_coords.iter().fold(f64::INFINITY, |a, &b| a.min(b));
-                            let max_x = x_coords.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-                            let min_y = y_coords.iter().fold(f64::INFINITY, |a, &b| a.min(b));
-                            let max_y = y_coords.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-
-                            let width = max_x - min_x;
-                            let height = max_y - min_y;
-
-                            return Ok(Some((min_x, min_y, width, height)));
-                        }
-                    }
-                }
-            }
+    /// Center of the element in viewport coordinates, scrolling it into view if needed
+    async fn visible_center(&self) -> Result<(f64, f64)> {
+        // Viewport and geometry are independent, so they share one round trip
+        let node = json!({ "backendNodeId": self.backend_node_id });
+        let session = Some(self.session_id.as_str());
+        let [metrics, quads, box_model] = self
+            .batch([
+                ("Page.getLayoutMetrics", json!({}), session),
+                ("DOM.getContentQuads", node.clone(), session),
+                ("DOM.getBoxModel", node, session),
+            ])
+            .await;
+        let (viewport_width, viewport_height) = viewport_size(&metrics?);
+        let in_viewport =
+            |(x, y): (f64, f64)| x >= 0.0 && y >= 0.0 && x < viewport_width && y < viewport_height;
+
+        let mut point = first_quad(quads, box_model).map(|quad| quad_center(&quad));
+        if !point.is_some_and(in_viewport) {
+            // Off-screen or not laid out yet: scroll into view and measure again
+            let _ = self
+                .send(
+                    "DOM.scrollIntoViewIfNeeded",
+                    json!({ "backendNodeId": self.backend_node_id }),
+                )
+                .await;
+            tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+            point = self.click_point().await;
         }
 
-        Ok(None)
+        match point {
+            Some(point) if in_viewport(point) => Ok(point),
+            Some(_) => Err(self.not_visible("is outside the viewport")),
+            None => Err(self.not_visible(
+                "has no size or layout (hidden, zero-size or detached from the document)",
+            )),
+        }
+    }
+
+    async fn click_point(&self) -> Option<(f64, f64)> {
+        self.content_quad().await.map(|quad| quad_center(&quad))
+    }
+
+    fn not_visible(&self, reason: &str) -> BrowsingError {
+        BrowsingError::NotVisible {
+            backend_node_id: self.backend_node_id,
+            reason: reason.to_string(),
+        }
     }
 
     /// Take a screenshot of this element
@@ -325,3 +1461,95 @@ impl Element {
         Ok(data.to_string())
     }
 }
+
+/// Layout viewport size from a `Page.getLayoutMetrics` result
+fn viewport_size(layout_metrics: &serde_json::Value) -> (f64, f64) {
+    let viewport = layout_metrics.get("layoutViewport");
+    let dimension = |name: &str| viewport.and_then(|v| v.get(name)).and_then(|v| v.as_f64());
+    (
+        dimension("clientWidth").unwrap_or(1920.0),
+        dimension("clientHeight").unwrap_or(1080.0),
+    )
+}
+
+/// First non-empty quad from `DOM.getContentQuads`, else from `DOM.getBoxModel`
+fn first_quad(
+    quads: Result<serde_json::Value>,
+    box_model: Result<serde_json::Value>,
+) -> Option<Vec<f64>> {
+    if let Ok(result) = quads
+        && let Some(quad) = result
+            .get("quads")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(parse_quad)
+            .find(|quad| quad_area(quad) > 0.0)
+    {
+        return Some(quad);
+    }
+
+    let result = box_model.ok()?;
+    parse_quad(result.get("model")?.get("content")?).filter(|quad| quad_area(quad) > 0.0)
+}
+
+/// Parse a CDP quad (`[x1, y1, x2, y2, x3, y3, x4, y4]`)
+fn parse_quad(value: &serde_json::Value) -> Option<Vec<f64>> {
+    let quad: Vec<f64> = value
+        .as_array()?
+        .iter()
+        .map(|v| v.as_f64())
+        .collect::<Option<_>>()?;
+    (quad.len() == 8).then_some(quad)
+}
+
+/// Area of a quad (shoelace formula)
+fn quad_area(quad: &[f64]) -> f64 {
+    let twice_area: f64 = (0..4)
+        .map(|i| {
+            let j = (i + 1) % 4;
+            quad[2 * i] * quad[2 * j + 1] - quad[2 * j] * quad[2 * i + 1]
+        })
+        .sum();
+    twice_area.abs() / 2.0
+}
+
+fn quad_center(quad: &[f64]) -> (f64, f64) {
+    let x = quad.iter().step_by(2).sum::<f64>() / 4.0;
+    let y = quad.iter().skip(1).step_by(2).sum::<f64>() / 4.0;
+    (x, y)
+}
+
+/// Axis-aligned bounds of a quad as (x, y, width, height)
+fn quad_bounds(quad: &[f64]) -> (f64, f64, f64, f64) {
+    let xs = quad.iter().step_by(2);
+    let ys = quad.iter().skip(1).step_by(2);
+    let min_x = xs.clone().fold(f64::INFINITY, |a, &b| a.min(b));
+    let max_x = xs.fold(f64::NEG_INFINITY, |a, &b| a.max(b));
+    let min_y = ys.clone().fold(f64::INFINITY, |a, &b| a.min(b));
+    let max_y = ys.fold(f64::NEG_INFINITY, |a, &b| a.max(b));
+    (min_x, min_y, max_x - min_x, max_y - min_y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quad_geometry() {
+        let quad = parse_quad(&json!([10, 20, 110, 20, 110, 70, 10, 70])).unwrap();
+        assert_eq!(quad_area(&quad), 5000.0);
+        assert_eq!(quad_center(&quad), (60.0, 45.0));
+        assert_eq!(quad_bounds(&quad), (10.0, 20.0, 100.0, 50.0));
+    }
+
+    #[test]
+    fn test_degenerate_quads() {
+        // Zero-size elements report a quad collapsed to a point
+        let point = parse_quad(&json!([5, 5, 5, 5, 5, 5, 5, 5])).unwrap();
+        assert_eq!(quad_area(&point), 0.0);
+
+        assert!(parse_quad(&json!([1, 2, 3])).is_none());
+        assert!(parse_quad(&json!(null)).is_none());
+    }
+}
@@ -0,0 +1,196 @@
+//! Color scheme, vision deficiency and Save-Data emulation
+//!
+//! Sites render differently in dark mode, for users with color vision
+//! deficiencies and when the browser asks for reduced data. These settings
+//! let a run check that extraction still works in each variant.
+//!
+//! Chrome scopes the overrides to a CDP session, so a tab loses them when it
+//! is re-attached; [`Browser`](crate::browser::Browser) remembers the settings
+//! of each tab and applies them again. `Emulation.setEmulatedMedia` replaces
+//! every emulated media feature at once, so [`EmulationSettings`] sends the
+//! color scheme and `prefers-reduced-data` together. Save-Data is sent as a
+//! request header, which replaces other extra headers set on the tab.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Value of `prefers-color-scheme`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorScheme {
+    /// `light`
+    Light,
+    /// `dark`
+    Dark,
+}
+
+impl ColorScheme {
+    /// Parse `light` or `dark`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "light" => Some(Self::Light),
+            "dark" => Some(Self::Dark),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ColorScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Light => "light",
+            Self::Dark => "dark",
+        })
+    }
+}
+
+/// Vision deficiency simulated by `Emulation.setEmulatedVisionDeficiency`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VisionDeficiency {
+    /// Normal vision
+    #[default]
+    None,
+    /// Blurred vision
+    BlurredVision,
+    /// Reduced contrast
+    ReducedContrast,
+    /// No color vision
+    Achromatopsia,
+    /// Red-green, missing green cones
+    Deuteranopia,
+    /// Red-green, missing red cones
+    Protanopia,
+    /// Blue-yellow
+    Tritanopia,
+}
+
+impl VisionDeficiency {
+    /// Name CDP uses for the deficiency
+    pub fn cdp_name(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::BlurredVision => "blurredVision",
+            Self::ReducedContrast => "reducedContrast",
+            Self::Achromatopsia => "achromatopsia",
+            Self::Deuteranopia => "deuteranopia",
+            Self::Protanopia => "protanopia",
+            Self::Tritanopia => "tritanopia",
+        }
+    }
+
+    /// Parse a name such as `deuteranopia` or `blurred_vision`
+    pub fn from_name(name: &str) -> Option<Self> {
+        serde_json::from_value(json!(name.to_ascii_lowercase())).ok()
+    }
+}
+
+impl fmt::Display for VisionDeficiency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.cdp_name())
+    }
+}
+
+/// Emulation settings of a tab
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmulationSettings {
+    /// `prefers-color-scheme` to report, `None` for the browser's own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color_scheme: Option<ColorScheme>,
+    /// Vision deficiency to simulate
+    #[serde(default, skip_serializing_if = "is_normal_vision")]
+    pub vision_deficiency: VisionDeficiency,
+    /// Send `Save-Data: on` and report `prefers-reduced-data: reduce`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub save_data: bool,
+}
+
+fn is_normal_vision(deficiency: &VisionDeficiency) -> bool {
+    *deficiency == VisionDeficiency::None
+}
+
+impl EmulationSettings {
+    /// Whether nothing is emulated
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Parameters of `Emulation.setEmulatedMedia`
+    pub fn media_params(&self) -> Value {
+        let color_scheme = self.color_scheme.map(|s| s.to_string()).unwrap_or_default();
+        let reduced_data = if self.save_data { "reduce" } else { "" };
+        json!({
+            "features": [
+                { "name": "prefers-color-scheme", "value": color_scheme },
+                { "name": "prefers-reduced-data", "value": reduced_data },
+            ]
+        })
+    }
+
+    /// Request headers the settings add, e.g. `Save-Data: on`
+    pub fn headers(&self) -> HashMap<String, String> {
+        if self.save_data {
+            HashMap::from([("Save-Data".to_string(), "on".to_string())])
+        } else {
+            HashMap::new()
+        }
+    }
+
+    /// Parameters of `Network.setExtraHTTPHeaders`
+    pub fn header_params(&self) -> Value {
+        json!({ "headers": self.headers() })
+    }
+
+    /// One line such as `dark color scheme, deuteranopia, Save-Data`
+    pub fn summary(&self) -> String {
+        let mut parts = vec![];
+        if let Some(scheme) = self.color_scheme {
+            parts.push(format!("{scheme} color scheme"));
+        }
+        if self.vision_deficiency != VisionDeficiency::None {
+            parts.push(self.vision_deficiency.to_string());
+        }
+        if self.save_data {
+            parts.push("Save-Data".to_string());
+        }
+        if parts.is_empty() {
+            "no emulation".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vision_deficiency_names() {
+        assert_eq!(
+            VisionDeficiency::from_name("Blurred_Vision"),
+            Some(VisionDeficiency::BlurredVision)
+        );
+        assert_eq!(VisionDeficiency::BlurredVision.cdp_name(), "blurredVision");
+        assert_eq!(VisionDeficiency::from_name("sepia"), None);
+    }
+
+    #[test]
+    fn test_media_params_clear_unset_features() {
+        let settings = EmulationSettings {
+            color_scheme: Some(ColorScheme::Dark),
+            ..Default::default()
+        };
+        assert_eq!(
+            settings.media_params()["features"],
+            json!([
+                { "name": "prefers-color-scheme", "value": "dark" },
+                { "name": "prefers-reduced-data", "value": "" },
+            ])
+        );
+        assert_eq!(settings.header_params(), json!({ "headers": {} }));
+        assert_eq!(settings.summary(), "dark color scheme");
+    }
+}
@@ -0,0 +1,93 @@
+//! Cheap page fingerprints for telling whether an action had any effect
+//!
+//! A click that lands on an invisible overlay succeeds as far as CDP is
+//! concerned but changes nothing. Comparing a [`PageFingerprint`] from before
+//! and after the click detects this without serializing the DOM.
+
+use serde::Deserialize;
+
+/// Reads the fingerprint of the top document
+///
+/// The first call installs a `MutationObserver`, so the mutation count of
+/// later fingerprints covers everything that changed in between. The focused
+/// element is identified by a number stored on it, not by its description.
+pub(crate) const PAGE_FINGERPRINT_JS: &str = r#"
+(() => {
+    if (!window.__browsingMutations) {
+        const counter = { count: 0 };
+        new MutationObserver(records => { counter.count += records.length; }).observe(document, {
+            subtree: true, childList: true, attributes: true, characterData: true,
+        });
+        window.__browsingMutations = counter;
+    }
+    const active = document.activeElement;
+    let focused = null;
+    if (active && active !== document.body) {
+        if (!active.__browsingFocusId) {
+            window.__browsingFocusSeq = (window.__browsingFocusSeq || 0) + 1;
+            active.__browsingFocusId = window.__browsingFocusSeq;
+        }
+        focused = active.__browsingFocusId;
+    }
+    return {
+        url: location.href,
+        focused,
+        bodyChildren: document.body ? document.body.childElementCount : 0,
+        scrollX: window.scrollX,
+        scrollY: window.scrollY,
+        mutations: window.__browsingMutations.count,
+    };
+})()
+"#;
+
+/// What an action could visibly change on a page
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageFingerprint {
+    /// Document URL
+    pub url: String,
+    /// Identifies the focused element; `None` if nothing or `<body>` has focus
+    pub focused: Option<u64>,
+    /// Number of element children of `<body>`
+    pub body_children: u64,
+    /// Horizontal scroll position
+    pub scroll_x: f64,
+    /// Vertical scroll position
+    pub scroll_y: f64,
+    /// DOM mutations observed since the first fingerprint of the document
+    pub mutations: u64,
+}
+
+impl PageFingerprint {
+    /// Whether anything differs from `before`
+    pub fn changed_since(&self, before: &PageFingerprint) -> bool {
+        self != before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_changed_since() {
+        let before: PageFingerprint = serde_json::from_value(json!({
+            "url": "https://example.com/", "focused": null, "bodyChildren": 4,
+            "scrollX": 0, "scrollY": 120, "mutations": 0
+        }))
+        .unwrap();
+        assert!(!before.clone().changed_since(&before));
+
+        let focused = PageFingerprint {
+            focused: Some(1),
+            ..before.clone()
+        };
+        assert!(focused.changed_since(&before));
+        let mutated = PageFingerprint {
+            mutations: 3,
+            ..before.clone()
+        };
+        assert!(mutated.changed_since(&before));
+    }
+}
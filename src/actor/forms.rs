@@ -0,0 +1,254 @@
+//! Form discovery types
+//!
+//! [`Page::get_all_forms`](crate::actor::Page::get_all_forms) enumerates every
+//! form on the page together with its fields in one round trip, so agents don't
+//! have to discover form fields element by element.
+
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Expression evaluating to every form control of every form, in document order
+pub(crate) const FORM_ELEMENTS_JS: &str =
+    "Array.from(document.forms).flatMap(f => Array.from(f.elements))";
+
+/// Function called on the [`FORM_ELEMENTS_JS`] array to describe each form
+///
+/// Field `index` refers to the position of the control in that array, which is
+/// later mapped to a backend node ID.
+pub(crate) const DESCRIBE_FORMS_JS: &str = r#"function() {
+    const controls = this;
+    const formProp = (form, name) =>
+        Object.getOwnPropertyDescriptor(HTMLFormElement.prototype, name).get.call(form);
+    const labelOf = (el) => {
+        if (el.labels && el.labels.length > 0) {
+            return el.labels[0].innerText.trim() || null;
+        }
+        return el.getAttribute('aria-label') || null;
+    };
+    return Array.from(document.forms).map(form => ({
+        form_id: form.getAttribute('id') || form.getAttribute('name') || null,
+        action: formProp(form, 'action') || null,
+        method: formProp(form, 'method'),
+        enctype: formProp(form, 'enctype'),
+        fields: Array.from(form.elements)
+            .filter(el => !['FIELDSET', 'OBJECT', 'OUTPUT'].includes(el.tagName))
+            .map(el => {
+                const type = (el.type || el.tagName).toLowerCase();
+                const checkable = type === 'checkbox' || type === 'radio';
+                return {
+                    index: controls.indexOf(el),
+                    name: el.getAttribute('name') || null,
+                    type: type,
+                    label: labelOf(el),
+                    placeholder: el.getAttribute('placeholder') || null,
+                    required: !!el.required,
+                    value: checkable ? (el.checked ? el.value : null) : (el.value || null),
+                    options: el.tagName === 'SELECT'
+                        ? Array.from(el.options).map(o => o.text.trim())
+                        : null,
+                };
+            }),
+    }));
+}"#;
+
+/// Function called on a form control to set its value like a user would
+///
+/// A radio stands for its whole group: the radio with the same name whose
+/// value or label is `value` is checked, or this one for a yes-like value.
+pub(crate) const FILL_FIELD_JS: &str = r#"function(value) {
+    const type = (this.type || '').toLowerCase();
+    const yes = ['true', 'on', 'yes', '1'].includes(value);
+    if (type === 'radio') {
+        const group = this.form && this.name
+            ? Array.from(this.form.elements).filter(el => el.type === 'radio' && el.name === this.name)
+            : [this];
+        const labelOf = (el) => el.labels && el.labels.length > 0 ? el.labels[0].innerText.trim() : null;
+        const radio = group.find(el => el.value === value)
+            || group.find(el => labelOf(el) === value)
+            || (yes ? this : null);
+        if (!radio) {
+            return false;
+        }
+        radio.checked = true;
+        radio.dispatchEvent(new Event('input', { bubbles: true }));
+        radio.dispatchEvent(new Event('change', { bubbles: true }));
+        return true;
+    } else if (type === 'checkbox') {
+        this.checked = yes || value === this.value;
+    } else if (this.tagName === 'SELECT') {
+        const option = Array.from(this.options)
+            .find(o => o.text.trim() === value || o.value === value);
+        if (!option) {
+            return false;
+        }
+        this.value = option.value;
+    } else {
+        this.focus();
+        this.value = value;
+    }
+    this.dispatchEvent(new Event('input', { bubbles: true }));
+    this.dispatchEvent(new Event('change', { bubbles: true }));
+    return true;
+}"#;
+
+/// A form on the page and its fields
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormInfo {
+    /// `id` or `name` attribute of the form
+    pub form_id: Option<String>,
+    /// Resolved submission URL
+    pub action: Option<String>,
+    /// Submission method (`get`, `post` or `dialog`)
+    pub method: String,
+    /// Encoding type used on submission
+    pub enctype: String,
+    /// Controls belonging to the form
+    pub fields: Vec<FormField>,
+}
+
+/// A single form control
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormField {
+    /// `name` attribute
+    pub name: Option<String>,
+    /// Control type (e.g. "text", "email", "select-one", "checkbox")
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// Text of the associated label, or `aria-label`
+    pub label: Option<String>,
+    /// Placeholder text
+    pub placeholder: Option<String>,
+    /// Whether the field is required
+    pub required: bool,
+    /// Current value (for checkboxes and radios, only when checked)
+    pub value: Option<String>,
+    /// Option texts for select elements
+    pub options: Option<Vec<String>>,
+    /// Backend node ID for interacting with the field
+    pub backend_node_id: Option<u32>,
+}
+
+impl FormInfo {
+    /// Find a field by name, label, or placeholder (case-insensitive)
+    pub fn find_field(&self, key: &str) -> Option<&FormField> {
+        let key = key.trim().to_lowercase();
+        let matches =
+            |v: &Option<String>| v.as_ref().is_some_and(|v| v.trim().to_lowercase() == key);
+        self.fields
+            .iter()
+            .find(|f| matches(&f.name))
+            .or_else(|| self.fields.iter().find(|f| matches(&f.label)))
+            .or_else(|| self.fields.iter().find(|f| matches(&f.placeholder)))
+    }
+}
+
+#[derive(Deserialize)]
+struct RawField {
+    index: i64,
+    #[serde(flatten)]
+    field: FormField,
+}
+
+#[derive(Deserialize)]
+struct RawForm {
+    form_id: Option<String>,
+    action: Option<String>,
+    method: String,
+    enctype: String,
+    fields: Vec<RawField>,
+}
+
+/// Build [`FormInfo`]s from [`DESCRIBE_FORMS_JS`] output and the backend node
+/// IDs of the form controls, keyed by their index
+pub(crate) fn assemble_forms(
+    raw: serde_json::Value,
+    backend_node_ids: &HashMap<usize, u32>,
+) -> Result<Vec<FormInfo>> {
+    let forms: Vec<RawForm> = serde_json::from_value(raw)?;
+    Ok(forms
+        .into_iter()
+        .map(|form| FormInfo {
+            form_id: form.form_id,
+            action: form.action,
+            method: form.method,
+            enctype: form.enctype,
+            fields: form
+                .fields
+                .into_iter()
+                .map(|raw| FormField {
+                    backend_node_id: usize::try_from(raw.index)
+                        .ok()
+                        .and_then(|i| backend_node_ids.get(&i).copied()),
+                    ..raw.field
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn raw_forms() -> serde_json::Value {
+        json!([{
+            "form_id": "login",
+            "action": "https://example.com/session",
+            "method": "post",
+            "enctype": "application/x-www-form-urlencoded",
+            "fields": [
+                {"index": 0, "name": "email", "type": "email", "label": "Email address",
+                 "placeholder": null, "required": true, "value": null, "options": null},
+                {"index": 1, "name": null, "type": "password", "label": null,
+                 "placeholder": "Password", "required": true, "value": null, "options": null},
+                {"index": 2, "name": "country", "type": "select-one", "label": "Country",
+                 "placeholder": null, "required": false, "value": "us",
+                 "options": ["United States", "Canada"]}
+            ]
+        }])
+    }
+
+    #[test]
+    fn test_assemble_forms_maps_backend_node_ids() {
+        let ids = HashMap::from([(0, 101), (2, 103)]);
+        let forms = assemble_forms(raw_forms(), &ids).unwrap();
+
+        assert_eq!(forms.len(), 1);
+        let form = &forms[0];
+        assert_eq!(form.form_id.as_deref(), Some("login"));
+        assert_eq!(form.method, "post");
+        assert_eq!(form.fields.len(), 3);
+        assert_eq!(form.fields[0].backend_node_id, Some(101));
+        assert_eq!(form.fields[1].backend_node_id, None);
+        assert_eq!(form.fields[2].type_, "select-one");
+        assert_eq!(
+            form.fields[2].options.as_deref(),
+            Some(&["United States".to_string(), "Canada".to_string()][..])
+        );
+    }
+
+    #[test]
+    fn test_find_field_by_name_label_or_placeholder() {
+        let forms = assemble_forms(raw_forms(), &HashMap::new()).unwrap();
+        let form = &forms[0];
+
+        assert_eq!(form.find_field("email").unwrap().type_, "email");
+        assert_eq!(form.find_field("email ADDRESS").unwrap().type_, "email");
+        assert_eq!(form.find_field("password").unwrap().type_, "password");
+        assert_eq!(
+            form.find_field("Country").unwrap().name.as_deref(),
+            Some("country")
+        );
+        assert!(form.find_field("phone").is_none());
+    }
+
+    #[test]
+    fn test_form_field_serializes_type() {
+        let forms = assemble_forms(raw_forms(), &HashMap::new()).unwrap();
+        let value = serde_json::to_value(&forms[0].fields[0]).unwrap();
+        assert_eq!(value["type"], "email");
+        assert!(value.get("index").is_none());
+    }
+}
@@ -0,0 +1,272 @@
+//! Image listing and downloading
+//!
+//! Images are listed with their rendered and natural sizes so callers can tell
+//! content images from tracking pixels and icons. Images with a `data:` URI
+//! source are usually placeholders or inlined icons and are skipped unless
+//! asked for.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use url::Url;
+
+/// Longest `data:` URI kept in an [`ImageInfo`]; longer ones are truncated
+pub const MAX_DATA_URI_CHARS: usize = 64;
+
+/// Images downloaded when no limit is given
+pub const DEFAULT_DOWNLOAD_LIMIT: usize = 5;
+
+/// Longest wait for one image download, connecting included
+pub const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The page's URL and user agent, which image downloads are sent with
+pub(crate) const REQUEST_IDENTITY_JS: &str =
+    "({ url: location.href, userAgent: navigator.userAgent })";
+
+/// Describes every `img[src]` element, in document order
+///
+/// The index matches `document.querySelectorAll('img[src]')`, so it stays
+/// stable when the list is filtered.
+pub(crate) const LIST_IMAGES_JS: &str = r#"
+(() => Array.from(document.querySelectorAll('img[src]')).map((img, index) => {
+    const rect = img.getBoundingClientRect();
+    const figure = img.closest('figure');
+    const figcaption = figure ? figure.querySelector('figcaption') : null;
+    const caption = figcaption ? figcaption.textContent.trim().replace(/\s+/g, ' ') : '';
+    return {
+        index,
+        src: img.currentSrc || img.src,
+        alt: img.alt || '',
+        caption: (caption || img.alt || '').slice(0, 200) || null,
+        width: Math.round(rect.width),
+        height: Math.round(rect.height),
+        natural_width: img.naturalWidth,
+        natural_height: img.naturalHeight,
+        loading: img.getAttribute('loading'),
+    };
+}))()
+"#;
+
+/// An image on the page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageInfo {
+    /// Position among the page's `img[src]` elements
+    pub index: usize,
+    /// Source URL; `data:` URIs are truncated
+    pub src: String,
+    /// `alt` text
+    pub alt: String,
+    /// Text of the enclosing figure's `<figcaption>`, or the alt text
+    pub caption: Option<String>,
+    /// Rendered width in CSS pixels
+    pub width: u32,
+    /// Rendered height in CSS pixels
+    pub height: u32,
+    /// Intrinsic width; 0 until the image has loaded
+    pub natural_width: u32,
+    /// Intrinsic height; 0 until the image has loaded
+    pub natural_height: u32,
+    /// `loading` attribute, e.g. `lazy`
+    pub loading: Option<String>,
+    /// Whether the source is a `data:` URI
+    #[serde(default)]
+    pub is_data_uri: bool,
+    /// Where the image was saved, if it was downloaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub saved_path: Option<PathBuf>,
+}
+
+impl ImageInfo {
+    /// Larger of the rendered and natural width
+    ///
+    /// Lazy images that have not loaded have no natural size, and hidden
+    /// images have no rendered size, so either is enough to count.
+    pub fn effective_width(&self) -> u32 {
+        self.width.max(self.natural_width)
+    }
+
+    /// Larger of the rendered and natural height
+    pub fn effective_height(&self) -> u32 {
+        self.height.max(self.natural_height)
+    }
+
+    /// Rendered area, used to pick the most prominent images
+    pub fn area(&self) -> u64 {
+        u64::from(self.width) * u64::from(self.height)
+    }
+
+    /// File extension for a download, from the URL path or `png`
+    pub(crate) fn file_extension(&self) -> &'static str {
+        let path = self
+            .src
+            .split(['?', '#'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        ["jpg", "jpeg", "gif", "webp", "svg", "avif", "png"]
+            .into_iter()
+            .find(|ext| path.ends_with(&format!(".{ext}")))
+            .unwrap_or("png")
+    }
+}
+
+/// Which images [`Page::list_images`](crate::actor::Page::list_images) returns
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageListOptions {
+    /// Minimum width in pixels, rendered or natural
+    pub min_width: u32,
+    /// Minimum height in pixels, rendered or natural
+    pub min_height: u32,
+    /// Whether to include images whose source is a `data:` URI
+    pub include_data_uris: bool,
+}
+
+impl Default for ImageListOptions {
+    /// Skips 1x1 tracking pixels and `data:` URIs
+    fn default() -> Self {
+        Self {
+            min_width: 2,
+            min_height: 2,
+            include_data_uris: false,
+        }
+    }
+}
+
+impl ImageListOptions {
+    /// Whether `image` passes the filters
+    pub fn matches(&self, image: &ImageInfo) -> bool {
+        (self.include_data_uris || !image.is_data_uri)
+            && image.effective_width() >= self.min_width
+            && image.effective_height() >= self.min_height
+    }
+}
+
+/// Mark `data:` URIs and shorten them, since they can be megabytes long
+pub(crate) fn normalize(mut image: ImageInfo) -> ImageInfo {
+    image.is_data_uri = image.src.starts_with("data:");
+    if image.is_data_uri && image.src.chars().count() > MAX_DATA_URI_CHARS {
+        image.src = image
+            .src
+            .chars()
+            .take(MAX_DATA_URI_CHARS)
+            .collect::<String>()
+            + "…";
+    }
+    image
+}
+
+/// Indices into `images` of the `limit` largest downloadable images, largest first
+pub fn download_candidates(images: &[ImageInfo], limit: usize) -> Vec<usize> {
+    let mut candidates: Vec<usize> = (0..images.len())
+        .filter(|&i| images[i].src.starts_with("http://") || images[i].src.starts_with("https://"))
+        .collect();
+    candidates.sort_by(|&a, &b| images[b].area().cmp(&images[a].area()).then(a.cmp(&b)));
+    candidates.truncate(limit);
+    candidates
+}
+
+/// `Referer` the browser would send for `image` from `page`
+///
+/// Follows Chrome's default `strict-origin-when-cross-origin` policy: the
+/// full URL without its fragment for the same origin, only the origin for
+/// another one, and nothing when going from HTTPS to HTTP.
+pub fn referer(page: &Url, image: &Url) -> Option<String> {
+    if !matches!(page.scheme(), "http" | "https")
+        || (page.scheme() == "https" && image.scheme() == "http")
+    {
+        return None;
+    }
+    if page.origin() == image.origin() {
+        let mut page = page.clone();
+        page.set_fragment(None);
+        let _ = page.set_username("");
+        let _ = page.set_password(None);
+        Some(page.to_string())
+    } else {
+        Some(format!("{}/", page.origin().ascii_serialization()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(index: usize, src: &str, size: (u32, u32), natural: (u32, u32)) -> ImageInfo {
+        normalize(ImageInfo {
+            index,
+            src: src.to_string(),
+            alt: String::new(),
+            caption: None,
+            width: size.0,
+            height: size.1,
+            natural_width: natural.0,
+            natural_height: natural.1,
+            loading: None,
+            is_data_uri: false,
+            saved_path: None,
+        })
+    }
+
+    #[test]
+    fn test_default_filters() {
+        let options = ImageListOptions::default();
+        let pixel = image(0, "https://t.example/p.gif", (1, 1), (1, 1));
+        let hero = image(1, "https://shop.example/hero.jpg", (800, 400), (1600, 800));
+        let placeholder = image(
+            2,
+            &format!("data:image/png;base64,{}", "A".repeat(500)),
+            (300, 200),
+            (1, 1),
+        );
+        // Lazy, not loaded yet, but laid out
+        let lazy = image(3, "https://shop.example/lazy.jpg", (300, 200), (0, 0));
+
+        assert!(!options.matches(&pixel));
+        assert!(options.matches(&hero));
+        assert!(!options.matches(&placeholder));
+        assert!(options.matches(&lazy));
+        assert!(placeholder.is_data_uri);
+        assert_eq!(placeholder.src.chars().count(), MAX_DATA_URI_CHARS + 1);
+
+        let large = ImageListOptions {
+            min_width: 500,
+            ..Default::default()
+        };
+        assert!(large.matches(&hero));
+        assert!(!large.matches(&lazy));
+    }
+
+    #[test]
+    fn test_download_candidates() {
+        let images = vec![
+            image(0, "https://a.example/small.png", (50, 50), (50, 50)),
+            image(1, "data:image/png;base64,AAAA", (900, 900), (900, 900)),
+            image(2, "https://a.example/big.webp?v=2", (600, 400), (600, 400)),
+            image(3, "https://a.example/mid.jpg", (200, 200), (200, 200)),
+        ];
+
+        assert_eq!(download_candidates(&images, 2), vec![2, 3]);
+        assert_eq!(images[2].file_extension(), "webp");
+        assert_eq!(images[0].file_extension(), "png");
+    }
+
+    #[test]
+    fn test_referer() {
+        let url = |s: &str| Url::parse(s).unwrap();
+        let page = url("https://shop.example/kettles?page=2#reviews");
+
+        assert_eq!(
+            referer(&page, &url("https://shop.example/hero.png")).as_deref(),
+            Some("https://shop.example/kettles?page=2")
+        );
+        assert_eq!(
+            referer(&page, &url("https://cdn.example/hero.png")).as_deref(),
+            Some("https://shop.example/")
+        );
+        assert_eq!(referer(&page, &url("http://shop.example/hero.png")), None);
+        assert_eq!(
+            referer(&url("about:blank"), &url("https://cdn.example/a.png")),
+            None
+        );
+    }
+}
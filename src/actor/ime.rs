@@ -0,0 +1,152 @@
+//! Text input through IME composition
+//!
+//! Japanese, Chinese or Korean text is typed through an input method editor:
+//! the page sees `compositionstart`, `compositionupdate` and `compositionend`
+//! around the inserted text, and there are no key events for the characters
+//! themselves. Frameworks that track composition (React, Vue, many rich text
+//! editors) hold back their own updates until `compositionend`, so text set
+//! without those events can be lost or duplicated.
+//! [`Element::input_with_ime`](crate::actor::Element::input_with_ime) inserts
+//! text one grapheme cluster at a time with `Input.insertText`, wrapped in the
+//! composition events an IME would dispatch.
+
+/// Clears the element and focuses it, through the native `value` setter so
+/// controlled inputs see the change
+pub(crate) const CLEAR_FOR_INPUT_JS: &str = r#"function() {
+    this.focus();
+    if (this.isContentEditable) {
+        this.textContent = '';
+    } else {
+        const setter = Object.getOwnPropertyDescriptor(Object.getPrototypeOf(this), 'value')?.set;
+        if (setter) setter.call(this, ''); else this.value = '';
+    }
+    this.dispatchEvent(new Event('input', { bubbles: true }));
+}"#;
+
+/// Dispatches a composition event of the type and data given as arguments
+pub(crate) const COMPOSITION_EVENT_JS: &str = r#"function(type, data) {
+    this.dispatchEvent(new CompositionEvent(type, { bubbles: true, cancelable: true, data }));
+}"#;
+
+/// Dispatches `change` once all text is in
+pub(crate) const DISPATCH_CHANGE_JS: &str =
+    "function() { this.dispatchEvent(new Event('change', { bubbles: true })); }";
+
+/// Whether `text` has characters of a script typed through an IME
+///
+/// Only Chinese, Japanese and Korean text is composed; accented letters and
+/// emoji are filled directly like any other text.
+pub fn needs_ime(text: &str) -> bool {
+    text.chars().any(is_composed)
+}
+
+/// Whether `c` belongs to a CJK or Hangul block
+fn is_composed(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1100..=0x11ff // Hangul Jamo
+            | 0x2e80..=0x2fdf // CJK and Kangxi radicals
+            | 0x3000..=0x31ff // CJK punctuation, kana, bopomofo, Hangul compatibility jamo
+            | 0x3400..=0x4dbf // CJK extension A
+            | 0x4e00..=0x9fff // CJK unified ideographs
+            | 0xa960..=0xa97f // Hangul Jamo extended A
+            | 0xac00..=0xd7ff // Hangul syllables and Jamo extended B
+            | 0xf900..=0xfaff // CJK compatibility ideographs
+            | 0xff00..=0xffef // Half- and full-width forms
+            | 0x20000..=0x3ffff // CJK extensions B onwards
+    )
+}
+
+/// Split `text` into user-perceived characters
+///
+/// Approximates extended grapheme clusters without the Unicode tables:
+/// combining marks, variation selectors, emoji skin tones and tags stay with
+/// the character before them, characters joined by a zero-width joiner stay
+/// together, and regional indicators pair up into flags.
+pub fn grapheme_clusters(text: &str) -> Vec<&str> {
+    let mut clusters = Vec::new();
+    let mut start = 0;
+    let mut previous: Option<char> = None;
+    let mut regional_indicators = 0;
+    for (at, c) in text.char_indices() {
+        let joins = match previous {
+            None => false,
+            Some('\u{200d}') => true,
+            Some(_) if is_extending(c) => true,
+            Some(p) if is_regional_indicator(p) && is_regional_indicator(c) => {
+                regional_indicators % 2 == 1
+            }
+            Some('\r') => c == '\n',
+            Some(_) => false,
+        };
+        if !joins && at > start {
+            clusters.push(&text[start..at]);
+            start = at;
+        }
+        regional_indicators = if is_regional_indicator(c) {
+            if joins { regional_indicators + 1 } else { 1 }
+        } else {
+            0
+        };
+        previous = Some(c);
+    }
+    if start < text.len() {
+        clusters.push(&text[start..]);
+    }
+    clusters
+}
+
+/// Characters that extend the cluster before them
+fn is_extending(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x0300..=0x036f // combining diacritical marks
+            | 0x1ab0..=0x1aff
+            | 0x1dc0..=0x1dff
+            | 0x20d0..=0x20ff // combining marks for symbols, including keycaps
+            | 0x3099..=0x309a // combining kana voiced sound marks
+            | 0xfe00..=0xfe0f // variation selectors
+            | 0xfe20..=0xfe2f
+            | 0x200d // zero-width joiner
+            | 0x1f3fb..=0x1f3ff // emoji skin tones
+            | 0xe0020..=0xe007f // tags
+            | 0xe0100..=0xe01ef
+    )
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c as u32, 0x1f1e6..=0x1f1ff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_needs_ime() {
+        assert!(!needs_ime("laptop 15\" <cheap>"));
+        assert!(needs_ime("東京タワー"));
+        assert!(needs_ime("서울 맛집"));
+        assert!(!needs_ime("café"));
+        assert!(!needs_ime("👍"));
+    }
+
+    #[test]
+    fn test_grapheme_clusters() {
+        assert_eq!(grapheme_clusters("東京"), ["東", "京"]);
+        assert_eq!(grapheme_clusters("e\u{301}a"), ["e\u{301}", "a"]);
+        assert_eq!(grapheme_clusters("か\u{3099}"), ["か\u{3099}"]);
+        assert_eq!(grapheme_clusters("👍🏽!"), ["👍🏽", "!"]);
+        assert_eq!(
+            grapheme_clusters("👨\u{200d}👩\u{200d}👧x"),
+            ["👨\u{200d}👩\u{200d}👧", "x"]
+        );
+        assert_eq!(grapheme_clusters("🇯🇵🇫🇷"), ["🇯🇵", "🇫🇷"]);
+        assert_eq!(
+            grapheme_clusters("1\u{fe0f}\u{20e3}"),
+            ["1\u{fe0f}\u{20e3}"]
+        );
+        assert_eq!(grapheme_clusters("a\r\nb"), ["a", "\r\n", "b"]);
+        assert!(grapheme_clusters("").is_empty());
+    }
+}
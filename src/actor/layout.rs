@@ -0,0 +1,250 @@
+//! Typed `Page.getLayoutMetrics` results
+//!
+//! CDP reports each viewport twice: in device pixels (deprecated) and in CSS
+//! pixels (the `css*` fields). [`LayoutMetrics`] uses CSS pixels when the
+//! browser provides them, which is what scroll deltas and `window.scrollY`
+//! are measured in.
+
+use crate::browser::cdp::CdpClient;
+use crate::error::{BrowsingError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// A rectangle in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Rect {
+    /// Left edge
+    pub x: f64,
+    /// Top edge
+    pub y: f64,
+    /// Width
+    pub width: f64,
+    /// Height
+    pub height: f64,
+}
+
+/// A size in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Size {
+    /// Width
+    pub width: f64,
+    /// Height
+    pub height: f64,
+}
+
+/// The part of the page the user sees, which pinch-zoom can shrink
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VisualViewport {
+    /// Horizontal offset relative to the layout viewport
+    pub offset_x: f64,
+    /// Vertical offset relative to the layout viewport
+    pub offset_y: f64,
+    /// Horizontal offset relative to the document
+    pub page_x: f64,
+    /// Vertical offset relative to the document
+    pub page_y: f64,
+    /// Width, excluding scrollbars
+    pub client_width: f64,
+    /// Height, excluding scrollbars
+    pub client_height: f64,
+    /// Pinch-zoom scale
+    pub scale: f64,
+    /// Page zoom factor (browser zoom), if reported
+    #[serde(default)]
+    pub zoom: Option<f64>,
+}
+
+/// Viewport and content geometry of a page
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LayoutMetrics {
+    /// Scroll position (`x`, `y`) and size of the layout viewport
+    pub layout_viewport: Rect,
+    /// Visual viewport
+    pub visual_viewport: VisualViewport,
+    /// Size of the scrollable content, in device pixels
+    pub content_size: Size,
+    /// Size of the scrollable content, in CSS pixels
+    pub css_content_size: Size,
+    /// Device pixels per CSS pixel
+    pub device_pixel_ratio: f64,
+    /// Pinch-zoom scale of the page
+    pub page_scale_factor: f64,
+}
+
+impl LayoutMetrics {
+    /// Parse a `Page.getLayoutMetrics` result
+    pub fn from_cdp(result: &Value) -> Result<Self> {
+        let field = |css: &str, device: &str| {
+            result
+                .get(css)
+                .or_else(|| result.get(device))
+                .ok_or_else(|| BrowsingError::Cdp(format!("No {device} in layout metrics")))
+        };
+        let number = |value: &Value, name: &str| value.get(name).and_then(Value::as_f64);
+
+        let layout = field("cssLayoutViewport", "layoutViewport")?;
+        let layout_viewport = Rect {
+            x: number(layout, "pageX").unwrap_or(0.0),
+            y: number(layout, "pageY").unwrap_or(0.0),
+            width: number(layout, "clientWidth").unwrap_or(0.0),
+            height: number(layout, "clientHeight").unwrap_or(0.0),
+        };
+        let visual_viewport: VisualViewport =
+            serde_json::from_value(field("cssVisualViewport", "visualViewport")?.clone())?;
+        let size = |value: Option<&Value>| Size {
+            width: value.and_then(|v| number(v, "width")).unwrap_or(0.0),
+            height: value.and_then(|v| number(v, "height")).unwrap_or(0.0),
+        };
+        let content_size = size(result.get("contentSize"));
+        let css_content_size = size(result.get("cssContentSize").or(result.get("contentSize")));
+
+        // Same ratio as the viewport widths in device and CSS pixels
+        let device_width = result
+            .get("visualViewport")
+            .and_then(|v| number(v, "clientWidth"));
+        let device_pixel_ratio = match device_width {
+            Some(width) if visual_viewport.client_width > 0.0 => {
+                width / visual_viewport.client_width
+            }
+            _ => 1.0,
+        };
+
+        Ok(Self {
+            layout_viewport,
+            page_scale_factor: visual_viewport.scale,
+            visual_viewport,
+            content_size,
+            css_content_size,
+            device_pixel_ratio,
+        })
+    }
+
+    /// Height of the layout viewport, i.e. one page of scrolling
+    pub fn viewport_height(&self) -> f64 {
+        self.layout_viewport.height
+    }
+}
+
+/// Viewport assumed when a page cannot report its own, as for scrolling
+/// "one page"
+pub const FALLBACK_VIEWPORT_SIZE: Size = Size {
+    width: 1280.0,
+    height: 1000.0,
+};
+
+/// Read the layout metrics of the page behind `session_id`
+///
+/// Also remembers the layout viewport's size for [`get_viewport_size`].
+pub(crate) async fn get_layout_metrics(
+    client: &CdpClient,
+    session_id: &str,
+) -> Result<LayoutMetrics> {
+    let result = client
+        .send_command_with_session("Page.getLayoutMetrics", json!({}), Some(session_id))
+        .await?;
+    let metrics = LayoutMetrics::from_cdp(&result)?;
+    let viewport = metrics.layout_viewport;
+    client.cache_viewport_size(session_id, (viewport.width, viewport.height));
+    Ok(metrics)
+}
+
+/// Size of the layout viewport of the page behind `session_id`, in CSS pixels
+///
+/// Read with `Page.getLayoutMetrics` the first time, then from the client's
+/// cache until the viewport resizes.
+pub(crate) async fn get_viewport_size(client: &CdpClient, session_id: &str) -> Result<Size> {
+    if let Some((width, height)) = client.cached_viewport_size(session_id) {
+        return Ok(Size { width, height });
+    }
+    let viewport = get_layout_metrics(client, session_id)
+        .await?
+        .layout_viewport;
+    Ok(Size {
+        width: viewport.width,
+        height: viewport.height,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Chrome at a device pixel ratio of 2, scrolled down 1200px
+    fn chrome_metrics() -> Value {
+        json!({
+            "layoutViewport": { "pageX": 0, "pageY": 2400, "clientWidth": 2560, "clientHeight": 1440 },
+            "visualViewport": {
+                "offsetX": 0, "offsetY": 0, "pageX": 0, "pageY": 2400,
+                "clientWidth": 2560, "clientHeight": 1440, "scale": 1, "zoom": 1
+            },
+            "contentSize": { "x": 0, "y": 0, "width": 2560, "height": 9000 },
+            "cssLayoutViewport": { "pageX": 0, "pageY": 1200, "clientWidth": 1280, "clientHeight": 720 },
+            "cssVisualViewport": {
+                "offsetX": 0, "offsetY": 0, "pageX": 0, "pageY": 1200,
+                "clientWidth": 1280, "clientHeight": 720, "scale": 1, "zoom": 1
+            },
+            "cssContentSize": { "x": 0, "y": 0, "width": 1280, "height": 4500 }
+        })
+    }
+
+    #[test]
+    fn test_prefers_css_pixels() {
+        let metrics = LayoutMetrics::from_cdp(&chrome_metrics()).unwrap();
+
+        assert_eq!(
+            metrics.layout_viewport,
+            Rect {
+                x: 0.0,
+                y: 1200.0,
+                width: 1280.0,
+                height: 720.0
+            }
+        );
+        assert_eq!(metrics.viewport_height(), 720.0);
+        assert_eq!(metrics.visual_viewport.page_y, 1200.0);
+        assert_eq!(metrics.visual_viewport.zoom, Some(1.0));
+        assert_eq!(
+            metrics.content_size,
+            Size {
+                width: 2560.0,
+                height: 9000.0
+            }
+        );
+        assert_eq!(
+            metrics.css_content_size,
+            Size {
+                width: 1280.0,
+                height: 4500.0
+            }
+        );
+        assert_eq!(metrics.device_pixel_ratio, 2.0);
+        assert_eq!(metrics.page_scale_factor, 1.0);
+    }
+
+    #[test]
+    fn test_older_browsers_without_css_metrics() {
+        let mut result = chrome_metrics();
+        for key in ["cssLayoutViewport", "cssVisualViewport", "cssContentSize"] {
+            result.as_object_mut().unwrap().remove(key);
+        }
+        result["visualViewport"]["scale"] = json!(1.5);
+        result["visualViewport"]
+            .as_object_mut()
+            .unwrap()
+            .remove("zoom");
+
+        let metrics = LayoutMetrics::from_cdp(&result).unwrap();
+
+        assert_eq!(metrics.viewport_height(), 1440.0);
+        assert_eq!(metrics.css_content_size, metrics.content_size);
+        assert_eq!(metrics.device_pixel_ratio, 1.0);
+        assert_eq!(metrics.page_scale_factor, 1.5);
+        assert_eq!(metrics.visual_viewport.zoom, None);
+    }
+
+    #[test]
+    fn test_missing_viewport_is_an_error() {
+        assert!(LayoutMetrics::from_cdp(&json!({})).is_err());
+    }
+}
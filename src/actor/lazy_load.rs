@@ -0,0 +1,273 @@
+//! Triggering lazy-loaded content before reading a page
+//!
+//! Images and sections loaded through `IntersectionObserver` or scroll
+//! handlers only appear once they come near the viewport, so text or a
+//! full-page screenshot taken right after navigation can be nearly empty.
+//! [`Page::prepare_page_for_extraction`](crate::actor::Page::prepare_page_for_extraction)
+//! scrolls through the page and back, fires a `resize` event and waits for
+//! the requests this started to finish.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Default limit on the whole preparation, scrolling and waiting included
+pub const DEFAULT_LAZY_LOAD_MAX_DURATION_MS: u64 = 10_000;
+
+/// Default time without requests in flight that counts as network-quiet
+pub const DEFAULT_NETWORK_QUIET_MS: u64 = 500;
+
+/// Time given to each smooth scroll step to finish and load what it revealed
+pub(crate) const SCROLL_STEP_MS: u64 = 150;
+
+/// Scroll position and size of the top document
+pub(crate) const SCROLL_METRICS_JS: &str = r#"
+(() => {
+    const root = document.scrollingElement || document.documentElement;
+    return {
+        scrollX: window.scrollX,
+        scrollY: window.scrollY,
+        scrollHeight: Math.max(root.scrollHeight, document.body ? document.body.scrollHeight : 0),
+        viewportHeight: window.innerHeight,
+    };
+})()
+"#;
+
+/// Smoothly scrolls down by most of a viewport, keeping some overlap
+pub(crate) const SCROLL_STEP_JS: &str =
+    "window.scrollBy({ top: Math.round(window.innerHeight * 0.8), behavior: 'smooth' })";
+
+/// Options for [`Page::prepare_page_for_extraction`](crate::actor::Page::prepare_page_for_extraction)
+#[derive(Debug, Clone)]
+pub struct LazyLoadOptions {
+    /// Limit on scrolling and waiting for the network together
+    pub max_duration_ms: u64,
+    /// Time without requests in flight that counts as network-quiet
+    pub network_quiet_ms: u64,
+    cancel: Option<Arc<AtomicBool>>,
+}
+
+impl Default for LazyLoadOptions {
+    fn default() -> Self {
+        Self {
+            max_duration_ms: DEFAULT_LAZY_LOAD_MAX_DURATION_MS,
+            network_quiet_ms: DEFAULT_NETWORK_QUIET_MS,
+            cancel: None,
+        }
+    }
+}
+
+impl LazyLoadOptions {
+    /// Create options with the default limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the limit on scrolling and waiting for the network together
+    pub fn with_max_duration_ms(mut self, max_duration_ms: u64) -> Self {
+        self.max_duration_ms = max_duration_ms;
+        self
+    }
+
+    /// Set how long the network must be idle to count as quiet
+    pub fn with_network_quiet_ms(mut self, network_quiet_ms: u64) -> Self {
+        self.network_quiet_ms = network_quiet_ms;
+        self
+    }
+
+    /// Stop early, restoring the scroll position, once `cancel` is set
+    pub fn with_cancel_flag(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Whether the cancel flag was set
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+}
+
+/// What preparing a page for extraction did
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LazyLoadReport {
+    /// Document height in CSS pixels before scrolling
+    pub initial_height: f64,
+    /// Document height in CSS pixels once done
+    pub final_height: f64,
+    /// Number of scroll steps taken
+    pub scroll_steps: u32,
+    /// Whether the bottom of the page was reached before the time ran out
+    pub reached_bottom: bool,
+    /// Whether the network went quiet before the time ran out
+    pub network_quiet: bool,
+    /// Whether the preparation was cancelled
+    pub cancelled: bool,
+    /// Time taken in milliseconds
+    pub duration_ms: u64,
+}
+
+impl LazyLoadReport {
+    /// How many CSS pixels the document grew by (negative if it shrank)
+    pub fn height_growth(&self) -> f64 {
+        self.final_height - self.initial_height
+    }
+}
+
+impl fmt::Display for LazyLoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let growth = self.height_growth();
+        if growth.abs() < 1.0 {
+            write!(f, "page height unchanged at {:.0}px", self.final_height)?;
+        } else {
+            write!(
+                f,
+                "page height went from {:.0}px to {:.0}px ({:+.0}px)",
+                self.initial_height, self.final_height, growth
+            )?;
+        }
+        write!(f, " after {} scroll steps", self.scroll_steps)?;
+        if self.cancelled {
+            write!(f, "; cancelled")
+        } else if !self.reached_bottom {
+            write!(f, "; stopped before the bottom")
+        } else if !self.network_quiet {
+            write!(f, "; requests still in flight")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Result of [`SCROLL_METRICS_JS`]
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ScrollMetrics {
+    pub scroll_x: f64,
+    pub scroll_y: f64,
+    pub scroll_height: f64,
+    pub viewport_height: f64,
+}
+
+impl ScrollMetrics {
+    /// Whether the viewport shows the end of the document
+    pub fn at_bottom(&self) -> bool {
+        self.scroll_y + self.viewport_height >= self.scroll_height - 1.0
+    }
+
+    /// Restores this scroll position, then lets resize listeners re-measure
+    pub fn restore_script(&self) -> String {
+        format!(
+            "window.scrollTo({{ left: {}, top: {}, behavior: 'instant' }}); \
+             window.dispatchEvent(new Event('resize'))",
+            self.scroll_x, self.scroll_y
+        )
+    }
+}
+
+/// Requests of one session started and not yet finished
+#[derive(Debug, Default)]
+pub(crate) struct InflightRequests {
+    requests: HashSet<String>,
+}
+
+impl InflightRequests {
+    /// Record a `Network.*` event, ignoring other sessions
+    ///
+    /// Returns whether the event started or finished a request.
+    pub fn apply_event(&mut self, event: &Value, session_id: &str) -> bool {
+        if event["sessionId"].as_str() != Some(session_id) {
+            return false;
+        }
+        let Some(request_id) = event["params"]["requestId"].as_str() else {
+            return false;
+        };
+        match event["method"].as_str() {
+            Some("Network.requestWillBeSent") => {
+                self.requests.insert(request_id.to_string());
+                true
+            }
+            Some("Network.loadingFinished" | "Network.loadingFailed") => {
+                self.requests.remove(request_id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether no request is in flight
+    pub fn is_idle(&self) -> bool {
+        self.requests.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn network(method: &str, request_id: &str, session_id: &str) -> Value {
+        json!({ "method": method, "params": { "requestId": request_id }, "sessionId": session_id })
+    }
+
+    #[test]
+    fn test_inflight_requests() {
+        let mut inflight = InflightRequests::default();
+        assert!(inflight.apply_event(&network("Network.requestWillBeSent", "1", "S1"), "S1"));
+        inflight.apply_event(&network("Network.requestWillBeSent", "2", "S1"), "S1");
+        // Redirects reuse the request ID
+        inflight.apply_event(&network("Network.requestWillBeSent", "2", "S1"), "S1");
+        assert!(!inflight.apply_event(&network("Network.requestWillBeSent", "3", "S2"), "S1"));
+        inflight.apply_event(&network("Network.loadingFinished", "1", "S1"), "S1");
+        assert!(!inflight.is_idle());
+
+        inflight.apply_event(&network("Network.loadingFailed", "2", "S1"), "S1");
+        assert!(inflight.is_idle());
+    }
+
+    #[test]
+    fn test_report_summary() {
+        let mut report = LazyLoadReport {
+            initial_height: 1200.0,
+            final_height: 4850.4,
+            scroll_steps: 7,
+            reached_bottom: true,
+            network_quiet: true,
+            cancelled: false,
+            duration_ms: 1800,
+        };
+        assert_eq!(
+            report.to_string(),
+            "page height went from 1200px to 4850px (+3650px) after 7 scroll steps"
+        );
+
+        report.final_height = 1200.0;
+        report.network_quiet = false;
+        assert_eq!(
+            report.to_string(),
+            "page height unchanged at 1200px after 7 scroll steps; requests still in flight"
+        );
+    }
+
+    #[test]
+    fn test_at_bottom() {
+        let metrics = ScrollMetrics {
+            scroll_x: 0.0,
+            scroll_y: 1480.0,
+            scroll_height: 2200.0,
+            viewport_height: 720.0,
+        };
+        assert!(metrics.at_bottom());
+        assert!(
+            !ScrollMetrics {
+                scroll_y: 0.0,
+                ..metrics
+            }
+            .at_bottom()
+        );
+    }
+}
@@ -1,11 +1,47 @@
 //! Actor module for low-level browser interactions
 
+pub mod audits;
+pub mod binding;
+pub mod checkpoint;
 pub mod element;
+pub mod emulation;
+pub mod fingerprint;
+pub mod forms;
+pub mod ime;
+pub mod images;
 pub mod keyboard;
+pub mod layout;
+pub mod lazy_load;
 pub mod mouse;
 pub mod page;
+pub mod performance;
+pub mod pointer;
+pub mod request_auth;
+pub mod response;
+pub mod screenshot;
+pub mod wait;
 
-pub use element::Element;
+pub use audits::{AuditIssue, AuditSeverity, AuditType, CspIssue};
+pub use binding::BindingHandle;
+pub use checkpoint::{CheckpointId, PageCheckpoint};
+pub use element::{
+    ActivationKey, ControlState, DescendantLocator, DescendantMatch, Element, FormSubmission,
+    ScrollPosition, SelectOption,
+};
+pub use emulation::{ColorScheme, EmulationSettings, VisionDeficiency};
+pub use fingerprint::PageFingerprint;
+pub use forms::{FormField, FormInfo};
+pub use ime::{grapheme_clusters, needs_ime};
+pub use images::{ImageInfo, ImageListOptions};
 pub use keyboard::get_key_info;
+pub use layout::{FALLBACK_VIEWPORT_SIZE, LayoutMetrics, Rect, Size, VisualViewport};
+pub use lazy_load::{LazyLoadOptions, LazyLoadReport};
 pub use mouse::Mouse;
-pub use page::Page;
+pub use page::{LoadState, NavigateOptions, Page};
+pub use performance::{PaintTiming, WebVitals};
+pub use pointer::{PointerEventType, PointerType};
+pub use response::{ResponseInfo, ResponseMarker, UrlPattern, WaitForResponseOptions};
+pub use screenshot::{
+    PreviewOptions, SavedScreenshot, ScreenshotOptions, ViewportPreview, image_dimensions,
+};
+pub use wait::{ElementState, ElementWait, WaitForElementOptions};
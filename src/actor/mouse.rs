@@ -1,5 +1,6 @@
 //! Mouse operations for browser automation
 
+use crate::actor::layout::get_viewport_size;
 use crate::browser::cdp::CdpClient;
 use crate::error::Result;
 use serde_json::json;
@@ -30,7 +31,6 @@ impl MouseButton {
 /// Mouse operations for a target
 pub struct Mouse {
     client: Arc<CdpClient>,
-    #[allow(dead_code)]
     session_id: String,
 }
 
@@ -116,6 +116,9 @@ impl Mouse {
     }
 
     /// Scroll the page
+    ///
+    /// The wheel event is dispatched at (`x`, `y`), or at the center of the
+    /// viewport for coordinates that are not positive.
     pub async fn scroll(
         &self,
         x: f64,
@@ -123,25 +126,16 @@ impl Mouse {
         delta_x: Option<f64>,
         delta_y: Option<f64>,
     ) -> Result<()> {
-        // Get viewport dimensions
-        let layout_metrics = self
-            .client
-            .send_command("Page.getLayoutMetrics", json!({}))
-            .await?;
-
-        let viewport_width = layout_metrics
-            .get("layoutViewport")
-            .and_then(|v| v.get("clientWidth"))
-            .and_then(|v| v.as_f64())
-            .unwrap_or(1920.0);
-        let viewport_height = layout_metrics
-            .get("layoutViewport")
-            .and_then(|v| v.get("clientHeight"))
-            .and_then(|v| v.as_f64())
-            .unwrap_or(1080.0);
-
-        let scroll_x = if x > 0.0 { x } else { viewport_width / 2.0 };
-        let scroll_y = if y > 0.0 { y } else { viewport_height / 2.0 };
+        let session_id = Some(self.session_id.as_str());
+        let (scroll_x, scroll_y) = if x > 0.0 && y > 0.0 {
+            (x, y)
+        } else {
+            let viewport = get_viewport_size(&self.client, &self.session_id).await?;
+            (
+                if x > 0.0 { x } else { viewport.width / 2.0 },
+                if y > 0.0 { y } else { viewport.height / 2.0 },
+            )
+        };
 
         let delta_x = delta_x.unwrap_or(0.0);
         let delta_y = delta_y.unwrap_or(0.0);
@@ -157,7 +151,7 @@ impl Mouse {
 
         match self
             .client
-            .send_command("Input.dispatchMouseEvent", params)
+            .send_command_with_session("Input.dispatchMouseEvent", params, session_id)
             .await
         {
             Ok(_) => Ok(()),
@@ -169,7 +163,7 @@ impl Mouse {
                     "returnByValue": true,
                 });
                 self.client
-                    .send_command("Runtime.evaluate", eval_params)
+                    .send_command_with_session("Runtime.evaluate", eval_params, session_id)
                     .await?;
                 Ok(())
             }
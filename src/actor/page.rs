@@ -1,16 +1,245 @@
 //! Page operations for browser automation
 
+use crate::actor::audits::{
+    self, AUDIT_SETTLE_MS, AUDIT_TIMEOUT_MS, AuditIssue, AuditType, CSS_PATH_JS, CspIssue,
+};
+use crate::actor::binding::{self, BindingHandle};
+use crate::actor::checkpoint::{CAPTURE_JS, PageCheckpoint};
+use crate::actor::emulation::{ColorScheme, EmulationSettings, VisionDeficiency};
+use crate::actor::fingerprint::{PAGE_FINGERPRINT_JS, PageFingerprint};
+use crate::actor::images::{
+    self, DOWNLOAD_TIMEOUT, ImageInfo, ImageListOptions, LIST_IMAGES_JS, REQUEST_IDENTITY_JS,
+};
+use crate::actor::layout::{self, LayoutMetrics, Size};
+use crate::actor::lazy_load::{
+    InflightRequests, LazyLoadOptions, LazyLoadReport, SCROLL_METRICS_JS, SCROLL_STEP_JS,
+    SCROLL_STEP_MS, ScrollMetrics,
+};
+use crate::actor::forms::{
+    DESCRIBE_FORMS_JS, FILL_FIELD_JS, FORM_ELEMENTS_JS, FormField, FormInfo, assemble_forms,
+};
+use crate::actor::performance::{
+    INSTALL_OBSERVERS_JS, NetworkActivity, PAINT_TIMING_JS, PaintTiming, READ_VITALS_JS,
+    VitalsSample, WebVitals, find_tti,
+};
+use crate::actor::pointer::{PointerEventType, PointerInput, PointerType};
+use crate::actor::request_auth::{bearer_token, cookie_header};
+use crate::actor::response::{ResponseInfo, ResponseMarker, UrlPattern, WaitForResponseOptions};
+use crate::actor::screenshot::{
+    self, PreviewOptions, SavedScreenshot, ScreenshotOptions, ViewportPreview,
+};
+use crate::actor::wait::{
+    COUNT_ELEMENTS_JS, ELEMENT_POLL_INTERVAL_MS, ElementWait, WaitForElementOptions,
+};
 use crate::actor::{Element, Mouse, get_key_info};
 use crate::browser::cdp::CdpClient;
+use crate::browser::document_interception::{Decision, DocumentRule, add_document_rule};
+use crate::browser::{
+    DEFAULT_REDIRECT_GRACE_MS, MAX_CLIENT_REDIRECTS, MetaRefresh, NavigationRecord,
+    PENDING_REDIRECT_JS, WebAppManifest,
+};
 use crate::error::{BrowsingError, Result};
 use serde_json::json;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+/// The value of a `Runtime.evaluate` result, as a string
+fn evaluation_value(result: &serde_json::Value) -> Result<String> {
+    if let Some(exception) = result.get("exceptionDetails") {
+        return Err(BrowsingError::Dom(format!(
+            "JavaScript evaluation failed: {exception}"
+        )));
+    }
+
+    let value = result.get("result").and_then(|v| v.get("value"));
+
+    match value {
+        Some(serde_json::Value::String(s)) => Ok(s.clone()),
+        Some(v) => Ok(serde_json::to_string(v)?),
+        None => Ok(String::new()),
+    }
+}
+
+/// Page load milestones that can be waited for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadState {
+    /// The HTML was parsed (`DOMContentLoaded`)
+    DomContentLoaded,
+    /// All resources loaded and content was painted
+    Load,
+}
+
+/// Time allowed for [`NavigateOptions::wait_until`] before navigation fails
+pub const NAVIGATION_TIMEOUT_MS: u64 = 30_000;
+
+/// Longest wait for `Page.frameResized` after changing the viewport size
+pub const VIEWPORT_RESIZE_TIMEOUT_MS: u64 = 200;
+
+/// Time allowed for scripts run by actions before they are terminated
+pub const EVALUATE_TIMEOUT_MS: u64 = 10_000;
+
+/// Time allowed for [`Page::evaluate_safe`]
+pub const SAFE_EVALUATE_TIMEOUT_MS: u64 = 5_000;
+
+/// Time to wait for the main-frame request when injecting headers
+const HEADER_INJECTION_TIMEOUT_MS: u64 = 10_000;
+
+/// Bytes written at a time by [`Page::save_mhtml`]
+const MHTML_WRITE_CHUNK: usize = 1 << 20;
+
+/// Per-navigation options for [`Page::goto_with_options`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NavigateOptions {
+    /// Referrer sent with the navigation request
+    pub referrer: Option<String>,
+    /// Extra headers sent with the main-frame request only
+    pub headers: HashMap<String, String>,
+    /// Load state to wait for before returning; `None` returns once navigation starts
+    pub wait_until: Option<LoadState>,
+    /// How long to keep watching for the page to redirect itself once
+    /// `wait_until` is reached; `None` uses [`DEFAULT_REDIRECT_GRACE_MS`] and
+    /// `Some(0)` does not watch
+    pub redirect_grace_ms: Option<u64>,
+}
+
+impl NavigateOptions {
+    /// Create options that navigate like [`Page::goto`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the referrer
+    pub fn with_referrer(mut self, referrer: impl Into<String>) -> Self {
+        self.referrer = Some(referrer.into());
+        self
+    }
+
+    /// Add a header for the main-frame request
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Wait for a load state before returning
+    pub fn with_wait_until(mut self, state: LoadState) -> Self {
+        self.wait_until = Some(state);
+        self
+    }
+
+    /// Watch for redirects by the page itself for `grace_ms` after load
+    pub fn with_redirect_grace(mut self, grace_ms: u64) -> Self {
+        self.redirect_grace_ms = Some(grace_ms);
+        self
+    }
+
+    /// Whether these options change nothing compared to [`Page::goto`]
+    ///
+    /// The redirect grace period only applies with `wait_until`, so it is
+    /// not considered.
+    pub fn is_empty(&self) -> bool {
+        self.referrer.is_none() && self.headers.is_empty() && self.wait_until.is_none()
+    }
+}
+
+/// Request headers with `extra` added, replacing headers of the same name
+pub(crate) fn merge_headers(original: &serde_json::Value, extra: &HashMap<String, String>) -> Vec<serde_json::Value> {
+    let mut headers: Vec<serde_json::Value> = original
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(name, _)| !extra.keys().any(|k| k.eq_ignore_ascii_case(name)))
+        .map(|(name, value)| json!({ "name": name, "value": value.as_str().unwrap_or_default() }))
+        .collect();
+    let mut extra: Vec<_> = extra.iter().collect();
+    extra.sort();
+    headers.extend(
+        extra
+            .into_iter()
+            .map(|(name, value)| json!({ "name": name, "value": value })),
+    );
+    headers
+}
+
+/// Adds headers to the first document request of the main frame
+struct MainFrameHeaders {
+    frame_id: String,
+    headers: HashMap<String, String>,
+    /// Told once the request went out with the headers
+    applied: std::sync::Mutex<Option<tokio::sync::oneshot::Sender<()>>>,
+}
+
+impl MainFrameHeaders {
+    fn is_pending(&self, paused: &serde_json::Value) -> bool {
+        paused["frameId"] == self.frame_id.as_str()
+            && self.applied.lock().is_ok_and(|applied| applied.is_some())
+    }
+}
+
+impl DocumentRule for MainFrameHeaders {
+    fn decide(&self, paused: &serde_json::Value) -> Decision {
+        if self.is_pending(paused) {
+            Decision::AddHeaders(self.headers.clone())
+        } else {
+            Decision::Continue
+        }
+    }
+
+    fn resolved(&self, paused: &serde_json::Value) {
+        if paused["frameId"] == self.frame_id.as_str()
+            && let Some(applied) = self.applied.lock().ok().and_then(|mut a| a.take())
+        {
+            let _ = applied.send(());
+        }
+    }
+}
+
+/// Requests of a page followed since [`Page::watch_requests`]
+pub(crate) struct RequestWatch {
+    events: tokio::sync::broadcast::Receiver<serde_json::Value>,
+    inflight: InflightRequests,
+    session_id: String,
+}
+
+impl RequestWatch {
+    /// Wait until no request is in flight, for at most `max_ms`
+    ///
+    /// Returns whether a request started or finished since the last call,
+    /// i.e. whether the page may have changed in response.
+    pub(crate) async fn wait_until_idle(&mut self, max_ms: u64) -> bool {
+        use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+        let deadline = tokio::time::Instant::now() + tokio::time::Duration::from_millis(max_ms);
+        let mut active = false;
+        loop {
+            match self.events.try_recv() {
+                Ok(event) => {
+                    active |= self.inflight.apply_event(&event, &self.session_id);
+                    continue;
+                }
+                Err(TryRecvError::Lagged(_)) => continue,
+                Err(TryRecvError::Empty | TryRecvError::Closed) => {}
+            }
+            let now = tokio::time::Instant::now();
+            if self.inflight.is_idle() || now >= deadline {
+                return active;
+            }
+            match tokio::time::timeout(deadline - now, self.events.recv()).await {
+                Ok(Ok(event)) => active |= self.inflight.apply_event(&event, &self.session_id),
+                Ok(Err(RecvError::Lagged(_))) => {}
+                Ok(Err(RecvError::Closed)) | Err(_) => return active,
+            }
+        }
+    }
+}
 
 /// Page operations (tab or iframe)
 pub struct Page {
     client: Arc<CdpClient>,
     session_id: String,
     mouse: Option<Mouse>,
+    /// Paint timing of the current document, once all milestones are known
+    paint_timing: Mutex<Option<PaintTiming>>,
 }
 
 impl Page {
@@ -20,6 +249,7 @@ impl Page {
             client,
             session_id,
             mouse: None,
+            paint_timing: Mutex::new(None),
         }
     }
 
@@ -37,18 +267,372 @@ impl Page {
     /// Reload the page
     pub async fn reload(&self) -> Result<()> {
         self.client.send_command("Page.reload", json!({})).await?;
+        *self.paint_timing.lock().unwrap() = None;
         Ok(())
     }
 
+    /// Reload the page, fetching it and every resource again instead of
+    /// using the HTTP cache
+    ///
+    /// Returns once the new document has replaced the old one.
+    pub async fn reload_ignoring_cache(&self) -> Result<()> {
+        self.reload_tracked(true, None).await.map(|_| ())
+    }
+
+    /// Reload the page and report where the reload landed, like
+    /// [`Page::goto_tracked`]
+    ///
+    /// `Page.reload` does not answer with the new document's loader ID, so
+    /// the main frame's `Page.frameNavigated` with a loader other than the
+    /// current one marks the new document; until then the old one is still
+    /// there. `wait_until` is then awaited on the new document. Enables the
+    /// `Page` and `Network` domains.
+    pub async fn reload_tracked(
+        &self,
+        ignore_cache: bool,
+        wait_until: Option<LoadState>,
+    ) -> Result<NavigationRecord> {
+        use tokio::sync::broadcast::error::RecvError;
+
+        let session_id = Some(self.session_id.as_str());
+        for domain in ["Page.enable", "Network.enable"] {
+            self.client
+                .send_command_with_session(domain, json!({}), session_id)
+                .await?;
+        }
+        let frame_tree = self
+            .client
+            .send_command_with_session("Page.getFrameTree", json!({}), session_id)
+            .await?;
+        let frame = &frame_tree["frameTree"]["frame"];
+        let old_loader_id = frame["loaderId"].as_str().unwrap_or_default().to_string();
+        let url = frame["url"].as_str().unwrap_or_default().to_string();
+
+        let mut events = self.client.subscribe_events();
+        self.client
+            .send_command_with_session(
+                "Page.reload",
+                json!({ "ignoreCache": ignore_cache }),
+                session_id,
+            )
+            .await?;
+        *self.paint_timing.lock().unwrap() = None;
+
+        let deadline = tokio::time::Instant::now()
+            + tokio::time::Duration::from_millis(NAVIGATION_TIMEOUT_MS);
+        let mut seen = vec![];
+        let (loader_id, final_url) = loop {
+            let event = match tokio::time::timeout_at(deadline, events.recv()).await {
+                Ok(Ok(event)) => event,
+                Ok(Err(RecvError::Lagged(_))) => continue,
+                Ok(Err(RecvError::Closed)) | Err(_) => {
+                    return Err(BrowsingError::Browser(format!(
+                        "Reload of {url} did not commit a new document within {NAVIGATION_TIMEOUT_MS}ms"
+                    )));
+                }
+            };
+            let frame = &event["params"]["frame"];
+            let loader_id = frame["loaderId"].as_str().unwrap_or_default();
+            let committed = event["method"] == "Page.frameNavigated"
+                && event["sessionId"] == self.session_id.as_str()
+                && frame.get("parentId").is_none()
+                && loader_id != old_loader_id;
+            if committed {
+                let final_url = frame["url"].as_str().unwrap_or_default().to_string();
+                break (loader_id.to_string(), final_url);
+            }
+            seen.push(event);
+        };
+
+        let mut record = NavigationRecord::from_events(&url, &loader_id, &self.session_id, &seen);
+        if !final_url.is_empty() {
+            record.final_url = final_url;
+        }
+        if let Some(state) = wait_until {
+            self.wait_for_load_state(state, NAVIGATION_TIMEOUT_MS).await?;
+        }
+        Ok(record)
+    }
+
+    /// Wait until the tab has committed a document other than the initial
+    /// `about:blank`, e.g. after `Target.createTarget` with a URL
+    ///
+    /// A new target starts on `about:blank`, whose `readyState` is already
+    /// `complete`, so its load state says nothing about the page it was
+    /// opened for until the first navigation commits. Enables the `Page` domain.
+    pub async fn wait_for_first_navigation(&self, timeout_ms: u64) -> Result<()> {
+        use tokio::sync::broadcast::error::RecvError;
+
+        let session_id = Some(self.session_id.as_str());
+        let mut events = self.client.subscribe_events();
+        self.client
+            .send_command_with_session("Page.enable", json!({}), session_id)
+            .await?;
+        let frame_tree = self
+            .client
+            .send_command_with_session("Page.getFrameTree", json!({}), session_id)
+            .await?;
+        if frame_tree["frameTree"]["frame"]["url"] != "about:blank" {
+            return Ok(());
+        }
+
+        let deadline =
+            tokio::time::Instant::now() + tokio::time::Duration::from_millis(timeout_ms);
+        loop {
+            let event = match tokio::time::timeout_at(deadline, events.recv()).await {
+                Ok(Ok(event)) => event,
+                Ok(Err(RecvError::Lagged(_))) => continue,
+                Ok(Err(RecvError::Closed)) | Err(_) => {
+                    return Err(BrowsingError::Browser(format!(
+                        "Tab did not navigate away from about:blank within {timeout_ms}ms"
+                    )));
+                }
+            };
+            let frame = &event["params"]["frame"];
+            if event["method"] == "Page.frameNavigated"
+                && event["sessionId"] == self.session_id.as_str()
+                && frame.get("parentId").is_none()
+                && frame["url"] != "about:blank"
+            {
+                *self.paint_timing.lock().unwrap() = None;
+                return Ok(());
+            }
+        }
+    }
+
     /// Navigate to URL
     pub async fn goto(&self, url: &str) -> Result<()> {
         let params = json!({
             "url": url
         });
         self.client.send_command("Page.navigate", params).await?;
+        *self.paint_timing.lock().unwrap() = None;
+        Ok(())
+    }
+
+    /// Navigate to URL with a referrer, one-shot headers or a load state to wait for
+    ///
+    /// Headers are added by intercepting the main-frame document request with
+    /// the `Fetch` domain; interception is disabled again right after, so
+    /// subresources and later navigations are unaffected.
+    pub async fn goto_with_options(&self, url: &str, options: &NavigateOptions) -> Result<()> {
+        self.goto_tracked(url, options).await.map(|_| ())
+    }
+
+    /// Navigate like [`Page::goto_with_options`], reporting the redirects
+    /// followed and the URL that loaded
+    ///
+    /// Enables the `Network` domain, whose events carry the redirects.
+    pub async fn goto_tracked(
+        &self,
+        url: &str,
+        options: &NavigateOptions,
+    ) -> Result<NavigationRecord> {
+        let session_id = Some(self.session_id.as_str());
+        self.client
+            .send_command_with_session("Network.enable", json!({}), session_id)
+            .await?;
+        // Redirects happen before the navigation commits, so their events
+        // arrive before the `Page.navigate` response
+        let mut events = self.client.subscribe_events();
+        let mut params = json!({ "url": url });
+        if let Some(ref referrer) = options.referrer {
+            params["referrer"] = json!(referrer);
+        }
+
+        let result = if options.headers.is_empty() {
+            self.client
+                .send_command_with_session("Page.navigate", params, session_id)
+                .await
+        } else {
+            self.navigate_with_headers(params, &options.headers).await
+        }?;
+        *self.paint_timing.lock().unwrap() = None;
+
+        if let Some(error) = result.get("errorText").and_then(|v| v.as_str())
+            && !error.is_empty()
+        {
+            return Err(BrowsingError::Browser(format!(
+                "Navigation to {url} failed: {error}"
+            )));
+        }
+        let mut seen = vec![];
+        loop {
+            match events.try_recv() {
+                Ok(event) => seen.push(event),
+                Err(tokio::sync::broadcast::error::TryRecvError::Lagged(_)) => continue,
+                Err(_) => break,
+            }
+        }
+        let loader_id = result["loaderId"].as_str().unwrap_or_default();
+        let mut record = NavigationRecord::from_events(url, loader_id, &self.session_id, &seen);
+        if let Some(state) = options.wait_until {
+            self.wait_for_load_state(state, NAVIGATION_TIMEOUT_MS).await?;
+            let grace_ms = options.redirect_grace_ms.unwrap_or(DEFAULT_REDIRECT_GRACE_MS);
+            if grace_ms > 0 {
+                self.watch_client_redirects(&mut record, &mut events, state, grace_ms)
+                    .await?;
+            }
+        }
+        Ok(record)
+    }
+
+    /// Follow redirects the current page makes by itself, for at most
+    /// `grace_ms` per page
+    ///
+    /// [`Page::goto_tracked`] does this when it waits for a load state; this
+    /// is for pages reached otherwise, e.g. by a click or a navigation that
+    /// did not wait. The record starts at the current URL; its
+    /// `client_redirects` are empty if the page stayed.
+    pub async fn follow_client_redirects(&self, grace_ms: u64) -> Result<NavigationRecord> {
+        let mut events = self.client.subscribe_events();
+        let mut record = NavigationRecord::default();
+        self.watch_client_redirects(
+            &mut record,
+            &mut events,
+            LoadState::DomContentLoaded,
+            grace_ms,
+        )
+        .await?;
+        Ok(record)
+    }
+
+    /// Follow redirects the page makes by itself after loading, waiting for
+    /// `state` again after each
+    ///
+    /// A cheap check of the loaded page decides whether to watch at all: only
+    /// a meta refresh due within `grace_ms`, or an inline script that sets
+    /// `location` at its top level or in a timer, keeps the navigation
+    /// waiting for the main frame's `Page.frameNavigated`, at most `grace_ms`
+    /// per page. A record without a final URL starts at the page's URL.
+    async fn watch_client_redirects(
+        &self,
+        record: &mut NavigationRecord,
+        events: &mut tokio::sync::broadcast::Receiver<serde_json::Value>,
+        state: LoadState,
+        grace_ms: u64,
+    ) -> Result<()> {
+        use tokio::sync::broadcast::error::RecvError;
+
+        let session_id = Some(self.session_id.as_str());
+        let without_fragment = |url: &str| url.split('#').next().unwrap_or_default().to_string();
+        let mut page_enabled = false;
+        while record.client_redirects.len() < MAX_CLIENT_REDIRECTS {
+            let check = self.evaluate_in_session(PENDING_REDIRECT_JS).await?;
+            let current_url = check["url"].as_str().unwrap_or_default();
+            if record.final_url.is_empty() {
+                record.requested_url = current_url.to_string();
+                record.final_url = current_url.to_string();
+            }
+            // The page may already have moved on while the load state was awaited
+            if !current_url.is_empty()
+                && without_fragment(current_url) != without_fragment(&record.final_url)
+            {
+                record.push_client_redirect(current_url);
+                self.wait_for_load_state(state, NAVIGATION_TIMEOUT_MS).await?;
+                continue;
+            }
+            let refresh_due = check["refresh"]
+                .as_str()
+                .and_then(MetaRefresh::parse)
+                .is_some_and(|refresh| refresh.delay_ms <= grace_ms);
+            if !refresh_due && check["script"] != true {
+                break;
+            }
+
+            if !page_enabled {
+                self.client
+                    .send_command_with_session("Page.enable", json!({}), session_id)
+                    .await?;
+                page_enabled = true;
+            }
+            let deadline =
+                tokio::time::Instant::now() + tokio::time::Duration::from_millis(grace_ms);
+            let navigated = loop {
+                let event = match tokio::time::timeout_at(deadline, events.recv()).await {
+                    Ok(Ok(event)) => event,
+                    Ok(Err(RecvError::Lagged(_))) => continue,
+                    Ok(Err(RecvError::Closed)) | Err(_) => break None,
+                };
+                let frame = &event["params"]["frame"];
+                if event["method"] != "Page.frameNavigated"
+                    || event["sessionId"] != self.session_id.as_str()
+                    || frame.get("parentId").is_some()
+                {
+                    continue;
+                }
+                if let Some(url) = frame["url"].as_str()
+                    && without_fragment(url) != without_fragment(&record.final_url)
+                {
+                    break Some(url.to_string());
+                }
+            };
+            let Some(url) = navigated else {
+                break;
+            };
+            tracing::info!("Page redirected itself from {} to {}", record.final_url, url);
+            record.push_client_redirect(url);
+            *self.paint_timing.lock().unwrap() = None;
+            self.wait_for_load_state(state, NAVIGATION_TIMEOUT_MS).await?;
+        }
         Ok(())
     }
 
+    /// URL the page declares with `<link rel="canonical">`, made absolute
+    pub async fn canonical_url(&self) -> Result<Option<String>> {
+        let href = self
+            .evaluate(r#"document.querySelector('link[rel~="canonical" i][href]')?.href ?? ''"#)
+            .await?;
+        Ok((!href.is_empty()).then_some(href))
+    }
+
+    /// Send `Page.navigate` while adding `headers` to the main-frame request
+    async fn navigate_with_headers(
+        &self,
+        params: serde_json::Value,
+        headers: &HashMap<String, String>,
+    ) -> Result<serde_json::Value> {
+        let session_id = Some(self.session_id.as_str());
+        let frame_tree = self
+            .client
+            .send_command_with_session("Page.getFrameTree", json!({}), session_id)
+            .await?;
+        let main_frame_id = frame_tree["frameTree"]["frame"]["id"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+
+        let (applied, applied_rx) = tokio::sync::oneshot::channel();
+        let rule = Arc::new(MainFrameHeaders {
+            frame_id: main_frame_id,
+            headers: headers.clone(),
+            applied: std::sync::Mutex::new(Some(applied)),
+        });
+        let rule = add_document_rule(&self.client, &self.session_id, rule).await?;
+
+        // Paused requests are resumed concurrently: `Page.navigate` may not
+        // answer until the main-frame request is on its way
+        let result = self
+            .client
+            .send_command_with_session("Page.navigate", params, session_id)
+            .await;
+        // Same-document and failed navigations never issue a request
+        let intercepted = match result {
+            Ok(ref navigated) if navigated.get("loaderId").is_some() => tokio::time::timeout(
+                tokio::time::Duration::from_millis(HEADER_INJECTION_TIMEOUT_MS),
+                applied_rx,
+            )
+            .await
+            .is_ok(),
+            _ => true,
+        };
+        rule.remove().await?;
+        if !intercepted {
+            tracing::warn!("Main-frame request was not intercepted; headers were not sent");
+        }
+        result
+    }
+
     /// Get an element by its backend node ID
     pub async fn get_element(&self, backend_node_id: u32) -> Element {
         Element::new(
@@ -124,21 +708,144 @@ impl Page {
             "returnByValue": true,
             "awaitPromise": true
         });
-        let result = self.client.send_command("Runtime.evaluate", params).await?;
+        let result = self
+            .client
+            .send_command_with_session("Runtime.evaluate", params, Some(&self.session_id))
+            .await?;
+        evaluation_value(&result)
+    }
 
-        if let Some(exception) = result.get("exceptionDetails") {
-            return Err(BrowsingError::Dom(format!(
-                "JavaScript evaluation failed: {exception}"
-            )));
-        }
+    /// Execute JavaScript in the page, terminating it after `timeout_ms`
+    ///
+    /// A script that runs too long, e.g. an infinite loop, is stopped with
+    /// `Runtime.terminateExecution` so the page stays usable, and an error is
+    /// returned.
+    pub async fn evaluate_with_timeout(&self, expression: &str, timeout_ms: u64) -> Result<String> {
+        self.evaluate_or_terminate(expression, timeout_ms)
+            .await?
+            .ok_or_else(|| {
+                BrowsingError::Dom(format!(
+                    "JavaScript evaluation timed out after {timeout_ms}ms"
+                ))
+            })
+    }
+
+    /// Execute JavaScript in the page, or `None` if it takes longer than
+    /// [`SAFE_EVALUATE_TIMEOUT_MS`]
+    ///
+    /// Useful for optional reads, where a slow page should not fail the caller.
+    pub async fn evaluate_safe(&self, expression: &str) -> Result<Option<String>> {
+        self.evaluate_or_terminate(expression, SAFE_EVALUATE_TIMEOUT_MS)
+            .await
+    }
 
-        let value = result.get("result").and_then(|v| v.get("value"));
+    async fn evaluate_or_terminate(
+        &self,
+        expression: &str,
+        timeout_ms: u64,
+    ) -> Result<Option<String>> {
+        let evaluation = tokio::time::timeout(
+            tokio::time::Duration::from_millis(timeout_ms),
+            self.evaluate(expression),
+        )
+        .await;
+        match evaluation {
+            Ok(result) => result.map(Some),
+            Err(_) => {
+                if let Err(e) = self
+                    .client
+                    .send_command_with_session(
+                        "Runtime.terminateExecution",
+                        json!({}),
+                        Some(&self.session_id),
+                    )
+                    .await
+                {
+                    tracing::warn!("Failed to terminate timed-out JavaScript: {e}");
+                }
+                Ok(None)
+            }
+        }
+    }
 
-        match value {
-            Some(serde_json::Value::String(s)) => Ok(s.clone()),
-            Some(v) => Ok(serde_json::to_string(v)?),
-            None => Ok(String::new()),
+    /// URL of the frame `frame_id`, if it is in this page's frame tree
+    pub async fn frame_url(&self, frame_id: &str) -> Result<Option<String>> {
+        fn find(tree: &serde_json::Value, frame_id: &str) -> Option<String> {
+            if tree["frame"]["id"].as_str() == Some(frame_id) {
+                return tree["frame"]["url"].as_str().map(str::to_string);
+            }
+            tree["childFrames"]
+                .as_array()?
+                .iter()
+                .find_map(|child| find(child, frame_id))
         }
+
+        let frame_tree = self
+            .client
+            .send_command_with_session("Page.getFrameTree", json!({}), Some(&self.session_id))
+            .await?;
+        Ok(find(&frame_tree["frameTree"], frame_id))
+    }
+
+    /// URL of the frame holding the focus, `None` if it is a cross-origin frame
+    pub async fn focused_frame_url(&self) -> Result<Option<String>> {
+        let url = self
+            .evaluate(
+                r#"(() => {
+                    let doc = document;
+                    let el = doc.activeElement;
+                    while (el && (el.tagName === 'IFRAME' || el.tagName === 'FRAME')) {
+                        try { doc = el.contentDocument; } catch (e) { doc = null; }
+                        if (!doc) return '';
+                        el = doc.activeElement;
+                    }
+                    return doc.location.href;
+                })()"#,
+            )
+            .await?;
+        Ok((!url.is_empty()).then_some(url))
+    }
+
+    /// Create an isolated JavaScript world in the main frame
+    ///
+    /// The world shares the DOM with the page but has its own globals and
+    /// prototypes. Returns its execution context ID for
+    /// [`evaluate_in_context`](Self::evaluate_in_context).
+    pub async fn create_isolated_world(&self, world_name: &str) -> Result<i64> {
+        let session_id = Some(self.session_id.as_str());
+        let frame_tree = self
+            .client
+            .send_command_with_session("Page.getFrameTree", json!({}), session_id)
+            .await?;
+        let frame_id = frame_tree["frameTree"]["frame"]["id"]
+            .as_str()
+            .ok_or_else(|| BrowsingError::Cdp("No main frame in frame tree".into()))?;
+        let world = self
+            .client
+            .send_command_with_session(
+                "Page.createIsolatedWorld",
+                json!({ "frameId": frame_id, "worldName": world_name }),
+                session_id,
+            )
+            .await?;
+        world["executionContextId"]
+            .as_i64()
+            .ok_or_else(|| BrowsingError::Cdp("No executionContextId for isolated world".into()))
+    }
+
+    /// Execute JavaScript in an execution context, e.g. an isolated world
+    pub async fn evaluate_in_context(&self, expression: &str, context_id: i64) -> Result<String> {
+        let params = json!({
+            "expression": expression,
+            "contextId": context_id,
+            "returnByValue": true,
+            "awaitPromise": true
+        });
+        let result = self
+            .client
+            .send_command_with_session("Runtime.evaluate", params, Some(&self.session_id))
+            .await?;
+        evaluation_value(&result)
     }
 
     /// Take a screenshot
@@ -155,39 +862,143 @@ impl Page {
         full_page: bool,
         clip: Option<(f64, f64, f64, f64)>,
     ) -> Result<String> {
-        let format = format.unwrap_or("png");
+        let options = ScreenshotOptions {
+            format: format.map(str::to_string),
+            quality,
+            full_page,
+            clip,
+            scale: None,
+        };
+        self.capture_screenshot(&options).await
+    }
+
+    /// Take a screenshot and write it to `path`, creating its directory
+    ///
+    /// The image is decoded to the file in chunks rather than all at once,
+    /// which keeps peak memory down for large full-page captures.
+    pub async fn screenshot_to_file(
+        &self,
+        path: &Path,
+        options: &ScreenshotOptions,
+    ) -> Result<SavedScreenshot> {
+        self.write_screenshot(path, options, true).await
+    }
+
+    /// [`Page::screenshot_to_file`], failing instead if `path` exists and
+    /// `overwrite` is unset
+    pub(crate) async fn write_screenshot(
+        &self,
+        path: &Path,
+        options: &ScreenshotOptions,
+        overwrite: bool,
+    ) -> Result<SavedScreenshot> {
+        let data = self.capture_screenshot(options).await?;
+        screenshot::write_base64_image(&data, path, overwrite).await
+    }
+
+    /// Base64 image data of a `Page.captureScreenshot` call
+    async fn capture_screenshot(&self, options: &ScreenshotOptions) -> Result<String> {
+        let format = options.format.as_deref().unwrap_or("png");
         let mut params = json!({
             "format": format,
-            "captureBeyondViewport": full_page
+            "captureBeyondViewport": options.full_page
         });
 
         if format == "jpeg" {
-            if let Some(q) = quality {
+            if let Some(q) = options.quality {
                 params["quality"] = json!(q);
             }
         }
 
-        if let Some((x, y, width, height)) = clip {
+        if let Some((x, y, width, height)) = options.clip {
             params["clip"] = json!({
                 "x": x,
                 "y": y,
                 "width": width,
                 "height": height,
-                "scale": 1.0
+                "scale": options.scale.unwrap_or(1.0)
             });
         }
 
-        let result = self
+        let mut result = self
             .client
             .send_command_with_session("Page.captureScreenshot", params, Some(&self.session_id))
             .await?;
 
-        let data = result
-            .get("data")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| BrowsingError::Browser("No screenshot data".to_string()))?;
+        // Take the data out of the result rather than copying it
+        match result.get_mut("data").map(serde_json::Value::take) {
+            Some(serde_json::Value::String(data)) => Ok(data),
+            _ => Err(BrowsingError::Browser("No screenshot data".to_string())),
+        }
+    }
+
+    /// Take a small screenshot of the viewport once the page has loaded
+    ///
+    /// Waits for [`LoadState::Load`] like navigations do, for at most
+    /// [`PREVIEW_SETTLE_TIMEOUT_MS`](screenshot::PREVIEW_SETTLE_TIMEOUT_MS),
+    /// so the capture is not taken mid-load; a page that is still loading
+    /// then is captured as it is. The viewport is scaled down to fit the
+    /// options' maximum size.
+    pub async fn viewport_preview(&self, options: &PreviewOptions) -> Result<ViewportPreview> {
+        if let Err(e) = self
+            .wait_for_load_state(LoadState::Load, screenshot::PREVIEW_SETTLE_TIMEOUT_MS)
+            .await
+        {
+            tracing::debug!("Taking the preview before the page loaded: {}", e);
+        }
+        let metrics = self.get_layout_metrics().await?;
+        let data = self
+            .capture_screenshot(&options.screenshot_options(&metrics))
+            .await?;
+        let header = screenshot::decode_header(&data);
+        let dimensions = screenshot::image_dimensions(&header);
+        Ok(ViewportPreview {
+            data,
+            mime_type: options.mime_type(),
+            width: dimensions.map(|(width, _)| width),
+            height: dimensions.map(|(_, height)| height),
+        })
+    }
+
+    /// Capture the page with its resources as a single MHTML document
+    ///
+    /// The snapshot holds the DOM as rendered, with styles, images and frames
+    /// embedded, so it opens offline exactly as the page looked.
+    pub async fn capture_mhtml(&self) -> Result<Vec<u8>> {
+        Ok(self.capture_snapshot().await?.into_bytes())
+    }
+
+    /// Save an MHTML snapshot of the page to `path`, returning its size in bytes
+    ///
+    /// The snapshot is written in chunks, without copying it first.
+    pub async fn save_mhtml(&self, path: &std::path::Path) -> Result<u64> {
+        use tokio::io::AsyncWriteExt;
+
+        let snapshot = self.capture_snapshot().await?;
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        let mut file = tokio::fs::File::create(path).await?;
+        for chunk in snapshot.as_bytes().chunks(MHTML_WRITE_CHUNK) {
+            file.write_all(chunk).await?;
+        }
+        file.flush().await?;
+        Ok(snapshot.len() as u64)
+    }
 
-        Ok(data.to_string())
+    async fn capture_snapshot(&self) -> Result<String> {
+        let mut result = self
+            .client
+            .send_command_with_session(
+                "Page.captureSnapshot",
+                json!({ "format": "mhtml" }),
+                Some(&self.session_id),
+            )
+            .await?;
+        match result.get_mut("data").map(serde_json::Value::take) {
+            Some(serde_json::Value::String(data)) => Ok(data),
+            _ => Err(BrowsingError::Browser("No snapshot data".to_string())),
+        }
     }
 
     /// Press a key on the page (supports key combinations like "Control+A")
@@ -302,8 +1113,52 @@ impl Page {
         Ok(())
     }
 
+    /// Dispatch one pointer event from a mouse, pen or finger at (`x`, `y`)
+    ///
+    /// Unlike [`Mouse`], this sets the `PointerEvent` fields drawing and
+    /// gesture handlers read: `pointerType`, `pressure` (0 to 1; 0.5 is
+    /// typical for a pressed mouse button) and `tilt_x`/`tilt_y` in degrees.
+    /// Touch input is sent as touch events with `pointer_id` as the touch id,
+    /// so several fingers can be down at once. A stroke is a `Down`, a run of
+    /// `Move`s with pressure and an `Up`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn dispatch_pointer_event(
+        &self,
+        event_type: PointerEventType,
+        x: f64,
+        y: f64,
+        pointer_id: u32,
+        pointer_type: PointerType,
+        pressure: f64,
+        tilt_x: f64,
+        tilt_y: f64,
+    ) -> Result<()> {
+        PointerInput {
+            event_type,
+            x,
+            y,
+            pointer_id,
+            pointer_type,
+            pressure,
+            tilt_x,
+            tilt_y,
+        }
+        .dispatch(&self.client, &self.session_id)
+        .await
+    }
+
     /// Set viewport size
+    ///
+    /// Returns once the page reports the resize (`Page.frameResized`), or
+    /// after [`VIEWPORT_RESIZE_TIMEOUT_MS`] if it never does, e.g. because the
+    /// size did not change.
     pub async fn set_viewport_size(&self, width: u32, height: u32) -> Result<()> {
+        let session_id = Some(self.session_id.as_str());
+        // Subscribe before resizing so the event cannot be missed
+        let mut events = self.client.subscribe_events();
+        self.client
+            .send_command_with_session("Page.enable", json!({}), session_id)
+            .await?;
         let params = json!({
             "width": width,
             "height": height,
@@ -311,8 +1166,1198 @@ impl Page {
             "mobile": false
         });
         self.client
-            .send_command("Emulation.setDeviceMetricsOverride", params)
+            .send_command_with_session("Emulation.setDeviceMetricsOverride", params, session_id)
+            .await?;
+
+        let deadline = tokio::time::Instant::now()
+            + tokio::time::Duration::from_millis(VIEWPORT_RESIZE_TIMEOUT_MS);
+        loop {
+            match tokio::time::timeout_at(deadline, events.recv()).await {
+                Ok(Ok(event)) => {
+                    if event["method"] == "Page.frameResized"
+                        && event["sessionId"] == self.session_id.as_str()
+                    {
+                        break;
+                    }
+                }
+                Ok(Err(tokio::sync::broadcast::error::RecvError::Lagged(_))) => continue,
+                Ok(Err(tokio::sync::broadcast::error::RecvError::Closed)) | Err(_) => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Resize the viewport and screenshot the page once it has reflowed
+    ///
+    /// Returns the decoded image bytes in `format` (`png`, `jpeg` or `webp`).
+    pub async fn set_viewport_and_capture(
+        &self,
+        width: u32,
+        height: u32,
+        format: &str,
+        full_page: bool,
+    ) -> Result<Vec<u8>> {
+        use base64::Engine;
+
+        self.set_viewport_size(width, height).await?;
+        let data = self
+            .screenshot_with_options(Some(format), None, full_page, None)
+            .await?;
+        base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .map_err(|e| BrowsingError::Browser(format!("Failed to decode screenshot: {e}")))
+    }
+
+    /// Simulate a vision deficiency when rendering the page
+    pub async fn emulate_vision_deficiency(&self, kind: VisionDeficiency) -> Result<()> {
+        self.client
+            .send_command_with_session(
+                "Emulation.setEmulatedVisionDeficiency",
+                json!({ "type": kind.cdp_name() }),
+                Some(&self.session_id),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Report `scheme` as `prefers-color-scheme`
+    ///
+    /// This replaces every other emulated media feature, such as reduced
+    /// motion; use [`Page::apply_emulation`] to set several together.
+    pub async fn set_prefers_color_scheme(&self, scheme: ColorScheme) -> Result<()> {
+        let settings = EmulationSettings {
+            color_scheme: Some(scheme),
+            ..Default::default()
+        };
+        self.client
+            .send_command_with_session(
+                "Emulation.setEmulatedMedia",
+                settings.media_params(),
+                Some(&self.session_id),
+            )
             .await?;
         Ok(())
     }
+
+    /// Send `Save-Data: on` with every request, or stop sending it
+    ///
+    /// This replaces other extra request headers set on the tab.
+    pub async fn set_save_data(&self, enabled: bool) -> Result<()> {
+        let settings = EmulationSettings {
+            save_data: enabled,
+            ..Default::default()
+        };
+        self.set_save_data_header(&settings).await
+    }
+
+    /// Apply all of `settings`, clearing whatever they leave unset
+    ///
+    /// The `Save-Data` header replaces other extra request headers set on
+    /// the tab; [`Browser::set_emulation`](crate::Browser::set_emulation)
+    /// keeps them.
+    pub async fn apply_emulation(&self, settings: &EmulationSettings) -> Result<()> {
+        self.apply_emulated_media(settings).await?;
+        self.set_save_data_header(settings).await
+    }
+
+    /// Apply the media features and vision deficiency of `settings`, leaving
+    /// request headers alone
+    pub(crate) async fn apply_emulated_media(&self, settings: &EmulationSettings) -> Result<()> {
+        self.client
+            .send_command_with_session(
+                "Emulation.setEmulatedMedia",
+                settings.media_params(),
+                Some(&self.session_id),
+            )
+            .await?;
+        self.emulate_vision_deficiency(settings.vision_deficiency)
+            .await
+    }
+
+    async fn set_save_data_header(&self, settings: &EmulationSettings) -> Result<()> {
+        self.set_extra_headers(&settings.headers()).await
+    }
+
+    /// Send `headers` with every request of the tab, replacing the extra
+    /// headers set before
+    pub async fn set_extra_headers(&self, headers: &HashMap<String, String>) -> Result<()> {
+        let session_id = Some(self.session_id.as_str());
+        self.client
+            .send_command_with_session("Network.enable", json!({}), session_id)
+            .await?;
+        self.client
+            .send_command_with_session(
+                "Network.setExtraHTTPHeaders",
+                json!({ "headers": headers }),
+                session_id,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Discover all forms on the page together with their fields
+    pub async fn get_all_forms(&self) -> Result<Vec<FormInfo>> {
+        let session_id = Some(self.session_id.as_str());
+        let object_group = "browsing-forms";
+
+        let controls = self
+            .client
+            .send_command_with_session(
+                "Runtime.evaluate",
+                json!({
+                    "expression": FORM_ELEMENTS_JS,
+                    "objectGroup": object_group,
+                }),
+                session_id,
+            )
+            .await?;
+        let array_id = controls
+            .get("result")
+            .and_then(|v| v.get("objectId"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| BrowsingError::Dom("Failed to collect form elements".to_string()))?
+            .to_string();
+
+        let forms = async {
+            let described = self
+                .client
+                .send_command_with_session(
+                    "Runtime.callFunctionOn",
+                    json!({
+                        "functionDeclaration": DESCRIBE_FORMS_JS,
+                        "objectId": array_id,
+                        "returnByValue": true,
+                    }),
+                    session_id,
+                )
+                .await?;
+            if let Some(exception) = described.get("exceptionDetails") {
+                return Err(BrowsingError::Dom(format!(
+                    "Form discovery failed: {exception}"
+                )));
+            }
+            let raw = described
+                .get("result")
+                .and_then(|v| v.get("value"))
+                .cloned()
+                .unwrap_or_else(|| json!([]));
+
+            // Map each control's array index to its backend node ID
+            let properties = self
+                .client
+                .send_command_with_session(
+                    "Runtime.getProperties",
+                    json!({ "objectId": array_id, "ownProperties": true }),
+                    session_id,
+                )
+                .await?;
+            let mut backend_node_ids = HashMap::new();
+            for property in properties
+                .get("result")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+            {
+                let Some(index) = property
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .and_then(|n| n.parse::<usize>().ok())
+                else {
+                    continue;
+                };
+                let Some(object_id) = property
+                    .get("value")
+                    .and_then(|v| v.get("objectId"))
+                    .and_then(|v| v.as_str())
+                else {
+                    continue;
+                };
+                let node = self
+                    .client
+                    .send_command_with_session(
+                        "DOM.describeNode",
+                        json!({ "objectId": object_id }),
+                        session_id,
+                    )
+                    .await?;
+                if let Some(backend_node_id) = node
+                    .get("node")
+                    .and_then(|v| v.get("backendNodeId"))
+                    .and_then(|v| v.as_u64())
+                {
+                    backend_node_ids.insert(index, backend_node_id as u32);
+                }
+            }
+
+            assemble_forms(raw, &backend_node_ids)
+        }
+        .await;
+
+        let _ = self
+            .client
+            .send_command_with_session(
+                "Runtime.releaseObjectGroup",
+                json!({ "objectGroup": object_group }),
+                session_id,
+            )
+            .await;
+
+        forms
+    }
+
+    /// Set the value of a form field discovered by [`Page::get_all_forms`]
+    ///
+    /// Checkboxes are checked for "true", "on", "yes", "1", or their own value.
+    /// For a radio, the radio of its group whose value or label is `value` is
+    /// checked, failing if there is none; selects are matched by option text or
+    /// value.
+    pub async fn fill_form_field(&self, field: &FormField, value: &str) -> Result<()> {
+        let session_id = Some(self.session_id.as_str());
+        let backend_node_id = field.backend_node_id.ok_or_else(|| {
+            BrowsingError::Dom(format!(
+                "Form field {} has no backend node ID",
+                field.name.as_deref().unwrap_or("<unnamed>")
+            ))
+        })?;
+
+        let resolved = self
+            .client
+            .send_command_with_session(
+                "DOM.resolveNode",
+                json!({ "backendNodeId": backend_node_id }),
+                session_id,
+            )
+            .await?;
+        let object_id = resolved
+            .get("object")
+            .and_then(|v| v.get("objectId"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| BrowsingError::Dom("Failed to resolve form field".to_string()))?;
+
+        let result = self
+            .client
+            .send_command_with_session(
+                "Runtime.callFunctionOn",
+                json!({
+                    "functionDeclaration": FILL_FIELD_JS,
+                    "objectId": object_id,
+                    "arguments": [{ "value": value }],
+                    "returnByValue": true,
+                }),
+                session_id,
+            )
+            .await?;
+        let filled = result
+            .get("result")
+            .and_then(|v| v.get("value"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        if !filled {
+            return Err(BrowsingError::Dom(format!(
+                "Value \"{value}\" is not an option of the field"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Measure Time to Interactive, in milliseconds since navigation start
+    ///
+    /// Waits for the first 5 second window after First Contentful Paint with no
+    /// long tasks and at most two requests in flight. Returns `None` if the page
+    /// doesn't settle within `timeout_ms`.
+    pub async fn measure_tti(&self, timeout_ms: u64) -> Result<Option<f64>> {
+        Ok(self.measure(timeout_ms).await?.tti)
+    }
+
+    /// Measure LCP, FID, CLS, TTI and FCP in one pass
+    ///
+    /// Waits up to `timeout_ms` for TTI; the other metrics are read when it is
+    /// found or the timeout expires.
+    pub async fn measure_web_vitals(&self, timeout_ms: u64) -> Result<WebVitals> {
+        self.measure(timeout_ms).await
+    }
+
+    /// First Paint, First Contentful Paint and Largest Contentful Paint
+    ///
+    /// Milestones that haven't happened yet are `None`. Once all three are
+    /// known the result is cached until the page navigates or reloads.
+    pub async fn get_paint_timing(&self) -> Result<PaintTiming> {
+        if let Some(timing) = *self.paint_timing.lock().unwrap() {
+            return Ok(timing);
+        }
+        let sample = self.evaluate_in_session(PAINT_TIMING_JS).await?;
+        let timing = PaintTiming::from_sample(&sample);
+        if timing.is_complete() {
+            *self.paint_timing.lock().unwrap() = Some(timing);
+        }
+        Ok(timing)
+    }
+
+    /// Wait until First Contentful Paint is reported and return it
+    pub async fn wait_for_first_contentful_paint(&self, timeout_ms: u64) -> Result<f64> {
+        let deadline =
+            tokio::time::Instant::now() + tokio::time::Duration::from_millis(timeout_ms);
+        loop {
+            if let Some(fcp) = self.get_paint_timing().await?.first_contentful_paint_ms {
+                return Ok(fcp);
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(BrowsingError::Browser(format!(
+                    "First Contentful Paint not reported within {timeout_ms}ms"
+                )));
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+        }
+    }
+
+    /// Wait until the document reaches `state`
+    ///
+    /// [`LoadState::Load`] also waits for First Contentful Paint so visual
+    /// content is on screen. Documents that never paint content (e.g. a blank
+    /// page) are considered loaded once the timeout expires.
+    pub async fn wait_for_load_state(&self, state: LoadState, timeout_ms: u64) -> Result<()> {
+        let deadline =
+            tokio::time::Instant::now() + tokio::time::Duration::from_millis(timeout_ms);
+        let ready = match state {
+            LoadState::DomContentLoaded => ["interactive", "complete"].as_slice(),
+            LoadState::Load => ["complete"].as_slice(),
+        };
+        loop {
+            let ready_state = self.evaluate_in_session("document.readyState").await?;
+            if ready_state.as_str().is_some_and(|s| ready.contains(&s)) {
+                break;
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(BrowsingError::Browser(format!(
+                    "Page did not reach {state:?} within {timeout_ms}ms"
+                )));
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+        }
+
+        if state == LoadState::Load {
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            if let Err(e) = self
+                .wait_for_first_contentful_paint(remaining.as_millis() as u64)
+                .await
+            {
+                tracing::debug!("Continuing without First Contentful Paint: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Wait for a response whose URL matches `url_pattern`
+    ///
+    /// See [`UrlPattern`] for the pattern syntax. Only responses received
+    /// after the call are seen. Enables the `Network` domain.
+    pub async fn wait_for_response(
+        &self,
+        url_pattern: &str,
+        timeout_ms: u64,
+    ) -> Result<ResponseInfo> {
+        let options = WaitForResponseOptions {
+            timeout_ms,
+            ..Default::default()
+        };
+        self.wait_for_response_with_options(url_pattern, &options)
+            .await
+    }
+
+    /// Wait for a response whose URL matches `url_pattern`, with its body if
+    /// `options` ask for it
+    ///
+    /// The body is read once the response has finished loading, within the
+    /// same timeout, and cut to `options.max_body_bytes`.
+    pub async fn wait_for_response_with_options(
+        &self,
+        url_pattern: &str,
+        options: &WaitForResponseOptions,
+    ) -> Result<ResponseInfo> {
+        let mut marker = self.mark_responses().await?;
+        self.wait_for_response_since(&mut marker, url_pattern, options)
+            .await
+    }
+
+    /// Keep this page's responses from now on, for [`Page::wait_for_response_since`]
+    ///
+    /// Set the marker before the action that sends the request, so a response
+    /// that arrives before the wait starts is not missed. Enables the
+    /// `Network` domain.
+    pub async fn mark_responses(&self) -> Result<ResponseMarker> {
+        // Subscribe before enabling so no response is missed
+        let events = self.client.subscribe_events();
+        self.client
+            .send_command_with_session("Network.enable", json!({}), Some(&self.session_id))
+            .await?;
+        Ok(ResponseMarker {
+            session_id: self.session_id.clone(),
+            events,
+        })
+    }
+
+    /// Like [`Page::wait_for_response_with_options`], but also seeing the
+    /// responses received since `marker` was set
+    ///
+    /// Responses the wait reads are used up, so waiting again with the same
+    /// marker sees only later ones. A marker set on another page is reset to
+    /// now.
+    pub async fn wait_for_response_since(
+        &self,
+        marker: &mut ResponseMarker,
+        url_pattern: &str,
+        options: &WaitForResponseOptions,
+    ) -> Result<ResponseInfo> {
+        use tokio::sync::broadcast::error::RecvError;
+
+        let pattern = UrlPattern::new(url_pattern)?;
+        let deadline =
+            tokio::time::Instant::now() + tokio::time::Duration::from_millis(options.timeout_ms);
+        let session_id = Some(self.session_id.as_str());
+        if marker.session_id != self.session_id {
+            *marker = self.mark_responses().await?;
+        }
+        let events = &mut marker.events;
+
+        let mut matched: Option<(serde_json::Value, ResponseInfo)> = None;
+        loop {
+            let event = match tokio::time::timeout_at(deadline, events.recv()).await {
+                Ok(Ok(event)) => event,
+                Ok(Err(RecvError::Lagged(_))) => continue,
+                Ok(Err(RecvError::Closed)) => {
+                    return Err(BrowsingError::Cdp(
+                        "Connection closed while waiting for a response".to_string(),
+                    ));
+                }
+                Err(_) => {
+                    return Err(BrowsingError::Browser(match matched {
+                        Some((_, response)) => format!(
+                            "Body of {} did not finish loading within {}ms",
+                            response.url, options.timeout_ms
+                        ),
+                        None => format!(
+                            "No response matching '{}' within {}ms",
+                            pattern.as_str(),
+                            options.timeout_ms
+                        ),
+                    }));
+                }
+            };
+            if event["sessionId"] != self.session_id.as_str() {
+                continue;
+            }
+            let params = &event["params"];
+            match (event["method"].as_str(), &matched) {
+                (Some("Network.responseReceived"), None)
+                    if pattern.matches(params["response"]["url"].as_str().unwrap_or_default()) =>
+                {
+                    let response = ResponseInfo::from_event(params);
+                    if !options.include_body {
+                        return Ok(response);
+                    }
+                    matched = Some((params["requestId"].clone(), response));
+                }
+                (Some("Network.loadingFinished"), Some((request_id, _)))
+                    if params["requestId"] == *request_id =>
+                {
+                    break;
+                }
+                (Some("Network.loadingFailed"), Some((request_id, response)))
+                    if params["requestId"] == *request_id =>
+                {
+                    return Err(BrowsingError::Browser(format!(
+                        "Body of {} failed to load: {}",
+                        response.url,
+                        params["errorText"].as_str().unwrap_or("unknown error")
+                    )));
+                }
+                _ => {}
+            }
+        }
+
+        let (request_id, mut response) = matched.expect("a response was matched");
+        let body = self
+            .client
+            .send_command_with_session(
+                "Network.getResponseBody",
+                json!({ "requestId": request_id }),
+                session_id,
+            )
+            .await?;
+        response.set_body(&body, options.max_body_bytes);
+        Ok(response)
+    }
+
+    /// Wait until elements matching `options` reach their state
+    ///
+    /// Polls the page every [`ELEMENT_POLL_INTERVAL_MS`], so elements that
+    /// appear and vanish between checks can be missed. A check that fails,
+    /// e.g. because a navigation destroyed the page's context, counts as the
+    /// state not being reached yet. Fails on an invalid selector at once, and
+    /// when the state is not reached within `options.timeout_ms`.
+    pub async fn wait_for_element(&self, options: &WaitForElementOptions) -> Result<ElementWait> {
+        options.validate()?;
+        let started = tokio::time::Instant::now();
+        let deadline = started + tokio::time::Duration::from_millis(options.timeout_ms);
+        let expression = format!(
+            "({COUNT_ELEMENTS_JS})({})",
+            json!({ "selector": options.selector, "text": options.text })
+        );
+        loop {
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            let counts = match tokio::time::timeout(remaining, self.evaluate_in_session(&expression)).await {
+                Ok(Ok(counts)) => Some(counts),
+                // `querySelectorAll` throws a SyntaxError for an invalid selector
+                Ok(Err(BrowsingError::Dom(message))) if message.contains("SyntaxError") => {
+                    return Err(BrowsingError::Dom(message));
+                }
+                Ok(Err(e)) => {
+                    tracing::debug!("Element check failed, retrying: {}", e);
+                    None
+                }
+                Err(_) => None,
+            };
+            let reached = counts.and_then(|counts| {
+                let count = |key: &str| counts[key].as_u64().unwrap_or_default() as usize;
+                options.state.reached(count("attached"), count("visible"))
+            });
+            if let Some(count) = reached {
+                return Ok(ElementWait {
+                    waited_ms: started.elapsed().as_millis() as u64,
+                    count,
+                });
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(BrowsingError::Browser(format!(
+                    "Elements matching {} did not become {} within {}ms",
+                    options.describe(),
+                    options.state.as_str(),
+                    options.timeout_ms
+                )));
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(ELEMENT_POLL_INTERVAL_MS)).await;
+        }
+    }
+
+    /// Start following the requests of this page, e.g. to wait for those an
+    /// action starts
+    pub(crate) async fn watch_requests(&self) -> Result<RequestWatch> {
+        // Subscribe before enabling so no request is missed
+        let events = self.client.subscribe_events();
+        self.client
+            .send_command_with_session("Network.enable", json!({}), Some(&self.session_id))
+            .await?;
+        Ok(RequestWatch {
+            events,
+            inflight: InflightRequests::default(),
+            session_id: self.session_id.clone(),
+        })
+    }
+
+    /// Capture what an action could change: URL, focus, `<body>` children,
+    /// scroll position and DOM mutations
+    ///
+    /// Compare fingerprints from before and after an action with
+    /// [`PageFingerprint::changed_since`].
+    pub async fn fingerprint(&self) -> Result<PageFingerprint> {
+        let value = self.evaluate_in_session(PAGE_FINGERPRINT_JS).await?;
+        serde_json::from_value(value)
+            .map_err(|e| BrowsingError::Dom(format!("Invalid page fingerprint: {e}")))
+    }
+
+    /// Load lazy content before reading the page or taking a full-page screenshot
+    ///
+    /// Smoothly scrolls to the bottom and back to where the page was,
+    /// dispatches a `resize` event and waits for the network to go quiet, all
+    /// within `options.max_duration_ms`; infinite-scroll pages stop at that
+    /// limit. Setting the cancel flag of `options` stops at the next step and
+    /// still restores the scroll position.
+    pub async fn prepare_page_for_extraction(
+        &self,
+        options: &LazyLoadOptions,
+    ) -> Result<LazyLoadReport> {
+        let started = tokio::time::Instant::now();
+        let deadline = started + tokio::time::Duration::from_millis(options.max_duration_ms);
+        // Subscribe before enabling so requests started by scrolling are seen
+        let mut events = self.client.subscribe_events();
+        self.client
+            .send_command_with_session("Network.enable", json!({}), Some(&self.session_id))
+            .await?;
+        let mut inflight = InflightRequests::default();
+
+        let start = self.scroll_metrics().await?;
+        let mut current = start;
+        let mut scroll_steps = 0;
+        let mut reached_bottom = false;
+        while !options.is_cancelled() && tokio::time::Instant::now() < deadline {
+            self.evaluate_in_session(SCROLL_STEP_JS).await?;
+            scroll_steps += 1;
+            let step_end = (tokio::time::Instant::now()
+                + tokio::time::Duration::from_millis(SCROLL_STEP_MS))
+            .min(deadline);
+            self.watch_network(&mut events, &mut inflight, step_end, options)
+                .await;
+
+            let previous = current;
+            current = self.scroll_metrics().await?;
+            // Done once the last step reached the end without appending anything
+            if current.at_bottom() && current.scroll_height <= previous.scroll_height {
+                reached_bottom = true;
+                break;
+            }
+        }
+        self.evaluate_in_session(&start.restore_script()).await?;
+
+        let quiet = tokio::time::Duration::from_millis(options.network_quiet_ms);
+        let mut network_quiet = false;
+        while !options.is_cancelled() {
+            let now = tokio::time::Instant::now();
+            if now >= deadline {
+                break;
+            }
+            let window_end = (now + quiet).min(deadline);
+            let active = self
+                .watch_network(&mut events, &mut inflight, window_end, options)
+                .await;
+            if !active && inflight.is_idle() && window_end == now + quiet {
+                network_quiet = true;
+                break;
+            }
+        }
+
+        let end = self.scroll_metrics().await?;
+        Ok(LazyLoadReport {
+            initial_height: start.scroll_height,
+            final_height: end.scroll_height,
+            scroll_steps,
+            reached_bottom,
+            network_quiet,
+            cancelled: options.is_cancelled(),
+            duration_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+
+    /// Audit the page for issues Chrome reported while loading it
+    ///
+    /// Issues are returned errors first, with a CSS selector for the offending
+    /// element where the issue names one.
+    pub async fn run_audit(&self, audit_type: AuditType) -> Result<Vec<AuditIssue>> {
+        let mut issues = Vec::new();
+        for raw in self.collect_issues().await? {
+            if let Some(mut issue) = audits::parse_issue(&raw, audit_type) {
+                if issue.element_selector.is_none()
+                    && let Some(node_id) = issue.violating_node_id
+                {
+                    issue.element_selector = self.selector_for_node(node_id).await;
+                }
+                issues.push(issue);
+            }
+        }
+        issues.sort_by_key(|issue| issue.severity);
+        Ok(issues)
+    }
+
+    /// Audit form labelling, ARIA references and text contrast
+    pub async fn run_accessibility_audit(&self) -> Result<Vec<AuditIssue>> {
+        self.run_audit(AuditType::Accessibility).await
+    }
+
+    /// Audit Content Security Policy violations
+    pub async fn run_content_security_policy_audit(&self) -> Result<Vec<CspIssue>> {
+        let mut issues = Vec::new();
+        for raw in self.collect_issues().await? {
+            if let Some(mut issue) = audits::parse_csp_issue(&raw) {
+                if let Some(node_id) =
+                    raw["details"]["contentSecurityPolicyIssueDetails"]["violatingNodeId"].as_u64()
+                {
+                    issue.element_selector = self.selector_for_node(node_id).await;
+                }
+                issues.push(issue);
+            }
+        }
+        issues.sort_by_key(|issue| issue.severity);
+        Ok(issues)
+    }
+
+    /// Audit cookies the browser rejected or flagged
+    pub async fn run_cookie_audit(&self) -> Result<Vec<AuditIssue>> {
+        self.run_audit(AuditType::Cookies).await
+    }
+
+    /// Capture URL, cookies, storage and scroll position of the page
+    pub async fn capture_checkpoint(&self) -> Result<PageCheckpoint> {
+        let mut checkpoint: PageCheckpoint =
+            serde_json::from_value(self.evaluate_in_session(CAPTURE_JS).await?)?;
+        checkpoint.cookies = self
+            .client
+            .send_command_with_session(
+                "Network.getAllCookies",
+                json!({}),
+                Some(&self.session_id),
+            )
+            .await?
+            .get("cookies")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        Ok(checkpoint)
+    }
+
+    /// Return the page to a checkpoint captured with [`Page::capture_checkpoint`]
+    ///
+    /// Cookies are replaced, then the page is re-navigated with its storage
+    /// seeded before any page script runs, and scrolled back into position.
+    /// Server-side state is not rolled back.
+    pub async fn restore_checkpoint(&self, checkpoint: &PageCheckpoint) -> Result<()> {
+        let session_id = Some(self.session_id.as_str());
+        self.client
+            .send_command_with_session("Network.clearBrowserCookies", json!({}), session_id)
+            .await?;
+        if !checkpoint.cookies.is_empty() {
+            self.client
+                .send_command_with_session(
+                    "Network.setCookies",
+                    json!({ "cookies": checkpoint.cookies }),
+                    session_id,
+                )
+                .await?;
+        }
+
+        let script = self
+            .client
+            .send_command_with_session(
+                "Page.addScriptToEvaluateOnNewDocument",
+                json!({ "source": checkpoint.seed_storage_script() }),
+                session_id,
+            )
+            .await?;
+        let navigated = self
+            .client
+            .send_command_with_session("Page.navigate", json!({ "url": checkpoint.url }), session_id)
+            .await;
+        *self.paint_timing.lock().unwrap() = None;
+        let loaded = match navigated {
+            Ok(_) => {
+                self.wait_for_load_state(LoadState::DomContentLoaded, NAVIGATION_TIMEOUT_MS)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        // Later navigations must not reset storage again
+        if let Some(identifier) = script.get("identifier") {
+            let _ = self
+                .client
+                .send_command_with_session(
+                    "Page.removeScriptToEvaluateOnNewDocument",
+                    json!({ "identifier": identifier }),
+                    session_id,
+                )
+                .await;
+        }
+        loaded?;
+
+        self.evaluate_in_session(&checkpoint.scroll_script()).await?;
+        Ok(())
+    }
+
+    /// Define `window[name]` in this page and every document it loads later,
+    /// calling `handler` each time page JavaScript calls it
+    ///
+    /// The function takes one JSON-serializable argument, which the handler
+    /// receives as a [`serde_json::Value`]; see [`crate::actor::binding`] for
+    /// the payload contract. Handlers run one call at a time, in order.
+    /// Callbacks stop when the returned handle is removed or dropped.
+    pub async fn expose_binding<F, Fut>(&self, name: &str, handler: F) -> Result<BindingHandle>
+    where
+        F: Fn(serde_json::Value) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = ()> + Send + 'static,
+    {
+        binding::expose(Arc::clone(&self.client), self.session_id.clone(), name, handler).await
+    }
+
+    /// Viewport and content geometry, in CSS pixels where available
+    pub async fn get_layout_metrics(&self) -> Result<LayoutMetrics> {
+        layout::get_layout_metrics(&self.client, &self.session_id).await
+    }
+
+    /// Size of the layout viewport in CSS pixels, i.e. one page of scrolling
+    ///
+    /// Cached per page until its viewport resizes, so callers need not keep
+    /// it themselves.
+    pub async fn get_viewport_size(&self) -> Result<Size> {
+        layout::get_viewport_size(&self.client, &self.session_id).await
+    }
+
+    /// The page's web app manifest, `None` if it links none
+    pub async fn get_web_app_manifest(&self) -> Result<Option<WebAppManifest>> {
+        let result = self
+            .client
+            .send_command_with_session("Page.getAppManifest", json!({}), Some(&self.session_id))
+            .await?;
+        WebAppManifest::from_cdp(&result)
+    }
+
+    /// Current scroll position as `(window.scrollX, window.scrollY)`
+    pub async fn get_scroll_position(&self) -> Result<(f64, f64)> {
+        let value = self
+            .evaluate_in_session("[window.scrollX, window.scrollY]")
+            .await?;
+        let coordinate = |i: usize| value.get(i).and_then(|v| v.as_f64());
+        match (coordinate(0), coordinate(1)) {
+            (Some(x), Some(y)) => Ok((x, y)),
+            _ => Err(BrowsingError::Dom(format!("Unexpected scroll position: {value}"))),
+        }
+    }
+
+    /// Images on the page that pass `options`, in document order
+    pub async fn list_images(&self, options: &ImageListOptions) -> Result<Vec<ImageInfo>> {
+        let value = self.evaluate_in_session(LIST_IMAGES_JS).await?;
+        let images: Vec<ImageInfo> = serde_json::from_value(value)?;
+        Ok(images
+            .into_iter()
+            .map(images::normalize)
+            .filter(|image| options.matches(image))
+            .collect())
+    }
+
+    /// Save the `limit` largest of `images` to `dir`, recording each file in
+    /// [`ImageInfo::saved_path`]
+    ///
+    /// Images are fetched with the page's cookies, user agent and `Referer`,
+    /// so images behind a login or hotlink protection work too. Each download
+    /// gives up after [`DOWNLOAD_TIMEOUT`]. `data:` URIs are skipped. An image that fails to download is
+    /// logged and skipped. An existing file is an error unless `overwrite`
+    /// is set. Returns the number of images saved.
+    pub async fn download_images(
+        &self,
+        images: &mut [ImageInfo],
+        dir: &std::path::Path,
+        limit: usize,
+        overwrite: bool,
+    ) -> Result<usize> {
+        tokio::fs::create_dir_all(dir).await?;
+        let identity = self.evaluate_in_session(REQUEST_IDENTITY_JS).await.ok();
+        let field = |name: &str| identity.as_ref()?.get(name)?.as_str();
+        let page_url = field("url").and_then(|url| Url::parse(url).ok());
+        let mut http = reqwest::Client::builder().timeout(DOWNLOAD_TIMEOUT);
+        if let Some(user_agent) = field("userAgent") {
+            http = http.user_agent(user_agent);
+        }
+        let http = http.build()?;
+        let mut saved = 0;
+        for i in images::download_candidates(images, limit) {
+            let image = &mut images[i];
+            let path = dir.join(format!("image-{}.{}", image.index, image.file_extension()));
+            match self.fetch_with_cookies(&http, &image.src, page_url.as_ref()).await {
+                Ok(bytes) => {
+                    let mut file = screenshot::create_file(&path, overwrite).await?;
+                    tokio::io::AsyncWriteExt::write_all(&mut file, &bytes).await?;
+                    image.saved_path = Some(path);
+                    saved += 1;
+                }
+                Err(e) => tracing::warn!("Could not download image {}: {}", image.src, e),
+            }
+        }
+        Ok(saved)
+    }
+
+    async fn fetch_with_cookies(
+        &self,
+        http: &reqwest::Client,
+        url: &str,
+        page_url: Option<&Url>,
+    ) -> Result<Vec<u8>> {
+        let mut request = http.get(url);
+        let cookies = self.get_request_cookie_header(url).await?;
+        if !cookies.is_empty() {
+            request = request.header(reqwest::header::COOKIE, cookies);
+        }
+        let referer = page_url.and_then(|page| images::referer(page, &Url::parse(url).ok()?));
+        if let Some(referer) = referer {
+            request = request.header(reqwest::header::REFERER, referer);
+        }
+        let response = request.send().await?.error_for_status()?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// `Cookie` header value the browser would send with a request to `url`
+    ///
+    /// Lets an agent repeat authenticated requests outside the browser, e.g.
+    /// with `reqwest`. Returns an empty string if no cookies apply.
+    pub async fn get_request_cookie_header(&self, url: &str) -> Result<String> {
+        let parsed = Url::parse(url)?;
+        let result = self
+            .client
+            .send_command_with_session(
+                "Network.getCookies",
+                json!({ "urls": [url] }),
+                Some(&self.session_id),
+            )
+            .await?;
+        let cookies = result
+            .get("cookies")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        Ok(cookie_header(&cookies, &parsed))
+    }
+
+    /// `Authorization` header value for `url` from a bearer token in web storage
+    ///
+    /// Looks through `localStorage`, then `sessionStorage`, of the URL's
+    /// origin. Returns `None` if no token is stored there; tokens kept only in
+    /// memory or in cookies are not found.
+    pub async fn get_authorization_header(&self, url: &str) -> Result<Option<String>> {
+        let origin = Url::parse(url)?.origin().ascii_serialization();
+        let session_id = Some(self.session_id.as_str());
+        let storage = |is_local: bool| {
+            json!({ "storageId": { "securityOrigin": origin, "isLocalStorage": is_local } })
+        };
+        let results = self
+            .client
+            .send_batch(vec![
+                ("DOMStorage.enable", json!({}), session_id),
+                ("DOMStorage.getDOMStorageItems", storage(true), session_id),
+                ("DOMStorage.getDOMStorageItems", storage(false), session_id),
+            ])
+            .await;
+
+        // An origin without storage in this page is not an error
+        let entries: Vec<(String, String)> = results
+            .into_iter()
+            .skip(1)
+            .filter_map(Result::ok)
+            .flat_map(|result| {
+                result
+                    .get("entries")
+                    .and_then(|v| v.as_array())
+                    .cloned()
+                    .unwrap_or_default()
+            })
+            .filter_map(|entry| {
+                Some((
+                    entry.get(0)?.as_str()?.to_string(),
+                    entry.get(1)?.as_str()?.to_string(),
+                ))
+            })
+            .collect();
+        Ok(bearer_token(&entries).map(|token| format!("Bearer {token}")))
+    }
+
+    /// Collect the `Audits` issues reported for this page so far
+    async fn collect_issues(&self) -> Result<Vec<serde_json::Value>> {
+        let session_id = Some(self.session_id.as_str());
+        // Subscribe before enabling: enabling replays the issues found so far
+        let mut events = self.client.subscribe_events();
+        self.client
+            .send_command_with_session("Audits.enable", json!({}), session_id)
+            .await?;
+
+        let settle = tokio::time::Duration::from_millis(AUDIT_SETTLE_MS);
+        let deadline =
+            tokio::time::Instant::now() + tokio::time::Duration::from_millis(AUDIT_TIMEOUT_MS);
+        let mut quiet_until = tokio::time::Instant::now() + settle;
+        let mut issues = Vec::new();
+        loop {
+            match tokio::time::timeout_at(quiet_until.min(deadline), events.recv()).await {
+                Ok(Ok(event)) => {
+                    if event["method"] == "Audits.issueAdded"
+                        && event["sessionId"] == self.session_id.as_str()
+                    {
+                        issues.push(event["params"]["issue"].clone());
+                        quiet_until = tokio::time::Instant::now() + settle;
+                    }
+                }
+                Ok(Err(tokio::sync::broadcast::error::RecvError::Lagged(_))) => continue,
+                Ok(Err(tokio::sync::broadcast::error::RecvError::Closed)) | Err(_) => break,
+            }
+        }
+
+        let _ = self
+            .client
+            .send_command_with_session("Audits.disable", json!({}), session_id)
+            .await;
+        Ok(issues)
+    }
+
+    /// CSS selector of a node, or `None` if it is gone
+    async fn selector_for_node(&self, backend_node_id: u64) -> Option<String> {
+        let session_id = Some(self.session_id.as_str());
+        let resolved = self
+            .client
+            .send_command_with_session(
+                "DOM.resolveNode",
+                json!({ "backendNodeId": backend_node_id }),
+                session_id,
+            )
+            .await
+            .ok()?;
+        let object_id = resolved["object"]["objectId"].as_str()?;
+        let result = self
+            .client
+            .send_command_with_session(
+                "Runtime.callFunctionOn",
+                json!({
+                    "functionDeclaration": CSS_PATH_JS,
+                    "objectId": object_id,
+                    "returnByValue": true,
+                }),
+                session_id,
+            )
+            .await;
+        let _ = self
+            .client
+            .send_command_with_session(
+                "Runtime.releaseObject",
+                json!({ "objectId": object_id }),
+                session_id,
+            )
+            .await;
+        result
+            .ok()?
+            .get("result")
+            .and_then(|v| v.get("value"))
+            .and_then(|v| v.as_str())
+            .filter(|selector| !selector.is_empty())
+            .map(str::to_string)
+    }
+
+    async fn measure(&self, timeout_ms: u64) -> Result<WebVitals> {
+        let session_id = Some(self.session_id.as_str());
+        let deadline =
+            tokio::time::Instant::now() + tokio::time::Duration::from_millis(timeout_ms);
+        let poll_interval = tokio::time::Duration::from_millis(250);
+
+        // Subscribe before enabling so no early request is missed
+        let mut events = self.client.subscribe_events();
+        for domain in ["Performance.enable", "Network.enable"] {
+            self.client
+                .send_command_with_session(domain, json!({}), session_id)
+                .await?;
+        }
+        self.evaluate_in_session(INSTALL_OBSERVERS_JS).await?;
+
+        // Network events carry no page timestamps, so map arrival time onto
+        // `performance.now()` relative to the first sample
+        let first = self.sample_vitals().await?;
+        let started_at = tokio::time::Instant::now();
+        let page_now = |at: tokio::time::Instant| {
+            first.now + at.duration_since(started_at).as_secs_f64() * 1000.0
+        };
+        let mut network = NetworkActivity::default();
+
+        loop {
+            while let Ok(event) = events.try_recv() {
+                let at = page_now(tokio::time::Instant::now());
+                network.apply_event(&event, &self.session_id, at);
+            }
+
+            let sample = self.sample_vitals().await?;
+            // Resource timing covers requests finished before we started
+            // listening; later ones come from the network events
+            let requests: Vec<(f64, f64)> = sample
+                .resources
+                .iter()
+                .copied()
+                .filter(|(start, _)| *start < first.now)
+                .chain(network.spans())
+                .collect();
+            let tti = sample
+                .fcp
+                .and_then(|fcp| find_tti(fcp, &sample.long_tasks, &requests, sample.now));
+
+            let now = tokio::time::Instant::now();
+            if tti.is_some() || now >= deadline {
+                return Ok(sample.into_vitals(tti));
+            }
+
+            // Wake up early on CDP events (e.g. `Performance.metrics`)
+            let wait = poll_interval.min(deadline - now);
+            if let Ok(Ok(event)) = tokio::time::timeout(wait, events.recv()).await {
+                let at = page_now(tokio::time::Instant::now());
+                network.apply_event(&event, &self.session_id, at);
+            }
+        }
+    }
+
+    async fn sample_vitals(&self) -> Result<VitalsSample> {
+        let value = self.evaluate_in_session(READ_VITALS_JS).await?;
+        serde_json::from_value(value)
+            .map_err(|e| BrowsingError::Dom(format!("Failed to read performance data: {e}")))
+    }
+
+    async fn scroll_metrics(&self) -> Result<ScrollMetrics> {
+        let metrics = self.evaluate_in_session(SCROLL_METRICS_JS).await?;
+        Ok(serde_json::from_value(metrics).unwrap_or_default())
+    }
+
+    /// Record this session's network events until `until`, or until cancelled
+    ///
+    /// Returns whether any request started or finished meanwhile.
+    async fn watch_network(
+        &self,
+        events: &mut tokio::sync::broadcast::Receiver<serde_json::Value>,
+        inflight: &mut InflightRequests,
+        until: tokio::time::Instant,
+        options: &LazyLoadOptions,
+    ) -> bool {
+        let poll_interval = tokio::time::Duration::from_millis(50);
+        let mut active = false;
+        loop {
+            let now = tokio::time::Instant::now();
+            if now >= until || options.is_cancelled() {
+                return active;
+            }
+            match tokio::time::timeout((until - now).min(poll_interval), events.recv()).await {
+                Ok(Ok(event)) => active |= inflight.apply_event(&event, &self.session_id),
+                Ok(Err(tokio::sync::broadcast::error::RecvError::Lagged(_))) | Err(_) => {}
+                Ok(Err(tokio::sync::broadcast::error::RecvError::Closed)) => return active,
+            }
+        }
+    }
+
+    async fn evaluate_in_session(&self, expression: &str) -> Result<serde_json::Value> {
+        let result = self
+            .client
+            .send_command_with_session(
+                "Runtime.evaluate",
+                json!({ "expression": expression, "returnByValue": true, "awaitPromise": true }),
+                Some(&self.session_id),
+            )
+            .await?;
+        if let Some(exception) = result.get("exceptionDetails") {
+            return Err(BrowsingError::Dom(format!(
+                "JavaScript evaluation failed: {exception}"
+            )));
+        }
+        Ok(result
+            .get("result")
+            .and_then(|v| v.get("value"))
+            .cloned()
+            .unwrap_or(serde_json::Value::Null))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_headers_replaces_case_insensitively() {
+        let original = json!({ "User-Agent": "Chrome", "referer": "https://old.example/" });
+        let extra = HashMap::from([
+            ("Referer".to_string(), "https://new.example/".to_string()),
+            ("X-Trace".to_string(), "abc".to_string()),
+        ]);
+
+        let merged = merge_headers(&original, &extra);
+        assert_eq!(
+            merged,
+            vec![
+                json!({ "name": "User-Agent", "value": "Chrome" }),
+                json!({ "name": "Referer", "value": "https://new.example/" }),
+                json!({ "name": "X-Trace", "value": "abc" }),
+            ]
+        );
+    }
 }
@@ -0,0 +1,360 @@
+//! Page performance metrics
+//!
+//! [`Page::measure_tti`](crate::actor::Page::measure_tti) and
+//! [`Page::measure_web_vitals`](crate::actor::Page::measure_web_vitals) combine
+//! `PerformanceObserver` entries collected in the page with network activity
+//! observed over CDP. [`Page::get_paint_timing`](crate::actor::Page::get_paint_timing)
+//! only reads paint entries and is cheap enough to poll.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Length of the quiet window that marks the page as interactive
+pub const TTI_QUIET_WINDOW_MS: f64 = 5000.0;
+
+/// Maximum in-flight requests allowed during the quiet window
+pub const TTI_MAX_INFLIGHT_REQUESTS: usize = 2;
+
+/// Installs observers for long tasks, LCP, CLS and FID once per document
+///
+/// Long tasks are not buffered by Chrome, so only those occurring after the
+/// observer is installed are seen.
+pub(crate) const INSTALL_OBSERVERS_JS: &str = r#"(() => {
+    if (window.__browsingVitals) return true;
+    const v = window.__browsingVitals = { longTasks: [], lcp: null, cls: 0, fid: null };
+    const observe = (type, onEntry) => {
+        try {
+            new PerformanceObserver(list => list.getEntries().forEach(onEntry))
+                .observe({ type, buffered: true });
+        } catch (e) {}
+    };
+    observe('longtask', e => v.longTasks.push([e.startTime, e.startTime + e.duration]));
+    observe('largest-contentful-paint', e => { v.lcp = e.renderTime || e.loadTime || e.startTime; });
+    observe('layout-shift', e => { if (!e.hadRecentInput) v.cls += e.value; });
+    observe('first-input', e => { if (v.fid === null) v.fid = e.processingStart - e.startTime; });
+    return true;
+})()"#;
+
+/// Reads the values collected by [`INSTALL_OBSERVERS_JS`] as a [`VitalsSample`]
+pub(crate) const READ_VITALS_JS: &str = r#"(() => {
+    const v = window.__browsingVitals || { longTasks: [], lcp: null, cls: 0, fid: null };
+    const fcp = performance.getEntriesByName('first-contentful-paint')[0];
+    return {
+        now: performance.now(),
+        fcp: fcp ? fcp.startTime : null,
+        lcp: v.lcp,
+        cls: v.cls,
+        fid: v.fid,
+        long_tasks: v.longTasks,
+        resources: performance.getEntriesByType('resource')
+            .map(r => [r.startTime, r.responseEnd || r.startTime]),
+    };
+})()"#;
+
+/// Reads paint entries and the latest LCP candidate
+///
+/// LCP entries are only delivered to observers, so a buffered observer is
+/// given a moment to report before resolving with `null`.
+pub(crate) const PAINT_TIMING_JS: &str = r#"new Promise(resolve => {
+    const paint = JSON.stringify(performance.getEntriesByType('paint'));
+    let lcp = null;
+    const done = () => resolve({ paint, lcp });
+    try {
+        const observer = new PerformanceObserver(list => {
+            const entries = list.getEntries();
+            const last = entries[entries.length - 1];
+            lcp = last.renderTime || last.loadTime || last.startTime;
+            observer.disconnect();
+            done();
+        });
+        observer.observe({ type: 'largest-contentful-paint', buffered: true });
+        setTimeout(() => { observer.disconnect(); done(); }, 100);
+    } catch (e) {
+        done();
+    }
+})"#;
+
+/// Paint milestones, in milliseconds since navigation start
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PaintTiming {
+    /// First Paint
+    pub first_paint_ms: Option<f64>,
+    /// First Contentful Paint
+    pub first_contentful_paint_ms: Option<f64>,
+    /// Largest Contentful Paint (latest candidate when measured)
+    pub largest_contentful_paint_ms: Option<f64>,
+}
+
+impl PaintTiming {
+    /// Build from the result of [`PAINT_TIMING_JS`]
+    pub(crate) fn from_sample(sample: &Value) -> Self {
+        let entries: Vec<Value> = sample
+            .get("paint")
+            .and_then(|v| v.as_str())
+            .and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_default();
+        let start_of = |name: &str| {
+            entries
+                .iter()
+                .find(|e| e.get("name").and_then(|v| v.as_str()) == Some(name))
+                .and_then(|e| e.get("startTime"))
+                .and_then(|v| v.as_f64())
+        };
+        Self {
+            first_paint_ms: start_of("first-paint"),
+            first_contentful_paint_ms: start_of("first-contentful-paint"),
+            largest_contentful_paint_ms: sample.get("lcp").and_then(|v| v.as_f64()),
+        }
+    }
+
+    /// Whether every milestone has been reported, so the timing won't change
+    pub fn is_complete(&self) -> bool {
+        self.first_paint_ms.is_some()
+            && self.first_contentful_paint_ms.is_some()
+            && self.largest_contentful_paint_ms.is_some()
+    }
+}
+
+/// Core Web Vitals and related load metrics, in milliseconds since navigation start
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WebVitals {
+    /// Largest Contentful Paint
+    pub lcp: Option<f64>,
+    /// First Input Delay (only available after the user interacted)
+    pub fid: Option<f64>,
+    /// Cumulative Layout Shift (unitless)
+    pub cls: Option<f64>,
+    /// Time to Interactive
+    pub tti: Option<f64>,
+    /// First Contentful Paint
+    pub fcp: Option<f64>,
+}
+
+/// Snapshot of performance data read from the page
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct VitalsSample {
+    pub now: f64,
+    pub fcp: Option<f64>,
+    pub lcp: Option<f64>,
+    pub cls: Option<f64>,
+    pub fid: Option<f64>,
+    pub long_tasks: Vec<(f64, f64)>,
+    /// Completed resource loads as (start, end)
+    pub resources: Vec<(f64, f64)>,
+}
+
+impl VitalsSample {
+    pub(crate) fn into_vitals(self, tti: Option<f64>) -> WebVitals {
+        WebVitals {
+            lcp: self.lcp,
+            fid: self.fid,
+            cls: self.cls,
+            tti,
+            fcp: self.fcp,
+        }
+    }
+}
+
+/// Requests observed through `Network.*` events, in page time (ms)
+#[derive(Debug, Default)]
+pub(crate) struct NetworkActivity {
+    requests: HashMap<String, (f64, Option<f64>)>,
+}
+
+impl NetworkActivity {
+    /// Record a CDP event received at `at_ms`, ignoring other sessions
+    pub(crate) fn apply_event(&mut self, event: &Value, session_id: &str, at_ms: f64) {
+        if event.get("sessionId").and_then(|v| v.as_str()) != Some(session_id) {
+            return;
+        }
+        let Some(method) = event.get("method").and_then(|v| v.as_str()) else {
+            return;
+        };
+        let Some(request_id) = event
+            .get("params")
+            .and_then(|p| p.get("requestId"))
+            .and_then(|v| v.as_str())
+        else {
+            return;
+        };
+
+        match method {
+            "Network.requestWillBeSent" => {
+                // Redirects reuse the request ID; keep the original start
+                self.requests
+                    .entry(request_id.to_string())
+                    .or_insert((at_ms, None));
+            }
+            "Network.loadingFinished" | "Network.loadingFailed" => {
+                if let Some(request) = self.requests.get_mut(request_id) {
+                    request.1 = Some(at_ms);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// All requests as (start, end); in-flight requests end at infinity
+    pub(crate) fn spans(&self) -> Vec<(f64, f64)> {
+        self.requests
+            .values()
+            .map(|(start, end)| (*start, end.unwrap_or(f64::INFINITY)))
+            .collect()
+    }
+}
+
+/// Number of requests in flight at any point of `[from, to)`
+fn max_inflight(requests: &[(f64, f64)], from: f64, to: f64) -> usize {
+    std::iter::once(from)
+        .chain(
+            requests
+                .iter()
+                .map(|(start, _)| *start)
+                .filter(|start| *start > from && *start < to),
+        )
+        .map(|at| {
+            requests
+                .iter()
+                .filter(|(start, end)| *start <= at && *end > at)
+                .count()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Time to Interactive using the quiet-window heuristic
+///
+/// Finds the first window of [`TTI_QUIET_WINDOW_MS`] after First Contentful
+/// Paint with no long tasks and at most [`TTI_MAX_INFLIGHT_REQUESTS`] requests
+/// in flight. TTI is the end of the last long task before that window, or FCP
+/// if there was none. Returns `None` if no such window has been observed by
+/// `observed_until`.
+pub fn find_tti(
+    fcp: f64,
+    long_tasks: &[(f64, f64)],
+    requests: &[(f64, f64)],
+    observed_until: f64,
+) -> Option<f64> {
+    // A quiet window can only start at FCP, or when a long task or request ends
+    let mut candidates: Vec<f64> = std::iter::once(fcp)
+        .chain(long_tasks.iter().map(|(_, end)| *end))
+        .chain(requests.iter().map(|(_, end)| *end))
+        .filter(|at| at.is_finite() && *at >= fcp)
+        .collect();
+    candidates.sort_by(f64::total_cmp);
+    candidates.dedup();
+
+    for start in candidates {
+        let end = start + TTI_QUIET_WINDOW_MS;
+        if end > observed_until {
+            return None;
+        }
+        let blocked = long_tasks
+            .iter()
+            .any(|(task_start, task_end)| *task_end > start && *task_start < end);
+        if blocked || max_inflight(requests, start, end) > TTI_MAX_INFLIGHT_REQUESTS {
+            continue;
+        }
+
+        let last_long_task = long_tasks
+            .iter()
+            .map(|(_, task_end)| *task_end)
+            .filter(|task_end| *task_end <= start)
+            .fold(fcp, f64::max);
+        return Some(last_long_task);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_tti_is_fcp_for_quiet_page() {
+        assert_eq!(find_tti(800.0, &[], &[], 6000.0), Some(800.0));
+    }
+
+    #[test]
+    fn test_tti_needs_a_full_quiet_window() {
+        assert_eq!(find_tti(800.0, &[], &[], 5000.0), None);
+    }
+
+    #[test]
+    fn test_tti_after_last_long_task() {
+        let long_tasks = [(1500.0, 1700.0), (3000.0, 3200.0)];
+        assert_eq!(find_tti(1000.0, &long_tasks, &[], 9000.0), Some(3200.0));
+        // The second task is still within five seconds of the window end
+        assert_eq!(find_tti(1000.0, &long_tasks, &[], 8000.0), None);
+    }
+
+    #[test]
+    fn test_tti_waits_for_network_quiet() {
+        // Three requests in flight until 4000ms, no long tasks
+        let requests = [(500.0, 4000.0), (600.0, 4000.0), (700.0, 4000.0)];
+        assert_eq!(find_tti(1000.0, &[], &requests, 8500.0), None);
+        assert_eq!(find_tti(1000.0, &[], &requests, 9000.0), Some(1000.0));
+
+        // Two concurrent requests are tolerated
+        assert_eq!(find_tti(1000.0, &[], &requests[..2], 6000.0), Some(1000.0));
+
+        // A request that never finishes keeps the page busy
+        let stuck = [
+            (500.0, f64::INFINITY),
+            (600.0, f64::INFINITY),
+            (700.0, f64::INFINITY),
+        ];
+        assert_eq!(find_tti(1000.0, &[], &stuck, 60000.0), None);
+    }
+
+    #[test]
+    fn test_network_activity_tracks_requests_per_session() {
+        let mut activity = NetworkActivity::default();
+        let event = |method: &str, session: &str, id: &str| json!({"method": method, "params": {"requestId": id}, "sessionId": session});
+
+        activity.apply_event(&event("Network.requestWillBeSent", "S1", "1"), "S1", 100.0);
+        activity.apply_event(&event("Network.requestWillBeSent", "S1", "2"), "S1", 150.0);
+        activity.apply_event(&event("Network.requestWillBeSent", "S2", "3"), "S1", 150.0);
+        activity.apply_event(&event("Network.requestWillBeSent", "S1", "1"), "S1", 180.0);
+        activity.apply_event(&event("Network.loadingFinished", "S1", "1"), "S1", 200.0);
+
+        let mut spans = activity.spans();
+        spans.sort_by(|a, b| a.0.total_cmp(&b.0));
+        assert_eq!(spans, vec![(100.0, 200.0), (150.0, f64::INFINITY)]);
+    }
+
+    #[test]
+    fn test_vitals_sample_deserializes() {
+        let sample: VitalsSample = serde_json::from_value(json!({
+            "now": 7000.0, "fcp": 900.5, "lcp": 1200.0, "cls": 0.05, "fid": null,
+            "long_tasks": [[1000.0, 1080.0]], "resources": [[10.0, 300.0]]
+        }))
+        .unwrap();
+        let vitals = sample.into_vitals(Some(1080.0));
+        assert_eq!(vitals.fcp, Some(900.5));
+        assert_eq!(vitals.tti, Some(1080.0));
+        assert!(vitals.fid.is_none());
+    }
+
+    #[test]
+    fn test_paint_timing_from_sample() {
+        let sample = json!({
+            "paint": r#"[{"name":"first-paint","entryType":"paint","startTime":310.2,"duration":0},
+                         {"name":"first-contentful-paint","entryType":"paint","startTime":355.8,"duration":0}]"#,
+            "lcp": 820.0
+        });
+        let timing = PaintTiming::from_sample(&sample);
+        assert_eq!(timing.first_paint_ms, Some(310.2));
+        assert_eq!(timing.first_contentful_paint_ms, Some(355.8));
+        assert_eq!(timing.largest_contentful_paint_ms, Some(820.0));
+        assert!(timing.is_complete());
+
+        // Nothing painted yet
+        let timing = PaintTiming::from_sample(&json!({ "paint": "[]", "lcp": null }));
+        assert_eq!(timing, PaintTiming::default());
+        assert!(!timing.is_complete());
+    }
+}
@@ -0,0 +1,257 @@
+//! Pointer Events input: pens and touch as well as the mouse
+//!
+//! Drawing apps, signature pads and gesture handlers listen for
+//! `pointerdown`/`pointermove` and read `pointerType`, `pressure` and the
+//! tilt of the stylus. Plain mouse events carry none of that. Pointer events
+//! are dispatched through `Input.dispatchMouseEvent` (mouse and pen) or
+//! `Input.dispatchTouchEvent` (touch) so the browser fires them natively;
+//! enter, leave and mouse or pen cancel have no CDP input and are dispatched
+//! as synthetic `PointerEvent`s on the element under the pointer.
+
+use crate::browser::cdp::CdpClient;
+use crate::error::Result;
+use serde_json::{Value, json};
+
+/// Dispatches synthetic pointer events of `types` on the element at (`x`, `y`)
+const DISPATCH_POINTER_EVENT_JS: &str = r#"
+(({ types, x, y, init }) => {
+    const target = document.elementFromPoint(x, y) || document.documentElement;
+    for (const type of types) {
+        const bubbles = type !== 'pointerenter' && type !== 'pointerleave';
+        target.dispatchEvent(new PointerEvent(type, {
+            ...init, clientX: x, clientY: y, bubbles, cancelable: bubbles, composed: true,
+        }));
+    }
+    return target.localName;
+})
+"#;
+
+/// Stage of a pointer interaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerEventType {
+    /// Contact made or button pressed (`pointerdown`)
+    Down,
+    /// Pointer moved, hovering or in contact (`pointermove`)
+    Move,
+    /// Contact lifted or button released (`pointerup`)
+    Up,
+    /// Interaction aborted by the browser (`pointercancel`)
+    Cancel,
+    /// Pointer entered the element under it (`pointerover`, `pointerenter`)
+    Enter,
+    /// Pointer left the element under it (`pointerout`, `pointerleave`)
+    Leave,
+}
+
+/// Kind of device behind a pointer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerType {
+    /// Mouse or touchpad
+    Mouse,
+    /// Stylus on a pen tablet or touch screen
+    Pen,
+    /// Finger on a touch screen
+    Touch,
+}
+
+impl PointerType {
+    /// Value of `PointerEvent.pointerType` for this device
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PointerType::Mouse => "mouse",
+            PointerType::Pen => "pen",
+            PointerType::Touch => "touch",
+        }
+    }
+}
+
+/// One pointer event at a point in the viewport
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PointerInput {
+    pub event_type: PointerEventType,
+    pub x: f64,
+    pub y: f64,
+    pub pointer_id: u32,
+    pub pointer_type: PointerType,
+    /// Normalized pressure from 0 to 1; above 0 means a button or contact is down
+    pub pressure: f64,
+    /// Plane angle in degrees, from -90 to 90
+    pub tilt_x: f64,
+    /// Plane angle in degrees, from -90 to 90
+    pub tilt_y: f64,
+}
+
+impl PointerInput {
+    /// CDP method and parameters that make the browser fire this event
+    pub fn command(&self) -> (&'static str, Value) {
+        use PointerEventType::*;
+        match (self.pointer_type, self.event_type) {
+            (_, Enter) => self.synthetic(&["pointerover", "pointerenter"]),
+            (_, Leave) => self.synthetic(&["pointerout", "pointerleave"]),
+            (PointerType::Touch, event_type) => {
+                let kind = match event_type {
+                    Down => "touchStart",
+                    Move => "touchMove",
+                    Up => "touchEnd",
+                    _ => "touchCancel",
+                };
+                // Ending and cancelling touches must not list touch points
+                let points = if matches!(event_type, Down | Move) {
+                    json!([{
+                        "x": self.x,
+                        "y": self.y,
+                        "id": self.pointer_id,
+                        "force": self.pressure,
+                        "tiltX": self.tilt_x,
+                        "tiltY": self.tilt_y,
+                    }])
+                } else {
+                    json!([])
+                };
+                (
+                    "Input.dispatchTouchEvent",
+                    json!({ "type": kind, "touchPoints": points }),
+                )
+            }
+            (_, Cancel) => self.synthetic(&["pointercancel"]),
+            (_, event_type) => {
+                let (kind, buttons) = match event_type {
+                    Down => ("mousePressed", 1),
+                    Up => ("mouseReleased", 0),
+                    _ => ("mouseMoved", i32::from(self.pressure > 0.0)),
+                };
+                let mut params = json!({
+                    "type": kind,
+                    "x": self.x,
+                    "y": self.y,
+                    "buttons": buttons,
+                    "pointerType": self.pointer_type.as_str(),
+                    "force": self.pressure,
+                    "tiltX": self.tilt_x,
+                    "tiltY": self.tilt_y,
+                });
+                if event_type != Move {
+                    params["button"] = json!("left");
+                    params["clickCount"] = json!(1);
+                }
+                ("Input.dispatchMouseEvent", params)
+            }
+        }
+    }
+
+    /// `Runtime.evaluate` dispatching `types` as `PointerEvent`s
+    fn synthetic(&self, types: &[&str]) -> (&'static str, Value) {
+        let args = json!({
+            "types": types,
+            "x": self.x,
+            "y": self.y,
+            "init": {
+                "pointerId": self.pointer_id,
+                "pointerType": self.pointer_type.as_str(),
+                "isPrimary": true,
+                "pressure": self.pressure,
+                "tiltX": self.tilt_x,
+                "tiltY": self.tilt_y,
+            },
+        });
+        let expression = format!("{}({})", DISPATCH_POINTER_EVENT_JS.trim(), args);
+        ("Runtime.evaluate", json!({ "expression": expression }))
+    }
+
+    /// Send this event to the page of `session_id`
+    pub async fn dispatch(&self, client: &CdpClient, session_id: &str) -> Result<()> {
+        let (method, params) = self.command();
+        client
+            .send_command_with_session(method, params, Some(session_id))
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(event_type: PointerEventType, pointer_type: PointerType) -> PointerInput {
+        PointerInput {
+            event_type,
+            x: 120.0,
+            y: 80.5,
+            pointer_id: 7,
+            pointer_type,
+            pressure: 0.6,
+            tilt_x: 15.0,
+            tilt_y: -30.0,
+        }
+    }
+
+    #[test]
+    fn test_pen_down_is_a_mouse_event_with_pressure_and_tilt() {
+        let (method, params) = input(PointerEventType::Down, PointerType::Pen).command();
+        assert_eq!(method, "Input.dispatchMouseEvent");
+        assert_eq!(params["type"], "mousePressed");
+        assert_eq!(params["pointerType"], "pen");
+        assert_eq!(params["button"], "left");
+        assert_eq!(params["force"], 0.6);
+        assert_eq!(params["tiltX"], 15.0);
+        assert_eq!(params["tiltY"], -30.0);
+    }
+
+    #[test]
+    fn test_move_holds_the_button_only_under_pressure() {
+        let mut hover = input(PointerEventType::Move, PointerType::Mouse);
+        hover.pressure = 0.0;
+        assert_eq!(hover.command().1["buttons"], 0);
+        assert!(hover.command().1.get("button").is_none());
+
+        let drag = input(PointerEventType::Move, PointerType::Mouse);
+        assert_eq!(drag.command().1["buttons"], 1);
+    }
+
+    #[test]
+    fn test_touch_uses_touch_events() {
+        let (method, params) = input(PointerEventType::Down, PointerType::Touch).command();
+        assert_eq!(method, "Input.dispatchTouchEvent");
+        assert_eq!(params["type"], "touchStart");
+        assert_eq!(
+            params["touchPoints"],
+            json!([{ "x": 120.0, "y": 80.5, "id": 7, "force": 0.6, "tiltX": 15.0, "tiltY": -30.0 }])
+        );
+
+        for (event_type, kind) in [
+            (PointerEventType::Up, "touchEnd"),
+            (PointerEventType::Cancel, "touchCancel"),
+        ] {
+            let (_, params) = input(event_type, PointerType::Touch).command();
+            assert_eq!(params["type"], kind);
+            assert_eq!(params["touchPoints"], json!([]));
+        }
+    }
+
+    #[test]
+    fn test_events_without_cdp_input_are_synthetic() {
+        for (event_type, pointer_type, types) in [
+            (
+                PointerEventType::Enter,
+                PointerType::Touch,
+                "[\"pointerover\",\"pointerenter\"]",
+            ),
+            (
+                PointerEventType::Leave,
+                PointerType::Mouse,
+                "[\"pointerout\",\"pointerleave\"]",
+            ),
+            (
+                PointerEventType::Cancel,
+                PointerType::Pen,
+                "[\"pointercancel\"]",
+            ),
+        ] {
+            let (method, params) = input(event_type, pointer_type).command();
+            assert_eq!(method, "Runtime.evaluate");
+            let expression = params["expression"].as_str().unwrap();
+            assert!(expression.contains(types), "{expression}");
+            assert!(expression.contains("\"pointerId\":7"), "{expression}");
+        }
+    }
+}
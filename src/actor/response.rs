@@ -0,0 +1,228 @@
+//! Waiting for network responses
+//!
+//! After a click starts an XHR, the page is ready once that response arrives,
+//! which can be long after the DOM goes quiet.
+//! [`Page::wait_for_response`](crate::actor::Page::wait_for_response) watches
+//! `Network.responseReceived` for a URL matching a [`UrlPattern`] and reports
+//! the response's status and MIME type, and its body if asked for. Only
+//! responses received after the wait starts are seen, unless the wait is
+//! given a [`ResponseMarker`] set before the action that sends the request:
+//! [`Page::wait_for_response_since`](crate::actor::Page::wait_for_response_since)
+//! also sees the responses that arrived between the marker and the wait.
+
+use crate::error::{BrowsingError, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+/// Default time to wait for a matching response
+pub const DEFAULT_RESPONSE_TIMEOUT_MS: u64 = 10_000;
+
+/// Default limit on the response body returned, in bytes
+pub const DEFAULT_MAX_RESPONSE_BODY_BYTES: usize = 64 * 1024;
+
+/// A pattern response URLs are matched against
+///
+/// - `/regex/`: a regular expression, matched anywhere in the URL
+/// - a glob with `*` (any characters) or `?` (one character): matched
+///   against the whole URL, e.g. `*/api/search?*`
+/// - anything else: matched as a substring, e.g. `/api/search`
+#[derive(Debug, Clone)]
+pub struct UrlPattern {
+    source: String,
+    regex: Regex,
+}
+
+impl UrlPattern {
+    /// Parse `pattern`, failing on an empty pattern or an invalid regex
+    pub fn new(pattern: &str) -> Result<Self> {
+        if pattern.is_empty() {
+            return Err(BrowsingError::Validation(
+                "URL pattern must not be empty".to_string(),
+            ));
+        }
+        let regex = if let Some(regex) = pattern
+            .strip_prefix('/')
+            .and_then(|rest| rest.strip_suffix('/'))
+            .filter(|regex| !regex.is_empty())
+        {
+            regex.to_string()
+        } else if pattern.contains(['*', '?']) {
+            let glob: String = pattern
+                .chars()
+                .map(|c| match c {
+                    '*' => ".*".to_string(),
+                    '?' => ".".to_string(),
+                    c => regex::escape(&c.to_string()),
+                })
+                .collect();
+            format!("^{glob}$")
+        } else {
+            regex::escape(pattern)
+        };
+        let regex = Regex::new(&regex).map_err(|e| {
+            BrowsingError::Validation(format!("Invalid URL pattern '{pattern}': {e}"))
+        })?;
+        Ok(Self {
+            source: pattern.to_string(),
+            regex,
+        })
+    }
+
+    /// Whether `url` matches the pattern
+    pub fn matches(&self, url: &str) -> bool {
+        self.regex.is_match(url)
+    }
+
+    /// The pattern as given
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+}
+
+/// Options for [`Page::wait_for_response_with_options`](crate::actor::Page::wait_for_response_with_options)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaitForResponseOptions {
+    /// Time to wait for the response, and for its body if included
+    pub timeout_ms: u64,
+    /// Whether to read the response body once it has finished loading
+    pub include_body: bool,
+    /// Bytes of the body kept; the rest is cut off
+    pub max_body_bytes: usize,
+}
+
+impl Default for WaitForResponseOptions {
+    fn default() -> Self {
+        Self {
+            timeout_ms: DEFAULT_RESPONSE_TIMEOUT_MS,
+            include_body: false,
+            max_body_bytes: DEFAULT_MAX_RESPONSE_BODY_BYTES,
+        }
+    }
+}
+
+/// The point from which a page's responses are kept for a later wait
+///
+/// Set with [`Page::mark_responses`](crate::actor::Page::mark_responses).
+/// Events are buffered by the CDP client's event channel, so a marker that is
+/// not waited on for more than its capacity of events loses the oldest ones.
+pub struct ResponseMarker {
+    pub(crate) session_id: String,
+    pub(crate) events: broadcast::Receiver<Value>,
+}
+
+impl std::fmt::Debug for ResponseMarker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResponseMarker")
+            .field("session_id", &self.session_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A response observed by [`Page::wait_for_response`](crate::actor::Page::wait_for_response)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResponseInfo {
+    /// Response URL
+    pub url: String,
+    /// HTTP status code
+    pub status: u16,
+    /// HTTP status text
+    #[serde(default)]
+    pub status_text: String,
+    /// MIME type
+    #[serde(default)]
+    pub mime_type: String,
+    /// Kind of request, e.g. `Fetch`, `XHR` or `Document`
+    #[serde(default)]
+    pub resource_type: String,
+    /// Body, if requested; base64 for binary bodies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// Whether the body is base64-encoded
+    #[serde(default)]
+    pub base64_encoded: bool,
+    /// Whether the body was cut off at the size limit
+    #[serde(default)]
+    pub body_truncated: bool,
+}
+
+impl ResponseInfo {
+    /// Response of a `Network.responseReceived` event
+    pub(crate) fn from_event(params: &Value) -> Self {
+        let response = &params["response"];
+        Self {
+            url: response["url"].as_str().unwrap_or_default().to_string(),
+            status: response["status"].as_u64().unwrap_or_default() as u16,
+            status_text: response["statusText"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            mime_type: response["mimeType"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            resource_type: params["type"].as_str().unwrap_or_default().to_string(),
+            body: None,
+            base64_encoded: false,
+            body_truncated: false,
+        }
+    }
+
+    /// Set the body from a `Network.getResponseBody` result, cut to `max_bytes`
+    pub(crate) fn set_body(&mut self, result: &Value, max_bytes: usize) {
+        let mut body = result["body"].as_str().unwrap_or_default().to_string();
+        if body.len() > max_bytes {
+            let mut end = max_bytes;
+            while !body.is_char_boundary(end) {
+                end -= 1;
+            }
+            body.truncate(end);
+            self.body_truncated = true;
+        }
+        self.base64_encoded = result["base64Encoded"].as_bool().unwrap_or(false);
+        self.body = Some(body);
+    }
+
+    /// One-line description, e.g. `200 application/json from https://...`
+    pub fn summary(&self) -> String {
+        format!("{} {} from {}", self.status, self.mime_type, self.url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_url_patterns() {
+        let substring = UrlPattern::new("/api/search").unwrap();
+        assert!(substring.matches("https://shop.example/api/search?q=tea"));
+        assert!(!substring.matches("https://shop.example/api/cart"));
+
+        let glob = UrlPattern::new("*/api/search?q=*").unwrap();
+        assert!(glob.matches("https://shop.example/api/search?q=tea"));
+        assert!(!glob.matches("https://shop.example/api/search"));
+
+        let regex = UrlPattern::new(r"/search\?q=\w+$/").unwrap();
+        assert!(regex.matches("https://shop.example/api/search?q=tea"));
+        assert!(!regex.matches("https://shop.example/api/search?q="));
+
+        assert!(UrlPattern::new("").is_err());
+        assert!(UrlPattern::new("/(/").is_err());
+    }
+
+    #[test]
+    fn test_body_is_cut_at_a_char_boundary() {
+        let mut response = ResponseInfo::from_event(&json!({
+            "type": "Fetch",
+            "response": { "url": "https://a.test/", "status": 200, "mimeType": "text/plain" }
+        }));
+        response.set_body(&json!({ "body": "héllo", "base64Encoded": false }), 2);
+
+        assert_eq!(response.body.as_deref(), Some("h"));
+        assert!(response.body_truncated);
+        assert_eq!(response.summary(), "200 text/plain from https://a.test/");
+    }
+}
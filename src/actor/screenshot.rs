@@ -0,0 +1,326 @@
+//! Screenshots written straight to disk
+//!
+//! `Page.captureScreenshot` returns the image as base64. Decoding all of it
+//! before writing holds the base64 string and the decoded image in memory at
+//! once, which adds up for full-page captures taken every step.
+//! [`Page::screenshot_to_file`](crate::actor::Page::screenshot_to_file)
+//! decodes the base64 in chunks of [`SCREENSHOT_DECODE_CHUNK`] characters and
+//! writes each chunk as it goes, reading the image size from the header of the
+//! first one.
+//!
+//! [`Page::viewport_preview`](crate::actor::Page::viewport_preview) takes a
+//! downscaled capture of the viewport instead, small enough to hand to a
+//! client along with the result of a navigation.
+
+use crate::actor::layout::LayoutMetrics;
+use crate::error::{BrowsingError, Result};
+use base64::Engine as _;
+use base64::engine::general_purpose;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+/// Base64 characters decoded at a time; a multiple of 4, so only the last
+/// chunk can hold padding
+pub const SCREENSHOT_DECODE_CHUNK: usize = 64 * 1024;
+
+/// Options for [`Page::screenshot_to_file`](crate::actor::Page::screenshot_to_file)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScreenshotOptions {
+    /// Image format: `png` (default), `jpeg` or `webp`
+    pub format: Option<String>,
+    /// JPEG quality, 0 to 100
+    pub quality: Option<u32>,
+    /// Capture the whole page instead of the viewport
+    pub full_page: bool,
+    /// Region to capture as (x, y, width, height) in CSS pixels
+    pub clip: Option<(f64, f64, f64, f64)>,
+    /// Scale of the captured region, e.g. 0.5 for half its size (default 1)
+    pub scale: Option<f64>,
+}
+
+impl ScreenshotOptions {
+    /// Capture the whole page
+    pub fn full_page() -> Self {
+        Self {
+            full_page: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// Largest width of a viewport preview by default, in pixels
+pub const DEFAULT_PREVIEW_MAX_WIDTH: u32 = 800;
+
+/// Largest height of a viewport preview by default, in pixels
+pub const DEFAULT_PREVIEW_MAX_HEIGHT: u32 = 600;
+
+/// How long a preview waits for the page to load before it is taken anyway
+pub const PREVIEW_SETTLE_TIMEOUT_MS: u64 = 5_000;
+
+/// Size and format of a [`Page::viewport_preview`](crate::actor::Page::viewport_preview)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreviewOptions {
+    /// Largest width of the image; wider viewports are scaled down
+    pub max_width: u32,
+    /// Largest height of the image; taller viewports are scaled down
+    pub max_height: u32,
+    /// Image format: `png` (default), `jpeg` or `webp`
+    pub format: Option<String>,
+    /// JPEG quality, 0 to 100
+    pub quality: Option<u32>,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        Self {
+            max_width: DEFAULT_PREVIEW_MAX_WIDTH,
+            max_height: DEFAULT_PREVIEW_MAX_HEIGHT,
+            format: None,
+            quality: None,
+        }
+    }
+}
+
+impl PreviewOptions {
+    /// Fit the image within `max_width` by `max_height`
+    pub fn with_max_size(mut self, max_width: u32, max_height: u32) -> Self {
+        self.max_width = max_width;
+        self.max_height = max_height;
+        self
+    }
+
+    /// Encode the image as `format`: `png`, `jpeg` or `webp`
+    pub fn with_format(mut self, format: impl Into<String>) -> Self {
+        self.format = Some(format.into());
+        self
+    }
+
+    /// MIME type of the image, e.g. `image/png`
+    pub fn mime_type(&self) -> String {
+        format!("image/{}", self.format.as_deref().unwrap_or("png"))
+    }
+
+    /// Capture of the visual viewport in `metrics`, scaled down to fit
+    pub(crate) fn screenshot_options(&self, metrics: &LayoutMetrics) -> ScreenshotOptions {
+        let viewport = &metrics.visual_viewport;
+        let (width, height) = (viewport.client_width, viewport.client_height);
+        let mut scale = 1.0_f64;
+        if width > 0.0 {
+            scale = scale.min(f64::from(self.max_width) / width);
+        }
+        if height > 0.0 {
+            scale = scale.min(f64::from(self.max_height) / height);
+        }
+        ScreenshotOptions {
+            format: self.format.clone(),
+            quality: self.quality,
+            full_page: false,
+            clip: Some((viewport.page_x, viewport.page_y, width, height)),
+            scale: Some(scale),
+        }
+    }
+}
+
+/// A downscaled screenshot of the viewport
+#[derive(Debug, Clone, PartialEq)]
+pub struct ViewportPreview {
+    /// Base64 image data
+    pub data: String,
+    /// MIME type of the image, e.g. `image/png`
+    pub mime_type: String,
+    /// Image width in pixels, if the header could be read
+    pub width: Option<u32>,
+    /// Image height in pixels, if the header could be read
+    pub height: Option<u32>,
+}
+
+/// A screenshot saved to disk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedScreenshot {
+    /// Where the screenshot was saved
+    pub path: PathBuf,
+    /// File size in bytes
+    pub size: u64,
+    /// Image width in pixels, if the header could be read
+    pub width: Option<u32>,
+    /// Image height in pixels, if the header could be read
+    pub height: Option<u32>,
+}
+
+impl SavedScreenshot {
+    /// Describe a screenshot already written to `path` from `image`
+    pub fn from_image(path: impl Into<PathBuf>, image: &[u8]) -> Self {
+        let dimensions = image_dimensions(image);
+        Self {
+            path: path.into(),
+            size: image.len() as u64,
+            width: dimensions.map(|(width, _)| width),
+            height: dimensions.map(|(_, height)| height),
+        }
+    }
+}
+
+/// Width and height of a PNG, JPEG, WebP or GIF image from its first bytes
+pub fn image_dimensions(header: &[u8]) -> Option<(u32, u32)> {
+    let be32 = |at: usize| -> Option<u32> {
+        Some(u32::from_be_bytes(header.get(at..at + 4)?.try_into().ok()?))
+    };
+    let be16 = |at: usize| -> Option<u32> {
+        Some(u16::from_be_bytes(header.get(at..at + 2)?.try_into().ok()?) as u32)
+    };
+    let le16 = |at: usize| -> Option<u32> {
+        Some(u16::from_le_bytes(header.get(at..at + 2)?.try_into().ok()?) as u32)
+    };
+    let le24 = |at: usize| -> Option<u32> {
+        let bytes = header.get(at..at + 3)?;
+        Some(u32::from(bytes[0]) | u32::from(bytes[1]) << 8 | u32::from(bytes[2]) << 16)
+    };
+
+    if header.starts_with(b"\x89PNG\r\n\x1a\n") && header.get(12..16)? == b"IHDR" {
+        return Some((be32(16)?, be32(20)?));
+    }
+    if header.starts_with(b"GIF8") {
+        return Some((le16(6)?, le16(8)?));
+    }
+    if header.starts_with(b"RIFF") && header.get(8..12)? == b"WEBP" {
+        return match header.get(12..16)? {
+            b"VP8 " => Some((le16(26)? & 0x3fff, le16(28)? & 0x3fff)),
+            b"VP8L" => {
+                let bits = u32::from_le_bytes(header.get(21..25)?.try_into().ok()?);
+                Some(((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1))
+            }
+            b"VP8X" => Some((le24(24)? + 1, le24(27)? + 1)),
+            _ => None,
+        };
+    }
+    if header.starts_with(&[0xff, 0xd8]) {
+        // Walk the JPEG segments to the first start-of-frame marker
+        let mut at = 2;
+        while at + 4 <= header.len() {
+            if header[at] != 0xff {
+                return None;
+            }
+            let marker = header[at + 1];
+            if marker == 0xff {
+                at += 1;
+                continue;
+            }
+            let is_frame = matches!(marker, 0xc0..=0xcf) && !matches!(marker, 0xc4 | 0xc8 | 0xcc);
+            if is_frame {
+                return Some((be16(at + 7)?, be16(at + 5)?));
+            }
+            at += 2 + be16(at + 2)? as usize;
+        }
+    }
+    None
+}
+
+/// First bytes of base64 image `data`, enough for [`image_dimensions`]
+pub(crate) fn decode_header(data: &str) -> Vec<u8> {
+    let end = data.len().min(SCREENSHOT_DECODE_CHUNK);
+    general_purpose::STANDARD
+        .decode(&data.as_bytes()[..end])
+        .unwrap_or_default()
+}
+
+/// Open `path` for writing
+///
+/// Without `overwrite`, fails if anything exists at `path`, a symlink
+/// included, instead of replacing it or writing through it.
+pub(crate) async fn create_file(path: &Path, overwrite: bool) -> Result<tokio::fs::File> {
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true);
+    if overwrite {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    options.open(path).await.map_err(|e| {
+        if e.kind() == std::io::ErrorKind::AlreadyExists {
+            BrowsingError::Validation(format!("{} already exists", path.display()))
+        } else {
+            e.into()
+        }
+    })
+}
+
+/// Decode base64 `data` to `path` chunk by chunk, see [`create_file`] for `overwrite`
+///
+/// A partly written file is removed if decoding fails.
+pub(crate) async fn write_base64_image(
+    data: &str,
+    path: &Path,
+    overwrite: bool,
+) -> Result<SavedScreenshot> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let mut file = create_file(path, overwrite).await?;
+    let written = decode_chunks(data.as_bytes(), &mut file).await;
+    let (size, dimensions) = match written {
+        Ok(written) => written,
+        Err(e) => {
+            drop(file);
+            let _ = tokio::fs::remove_file(path).await;
+            return Err(e);
+        }
+    };
+    Ok(SavedScreenshot {
+        path: path.to_path_buf(),
+        size,
+        width: dimensions.map(|(width, _)| width),
+        height: dimensions.map(|(_, height)| height),
+    })
+}
+
+async fn decode_chunks(
+    data: &[u8],
+    file: &mut tokio::fs::File,
+) -> Result<(u64, Option<(u32, u32)>)> {
+    let mut buffer = vec![0u8; SCREENSHOT_DECODE_CHUNK / 4 * 3];
+    let mut size = 0u64;
+    let mut dimensions = None;
+    for (i, chunk) in data.chunks(SCREENSHOT_DECODE_CHUNK).enumerate() {
+        let decoded = general_purpose::STANDARD
+            .decode_slice(chunk, &mut buffer)
+            .map_err(|e| BrowsingError::Browser(format!("Failed to decode screenshot: {e}")))?;
+        if i == 0 {
+            dimensions = image_dimensions(&buffer[..decoded]);
+        }
+        file.write_all(&buffer[..decoded]).await?;
+        size += decoded as u64;
+    }
+    file.flush().await?;
+    Ok((size, dimensions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_png_dimensions() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        png.extend(3840u32.to_be_bytes());
+        png.extend(21600u32.to_be_bytes());
+        assert_eq!(image_dimensions(&png), Some((3840, 21600)));
+        assert_eq!(image_dimensions(&png[..20]), None);
+        assert_eq!(image_dimensions(b"not an image"), None);
+    }
+
+    #[test]
+    fn test_jpeg_dimensions() {
+        // SOI, an APP0 segment, then a baseline frame of 640x480
+        let mut jpeg = vec![0xff, 0xd8, 0xff, 0xe0, 0x00, 0x04, 0x00, 0x00];
+        jpeg.extend([0xff, 0xc0, 0x00, 0x11, 0x08, 0x01, 0xe0, 0x02, 0x80]);
+        assert_eq!(image_dimensions(&jpeg), Some((640, 480)));
+    }
+
+    #[test]
+    fn test_webp_dimensions() {
+        let mut webp = b"RIFF\0\0\0\0WEBPVP8X\x0a\0\0\0\0\0\0\0".to_vec();
+        webp.extend([0x7f, 0x07, 0x00, 0x37, 0x04, 0x00]);
+        assert_eq!(image_dimensions(&webp), Some((1920, 1080)));
+    }
+}
@@ -0,0 +1,167 @@
+//! Waiting for elements
+//!
+//! Multi-step flows often need an element to show up (a results list, a
+//! dialog) or go away (a spinner) before carrying on.
+//! [`Page::wait_for_element`](crate::actor::Page::wait_for_element) polls the
+//! page for elements matching a CSS selector, a text, or both, until they
+//! reach an [`ElementState`].
+
+use crate::error::{BrowsingError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Default time to wait for an element
+pub const DEFAULT_ELEMENT_TIMEOUT_MS: u64 = 10_000;
+
+/// Time between checks of the page
+pub(crate) const ELEMENT_POLL_INTERVAL_MS: u64 = 100;
+
+/// Counts elements matching a selector and/or text, and how many are visible.
+/// Without a selector, text matches the innermost elements containing it.
+pub(crate) const COUNT_ELEMENTS_JS: &str = r#"(({ selector, text }) => {
+  const visible = el => {
+    const rect = el.getBoundingClientRect();
+    const style = getComputedStyle(el);
+    return rect.width > 0 && rect.height > 0 && style.visibility !== 'hidden' &&
+      style.display !== 'none' && style.opacity !== '0';
+  };
+  const textOf = el => (el.innerText ?? el.textContent ?? '').toLowerCase();
+  let elements = [...document.querySelectorAll(selector || 'body *')];
+  if (text) {
+    const needle = text.toLowerCase();
+    elements = elements.filter(el => textOf(el).includes(needle));
+    if (!selector) {
+      elements = elements.filter(el => ![...el.children].some(c => textOf(c).includes(needle)));
+    }
+  }
+  return { attached: elements.length, visible: elements.filter(visible).length };
+})"#;
+
+/// State an element is waited for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ElementState {
+    /// In the DOM and rendered with a size, not hidden by CSS
+    #[default]
+    Visible,
+    /// Not visible: gone from the DOM or hidden
+    Hidden,
+    /// In the DOM, visible or not
+    Attached,
+}
+
+impl ElementState {
+    /// Parse `visible`, `hidden` or `attached`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "visible" => Some(Self::Visible),
+            "hidden" => Some(Self::Hidden),
+            "attached" => Some(Self::Attached),
+            _ => None,
+        }
+    }
+
+    /// Name of the state, as parsed by [`ElementState::from_name`]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Visible => "visible",
+            Self::Hidden => "hidden",
+            Self::Attached => "attached",
+        }
+    }
+
+    /// Elements counted toward the state, if the counts reach it
+    ///
+    /// [`ElementState::Hidden`] is reached once no match is visible, and counts
+    /// the matches still in the DOM.
+    pub(crate) fn reached(self, attached: usize, visible: usize) -> Option<usize> {
+        match self {
+            Self::Visible => (visible > 0).then_some(visible),
+            Self::Attached => (attached > 0).then_some(attached),
+            Self::Hidden => (visible == 0).then_some(attached),
+        }
+    }
+}
+
+/// What to wait for with [`Page::wait_for_element`](crate::actor::Page::wait_for_element)
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaitForElementOptions {
+    /// CSS selector elements must match
+    pub selector: Option<String>,
+    /// Text elements must contain, case-insensitively
+    pub text: Option<String>,
+    /// State to wait for
+    pub state: ElementState,
+    /// How long to wait before failing
+    pub timeout_ms: u64,
+}
+
+impl WaitForElementOptions {
+    /// Wait for elements matching a CSS selector to become visible
+    pub fn selector(selector: impl Into<String>) -> Self {
+        Self {
+            selector: Some(selector.into()),
+            text: None,
+            state: ElementState::default(),
+            timeout_ms: DEFAULT_ELEMENT_TIMEOUT_MS,
+        }
+    }
+
+    /// Wait for elements containing `text` to become visible
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            selector: None,
+            text: Some(text.into()),
+            state: ElementState::default(),
+            timeout_ms: DEFAULT_ELEMENT_TIMEOUT_MS,
+        }
+    }
+
+    /// Also require the text `text`
+    pub fn with_text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    /// Wait for `state` instead of visibility
+    pub fn with_state(mut self, state: ElementState) -> Self {
+        self.state = state;
+        self
+    }
+
+    /// Fail after `timeout_ms`
+    pub fn with_timeout(mut self, timeout_ms: u64) -> Self {
+        self.timeout_ms = timeout_ms;
+        self
+    }
+
+    /// Fail unless a selector or a text is given
+    pub(crate) fn validate(&self) -> Result<()> {
+        let given = |value: &Option<String>| value.as_deref().is_some_and(|v| !v.is_empty());
+        if !given(&self.selector) && !given(&self.text) {
+            return Err(BrowsingError::Validation(
+                "Waiting for an element needs a selector or a text".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The elements waited for, e.g. `'.results li' containing "Tea"` or `text "Tea"`
+    pub(crate) fn describe(&self) -> String {
+        match (&self.selector, &self.text) {
+            (Some(selector), Some(text)) => format!("'{selector}' containing \"{text}\""),
+            (Some(selector), None) => format!("'{selector}'"),
+            (None, Some(text)) => format!("text \"{text}\""),
+            (None, None) => "nothing".to_string(),
+        }
+    }
+}
+
+/// How a wait for an element ended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElementWait {
+    /// Time waited, in milliseconds
+    pub waited_ms: u64,
+    /// Elements in the state waited for; for [`ElementState::Hidden`], the
+    /// matches still in the DOM
+    pub count: usize,
+}
@@ -0,0 +1,156 @@
+//! Assertions for test-oriented runs
+//!
+//! The `assert_text_present`, `assert_element_exists` and `assert_url_matches`
+//! actions check a condition on the current page. A failed assertion is an
+//! unsuccessful result whose error explains what was expected and what was
+//! found; it does not stop the run. Each result carries an
+//! [`AssertionOutcome`], and the agent tallies them per step into the
+//! [`AssertionSummary`] of its history.
+
+use crate::agent::views::ActionResult;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Metadata key of an assertion result, holding the [`AssertionOutcome`]
+pub const ASSERTION_METADATA_KEY: &str = "assertion";
+
+/// Result of checking one assertion
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssertionOutcome {
+    /// The assertion action, e.g. `assert_url_matches`
+    pub assertion: String,
+    /// Whether the condition held
+    pub passed: bool,
+    /// What was checked; for a failure, the expected and actual values
+    pub message: String,
+}
+
+impl AssertionOutcome {
+    /// A passed assertion
+    pub fn pass(assertion: &str, message: impl Into<String>) -> Self {
+        Self {
+            assertion: assertion.to_string(),
+            passed: true,
+            message: message.into(),
+        }
+    }
+
+    /// A failed assertion, with a diff-style message of `expected` and `actual`
+    pub fn fail(assertion: &str, expected: &str, actual: &str) -> Self {
+        Self {
+            assertion: assertion.to_string(),
+            passed: false,
+            message: format!("{assertion} failed\n- expected: {expected}\n+ actual:   {actual}"),
+        }
+    }
+
+    /// The action result reporting this outcome
+    pub fn into_result(self) -> ActionResult {
+        let metadata = serde_json::to_value(&self)
+            .ok()
+            .map(|outcome| HashMap::from([(ASSERTION_METADATA_KEY.to_string(), outcome)]));
+        if self.passed {
+            ActionResult {
+                success: Some(true),
+                extracted_content: Some(format!("✓ {}", self.message)),
+                long_term_memory: Some(format!("Assertion passed: {}", self.message)),
+                metadata,
+                ..Default::default()
+            }
+        } else {
+            ActionResult {
+                success: Some(false),
+                error: Some(self.message.clone()),
+                long_term_memory: Some(format!("Assertion failed: {}", self.message)),
+                metadata,
+                ..Default::default()
+            }
+        }
+    }
+
+    /// The outcome recorded in `result`, if it is an assertion result
+    pub fn from_result(result: &ActionResult) -> Option<Self> {
+        let outcome = result.metadata.as_ref()?.get(ASSERTION_METADATA_KEY)?;
+        serde_json::from_value(outcome.clone()).ok()
+    }
+}
+
+/// An assertion checked during a run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssertionRecord {
+    /// Step the assertion was checked in
+    pub step: u32,
+    /// The assertion action
+    pub assertion: String,
+    /// What was checked, or why it failed
+    pub message: String,
+}
+
+/// Assertions of a run, passed and failed, in the order they were checked
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssertionSummary {
+    /// Assertions that held
+    pub passed: Vec<AssertionRecord>,
+    /// Assertions that failed
+    pub failed: Vec<AssertionRecord>,
+}
+
+impl AssertionSummary {
+    /// Count the assertion results among `results` of `step`
+    pub fn record(&mut self, step: u32, results: &[ActionResult]) {
+        for outcome in results.iter().filter_map(AssertionOutcome::from_result) {
+            let record = AssertionRecord {
+                step,
+                assertion: outcome.assertion,
+                message: outcome.message,
+            };
+            if outcome.passed {
+                self.passed.push(record);
+            } else {
+                self.failed.push(record);
+            }
+        }
+    }
+
+    /// Number of assertions checked
+    pub fn total(&self) -> usize {
+        self.passed.len() + self.failed.len()
+    }
+
+    /// Whether no assertion was checked
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+
+    /// Whether every assertion held, including when there were none
+    pub fn all_passed(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outcomes_are_tallied_from_results() {
+        let results = vec![
+            AssertionOutcome::pass("assert_url_matches", "URL matches */cart").into_result(),
+            ActionResult::default(),
+            AssertionOutcome::fail("assert_text_present", "\"Paid\"", "not found").into_result(),
+        ];
+
+        let mut summary = AssertionSummary::default();
+        summary.record(3, &results);
+
+        assert_eq!(summary.total(), 2);
+        assert_eq!(summary.passed[0].step, 3);
+        assert_eq!(summary.failed[0].assertion, "assert_text_present");
+        assert_eq!(
+            summary.failed[0].message,
+            "assert_text_present failed\n- expected: \"Paid\"\n+ actual:   not found"
+        );
+        assert!(!summary.all_passed());
+        assert_eq!(results[2].success, Some(false));
+    }
+}
@@ -0,0 +1,344 @@
+//! Secrets filled into actions when they run
+//!
+//! The model never sees secret values. It writes a placeholder such as
+//! `<secret>password</secret>` into the text an action types (see
+//! [`SECRET_PARAMS`]), and the agent replaces it just before the action runs:
+//! from the secrets given with
+//! [`Agent::with_secrets`](crate::agent::Agent::with_secrets) first, then from
+//! the [`CredentialsProvider`] for the host of the frame the text goes to.
+//! Placeholders anywhere else, such as a URL to navigate to, fail the action
+//! so a secret can't be sent to another site. Values are kept in
+//! [`SecretString`]s, which print as `***` and are zeroed when dropped, and
+//! they are masked in results, history and logs.
+//!
+//! Plug a vault in by implementing [`CredentialsProvider`];
+//! [`EnvCredentialsProvider`] reads environment variables.
+
+use crate::error::{BrowsingError, Result};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use zeroize::Zeroizing;
+
+/// Opening tag of a secret placeholder in action parameters
+pub const SECRET_PLACEHOLDER_OPEN: &str = "<secret>";
+
+/// Closing tag of a secret placeholder in action parameters
+pub const SECRET_PLACEHOLDER_CLOSE: &str = "</secret>";
+
+/// Prefix of the variables [`EnvCredentialsProvider`] reads by default
+pub const DEFAULT_SECRET_ENV_PREFIX: &str = "BROWSING_SECRET_";
+
+/// Actions that type secrets, with the parameter the secrets are filled into
+pub const SECRET_PARAMS: &[(&str, &str)] = &[
+    ("input", "text"),
+    ("send_keys", "keys"),
+    ("form_autofill", "values"),
+];
+
+/// Parameter of `action_type` that secrets are filled into, if it types any
+pub fn secret_param(action_type: &str) -> Option<&'static str> {
+    SECRET_PARAMS
+        .iter()
+        .find(|(action, _)| *action == action_type)
+        .map(|(_, param)| *param)
+}
+
+/// A secret value, zeroed when dropped
+///
+/// `Debug` and `Display` print `***`; use [`SecretString::expose_secret`]
+/// where the value itself is needed.
+#[derive(Clone, Default)]
+pub struct SecretString(Zeroizing<String>);
+
+impl SecretString {
+    /// Wrap `value`
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(Zeroizing::new(value.into()))
+    }
+
+    /// The secret value
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the value is empty
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretString(***)")
+    }
+}
+
+impl fmt::Display for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("***")
+    }
+}
+
+/// Source of secrets by domain, such as a vault
+#[async_trait]
+pub trait CredentialsProvider: Send + Sync {
+    /// Secret `key` for `domain` (the host of the current page), if known
+    async fn get(&self, domain: &str, key: &str) -> Option<SecretString>;
+
+    /// Names of the secrets available for `domain`, listed to the model
+    ///
+    /// Defaults to none; the model can still use names it was told in the task.
+    async fn keys(&self, _domain: &str) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Secrets from environment variables
+///
+/// Secret `password` for `shop.example.com` is read from
+/// `BROWSING_SECRET_SHOP_EXAMPLE_COM__PASSWORD`. There is no fallback for
+/// every domain, so a secret is only typed on the host it was set for. Names
+/// are uppercased and characters other than letters and digits become `_`.
+#[derive(Debug, Clone)]
+pub struct EnvCredentialsProvider {
+    prefix: String,
+}
+
+impl EnvCredentialsProvider {
+    /// Provider reading variables starting with [`DEFAULT_SECRET_ENV_PREFIX`]
+    pub fn new() -> Self {
+        Self::with_prefix(DEFAULT_SECRET_ENV_PREFIX)
+    }
+
+    /// Provider reading variables starting with `prefix`
+    pub fn with_prefix(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+        }
+    }
+
+    /// Variable holding `key` for `domain`
+    pub fn variable_name(&self, domain: &str, key: &str) -> String {
+        format!("{}{}__{}", self.prefix, env_name(domain), env_name(key))
+    }
+}
+
+impl Default for EnvCredentialsProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl CredentialsProvider for EnvCredentialsProvider {
+    async fn get(&self, domain: &str, key: &str) -> Option<SecretString> {
+        if domain.is_empty() {
+            return None;
+        }
+        std::env::var(self.variable_name(domain, key))
+            .ok()
+            .map(SecretString::from)
+    }
+
+    async fn keys(&self, domain: &str) -> Vec<String> {
+        if domain.is_empty() {
+            return Vec::new();
+        }
+        let domain_prefix = format!("{}{}__", self.prefix, env_name(domain));
+        let mut keys: Vec<String> = std::env::vars_os()
+            .filter_map(|(name, _)| {
+                let name = name.into_string().ok()?;
+                name.strip_prefix(&domain_prefix).map(str::to_lowercase)
+            })
+            .collect();
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+}
+
+/// `name` uppercased, with characters other than letters and digits as `_`
+fn env_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Secrets given explicitly, backed by an optional provider
+#[derive(Clone, Default)]
+pub struct Credentials {
+    secrets: HashMap<String, SecretString>,
+    provider: Option<Arc<dyn CredentialsProvider>>,
+}
+
+impl Credentials {
+    /// No secrets and no provider
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add secrets that take precedence over the provider's
+    pub fn with_secrets<S: Into<SecretString>>(
+        mut self,
+        secrets: impl IntoIterator<Item = (String, S)>,
+    ) -> Self {
+        self.secrets
+            .extend(secrets.into_iter().map(|(key, value)| (key, value.into())));
+        self
+    }
+
+    /// Look up secrets the explicit ones lack in `provider`
+    pub fn with_provider(mut self, provider: Arc<dyn CredentialsProvider>) -> Self {
+        self.provider = Some(provider);
+        self
+    }
+
+    /// Whether there are no explicit secrets and no provider
+    pub fn is_empty(&self) -> bool {
+        self.secrets.is_empty() && self.provider.is_none()
+    }
+
+    /// Only the provider, for text typed into a frame from another site
+    ///
+    /// Explicit secrets aren't tied to a domain, so they are only typed on the
+    /// page's own host.
+    pub fn provider_only(&self) -> Self {
+        Self {
+            secrets: HashMap::new(),
+            provider: self.provider.clone(),
+        }
+    }
+
+    /// Secret `key` for `domain`: the explicit one, else the provider's
+    pub async fn get(&self, domain: &str, key: &str) -> Option<SecretString> {
+        if let Some(secret) = self.secrets.get(key) {
+            return Some(secret.clone());
+        }
+        self.provider.as_ref()?.get(domain, key).await
+    }
+
+    /// Names of the secrets available for `domain`, sorted
+    pub async fn keys(&self, domain: &str) -> Vec<String> {
+        let mut keys: Vec<String> = self.secrets.keys().cloned().collect();
+        if let Some(provider) = &self.provider {
+            keys.extend(provider.keys(domain).await);
+        }
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
+    /// Replace the placeholders in every string of `value` with secrets for `domain`
+    ///
+    /// Returns the secrets filled in, so they can be masked afterwards. Fails
+    /// without changing `value` if a placeholder names an unknown secret.
+    pub async fn substitute(&self, domain: &str, value: &mut Value) -> Result<Vec<SecretString>> {
+        let mut names = Vec::new();
+        collect_placeholders(value, &mut names);
+        if names.is_empty() {
+            return Ok(Vec::new());
+        }
+        names.sort();
+        names.dedup();
+
+        let mut resolved = HashMap::new();
+        for name in names {
+            let secret = self.get(domain, &name).await.ok_or_else(|| {
+                BrowsingError::Tool(format!("No secret named '{name}' for {domain}"))
+            })?;
+            resolved.insert(name, secret);
+        }
+        replace_placeholders(value, &resolved);
+        Ok(resolved.into_values().collect())
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("secrets", &self.secrets)
+            .field("provider", &self.provider.is_some())
+            .finish()
+    }
+}
+
+/// Whether any string in `value` holds a secret placeholder
+pub fn has_placeholders(value: &Value) -> bool {
+    let mut names = Vec::new();
+    collect_placeholders(value, &mut names);
+    !names.is_empty()
+}
+
+/// Names of the secret placeholders in `text`, in order
+pub fn secret_placeholders(text: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(SECRET_PLACEHOLDER_OPEN) {
+        let after = &rest[start + SECRET_PLACEHOLDER_OPEN.len()..];
+        let Some(end) = after.find(SECRET_PLACEHOLDER_CLOSE) else {
+            break;
+        };
+        names.push(after[..end].trim());
+        rest = &after[end + SECRET_PLACEHOLDER_CLOSE.len()..];
+    }
+    names
+}
+
+fn collect_placeholders(value: &Value, names: &mut Vec<String>) {
+    match value {
+        Value::String(text) => {
+            names.extend(secret_placeholders(text).into_iter().map(str::to_string))
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_placeholders(v, names)),
+        Value::Object(map) => map.values().for_each(|v| collect_placeholders(v, names)),
+        _ => {}
+    }
+}
+
+fn replace_placeholders(value: &mut Value, secrets: &HashMap<String, SecretString>) {
+    match value {
+        Value::String(text) => {
+            let mut filled = String::with_capacity(text.len());
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find(SECRET_PLACEHOLDER_OPEN) {
+                let after = &rest[start + SECRET_PLACEHOLDER_OPEN.len()..];
+                let Some(end) = after.find(SECRET_PLACEHOLDER_CLOSE) else {
+                    break;
+                };
+                filled.push_str(&rest[..start]);
+                filled.push_str(secrets[after[..end].trim()].expose_secret());
+                rest = &after[end + SECRET_PLACEHOLDER_CLOSE.len()..];
+            }
+            filled.push_str(rest);
+            *text = filled;
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|v| replace_placeholders(v, secrets)),
+        Value::Object(map) => map
+            .values_mut()
+            .for_each(|v| replace_placeholders(v, secrets)),
+        _ => {}
+    }
+}
@@ -0,0 +1,171 @@
+//! Deterministic mode for reproducible runs
+//!
+//! Pages are a big source of run-to-run variation: `Math.random`, the clock
+//! and animations caught half-way all change what the model sees. With a
+//! [`Determinism`] config the agent seeds `Math.random`, freezes `Date` and
+//! turns off animations in the tab the run starts in, and records the seed in
+//! the history so a run can be repeated from a bug report.
+//!
+//! The crate itself draws no random numbers (waits and retries use fixed
+//! intervals), so the seed only drives the page.
+
+use crate::browser::cdp::CdpClient;
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Time `Date` is frozen at by default: 2024-01-01T00:00:00Z, in milliseconds
+pub const DEFAULT_FROZEN_TIME_MS: i64 = 1_704_067_200_000;
+
+/// Stylesheet that stops CSS animations, transitions and smooth scrolling
+pub const NO_ANIMATIONS_CSS: &str = "*, *::before, *::after { \
+animation-duration: 0s !important; animation-delay: 0s !important; \
+transition-duration: 0s !important; transition-delay: 0s !important; \
+caret-color: transparent !important; scroll-behavior: auto !important; }";
+
+/// How to make the page behave the same way on every run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Determinism {
+    /// Seed for the page's `Math.random`
+    pub seed: u64,
+    /// Freeze `Date.now()` and `new Date()` at [`Determinism::frozen_time_ms`]
+    #[serde(default = "default_true")]
+    pub freeze_time: bool,
+    /// Time the clock is frozen at, in milliseconds since the Unix epoch
+    #[serde(default = "default_frozen_time_ms")]
+    pub frozen_time_ms: i64,
+    /// Emulate `prefers-reduced-motion: reduce` and stop CSS animations
+    #[serde(default = "default_true")]
+    pub disable_animations: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_frozen_time_ms() -> i64 {
+    DEFAULT_FROZEN_TIME_MS
+}
+
+impl Determinism {
+    /// Seed `Math.random`, freeze the clock and disable animations
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            freeze_time: true,
+            frozen_time_ms: DEFAULT_FROZEN_TIME_MS,
+            disable_animations: true,
+        }
+    }
+
+    /// Script that installs the configured behavior in a document
+    ///
+    /// `Math.random` becomes a mulberry32 generator seeded from
+    /// [`Determinism::seed`]. `Date` keeps working for explicit dates and
+    /// only reads the frozen time when asked for "now".
+    pub fn init_script(&self) -> String {
+        let seed = (self.seed ^ (self.seed >> 32)) as u32;
+        let mut script = format!(
+            r#"(() => {{
+  let state = {seed};
+  Math.random = function random() {{
+    state = (state + 0x6D2B79F5) | 0;
+    let t = Math.imul(state ^ (state >>> 15), 1 | state);
+    t = (t + Math.imul(t ^ (t >>> 7), 61 | t)) ^ t;
+    return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+  }};
+"#
+        );
+        if self.freeze_time {
+            script.push_str(&format!(
+                r#"  const NOW = {};
+  const RealDate = Date;
+  function FrozenDate(...args) {{
+    if (!new.target) return new RealDate(NOW).toString();
+    return args.length ? new RealDate(...args) : new RealDate(NOW);
+  }}
+  FrozenDate.prototype = RealDate.prototype;
+  FrozenDate.now = () => NOW;
+  FrozenDate.parse = RealDate.parse;
+  FrozenDate.UTC = RealDate.UTC;
+  globalThis.Date = FrozenDate;
+"#,
+                self.frozen_time_ms
+            ));
+        }
+        if self.disable_animations {
+            // Adopted stylesheets work before the document has any elements
+            script.push_str(&format!(
+                r#"  const sheet = new CSSStyleSheet();
+  sheet.replaceSync({});
+  document.adoptedStyleSheets = [...document.adoptedStyleSheets, sheet];
+"#,
+                json!(NO_ANIMATIONS_CSS)
+            ));
+        }
+        script.push_str("})();");
+        script
+    }
+}
+
+/// Make the tab attached as `session_id` behave as `determinism` asks
+///
+/// The script is registered for every new document and also run in the
+/// current one.
+pub async fn apply_determinism(
+    client: &CdpClient,
+    session_id: &str,
+    determinism: &Determinism,
+) -> Result<()> {
+    let session = Some(session_id);
+    let script = determinism.init_script();
+    client
+        .send_command_with_session(
+            "Page.addScriptToEvaluateOnNewDocument",
+            json!({ "source": script }),
+            session,
+        )
+        .await?;
+    if determinism.disable_animations {
+        client
+            .send_command_with_session(
+                "Emulation.setEmulatedMedia",
+                json!({ "features": [{ "name": "prefers-reduced-motion", "value": "reduce" }] }),
+                session,
+            )
+            .await?;
+    }
+    client
+        .send_command_with_session("Runtime.evaluate", json!({ "expression": script }), session)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_init_script_embeds_the_config() {
+        let script = Determinism::new(42).init_script();
+        assert!(script.contains("let state = 42;"));
+        assert!(script.contains(&format!("const NOW = {DEFAULT_FROZEN_TIME_MS};")));
+        assert!(script.contains("adoptedStyleSheets"));
+
+        let script = Determinism {
+            freeze_time: false,
+            disable_animations: false,
+            ..Determinism::new(u64::MAX)
+        }
+        .init_script();
+        assert!(script.contains("let state = 0;"));
+        assert!(!script.contains("FrozenDate"));
+        assert!(!script.contains("adoptedStyleSheets"));
+    }
+
+    #[test]
+    fn test_defaults_when_deserializing() {
+        let determinism: Determinism = serde_json::from_str(r#"{"seed": 7}"#).unwrap();
+        assert_eq!(determinism, Determinism::new(7));
+    }
+}
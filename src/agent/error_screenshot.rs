@@ -0,0 +1,206 @@
+//! Screenshots of failed actions for postmortems
+//!
+//! With [`AgentSettings::screenshot_on_error`](crate::agent::views::AgentSettings::screenshot_on_error)
+//! set, the first failing action of a step gets a viewport screenshot in the
+//! run's artifacts directory. A banner naming the action (and an outline of
+//! the indexed element, if any) is drawn on the page for the capture and
+//! removed right after. Later failures in the same step are not captured, so
+//! retries of one action do not flood the directory.
+
+use crate::agent::views::ActionResult;
+use crate::dom::views::DOMInteractedElement;
+use crate::tools::views::ActionModel;
+use crate::traits::BrowserClient;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Metadata key of a failed action's result holding its screenshot path
+pub const ERROR_SCREENSHOT_METADATA_KEY: &str = "error_screenshot";
+
+/// Draws a banner with `label` and outlines `rect` (x, y, width, height) if given
+const ANNOTATE_JS: &str = r#"
+(({ label, rect }) => {
+    const root = document.createElement('div');
+    root.id = '__browsing_error_annotation';
+    root.style.cssText = 'position:fixed;inset:0;pointer-events:none;z-index:2147483647';
+    const banner = document.createElement('div');
+    banner.textContent = label;
+    banner.style.cssText = 'position:absolute;left:0;right:0;top:0;padding:6px 10px;' +
+        'background:rgba(200,0,0,.85);color:#fff;font:bold 14px/1.4 sans-serif';
+    root.appendChild(banner);
+    if (rect) {
+        const box = document.createElement('div');
+        box.style.cssText = `position:absolute;left:${rect[0]}px;top:${rect[1]}px;` +
+            `width:${rect[2]}px;height:${rect[3]}px;outline:3px solid red;outline-offset:1px`;
+        root.appendChild(box);
+    }
+    (document.body || document.documentElement).appendChild(root);
+    return true;
+})
+"#;
+
+/// Removes what [`ANNOTATE_JS`] drew
+const REMOVE_ANNOTATION_JS: &str =
+    "document.getElementById('__browsing_error_annotation')?.remove()";
+
+/// Whether an action result counts as a failure
+pub fn is_failure(result: &ActionResult) -> bool {
+    result.error.is_some() || result.success == Some(false)
+}
+
+/// File name of the screenshot of `action_type` failing at `step`, e.g. `step-003-click-12.png`
+pub fn screenshot_file_name(step: u32, action_type: &str, index: Option<u32>) -> String {
+    let action: String = action_type
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    match index {
+        Some(index) => format!("step-{step:03}-{action}-{index}.png"),
+        None => format!("step-{step:03}-{action}.png"),
+    }
+}
+
+/// Takes at most one error screenshot per step into a directory
+#[derive(Debug)]
+pub struct ErrorScreenshots {
+    dir: PathBuf,
+    last_step: Option<u32>,
+}
+
+impl ErrorScreenshots {
+    /// Save screenshots into `dir`, created on the first capture
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            last_step: None,
+        }
+    }
+
+    /// Directory screenshots are saved into
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Capture the page after `action` failed with `error` at `step`
+    ///
+    /// Returns the screenshot path, or `None` if this step already has a
+    /// screenshot or capturing failed. Failures are logged, not returned: the
+    /// screenshot is a diagnostic and must not mask the action's own error.
+    pub async fn capture(
+        &mut self,
+        browser: &dyn BrowserClient,
+        step: u32,
+        action: &ActionModel,
+        error: &str,
+        selector_map: Option<&HashMap<u32, DOMInteractedElement>>,
+    ) -> Option<String> {
+        if self.last_step == Some(step) {
+            return None;
+        }
+        self.last_step = Some(step);
+
+        if let Err(e) = tokio::fs::create_dir_all(&self.dir).await {
+            warn!("Failed to create {}: {}", self.dir.display(), e);
+            return None;
+        }
+        let index = action
+            .params
+            .get("index")
+            .and_then(|v| v.as_u64())
+            .map(|index| index as u32);
+        let path = self
+            .dir
+            .join(screenshot_file_name(step, &action.action_type, index));
+
+        let annotated = annotate(browser, step, action, index, error, selector_map).await;
+        let taken = browser.screenshot_to_file(&path, false).await;
+        if annotated && let Ok(page) = browser.get_page() {
+            let _ = page.evaluate(REMOVE_ANNOTATION_JS).await;
+        }
+        match taken {
+            Ok(_) => {
+                let path = path.to_string_lossy().into_owned();
+                info!("📸 Saved error screenshot to {}", path);
+                Some(path)
+            }
+            Err(e) => {
+                warn!("Failed to take error screenshot: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// Draw the failing action onto the page; returns whether anything was drawn
+async fn annotate(
+    browser: &dyn BrowserClient,
+    step: u32,
+    action: &ActionModel,
+    index: Option<u32>,
+    error: &str,
+    selector_map: Option<&HashMap<u32, DOMInteractedElement>>,
+) -> bool {
+    let Ok(page) = browser.get_page() else {
+        return false;
+    };
+    let target = match index {
+        Some(index) => format!(" [{index}]"),
+        None => String::new(),
+    };
+    let error: String = error.chars().take(200).collect();
+    let label = format!(
+        "Step {step}: {}{target} failed: {error}",
+        action.action_type
+    );
+
+    let element = index
+        .and_then(|index| selector_map?.get(&index))
+        .and_then(|element| element.backend_node_id);
+    let rect = match element {
+        Some(backend_node_id) => page
+            .get_element(backend_node_id)
+            .await
+            .get_bounding_box()
+            .await
+            .ok()
+            .flatten()
+            .map(|(x, y, width, height)| json!([x, y, width, height])),
+        None => None,
+    };
+
+    let args = json!({ "label": label, "rect": rect });
+    let script = format!("{}({})", ANNOTATE_JS.trim(), args);
+    page.evaluate(&script).await.is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_screenshot_file_name() {
+        assert_eq!(
+            screenshot_file_name(3, "click", Some(12)),
+            "step-003-click-12.png"
+        );
+        assert_eq!(
+            screenshot_file_name(41, "go/back", None),
+            "step-041-go_back.png"
+        );
+    }
+
+    #[test]
+    fn test_is_failure() {
+        assert!(!is_failure(&ActionResult::default()));
+        assert!(is_failure(&ActionResult {
+            error: Some("Element 4 is outside the viewport".to_string()),
+            ..Default::default()
+        }));
+        assert!(is_failure(&ActionResult {
+            success: Some(false),
+            ..Default::default()
+        }));
+    }
+}
@@ -0,0 +1,149 @@
+//! Asking a human for help during a run
+//!
+//! Some steps need a person: a 2FA code sent to their phone, or a choice the
+//! task leaves open. The model asks with the `ask_human` action, which waits
+//! for an answer given through an [`AgentHandle`] from
+//! [`Agent::handle`](crate::agent::Agent::handle). The question is sent as a
+//! [`StepEvent::HumanInputRequested`](crate::agent::StepEvent::HumanInputRequested)
+//! to [`Agent::run_stream`](crate::agent::Agent::run_stream) consumers and can be
+//! awaited with [`AgentHandle::next_question`]. The answer is shown to the
+//! model in the next step.
+//!
+//! Without a handle nobody can answer, so the action returns at once. With one,
+//! it gives up after the settings' `human_input_timeout`; either way the model
+//! is told there was no answer, so unattended runs carry on.
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Notify, oneshot};
+
+/// Name of the action asking a human
+pub const ASK_HUMAN_ACTION: &str = "ask_human";
+
+/// Default time to wait for an answer, in seconds
+pub const DEFAULT_HUMAN_INPUT_TIMEOUT_SECS: u32 = 300;
+
+/// Metadata key of an `ask_human` result, holding the [`HumanExchange`]
+pub const HUMAN_INPUT_METADATA_KEY: &str = "human_input";
+
+/// A question the model asked
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HumanQuestion {
+    /// Step the question was asked in
+    pub step: u32,
+    /// The question
+    pub question: String,
+    /// Screenshot of the page when the question was asked, if requested
+    pub screenshot_path: Option<String>,
+}
+
+/// A question and its answer, `None` if nobody answered
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HumanExchange {
+    /// The question
+    pub question: String,
+    /// The human's answer
+    pub answer: Option<String>,
+}
+
+impl HumanExchange {
+    /// The reply as shown to the model
+    pub fn reply(&self) -> String {
+        match &self.answer {
+            Some(answer) => answer.clone(),
+            None => "(no answer; continue without it or finish with done)".to_string(),
+        }
+    }
+}
+
+struct Pending {
+    question: HumanQuestion,
+    answer: oneshot::Sender<String>,
+}
+
+#[derive(Default)]
+struct Shared {
+    pending: Mutex<Option<Pending>>,
+    asked: Notify,
+}
+
+/// Answers the questions an agent run asks
+///
+/// Cheap to clone; every clone answers for the same run.
+#[derive(Clone, Default)]
+pub struct AgentHandle {
+    shared: Arc<Shared>,
+}
+
+impl std::fmt::Debug for AgentHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AgentHandle")
+            .field("pending_question", &self.pending_question())
+            .finish()
+    }
+}
+
+impl AgentHandle {
+    /// The question waiting for an answer, if any
+    pub fn pending_question(&self) -> Option<HumanQuestion> {
+        let pending = self.shared.pending.lock().unwrap();
+        pending.as_ref().map(|pending| pending.question.clone())
+    }
+
+    /// Wait until the run asks a question, returning it
+    ///
+    /// Returns at once if a question is already waiting.
+    pub async fn next_question(&self) -> HumanQuestion {
+        loop {
+            let asked = self.shared.asked.notified();
+            if let Some(question) = self.pending_question() {
+                return question;
+            }
+            asked.await;
+        }
+    }
+
+    /// Answer the waiting question; `false` if no question was waiting
+    pub fn provide_human_input(&self, answer: impl Into<String>) -> bool {
+        let pending = self.shared.pending.lock().unwrap().take();
+        match pending {
+            Some(pending) => pending.answer.send(answer.into()).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Ask `question`, returning a receiver for the answer
+    pub(crate) fn ask(&self, question: HumanQuestion) -> oneshot::Receiver<String> {
+        let (answer, receiver) = oneshot::channel();
+        *self.shared.pending.lock().unwrap() = Some(Pending { question, answer });
+        self.shared.asked.notify_waiters();
+        receiver
+    }
+
+    /// Drop the waiting question after the run stopped waiting for it
+    pub(crate) fn withdraw(&self) {
+        self.shared.pending.lock().unwrap().take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_answer_reaches_the_asker() {
+        let handle = AgentHandle::default();
+        assert!(!handle.provide_human_input("too early"));
+
+        let question = HumanQuestion {
+            step: 2,
+            question: "What is the code?".to_string(),
+            screenshot_path: None,
+        };
+        let answer = handle.ask(question.clone());
+        assert_eq!(handle.clone().next_question().await, question);
+        assert!(handle.provide_human_input("123456"));
+        assert_eq!(answer.await.unwrap(), "123456");
+        assert_eq!(handle.pending_question(), None);
+    }
+}
@@ -0,0 +1,620 @@
+//! Agent memory
+//!
+//! Three stores with different lifetimes:
+//!
+//! - **short-term**: `extracted_content` and `long_term_memory` of the last few
+//!   results, always shown in full in the next prompt and then dropped. Results
+//!   are never stored for longer on their own.
+//! - **long-term**: entries added explicitly with
+//!   [`AgentMemory::add_long_term`], shown as a timestamped summary.
+//!   Consecutive repeats are counted instead of stored again, and the oldest
+//!   entries are evicted once the summary grows too long, except for important
+//!   ones.
+//! - **working memory**: facts the model decides are worth keeping, stored and
+//!   read with the `remember` and `recall` actions (`set_memory` and
+//!   `get_memory` are kept as older names). Unlike long-term memory it is never
+//!   evicted, and `recall` can search it by keywords.
+
+use crate::agent::prompts::{ENGLISH_LABELS, PromptLabels};
+use crate::agent::views::ActionResult;
+use crate::error::{BrowsingError, Result};
+use crate::tools::views::{ActionModel, ActionParams};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Number of recent results kept in short-term memory
+pub const SHORT_TERM_CAPACITY: usize = 5;
+
+/// Characters of long-term memory kept before the oldest entries are evicted
+pub const LONG_TERM_MAX_CHARS: usize = 4000;
+
+/// Actions served from working memory instead of the browser
+pub const MEMORY_ACTIONS: &[&str] = &["remember", "recall", "set_memory", "get_memory"];
+
+/// A long-term memory entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryEntry {
+    /// Step that produced the entry (the latest step, for repeated entries)
+    pub step: u32,
+    /// When the entry was last recorded
+    pub timestamp: DateTime<Utc>,
+    /// Memory content
+    pub content: String,
+    /// Number of consecutive times the entry was recorded
+    #[serde(default = "default_count")]
+    pub count: u32,
+    /// Whether the entry is kept when memory is over its limit
+    #[serde(default)]
+    pub important: bool,
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+impl MemoryEntry {
+    /// Content with the repeat count, e.g. `Scrolled down 1 pages (x7)`
+    pub fn display_content(&self) -> String {
+        if self.count > 1 {
+            format!("{} (x{})", self.content, self.count)
+        } else {
+            self.content.clone()
+        }
+    }
+
+    /// Summary line, as shown in the prompt
+    pub fn summary_line(&self) -> String {
+        format!(
+            "[{}] step {}: {}",
+            self.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            self.step,
+            self.display_content()
+        )
+    }
+}
+
+/// Short-term, long-term and working memory of an agent
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentMemory {
+    /// Content and memory of the most recent results, oldest first
+    pub short_term: VecDeque<String>,
+    /// Entries added with [`AgentMemory::add_long_term`], oldest first
+    pub long_term: Vec<MemoryEntry>,
+    /// Facts stored by the `remember` action
+    pub working_memory: HashMap<String, String>,
+}
+
+impl AgentMemory {
+    /// Create an empty memory
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the results of a step in short-term memory
+    ///
+    /// Each result's `extracted_content`, and its `long_term_memory` if it is
+    /// `include_in_memory`, is shown in the next prompts until newer results
+    /// push it out. Nothing is kept in long-term memory; the model stores what
+    /// is worth keeping with `remember`.
+    pub fn record_results(&mut self, results: &[ActionResult]) {
+        for result in results {
+            let content = result
+                .extracted_content
+                .as_deref()
+                .filter(|c| !c.is_empty());
+            if let Some(content) = content {
+                self.push_short_term(content.to_string());
+            }
+            // `debug_info` is for the history and logs, never for the prompt
+            if let Some(memory) = result
+                .long_term_memory
+                .as_deref()
+                .filter(|m| result.include_in_memory && !m.is_empty() && Some(*m) != content)
+            {
+                self.push_short_term(memory.to_string());
+            }
+        }
+    }
+
+    fn push_short_term(&mut self, content: String) {
+        if self.short_term.len() == SHORT_TERM_CAPACITY {
+            self.short_term.pop_front();
+        }
+        self.short_term.push_back(content);
+    }
+
+    /// Add an entry to long-term memory
+    ///
+    /// A repeat of the last entry is counted instead of stored again.
+    /// `important` entries are never evicted. Returns the entries evicted to
+    /// stay within [`LONG_TERM_MAX_CHARS`], oldest first.
+    pub fn add_long_term(&mut self, step: u32, content: &str, important: bool) -> Vec<MemoryEntry> {
+        if let Some(last) = self.long_term.last_mut()
+            && last.content == content
+        {
+            last.count += 1;
+            last.step = step;
+            last.timestamp = Utc::now();
+            last.important |= important;
+        } else {
+            self.long_term.push(MemoryEntry {
+                step,
+                timestamp: Utc::now(),
+                content: content.to_string(),
+                count: 1,
+                important,
+            });
+        }
+        self.evict_long_term()
+    }
+
+    /// Characters of the long-term summary
+    pub fn long_term_chars(&self) -> usize {
+        self.long_term
+            .iter()
+            .map(|e| e.summary_line().chars().count() + 1)
+            .sum()
+    }
+
+    /// Drop the oldest unimportant entries until the summary fits
+    fn evict_long_term(&mut self) -> Vec<MemoryEntry> {
+        let mut evicted = Vec::new();
+        let mut chars = self.long_term_chars();
+        while chars > LONG_TERM_MAX_CHARS {
+            let Some(oldest) = self.long_term.iter().position(|e| !e.important) else {
+                break;
+            };
+            let entry = self.long_term.remove(oldest);
+            chars -= entry.summary_line().chars().count() + 1;
+            evicted.push(entry);
+        }
+        evicted
+    }
+
+    /// Long-term memory as one line per entry, with timestamps
+    pub fn summarize_long_term(&self) -> String {
+        self.long_term
+            .iter()
+            .map(MemoryEntry::summary_line)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Store a working memory value, returning the previous one
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<String>) -> Option<String> {
+        self.working_memory.insert(key.into(), value.into())
+    }
+
+    /// Read a working memory value
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.working_memory.get(key).map(String::as_str)
+    }
+
+    /// Search working memory by keywords
+    ///
+    /// Each entry is scored by the fraction of query words that appear in its
+    /// key or value, ignoring case and punctuation. Returns `(key, value, score)`
+    /// for entries with a positive score, best first.
+    pub fn search(&self, query: &str) -> Vec<(String, String, f64)> {
+        let query = tokens(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut matches: Vec<(String, String, f64)> = self
+            .working_memory
+            .iter()
+            .filter_map(|(key, value)| {
+                let words: HashSet<String> = tokens(key).into_iter().chain(tokens(value)).collect();
+                let overlap = query.iter().filter(|w| words.contains(*w)).count();
+                (overlap > 0).then(|| {
+                    (
+                        key.clone(),
+                        value.clone(),
+                        overlap as f64 / query.len() as f64,
+                    )
+                })
+            })
+            .collect();
+        matches.sort_by(|a, b| b.2.total_cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+        matches
+    }
+
+    /// Memory sections for the prompt; empty if there is nothing to show
+    pub fn prompt_section(&self) -> String {
+        self.prompt_section_with(&ENGLISH_LABELS)
+    }
+
+    /// Memory sections for the prompt, headed with `labels`
+    pub fn prompt_section_with(&self, labels: &PromptLabels) -> String {
+        let mut section = String::new();
+        if !self.long_term.is_empty() {
+            let summary = self.summarize_long_term();
+            section.push_str(&format!("{}:\n{}\n\n", labels.memory, summary));
+        }
+        if !self.short_term.is_empty() {
+            let recent: Vec<&str> = self.short_term.iter().map(String::as_str).collect();
+            let recent = recent.join("\n- ");
+            section.push_str(&format!("{}:\n- {}\n\n", labels.recent_results, recent));
+        }
+        if !self.working_memory.is_empty() {
+            let mut entries: Vec<_> = self.working_memory.iter().collect();
+            entries.sort();
+            let lines: Vec<String> = entries.iter().map(|(k, v)| format!("{k}: {v}")).collect();
+            let lines = lines.join("\n- ");
+            section.push_str(&format!("{}:\n- {}\n\n", labels.working_memory, lines));
+        }
+        section
+    }
+
+    /// Whether an action is served by [`AgentMemory::handle_action`]
+    pub fn handles(action_type: &str) -> bool {
+        MEMORY_ACTIONS.contains(&action_type)
+    }
+
+    /// Execute a `remember` or `recall` action (or `set_memory`/`get_memory`)
+    pub fn handle_action(&mut self, action: &ActionModel) -> Result<ActionResult> {
+        let content = match action.action_type.as_str() {
+            "remember" | "set_memory" => {
+                let (key, value) = fact(action)?;
+                let content = format!("Stored '{key}' in working memory");
+                self.set(key, value);
+                content
+            }
+            "recall" | "get_memory" => {
+                let key = action.params.get("key").and_then(|v| v.as_str());
+                let query = action.params.get("query").and_then(|v| v.as_str());
+                match (key, query) {
+                    (Some(key), _) => match self.get(key) {
+                        Some(value) => format!("{key}: {value}"),
+                        None => format!("No value stored for '{key}'"),
+                    },
+                    (None, Some(query)) => {
+                        let matches = self.search(query);
+                        if matches.is_empty() {
+                            format!("Nothing remembered matches '{query}'")
+                        } else {
+                            let lines: Vec<String> = matches
+                                .iter()
+                                .map(|(k, v, score)| format!("{k}: {v} (relevance {score:.2})"))
+                                .collect();
+                            format!("Remembered facts matching '{query}':\n- {}", lines.join("\n- "))
+                        }
+                    }
+                    (None, None) => {
+                        let mut keys: Vec<&str> =
+                            self.working_memory.keys().map(String::as_str).collect();
+                        keys.sort();
+                        if keys.is_empty() {
+                            "Working memory is empty".to_string()
+                        } else {
+                            format!("Working memory keys: {}", keys.join(", "))
+                        }
+                    }
+                }
+            }
+            other => return Err(BrowsingError::Tool(format!("Not a memory action: {other}"))),
+        };
+        Ok(ActionResult {
+            extracted_content: Some(content),
+            ..Default::default()
+        })
+    }
+}
+
+/// Key and value of a `remember` action, with non-string values as JSON
+pub(crate) fn fact(action: &ActionModel) -> Result<(String, String)> {
+    let key = ActionParams::new(&action.params).get_required_str("key")?.to_string();
+    let value = match action.params.get("value") {
+        Some(serde_json::Value::String(value)) => value.clone(),
+        Some(value) if !value.is_null() => value.to_string(),
+        _ => return Err(BrowsingError::Tool("Missing 'value' parameter".to_string())),
+    };
+    Ok((key, value))
+}
+
+/// Lowercase words of `text`, split on anything that is not alphanumeric
+fn tokens(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn result(content: &str, memory: Option<&str>) -> ActionResult {
+        ActionResult {
+            extracted_content: Some(content.to_string()),
+            long_term_memory: memory.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn action(action_type: &str, params: serde_json::Value) -> ActionModel {
+        serde_json::from_value(json!({"action_type": action_type, "params": params})).unwrap()
+    }
+
+    #[test]
+    fn test_short_term_keeps_last_five() {
+        let mut memory = AgentMemory::new();
+        for step in 1..=7 {
+            memory.record_results(&[result(&format!("result {step}"), None)]);
+        }
+
+        assert_eq!(memory.short_term.len(), SHORT_TERM_CAPACITY);
+        assert_eq!(memory.short_term.front().unwrap(), "result 3");
+        assert_eq!(memory.short_term.back().unwrap(), "result 7");
+        assert!(memory.long_term.is_empty());
+    }
+
+    #[test]
+    fn test_results_are_not_stored_in_long_term() {
+        let mut memory = AgentMemory::new();
+        memory.record_results(&[result(
+            "Full page text that is long and only needed once",
+            Some("Extracted 3 prices"),
+        )]);
+
+        assert!(memory.long_term.is_empty());
+        assert_eq!(
+            memory.short_term,
+            [
+                "Full page text that is long and only needed once",
+                "Extracted 3 prices"
+            ]
+        );
+
+        // Pushed out by later results
+        for step in 1..=5 {
+            memory.record_results(&[result(&format!("result {step}"), None)]);
+        }
+        assert!(!memory.prompt_section().contains("Extracted 3 prices"));
+    }
+
+    #[test]
+    fn test_long_term_entries_have_timestamps() {
+        let mut memory = AgentMemory::new();
+        memory.add_long_term(1, "Extracted 3 prices", false);
+
+        assert_eq!(memory.long_term.len(), 1);
+        assert_eq!(memory.long_term[0].content, "Extracted 3 prices");
+        assert_eq!(memory.long_term[0].step, 1);
+
+        let summary = memory.summarize_long_term();
+        assert!(summary.ends_with("] step 1: Extracted 3 prices"));
+        assert!(summary.starts_with('['));
+    }
+
+    #[test]
+    fn test_consecutive_repeats_are_counted() {
+        let mut memory = AgentMemory::new();
+        for step in 1..=7 {
+            memory.add_long_term(step, "Scrolled down 1 pages", false);
+        }
+        memory.add_long_term(8, "Clicked element 4", false);
+        memory.add_long_term(9, "Scrolled down 1 pages", false);
+
+        assert_eq!(memory.long_term.len(), 3);
+        assert_eq!(memory.long_term[0].count, 7);
+        assert_eq!(memory.long_term[0].step, 7);
+        assert_eq!(
+            memory.long_term[0].display_content(),
+            "Scrolled down 1 pages (x7)"
+        );
+        // Not consecutive, so recorded again
+        assert_eq!(memory.long_term[2].count, 1);
+        assert!(memory.summarize_long_term().contains("step 7: Scrolled down 1 pages (x7)"));
+    }
+
+    /// 200 entries of a long session: scrolling runs, clicks, navigations,
+    /// extractions and the odd error, the last three kept as important
+    fn synthetic_stream(memory: &mut AgentMemory) -> Vec<MemoryEntry> {
+        let mut evicted = Vec::new();
+        for step in 1..=200u32 {
+            let (content, important) = match step {
+                s if s % 50 == 0 => (format!("Navigated to https://shop.example/page/{s}"), true),
+                s if s % 37 == 0 => (format!("Extracted 12 prices from listing {s}"), true),
+                s if s % 23 == 0 => (format!("Could not click element {s}"), true),
+                s if s % 10 < 6 => ("Scrolled down 1 pages".to_string(), false),
+                s => (format!("Clicked element {s} on the product grid to open the details panel"), false),
+            };
+            evicted.extend(memory.add_long_term(step, &content, important));
+        }
+        evicted
+    }
+
+    #[test]
+    fn test_long_term_is_bounded() {
+        let mut memory = AgentMemory::new();
+        let evicted = synthetic_stream(&mut memory);
+
+        assert!(memory.long_term_chars() <= LONG_TERM_MAX_CHARS);
+        assert!(!evicted.is_empty());
+        // Evicted oldest first, and only unimportant entries
+        assert!(evicted.windows(2).all(|w| w[0].step <= w[1].step));
+        assert!(evicted.iter().all(|e| !e.important));
+        // Scroll runs were collapsed rather than stored 100+ times
+        let scrolls = memory
+            .long_term
+            .iter()
+            .chain(&evicted)
+            .filter(|e| e.content == "Scrolled down 1 pages")
+            .collect::<Vec<_>>();
+        let scrolled: u32 = scrolls.iter().map(|e| e.count).sum();
+        assert!(scrolled >= 100, "{scrolled}");
+        assert!(scrolls.len() <= 25, "{}", scrolls.len());
+        // The most recent entry survives
+        assert_eq!(memory.long_term.last().unwrap().step, 200);
+    }
+
+    #[test]
+    fn test_important_entries_are_never_evicted() {
+        let mut memory = AgentMemory::new();
+        synthetic_stream(&mut memory);
+
+        let kept = |needle: &str| {
+            memory
+                .long_term
+                .iter()
+                .filter(|e| e.content.starts_with(needle))
+                .count()
+        };
+        assert_eq!(kept("Navigated to"), 4);
+        assert_eq!(kept("Extracted 12 prices"), 5);
+        assert_eq!(kept("Could not click"), 8);
+        assert!(
+            memory
+                .long_term
+                .iter()
+                .filter(|e| e.important)
+                .all(|e| !e.content.starts_with("Scrolled"))
+        );
+    }
+
+    #[test]
+    fn test_important_entries_may_exceed_limit() {
+        let mut memory = AgentMemory::new();
+        let long = "x".repeat(LONG_TERM_MAX_CHARS);
+        let evicted = memory.add_long_term(1, &long, true);
+
+        assert!(evicted.is_empty());
+        assert_eq!(memory.long_term.len(), 1);
+        assert!(memory.long_term_chars() > LONG_TERM_MAX_CHARS);
+
+        // The next unimportant entry is dropped straight away
+        let evicted = memory.add_long_term(2, "Scrolled down 1 pages", false);
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(memory.long_term.len(), 1);
+    }
+
+    #[test]
+    fn test_set_and_get_memory_actions() {
+        let mut memory = AgentMemory::new();
+        memory
+            .handle_action(&action(
+                "set_memory",
+                json!({"key": "order_id", "value": "A-1042"}),
+            ))
+            .unwrap();
+        memory
+            .handle_action(&action(
+                "set_memory",
+                json!({"key": "total", "value": 42.5}),
+            ))
+            .unwrap();
+        assert_eq!(memory.get("order_id"), Some("A-1042"));
+        assert_eq!(memory.get("total"), Some("42.5"));
+
+        let found = memory
+            .handle_action(&action("get_memory", json!({"key": "order_id"})))
+            .unwrap();
+        assert_eq!(found.extracted_content.as_deref(), Some("order_id: A-1042"));
+
+        let missing = memory
+            .handle_action(&action("get_memory", json!({"key": "coupon"})))
+            .unwrap();
+        assert_eq!(
+            missing.extracted_content.as_deref(),
+            Some("No value stored for 'coupon'")
+        );
+
+        let keys = memory
+            .handle_action(&action("get_memory", json!({})))
+            .unwrap();
+        assert_eq!(
+            keys.extracted_content.as_deref(),
+            Some("Working memory keys: order_id, total")
+        );
+
+        assert!(
+            memory
+                .handle_action(&action("set_memory", json!({"key": "x"})))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_search_scores_by_word_overlap() {
+        let mut memory = AgentMemory::new();
+        memory.set("order_id", "A-1042");
+        memory.set("shipping", "Express delivery to Berlin");
+        memory.set("billing_address", "Hauptstr. 5, Berlin");
+
+        let matches = memory.search("Berlin delivery");
+        assert_eq!(
+            matches,
+            vec![
+                (
+                    "shipping".to_string(),
+                    "Express delivery to Berlin".to_string(),
+                    1.0
+                ),
+                (
+                    "billing_address".to_string(),
+                    "Hauptstr. 5, Berlin".to_string(),
+                    0.5
+                ),
+            ]
+        );
+        // Keys are searched too, split on punctuation
+        assert_eq!(memory.search("ORDER")[0].0, "order_id");
+        assert!(memory.search("coupon").is_empty());
+        assert!(memory.search("  ").is_empty());
+    }
+
+    #[test]
+    fn test_remember_and_recall_actions() {
+        let mut memory = AgentMemory::new();
+        memory
+            .handle_action(&action(
+                "remember",
+                json!({"key": "cheapest", "value": "Acme kettle at $19"}),
+            ))
+            .unwrap();
+        memory.set("store", "shop.example");
+
+        let exact = memory
+            .handle_action(&action("recall", json!({"key": "cheapest"})))
+            .unwrap();
+        assert_eq!(
+            exact.extracted_content.as_deref(),
+            Some("cheapest: Acme kettle at $19")
+        );
+
+        let searched = memory
+            .handle_action(&action("recall", json!({"query": "kettle price"})))
+            .unwrap();
+        assert_eq!(
+            searched.extracted_content.as_deref(),
+            Some("Remembered facts matching 'kettle price':\n- cheapest: Acme kettle at $19 (relevance 0.50)")
+        );
+
+        let none = memory
+            .handle_action(&action("recall", json!({"query": "coupon"})))
+            .unwrap();
+        assert_eq!(
+            none.extracted_content.as_deref(),
+            Some("Nothing remembered matches 'coupon'")
+        );
+    }
+
+    #[test]
+    fn test_prompt_section() {
+        let mut memory = AgentMemory::new();
+        assert!(memory.prompt_section().is_empty());
+
+        memory.record_results(&[result("Clicked button", Some("Opened the cart"))]);
+        memory.add_long_term(1, "Cart has 2 items", false);
+        memory.set("coupon", "SAVE10");
+
+        let section = memory.prompt_section();
+        assert!(section.contains("Memory:\n["));
+        assert!(section.contains("step 1: Cart has 2 items"));
+        assert!(section.contains("Recent results:\n- Clicked button\n- Opened the cart"));
+        assert!(section.contains("Working memory:\n- coupon: SAVE10"));
+    }
+}
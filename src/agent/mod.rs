@@ -1,7 +1,38 @@
 //! Agent service for autonomous web automation
 
-mod json_extractor;
+pub mod assertions;
+pub mod credentials;
+pub mod determinism;
+pub mod error_screenshot;
+pub mod human;
+pub(crate) mod json_extractor;
+pub mod memory;
+pub mod output_schema;
+pub mod page_group;
+pub mod prompts;
+pub mod report;
+pub mod resume;
+pub mod run_id;
+pub mod sanitize;
 pub mod service;
+pub mod site_memory;
+pub mod stream;
+pub mod tab_hygiene;
+pub mod timeouts;
 pub mod views;
 
+pub use assertions::{AssertionOutcome, AssertionRecord, AssertionSummary};
+pub use credentials::{
+    Credentials, CredentialsProvider, EnvCredentialsProvider, SecretString,
+};
+pub use determinism::Determinism;
+pub use human::{AgentHandle, HumanExchange, HumanQuestion};
+pub use memory::AgentMemory;
+pub use page_group::{PageGroup, TabState};
+pub use report::{ReportFormat, ReportRecord};
+pub use resume::{SavedAgentState, UsageTotals};
+pub use run_id::RunIdHint;
 pub use service::Agent;
+pub use site_memory::SiteMemory;
+pub use stream::{StepEvent, StepUpdate};
+pub use timeouts::StopReason;
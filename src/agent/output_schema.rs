@@ -0,0 +1,173 @@
+//! The JSON schema of the agent's model output
+//!
+//! [`AgentOutput`] derives its schema, so the output format in the system
+//! prompt and the checks on the model's replies come from the same type and
+//! cannot drift apart. A reply that does not match is not an error for the run:
+//! the problems found are sent back to the model in the next step, see
+//! [`feedback`].
+//!
+//! Histories record the output format version in
+//! [`AgentHistoryList::output_version`](crate::agent::views::AgentHistoryList::output_version).
+//! Histories saved before it existed have version 0 and may hold the model
+//! output as a JSON string; [`deserialize_model_output`] reads both forms.
+
+use crate::agent::views::AgentOutput;
+use schemars::generate::SchemaSettings;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+/// Version of the model output format recorded in new histories
+pub const AGENT_OUTPUT_VERSION: u32 = 1;
+
+impl AgentOutput {
+    /// JSON schema of the output, with subschemas inlined
+    pub fn json_schema() -> Value {
+        let mut schema = SchemaSettings::draft2020_12()
+            .with(|settings| {
+                settings.inline_subschemas = true;
+                settings.meta_schema = None;
+            })
+            .into_generator()
+            .into_root_schema_for::<AgentOutput>()
+            .to_value();
+        if let Some(root) = schema.as_object_mut() {
+            root.remove("title");
+            root.remove("description");
+        }
+        schema
+    }
+
+    /// Check a parsed reply against [`AgentOutput::json_schema`]
+    ///
+    /// On failure, returns one line per problem, naming the field at fault,
+    /// e.g. ``missing field `action[0].action_type` ``.
+    pub fn validate(value: &Value) -> Result<Self, Vec<String>> {
+        let mut problems = Vec::new();
+        check(&Self::json_schema(), value, "", &mut problems);
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        serde_json::from_value(value.clone()).map_err(|e| vec![e.to_string()])
+    }
+}
+
+/// Schema shown in the output format section, compact
+///
+/// Without `thinking` the field is left out, for models asked not to think
+/// aloud; replies with it are still accepted.
+pub fn prompt_schema(thinking: bool) -> String {
+    let mut schema = AgentOutput::json_schema();
+    if !thinking && let Some(properties) = schema["properties"].as_object_mut() {
+        properties.remove("thinking");
+    }
+    schema.to_string()
+}
+
+/// Message telling the model what was wrong with its last reply
+pub fn feedback(problems: &[String]) -> String {
+    format!(
+        "Your last reply was not valid output: {}. Reply with a single JSON object matching \
+         the schema in the output format.",
+        problems.join("; ")
+    )
+}
+
+/// Reads [`AgentHistory::model_output`](crate::agent::views::AgentHistory::model_output)
+/// as an object, or as the JSON string older histories stored
+pub fn deserialize_model_output<'de, D>(deserializer: D) -> Result<Option<AgentOutput>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<Value>::deserialize(deserializer)? {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(json)) => AgentOutput::from_json(&json)
+            .map(Some)
+            .map_err(D::Error::custom),
+        Some(value) => serde_json::from_value(value)
+            .map(Some)
+            .map_err(D::Error::custom),
+    }
+}
+
+/// Collect where `value` breaks `schema`: types, required and unknown fields
+/// of objects, and array items
+fn check(schema: &Value, value: &Value, path: &str, problems: &mut Vec<String>) {
+    let types: Vec<&str> = match &schema["type"] {
+        Value::String(name) => vec![name.as_str()],
+        Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if !types.is_empty() && !types.iter().any(|name| is_type(value, name)) {
+        let subject = if path.is_empty() {
+            "the reply".to_string()
+        } else {
+            format!("`{path}`")
+        };
+        problems.push(format!(
+            "{subject} should be {}, got {}",
+            types.join(" or "),
+            type_name(value)
+        ));
+        return;
+    }
+
+    match value {
+        Value::Object(fields) => {
+            let properties = schema["properties"].as_object();
+            for name in schema["required"].as_array().into_iter().flatten() {
+                if let Some(name) = name.as_str()
+                    && !fields.contains_key(name)
+                {
+                    problems.push(format!("missing field `{}`", field_path(path, name)));
+                }
+            }
+            for (name, field) in fields {
+                match properties.and_then(|properties| properties.get(name)) {
+                    Some(field_schema) => {
+                        check(field_schema, field, &field_path(path, name), problems)
+                    }
+                    None if schema["additionalProperties"] == Value::Bool(false) => {
+                        problems.push(format!("unexpected field `{}`", field_path(path, name)))
+                    }
+                    None => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    check(item_schema, item, &format!("{path}[{index}]"), problems);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn field_path(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{path}.{name}")
+    }
+}
+
+fn is_type(value: &Value, name: &str) -> bool {
+    match name {
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        _ => type_name(value) == name,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
@@ -0,0 +1,223 @@
+//! Per-tab state for agents working across several tabs
+//!
+//! The model only sees the page state of the current tab. [`PageGroup`]
+//! remembers what every open tab held when it was last current, so each step's
+//! prompt can give an overview of all of them.
+
+use crate::browser::views::TabInfo;
+use crate::dom::views::DOMInteractedElement;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Metadata key set by the `switch` action with the target ID switched to
+pub const SWITCHED_TAB_METADATA_KEY: &str = "switched_tab";
+
+/// What the agent knows about one tab
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TabState {
+    /// The tab as the browser last listed it
+    pub tab: TabInfo,
+    /// Page state of the tab when it was last current, `None` if it has not
+    /// been current since it loaded its URL
+    pub dom_state: Option<String>,
+    /// Selector map of the tab when it was last current
+    pub selector_map: HashMap<u32, DOMInteractedElement>,
+}
+
+impl TabState {
+    /// Number of interactive elements, if the tab has been seen on its URL
+    pub fn interactive_count(&self) -> Option<usize> {
+        self.dom_state.as_ref().map(|_| self.selector_map.len())
+    }
+}
+
+/// State of the open tabs, by target ID
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PageGroup {
+    /// State of each open tab, by target ID
+    pub tabs: HashMap<String, TabState>,
+    /// Target IDs in the browser's tab order
+    order: Vec<String>,
+    /// Target ID of the current tab
+    current: Option<String>,
+}
+
+impl PageGroup {
+    /// Create an empty group
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match the group to the browser's open tabs
+    ///
+    /// Closed tabs are dropped. A tab that navigated since it was last current
+    /// forgets its page state.
+    pub fn sync_tabs(&mut self, tabs: &[TabInfo]) {
+        let mut previous = std::mem::take(&mut self.tabs);
+        self.order = tabs.iter().map(|tab| tab.target_id.clone()).collect();
+        for tab in tabs {
+            let mut state = previous.remove(&tab.target_id).unwrap_or_default();
+            if state.tab.url != tab.url {
+                state.dom_state = None;
+                state.selector_map.clear();
+            }
+            state.tab = tab.clone();
+            self.tabs.insert(tab.target_id.clone(), state);
+        }
+        if self
+            .current
+            .as_ref()
+            .is_some_and(|current| !self.tabs.contains_key(current))
+        {
+            self.current = None;
+        }
+    }
+
+    /// Make `target_id` the current tab
+    pub fn set_current(&mut self, target_id: &str) {
+        self.current = Some(target_id.to_string());
+    }
+
+    /// Target ID of the current tab
+    pub fn current(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    /// Record the page state of the current tab, `target_id`
+    pub fn record_current(
+        &mut self,
+        target_id: &str,
+        dom_state: String,
+        selector_map: HashMap<u32, DOMInteractedElement>,
+    ) {
+        self.set_current(target_id);
+        let state = self.tabs.entry(target_id.to_string()).or_insert_with(|| {
+            self.order.push(target_id.to_string());
+            TabState::default()
+        });
+        state.dom_state = Some(dom_state);
+        state.selector_map = selector_map;
+    }
+
+    /// State of a tab
+    pub fn get(&self, target_id: &str) -> Option<&TabState> {
+        self.tabs.get(target_id)
+    }
+
+    /// One line per tab for the model, in tab order
+    ///
+    /// For example:
+    /// `Tab 1 (current): #1a2b Example Domain (https://example.com) - 5 interactive elements`.
+    /// The `#` ID is what the `switch` and `close` actions take as `tab_id`.
+    pub fn get_tab_summary(&self) -> String {
+        let lines: Vec<String> = self
+            .order
+            .iter()
+            .enumerate()
+            .filter_map(|(i, target_id)| {
+                let state = self.tabs.get(target_id)?;
+                let elements = match state.interactive_count() {
+                    Some(count) => format!("{count} interactive elements"),
+                    None => "not viewed yet".to_string(),
+                };
+                let current = if self.current.as_deref() == Some(target_id.as_str()) {
+                    " (current)"
+                } else {
+                    ""
+                };
+                Some(format!(
+                    "Tab {}{current}: {} - {elements}",
+                    i + 1,
+                    state.tab.summary()
+                ))
+            })
+            .collect();
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tab(target_id: &str, url: &str) -> TabInfo {
+        TabInfo {
+            url: url.to_string(),
+            title: String::new(),
+            target_id: target_id.to_string(),
+            parent_target_id: None,
+            target_type: "page".to_string(),
+            is_loading: false,
+            favicon_url: None,
+            opener_tab_id: None,
+            last_active_at: None,
+        }
+    }
+
+    fn elements(count: u32) -> HashMap<u32, DOMInteractedElement> {
+        (1..=count)
+            .map(|index| {
+                let element = DOMInteractedElement {
+                    index,
+                    backend_node_id: Some(index),
+                    tag: "a".to_string(),
+                    text: None,
+                    attributes: HashMap::new(),
+                    selector: None,
+                    bounds: None,
+                    form_id: None,
+                    frame_id: None,
+                };
+                (index, element)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_tab_summary() {
+        let opened = TabInfo {
+            title: "Other".to_string(),
+            opener_tab_id: Some("AAAA0001".to_string()),
+            is_loading: true,
+            ..tab("BBBB0002", "https://other.com")
+        };
+        let extension = TabInfo {
+            target_type: "background_page".to_string(),
+            ..tab("CCCC0003", "chrome-extension://abc/background.html")
+        };
+        let mut group = PageGroup::new();
+        group.sync_tabs(&[
+            TabInfo {
+                title: "Example Domain".to_string(),
+                ..tab("AAAA0001", "https://example.com")
+            },
+            opened,
+            extension,
+        ]);
+        group.record_current("AAAA0001", "a [1]".to_string(), elements(5));
+        group.record_current("BBBB0002", "a [1]".to_string(), elements(12));
+
+        assert_eq!(
+            group.get_tab_summary(),
+            "Tab 1: #0001 Example Domain (https://example.com) - 5 interactive elements\n\
+             Tab 2 (current): #0002 Other (https://other.com) [loading, opened by #0001] - 12 interactive elements\n\
+             Tab 3: #0003 (untitled) (chrome-extension://abc/background.html) [background_page] - not viewed yet"
+        );
+    }
+
+    #[test]
+    fn test_sync_forgets_navigated_and_closed_tabs() {
+        let mut group = PageGroup::new();
+        group.sync_tabs(&[tab("A", "https://example.com"), tab("B", "https://other.com")]);
+        group.record_current("A", "a [1]".to_string(), elements(5));
+        group.record_current("B", "a [1]".to_string(), elements(2));
+
+        group.sync_tabs(&[tab("A", "https://example.com/next")]);
+
+        assert!(group.get("B").is_none());
+        assert_eq!(group.current(), None);
+        let a = group.get("A").unwrap();
+        assert_eq!(a.tab.url, "https://example.com/next");
+        assert_eq!(a.interactive_count(), None);
+    }
+}
@@ -0,0 +1,280 @@
+//! Flat per-step reports of a run, for analytics pipelines
+//!
+//! [`AgentHistoryList::export_report`] writes one record per step followed by
+//! a summary record, as CSV or JSON Lines. Every record has the same columns,
+//! in the order of [`REPORT_COLUMNS`]. In CSV, action types, success flags and
+//! models are joined with `;` and errors with ` | `.
+
+use crate::agent::views::{AgentHistory, AgentHistoryList};
+use crate::error::Result;
+use crate::tokens::views::ModelUsage;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+
+/// Columns of a report, in order
+pub const REPORT_COLUMNS: &[&str] = &[
+    "record_type",
+    "step",
+    "url",
+    "actions",
+    "success",
+    "start_time",
+    "end_time",
+    "duration_seconds",
+    "prompt_tokens",
+    "completion_tokens",
+    "cumulative_cost",
+    "errors",
+    "models",
+];
+
+/// Characters of an error message kept in a report
+const MAX_ERROR_CHARS: usize = 200;
+
+/// File format of a report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    /// Comma-separated values with a header row
+    Csv,
+    /// One JSON object per line
+    JsonLines,
+}
+
+impl ReportFormat {
+    /// File extension for the format
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Csv => "csv",
+            ReportFormat::JsonLines => "jsonl",
+        }
+    }
+}
+
+/// One record of a report: a step, or the summary of the run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportRecord {
+    /// `step` or `summary`
+    pub record_type: String,
+    /// Step number; for the summary, the number of steps
+    pub step: u32,
+    /// URL after the step; for the summary, the final URL
+    pub url: String,
+    /// Types of the actions taken, in order; empty for the summary
+    pub actions: Vec<String>,
+    /// Whether each action succeeded; for the summary, whether the run did
+    pub success: Vec<bool>,
+    /// Start of the step (Unix seconds)
+    pub start_time: Option<f64>,
+    /// End of the step (Unix seconds)
+    pub end_time: Option<f64>,
+    /// Duration of the step; for the summary, of all steps
+    pub duration_seconds: Option<f64>,
+    /// Prompt tokens of the step's model call; for the summary, of the run
+    pub prompt_tokens: Option<u32>,
+    /// Completion tokens of the step's model call; for the summary, of the run
+    pub completion_tokens: Option<u32>,
+    /// Estimated cost of the run up to and including this record
+    pub cumulative_cost: Option<f64>,
+    /// Errors of the step's actions, shortened; for the summary, a count
+    pub errors: Vec<String>,
+    /// Models the step called, as `provider/model`; for the summary, the
+    /// calls, tokens, latency and cost of each model
+    #[serde(default)]
+    pub models: Vec<String>,
+}
+
+impl ReportRecord {
+    fn from_step(item: &AgentHistory, index: usize, cumulative_cost: Option<f64>) -> Self {
+        let metadata = item.metadata.as_ref();
+        let usage = metadata.and_then(|m| m.usage.as_ref());
+        let actions = item
+            .model_output
+            .as_ref()
+            .map(|output| {
+                output
+                    .action
+                    .iter()
+                    .map(|action| action.action_type.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            record_type: "step".to_string(),
+            step: metadata.map_or(index as u32 + 1, |m| m.step_number),
+            url: item.state.url.clone(),
+            actions,
+            success: item
+                .result
+                .iter()
+                .map(|result| result.error.is_none() && result.success != Some(false))
+                .collect(),
+            start_time: metadata.map(|m| m.step_start_time),
+            end_time: metadata.map(|m| m.step_end_time),
+            duration_seconds: metadata.map(|m| m.duration_seconds()),
+            prompt_tokens: usage.map(|u| u.prompt_tokens),
+            completion_tokens: usage.map(|u| u.completion_tokens),
+            cumulative_cost,
+            errors: item
+                .result
+                .iter()
+                .filter_map(|result| result.error.as_deref())
+                .map(shorten_error)
+                .collect(),
+            models: metadata.map(|m| m.models()).unwrap_or_default(),
+        }
+    }
+
+    /// Fields as CSV cells, in the order of [`REPORT_COLUMNS`]
+    fn csv_cells(&self) -> [String; 13] {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        [
+            self.record_type.clone(),
+            self.step.to_string(),
+            self.url.clone(),
+            self.actions.join(";"),
+            self.success
+                .iter()
+                .map(bool::to_string)
+                .collect::<Vec<_>>()
+                .join(";"),
+            optional(self.start_time.map(|v| format!("{v:.3}"))),
+            optional(self.end_time.map(|v| format!("{v:.3}"))),
+            optional(self.duration_seconds.map(|v| format!("{v:.3}"))),
+            optional(self.prompt_tokens.map(|v| v.to_string())),
+            optional(self.completion_tokens.map(|v| v.to_string())),
+            optional(self.cumulative_cost.map(|v| format!("{v:.6}"))),
+            self.errors.join(" | "),
+            self.models.join(";"),
+        ]
+    }
+}
+
+impl AgentHistoryList {
+    /// Records of [`AgentHistoryList::export_report`]: one per step, then the summary
+    pub fn report_records(&self) -> Vec<ReportRecord> {
+        let mut cost = None;
+        let mut records: Vec<ReportRecord> = self
+            .history
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                if let Some(step_cost) = item.metadata.as_ref().and_then(|m| m.total_cost()) {
+                    cost = Some(cost.unwrap_or(0.0) + step_cost);
+                }
+                ReportRecord::from_step(item, index, cost)
+            })
+            .collect();
+
+        let usage = self.usage.as_ref();
+        let step_tokens = |tokens: fn(&ReportRecord) -> Option<u32>| {
+            records.iter().filter_map(tokens).reduce(|a, b| a + b)
+        };
+        let actions: usize = records.iter().map(|r| r.success.len()).sum();
+        let failed = records
+            .iter()
+            .flat_map(|r| &r.success)
+            .filter(|ok| !**ok)
+            .count();
+        let summary = ReportRecord {
+            record_type: "summary".to_string(),
+            step: self.history.len() as u32,
+            url: self
+                .history
+                .last()
+                .map(|item| item.state.url.clone())
+                .unwrap_or_default(),
+            actions: Vec::new(),
+            success: vec![
+                self.is_successful()
+                    .unwrap_or(self.is_done() && !self.has_errors()),
+            ],
+            start_time: records.first().and_then(|r| r.start_time),
+            end_time: records.last().and_then(|r| r.end_time),
+            duration_seconds: Some(self.total_duration_seconds()),
+            prompt_tokens: usage
+                .and_then(|u| u.prompt_tokens)
+                .or_else(|| step_tokens(|r| r.prompt_tokens)),
+            completion_tokens: usage
+                .and_then(|u| u.completion_tokens)
+                .or_else(|| step_tokens(|r| r.completion_tokens)),
+            cumulative_cost: usage.and_then(|u| u.cost).or(cost),
+            errors: match failed {
+                0 => Vec::new(),
+                _ => vec![format!("{failed} of {actions} actions failed")],
+            },
+            models: usage
+                .map(|u| u.by_model.iter().map(ModelUsage::summary).collect())
+                .unwrap_or_default(),
+        };
+        records.push(summary);
+        records
+    }
+
+    /// Write one record per step and a final summary record to `path`
+    ///
+    /// Steps report their URL, action types, per-action success, timing from
+    /// [`StepMetadata`](crate::agent::views::StepMetadata), tokens, the
+    /// cumulative cost (if token prices were set), shortened errors and the
+    /// models called; the summary breaks usage down by model.
+    pub fn export_report(&self, path: impl AsRef<Path>, format: ReportFormat) -> Result<()> {
+        let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let records = self.report_records();
+        match format {
+            ReportFormat::Csv => {
+                writeln!(out, "{}", REPORT_COLUMNS.join(","))?;
+                for record in &records {
+                    let cells: Vec<String> =
+                        record.csv_cells().iter().map(|c| csv_escape(c)).collect();
+                    writeln!(out, "{}", cells.join(","))?;
+                }
+            }
+            ReportFormat::JsonLines => {
+                for record in &records {
+                    serde_json::to_writer(&mut out, record)?;
+                    writeln!(out)?;
+                }
+            }
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Quote a CSV cell if it contains a comma, quote or line break
+fn csv_escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// First line of an error message, truncated to [`MAX_ERROR_CHARS`]
+fn shorten_error(error: &str) -> String {
+    let line = error.lines().next().unwrap_or_default().trim();
+    match line.char_indices().nth(MAX_ERROR_CHARS) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_escape() {
+        assert_eq!(csv_escape("click;input"), "click;input");
+        assert_eq!(csv_escape("a,b"), "\"a,b\"");
+        assert_eq!(csv_escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_shorten_error() {
+        assert_eq!(shorten_error("Timed out\n  at step 3"), "Timed out");
+        let long = "x".repeat(300);
+        assert_eq!(shorten_error(&long).chars().count(), MAX_ERROR_CHARS + 1);
+    }
+}
@@ -0,0 +1,134 @@
+//! Saving an agent mid-task and resuming it in another process
+//!
+//! [`Agent::save_state`](crate::agent::Agent::save_state) writes a
+//! [`SavedAgentState`] as JSON: the task, [`AgentState`], the history so far,
+//! the memory the prompt is built from, token usage totals and the cookies and
+//! storage of the current page. [`Agent::resume`](crate::agent::Agent::resume)
+//! reads it back; on the next run the agent re-navigates to the last page with
+//! its cookies and storage restored and numbers steps on from the saved ones.
+//!
+//! Some things cannot be restored:
+//! - In-page JavaScript state (variables, timers, open sockets, unsaved form
+//!   input): the page is loaded afresh.
+//! - Tabs other than the current one.
+//! - Secrets and sensitive context, which are never written to the file, and
+//!   site memory, credentials providers and human handles. Set them up again
+//!   on the resumed agent.
+//! - Server-side state, which was never the agent's to save.
+
+use crate::actor::PageCheckpoint;
+use crate::agent::human::HumanExchange;
+use crate::agent::memory::AgentMemory;
+use crate::agent::views::{AgentHistoryList, AgentSettings, AgentState};
+use crate::error::{BrowsingError, Result};
+use crate::tokens::views::ModelUsage;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+
+/// Version of the saved state format, bumped on incompatible changes
+pub const SAVED_STATE_VERSION: u32 = 1;
+
+/// Token usage summed over the responses of a run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageTotals {
+    /// Prompt tokens
+    pub prompt_tokens: u32,
+    /// Completion tokens
+    pub completion_tokens: u32,
+    /// Total tokens
+    pub total_tokens: u32,
+    /// Number of responses counted
+    pub responses: u32,
+    /// Estimated cost, if token prices are known
+    pub cost: Option<f64>,
+    /// Usage of each model called
+    #[serde(default)]
+    pub by_model: Vec<ModelUsage>,
+}
+
+/// Everything needed to continue an agent's task in another process
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedAgentState {
+    /// Format version, [`SAVED_STATE_VERSION`] when written
+    pub version: u32,
+    /// Task the agent is working on
+    pub task: String,
+    /// ID of the run, kept by the resumed agent
+    pub run_id: String,
+    /// Step limit, counting the steps already taken
+    pub max_steps: u32,
+    /// Agent settings
+    pub settings: AgentSettings,
+    /// Agent state, including the last results and model output
+    pub state: AgentState,
+    /// History of the steps taken so far
+    pub history: AgentHistoryList,
+    /// Short-term, long-term and working memory shown in the prompt
+    pub memory: AgentMemory,
+    /// Exchanges with the human in the last step, not yet shown to the model
+    #[serde(default)]
+    pub human_replies: Vec<HumanExchange>,
+    /// Token usage so far
+    pub usage: UsageTotals,
+    /// URL, cookies and storage of the current page, if they could be read
+    #[serde(default)]
+    pub page: Option<PageCheckpoint>,
+}
+
+impl SavedAgentState {
+    /// Read a state written by [`SavedAgentState::save`]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let saved: Self = serde_json::from_slice(&std::fs::read(path)?)?;
+        if saved.version != SAVED_STATE_VERSION {
+            return Err(BrowsingError::Agent(format!(
+                "Saved agent state has version {}, expected {SAVED_STATE_VERSION}",
+                saved.version
+            )));
+        }
+        Ok(saved)
+    }
+
+    /// Write the state as JSON
+    ///
+    /// The file holds the page's cookies and storage, so on Unix it is
+    /// readable by the owner only (mode 0600), including when it already
+    /// existed with wider permissions.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self)?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(path)?;
+        #[cfg(unix)]
+        file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+        file.write_all(&json)?;
+        Ok(())
+    }
+
+    /// Number of the last step recorded in the history, 0 if none
+    pub fn steps_taken(&self) -> u32 {
+        self.history
+            .history
+            .iter()
+            .rev()
+            .find_map(|item| item.metadata.as_ref())
+            .map_or(0, |metadata| metadata.step_number)
+    }
+
+    /// Page to return to: the saved page, else the last URL in the history
+    pub fn last_url(&self) -> Option<&str> {
+        self.page
+            .as_ref()
+            .map(|page| page.url.as_str())
+            .or_else(|| {
+                self.history
+                    .history
+                    .iter()
+                    .rev()
+                    .map(|item| item.state.url.as_str())
+                    .find(|url| !url.is_empty())
+            })
+    }
+}
@@ -0,0 +1,90 @@
+//! Per-run identifiers for correlating agent runs with other logs
+//!
+//! Every [`Agent`](crate::agent::Agent) gets a UUID v7 run ID. It is recorded
+//! on the run's tracing span, in the history and in saved artifacts, and can
+//! be sent to the sites the agent visits (see [`RunIdHint`]) so their server
+//! logs can be matched against the run.
+
+use crate::browser::cdp::CdpClient;
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Request header carrying the run ID with [`RunIdHint::Header`]
+pub const RUN_ID_HEADER: &str = "X-Agent-Run";
+
+/// How the run ID is sent to visited sites
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunIdHint {
+    /// Do not send the run ID
+    #[default]
+    None,
+    /// Append `AgentRun/<id>` to the browser's user agent
+    UserAgent,
+    /// Send an `X-Agent-Run: <id>` header with every request
+    Header,
+}
+
+/// Generate a new run ID
+pub fn new_run_id() -> String {
+    uuid::Uuid::now_v7().to_string()
+}
+
+/// `user_agent` with an `AgentRun/<id>` product token appended
+pub fn user_agent_with_run_id(user_agent: &str, run_id: &str) -> String {
+    format!("{} AgentRun/{}", user_agent.trim_end(), run_id)
+}
+
+/// Send the run ID with the requests of the tab attached as `session_id`
+///
+/// `user_agent` is the browser's own user agent, needed for
+/// [`RunIdHint::UserAgent`] since CDP can only replace it.
+pub async fn apply_run_id_hint(
+    client: &CdpClient,
+    session_id: &str,
+    hint: RunIdHint,
+    run_id: &str,
+    user_agent: &str,
+) -> Result<()> {
+    let (method, params) = match hint {
+        RunIdHint::None => return Ok(()),
+        RunIdHint::UserAgent => (
+            "Network.setUserAgentOverride",
+            json!({ "userAgent": user_agent_with_run_id(user_agent, run_id) }),
+        ),
+        RunIdHint::Header => (
+            "Network.setExtraHTTPHeaders",
+            json!({ "headers": { RUN_ID_HEADER: run_id } }),
+        ),
+    };
+    client
+        .send_command_with_session("Network.enable", json!({}), Some(session_id))
+        .await?;
+    client
+        .send_command_with_session(method, params, Some(session_id))
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_ids_are_unique_v7() {
+        let first = new_run_id();
+        let second = new_run_id();
+        assert_ne!(first, second);
+        let uuid = uuid::Uuid::parse_str(&first).unwrap();
+        assert_eq!(uuid.get_version_num(), 7);
+    }
+
+    #[test]
+    fn test_user_agent_with_run_id() {
+        assert_eq!(
+            user_agent_with_run_id("Mozilla/5.0 Chrome/130.0 ", "0192-ab"),
+            "Mozilla/5.0 Chrome/130.0 AgentRun/0192-ab"
+        );
+    }
+}
@@ -1,22 +1,66 @@
 //! Agent service implementation
 
+use crate::actor::PageCheckpoint;
+use crate::agent::assertions::AssertionSummary;
+use crate::agent::credentials::{
+    Credentials, CredentialsProvider, SECRET_PARAMS, SecretString, has_placeholders, secret_param,
+};
+use crate::agent::error_screenshot::{ERROR_SCREENSHOT_METADATA_KEY, ErrorScreenshots, is_failure};
+use crate::agent::human::{
+    ASK_HUMAN_ACTION, AgentHandle, HUMAN_INPUT_METADATA_KEY, HumanExchange, HumanQuestion,
+};
 use crate::agent::json_extractor::JSONExtractor;
+use crate::agent::memory::{AgentMemory, MemoryEntry, fact};
+use crate::agent::output_schema::{self, AGENT_OUTPUT_VERSION};
+use crate::agent::page_group::SWITCHED_TAB_METADATA_KEY;
+use crate::agent::run_id::{RUN_ID_HEADER, RunIdHint, apply_run_id_hint, new_run_id};
+use crate::agent::prompts::{
+    PromptLabels, build_system_prompt, context_block, dry_run_block, secrets_block,
+};
+use crate::agent::report::ReportFormat;
+use crate::agent::resume::{SAVED_STATE_VERSION, SavedAgentState, UsageTotals};
+use crate::agent::sanitize::{InjectionDetector, sanitize_page_content, strip_delimiters};
+use crate::agent::site_memory::SiteMemory;
+use crate::agent::stream::{STEP_EVENT_BUFFER, StepEvent, StepUpdate};
+use crate::agent::tab_hygiene::TabTracker;
+use crate::agent::timeouts::{RunClock, StopReason};
 use crate::agent::views::{
     ActionResult, AgentHistory, AgentHistoryList, AgentOutput, AgentSettings, AgentState,
+    StepMetadata,
 };
+use crate::browser::cdp::CdpClient;
+use crate::browser::views::BrowserVersionInfo;
+use crate::browser::{BrowserStateOptions, NavigationRecord, WebAppManifest, build_browser_state};
+use crate::config::ToolsConfig;
+use crate::dom::{DOMInteractedElement, DOMProcessorImpl, SerializationOptions, is_blank_page_url};
 use crate::error::{BrowsingError, Result};
-use crate::llm::base::{ChatMessage, ChatModel};
+use crate::llm::base::{
+    CallPurpose, ChatInvokeUsage, ChatMessage, ChatModel, LlmCall, RecordedModel,
+};
+use crate::logging::AgentLogger;
 use crate::traits::{BrowserClient, DOMProcessor};
 use crate::tools::Tools;
 use crate::tools::views::ActionModel;
+use crate::tokens::views::{ModelUsage, TokenPricing};
+use futures_util::FutureExt;
+use futures_util::stream::{self, Stream};
 use serde_json::Value;
-use tracing::info;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{Instrument, info};
 
 /// Agent for autonomous web automation
 pub struct Agent<L: ChatModel> {
     task: String,
     browser: Box<dyn BrowserClient>,
     llm: L,
+    /// Answers questions about page content, if set
+    extraction_llm: Option<Box<dyn ChatModel>>,
+    /// Model calls made by the current step's actions
+    action_llm_calls: Vec<LlmCall>,
     tools: Tools,
     dom_processor: Box<dyn DOMProcessor>,
     max_steps: u32,
@@ -24,6 +68,58 @@ pub struct Agent<L: ChatModel> {
     state: AgentState,
     history: AgentHistoryList,
     usage_tracker: UsageTracker,
+    logger: Option<AgentLogger>,
+    tab_tracker: TabTracker,
+    memory: AgentMemory,
+    /// Long-term entries evicted since the last step was recorded
+    memory_evictions: Vec<MemoryEntry>,
+    /// Notes about sites shared with other runs, if enabled
+    site_memory: Option<SiteMemory>,
+    /// Context whose values are shown to the model but masked in logs
+    sensitive_context: HashMap<String, String>,
+    /// Secrets filled into `<secret>` placeholders, never shown to the model
+    credentials: Credentials,
+    /// Secrets filled in so far, masked in results, history and logs
+    filled_secrets: Vec<SecretString>,
+    /// Flags injected instructions in page content (built from settings on run)
+    injection_detector: Option<InjectionDetector>,
+    /// Screenshots of failed actions (set up from settings on run)
+    error_screenshots: Option<ErrorScreenshots>,
+    /// Correlates logs, artifacts and visited sites' logs with this run
+    run_id: String,
+    /// Web app manifest of the last page looked up, by URL without fragment
+    web_app: Option<(String, Option<WebAppManifest>)>,
+    /// Target the DOM processor reads, followed when the current tab changes
+    dom_target_id: Option<String>,
+    /// Receives an event after each step, set by [`Agent::run_stream`]
+    step_events: Option<mpsc::Sender<StepEvent>>,
+    /// Answers `ask_human`, once taken with [`Agent::handle`]
+    human: Option<AgentHandle>,
+    /// Exchanges with the human in the last step, shown in the next prompt
+    human_replies: Vec<HumanExchange>,
+    /// Steps taken by the saved run this agent was resumed from
+    first_step: u32,
+    /// Measures the step and run timeouts, stopped while waiting for the human
+    clock: Arc<RunClock>,
+    /// Page to return to on the first run after [`Agent::resume`]
+    resume_url: Option<String>,
+    /// Cookies and storage to restore on that page, if they were saved
+    resume_page: Option<PageCheckpoint>,
+    /// Cookies and storage of the current page when the last run ended
+    last_page: Option<PageCheckpoint>,
+}
+
+/// What a step has done so far, kept when its deadline cuts it short
+#[derive(Default)]
+struct StepProgress {
+    agent_output: Option<AgentOutput>,
+    actions: Vec<ActionModel>,
+    results: Vec<ActionResult>,
+    llm_calls: Vec<LlmCall>,
+    usage: Option<ChatInvokeUsage>,
+    cost: Option<f64>,
+    /// What the step is waiting on, for the timeout message
+    running: Option<String>,
 }
 
 /// Simple usage tracker that aggregates token counts
@@ -31,6 +127,12 @@ struct UsageTracker {
     total_prompt_tokens: u32,
     total_completion_tokens: u32,
     total_tokens: u32,
+    /// Number of responses counted
+    responses: u32,
+    /// Estimated cost, if token prices are known
+    cost: Option<f64>,
+    /// Usage of each model called
+    by_model: Vec<ModelUsage>,
 }
 
 impl UsageTracker {
@@ -39,13 +141,46 @@ impl UsageTracker {
             total_prompt_tokens: 0,
             total_completion_tokens: 0,
             total_tokens: 0,
+            responses: 0,
+            cost: None,
+            by_model: Vec::new(),
         }
     }
 
-    fn add_usage(&mut self, usage: &crate::llm::base::ChatInvokeUsage) {
+    fn add_call(&mut self, call: &LlmCall) {
+        ModelUsage::record(&mut self.by_model, call);
+        let Some(usage) = &call.usage else {
+            return;
+        };
         self.total_prompt_tokens += usage.prompt_tokens;
         self.total_completion_tokens += usage.completion_tokens;
         self.total_tokens += usage.total_tokens;
+        self.responses += 1;
+        if let Some(cost) = call.cost {
+            self.cost = Some(self.cost.unwrap_or(0.0) + cost);
+        }
+    }
+
+    fn from_totals(totals: UsageTotals) -> Self {
+        Self {
+            total_prompt_tokens: totals.prompt_tokens,
+            total_completion_tokens: totals.completion_tokens,
+            total_tokens: totals.total_tokens,
+            responses: totals.responses,
+            cost: totals.cost,
+            by_model: totals.by_model,
+        }
+    }
+
+    fn totals(&self) -> UsageTotals {
+        UsageTotals {
+            prompt_tokens: self.total_prompt_tokens,
+            completion_tokens: self.total_completion_tokens,
+            total_tokens: self.total_tokens,
+            responses: self.responses,
+            cost: self.cost,
+            by_model: self.by_model.clone(),
+        }
     }
 
     fn to_summary(&self) -> crate::tokens::views::UsageSummary {
@@ -53,7 +188,10 @@ impl UsageTracker {
             prompt_tokens: Some(self.total_prompt_tokens),
             completion_tokens: Some(self.total_completion_tokens),
             total_tokens: Some(self.total_tokens),
-            cost: None, // Cost calculation can be added later
+            cost: self.cost,
+            avg_completion_tokens_per_step: (self.responses > 0)
+                .then(|| f64::from(self.total_completion_tokens) / f64::from(self.responses)),
+            by_model: self.by_model.clone(),
         }
     }
 }
@@ -66,21 +204,121 @@ impl<L: ChatModel> Agent<L> {
         dom_processor: Box<dyn DOMProcessor>,
         llm: L,
     ) -> Self {
+        let run_id = new_run_id();
         Self {
             task: task.clone(),
             browser,
             llm,
+            extraction_llm: None,
+            action_llm_calls: Vec::new(),
             tools: Tools::default(),
             dom_processor,
             max_steps: 100,
             settings: AgentSettings::default(),
             state: AgentState::default(),
             history: AgentHistoryList {
+                run_id: Some(run_id.clone()),
+                seed: None,
+                output_version: AGENT_OUTPUT_VERSION,
                 history: vec![],
                 usage: None,
+                environment: None,
+                assertions: AssertionSummary::default(),
+                report_path: None,
+                stop_reason: None,
             },
             usage_tracker: UsageTracker::new(),
+            logger: None,
+            tab_tracker: TabTracker::new(),
+            memory: AgentMemory::new(),
+            memory_evictions: Vec::new(),
+            site_memory: None,
+            sensitive_context: HashMap::new(),
+            credentials: Credentials::new(),
+            filled_secrets: Vec::new(),
+            injection_detector: None,
+            error_screenshots: None,
+            web_app: None,
+            dom_target_id: None,
+            step_events: None,
+            human: None,
+            human_replies: Vec::new(),
+            first_step: 0,
+            clock: Arc::new(RunClock::new()),
+            resume_url: None,
+            resume_page: None,
+            last_page: None,
+            run_id,
+        }
+    }
+
+    /// Rebuild an agent from a state written by [`Agent::save_state`]
+    ///
+    /// The agent keeps the saved task, run ID, settings, history, memory and
+    /// token usage. When run, it goes back to the last page with its cookies
+    /// and storage restored, and numbers its steps on from the saved ones;
+    /// `max_steps` counts the steps already taken. In-page JavaScript state,
+    /// other tabs, secrets and sensitive context are not restored; see
+    /// [`crate::agent::resume`].
+    pub fn resume(
+        path: impl AsRef<Path>,
+        browser: Box<dyn BrowserClient>,
+        dom_processor: Box<dyn DOMProcessor>,
+        llm: L,
+    ) -> Result<Self> {
+        let saved = SavedAgentState::load(path)?;
+        let mut agent = Self::new(saved.task.clone(), browser, dom_processor, llm);
+        agent.first_step = saved.steps_taken();
+        agent.resume_url = saved.last_url().map(str::to_string);
+        agent.run_id = saved.run_id;
+        agent.max_steps = saved.max_steps;
+        agent.settings = saved.settings;
+        agent.state = saved.state;
+        agent.history = saved.history;
+        agent.memory = saved.memory;
+        agent.human_replies = saved.human_replies;
+        agent.usage_tracker = UsageTracker::from_totals(saved.usage);
+        agent.resume_page = saved.page;
+        Ok(agent)
+    }
+
+    /// Save the agent's progress to `path` so [`Agent::resume`] can continue it
+    ///
+    /// Call it after [`Agent::run`] returns, e.g. when the run hit its step
+    /// limit. The current page's cookies and storage are read from the browser
+    /// if it is still running, else taken from the end of the last run.
+    pub async fn save_state(&self, path: impl AsRef<Path>) -> Result<()> {
+        let page = match self.browser.get_page() {
+            Ok(page) => page.capture_checkpoint().await.ok(),
+            Err(_) => None,
+        };
+        SavedAgentState {
+            version: SAVED_STATE_VERSION,
+            task: self.task.clone(),
+            run_id: self.run_id.clone(),
+            max_steps: self.max_steps,
+            settings: self.settings.clone(),
+            state: self.state.clone(),
+            history: self.history.clone(),
+            memory: self.memory.clone(),
+            human_replies: self.human_replies.clone(),
+            usage: self.usage_tracker.totals(),
+            page: page.or_else(|| self.last_page.clone()),
         }
+        .save(path)
+    }
+
+    /// UUID v7 identifying this run in tracing spans, history and artifacts
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    /// Handle for answering the questions the model asks with `ask_human`
+    ///
+    /// Take it before [`Agent::run`] or [`Agent::run_stream`]. Until a handle
+    /// is taken nobody can answer, and `ask_human` returns without waiting.
+    pub fn handle(&mut self) -> AgentHandle {
+        self.human.get_or_insert_with(AgentHandle::default).clone()
     }
 
     /// Set the maximum number of steps the agent will take
@@ -89,35 +327,224 @@ impl<L: ChatModel> Agent<L> {
         self
     }
 
+    /// Short-term, long-term and working memory accumulated so far
+    pub fn memory(&self) -> &AgentMemory {
+        &self.memory
+    }
+
+    /// Store a fact in working memory, as the `remember` action does
+    ///
+    /// Useful to seed the agent with facts before [`Agent::run`]; they stay in
+    /// the prompt for the whole run and are never evicted.
+    pub fn remember(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.memory.set(key, value);
+    }
+
+    /// Add an entry to long-term memory
+    ///
+    /// Entries are shown as a timestamped summary and the oldest are evicted
+    /// once it grows too long, unless `important`.
+    pub fn add_long_term_memory(&mut self, content: &str, important: bool) {
+        let step = self.state.n_steps;
+        for entry in self.memory.add_long_term(step, content, important) {
+            tracing::debug!("Evicted from long-term memory: {}", entry.summary_line());
+            self.memory_evictions.push(entry);
+        }
+    }
+
+    /// Search working memory by keywords, as the `recall` action does
+    ///
+    /// Returns `(key, value, score)` tuples, best match first.
+    pub fn recall_memory(&self, query: &str) -> Vec<(String, String, f64)> {
+        self.memory.search(query)
+    }
+
+    /// Keep notes about sites across runs in `site_memory`
+    ///
+    /// `remember` with `site: true` stores a note for the current page's site,
+    /// and each step shows the notes for the site being visited.
+    pub fn with_site_memory(mut self, site_memory: SiteMemory) -> Self {
+        self.site_memory = Some(site_memory);
+        self
+    }
+
+    /// Notes about sites shared across runs, if enabled
+    pub fn site_memory(&self) -> Option<&SiteMemory> {
+        self.site_memory.as_ref()
+    }
+
+    /// Answer questions about page content with `llm`
+    ///
+    /// Without it, `extract` returns page content as is. Its calls are
+    /// recorded in each step's metadata and in the per-model usage breakdown,
+    /// so a cheaper model can serve extraction and its share be compared.
+    pub fn with_extraction_llm(mut self, llm: impl ChatModel + 'static) -> Self {
+        self.extraction_llm = Some(Box::new(llm));
+        self
+    }
+
     /// Set agent configuration settings
     pub fn with_settings(mut self, settings: AgentSettings) -> Self {
         self.settings = settings;
         self
     }
 
+    /// Limit the actions the model may use, e.g. to the `safe` capability profile
+    ///
+    /// Excluded actions are left out of the prompt and refused if the model
+    /// asks for them anyway. The config's evaluate policy applies unless the
+    /// settings set `evaluate_policy`.
+    pub fn with_tools_config(mut self, config: &ToolsConfig) -> Self {
+        self.tools = Tools::from_config(config);
+        self
+    }
+
+    /// Add background information for the model, e.g. domain knowledge or configuration
+    ///
+    /// Entries are added to the system message as `key: value` lines in a
+    /// `<context>` block. Settings applied later with [`Agent::with_settings`]
+    /// replace them.
+    pub fn with_task_context(mut self, context: HashMap<String, String>) -> Self {
+        self.settings.task_context.extend(context);
+        self
+    }
+
+    /// Add context such as credentials that must not be logged
+    ///
+    /// Entries are given to the model like [`Agent::with_task_context`], in a
+    /// separate `<context>` block, but their values are replaced with `***`
+    /// in tracing output and the action log.
+    pub fn with_sensitive_context(mut self, context: HashMap<String, String>) -> Self {
+        self.sensitive_context.extend(context);
+        self
+    }
+
+    /// Add secrets the model can use without seeing them
+    ///
+    /// The model is told the names and writes `<secret>name</secret>` in action
+    /// parameters; the value is filled in when the action runs and replaced
+    /// with `***` in results, history and the action log. These take
+    /// precedence over the [credentials provider](Agent::with_credentials_provider).
+    pub fn with_secrets(mut self, secrets: HashMap<String, String>) -> Self {
+        self.credentials = self.credentials.with_secrets(secrets);
+        self
+    }
+
+    /// Look up secrets not given with [`Agent::with_secrets`] in `provider`,
+    /// by the host of the current page
+    pub fn with_credentials_provider(mut self, provider: Arc<dyn CredentialsProvider>) -> Self {
+        self.credentials = self.credentials.with_provider(provider);
+        self
+    }
+
     /// Run the agent to complete the task
+    ///
+    /// Everything logged during the run is inside an `agent_run` span
+    /// carrying the run ID.
     pub async fn run(&mut self) -> Result<AgentHistoryList> {
+        let span = tracing::info_span!("agent_run", run_id = %self.run_id);
+        self.run_steps().instrument(span).await
+    }
+
+    /// Run the agent, yielding an event as each step finishes
+    ///
+    /// The run only makes progress while the stream is polled, and waits before
+    /// its next step once [`STEP_EVENT_BUFFER`] events are waiting to be read.
+    /// The last event is always [`StepEvent::RunComplete`].
+    pub fn run_stream(mut self) -> impl Stream<Item = StepEvent> {
+        let (sender, receiver) = mpsc::channel(STEP_EVENT_BUFFER);
+        self.step_events = Some(sender);
+        let run = Box::pin(
+            async move {
+                let result = self.run().await;
+                let complete = StepEvent::RunComplete {
+                    history: Box::new(self.history.clone()),
+                    error: result.err().map(|e| e.to_string()),
+                };
+                if let Some(sender) = self.step_events.take() {
+                    let _ = sender.send(complete).await;
+                }
+            }
+            .fuse(),
+        );
+        // Drive the run while reading its events; the stream ends once the
+        // run has finished and every event it sent has been read
+        stream::unfold((run, receiver), |(mut run, mut receiver)| async move {
+            loop {
+                tokio::select! {
+                    biased;
+                    event = receiver.recv() => return event.map(|event| (event, (run, receiver))),
+                    () = &mut run => {}
+                }
+            }
+        })
+    }
+
+    async fn run_steps(&mut self) -> Result<AgentHistoryList> {
+        self.clock = Arc::new(RunClock::new());
+        let clock = self.clock.clone();
+        // Open the audit log before touching the browser so a bad path fails fast
+        if let Some(ref log_file) = self.settings.log_file {
+            self.logger = Some(AgentLogger::new(log_file)?.with_run_id(&self.run_id));
+        }
+        self.injection_detector = if self.settings.detect_prompt_injection {
+            Some(InjectionDetector::new(&self.settings.prompt_injection_patterns)?)
+        } else {
+            None
+        };
+        self.tools.injection_detector = self.injection_detector.clone();
+        if let Some(ref detector) = self.injection_detector {
+            self.site_memory = self
+                .site_memory
+                .take()
+                .map(|memory| memory.with_injection_detector(detector.clone()));
+        }
+        self.tools.new_window_handling = self.settings.new_window_handling;
+        self.tools.pointer_events_mode = self.settings.pointer_events_mode;
+        if let Some(policy) = self.settings.evaluate_policy {
+            self.tools.evaluate_policy = policy;
+        }
+        let artifacts_dir = self.settings.artifacts_dir.clone().unwrap_or_else(|| {
+            std::env::temp_dir().join(format!("browsing-run-{}", self.run_id))
+        });
+        self.tools.artifacts_dir = Some(artifacts_dir.clone());
+        self.error_screenshots = self
+            .settings
+            .screenshot_on_error
+            .then(|| ErrorScreenshots::new(artifacts_dir));
+
         // Start browser
         self.browser.start().await?;
+        let browser_version = self.browser.version_info().await.ok();
+        self.history.environment =
+            Some(crate::version::EnvironmentInfo::new(browser_version.clone()));
 
         // Initialize DOM processor with browser's CDP client
         let cdp_client = self.browser.get_cdp_client()?;
         let session_info = self.browser.get_session_info().await?;
+        self.send_run_id(browser_version.as_ref(), &session_info.session_id)
+            .await;
+        if let Some(determinism) = &self.settings.determinism {
+            info!("Deterministic mode with seed {}", determinism.seed);
+            self.history.seed = Some(determinism.seed);
+            if let Err(e) = self.browser.set_determinism(determinism.clone()).await {
+                tracing::warn!("Failed to make the page deterministic: {}", e);
+            }
+        }
 
         // Create a new DOM processor with the CDP client and target ID
-        let dom_processor = Box::new(
-            crate::dom::DOMProcessorImpl::new()
-                .with_cdp_client(cdp_client, session_info.session_id)
-                .with_target_id(session_info.target_id)
-        );
-        self.dom_processor = dom_processor;
+        self.dom_target_id = Some(session_info.target_id.clone());
+        self.dom_processor = Box::new(self.dom_processor_for(
+            cdp_client,
+            session_info.session_id,
+            session_info.target_id,
+        ));
 
-        // Extract URL from task if present
-        let initial_url = crate::utils::extract_urls(&self.task).first().cloned();
-
-        // Navigate to initial URL if found
-        if let Some(url) = initial_url {
-            self.browser.navigate(&url).await?;
+        if let Some(url) = self.resume_url.take() {
+            self.return_to_page(&url).await?;
+        } else if let Some(url) = crate::utils::extract_urls(&self.task).first() {
+            // Navigate to the URL in the task, if any
+            self.browser.navigate(url).await?;
         }
 
         // Set up signal handler for graceful shutdown
@@ -125,78 +552,207 @@ impl<L: ChatModel> Agent<L> {
         let _shutdown_listener = signal_handler.spawn_shutdown_listener();
 
         // Main execution loop
-        for step in 0..self.max_steps {
+        let run_deadline = self
+            .settings
+            .run_timeout
+            .filter(|&secs| secs > 0)
+            .map(|secs| Duration::from_secs(secs.into()));
+        let mut stop_reason = StopReason::MaxSteps;
+        for step in self.first_step..self.max_steps {
             // Check for shutdown request
             if signal_handler.is_shutdown_requested()
                 || crate::utils::signal::is_shutdown_requested()
             {
                 info!("🛑 Shutdown requested, stopping agent execution");
+                stop_reason = StopReason::Shutdown;
+                break;
+            }
+            if run_deadline.is_some_and(|run| clock.elapsed() >= run) {
+                info!("⏱ Run timed out after {}s", clock.elapsed().as_secs());
+                stop_reason = StopReason::TimedOut;
                 break;
             }
 
             self.state.n_steps = step + 1;
+            let step_start_time = unix_seconds();
 
-            // Get page state
-            let page_state = self.get_page_state().await?;
-
-            // Build messages for LLM
-            let messages = self.build_messages(&page_state)?;
+            // Get page state, remembering it for the tab overview
+            let (page_state, title) = self.get_page_state().await?;
 
-            // Get next action from LLM
-            let response = self.llm.chat(&messages).await?;
+            let web_app = self.web_app_manifest().await;
+            let navigation = self.current_navigation().await;
+            let site_notes = self.site_notes().await;
+            let captured_responses = self.browser.response_capture_summary();
+            let secret_names = self.secret_names().await;
 
-            // Track token usage if available
-            if let Some(ref usage) = response.usage {
-                self.track_usage(usage);
-            }
+            // Build messages for LLM
+            let messages = self.build_messages(
+                &page_state,
+                web_app.as_ref(),
+                navigation.as_ref(),
+                &site_notes,
+                captured_responses.as_deref(),
+                &secret_names,
+            )?;
+            self.human_replies.clear();
 
-            // Parse AgentOutput from LLM response
-            let agent_output = self.parse_agent_output(&response.completion)?;
-
-            // Execute actions
-            let mut results = vec![];
-            for action_value in &agent_output.action {
-                // Convert serde_json::Value to ActionModel
-                let action: ActionModel =
-                    serde_json::from_value(action_value.clone()).map_err(|e| {
-                        BrowsingError::Agent(format!("Failed to parse action: {e}"))
-                    })?;
-
-                match self.execute_action(&action).await {
-                    Ok(result) => results.push(result),
-                    Err(e) => {
-                        results.push(ActionResult {
-                            error: Some(e.to_string()),
-                            ..Default::default()
-                        });
+            // Ask the model and run its actions within the step's deadline;
+            // what finished before it is kept
+            let step_deadline = (self.settings.step_timeout > 0).then(|| {
+                clock.elapsed() + Duration::from_secs(self.settings.step_timeout.into())
+            });
+            let deadline = match (step_deadline, run_deadline) {
+                (Some(step), Some(run)) => Some(step.min(run)),
+                (step, run) => step.or(run),
+            };
+            let mut progress = StepProgress::default();
+            let work = self.decide_and_act(step + 1, &messages, &mut progress);
+            let finished = match deadline {
+                Some(deadline) => clock.run_until(deadline, work).await,
+                None => Some(work.await),
+            };
+            let StepProgress {
+                agent_output,
+                actions,
+                mut results,
+                mut llm_calls,
+                usage: step_usage,
+                cost: step_cost,
+                running,
+            } = progress;
+            let mut run_timed_out = false;
+            match finished {
+                Some(outcome) => {
+                    outcome?;
+                    if agent_output.is_some() {
+                        self.state.consecutive_failures = 0;
+                    }
+                }
+                None => {
+                    run_timed_out = run_deadline.is_some_and(|run| clock.elapsed() >= run);
+                    let mut message = if run_timed_out {
+                        format!("Run timed out after {}s", clock.elapsed().as_secs())
+                    } else {
+                        format!("Step timed out after {}s", self.settings.step_timeout)
+                    };
+                    if let Some(running) = running {
+                        message = format!("{message} while {running}");
+                    }
+                    info!("⏱ {}", message);
+                    if !run_timed_out {
+                        self.state.consecutive_failures += 1;
+                        if self.state.consecutive_failures >= self.settings.max_failures {
+                            return Err(BrowsingError::Agent(format!(
+                                "{message}; stopping after {} consecutive failures",
+                                self.state.consecutive_failures
+                            )));
+                        }
                     }
+                    results.push(ActionResult {
+                        error: Some(message),
+                        ..Default::default()
+                    });
                 }
             }
+            for call in std::mem::take(&mut self.action_llm_calls) {
+                llm_calls.push(self.track_call(call));
+            }
+            if let Some(target_id) = results.iter().rev().find_map(|result| {
+                result.metadata.as_ref()?.get(SWITCHED_TAB_METADATA_KEY)?.as_str()
+            }) {
+                self.state.page_group.set_current(target_id);
+            }
+
+            // Close stale tabs, keeping the model informed of each closure
+            self.tab_tracker.pin_from_results(&results);
+            match self
+                .tab_tracker
+                .enforce(&mut *self.browser, step + 1, &self.settings)
+                .await
+            {
+                Ok(closed) => results.extend(closed),
+                Err(e) => tracing::warn!("Failed to close unused tabs: {}", e),
+            }
+            self.memory.record_results(&results);
+            let evicted = std::mem::take(&mut self.memory_evictions);
+            self.state.last_result = Some(results.clone());
+            self.history.assertions.record(step + 1, &results);
 
             // Record step in history
             let history_item = AgentHistory {
-                model_output: Some(agent_output.clone()),
+                model_output: agent_output.clone(),
                 result: results.clone(),
                 state: crate::browser::views::BrowserStateHistory {
                     url: self.browser.get_current_url().await.unwrap_or_default(),
-                    title: "Unknown".to_string(),
-                    tabs: vec![],
+                    title,
+                    tabs: self.browser.get_tabs().await.unwrap_or_default(),
                     interacted_element: vec![],
-                    screenshot_path: None,
+                    screenshot_path: results.iter().find_map(|result| {
+                        let path = result.metadata.as_ref()?.get(ERROR_SCREENSHOT_METADATA_KEY)?;
+                        path.as_str().map(str::to_string)
+                    }),
+                    navigation: self.current_navigation().await,
+                    emulation: self
+                        .browser
+                        .emulation()
+                        .await
+                        .filter(|settings| !settings.is_default()),
                 },
-                metadata: None,
+                metadata: Some(StepMetadata {
+                    step_start_time,
+                    step_end_time: unix_seconds(),
+                    step_number: step + 1,
+                    memory_evictions: evicted.iter().map(MemoryEntry::summary_line).collect(),
+                    usage: step_usage.clone(),
+                    cost: step_cost,
+                    llm_calls,
+                }),
                 state_message: None,
             };
+            self.log_actions(step + 1, &actions, &history_item);
+            if let Some(ref sender) = self.step_events {
+                let update = StepUpdate {
+                    step: step + 1,
+                    summary: agent_output
+                        .as_ref()
+                        .map(StepUpdate::summarize)
+                        .unwrap_or_default(),
+                    actions: actions.clone(),
+                    results: results.clone(),
+                    screenshot_path: history_item.state.screenshot_path.clone(),
+                    usage: step_usage,
+                    url: history_item.state.url.clone(),
+                };
+                // Waits while the consumer is behind; fails only if it is gone
+                let _ = sender.send(StepEvent::Step(update)).await;
+            }
             self.history.history.push(history_item);
 
             // Check if task is complete
             if self.is_task_complete(&results) {
+                stop_reason = StopReason::Done;
+                break;
+            }
+            if run_timed_out {
+                stop_reason = StopReason::TimedOut;
                 break;
             }
         }
+        self.history.stop_reason = Some(stop_reason);
 
         // Update history with final usage summary
         self.history.usage = Some(self.usage_tracker.to_summary());
+        if let Some(format) = self.settings.report_format {
+            self.write_report(format);
+        }
+
+        // Keep the page's cookies and storage for save_state
+        if let Ok(page) = self.browser.get_page() {
+            match page.capture_checkpoint().await {
+                Ok(checkpoint) => self.last_page = Some(checkpoint),
+                Err(e) => tracing::debug!("Failed to read the page's cookies and storage: {}", e),
+            }
+        }
 
         // Gracefully close browser session
         if let Err(e) = self.browser.stop().await {
@@ -206,46 +762,449 @@ impl<L: ChatModel> Agent<L> {
         Ok(self.history.clone())
     }
 
-    /// Track token usage from an LLM response
-    fn track_usage(&mut self, usage: &crate::llm::base::ChatInvokeUsage) {
-        self.usage_tracker.add_usage(usage);
+    /// Ask the model for the step's actions and run them
+    ///
+    /// Each result goes into `progress` as soon as it is known, so a step
+    /// cut short by its deadline keeps what it did.
+    async fn decide_and_act(
+        &mut self,
+        step: u32,
+        messages: &[ChatMessage],
+        progress: &mut StepProgress,
+    ) -> Result<()> {
+        // Get next action from LLM
+        progress.running = Some("waiting for the model".to_string());
+        let started = std::time::Instant::now();
+        let response = self.llm.chat(messages).await?;
+
+        // Track token usage if available
+        let call = self.track_call(LlmCall::new(
+            &self.llm,
+            CallPurpose::ActionSelection,
+            started.elapsed(),
+            &response,
+        ));
+        progress.usage = call.usage.clone();
+        progress.cost = call.cost;
+        progress.llm_calls.push(call);
+        progress.running = None;
+
+        // Parse AgentOutput from LLM response; an invalid reply is sent
+        // back to the model as the step's result
+        let agent_output = match self.parse_agent_output(&response.completion) {
+            Ok(agent_output) => agent_output,
+            Err(problems) => {
+                self.state.consecutive_failures += 1;
+                if self.state.consecutive_failures >= self.settings.max_failures {
+                    return Err(BrowsingError::Agent(format!(
+                        "Failed to parse agent output: {}",
+                        problems.join("; ")
+                    )));
+                }
+                let message = output_schema::feedback(&problems);
+                progress.results.push(ActionResult {
+                    error: Some(message.clone()),
+                    extracted_content: Some(message),
+                    include_in_memory: false,
+                    ..Default::default()
+                });
+                return Ok(());
+            }
+        };
+        let planned = agent_output.action.clone();
+        progress.agent_output = Some(agent_output);
+
+        // A response the wait is for can arrive before the wait starts
+        let waits_for_response = planned
+            .iter()
+            .any(|action| action.action_type == "wait_for_response");
+        if waits_for_response {
+            self.tools.mark_responses(&*self.browser).await;
+        }
+
+        // Execute actions
+        for action in planned {
+            progress.running = Some(format!("running {}", action.action_type));
+            let mut result = match self.execute_action(&action).await {
+                Ok(result) => result,
+                Err(e) => ActionResult {
+                    error: Some(e.to_string()),
+                    ..Default::default()
+                },
+            };
+            self.redact_result(&mut result);
+            if is_failure(&result) {
+                self.capture_error_screenshot(step, &action, &mut result)
+                    .await;
+            }
+            progress.results.push(result);
+            progress.actions.push(action);
+        }
+        if waits_for_response {
+            self.tools.clear_response_marker().await;
+        }
+        progress.running = Some("waiting for the page to settle".to_string());
+        self.follow_client_redirects().await;
+        progress.running = None;
+        Ok(())
+    }
+
+    /// Go back to the page a resumed run stopped on, restoring its cookies and storage
+    async fn return_to_page(&mut self, url: &str) -> Result<()> {
+        // Navigating through the browser first applies its URL restrictions
+        self.browser.navigate(url).await?;
+        let Some(checkpoint) = self.resume_page.take() else {
+            return Ok(());
+        };
+        let restored = match self.browser.get_page() {
+            Ok(page) => page.restore_checkpoint(&checkpoint).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = restored {
+            tracing::warn!("Failed to restore cookies and storage of {}: {}", url, e);
+        }
+        Ok(())
+    }
+
+    /// Append an audit entry for each executed action, if logging is enabled
+    fn log_actions(&mut self, step: u32, actions: &[ActionModel], history_item: &AgentHistory) {
+        let Some(ref mut logger) = self.logger else {
+            return;
+        };
+        let secrets = secret_values(&self.sensitive_context, &self.filled_secrets);
+        for (action, result) in actions.iter().zip(&history_item.result) {
+            let mut action = action.clone();
+            for value in action.params.values_mut() {
+                redact_value(value, &secrets);
+            }
+            let mut result = result.clone();
+            for text in [&mut result.extracted_content, &mut result.debug_info]
+                .into_iter()
+                .flatten()
+            {
+                *text = redact(text, &secrets);
+            }
+            if let Err(e) = logger.log_action(step, &action, &result, &history_item.state) {
+                tracing::warn!("Failed to write agent log entry: {}", e);
+            }
+        }
+    }
+
+    /// Write the per-step report to the artifacts directory, noting its path in the history
+    fn write_report(&mut self, format: ReportFormat) {
+        let Some(dir) = self.tools.artifacts_dir.clone() else {
+            return;
+        };
+        let path = dir.join(format!("report-{}.{}", self.run_id, format.extension()));
+        let written = std::fs::create_dir_all(&dir)
+            .map_err(BrowsingError::from)
+            .and_then(|_| self.history.export_report(&path, format));
+        match written {
+            Ok(()) => {
+                info!("📊 Wrote run report to {}", path.display());
+                self.history.report_path = Some(path);
+            }
+            Err(e) => tracing::warn!("Failed to write the run report: {}", e),
+        }
+    }
+
+    /// Token prices of the model that made `call`, if known
+    fn pricing_of(&self, call: &LlmCall) -> Option<TokenPricing> {
+        let key = format!("{}/{}", call.provider, call.model);
+        if let Some(pricing) = self.settings.model_pricing.get(&key) {
+            return Some(*pricing);
+        }
+        let own_model = call.provider == self.llm.provider() && call.model == self.llm.model();
+        self.settings.token_pricing.filter(|_| own_model)
+    }
+
+    /// Track the token usage of a model call
+    ///
+    /// Returns the call with its estimated cost, if its model has token prices.
+    fn track_call(&mut self, mut call: LlmCall) -> LlmCall {
+        call.cost = self.pricing_of(&call).zip(call.usage.as_ref()).map(
+            |(pricing, usage)| pricing.cost(usage.prompt_tokens, usage.completion_tokens),
+        );
+        self.usage_tracker.add_call(&call);
+        call
+    }
+
+    /// Wait out the redirect grace period of the current page, if it is
+    /// about to send the browser on by itself
+    async fn follow_client_redirects(&mut self) {
+        let grace_ms = self.settings.redirect_grace_ms;
+        if grace_ms == 0 {
+            return;
+        }
+        if let Err(e) = self.browser.follow_client_redirects(grace_ms).await {
+            tracing::debug!("Not following redirects by the page: {}", e);
+        }
+    }
+
+    /// Page state of the current tab, also recorded in the tab overview, and its title
+    async fn get_page_state(&mut self) -> Result<(String, String)> {
+        self.follow_client_redirects().await;
+        let target_id = self.follow_current_tab().await;
+        let state = build_browser_state(
+            &*self.browser,
+            &*self.dom_processor,
+            &BrowserStateOptions::default(),
+        )
+        .await?;
+        self.state.page_group.sync_tabs(&state.tabs);
+        let dom_state = state.dom_state;
+        let page_state = dom_state.page_state();
+        if let Some(target_id) = target_id {
+            self.state
+                .page_group
+                .record_current(&target_id, page_state.clone(), dom_state.selector_map);
+        }
+        Ok((page_state, state.title))
+    }
+
+    /// The current tab's last navigation, while the tab is still on the page
+    /// it landed on and that page is not simply the one asked for
+    async fn current_navigation(&self) -> Option<NavigationRecord> {
+        let mut record = self.browser.last_navigation().await?;
+        let url = self.browser.get_current_url().await.ok()?;
+        let without_fragment = |url: &str| url.split('#').next().unwrap_or_default().to_string();
+        if without_fragment(&record.final_url) != without_fragment(&url) {
+            return None;
+        }
+        record.canonical_url = self.browser.get_url(true).await.ok().filter(|c| *c != url);
+        record.summary().is_some().then_some(record)
+    }
+
+    /// Send the run ID to visited sites as the settings ask
+    ///
+    /// The header is kept by the browser for every tab; the user agent can
+    /// only be set per tab, so it is set on the tab attached as `session_id`.
+    async fn send_run_id(&mut self, browser_version: Option<&BrowserVersionInfo>, session_id: &str) {
+        let sent = match self.settings.run_id_hint {
+            RunIdHint::None => return,
+            RunIdHint::Header => {
+                self.browser
+                    .set_extra_headers(HashMap::from([(
+                        RUN_ID_HEADER.to_string(),
+                        self.run_id.clone(),
+                    )]))
+                    .await
+            }
+            RunIdHint::UserAgent => match self.browser.get_cdp_client() {
+                Ok(client) => {
+                    let user_agent = browser_version
+                        .map(|version| version.user_agent.as_str())
+                        .unwrap_or_default();
+                    apply_run_id_hint(
+                        &client,
+                        session_id,
+                        RunIdHint::UserAgent,
+                        &self.run_id,
+                        user_agent,
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            },
+        };
+        if let Err(e) = sent {
+            tracing::warn!("Failed to send the run ID to visited sites: {}", e);
+        }
+    }
+
+    /// Point the DOM processor at the current tab after a tab switch, returning its target ID
+    async fn follow_current_tab(&mut self) -> Option<String> {
+        let session_info = self.browser.get_session_info().await.ok()?;
+        if self
+            .dom_target_id
+            .as_ref()
+            .is_some_and(|target_id| *target_id != session_info.target_id)
+        {
+            let client = self.browser.get_cdp_client().ok()?;
+            self.dom_processor = Box::new(self.dom_processor_for(
+                client,
+                session_info.session_id.clone(),
+                session_info.target_id.clone(),
+            ));
+            self.dom_target_id = Some(session_info.target_id.clone());
+            if self.settings.run_id_hint == RunIdHint::UserAgent {
+                let browser_version = self.browser.version_info().await.ok();
+                self.send_run_id(browser_version.as_ref(), &session_info.session_id)
+                    .await;
+            }
+        }
+        Some(session_info.target_id)
+    }
+
+    /// DOM processor for the tab at `target_id`, serializing with the configured options
+    /// Serialization options of the settings, with their node categories
+    fn serialization_options(&self) -> SerializationOptions {
+        let mut options = self.settings.serialization_options.clone();
+        if !self.settings.dom_node_categories.is_empty() {
+            options.node_categories = self.settings.dom_node_categories.clone();
+        }
+        options
+    }
+
+    fn dom_processor_for(
+        &self,
+        client: Arc<CdpClient>,
+        session_id: String,
+        target_id: String,
+    ) -> DOMProcessorImpl {
+        DOMProcessorImpl::new()
+            .with_cdp_client(client, session_id)
+            .with_target_id(target_id)
+            .with_snapshot_options(self.settings.snapshot_options.clone())
+            .with_serialization_options(self.serialization_options())
+    }
+
+    /// Web app manifest of the current page, looked up again only when the URL changes
+    async fn web_app_manifest(&mut self) -> Option<WebAppManifest> {
+        let url = self.browser.get_current_url().await.ok()?;
+        if is_blank_page_url(&url) {
+            return None;
+        }
+        let url = url.split('#').next().unwrap_or_default().to_string();
+        if let Some((ref cached_url, ref manifest)) = self.web_app
+            && *cached_url == url
+        {
+            return manifest.clone();
+        }
+        let manifest = self
+            .browser
+            .get_web_app_manifest()
+            .await
+            .unwrap_or_else(|e| {
+                tracing::debug!("Failed to read the web app manifest: {}", e);
+                None
+            });
+        self.web_app = Some((url, manifest.clone()));
+        manifest
+    }
+
+    /// Prompt section with the site memory notes for the current page
+    async fn site_notes(&self) -> String {
+        let Some(ref site_memory) = self.site_memory else {
+            return String::new();
+        };
+        match self.browser.get_current_url().await {
+            Ok(url) => site_memory.prompt_section(&url, self.labels()),
+            Err(_) => String::new(),
+        }
     }
 
-    async fn get_page_state(&self) -> Result<String> {
-        // Get page state from DOM processor
-        self.dom_processor.get_page_state_string().await
+    /// Labels of the state message, in the configured language
+    fn labels(&self) -> &'static PromptLabels {
+        PromptLabels::for_language(self.settings.language.as_deref())
     }
 
-    fn build_messages(&self, page_state: &str) -> Result<Vec<ChatMessage>> {
+    fn build_messages(
+        &self,
+        page_state: &str,
+        web_app: Option<&WebAppManifest>,
+        navigation: Option<&NavigationRecord>,
+        site_notes: &str,
+        captured_responses: Option<&str>,
+        secret_names: &[String],
+    ) -> Result<Vec<ChatMessage>> {
         let mut messages = vec![];
 
         // System message
-        if let Some(ref system_prompt) = self.settings.override_system_message {
-            messages.push(ChatMessage::system(system_prompt.clone()));
-        } else {
-            // Default system prompt
-            messages.push(ChatMessage::system(
-                "You are a browser automation agent. Help the user complete their task."
-                    .to_string(),
-            ));
+        let available = self
+            .tools
+            .registry
+            .registry
+            .available_for(self.browser.profile());
+        let mut system_prompt = build_system_prompt(&self.settings, &available);
+        if let Some(context) = context_block(&self.sensitive_context) {
+            system_prompt.push_str("\n\n");
+            system_prompt.push_str(&context);
+        }
+        if !secret_names.is_empty() {
+            system_prompt.push_str("\n\n");
+            system_prompt.push_str(&secrets_block(secret_names));
         }
+        if self.tools.dry_run {
+            system_prompt.push_str("\n\n");
+            system_prompt.push_str(&dry_run_block(&self.tools.force_execute));
+        }
+        messages.push(ChatMessage::system(system_prompt));
 
-        // Add task
+        // Add task, with memory carried over from previous steps
+        let labels = self.labels();
+        let memory_section = self.memory.prompt_section_with(labels);
+        // URLs come from the pages, so they may not mimic prompt structure either
+        let tab_summary = self.state.page_group.get_tab_summary();
+        let tabs_section = if tab_summary.is_empty() {
+            String::new()
+        } else {
+            format!("{}:\n{}\n\n", labels.open_tabs, strip_delimiters(&tab_summary))
+        };
+        // Names and URLs come from the site's manifest
+        let web_app_section = web_app
+            .map(|manifest| {
+                let summary = strip_delimiters(&manifest.summary());
+                format!("{}: {}\n\n", labels.web_app, summary)
+            })
+            .unwrap_or_default();
+        // Redirect targets and canonical links are chosen by the site
+        let navigation_section = navigation
+            .and_then(NavigationRecord::summary)
+            .map(|summary| {
+                format!("{}: {}\n\n", labels.navigation, strip_delimiters(&summary))
+            })
+            .unwrap_or_default();
+        // Notes were written by earlier runs from what they read on the site
+        let site_notes = strip_delimiters(site_notes);
+        let human_section: String = self
+            .human_replies
+            .iter()
+            .map(|exchange| {
+                format!(
+                    "{} (to \"{}\"): {}\n\n",
+                    labels.human_reply,
+                    exchange.question,
+                    exchange.reply()
+                )
+            })
+            .collect();
+        let captured_section = captured_responses
+            .map(|summary| {
+                format!(
+                    "{}: {} (read them with get_captured_responses)\n\n",
+                    labels.captured_responses,
+                    strip_delimiters(summary)
+                )
+            })
+            .unwrap_or_default();
+        let page_state = sanitize_page_content(page_state, self.injection_detector.as_ref());
         messages.push(ChatMessage::user(format!(
-            "Task: {}\n\nPage state:\n{}",
-            self.task, page_state
+            "{}: {}\n\n{}{}{}{}{}{}{}{}:\n{}",
+            labels.task,
+            self.task,
+            memory_section,
+            tabs_section,
+            web_app_section,
+            navigation_section,
+            site_notes,
+            human_section,
+            captured_section,
+            labels.page_state,
+            page_state
         )));
 
         Ok(messages)
     }
 
-    fn parse_agent_output(&self, response: &str) -> Result<AgentOutput> {
+    /// The model's reply as output, or the problems to tell it about
+    fn parse_agent_output(&self, response: &str) -> std::result::Result<AgentOutput, Vec<String>> {
         // Use JSONExtractor to extract JSON from response
         let extractor = JSONExtractor::new();
         let json_str = extractor.extract_from_response(response);
 
-        tracing::debug!("Raw LLM response: {}", response);
-        tracing::debug!("Extracted JSON: {}", json_str);
+        tracing::debug!("Raw LLM response: {}", self.redact(response));
+        tracing::debug!("Extracted JSON: {}", self.redact(&json_str));
 
         // Try to repair JSON if needed using anyrepair
         // First try to parse directly, if that fails, try to repair
@@ -258,29 +1217,330 @@ impl<L: ChatModel> Agent<L> {
             }
         };
 
-        tracing::debug!("Repaired JSON: {}", repaired);
+        tracing::debug!("Repaired JSON: {}", self.redact(&repaired));
 
         // Parse JSON
         let value: Value = serde_json::from_str(&repaired)
-            .map_err(|e| BrowsingError::Agent(format!("Failed to parse agent output: {e}")))?;
-
-        // Convert to AgentOutput
-        let agent_output = serde_json::from_value(value.clone()).map_err(|e| {
-            tracing::error!("Failed to deserialize agent output. Value: {}", value);
-            BrowsingError::Agent(format!("Failed to deserialize agent output: {e}"))
-        })?;
+            .map_err(|e| vec![format!("the reply is not valid JSON ({e})")])?;
 
-        Ok(agent_output)
+        // Check it against the schema the prompt shows
+        AgentOutput::validate(&value).inspect_err(|problems| {
+            tracing::warn!(
+                "Invalid agent output ({}). Value: {}",
+                problems.join("; "),
+                self.redact(&value.to_string())
+            );
+        })
     }
 
     async fn execute_action(&mut self, action: &ActionModel) -> Result<ActionResult> {
+        // Memory actions never touch the browser; excluded ones are refused by the tools
+        if AgentMemory::handles(&action.action_type)
+            && !self.tools.registry.is_excluded(&action.action_type)
+        {
+            if action.action_type == "remember"
+                && action.params.get("site") == Some(&Value::Bool(true))
+            {
+                return self.remember_for_site(action).await;
+            }
+            return self.memory.handle_action(action);
+        }
+        if action.action_type == ASK_HUMAN_ACTION
+            && !self.tools.registry.is_excluded(ASK_HUMAN_ACTION)
+        {
+            return self.ask_human(action).await;
+        }
+
         // Get selector map from DOM processor
         let selector_map = self.dom_processor.get_selector_map().await.ok();
 
+        // Fill in secrets only now, so the model and the history never hold them
+        let mut action = action.clone();
+        if !self.credentials.is_empty() {
+            self.fill_secrets(&mut action, selector_map.as_ref()).await?;
+        }
+
         // Execute action via tools
-        self.tools
-            .act(action.clone(), &mut *self.browser, selector_map.as_ref())
+        let Some(extraction_llm) = self.extraction_llm.as_deref() else {
+            return self
+                .tools
+                .act(action, &mut *self.browser, selector_map.as_ref())
+                .await;
+        };
+        let recorded = RecordedModel::new(extraction_llm, CallPurpose::Extraction);
+        let result = self
+            .tools
+            .act_with_llm(
+                action,
+                &mut *self.browser,
+                selector_map.as_ref(),
+                Some(&recorded),
+            )
+            .await;
+        self.action_llm_calls.extend(recorded.into_calls());
+        result
+    }
+
+    /// Host of the current page, which secrets are looked up by
+    async fn current_host(&self) -> String {
+        let url = self.browser.get_current_url().await.unwrap_or_default();
+        url::Url::parse(&url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+
+    /// Replace the secret placeholders in the text `action` types
+    ///
+    /// Secrets are looked up by the host of the frame the text goes to, and
+    /// placeholders in any other parameter fail the action.
+    async fn fill_secrets(
+        &mut self,
+        action: &mut ActionModel,
+        selector_map: Option<&HashMap<u32, DOMInteractedElement>>,
+    ) -> Result<()> {
+        let param = secret_param(&action.action_type);
+        if let Some(name) = action
+            .params
+            .iter()
+            .find(|(name, value)| Some(name.as_str()) != param && has_placeholders(value))
+            .map(|(name, _)| name)
+        {
+            let typed: Vec<&str> = SECRET_PARAMS.iter().map(|(action, _)| *action).collect();
+            return Err(BrowsingError::Tool(format!(
+                "Secrets can only be typed with {}, not put in '{name}' of {}",
+                typed.join(", "),
+                action.action_type
+            )));
+        }
+        let Some(param) = param else {
+            return Ok(());
+        };
+        if !action.params.get(param).is_some_and(has_placeholders) {
+            return Ok(());
+        }
+
+        let page_host = self.current_host().await;
+        let frame_host = self.typing_host(action, selector_map).await?;
+        if frame_host.is_empty() {
+            return Err(BrowsingError::Tool(format!(
+                "Secrets are not typed with {} into a frame whose site is unknown",
+                action.action_type
+            )));
+        }
+        let credentials = if frame_host == page_host {
+            self.credentials.clone()
+        } else {
+            self.credentials.provider_only()
+        };
+        if let Some(value) = action.params.get_mut(param) {
+            let filled = credentials.substitute(&frame_host, value).await?;
+            self.filled_secrets.extend(filled);
+        }
+        Ok(())
+    }
+
+    /// Host of the frame the text of `action` goes to, empty if it can't be told
+    async fn typing_host(
+        &self,
+        action: &ActionModel,
+        selector_map: Option<&HashMap<u32, DOMInteractedElement>>,
+    ) -> Result<String> {
+        let url = match action.action_type.as_str() {
+            // The element's frame, which may be an iframe from another site
+            "input" => {
+                let frame_id = action
+                    .params
+                    .get("index")
+                    .and_then(Value::as_u64)
+                    .and_then(|index| selector_map?.get(&(index as u32))?.frame_id.clone());
+                match frame_id {
+                    Some(frame_id) => self
+                        .browser
+                        .get_page()?
+                        .frame_url(&frame_id)
+                        .await?
+                        .unwrap_or_default(),
+                    None => return Ok(self.current_host().await),
+                }
+            }
+            // The frame holding the focus
+            "send_keys" => self
+                .browser
+                .get_page()?
+                .focused_frame_url()
+                .await?
+                .unwrap_or_default(),
+            _ => return Ok(self.current_host().await),
+        };
+        Ok(url::Url::parse(&url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default())
+    }
+
+    /// Names of the secrets the model may use on the current page
+    async fn secret_names(&self) -> Vec<String> {
+        if self.credentials.is_empty() {
+            return Vec::new();
+        }
+        let domain = self.current_host().await;
+        self.credentials.keys(&domain).await
+    }
+
+    /// Mask filled-in secrets in what an action reports back
+    fn redact_result(&self, result: &mut ActionResult) {
+        if self.filled_secrets.is_empty() {
+            return;
+        }
+        let secrets: Vec<&str> = self
+            .filled_secrets
+            .iter()
+            .map(SecretString::expose_secret)
+            .collect();
+        for text in [
+            &mut result.extracted_content,
+            &mut result.long_term_memory,
+            &mut result.debug_info,
+            &mut result.error,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *text = redact(text, &secrets);
+        }
+        if let Some(ref mut metadata) = result.metadata {
+            metadata.values_mut().for_each(|v| redact_value(v, &secrets));
+        }
+    }
+
+    /// Store the fact of a `remember` action in site memory, for the current page's site
+    async fn remember_for_site(&self, action: &ActionModel) -> Result<ActionResult> {
+        let site_memory = self.site_memory.as_ref().ok_or_else(|| {
+            BrowsingError::Tool("Site memory is not enabled; remember without 'site'".to_string())
+        })?;
+        let (key, value) = fact(action)?;
+        let url = self.browser.get_current_url().await?;
+        let domain = site_memory.remember(&url, &key, &value)?;
+        Ok(ActionResult {
+            extracted_content: Some(format!("Stored '{key}' in site memory for {domain}")),
+            ..Default::default()
+        })
+    }
+
+    /// Ask the human the question of an `ask_human` action and wait for the answer
+    ///
+    /// Gives up after the settings' `human_input_timeout`, recording that
+    /// nobody answered rather than failing.
+    async fn ask_human(&mut self, action: &ActionModel) -> Result<ActionResult> {
+        let question = action
+            .params
+            .get("question")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| BrowsingError::Tool("Missing 'question' parameter".to_string()))?
+            .to_string();
+        let step = self.state.n_steps;
+        let screenshot_path = if action.params.get("screenshot") == Some(&Value::Bool(true)) {
+            self.question_screenshot(step).await
+        } else {
+            None
+        };
+        let asked = HumanQuestion {
+            step,
+            question: question.clone(),
+            screenshot_path,
+        };
+        info!("🙋 Asking the human: {}", question);
+        if let Some(ref sender) = self.step_events {
+            let _ = sender
+                .send(StepEvent::HumanInputRequested(asked.clone()))
+                .await;
+        }
+
+        let answer = match self.human {
+            Some(ref handle) => {
+                // Waiting for an answer does not count toward the timeouts
+                let _paused = self.clock.pause();
+                let timeout = Duration::from_secs(self.settings.human_input_timeout.into());
+                match tokio::time::timeout(timeout, handle.ask(asked)).await {
+                    Ok(Ok(answer)) => Some(answer),
+                    _ => {
+                        handle.withdraw();
+                        info!("No answer from the human within {}s", timeout.as_secs());
+                        None
+                    }
+                }
+            }
+            None => {
+                info!("No one can answer; take Agent::handle to answer questions");
+                None
+            }
+        };
+
+        let exchange = HumanExchange { question, answer };
+        let memory = match exchange.answer {
+            Some(ref answer) => {
+                format!("Asked the human \"{}\"; they replied: {answer}", exchange.question)
+            }
+            None => format!("Asked the human \"{}\"; no answer", exchange.question),
+        };
+        self.human_replies.push(exchange.clone());
+        let mut metadata = HashMap::new();
+        metadata.insert(
+            HUMAN_INPUT_METADATA_KEY.to_string(),
+            serde_json::to_value(&exchange)?,
+        );
+        Ok(ActionResult {
+            extracted_content: Some(format!("Human's reply: {}", exchange.reply())),
+            long_term_memory: Some(memory),
+            metadata: Some(metadata),
+            ..Default::default()
+        })
+    }
+
+    /// Screenshot the page for a question asked in `step`, in the artifacts directory
+    async fn question_screenshot(&self, step: u32) -> Option<String> {
+        let dir = self.tools.artifacts_dir.as_ref()?;
+        let path = dir.join(format!("question_step{step}.png"));
+        match self.browser.screenshot_to_file(&path, false).await {
+            Ok(saved) => Some(saved.path.to_string_lossy().into_owned()),
+            Err(e) => {
+                tracing::warn!("Failed to screenshot the page for the human: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Screenshot the page after `action` failed, if enabled, and reference it in `result`
+    async fn capture_error_screenshot(
+        &mut self,
+        step: u32,
+        action: &ActionModel,
+        result: &mut ActionResult,
+    ) {
+        let Some(ref mut screenshots) = self.error_screenshots else {
+            return;
+        };
+        let selector_map = self.dom_processor.get_selector_map().await.ok();
+        let error = result
+            .error
+            .clone()
+            .unwrap_or_else(|| "no success".to_string());
+        if let Some(path) = screenshots
+            .capture(&*self.browser, step, action, &error, selector_map.as_ref())
             .await
+        {
+            result
+                .metadata
+                .get_or_insert_with(HashMap::new)
+                .insert(ERROR_SCREENSHOT_METADATA_KEY.to_string(), Value::String(path));
+        }
+    }
+
+    /// Replace sensitive context values and filled-in secrets in `text` with `***`
+    fn redact(&self, text: &str) -> String {
+        let secrets = secret_values(&self.sensitive_context, &self.filled_secrets);
+        redact(text, &secrets)
     }
 
     fn is_task_complete(&self, results: &[ActionResult]) -> bool {
@@ -288,3 +1548,39 @@ impl<L: ChatModel> Agent<L> {
         results.iter().any(|r| r.is_done == Some(true))
     }
 }
+
+/// Current time as fractional seconds since the Unix epoch
+fn unix_seconds() -> f64 {
+    chrono::Utc::now().timestamp_millis() as f64 / 1000.0
+}
+
+/// Sensitive context values and filled-in secrets, masked in output
+fn secret_values<'a>(
+    sensitive_context: &'a HashMap<String, String>,
+    filled_secrets: &'a [SecretString],
+) -> Vec<&'a str> {
+    sensitive_context
+        .values()
+        .map(String::as_str)
+        .chain(filled_secrets.iter().map(SecretString::expose_secret))
+        .collect()
+}
+
+/// Replace every one of `secrets` in `text` with `***`, longest first
+fn redact(text: &str, secrets: &[&str]) -> String {
+    let mut values: Vec<&str> = secrets.iter().copied().filter(|v| !v.is_empty()).collect();
+    values.sort_by_key(|v| std::cmp::Reverse(v.len()));
+    values
+        .into_iter()
+        .fold(text.to_string(), |text, secret| text.replace(secret, "***"))
+}
+
+/// Redact every string inside a JSON value
+fn redact_value(value: &mut Value, secrets: &[&str]) {
+    match value {
+        Value::String(text) => *text = redact(text, secrets),
+        Value::Array(items) => items.iter_mut().for_each(|v| redact_value(v, secrets)),
+        Value::Object(map) => map.values_mut().for_each(|v| redact_value(v, secrets)),
+        _ => {}
+    }
+}
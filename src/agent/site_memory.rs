@@ -0,0 +1,318 @@
+//! Site memory shared across runs
+//!
+//! Notes the model stores with `remember` and `site: true` go to a JSON file,
+//! keyed by the host of the current page. At the start of each step the notes
+//! for the current page's host are shown in the prompt, so a later run on the
+//! same site starts out knowing what earlier runs learned, e.g. that a login
+//! form needs its cookie banner dismissed first.
+//!
+//! Hosts are not merged into registrable domains: without the public suffix
+//! list, `alice.github.io` and `bob.github.io` would share notes, and one site
+//! could leave instructions for another. Notes are written by a model reading
+//! untrusted pages, so the store's [`InjectionDetector`] refuses notes that
+//! look like instructions, and notes flagged when read back are left out of
+//! the prompt.
+//!
+//! The file is read on every access, so agents sharing one path see each
+//! other's notes. Writes hold a lock on a sibling `.lock` file and go through
+//! a temporary file of their own, so concurrent agents neither lose notes nor
+//! see a partial file. A site keeps at most [`MAX_NOTES_PER_SITE`] notes by
+//! default, dropping the least recently updated first, and notes older than
+//! the store's maximum age are ignored and removed on the next write.
+
+use crate::agent::prompts::PromptLabels;
+use crate::agent::sanitize::{InjectionDetector, strip_delimiters};
+use crate::error::{BrowsingError, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use url::{Host, Url};
+
+/// Notes kept per site by default
+pub const MAX_NOTES_PER_SITE: usize = 20;
+
+/// Characters of a note kept; longer notes are truncated
+pub const MAX_NOTE_CHARS: usize = 500;
+
+/// Days a note is kept by default after it was last updated
+pub const DEFAULT_MAX_AGE_DAYS: i64 = 90;
+
+/// Sequence number of the temporary files written by this process
+static NEXT_TEMP: AtomicU64 = AtomicU64::new(0);
+
+/// A note about a site
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SiteNote {
+    /// Key the note was stored under; storing the same key again replaces it
+    pub key: String,
+    /// Note content
+    pub note: String,
+    /// When the note was last stored
+    pub updated: DateTime<Utc>,
+}
+
+/// Notes by host
+pub type SiteNotes = BTreeMap<String, Vec<SiteNote>>;
+
+/// File-backed notes about sites, shared across runs
+#[derive(Debug, Clone)]
+pub struct SiteMemory {
+    path: PathBuf,
+    max_notes: usize,
+    max_age: Option<Duration>,
+    detector: InjectionDetector,
+}
+
+impl SiteMemory {
+    /// Store notes in the JSON file at `path`, created on the first write
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            max_notes: MAX_NOTES_PER_SITE,
+            max_age: Some(Duration::days(DEFAULT_MAX_AGE_DAYS)),
+            detector: InjectionDetector::default(),
+        }
+    }
+
+    /// Set the number of notes kept per site
+    pub fn with_max_notes(mut self, max_notes: usize) -> Self {
+        self.max_notes = max_notes.max(1);
+        self
+    }
+
+    /// Set how long notes are kept after they were last updated; `None` keeps them forever
+    pub fn with_max_age(mut self, max_age: Option<Duration>) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Check notes with `detector` instead of the default patterns
+    pub fn with_injection_detector(mut self, detector: InjectionDetector) -> Self {
+        self.detector = detector;
+        self
+    }
+
+    /// Path of the backing file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Store `note` under `key` for the site of `url`, returning the site's host
+    ///
+    /// Fails if the key or note looks like instructions to the model.
+    pub fn remember(&self, url: &str, key: &str, note: &str) -> Result<String> {
+        let domain = site_host(url).ok_or_else(|| {
+            BrowsingError::Tool(format!("No site to remember notes for at '{url}'"))
+        })?;
+        let note = SiteNote {
+            key: key.to_string(),
+            note: note.chars().take(MAX_NOTE_CHARS).collect(),
+            updated: Utc::now(),
+        };
+        let findings = self.findings(&note);
+        if !findings.is_empty() {
+            return Err(BrowsingError::Tool(format!(
+                "Not storing '{}' in site memory: it looks like instructions (\"{}\")",
+                note.key,
+                findings.join("\", \"")
+            )));
+        }
+        let _lock = self.lock()?;
+        let mut sites = self.live_notes()?;
+        let notes = sites.entry(domain.clone()).or_default();
+        notes.retain(|existing| existing.key != note.key);
+        notes.push(note);
+        notes.sort_by_key(|note| note.updated);
+        let excess = notes.len().saturating_sub(self.max_notes);
+        notes.drain(..excess);
+        self.save(&sites)?;
+        Ok(domain)
+    }
+
+    /// Notes for the site of `url`, least recently updated first
+    pub fn notes_for(&self, url: &str) -> Result<Vec<SiteNote>> {
+        let Some(domain) = site_host(url) else {
+            return Ok(Vec::new());
+        };
+        Ok(self.live_notes()?.remove(&domain).unwrap_or_default())
+    }
+
+    /// Notes of every site, without expired ones
+    pub fn list(&self) -> Result<SiteNotes> {
+        self.live_notes()
+    }
+
+    /// Remove the notes of one site (given as a host or URL), or of every
+    /// site with `None`, returning the number of notes removed
+    pub fn clear(&self, site: Option<&str>) -> Result<usize> {
+        let _lock = self.lock()?;
+        let mut sites = self.live_notes()?;
+        let removed = match site {
+            Some(site) => {
+                let domain = site_host(site)
+                    .or_else(|| site_host(&format!("https://{site}")))
+                    .ok_or_else(|| BrowsingError::Tool(format!("Not a site: '{site}'")))?;
+                sites.remove(&domain).map_or(0, |notes| notes.len())
+            }
+            None => std::mem::take(&mut sites)
+                .into_values()
+                .map(|notes| notes.len())
+                .sum(),
+        };
+        self.save(&sites)?;
+        Ok(removed)
+    }
+
+    /// Prompt section with the notes for the site of `url`, headed with
+    /// `labels`; empty if there are none
+    ///
+    /// Notes that look like instructions are left out, and prompt delimiters
+    /// are stripped from the rest, as for page content.
+    pub fn prompt_section(&self, url: &str, labels: &PromptLabels) -> String {
+        let notes = match self.notes_for(url) {
+            Ok(notes) => notes,
+            Err(e) => {
+                tracing::warn!("Failed to read site memory {}: {}", self.path.display(), e);
+                return String::new();
+            }
+        };
+        let domain = site_host(url).unwrap_or_default();
+        let lines: Vec<String> = notes
+            .iter()
+            .filter(|n| {
+                let findings = self.findings(n);
+                if !findings.is_empty() {
+                    tracing::warn!(
+                        "Leaving site note '{}' for {} out of the prompt: it looks like instructions",
+                        n.key,
+                        domain
+                    );
+                }
+                findings.is_empty()
+            })
+            .map(|n| strip_delimiters(&format!("{}: {}", n.key, n.note)))
+            .collect();
+        if lines.is_empty() {
+            return String::new();
+        }
+        format!(
+            "{}:\n- {}\n\n",
+            labels.site_notes_for(&domain),
+            lines.join("\n- ")
+        )
+    }
+
+    /// Stored notes without expired ones; a missing file has no notes
+    fn live_notes(&self) -> Result<SiteNotes> {
+        let mut sites: SiteNotes = match std::fs::read_to_string(&self.path) {
+            Ok(json) if json.trim().is_empty() => SiteNotes::new(),
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => SiteNotes::new(),
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(max_age) = self.max_age {
+            let oldest = Utc::now() - max_age;
+            for notes in sites.values_mut() {
+                notes.retain(|note| note.updated >= oldest);
+            }
+        }
+        sites.retain(|_, notes| !notes.is_empty());
+        Ok(sites)
+    }
+
+    /// Snippets of the key and note of `note` flagged by the detector
+    fn findings(&self, note: &SiteNote) -> Vec<String> {
+        let mut findings = self.detector.detect(&note.key);
+        findings.extend(self.detector.detect(&note.note));
+        findings
+    }
+
+    /// Exclusive lock on the store for a read-modify-write, released on drop
+    fn lock(&self) -> Result<File> {
+        self.create_parent()?;
+        let lock = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.path.with_extension("json.lock"))?;
+        lock.lock()?;
+        Ok(lock)
+    }
+
+    fn create_parent(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(())
+    }
+
+    /// Write `sites` through a temporary file of this writer's own, so readers
+    /// never see a partial file
+    fn save(&self, sites: &SiteNotes) -> Result<()> {
+        self.create_parent()?;
+        let temp = self.path.with_extension(format!(
+            "json.{}.{}.tmp",
+            std::process::id(),
+            NEXT_TEMP.fetch_add(1, Ordering::Relaxed)
+        ));
+        let written = std::fs::write(&temp, serde_json::to_string_pretty(sites)?)
+            .and_then(|()| std::fs::rename(&temp, &self.path));
+        if written.is_err() {
+            let _ = std::fs::remove_file(&temp);
+        }
+        Ok(written?)
+    }
+}
+
+/// Host of `url` that its notes are kept under, e.g. `shop.example.co.uk`
+/// for `https://Shop.Example.co.uk./cart`
+///
+/// Lowercased and without a trailing dot; IP addresses are used as they are.
+/// Returns `None` for URLs without a host.
+pub fn site_host(url: &str) -> Option<String> {
+    let parsed = Url::parse(url).ok()?;
+    let host = match parsed.host()? {
+        Host::Domain(domain) => domain.trim_end_matches('.').to_ascii_lowercase(),
+        Host::Ipv4(ip) => ip.to_string(),
+        Host::Ipv6(ip) => ip.to_string(),
+    };
+    Some(host).filter(|host| !host.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_site_host() {
+        assert_eq!(
+            site_host("https://www.example.com/a").as_deref(),
+            Some("www.example.com")
+        );
+        assert_eq!(
+            site_host("https://shop.Example.co.uk/").as_deref(),
+            Some("shop.example.co.uk")
+        );
+        assert_eq!(
+            site_host("https://example.com./").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(
+            site_host("https://alice.github.io/").as_deref(),
+            Some("alice.github.io")
+        );
+        assert_eq!(
+            site_host("http://localhost:8080/").as_deref(),
+            Some("localhost")
+        );
+        assert_eq!(
+            site_host("http://127.0.0.1:8080/").as_deref(),
+            Some("127.0.0.1")
+        );
+        assert_eq!(site_host("about:blank"), None);
+        assert_eq!(site_host("not a url"), None);
+    }
+}
@@ -0,0 +1,71 @@
+//! Step events for watching an agent run as it happens
+//!
+//! [`Agent::run_stream`](crate::agent::Agent::run_stream) yields a
+//! [`StepEvent`] after each step and a final [`StepEvent::RunComplete`], with a
+//! [`StepEvent::HumanInputRequested`] whenever the model asks a human. Events
+//! pass through a bounded buffer of [`STEP_EVENT_BUFFER`] steps: when the
+//! consumer falls that far behind, the run waits before starting the next step.
+
+use crate::agent::human::HumanQuestion;
+use crate::agent::views::{ActionResult, AgentHistoryList, AgentOutput};
+use crate::llm::base::ChatInvokeUsage;
+use crate::tools::views::ActionModel;
+
+/// Number of step events a run gets ahead of its consumer before it waits
+pub const STEP_EVENT_BUFFER: usize = 4;
+
+/// Progress of an agent run
+#[derive(Debug, Clone)]
+pub enum StepEvent {
+    /// A step finished
+    Step(StepUpdate),
+    /// The model asked a human; answer through an
+    /// [`AgentHandle`](crate::agent::AgentHandle) taken before the run, from
+    /// another task or without awaiting, as the run only waits for the answer
+    /// while the stream is polled
+    HumanInputRequested(HumanQuestion),
+    /// The run ended; no more events follow
+    RunComplete {
+        /// History of every step, with the final usage summary
+        history: Box<AgentHistoryList>,
+        /// Why the run stopped early, if it failed
+        error: Option<String>,
+    },
+}
+
+/// What happened in one step
+#[derive(Debug, Clone)]
+pub struct StepUpdate {
+    /// Step number, starting at 1
+    pub step: u32,
+    /// One line on what the model meant to do, from its next goal or thinking
+    pub summary: String,
+    /// Actions the model chose
+    pub actions: Vec<ActionModel>,
+    /// Result of each action, followed by any tabs closed after the step
+    pub results: Vec<ActionResult>,
+    /// Screenshot taken in the step, e.g. of a failed action
+    pub screenshot_path: Option<String>,
+    /// Tokens the step's model call used, if the model reports usage
+    pub usage: Option<ChatInvokeUsage>,
+    /// URL of the current tab after the step
+    pub url: String,
+}
+
+impl StepUpdate {
+    /// Summary line for a model output: its next goal, or else its thinking
+    pub(crate) fn summarize(output: &AgentOutput) -> String {
+        output
+            .next_goal
+            .as_deref()
+            .or(output.thinking.as_deref())
+            .and_then(|text| text.lines().map(str::trim).find(|line| !line.is_empty()))
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    /// Whether every action succeeded
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(|result| result.error.is_none())
+    }
+}
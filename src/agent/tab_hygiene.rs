@@ -0,0 +1,140 @@
+//! Tab hygiene for long-running agents
+//!
+//! Tracks the last step each tab was touched and closes the least recently
+//! used ones once the limits in [`AgentSettings`] are exceeded. The current tab
+//! and tabs pinned via the `keep_tab` action are never closed.
+
+use crate::agent::views::{ActionResult, AgentSettings};
+use crate::browser::views::TabInfo;
+use crate::error::Result;
+use crate::traits::BrowserClient;
+use std::collections::{HashMap, HashSet};
+use tracing::info;
+
+/// Metadata key set by the `keep_tab` action with the pinned target ID
+pub const PINNED_TAB_METADATA_KEY: &str = "pinned_tab";
+
+/// Tracks tab usage across agent steps
+#[derive(Debug, Default)]
+pub struct TabTracker {
+    last_touched: HashMap<String, u32>,
+    pinned: HashSet<String>,
+}
+
+impl TabTracker {
+    /// Create an empty tracker
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark a tab as used at the given step
+    pub fn touch(&mut self, target_id: &str, step: u32) {
+        self.last_touched.insert(target_id.to_string(), step);
+    }
+
+    /// Pin a tab so it is never closed automatically
+    pub fn pin(&mut self, target_id: &str) {
+        self.pinned.insert(target_id.to_string());
+    }
+
+    /// Whether a tab is pinned
+    pub fn is_pinned(&self, target_id: &str) -> bool {
+        self.pinned.contains(target_id)
+    }
+
+    /// Pin any tabs requested by `keep_tab` results
+    pub fn pin_from_results(&mut self, results: &[ActionResult]) {
+        for result in results {
+            if let Some(target_id) = result
+                .metadata
+                .as_ref()
+                .and_then(|m| m.get(PINNED_TAB_METADATA_KEY))
+                .and_then(|v| v.as_str())
+            {
+                self.pin(target_id);
+            }
+        }
+    }
+
+    /// Decide which tabs to close, least recently used first
+    ///
+    /// Tabs seen for the first time count as touched at `step`, as does the
+    /// current tab. Tabs idle for `close_unused_tabs_after_steps` are closed, then
+    /// further LRU tabs until at most `max_open_tabs` remain.
+    pub fn select_tabs_to_close(
+        &mut self,
+        tabs: &[TabInfo],
+        current_target_id: Option<&str>,
+        step: u32,
+        settings: &AgentSettings,
+    ) -> Vec<TabInfo> {
+        // Forget tabs that no longer exist
+        self.last_touched
+            .retain(|id, _| tabs.iter().any(|t| &t.target_id == id));
+        self.pinned
+            .retain(|id| tabs.iter().any(|t| &t.target_id == id));
+
+        for tab in tabs {
+            self.last_touched
+                .entry(tab.target_id.clone())
+                .or_insert(step);
+        }
+        if let Some(current) = current_target_id {
+            self.touch(current, step);
+        }
+
+        let mut candidates: Vec<&TabInfo> = tabs
+            .iter()
+            .filter(|t| Some(t.target_id.as_str()) != current_target_id)
+            .filter(|t| !self.is_pinned(&t.target_id))
+            .collect();
+        candidates.sort_by_key(|t| self.last_touched[&t.target_id]);
+
+        let mut remaining = tabs.len();
+        let mut to_close = Vec::new();
+        for tab in candidates {
+            let idle = step.saturating_sub(self.last_touched[&tab.target_id]);
+            let is_stale = settings
+                .close_unused_tabs_after_steps
+                .is_some_and(|after| idle >= after);
+            let over_limit = settings
+                .max_open_tabs
+                .is_some_and(|max| remaining > max as usize);
+            if is_stale || over_limit {
+                remaining -= 1;
+                to_close.push(tab.clone());
+            }
+        }
+        to_close
+    }
+
+    /// Close tabs exceeding the configured limits and describe each closure
+    pub async fn enforce(
+        &mut self,
+        browser: &mut dyn BrowserClient,
+        step: u32,
+        settings: &AgentSettings,
+    ) -> Result<Vec<ActionResult>> {
+        if settings.max_open_tabs.is_none() && settings.close_unused_tabs_after_steps.is_none() {
+            return Ok(vec![]);
+        }
+
+        let tabs = browser.get_tabs().await?;
+        let current = browser.get_session_info().await.ok().map(|s| s.target_id);
+        let to_close = self.select_tabs_to_close(&tabs, current.as_deref(), step, settings);
+
+        let mut results = Vec::new();
+        for tab in to_close {
+            browser.close_tab(&tab.target_id).await?;
+            self.last_touched.remove(&tab.target_id);
+
+            let memory = format!("Closed unused tab #{} ({})", tab.tab_id(), tab.url);
+            info!("🧹 {}", memory);
+            results.push(ActionResult {
+                long_term_memory: Some(memory),
+                ..Default::default()
+            });
+        }
+        Ok(results)
+    }
+}
@@ -0,0 +1,156 @@
+//! Step and run timeouts
+//!
+//! A site that never answers or a slow model can hold a step forever, and a
+//! scheduled run with it. `step_timeout` in the settings bounds the model
+//! call and the actions of each step: when it expires the step fails, keeping
+//! the results of the actions that finished, and counts toward
+//! `max_failures`. `run_timeout` bounds the whole run: no step starts once it
+//! has passed, a step still running is cut short, and the run ends with
+//! [`StopReason::TimedOut`] in its history.
+//!
+//! Both are measured on a [`RunClock`] that stops while the agent waits for
+//! the human to answer `ask_human`, so a slow answer never times a run out.
+//! Time spent in the `wait` action counts like any other action.
+
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Shortest sleep between checks of a paused clock
+const PAUSED_POLL: Duration = Duration::from_millis(20);
+
+/// Why a run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// The model called `done`
+    Done,
+    /// The step limit was reached
+    MaxSteps,
+    /// A shutdown was requested
+    Shutdown,
+    /// The run timeout expired
+    TimedOut,
+}
+
+/// Time a run has been working, not counting pauses
+#[derive(Debug)]
+pub(crate) struct RunClock {
+    started: Instant,
+    /// Time paused so far, and when the current pause began
+    paused: Mutex<(Duration, Option<Instant>)>,
+}
+
+impl RunClock {
+    pub(crate) fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            paused: Mutex::new((Duration::ZERO, None)),
+        }
+    }
+
+    /// Time since the clock started, minus pauses
+    pub(crate) fn elapsed(&self) -> Duration {
+        let (total, since) = *self.paused.lock().unwrap();
+        let now = since.unwrap_or_else(Instant::now);
+        now.duration_since(self.started).saturating_sub(total)
+    }
+
+    fn is_paused(&self) -> bool {
+        self.paused.lock().unwrap().1.is_some()
+    }
+
+    /// Stop the clock until the returned guard is dropped
+    pub(crate) fn pause(&self) -> PauseGuard<'_> {
+        let mut paused = self.paused.lock().unwrap();
+        if paused.1.is_none() {
+            paused.1 = Some(Instant::now());
+        }
+        PauseGuard { clock: self }
+    }
+
+    fn resume(&self) {
+        let mut paused = self.paused.lock().unwrap();
+        if let Some(since) = paused.1.take() {
+            paused.0 += since.elapsed();
+        }
+    }
+
+    /// Run `future` until it finishes or the clock reaches `deadline`
+    ///
+    /// Returns `None` if the deadline came first, dropping the future, so the
+    /// action running then stops at its next await. State an action sets up
+    /// in the browser for its own duration, such as request interception
+    /// (see [`RuleHandle`](crate::browser::document_interception::RuleHandle)),
+    /// is undone when its guard is dropped.
+    pub(crate) async fn run_until<F: Future>(
+        &self,
+        deadline: Duration,
+        future: F,
+    ) -> Option<F::Output> {
+        tokio::pin!(future);
+        loop {
+            let remaining = deadline.saturating_sub(self.elapsed());
+            if remaining.is_zero() && !self.is_paused() {
+                return None;
+            }
+            tokio::select! {
+                output = &mut future => return Some(output),
+                _ = tokio::time::sleep(remaining.max(PAUSED_POLL)) => {}
+            }
+        }
+    }
+}
+
+/// Restarts a [`RunClock`] when dropped
+pub(crate) struct PauseGuard<'a> {
+    clock: &'a RunClock,
+}
+
+impl Drop for PauseGuard<'_> {
+    fn drop(&mut self) {
+        self.clock.resume();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_pauses_are_not_counted() {
+        let clock = RunClock::new();
+        {
+            let _paused = clock.pause();
+            tokio::time::advance(Duration::from_millis(60)).await;
+            assert_eq!(clock.elapsed(), Duration::ZERO);
+        }
+        tokio::time::advance(Duration::from_millis(20)).await;
+        assert_eq!(clock.elapsed(), Duration::from_millis(20));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_run_until_waits_out_pauses() {
+        let clock = RunClock::new();
+        let finished = clock
+            .run_until(Duration::from_millis(40), async {
+                let _paused = clock.pause();
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                "answered"
+            })
+            .await;
+        assert_eq!(finished, Some("answered"));
+        assert_eq!(clock.elapsed(), Duration::ZERO);
+
+        let timed_out = clock
+            .run_until(
+                Duration::from_millis(30),
+                tokio::time::sleep(Duration::from_secs(5)),
+            )
+            .await;
+        assert_eq!(timed_out, None);
+        assert_eq!(clock.elapsed(), Duration::from_millis(30));
+    }
+}
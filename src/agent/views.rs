@@ -1,7 +1,22 @@
 //! Agent view types and data structures
 
+use crate::agent::assertions::AssertionSummary;
+use crate::agent::determinism::Determinism;
+use crate::agent::page_group::PageGroup;
+use crate::agent::prompts::SectionName;
+use crate::agent::report::ReportFormat;
+use crate::agent::run_id::RunIdHint;
+use crate::agent::timeouts::StopReason;
+use crate::browser::{DEFAULT_REDIRECT_GRACE_MS, NewWindowHandling};
+use crate::dom::{NodeCategory, SerializationOptions, SnapshotOptions};
+use crate::llm::base::{ChatInvokeUsage, LlmCall};
+use crate::tokens::TokenPricing;
+use crate::tools::evaluate::EvaluatePolicy;
+use crate::tools::views::ActionModel;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 /// Configuration options for the Agent
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +37,9 @@ pub struct AgentSettings {
     pub override_system_message: Option<String>,
     /// Additional text to extend the system message
     pub extend_system_message: Option<String>,
+    /// Replacement text for individual system prompt sections
+    #[serde(default)]
+    pub override_sections: HashMap<SectionName, String>,
     /// List of attributes to include in DOM processing
     pub include_attributes: Option<Vec<String>>,
     /// Maximum number of actions per step
@@ -40,10 +58,110 @@ pub struct AgentSettings {
     pub include_tool_call_examples: bool,
     /// Timeout for LLM calls in seconds
     pub llm_timeout: u32,
-    /// Timeout for each step in seconds
+    /// Seconds the model call and actions of a step may take before the
+    /// step fails; 0 for no limit (see [`crate::agent::timeouts`])
     pub step_timeout: u32,
+    /// Seconds the whole run may take before it stops with
+    /// [`StopReason::TimedOut`](crate::agent::timeouts::StopReason); no limit by default
+    #[serde(default)]
+    pub run_timeout: Option<u32>,
     /// Whether to provide final response after failure
     pub final_response_after_failure: bool,
+    /// Path of a JSON Lines file to append an audit entry for every action to
+    pub log_file: Option<PathBuf>,
+    /// Maximum number of open tabs before the least recently used are closed
+    pub max_open_tabs: Option<u32>,
+    /// Close tabs that have not been used for this many steps
+    pub close_unused_tabs_after_steps: Option<u32>,
+    /// Background information for the model, added to the system message
+    #[serde(default)]
+    pub task_context: HashMap<String, String>,
+    /// Element categories to include in the page state; empty includes every element
+    #[serde(default)]
+    pub dom_node_categories: Vec<NodeCategory>,
+    /// Warn the model when page content looks like injected instructions
+    #[serde(default = "default_detect_prompt_injection")]
+    pub detect_prompt_injection: bool,
+    /// Regular expressions or keywords flagged as injected instructions;
+    /// empty uses [`DEFAULT_INJECTION_PATTERNS`](crate::agent::sanitize::DEFAULT_INJECTION_PATTERNS)
+    #[serde(default)]
+    pub prompt_injection_patterns: Vec<String>,
+    /// What to do when a click opens a new window or tab
+    #[serde(default)]
+    pub new_window_handling: NewWindowHandling,
+    /// Click through pointer events (enter, move, down, up with pressure)
+    /// instead of plain mouse events, for pages that handle pointer input
+    #[serde(default)]
+    pub pointer_events_mode: bool,
+    /// Save a viewport screenshot when an action fails, at most one per step
+    #[serde(default)]
+    pub screenshot_on_error: bool,
+    /// Directory for run artifacts such as error screenshots and downloaded
+    /// images; defaults to `browsing-run-<run id>` in the system temp directory
+    #[serde(default)]
+    pub artifacts_dir: Option<PathBuf>,
+    /// Send the run ID to visited sites so their logs can be matched to the run
+    #[serde(default)]
+    pub run_id_hint: RunIdHint,
+    /// What the evaluate action may run, instead of the tools' policy (by
+    /// default [`EvaluatePolicy::Full`], or that of [`Agent::with_tools_config`])
+    ///
+    /// [`Agent::with_tools_config`]: crate::agent::service::Agent::with_tools_config
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evaluate_policy: Option<EvaluatePolicy>,
+    /// Milliseconds to watch for a page redirecting itself (meta refresh or
+    /// script) after each step's actions and before reading the page state;
+    /// 0 does not watch
+    #[serde(default = "default_redirect_grace_ms")]
+    pub redirect_grace_ms: u64,
+    /// Seed `Math.random`, freeze the clock and disable animations in the
+    /// page so runs can be reproduced
+    #[serde(default)]
+    pub determinism: Option<Determinism>,
+    /// Language the model should respond in, e.g. `ja` or `de`; the state
+    /// message's labels are localized where translations exist
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Seconds `ask_human` waits for an answer before carrying on without one
+    #[serde(default = "default_human_input_timeout")]
+    pub human_input_timeout: u32,
+    /// How much reasoning the model is asked to write in its `thinking` field
+    #[serde(default)]
+    pub reasoning_effort: ReasoningEffort,
+    /// Token prices of the agent's model, for estimating the run's cost;
+    /// without them its calls have no cost
+    #[serde(default)]
+    pub token_pricing: Option<TokenPricing>,
+    /// Token prices of other models, e.g. the extraction model, keyed by
+    /// `provider/model` such as `anthropic/claude-3-5-haiku`. Calls to a
+    /// model without prices have no cost.
+    #[serde(default)]
+    pub model_pricing: HashMap<String, TokenPricing>,
+    /// Write a per-step report in this format to the artifacts directory at
+    /// the end of the run (see [`AgentHistoryList::export_report`])
+    #[serde(default)]
+    pub report_format: Option<ReportFormat>,
+    /// What the DOM snapshot captures each step: fewer computed styles for
+    /// speed, or colors for contrast and occlusion checks
+    #[serde(default)]
+    pub snapshot_options: SnapshotOptions,
+    /// How the page state is written for the model, e.g. collapsing runs of
+    /// repeated siblings; [`AgentSettings::dom_node_categories`] takes
+    /// precedence over its `node_categories` when not empty
+    #[serde(default)]
+    pub serialization_options: SerializationOptions,
+}
+
+fn default_detect_prompt_injection() -> bool {
+    true
+}
+
+fn default_redirect_grace_ms() -> u64 {
+    DEFAULT_REDIRECT_GRACE_MS
+}
+
+fn default_human_input_timeout() -> u32 {
+    crate::agent::human::DEFAULT_HUMAN_INPUT_TIMEOUT_SECS
 }
 
 /// Vision mode options for the agent
@@ -67,6 +185,20 @@ pub enum VisionDetailLevel {
     High,
 }
 
+/// How much reasoning the model writes each step, trading insight for output tokens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReasoningEffort {
+    /// No `thinking` field; the goal fields carry the reasoning
+    Minimal,
+    /// A `thinking` field of at most
+    /// [`MAX_THINKING_SENTENCES`](crate::agent::prompts::MAX_THINKING_SENTENCES) sentences
+    #[default]
+    Normal,
+    /// A `thinking` field of any length
+    Verbose,
+}
+
 impl Default for AgentSettings {
     fn default() -> Self {
         Self {
@@ -78,6 +210,7 @@ impl Default for AgentSettings {
             generate_gif: false,
             override_system_message: None,
             extend_system_message: None,
+            override_sections: HashMap::new(),
             include_attributes: None,
             max_actions_per_step: 4,
             use_thinking: true,
@@ -88,7 +221,31 @@ impl Default for AgentSettings {
             include_tool_call_examples: false,
             llm_timeout: 60,
             step_timeout: 180,
+            run_timeout: None,
             final_response_after_failure: true,
+            log_file: None,
+            max_open_tabs: None,
+            close_unused_tabs_after_steps: None,
+            task_context: HashMap::new(),
+            dom_node_categories: Vec::new(),
+            detect_prompt_injection: true,
+            prompt_injection_patterns: Vec::new(),
+            new_window_handling: NewWindowHandling::Ignore,
+            pointer_events_mode: false,
+            screenshot_on_error: false,
+            artifacts_dir: None,
+            run_id_hint: RunIdHint::None,
+            evaluate_policy: None,
+            redirect_grace_ms: DEFAULT_REDIRECT_GRACE_MS,
+            determinism: None,
+            language: None,
+            human_input_timeout: default_human_input_timeout(),
+            reasoning_effort: ReasoningEffort::Normal,
+            token_pricing: None,
+            model_pricing: HashMap::new(),
+            report_format: None,
+            snapshot_options: SnapshotOptions::default(),
+            serialization_options: SerializationOptions::default(),
         }
     }
 }
@@ -116,6 +273,9 @@ pub struct AgentState {
     pub session_initialized: bool,
     /// Whether there is a follow-up task
     pub follow_up_task: bool,
+    /// What each open tab held when it was last current
+    #[serde(default)]
+    pub page_group: PageGroup,
 }
 
 impl Default for AgentState {
@@ -131,6 +291,7 @@ impl Default for AgentState {
             stopped: false,
             session_initialized: false,
             follow_up_task: false,
+            page_group: PageGroup::default(),
         }
     }
 }
@@ -181,8 +342,15 @@ pub struct ActionResult {
     pub attachments: Option<Vec<String>>,
     /// List of images
     pub images: Option<Vec<ImageData>>,
-    /// Long term memory content
+    /// Long term memory content: concise, shown to the model in later prompts
     pub long_term_memory: Option<String>,
+    /// Whether `long_term_memory` is added to the agent's long-term memory
+    #[serde(default = "default_include_in_memory")]
+    pub include_in_memory: bool,
+    /// Diagnostics for the history, logs and other artifacts; never put in a
+    /// prompt, so it can be as verbose as is useful
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug_info: Option<String>,
     /// Extracted content from the page
     pub extracted_content: Option<String>,
     /// Whether to include extracted content only once
@@ -210,6 +378,8 @@ impl Default for ActionResult {
             attachments: None,
             images: None,
             long_term_memory: None,
+            include_in_memory: true,
+            debug_info: None,
             extracted_content: None,
             include_extracted_content_only_once: false,
             metadata: None,
@@ -217,6 +387,10 @@ impl Default for ActionResult {
     }
 }
 
+fn default_include_in_memory() -> bool {
+    true
+}
+
 impl ActionResult {
     /// Creates a success result with extracted content and long-term memory.
     /// Reduces repetition across action handlers.
@@ -229,6 +403,12 @@ impl ActionResult {
         }
     }
 
+    /// Adds diagnostics that are kept out of prompts
+    pub fn with_debug_info(mut self, debug_info: impl Into<String>) -> Self {
+        self.debug_info = Some(debug_info.into());
+        self
+    }
+
     /// Creates a done result indicating task completion.
     pub fn done(text: impl Into<String>) -> Self {
         Self {
@@ -249,6 +429,18 @@ pub struct StepMetadata {
     pub step_end_time: f64,
     /// Step number
     pub step_number: u32,
+    /// Long-term memory entries evicted during the step
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub memory_evictions: Vec<String>,
+    /// Tokens of the model call that decided the step's actions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<ChatInvokeUsage>,
+    /// Estimated cost of that call, if token prices are set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+    /// Every model call of the step, with the model and what it was for
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub llm_calls: Vec<LlmCall>,
 }
 
 impl StepMetadata {
@@ -256,6 +448,28 @@ impl StepMetadata {
     pub fn duration_seconds(&self) -> f64 {
         self.step_end_time - self.step_start_time
     }
+
+    /// Estimated cost of all the step's model calls, if token prices are set
+    pub fn total_cost(&self) -> Option<f64> {
+        if self.llm_calls.is_empty() {
+            return self.cost;
+        }
+        self.llm_calls
+            .iter()
+            .filter_map(|call| call.cost)
+            .reduce(|a, b| a + b)
+    }
+
+    /// Models called during the step, as `provider/model`, in order of first use
+    pub fn models(&self) -> Vec<String> {
+        let mut models: Vec<String> = Vec::new();
+        for key in self.llm_calls.iter().map(LlmCall::model_key) {
+            if !models.contains(&key) {
+                models.push(key);
+            }
+        }
+        models
+    }
 }
 
 /// Agent's reasoning process
@@ -272,18 +486,22 @@ pub struct AgentBrain {
 }
 
 /// Agent output from LLM
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Its JSON schema is what the system prompt shows the model, and replies are
+/// checked against it; see [`crate::agent::output_schema`].
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct AgentOutput {
-    /// Thinking process from the LLM
+    /// Reasoning about the page and the task before acting
     pub thinking: Option<String>,
-    /// Evaluation of the previous goal
+    /// Whether the previous step achieved its goal, and why
     pub evaluation_previous_goal: Option<String>,
-    /// Memory content
+    /// Progress and facts to keep in mind for the next steps
     pub memory: Option<String>,
-    /// Next goal to achieve
+    /// What the actions of this step should achieve
     pub next_goal: Option<String>,
-    /// Actions to be performed
-    pub action: Vec<serde_json::Value>, // ActionModel - will be properly typed later
+    /// Actions to perform, in order
+    pub action: Vec<ActionModel>,
 }
 
 impl AgentOutput {
@@ -308,7 +526,11 @@ impl AgentOutput {
 /// History item for agent actions
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentHistory {
-    /// Output from the model
+    /// Output from the model; `None` if its reply was not valid output
+    #[serde(
+        default,
+        deserialize_with = "crate::agent::output_schema::deserialize_model_output"
+    )]
     pub model_output: Option<AgentOutput>,
     /// Result of actions taken
     pub result: Vec<ActionResult>,
@@ -323,10 +545,32 @@ pub struct AgentHistory {
 /// List of AgentHistory messages
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentHistoryList {
+    /// ID of the run that produced this history
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    /// Seed of a deterministic run, to repeat it with the same page behavior
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Version of the model output format; 0 for histories saved before it
+    /// was versioned
+    #[serde(default)]
+    pub output_version: u32,
     /// List of agent history items
     pub history: Vec<AgentHistory>,
     /// Token usage summary
     pub usage: Option<crate::tokens::views::UsageSummary>,
+    /// Crate and browser versions used for the run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<crate::version::EnvironmentInfo>,
+    /// Assertions checked during the run
+    #[serde(default, skip_serializing_if = "AssertionSummary::is_empty")]
+    pub assertions: AssertionSummary,
+    /// Per-step report written at the end of the run, if one was asked for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report_path: Option<PathBuf>,
+    /// Why the last run ended; `None` for histories saved before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<StopReason>,
 }
 
 impl AgentHistoryList {
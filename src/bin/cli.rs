@@ -1,8 +1,7 @@
 //! CLI interface for browsing
 
 use anyhow::Result;
-use browsing::{Browser, Config};
-use browsing::browser::profile::BrowserProfile;
+use browsing::{Browser, ConfigBuilder};
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 use tracing::info;
@@ -20,6 +19,12 @@ struct Cli {
 
     #[arg(short, long, global = true)]
     verbose: bool,
+
+    #[arg(long, global = true, help = "Append version and OS details to error messages")]
+    debug_errors: bool,
+
+    #[arg(long, global = true, help = "Reconnect to the browser if the CDP connection drops")]
+    reconnect_on_disconnect: bool,
 }
 
 #[derive(Subcommand)]
@@ -32,8 +37,8 @@ enum Commands {
         #[arg(short, long, help = "Starting URL")]
         url: Option<String>,
 
-        #[arg(long, help = "Maximum number of steps", default_value = "100")]
-        max_steps: u32,
+        #[arg(long, help = "Maximum number of steps (default: 100)")]
+        max_steps: Option<u32>,
 
         #[arg(long, help = "Run browser in headless mode")]
         headless: bool,
@@ -70,20 +75,58 @@ async fn main() -> Result<()> {
         }
     }
 
-    let _config = if let Some(config_path) = cli.config {
-        Config::load_from_file(config_path)?
-    } else {
-        Config::from_env()
-    };
-
-    match cli.command {
+    // Command-line flags take precedence over the environment, which takes
+    // precedence over the config file
+    let mut config_args = Vec::new();
+    match &cli.command {
         Commands::Run {
-            task,
-            url,
-            max_steps: _,
+            max_steps,
             headless,
-            vision: _,
+            vision,
+            ..
         } => {
+            if let Some(max_steps) = max_steps {
+                config_args.push(format!("--max-steps={max_steps}"));
+            }
+            if *headless {
+                config_args.push("--headless".to_string());
+            }
+            if *vision {
+                config_args.push("--vision".to_string());
+            }
+        }
+        Commands::Launch {
+            headless,
+            user_data_dir,
+        } => {
+            if *headless {
+                config_args.push("--headless".to_string());
+            }
+            if let Some(dir) = user_data_dir {
+                config_args.push(format!("--user-data-dir={}", dir.display()));
+            }
+        }
+        Commands::Connect { .. } => {}
+    }
+    if cli.debug_errors {
+        config_args.push("--debug-errors".to_string());
+    }
+    if cli.reconnect_on_disconnect {
+        config_args.push("--reconnect-on-disconnect".to_string());
+    }
+
+    let mut builder = ConfigBuilder::new();
+    if let Some(config_path) = &cli.config {
+        builder = builder.from_file(config_path);
+    }
+    let config = builder.from_env().from_args(&config_args).build()?;
+    browsing::error::set_debug_errors(config.debug_errors);
+    if cli.verbose {
+        config.print_effective_config();
+    }
+
+    match cli.command {
+        Commands::Run { task, url, .. } => {
             info!("Starting autonomous browsing task: {}", task);
 
             println!("\n=== Autonomous Browsing ===");
@@ -93,10 +136,8 @@ async fn main() -> Result<()> {
             println!("See docs/LIBRARY_USAGE.md for details.");
             
             // For now, just demonstrate browser capabilities
-            let mut profile = BrowserProfile::default();
-            profile.headless = Some(headless);
-            
-            let mut browser = Browser::new(profile);
+            let mut browser = Browser::new(config.browser_profile.clone())
+                .with_reconnect_on_disconnect(config.reconnect_on_disconnect);
             browser.start().await?;
             info!("Browser launched successfully");
 
@@ -115,17 +156,9 @@ async fn main() -> Result<()> {
             let _ = browser.stop().await;
         }
 
-        Commands::Launch {
-            headless,
-            user_data_dir,
-        } => {
-            let mut profile = BrowserProfile::default();
-            profile.headless = Some(headless);
-            if let Some(dir) = user_data_dir {
-                profile.user_data_dir = Some(dir);
-            }
-
-            let mut browser = Browser::new(profile);
+        Commands::Launch { .. } => {
+            let mut browser = Browser::new(config.browser_profile.clone())
+                .with_reconnect_on_disconnect(config.reconnect_on_disconnect);
             browser.start().await?;
 
             println!("Browser launched successfully!");
@@ -138,8 +171,10 @@ async fn main() -> Result<()> {
 
         Commands::Connect { cdp_url } => {
             info!("Connecting to browser at: {}", cdp_url);
-            let profile = BrowserProfile::default();
-            let mut browser = Browser::new(profile).with_cdp_url(cdp_url.clone());
+            let mut browser =
+                Browser::new(config.browser_profile.clone())
+                    .with_cdp_url(cdp_url.clone())
+                    .with_reconnect_on_disconnect(config.reconnect_on_disconnect);
             browser.start().await?;
 
             println!("Connected to browser successfully!");
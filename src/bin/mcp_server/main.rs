@@ -1,14 +1,20 @@
-//! Lightweight MCP server: browse, navigate, get links, follow links,
+//! Lightweight MCP server: browse, navigate, get links, follow links (both
+//! optionally returning a viewport screenshot),
 //! list content (links/images), get/save content, screenshot (full or element),
-//! generate_sitemap. Lazy browser init. RwLock enables parallel operations.
+//! generate_sitemap, and the current page state as subscribable resources.
+//! Lazy browser init. RwLock enables parallel operations.
 
 mod params;
+mod preview;
+mod resources;
 mod service;
 mod sitemap;
+mod write_roots;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     browsing::init();
+    browsing::error::set_debug_errors(browsing::config::Config::from_env().debug_errors);
     let service = service::BrowsingService::new();
     let browser_guard = std::sync::Arc::clone(&service.browser);
     let transport = (tokio::io::stdin(), tokio::io::stdout());
@@ -7,6 +7,29 @@ use serde::Deserialize;
 pub struct NavigateParams {
     #[schemars(description = "URL to navigate to")]
     pub url: String,
+    #[schemars(description = "Referrer to send with the navigation request")]
+    pub referrer: Option<String>,
+    #[schemars(description = "Extra headers sent with this navigation request only")]
+    pub headers: Option<std::collections::HashMap<String, String>>,
+    #[schemars(description = "Wait for 'domcontentloaded' or 'load' before returning (default: none)")]
+    pub wait_until: Option<String>,
+    #[schemars(description = "With wait_until, how long in ms to keep watching for the page to redirect itself with a meta refresh or script (default: 1500, 0 to not watch)")]
+    pub redirect_grace_ms: Option<u64>,
+    #[serde(flatten)]
+    pub preview: PreviewParams,
+}
+
+/// Screenshot returned with the result of a tool that changes the page
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct PreviewParams {
+    #[schemars(description = "Also return a screenshot of the viewport, taken once the page has loaded (default: false)")]
+    pub include_screenshot: Option<bool>,
+    #[schemars(description = "Largest width of the screenshot in pixels; the viewport is scaled down to fit (default: 800)")]
+    pub screenshot_max_width: Option<u32>,
+    #[schemars(description = "Largest height of the screenshot in pixels (default: 600)")]
+    pub screenshot_max_height: Option<u32>,
+    #[schemars(description = "Screenshot format: png, jpeg or webp (default: png)")]
+    pub screenshot_format: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -15,12 +38,30 @@ pub struct FollowLinkParams {
     pub index: Option<u32>,
     #[schemars(description = "Or specify URL directly")]
     pub url: Option<String>,
+    #[serde(flatten)]
+    pub preview: PreviewParams,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WaitForParams {
+    #[schemars(description = "CSS selector of the elements to wait for")]
+    pub selector: Option<String>,
+    #[schemars(description = "Text the elements must contain (case-insensitive); alone, matches the innermost elements containing it")]
+    pub text: Option<String>,
+    #[schemars(description = "State to wait for: visible, hidden (none visible, or gone) or attached (default: visible)")]
+    pub state: Option<String>,
+    #[schemars(description = "Time to wait in ms before failing (default: 10000)")]
+    pub timeout_ms: Option<u64>,
+    #[schemars(description = "Tab to wait in, by the last 4 characters of its target ID (default: the current tab)")]
+    pub tab_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct GetContentParams {
     #[schemars(description = "Max characters to return")]
     pub max_chars: Option<u32>,
+    #[schemars(description = "Scroll through the page first so lazy-loaded content is included")]
+    pub trigger_lazy_load: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -31,6 +72,46 @@ pub struct SaveContentParams {
     pub content_type: String,
     #[schemars(description = "For image: index from list_content.images")]
     pub image_index: Option<u32>,
+    #[schemars(description = "Replace the file if it exists (default: false)")]
+    pub overwrite: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SavePageParams {
+    #[schemars(description = "Path of the .mhtml file to write (default: a new file in the first write root)")]
+    pub path: Option<String>,
+    #[schemars(description = "Replace the file if it exists (default: false)")]
+    pub overwrite: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct MeasureWebVitalsParams {
+    #[schemars(description = "Max time in ms to wait for the page to become interactive (default: 15000)")]
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RunAuditParams {
+    #[schemars(description = "Issues to report: accessibility, csp, cookies, or all (default: all)")]
+    pub audit_type: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ListContentParams {
+    #[schemars(description = "Skip images narrower than this, rendered or natural (default: 2, which skips tracking pixels)")]
+    pub min_width: Option<u32>,
+    #[schemars(description = "Skip images shorter than this, rendered or natural (default: 2)")]
+    pub min_height: Option<u32>,
+    #[schemars(description = "Include images whose src is a data: URI (default: false)")]
+    pub include_data_uris: Option<bool>,
+    #[schemars(description = "Save the largest images to save_dir")]
+    pub download: Option<bool>,
+    #[schemars(description = "Number of images to download (default: 5)")]
+    pub download_limit: Option<u32>,
+    #[schemars(description = "Directory to save downloaded images in (default: a new directory in the first write root)")]
+    pub save_dir: Option<String>,
+    #[schemars(description = "Replace image files that already exist in save_dir (default: false)")]
+    pub overwrite: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -53,10 +134,34 @@ pub struct GenerateSitemapParams {
     pub content_preview_chars: Option<u32>,
     #[schemars(description = "Path to save sitemap JSON file")]
     pub save_path: Option<String>,
+    #[schemars(description = "Replace the save_path file if it exists (default: false)")]
+    pub overwrite: Option<bool>,
     #[schemars(description = "Delay in ms between page navigations")]
     pub delay_ms: Option<u64>,
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct BuildLinkGraphParams {
+    #[schemars(description = "Maximum pages to crawl (default: 30)")]
+    pub max_pages: Option<u32>,
+    #[schemars(description = "Maximum link depth from the current page (default: 3)")]
+    pub max_depth: Option<u32>,
+    #[schemars(description = "Hosts besides the current page's whose links are followed")]
+    pub allowed_domains: Option<Vec<String>>,
+    #[schemars(description = "Only follow links to the current page's host and allowed_domains (default: true)")]
+    pub same_domain_only: Option<bool>,
+    #[schemars(description = "Delay in ms between page navigations (default: 800)")]
+    pub delay_ms: Option<u64>,
+    #[schemars(description = "Also return the graph in Graphviz DOT (default: false)")]
+    pub include_dot: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CancelSitemapParams {
+    #[schemars(description = "Job ID returned in generate_sitemap progress and results")]
+    pub job_id: String,
+}
+
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct ScreenshotParams {
     #[schemars(description = "Capture full scrollable page")]
@@ -65,4 +170,16 @@ pub struct ScreenshotParams {
     pub selector: Option<String>,
     #[schemars(description = "If selector matches multiple elements, use this index")]
     pub element_index: Option<u32>,
+    #[schemars(description = "For full_page: scroll through the page first so lazy-loaded images are captured")]
+    pub trigger_lazy_load: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct MonitorPageVisuallyParams {
+    #[schemars(description = "Delay in ms between screenshots (default: 1000, min: 100)")]
+    pub interval_ms: Option<u64>,
+    #[schemars(description = "Number of screenshots to take (default: 5, max: 100)")]
+    pub count: Option<u32>,
+    #[schemars(description = "Directory to save screenshots in (default: a new directory in the first write root)")]
+    pub save_dir: Option<String>,
 }
@@ -0,0 +1,175 @@
+//! Viewport screenshots returned with the results of navigating tools
+//!
+//! Clients that show the page call `screenshot` after every navigation.
+//! With `include_screenshot`, `navigate` and `follow_link` take a downscaled
+//! viewport screenshot themselves, once the page has loaded, and return it
+//! as an image content item after the structured result.
+
+use super::params::PreviewParams;
+use browsing::actor::{Page, PreviewOptions, ViewportPreview};
+use rmcp::model::{CallToolResult, Content, ErrorData as McpError};
+use serde_json::Value;
+
+/// Formats a preview can be encoded in
+const PREVIEW_FORMATS: &[&str] = &["png", "jpeg", "webp"];
+
+impl PreviewParams {
+    /// Options of the screenshot asked for, `None` if none was
+    pub fn options(&self) -> Result<Option<PreviewOptions>, McpError> {
+        if !self.include_screenshot.unwrap_or(false) {
+            return Ok(None);
+        }
+        let defaults = PreviewOptions::default();
+        let mut options = defaults.clone().with_max_size(
+            self.screenshot_max_width.unwrap_or(defaults.max_width),
+            self.screenshot_max_height.unwrap_or(defaults.max_height),
+        );
+        if options.max_width == 0 || options.max_height == 0 {
+            return Err(McpError::invalid_params(
+                "screenshot_max_width and screenshot_max_height must be positive",
+                None,
+            ));
+        }
+        if let Some(format) = &self.screenshot_format {
+            if !PREVIEW_FORMATS.contains(&format.as_str()) {
+                return Err(McpError::invalid_params(
+                    format!("Invalid screenshot_format '{format}': expected png, jpeg or webp"),
+                    None,
+                ));
+            }
+            options = options.with_format(format);
+        }
+        Ok(Some(options))
+    }
+}
+
+/// Take the preview `options` asks for on `page`
+///
+/// The tool already did its work, so a failed capture is returned as a
+/// message to report rather than failing the call.
+pub async fn capture(
+    page: &Page,
+    options: Option<&PreviewOptions>,
+) -> Option<Result<ViewportPreview, String>> {
+    let options = options?;
+    Some(page.viewport_preview(options).await.map_err(|e| {
+        tracing::warn!("Failed to take the viewport screenshot: {}", e);
+        e.to_string()
+    }))
+}
+
+/// Structured `result`, followed by the preview's image if one was taken
+///
+/// A preview that failed is reported as `screenshot_error` in the result.
+pub fn tool_result(
+    mut result: Value,
+    preview: Option<Result<ViewportPreview, String>>,
+) -> CallToolResult {
+    match preview {
+        Some(Ok(preview)) => {
+            result["screenshot"] = serde_json::json!({
+                "mime_type": preview.mime_type,
+                "width": preview.width,
+                "height": preview.height,
+            });
+            let mut tool_result = CallToolResult::structured(result);
+            tool_result
+                .content
+                .push(Content::image(preview.data, preview.mime_type));
+            tool_result
+        }
+        Some(Err(error)) => {
+            result["screenshot_error"] = serde_json::json!(error);
+            CallToolResult::structured(result)
+        }
+        None => CallToolResult::structured(result),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn params(value: Value) -> PreviewParams {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_options_only_when_asked() {
+        assert_eq!(params(json!({})).options().unwrap(), None);
+        assert_eq!(
+            params(json!({ "include_screenshot": false, "screenshot_format": "jpeg" }))
+                .options()
+                .unwrap(),
+            None
+        );
+
+        let options = params(json!({
+            "include_screenshot": true,
+            "screenshot_max_width": 400,
+            "screenshot_format": "jpeg"
+        }))
+        .options()
+        .unwrap()
+        .unwrap();
+        assert_eq!(options.max_width, 400);
+        assert_eq!(options.max_height, PreviewOptions::default().max_height);
+        assert_eq!(options.mime_type(), "image/jpeg");
+
+        assert!(
+            params(json!({ "include_screenshot": true, "screenshot_format": "gif" }))
+                .options()
+                .is_err()
+        );
+        assert!(
+            params(json!({ "include_screenshot": true, "screenshot_max_height": 0 }))
+                .options()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_result_has_structured_json_and_image() {
+        let preview = ViewportPreview {
+            data: "iVBORw0KGgo=".to_string(),
+            mime_type: "image/png".to_string(),
+            width: Some(800),
+            height: Some(450),
+        };
+        let result = tool_result(
+            json!({ "success": true, "url": "https://example.com/" }),
+            Some(Ok(preview)),
+        );
+
+        let structured = result.structured_content.as_ref().unwrap();
+        assert_eq!(structured["url"], "https://example.com/");
+        assert_eq!(structured["screenshot"]["width"], 800);
+        assert_eq!(result.content.len(), 2);
+        let text = result.content[0].as_text().unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&text.text).unwrap(),
+            *structured
+        );
+        let image = result.content[1].as_image().unwrap();
+        assert_eq!(image.data, "iVBORw0KGgo=");
+        assert_eq!(image.mime_type, "image/png");
+    }
+
+    #[test]
+    fn test_result_without_preview() {
+        let result = tool_result(json!({ "success": true }), None);
+        assert_eq!(result.content.len(), 1);
+        assert!(result.content[0].as_image().is_none());
+
+        let result = tool_result(
+            json!({ "success": true }),
+            Some(Err("Screenshot failed".to_string())),
+        );
+        assert_eq!(result.content.len(), 1);
+        assert_eq!(
+            result.structured_content.unwrap()["screenshot_error"],
+            "Screenshot failed"
+        );
+    }
+}
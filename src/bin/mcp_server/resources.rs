@@ -0,0 +1,306 @@
+//! Page state exposed as MCP resources
+//!
+//! `browsing://current/state` is the serialized DOM the agent sees and
+//! `browsing://current/selector_map` the JSON map from element index to element.
+//! Both are captured after each navigation; subscribed clients are sent
+//! `notifications/resources/updated` instead of polling a tool.
+
+use browsing::Browser;
+use browsing::dom::{
+    DOMProcessorImpl, EMPTY_PAGE_STATE, SerializedDOMState, SerializedElement, is_blank_page_url,
+};
+use browsing::traits::{BrowserClient, DOMProcessor};
+use rmcp::model::{AnnotateAble, ErrorData as McpError, RawResource, Resource, ResourceContents};
+use rmcp::service::{Peer, RoleServer};
+use std::collections::{BTreeMap, HashSet};
+
+/// Serialized DOM of the current page
+pub const STATE_URI: &str = "browsing://current/state";
+
+/// Selector map of the current page
+pub const SELECTOR_MAP_URI: &str = "browsing://current/selector_map";
+
+/// Maximum characters of each resource's contents
+pub const MAX_RESOURCE_CHARS: usize = 100_000;
+
+/// Latest page state and the clients to notify when it changes
+#[derive(Default)]
+pub struct PageResources {
+    state: Option<String>,
+    selector_map: Option<String>,
+    subscriptions: HashSet<String>,
+    peer: Option<Peer<RoleServer>>,
+}
+
+impl PageResources {
+    /// Resources offered by the server
+    pub fn list() -> Vec<Resource> {
+        let resource = |uri: &str, name: &str, description: &str, mime_type: &str| {
+            let mut raw = RawResource::new(uri, name);
+            raw.description = Some(description.to_string());
+            raw.mime_type = Some(mime_type.to_string());
+            raw.no_annotation()
+        };
+        vec![
+            resource(
+                STATE_URI,
+                "Current page state",
+                "Serialized DOM of the current page, as given to the agent",
+                "text/plain",
+            ),
+            resource(
+                SELECTOR_MAP_URI,
+                "Current selector map",
+                "Interactive elements of the current page by index, as JSON",
+                "application/json",
+            ),
+        ]
+    }
+
+    /// Contents of `uri` as last captured, or `None` if nothing was captured yet
+    pub fn read(&self, uri: &str) -> Result<Option<ResourceContents>, McpError> {
+        let (text, mime_type) = match uri {
+            STATE_URI => (&self.state, "text/plain"),
+            SELECTOR_MAP_URI => (&self.selector_map, "application/json"),
+            _ => {
+                return Err(McpError::resource_not_found(
+                    format!("Unknown resource: {uri}"),
+                    None,
+                ));
+            }
+        };
+        Ok(text
+            .as_ref()
+            .map(|text| ResourceContents::TextResourceContents {
+                uri: uri.to_string(),
+                mime_type: Some(mime_type.to_string()),
+                text: text.clone(),
+                meta: None,
+            }))
+    }
+
+    /// Notify `peer` when `uri` changes
+    pub fn subscribe(&mut self, uri: &str, peer: Peer<RoleServer>) -> Result<(), McpError> {
+        self.read(uri)?;
+        self.subscriptions.insert(uri.to_string());
+        self.peer = Some(peer);
+        Ok(())
+    }
+
+    /// Stop notifying about `uri`
+    pub fn unsubscribe(&mut self, uri: &str) {
+        self.subscriptions.remove(uri);
+    }
+
+    /// Store a new capture, returning the subscribed URIs whose contents changed
+    pub fn update(&mut self, state: String, selector_map: String) -> Vec<String> {
+        let mut changed = Vec::new();
+        if self.state.as_ref() != Some(&state) {
+            changed.push(STATE_URI);
+        }
+        if self.selector_map.as_ref() != Some(&selector_map) {
+            changed.push(SELECTOR_MAP_URI);
+        }
+        self.state = Some(state);
+        self.selector_map = Some(selector_map);
+        changed
+            .into_iter()
+            .filter(|uri| self.subscriptions.contains(*uri))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Client to send update notifications to
+    pub fn peer(&self) -> Option<Peer<RoleServer>> {
+        self.peer.clone()
+    }
+}
+
+/// Capture the serialized DOM and selector map of the browser's current page
+pub async fn capture(browser: &Browser) -> browsing::error::Result<(String, String)> {
+    let dom = serialized_dom(browser).await?;
+
+    let state = dom.page_state();
+    // Ordered by index so consecutive captures compare equal
+    let selector_map: BTreeMap<_, _> = dom.selector_map.into_iter().collect();
+    let selector_map = cap_json(&serde_json::to_value(selector_map)?);
+    Ok((cap_text(&state), selector_map))
+}
+
+/// Interactive elements of the browser's current page, indexed as in the page state
+pub async fn capture_elements(
+    browser: &Browser,
+) -> browsing::error::Result<Vec<SerializedElement>> {
+    Ok(serialized_dom(browser).await?.elements)
+}
+
+async fn serialized_dom(browser: &Browser) -> browsing::error::Result<SerializedDOMState> {
+    let client = browser.get_cdp_client()?;
+    let session = browser.get_session_info().await?;
+    DOMProcessorImpl::new()
+        .with_cdp_client(client, session.session_id)
+        .with_target_id(session.target_id)
+        .get_serialized_dom()
+        .await
+}
+
+/// Tool result for `url`, noting that nothing was navigated to yet if the page is blank
+///
+/// Tools called before the first `navigate` return their empty results with
+/// this note rather than failing.
+pub fn with_blank_page_note(url: &str, mut result: serde_json::Value) -> serde_json::Value {
+    if is_blank_page_url(url)
+        && let Some(fields) = result.as_object_mut()
+    {
+        fields.insert("note".to_string(), EMPTY_PAGE_STATE.into());
+    }
+    result
+}
+
+/// Truncate text to [`MAX_RESOURCE_CHARS`], marking the cut
+fn cap_text(text: &str) -> String {
+    match text.char_indices().nth(MAX_RESOURCE_CHARS) {
+        Some((end, _)) => format!("{}\n[truncated]", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Serialize JSON within [`MAX_RESOURCE_CHARS`], dropping the highest indices first
+fn cap_json(map: &serde_json::Value) -> String {
+    let text = map.to_string();
+    if text.chars().count() <= MAX_RESOURCE_CHARS {
+        return text;
+    }
+    let mut entries: Vec<(u32, serde_json::Value)> = map
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(k, v)| Some((k.parse().ok()?, v.clone())))
+        .collect();
+    entries.sort_by_key(|(index, _)| *index);
+    let mut kept = serde_json::Map::new();
+    let mut length = 2;
+    for (index, value) in entries {
+        let entry = format!("\"{index}\":{value},");
+        length += entry.chars().count();
+        if length > MAX_RESOURCE_CHARS {
+            break;
+        }
+        kept.insert(index.to_string(), value);
+    }
+    serde_json::Value::Object(kept).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_reports_changed_subscribed_uris() {
+        let mut resources = PageResources::default();
+        assert!(resources.read(STATE_URI).unwrap().is_none());
+        assert!(resources.read("browsing://other").is_err());
+
+        resources.subscriptions.insert(STATE_URI.to_string());
+        let changed = resources.update("[0]<button>Buy</button>".into(), "{}".into());
+        assert_eq!(changed, [STATE_URI]);
+
+        // Same state again: nothing to notify
+        assert!(
+            resources
+                .update("[0]<button>Buy</button>".into(), "{}".into())
+                .is_empty()
+        );
+
+        let ResourceContents::TextResourceContents {
+            text, mime_type, ..
+        } = resources.read(STATE_URI).unwrap().unwrap()
+        else {
+            panic!("expected text contents");
+        };
+        assert_eq!(text, "[0]<button>Buy</button>");
+        assert_eq!(mime_type.as_deref(), Some("text/plain"));
+    }
+
+    #[test]
+    fn test_contents_are_size_capped() {
+        let long = "é".repeat(MAX_RESOURCE_CHARS + 10);
+        let capped = cap_text(&long);
+        assert!(capped.ends_with("\n[truncated]"));
+        assert_eq!(
+            capped.chars().count(),
+            MAX_RESOURCE_CHARS + "\n[truncated]".len()
+        );
+
+        let filler = "x".repeat(1000);
+        let map: serde_json::Map<String, serde_json::Value> = (0..200)
+            .map(|i| (i.to_string(), serde_json::json!({ "text": filler })))
+            .collect();
+        let capped = cap_json(&serde_json::Value::Object(map));
+        assert!(capped.chars().count() <= MAX_RESOURCE_CHARS);
+        let parsed: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(&capped).unwrap();
+        assert!(parsed.contains_key("0"));
+        assert!(!parsed.contains_key("199"));
+    }
+
+    #[test]
+    fn test_blank_page_note() {
+        let result = with_blank_page_note("about:blank", serde_json::json!({ "links": [] }));
+        assert_eq!(result["note"], EMPTY_PAGE_STATE);
+        assert_eq!(result["links"], serde_json::json!([]));
+
+        let result = with_blank_page_note("https://example.com/", serde_json::json!({}));
+        assert!(result.get("note").is_none());
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires actual Chrome installation
+    async fn test_resources_before_navigating() {
+        let mut browser =
+            Browser::new(browsing::browser::BrowserProfile::new().with_headless(true));
+        browser.start().await.unwrap();
+
+        let (state, selector_map) = capture(&browser).await.unwrap();
+        assert_eq!(state, EMPTY_PAGE_STATE);
+        assert_eq!(selector_map, "{}");
+
+        browser.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires actual Chrome installation
+    async fn test_resources_after_navigating_fixture() {
+        use base64::Engine;
+
+        let html = include_str!("../../../tests/fixtures/clickability/cards.html");
+        let url = format!(
+            "data:text/html;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(html)
+        );
+        let mut browser =
+            Browser::new(browsing::browser::BrowserProfile::new().with_headless(true));
+        browser.start().await.unwrap();
+        browser.navigate(&url).await.unwrap();
+
+        let mut resources = PageResources::default();
+        let (state, selector_map) = capture(&browser).await.unwrap();
+        resources.update(state, selector_map);
+
+        let Some(ResourceContents::TextResourceContents { text, .. }) =
+            resources.read(STATE_URI).unwrap()
+        else {
+            panic!("state was not captured");
+        };
+        assert!(text.contains("Add to cart"), "{text}");
+        let Some(ResourceContents::TextResourceContents { text, .. }) =
+            resources.read(SELECTOR_MAP_URI).unwrap()
+        else {
+            panic!("selector map was not captured");
+        };
+        let map: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert!(!map.as_object().unwrap().is_empty());
+
+        browser.stop().await.unwrap();
+    }
+}
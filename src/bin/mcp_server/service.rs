@@ -1,39 +1,139 @@
 //! MCP BrowsingService: tool implementations
 
-use browsing::{config::Config, Browser};
+use browsing::actor::wait::DEFAULT_ELEMENT_TIMEOUT_MS;
+use browsing::actor::{
+    AuditType, ElementState, ImageListOptions, LazyLoadOptions, LazyLoadReport, LoadState,
+    NavigateOptions, Page, PreviewOptions, ViewportPreview, WaitForElementOptions,
+};
+use browsing::browser::CrawlOptions;
+use browsing::{
+    config::{Config, ToolsConfig},
+    Browser, EnvironmentInfo,
+};
 use rmcp::{
-    handler::server::{router::tool::ToolRouter, wrapper::Parameters},
+    handler::server::{router::tool::ToolRouter, tool::ToolCallContext, wrapper::Parameters},
     model::{Content, ErrorData as McpError, *},
-    tool, tool_handler, tool_router,
-    ServerHandler,
+    service::RequestContext,
+    tool, tool_router,
+    RoleServer, ServerHandler,
 };
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use tracing::Instrument;
 
 use super::params::*;
-use super::sitemap;
+use super::preview;
+use super::resources::{self, PageResources};
+use super::sitemap::{self, JobStatus, SitemapJobs};
+use super::write_roots::WriteRoots;
+
+/// Tools that exist to write files, removed when the tools config forbids it
+const FILE_WRITING_TOOLS: &[&str] = &["save_content", "save_page", "monitor_page_visually"];
 
 #[derive(Clone)]
 pub struct BrowsingService {
     /// Shared browser instance; cloned for shutdown handler in main
     pub browser: Arc<RwLock<Option<Browser>>>,
     pub tool_router: ToolRouter<Self>,
+    /// Page state resources, captured after each navigation
+    resources: Arc<tokio::sync::Mutex<PageResources>>,
+    /// Sitemap crawl jobs, running and recently finished
+    sitemaps: Arc<tokio::sync::Mutex<SitemapJobs>>,
+    /// Identifies this connection in the logs of every tool call
+    session_id: String,
+    /// Capability profile and adjustments, from `tools` in the config
+    tools: Arc<ToolsConfig>,
+    /// Directories files may be written in
+    write_roots: Arc<WriteRoots>,
 }
 
 #[tool_router]
 impl BrowsingService {
     pub fn new() -> Self {
+        Self::with_tools_config(Config::from_env().tools)
+    }
+
+    /// Service offering only the tools `tools` allows
+    ///
+    /// Tools named in its excluded actions are removed, as are the tools that
+    /// write files when file writes are not allowed.
+    pub fn with_tools_config(tools: ToolsConfig) -> Self {
+        let mut tool_router = Self::tool_router();
+        for name in tools.excluded_actions() {
+            tool_router.remove_route(&name);
+        }
+        if !tools.allows_file_writes() {
+            for name in FILE_WRITING_TOOLS {
+                tool_router.remove_route(name);
+            }
+        }
+        tracing::info!("Tools profile: {}", tools.profile);
         Self {
             browser: Arc::new(RwLock::new(None)),
-            tool_router: Self::tool_router(),
+            tool_router,
+            resources: Arc::new(tokio::sync::Mutex::new(PageResources::default())),
+            sitemaps: Arc::new(tokio::sync::Mutex::new(SitemapJobs::default())),
+            session_id: uuid::Uuid::now_v7().to_string(),
+            write_roots: Arc::new(WriteRoots::new(tools.write_roots())),
+            tools: Arc::new(tools),
+        }
+    }
+
+    /// Refuse to save files `what` asks for if the tools config forbids it
+    fn check_file_writes(&self, what: &str) -> Result<(), McpError> {
+        if self.tools.allows_file_writes() {
+            return Ok(());
+        }
+        Err(McpError::invalid_params(
+            format!(
+                "Saving files is disabled by the '{}' tools profile; call again without {}",
+                self.tools.profile, what
+            ),
+            None,
+        ))
+    }
+
+    /// Capture the page state resources and notify subscribers of changes
+    ///
+    /// Failures are logged: a navigation that succeeded is not reported as failed.
+    async fn refresh_resources(&self) {
+        let captured = {
+            let g = self.browser.read().await;
+            let Some(browser) = g.as_ref() else {
+                return;
+            };
+            resources::capture(browser).await
+        };
+        let (state, selector_map) = match captured {
+            Ok(captured) => captured,
+            Err(e) => {
+                tracing::debug!("Failed to capture page state resources: {}", e);
+                return;
+            }
+        };
+        let (changed, peer) = {
+            let mut resources = self.resources.lock().await;
+            (resources.update(state, selector_map), resources.peer())
+        };
+        let Some(peer) = peer else {
+            return;
+        };
+        for uri in changed {
+            if let Err(e) = peer
+                .notify_resource_updated(ResourceUpdatedNotificationParam { uri })
+                .await
+            {
+                tracing::debug!("Failed to send resource update: {}", e);
+            }
         }
     }
 
     async fn ensure_browser(&self) -> Result<(), McpError> {
         let mut g = self.browser.write().await;
         if g.is_none() {
-            let profile = Config::from_env().browser_profile;
-            let mut browser = Browser::new(profile);
+            let config = Config::from_env();
+            let mut browser = Browser::new(config.browser_profile)
+                .with_reconnect_on_disconnect(config.reconnect_on_disconnect);
             browser.start().await.map_err(|e| {
                 McpError::internal_error(format!("Browser start failed: {}", e), None)
             })?;
@@ -42,22 +142,62 @@ impl BrowsingService {
         Ok(())
     }
 
-    #[tool(description = "Navigate to a URL")]
+    #[tool(description = "Navigate to a URL, optionally with a referrer, one-shot headers and a load state to wait for. With include_screenshot, also returns a downscaled viewport screenshot taken once the page has loaded")]
     async fn navigate(
         &self,
         Parameters(p): Parameters<NavigateParams>,
     ) -> Result<CallToolResult, McpError> {
+        let wait_until = match p.wait_until.as_deref() {
+            None | Some("none") => None,
+            Some("domcontentloaded") => Some(LoadState::DomContentLoaded),
+            Some("load") => Some(LoadState::Load),
+            Some(other) => {
+                return Err(McpError::invalid_params(
+                    format!("Invalid wait_until '{}': expected none, domcontentloaded or load", other),
+                    None,
+                ));
+            }
+        };
+        let preview_options = p.preview.options()?;
+        let options = NavigateOptions {
+            referrer: p.referrer,
+            headers: p.headers.unwrap_or_default(),
+            wait_until,
+            redirect_grace_ms: p.redirect_grace_ms,
+        };
+
         self.ensure_browser().await?;
         let mut g = self.browser.write().await;
         let browser = g.as_mut().ok_or_else(|| McpError::internal_error("No browser", None))?;
         browser
-            .navigate(&p.url)
+            .navigate_with_options(&p.url, options)
             .await
             .map_err(|e| McpError::internal_error(format!("Navigate failed: {}", e), None))?;
-        Ok(CallToolResult::structured(serde_json::json!({
+        let redirects = browser.last_navigation().and_then(|record| record.summary());
+        let preview = self.capture_preview(browser, preview_options.as_ref()).await;
+        drop(g);
+        self.refresh_resources().await;
+        let mut result = serde_json::json!({
             "success": true,
             "url": p.url
-        })))
+        });
+        if let Some(redirects) = redirects {
+            result["redirects"] = serde_json::json!(redirects);
+        }
+        Ok(preview::tool_result(result, preview))
+    }
+
+    /// Viewport screenshot of the current page, if `options` asks for one
+    async fn capture_preview(
+        &self,
+        browser: &Browser,
+        options: Option<&PreviewOptions>,
+    ) -> Option<Result<ViewportPreview, String>> {
+        options?;
+        match browser.get_page() {
+            Ok(page) => preview::capture(&page, options).await,
+            Err(e) => Some(Err(e.to_string())),
+        }
     }
 
     #[tool(description = "Get all links on the current page (index, href, text)")]
@@ -84,18 +224,63 @@ impl BrowsingService {
         let links: Vec<serde_json::Value> = serde_json::from_str(&result).unwrap_or_default();
         let url = browser.get_current_url().await.unwrap_or_default();
         drop(g);
-        Ok(CallToolResult::structured(serde_json::json!({
-            "url": url,
-            "links": links,
-            "count": links.len()
-        })))
+        Ok(CallToolResult::structured(resources::with_blank_page_note(
+            &url,
+            serde_json::json!({
+                "url": url,
+                "links": links,
+                "count": links.len()
+            }),
+        )))
+    }
+
+    #[tool(description = "Get the interactive elements of the current page as JSON: index (the same as in the page state resource), tag, role, name, text, rect, href, form_id and is_visible")]
+    async fn get_elements(&self) -> Result<CallToolResult, McpError> {
+        self.ensure_browser().await?;
+        let g = self.browser.read().await;
+        let browser = g.as_ref().ok_or_else(|| McpError::internal_error("No browser", None))?;
+        let elements = resources::capture_elements(browser)
+            .await
+            .map_err(|e| McpError::internal_error(format!("Get elements failed: {}", e), None))?;
+        let url = browser.get_current_url().await.unwrap_or_default();
+        drop(g);
+        Ok(CallToolResult::structured(resources::with_blank_page_note(
+            &url,
+            serde_json::json!({
+                "url": url,
+                "elements": elements,
+                "count": elements.len()
+            }),
+        )))
     }
 
-    #[tool(description = "Follow a link by index (from get_links) or by URL")]
+    #[tool(description = "Get the current page's web app (PWA) manifest: name, short_name, description, start_url, display, background_color, theme_color, icons, categories and screenshots, with URLs made absolute. manifest is null if the page links none")]
+    async fn get_pwa_info(&self) -> Result<CallToolResult, McpError> {
+        self.ensure_browser().await?;
+        let g = self.browser.read().await;
+        let browser = g.as_ref().ok_or_else(|| McpError::internal_error("No browser", None))?;
+        let manifest = browser
+            .get_web_app_manifest()
+            .await
+            .map_err(|e| McpError::internal_error(format!("Get manifest failed: {}", e), None))?;
+        let url = browser.get_current_url().await.unwrap_or_default();
+        drop(g);
+        Ok(CallToolResult::structured(resources::with_blank_page_note(
+            &url,
+            serde_json::json!({
+                "url": url,
+                "is_pwa": manifest.is_some(),
+                "manifest": manifest
+            }),
+        )))
+    }
+
+    #[tool(description = "Follow a link by index (from get_links) or by URL. With include_screenshot, also returns a downscaled viewport screenshot taken once the page has loaded")]
     async fn follow_link(
         &self,
         Parameters(p): Parameters<FollowLinkParams>,
     ) -> Result<CallToolResult, McpError> {
+        let preview_options = p.preview.options()?;
         self.ensure_browser().await?;
         let url = if let Some(u) = p.url {
             u
@@ -134,14 +319,79 @@ impl BrowsingService {
             .navigate(&url)
             .await
             .map_err(|e| McpError::internal_error(format!("Navigate failed: {}", e), None))?;
+        let preview = self.capture_preview(browser, preview_options.as_ref()).await;
+        drop(g);
+        self.refresh_resources().await;
+        Ok(preview::tool_result(
+            serde_json::json!({
+                "success": true,
+                "url": url
+            }),
+            preview,
+        ))
+    }
+
+    #[tool(description = "Wait for elements matching a CSS selector and/or text to become visible, hidden or attached, in the current tab or the tab tab_id. Returns how long it waited (waited_ms) and how many elements matched (count); fails after timeout_ms")]
+    async fn wait_for(
+        &self,
+        Parameters(p): Parameters<WaitForParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let state = match p.state.as_deref() {
+            None => ElementState::Visible,
+            Some(name) => ElementState::from_name(name).ok_or_else(|| {
+                McpError::invalid_params(
+                    format!("Invalid state '{}': expected visible, hidden or attached", name),
+                    None,
+                )
+            })?,
+        };
+        if p.selector.is_none() && p.text.is_none() {
+            return Err(McpError::invalid_params("Provide 'selector' or 'text'", None));
+        }
+        let options = WaitForElementOptions {
+            selector: p.selector,
+            text: p.text,
+            state,
+            timeout_ms: p.timeout_ms.unwrap_or(DEFAULT_ELEMENT_TIMEOUT_MS),
+        };
+
+        self.ensure_browser().await?;
+        let g = self.browser.read().await;
+        let browser = g.as_ref().ok_or_else(|| McpError::internal_error("No browser", None))?;
+        let page = match p.tab_id {
+            Some(ref tab_id) => {
+                let target_id = browser
+                    .get_target_id_from_tab_id(tab_id)
+                    .await
+                    .map_err(|e| McpError::invalid_params(format!("Unknown tab: {}", e), None))?;
+                browser.get_page_for_target(&target_id)
+            }
+            None => browser.get_page(),
+        }
+        .map_err(|e| McpError::internal_error(format!("Get page failed: {}", e), None))?;
+        // Other tools may use the browser while this one waits
+        drop(g);
+
+        let waited = page
+            .wait_for_element(&options)
+            .await
+            .map_err(|e| McpError::internal_error(format!("Wait failed: {}", e), None))?;
         Ok(CallToolResult::structured(serde_json::json!({
             "success": true,
-            "url": url
+            "state": state,
+            "waited_ms": waited.waited_ms,
+            "count": waited.count
         })))
     }
 
-    #[tool(description = "List available content: links and images with indices")]
-    async fn list_content(&self) -> Result<CallToolResult, McpError> {
+    #[tool(description = "List available content: links, and images with indices, rendered and natural size, loading attribute and caption. Tracking pixels and data: URIs are skipped by default; optionally download the largest images")]
+    async fn list_content(
+        &self,
+        Parameters(p): Parameters<ListContentParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if p.download.unwrap_or(false) {
+            self.check_file_writes("download")?;
+        }
         self.ensure_browser().await?;
         let g = self.browser.read().await;
         let browser = g.as_ref().ok_or_else(|| McpError::internal_error("No browser", None))?;
@@ -153,23 +403,165 @@ impl BrowsingService {
                 const links = Array.from(document.querySelectorAll('a[href]'))
                     .filter(a => a.href && !a.href.startsWith('javascript:'))
                     .map((a, i) => ({ index: i, href: a.href, text: (a.textContent||'').trim().slice(0, 100) }));
-                const images = Array.from(document.querySelectorAll('img[src]'))
-                    .map((img, i) => ({ index: i, src: img.src, alt: (img.alt||'').slice(0, 80) }));
-                return JSON.stringify({ links, images });
+                return JSON.stringify({ links });
             })()
         "#;
         let result = page
             .evaluate(script)
             .await
-            .unwrap_or_else(|_| "{\"links\":[],\"images\":[]}".to_string());
+            .unwrap_or_else(|_| "{\"links\":[]}".to_string());
         let content: serde_json::Value =
-            serde_json::from_str(&result).unwrap_or(serde_json::json!({"links":[],"images":[]}));
+            serde_json::from_str(&result).unwrap_or(serde_json::json!({"links":[]}));
+
+        let defaults = ImageListOptions::default();
+        let options = ImageListOptions {
+            min_width: p.min_width.unwrap_or(defaults.min_width),
+            min_height: p.min_height.unwrap_or(defaults.min_height),
+            include_data_uris: p.include_data_uris.unwrap_or(false),
+        };
+        let mut images = page
+            .list_images(&options)
+            .await
+            .map_err(|e| McpError::internal_error(format!("Listing images failed: {}", e), None))?;
+        if p.download.unwrap_or(false) {
+            let save_dir = match p.save_dir {
+                Some(dir) => self.write_roots.dir(std::path::Path::new(&dir))?,
+                None => self.write_roots.dir(std::path::Path::new(&format!(
+                    "browsing-images-{}",
+                    chrono::Utc::now().format("%Y%m%dT%H%M%S%.3f")
+                )))?,
+            };
+            let limit = p
+                .download_limit
+                .map_or(browsing::actor::images::DEFAULT_DOWNLOAD_LIMIT, |n| n as usize);
+            page.download_images(&mut images, &save_dir, limit, p.overwrite.unwrap_or(false))
+                .await
+                .map_err(|e| McpError::internal_error(format!("Download failed: {}", e), None))?;
+        }
+        let url = browser.get_current_url().await.unwrap_or_default();
+        drop(g);
+        Ok(CallToolResult::structured(resources::with_blank_page_note(
+            &url,
+            serde_json::json!({
+                "url": url,
+                "links": content.get("links").cloned().unwrap_or_default(),
+                "images": images
+            }),
+        )))
+    }
+
+    #[tool(description = "Discover all forms on the page with their fields (name, type, label, placeholder, required, value, options)")]
+    async fn discover_forms(&self) -> Result<CallToolResult, McpError> {
+        self.ensure_browser().await?;
+        let g = self.browser.read().await;
+        let browser = g.as_ref().ok_or_else(|| McpError::internal_error("No browser", None))?;
+        let page = browser
+            .get_page()
+            .map_err(|e| McpError::internal_error(format!("Get page failed: {}", e), None))?;
+        let forms = page
+            .get_all_forms()
+            .await
+            .map_err(|e| McpError::internal_error(format!("Form discovery failed: {}", e), None))?;
+        let url = browser.get_current_url().await.unwrap_or_default();
+        drop(g);
+        Ok(CallToolResult::structured(resources::with_blank_page_note(
+            &url,
+            serde_json::json!({
+                "url": url,
+                "forms": forms,
+                "count": forms.len()
+            }),
+        )))
+    }
+
+    #[tool(description = "Measure Core Web Vitals of the current page: LCP, FID, CLS, TTI and FCP in milliseconds (CLS is unitless). Metrics that aren't available yet are null")]
+    async fn measure_web_vitals(
+        &self,
+        Parameters(p): Parameters<MeasureWebVitalsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        self.ensure_browser().await?;
+        let g = self.browser.read().await;
+        let browser = g.as_ref().ok_or_else(|| McpError::internal_error("No browser", None))?;
+        let page = browser
+            .get_page()
+            .map_err(|e| McpError::internal_error(format!("Get page failed: {}", e), None))?;
+        let vitals = page
+            .measure_web_vitals(p.timeout_ms.unwrap_or(15_000))
+            .await
+            .map_err(|e| McpError::internal_error(format!("Measuring web vitals failed: {}", e), None))?;
         let url = browser.get_current_url().await.unwrap_or_default();
         drop(g);
         Ok(CallToolResult::structured(serde_json::json!({
             "url": url,
-            "links": content.get("links").cloned().unwrap_or_default(),
-            "images": content.get("images").cloned().unwrap_or_default()
+            "lcp": vitals.lcp,
+            "fid": vitals.fid,
+            "cls": vitals.cls,
+            "tti": vitals.tti,
+            "fcp": vitals.fcp
+        })))
+    }
+
+    #[tool(description = "Audit the current page for issues Chrome reported while loading it. audit_type: accessibility (form labels, ARIA references, text contrast), csp (Content Security Policy violations), cookies (rejected or flagged cookies), or all. Each issue has code, description, element_selector and severity (error or warning)")]
+    async fn run_audit(
+        &self,
+        Parameters(p): Parameters<RunAuditParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let audit_type = AuditType::from_name(p.audit_type.as_deref().unwrap_or("all"))
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+        self.ensure_browser().await?;
+        let g = self.browser.read().await;
+        let browser = g.as_ref().ok_or_else(|| McpError::internal_error("No browser", None))?;
+        let page = browser
+            .get_page()
+            .map_err(|e| McpError::internal_error(format!("Get page failed: {}", e), None))?;
+        let issues = page
+            .run_audit(audit_type)
+            .await
+            .map_err(|e| McpError::internal_error(format!("Audit failed: {}", e), None))?;
+        let url = browser.get_current_url().await.unwrap_or_default();
+        drop(g);
+        Ok(CallToolResult::structured(serde_json::json!({
+            "url": url,
+            "audit_type": audit_type,
+            "issues": issues,
+            "count": issues.len()
+        })))
+    }
+
+    #[tool(description = "Get server diagnostics for bug reports: crate version and git revision, OS, browser and protocol versions, and CDP commands sent per method. Does not start a browser")]
+    async fn get_server_stats(&self) -> Result<CallToolResult, McpError> {
+        let g = self.browser.read().await;
+        let (browser_version, cdp_stats) = match g.as_ref() {
+            Some(browser) => (
+                browser.version_info().await.ok(),
+                browser.get_cdp_client().ok().map(|client| client.stats()),
+            ),
+            None => (None, None),
+        };
+        drop(g);
+        let environment = EnvironmentInfo::new(browser_version);
+        Ok(CallToolResult::structured(serde_json::json!({
+            "fingerprint": environment.fingerprint(),
+            "build": environment.build,
+            "browser": environment.browser,
+            "browser_running": cdp_stats.is_some(),
+            "session_id": self.session_id,
+            "cdp": cdp_stats
+        })))
+    }
+
+    #[tool(description = "Get the current tab's network throttling: whether it is throttled or offline, and the latency and throughputs set. Does not start a browser")]
+    async fn get_network_status(&self) -> Result<CallToolResult, McpError> {
+        let g = self.browser.read().await;
+        let conditions = match g.as_ref() {
+            Some(browser) => browser.get_network_conditions().await.ok().flatten(),
+            None => None,
+        };
+        Ok(CallToolResult::structured(serde_json::json!({
+            "browser_running": g.is_some(),
+            "throttled": conditions.is_some(),
+            "offline": conditions.is_some_and(|c| c.offline),
+            "conditions": conditions
         })))
     }
 
@@ -185,6 +577,11 @@ impl BrowsingService {
             .get_page()
             .map_err(|e| McpError::internal_error(format!("Get page failed: {}", e), None))?;
         let url = browser.get_current_url().await.unwrap_or_default();
+        let lazy_load = if p.trigger_lazy_load.unwrap_or(false) {
+            Some(prepare_for_extraction(&page).await?)
+        } else {
+            None
+        };
         let max_chars = p.max_chars.unwrap_or(100_000) as usize;
         let expr = format!(
             "(document.body?.innerText||document.body?.textContent||'').slice(0,{})",
@@ -192,11 +589,17 @@ impl BrowsingService {
         );
         let text = page.evaluate(&expr).await.unwrap_or_default();
         drop(g);
-        Ok(CallToolResult::structured(serde_json::json!({
+        let mut content = serde_json::json!({
             "url": url,
             "text": text,
             "length": text.len()
-        })))
+        });
+        if let Some(report) = lazy_load {
+            content["lazy_load"] = serde_json::json!(report);
+        }
+        Ok(CallToolResult::structured(resources::with_blank_page_note(
+            &url, content,
+        )))
     }
 
     #[tool(description = "Get or save image by index from list_content.images (captures visible element as screenshot)")]
@@ -229,13 +632,15 @@ impl BrowsingService {
         Ok(CallToolResult::success(vec![Content::image(b64, "image/png")]))
     }
 
-    #[tool(description = "Save text content or image (by index) to a file")]
+    #[tool(description = "Save text content or image (by index) to a file inside the allowed write roots; relative paths are under the first root. Fails if the file exists unless overwrite is true")]
     async fn save_content(
         &self,
         Parameters(p): Parameters<SaveContentParams>,
     ) -> Result<CallToolResult, McpError> {
+        let path = self
+            .write_roots
+            .file(std::path::Path::new(&p.path), p.overwrite.unwrap_or(false))?;
         self.ensure_browser().await?;
-        let path = p.path;
         match p.content_type.to_lowercase().as_str() {
             "text" => {
                 let g = self.browser.read().await;
@@ -297,6 +702,44 @@ impl BrowsingService {
         })))
     }
 
+    #[tool(description = "Save the current page with its styles, images and frames as an MHTML file that opens offline as the page looked")]
+    async fn save_page(
+        &self,
+        Parameters(p): Parameters<SavePageParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = match p.path {
+            Some(path) => self
+                .write_roots
+                .file(std::path::Path::new(&path), p.overwrite.unwrap_or(false))?,
+            None => self.write_roots.file(
+                std::path::Path::new(&format!(
+                    "browsing-page-{}.mhtml",
+                    chrono::Utc::now().format("%Y%m%dT%H%M%S%.3f")
+                )),
+                false,
+            )?,
+        };
+
+        self.ensure_browser().await?;
+        let g = self.browser.read().await;
+        let browser = g.as_ref().ok_or_else(|| McpError::internal_error("No browser", None))?;
+        let page = browser
+            .get_page()
+            .map_err(|e| McpError::internal_error(format!("Get page failed: {}", e), None))?;
+        let size = page
+            .save_mhtml(&path)
+            .await
+            .map_err(|e| McpError::internal_error(format!("Save page failed: {}", e), None))?;
+        let url = browser.get_current_url().await.unwrap_or_default();
+        drop(g);
+        Ok(CallToolResult::structured(serde_json::json!({
+            "success": true,
+            "url": url,
+            "path": path,
+            "size": size
+        })))
+    }
+
     #[tool(description = "Take screenshot: full page, or a specific element by CSS selector")]
     async fn screenshot(
         &self,
@@ -335,8 +778,15 @@ impl BrowsingService {
             base64::Engine::decode(&base64::engine::general_purpose::STANDARD, &b64)
                 .map_err(|e| McpError::internal_error(format!("Base64 decode: {}", e), None))?
         } else {
+            let full_page = p.full_page.unwrap_or(false);
+            if full_page && p.trigger_lazy_load.unwrap_or(false) {
+                let page = browser.get_page().map_err(|e| {
+                    McpError::internal_error(format!("Get page failed: {}", e), None)
+                })?;
+                prepare_for_extraction(&page).await?;
+            }
             browser
-                .take_screenshot(None, p.full_page.unwrap_or(false), None, None)
+                .take_screenshot(None, full_page, None, None)
                 .await
                 .map_err(|e| McpError::internal_error(format!("Screenshot failed: {}", e), None))?
         };
@@ -345,15 +795,129 @@ impl BrowsingService {
         Ok(CallToolResult::success(vec![Content::image(b64, "image/png")]))
     }
 
-    #[tool(description = "Generate sitemap by crawling from URL: navigate, capture title and content preview, discover links. Returns structured sitemap (optionally save to file).")]
+    #[tool(description = "Take a series of screenshots of the current page at a fixed interval to watch it change (animations, loading, live updates). Returns the saved file paths")]
+    async fn monitor_page_visually(
+        &self,
+        Parameters(p): Parameters<MonitorPageVisuallyParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let interval_ms = p.interval_ms.unwrap_or(1000);
+        let count = p.count.unwrap_or(5);
+        if interval_ms < 100 {
+            return Err(McpError::invalid_params("interval_ms must be at least 100", None));
+        }
+        if count == 0 || count > 100 {
+            return Err(McpError::invalid_params("count must be between 1 and 100", None));
+        }
+        let save_dir = match p.save_dir {
+            Some(dir) => self.write_roots.dir(std::path::Path::new(&dir))?,
+            None => self.write_roots.dir(std::path::Path::new(&format!(
+                "browsing-monitor-{}",
+                chrono::Utc::now().format("%Y%m%dT%H%M%S%.3f")
+            )))?,
+        };
+
+        self.ensure_browser().await?;
+        let mut g = self.browser.write().await;
+        let browser = g.as_mut().ok_or_else(|| McpError::internal_error("No browser", None))?;
+        let mut handle = browser
+            .start_interval_screenshots(interval_ms, count, &save_dir)
+            .await
+            .map_err(|e| McpError::internal_error(format!("Monitoring failed: {}", e), None))?;
+        drop(g);
+
+        let paths = handle
+            .wait()
+            .await
+            .map_err(|e| McpError::internal_error(format!("Monitoring failed: {}", e), None))?;
+        Ok(CallToolResult::structured(serde_json::json!({
+            "save_dir": save_dir,
+            "count": paths.len(),
+            "latest": paths.last(),
+            "paths": paths
+        })))
+    }
+
+    #[tool(description = "Generate sitemap by crawling from URL: navigate, capture title and content preview, discover links. Returns structured sitemap (optionally save to file). Runs as a job: newly discovered URLs arrive as progress notifications, the sitemap so far is the resource browsing://sitemap/<job_id>, and cancel_sitemap stops it.")]
     async fn generate_sitemap(
         &self,
         Parameters(p): Parameters<GenerateSitemapParams>,
+        context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, McpError> {
+        if p.save_path.is_some() {
+            self.check_file_writes("save_path")?;
+        }
+        let save_path = p
+            .save_path
+            .as_deref()
+            .map(|path| {
+                self.write_roots
+                    .file(std::path::Path::new(path), p.overwrite.unwrap_or(false))
+            })
+            .transpose()?;
         self.ensure_browser().await?;
-        let sitemap = sitemap::run_sitemap_crawl(self.browser.clone(), p.clone()).await?;
 
-        if let Some(path) = &p.save_path {
+        let job_id = uuid::Uuid::now_v7().to_string();
+        let (batches_tx, mut batches) = tokio::sync::mpsc::channel(sitemap::CRAWL_CHANNEL_CAPACITY);
+        let mut visitor = sitemap::BrowserVisitor::new(
+            self.browser.clone(),
+            p.content_preview_chars.unwrap_or(500) as usize,
+            p.delay_ms.unwrap_or(800),
+        );
+        let crawl_params = p.clone();
+        let task = tokio::spawn(async move {
+            sitemap::run_sitemap_crawl(&mut visitor, &crawl_params, batches_tx).await
+        });
+        self.sitemaps
+            .lock()
+            .await
+            .start(&job_id, &p.url, task.abort_handle());
+
+        let progress_token = context.meta.get_progress_token();
+        let max_pages = p.max_pages.unwrap_or(30);
+        loop {
+            let batch = tokio::select! {
+                batch = batches.recv() => batch,
+                // The client gave up on the request; stop crawling for it too
+                _ = context.ct.cancelled() => {
+                    let _ = self.sitemaps.lock().await.cancel(&job_id);
+                    None
+                }
+            };
+            let Some(batch) = batch else { break };
+            let message = sitemap::progress_message(&job_id, &batch);
+            let crawled = self.sitemaps.lock().await.record(&job_id, batch);
+            if let Some(progress_token) = &progress_token {
+                let _ = context
+                    .peer
+                    .notify_progress(ProgressNotificationParam {
+                        progress_token: progress_token.clone(),
+                        progress: crawled as f64,
+                        total: Some(max_pages as f64),
+                        message: Some(message),
+                    })
+                    .await;
+            }
+        }
+
+        let (status, error) = match task.await {
+            Ok(Ok(())) => (JobStatus::Completed, None),
+            Ok(Err(e)) => (JobStatus::Failed, Some(e.message.to_string())),
+            Err(_) => (JobStatus::Cancelled, None),
+        };
+        let sitemap = {
+            let mut sitemaps = self.sitemaps.lock().await;
+            sitemaps.finish(&job_id, status, error.clone());
+            sitemaps.sitemap(&job_id).unwrap_or_default()
+        };
+        self.refresh_resources().await;
+        if let Some(error) = error {
+            return Err(McpError::internal_error(
+                format!("Sitemap job {} failed: {}", job_id, error),
+                None,
+            ));
+        }
+
+        if let Some(path) = &save_path {
             let s = serde_json::to_string_pretty(&sitemap)
                 .map_err(|e| McpError::internal_error(format!("Serialize failed: {}", e), None))?;
             tokio::fs::write(path, s)
@@ -364,26 +928,176 @@ impl BrowsingService {
         let total = sitemap.get("total_pages").and_then(|v| v.as_u64()).unwrap_or(0);
         Ok(CallToolResult::structured(serde_json::json!({
             "success": true,
+            "job_id": job_id,
+            "status": sitemap["status"],
             "total_pages": total,
             "sitemap": sitemap,
-            "saved_to": p.save_path
+            "saved_to": save_path
+        })))
+    }
+
+    #[tool(description = "Map how pages link to each other: crawl from the current page within max_pages, max_depth and the allowed domains, and return the graph as nodes (url, title; null for pages linked but not crawled) and edges (source, target, anchor_text), optionally also in Graphviz DOT")]
+    async fn build_link_graph(
+        &self,
+        Parameters(p): Parameters<BuildLinkGraphParams>,
+    ) -> Result<CallToolResult, McpError> {
+        self.ensure_browser().await?;
+        let start_url = {
+            let g = self.browser.read().await;
+            let b = g
+                .as_ref()
+                .ok_or_else(|| McpError::internal_error("No browser", None))?;
+            b.get_current_url()
+                .await
+                .map_err(|e| McpError::internal_error(format!("Get URL failed: {}", e), None))?
+        };
+        let options = CrawlOptions {
+            max_pages: p.max_pages.unwrap_or(30) as usize,
+            max_depth: p.max_depth.unwrap_or(3),
+            same_domain_only: p.same_domain_only.unwrap_or(true),
+            allowed_domains: p.allowed_domains.unwrap_or_default(),
+            delay_ms: p.delay_ms.unwrap_or(800),
+        };
+        let mut visitor = sitemap::BrowserVisitor::new(self.browser.clone(), 0, options.delay_ms);
+        let graph = browsing::browser::build_link_graph(&mut visitor, &start_url, &options)
+            .await
+            .map_err(sitemap::crawl_error)?;
+        self.refresh_resources().await;
+
+        let mut result = serde_json::json!({
+            "success": true,
+            "start_url": start_url,
+            "total_nodes": graph.nodes.len(),
+            "total_edges": graph.edges.len(),
+            "graph": graph
+        });
+        if p.include_dot.unwrap_or(false) {
+            result["dot"] = graph.to_dot().into();
+        }
+        Ok(CallToolResult::structured(result))
+    }
+
+    #[tool(description = "Stop a running generate_sitemap job. The pages crawled so far are kept and returned by generate_sitemap and the browsing://sitemap/<job_id> resource")]
+    async fn cancel_sitemap(
+        &self,
+        Parameters(p): Parameters<CancelSitemapParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let status = self.sitemaps.lock().await.cancel(&p.job_id)?;
+        Ok(CallToolResult::structured(serde_json::json!({
+            "job_id": p.job_id,
+            "status": status,
+            "resource": sitemap::sitemap_uri(&p.job_id)
         })))
     }
 }
 
-#[tool_handler]
 impl ServerHandler for BrowsingService {
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        // Everything a tool logs carries the connection it was called on
+        let span = tracing::info_span!(
+            "tool_call",
+            session_id = %self.session_id,
+            tool = %request.name
+        );
+        let tcc = ToolCallContext::new(self, request, context);
+        self.tool_router.call(tcc).instrument(span).await
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        Ok(ListToolsResult {
+            tools: self.tool_router.list_all(),
+            meta: None,
+            next_cursor: None,
+        })
+    }
+
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             protocol_version: ProtocolVersion::V_2024_11_05,
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_resources()
+                .enable_resources_subscribe()
+                .build(),
             server_info: Implementation::from_build_env(),
-            instructions: Some(
+            instructions: Some(format!(
                 "Browse the web: navigate, get_links, follow_link, list_content (links+images), \
-                 get_content, get_image, save_content, screenshot (full or by selector), \
-                 generate_sitemap (crawl and capture navigation+content)."
-                    .into(),
-            ),
+                 get_content, get_image, save_content, save_page (MHTML snapshot), \
+                 screenshot (full or by selector), \
+                 generate_sitemap (crawl and capture navigation+content), cancel_sitemap, \
+                 build_link_graph (pages and the links between them). \
+                 Resources browsing://current/state and browsing://current/selector_map \
+                 hold the current page's serialized DOM and selector map; \
+                 browsing://sitemap/<job_id> holds a sitemap crawl's pages so far. \
+                 Include get_server_stats output in bug reports. Server: {}.",
+                browsing::build_info().fingerprint(None)
+            )),
+        }
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        let mut resources = PageResources::list();
+        resources.extend(self.sitemaps.lock().await.list());
+        Ok(ListResourcesResult::with_all_items(resources))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        if sitemap::job_id_from_uri(&request.uri).is_some() {
+            let contents = self.sitemaps.lock().await.read(&request.uri)?;
+            return Ok(ReadResourceResult {
+                contents: vec![contents],
+            });
         }
+        // Capture on first read if the browser was started without navigating
+        if self.resources.lock().await.read(&request.uri)?.is_none() {
+            self.refresh_resources().await;
+        }
+        let contents = self.resources.lock().await.read(&request.uri)?;
+        Ok(ReadResourceResult {
+            contents: contents.into_iter().collect(),
+        })
     }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.resources
+            .lock()
+            .await
+            .subscribe(&request.uri, context.peer)
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.resources.lock().await.unsubscribe(&request.uri);
+        Ok(())
+    }
+}
+
+/// Scroll through the page so lazy-loaded content is there before reading it
+async fn prepare_for_extraction(page: &Page) -> Result<LazyLoadReport, McpError> {
+    page.prepare_page_for_extraction(&LazyLoadOptions::default())
+        .await
+        .map_err(|e| McpError::internal_error(format!("Lazy loading failed: {}", e), None))
 }
@@ -1,128 +1,475 @@
 //! Sitemap generation by crawling and capturing navigation + content
+//!
+//! Pages are crawled with [`browsing::browser::crawl`], shared with
+//! `build_link_graph`, through a [`BrowserVisitor`] on the server's browser.
+//!
+//! A crawl runs as a job on its own task. After each page it sends a
+//! [`CrawlBatch`] — the page and the URLs it newly discovered — over a bounded
+//! channel. `generate_sitemap` relays each batch as an MCP progress
+//! notification and records it in [`SitemapJobs`], so the sitemap so far can
+//! be read as the `browsing://sitemap/<job_id>` resource while the crawl runs.
+//! `cancel_sitemap` aborts a job's task; the pages crawled until then are kept.
 
+use async_trait::async_trait;
 use browsing::Browser;
-use rmcp::model::ErrorData as McpError;
-use std::collections::{HashSet, VecDeque};
+use browsing::browser::{CrawlOptions, CrawledPage, PageVisitor, VisitedPage, capture_page, crawl};
+use browsing::error::BrowsingError;
+use rmcp::model::{AnnotateAble, ErrorData as McpError, RawResource, Resource, ResourceContents};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use url::Url;
+use tokio::sync::{RwLock, mpsc};
+use tokio::task::AbortHandle;
 
 use super::params::GenerateSitemapParams;
 
-fn extract_domain(url_str: &str) -> Option<String> {
-    Url::parse(url_str)
-        .ok()
-        .and_then(|u| u.host_str().map(|s| s.to_string()))
+/// Prefix of the resource URI of a crawl job's sitemap
+pub const SITEMAP_URI_PREFIX: &str = "browsing://sitemap/";
+
+/// Batches a crawl may get ahead of the client before it waits
+pub const CRAWL_CHANNEL_CAPACITY: usize = 8;
+
+/// Finished jobs whose sitemaps stay readable; older ones are forgotten
+pub const MAX_FINISHED_JOBS: usize = 10;
+
+/// Crawls with the server's browser: navigate, wait, then capture title, content preview and links
+///
+/// Takes the browser lock for each page only, so other tools can run between pages.
+pub struct BrowserVisitor {
+    browser: Arc<RwLock<Option<Browser>>>,
+    preview_chars: usize,
+    delay_ms: u64,
 }
 
-fn is_same_domain(url_str: &str, base: &str) -> bool {
-    extract_domain(url_str).as_deref() == Some(base)
+impl BrowserVisitor {
+    /// Visitor using `browser`, keeping `preview_chars` of each page's text
+    pub fn new(browser: Arc<RwLock<Option<Browser>>>, preview_chars: usize, delay_ms: u64) -> Self {
+        Self {
+            browser,
+            preview_chars,
+            delay_ms,
+        }
+    }
 }
 
-/// Run sitemap crawl: navigate, capture title + content preview, discover links.
-pub async fn run_sitemap_crawl(
-    browser: Arc<RwLock<Option<Browser>>>,
-    p: GenerateSitemapParams,
-) -> Result<serde_json::Value, McpError> {
-    let max_pages = p.max_pages.unwrap_or(30) as usize;
-    let max_depth = p.max_depth.unwrap_or(3) as u32;
-    let same_domain_only = p.same_domain_only.unwrap_or(true);
-    let preview_chars = p.content_preview_chars.unwrap_or(500) as usize;
-    let delay_ms = p.delay_ms.unwrap_or(800);
-
-    let base_url =
-        Url::parse(&p.url).map_err(|e| McpError::invalid_params(format!("Invalid URL: {}", e), None))?;
-    let base_domain = base_url.host_str().unwrap_or("").to_string();
-
-    let mut visited: HashSet<String> = HashSet::new();
-    let mut queue: VecDeque<(String, u32)> = VecDeque::new();
-    queue.push_back((p.url.clone(), 0));
-
-    let mut pages: Vec<serde_json::Value> = Vec::new();
-
-    while let Some((url, depth)) = queue.pop_front() {
-        if pages.len() >= max_pages || depth > max_depth {
-            continue;
+fn no_browser() -> BrowsingError {
+    BrowsingError::Browser("No browser".to_string())
+}
+
+#[async_trait]
+impl PageVisitor for BrowserVisitor {
+    async fn visit(&mut self, url: &str) -> browsing::error::Result<Option<VisitedPage>> {
+        {
+            let mut g = self.browser.write().await;
+            let b = g.as_mut().ok_or_else(no_browser)?;
+            if b.navigate(url).await.is_err() {
+                return Ok(None);
+            }
         }
-        let url_norm = url.trim_end_matches('/').to_string();
-        if visited.contains(&url_norm) {
-            continue;
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(self.delay_ms)).await;
+
+        let g = self.browser.read().await;
+        let b = g.as_ref().ok_or_else(no_browser)?;
+        capture_page(b, url, self.preview_chars).await.map(Some)
+    }
+}
+
+/// Crawl limits of a `generate_sitemap` call
+pub fn crawl_options(p: &GenerateSitemapParams) -> CrawlOptions {
+    CrawlOptions {
+        max_pages: p.max_pages.unwrap_or(30) as usize,
+        max_depth: p.max_depth.unwrap_or(3),
+        same_domain_only: p.same_domain_only.unwrap_or(true),
+        allowed_domains: Vec::new(),
+        delay_ms: p.delay_ms.unwrap_or(800),
+    }
+}
+
+/// A crawl error as an MCP error: invalid params for a bad start URL
+pub fn crawl_error(e: BrowsingError) -> McpError {
+    match e {
+        BrowsingError::Validation(message) => McpError::invalid_params(message, None),
+        e => McpError::internal_error(e.to_string(), None),
+    }
+}
+
+/// One crawled page and the URLs it led to that had not been seen before
+#[derive(Debug, Clone)]
+pub struct CrawlBatch {
+    /// The page: url, title, content_preview, links and depth
+    pub page: serde_json::Value,
+    /// Links of the page queued for crawling
+    pub discovered: Vec<String>,
+}
+
+impl From<CrawledPage> for CrawlBatch {
+    fn from(crawled: CrawledPage) -> Self {
+        let links: Vec<String> = crawled.outbound.into_iter().map(|link| link.url).collect();
+        Self {
+            page: serde_json::json!({
+                "url": crawled.page.url,
+                "title": crawled.page.title,
+                "content_preview": crawled.page.content_preview,
+                "links": links,
+                "depth": crawled.depth
+            }),
+            discovered: crawled.discovered,
         }
-        visited.insert(url_norm.clone());
+    }
+}
 
-        // Navigate
-        {
-            let mut g = browser.write().await;
-            let b = g.as_mut().ok_or_else(|| McpError::internal_error("No browser", None))?;
-            if b.navigate(&url).await.is_err() {
-                drop(g);
-                continue;
+/// Crawl breadth-first from `p.url`, sending a [`CrawlBatch`] after each page
+///
+/// Stops early if the receiver of `batches` is dropped.
+pub async fn run_sitemap_crawl(
+    visitor: &mut dyn PageVisitor,
+    p: &GenerateSitemapParams,
+    batches: mpsc::Sender<CrawlBatch>,
+) -> Result<(), McpError> {
+    let options = crawl_options(p);
+    let (pages_tx, mut pages) = mpsc::channel(1);
+    let forward = async move {
+        while let Some(crawled) = pages.recv().await {
+            if batches.send(CrawlBatch::from(crawled)).await.is_err() {
+                // Dropping `pages` stops the crawl
+                break;
             }
         }
+    };
+    let (result, ()) = tokio::join!(crawl(visitor, &p.url, &options, pages_tx), forward);
+    result.map_err(crawl_error)
+}
 
-        tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
+/// Where a crawl job is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Still crawling
+    Running,
+    /// Crawled every page it could within the limits
+    Completed,
+    /// Stopped by `cancel_sitemap` or the client cancelling the request
+    Cancelled,
+    /// Stopped by an error
+    Failed,
+}
 
-        // Capture content
-        let (current_url, title, content_preview, links) = {
-            let g = browser.read().await;
-            let b = g.as_ref().ok_or_else(|| McpError::internal_error("No browser", None))?;
-            let page = b.get_page().map_err(|e| {
-                McpError::internal_error(format!("Get page failed: {}", e), None)
-            })?;
+struct SitemapJob {
+    base_url: String,
+    pages: Vec<serde_json::Value>,
+    discovered: usize,
+    status: JobStatus,
+    error: Option<String>,
+    task: Option<AbortHandle>,
+}
 
-            let current_url = b.get_current_url().await.unwrap_or_else(|_| url.clone());
-            let title = page.evaluate("document.title").await.unwrap_or_default();
-            let content_script = format!(
-                "(document.body?.innerText||document.body?.textContent||'').slice(0,{})",
-                preview_chars
-            );
-            let content_preview = page.evaluate(&content_script).await.unwrap_or_default();
-
-            let links_script = r#"
-                (function() {
-                    const links = Array.from(document.querySelectorAll('a[href]'))
-                        .filter(a => a.href && !a.href.startsWith('javascript:'))
-                        .map(a => a.href);
-                    return JSON.stringify([...new Set(links)]);
-                })()
-            "#;
-            let links_result = page.evaluate(links_script).await.unwrap_or_else(|_| "[]".to_string());
-            let links: Vec<String> = serde_json::from_str(&links_result).unwrap_or_default();
-
-            (current_url, title, content_preview, links)
+/// Crawl jobs by ID, with the sitemap each has built so far
+#[derive(Default)]
+pub struct SitemapJobs {
+    jobs: HashMap<String, SitemapJob>,
+    /// Finished job IDs, oldest first
+    finished: VecDeque<String>,
+}
+
+impl SitemapJobs {
+    /// Register a job crawling from `base_url` on the task behind `task`
+    pub fn start(&mut self, job_id: &str, base_url: &str, task: AbortHandle) {
+        self.jobs.insert(
+            job_id.to_string(),
+            SitemapJob {
+                base_url: base_url.to_string(),
+                pages: Vec::new(),
+                discovered: 0,
+                status: JobStatus::Running,
+                error: None,
+                task: Some(task),
+            },
+        );
+    }
+
+    /// Add a batch to a job's sitemap, returning the number of pages crawled so far
+    pub fn record(&mut self, job_id: &str, batch: CrawlBatch) -> usize {
+        let Some(job) = self.jobs.get_mut(job_id) else {
+            return 0;
+        };
+        job.pages.push(batch.page);
+        job.discovered += batch.discovered.len();
+        job.pages.len()
+    }
+
+    /// Mark a job finished; a cancelled job stays cancelled
+    pub fn finish(&mut self, job_id: &str, status: JobStatus, error: Option<String>) {
+        let Some(job) = self.jobs.get_mut(job_id) else {
+            return;
         };
+        job.task = None;
+        if job.status == JobStatus::Running {
+            job.status = status;
+            job.error = error;
+        }
+        self.finished.push_back(job_id.to_string());
+        while self.finished.len() > MAX_FINISHED_JOBS {
+            if let Some(oldest) = self.finished.pop_front() {
+                self.jobs.remove(&oldest);
+            }
+        }
+    }
+
+    /// Stop a running job, returning its status afterwards
+    pub fn cancel(&mut self, job_id: &str) -> Result<JobStatus, McpError> {
+        let job = self.jobs.get_mut(job_id).ok_or_else(|| {
+            McpError::invalid_params(format!("No sitemap job with ID {job_id}"), None)
+        })?;
+        if let Some(task) = job.task.take() {
+            task.abort();
+            job.status = JobStatus::Cancelled;
+        }
+        Ok(job.status)
+    }
 
-        let outbound: Vec<String> = links
-            .iter()
-            .filter(|href| {
-                if same_domain_only {
-                    is_same_domain(href, &base_domain)
-                } else {
-                    true
-                }
+    /// Sitemap of a job so far: base_url, status, total_pages, pages
+    pub fn sitemap(&self, job_id: &str) -> Option<serde_json::Value> {
+        let job = self.jobs.get(job_id)?;
+        let mut sitemap = serde_json::json!({
+            "job_id": job_id,
+            "base_url": job.base_url,
+            "status": job.status,
+            "total_pages": job.pages.len(),
+            "discovered_urls": job.discovered,
+            "pages": job.pages
+        });
+        if let Some(error) = &job.error {
+            sitemap["error"] = error.clone().into();
+        }
+        Some(sitemap)
+    }
+
+    /// A resource for the sitemap of every known job
+    pub fn list(&self) -> Vec<Resource> {
+        let mut ids: Vec<&String> = self.jobs.keys().collect();
+        ids.sort();
+        ids.into_iter()
+            .map(|job_id| {
+                let job = &self.jobs[job_id];
+                let mut raw =
+                    RawResource::new(sitemap_uri(job_id), format!("Sitemap of {}", job.base_url));
+                raw.description = Some(format!(
+                    "Pages crawled so far by sitemap job {job_id}, as JSON"
+                ));
+                raw.mime_type = Some("application/json".to_string());
+                raw.no_annotation()
             })
-            .cloned()
-            .collect();
+            .collect()
+    }
+
+    /// Contents of a `browsing://sitemap/<job_id>` resource
+    pub fn read(&self, uri: &str) -> Result<ResourceContents, McpError> {
+        let sitemap = job_id_from_uri(uri)
+            .and_then(|job_id| self.sitemap(job_id))
+            .ok_or_else(|| {
+                McpError::resource_not_found(format!("Unknown resource: {uri}"), None)
+            })?;
+        Ok(ResourceContents::TextResourceContents {
+            uri: uri.to_string(),
+            mime_type: Some("application/json".to_string()),
+            text: sitemap.to_string(),
+            meta: None,
+        })
+    }
+}
+
+/// Resource URI of a job's sitemap
+pub fn sitemap_uri(job_id: &str) -> String {
+    format!("{SITEMAP_URI_PREFIX}{job_id}")
+}
+
+/// Job ID of a sitemap resource URI
+pub fn job_id_from_uri(uri: &str) -> Option<&str> {
+    uri.strip_prefix(SITEMAP_URI_PREFIX)
+}
+
+/// Progress message for a batch: the page crawled and the URLs it led to
+pub fn progress_message(job_id: &str, batch: &CrawlBatch) -> String {
+    let url = batch.page["url"].as_str().unwrap_or_default();
+    if batch.discovered.is_empty() {
+        format!("Sitemap job {job_id}: crawled {url}")
+    } else {
+        format!(
+            "Sitemap job {job_id}: crawled {url}; discovered {}",
+            batch.discovered.join(" ")
+        )
+    }
+}
+
+/// The crawl tests' fixture site visitor, shared with the integration tests
+#[cfg(test)]
+#[path = "../../../tests/common/fixture_site.rs"]
+mod fixture_site;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fixture_site::FixtureSite;
+
+    const ORIGIN: &str = "http://fixture.test";
 
-        for href in &outbound {
-            let h = href.trim_end_matches('/').to_string();
-            if !visited.contains(&h) {
-                queue.push_back((href.clone(), depth + 1));
+    /// Pages of the site in tests/fixtures/sitemap
+    fn fixture_page(path: &str) -> Option<&'static str> {
+        Some(match path {
+            "/" | "/index.html" => include_str!("../../../tests/fixtures/sitemap/index.html"),
+            "/about.html" => include_str!("../../../tests/fixtures/sitemap/about.html"),
+            "/products.html" => include_str!("../../../tests/fixtures/sitemap/products.html"),
+            "/contact.html" => include_str!("../../../tests/fixtures/sitemap/contact.html"),
+            "/products/lamp.html" => {
+                include_str!("../../../tests/fixtures/sitemap/products/lamp.html")
             }
+            "/products/chair.html" => {
+                include_str!("../../../tests/fixtures/sitemap/products/chair.html")
+            }
+            _ => return None,
+        })
+    }
+
+    fn params(max_pages: u32) -> GenerateSitemapParams {
+        GenerateSitemapParams {
+            url: format!("{ORIGIN}/index.html"),
+            max_pages: Some(max_pages),
+            max_depth: None,
+            same_domain_only: None,
+            content_preview_chars: None,
+            save_path: None,
+            overwrite: None,
+            delay_ms: None,
         }
+    }
+
+    #[tokio::test]
+    async fn test_batches_arrive_before_the_crawl_completes() {
+        let (sender, mut batches) = mpsc::channel(1);
+        let task = tokio::spawn(async move {
+            let mut site = FixtureSite::new(fixture_page);
+            run_sitemap_crawl(&mut site, &params(30), sender).await
+        });
+        let mut jobs = SitemapJobs::default();
+        jobs.start(
+            "job-1",
+            &format!("{ORIGIN}/index.html"),
+            task.abort_handle(),
+        );
+
+        let first = batches.recv().await.unwrap();
+        // The channel holds one batch, so the crawl is waiting on us
+        assert!(!task.is_finished());
+        assert_eq!(first.page["title"], "Fixture Shop");
+        assert_eq!(
+            first.discovered,
+            [
+                format!("{ORIGIN}/about.html"),
+                format!("{ORIGIN}/products.html"),
+                format!("{ORIGIN}/contact.html")
+            ]
+        );
+        assert_eq!(
+            progress_message("job-1", &first),
+            format!(
+                "Sitemap job job-1: crawled {ORIGIN}/index.html; discovered {ORIGIN}/about.html {ORIGIN}/products.html {ORIGIN}/contact.html"
+            )
+        );
+        jobs.record("job-1", first);
 
-        pages.push(serde_json::json!({
-            "url": current_url,
-            "title": title,
-            "content_preview": content_preview,
-            "links": outbound,
-            "depth": depth
-        }));
+        // The sitemap so far is readable while the crawl runs
+        let ResourceContents::TextResourceContents { text, .. } =
+            jobs.read(&sitemap_uri("job-1")).unwrap()
+        else {
+            panic!("expected text contents");
+        };
+        let partial: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(partial["status"], "running");
+        assert_eq!(partial["total_pages"], 1);
+
+        let mut titles = vec![];
+        while let Some(batch) = batches.recv().await {
+            titles.push(batch.page["title"].as_str().unwrap().to_string());
+            jobs.record("job-1", batch);
+        }
+        task.await.unwrap().unwrap();
+        jobs.finish("job-1", JobStatus::Completed, None);
+
+        assert_eq!(titles, ["About", "Products", "Contact", "Lamp", "Chair"]);
+        let sitemap = jobs.sitemap("job-1").unwrap();
+        assert_eq!(sitemap["status"], "completed");
+        assert_eq!(sitemap["total_pages"], 6);
+        // The partner link is on another domain
+        assert_eq!(sitemap["discovered_urls"], 5);
     }
 
-    Ok(serde_json::json!({
-        "base_url": p.url,
-        "total_pages": pages.len(),
-        "pages": pages
-    }))
+    #[tokio::test]
+    async fn test_cancelled_job_keeps_pages_crawled_so_far() {
+        let (sender, mut batches) = mpsc::channel(1);
+        let task = tokio::spawn(async move {
+            let mut site = FixtureSite::new(fixture_page);
+            run_sitemap_crawl(&mut site, &params(30), sender).await
+        });
+        let mut jobs = SitemapJobs::default();
+        jobs.start(
+            "job-2",
+            &format!("{ORIGIN}/index.html"),
+            task.abort_handle(),
+        );
+
+        let first = batches.recv().await.unwrap();
+        jobs.record("job-2", first);
+        assert_eq!(jobs.cancel("job-2").unwrap(), JobStatus::Cancelled);
+
+        // Aborting drops the sender, so the channel ends after what was queued
+        while let Some(batch) = batches.recv().await {
+            jobs.record("job-2", batch);
+        }
+        assert!(task.await.unwrap_err().is_cancelled());
+        jobs.finish("job-2", JobStatus::Completed, None);
+
+        let sitemap = jobs.sitemap("job-2").unwrap();
+        assert_eq!(sitemap["status"], "cancelled");
+        assert!(sitemap["total_pages"].as_u64().unwrap() < 6);
+        // Cancelling again reports the final status
+        assert_eq!(jobs.cancel("job-2").unwrap(), JobStatus::Cancelled);
+        assert!(jobs.cancel("job-unknown").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_crawl_respects_max_pages() {
+        let (sender, mut batches) = mpsc::channel(CRAWL_CHANNEL_CAPACITY);
+        let mut site = FixtureSite::new(fixture_page);
+        let params = params(2);
+        let crawl = run_sitemap_crawl(&mut site, &params, sender);
+        let (result, count) = tokio::join!(crawl, async {
+            let mut count = 0;
+            while batches.recv().await.is_some() {
+                count += 1;
+            }
+            count
+        });
+        result.unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_finished_jobs_are_forgotten_oldest_first() {
+        let mut jobs = SitemapJobs::default();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let _guard = runtime.enter();
+        for i in 0..=MAX_FINISHED_JOBS {
+            let task = tokio::spawn(async {});
+            jobs.start(&format!("job-{i}"), ORIGIN, task.abort_handle());
+            jobs.finish(&format!("job-{i}"), JobStatus::Completed, None);
+        }
+        assert!(jobs.sitemap("job-0").is_none());
+        let newest = jobs.sitemap(&format!("job-{MAX_FINISHED_JOBS}")).unwrap();
+        assert_eq!(newest["status"], "completed");
+        assert_eq!(jobs.list().len(), MAX_FINISHED_JOBS);
+        assert!(jobs.read(&sitemap_uri("job-0")).is_err());
+    }
 }
@@ -0,0 +1,311 @@
+//! Where file-writing tools may write
+//!
+//! Paths from clients are resolved before anything is written: relative paths
+//! against the first root, then `..` and symlinks by canonicalizing the
+//! deepest part of the path that exists. A path is allowed only if it resolves
+//! inside one of the roots, so neither `../../etc` nor a symlink pointing out
+//! of a root escapes it.
+
+use rmcp::model::ErrorData as McpError;
+use std::path::{Path, PathBuf};
+
+/// Directories tools may write files in
+pub struct WriteRoots {
+    roots: Vec<PathBuf>,
+}
+
+impl WriteRoots {
+    /// Roots from the tools config; see `ToolsConfig::write_roots`
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        Self { roots }
+    }
+
+    /// Directory for files saved without a path: the first root
+    pub fn default_dir(&self) -> PathBuf {
+        self.roots
+            .first()
+            .cloned()
+            .unwrap_or_else(browsing::config::default_write_root)
+    }
+
+    /// Resolve a file to write, refusing an existing file unless `overwrite` is set
+    pub fn file(&self, requested: &Path, overwrite: bool) -> Result<PathBuf, McpError> {
+        let path = self.resolve(requested)?;
+        if !overwrite && std::fs::symlink_metadata(&path).is_ok() {
+            return Err(McpError::invalid_params(
+                format!(
+                    "{} already exists; call again with overwrite: true to replace it",
+                    path.display()
+                ),
+                None,
+            ));
+        }
+        Ok(path)
+    }
+
+    /// Resolve a directory to write files in
+    pub fn dir(&self, requested: &Path) -> Result<PathBuf, McpError> {
+        self.resolve(requested)
+    }
+
+    fn resolve(&self, requested: &Path) -> Result<PathBuf, McpError> {
+        let roots = self.canonical_roots();
+        let absolute = if requested.is_absolute() {
+            requested.to_path_buf()
+        } else {
+            self.default_dir().join(requested)
+        };
+        match resolve_lenient(&absolute) {
+            Some(path) if roots.iter().any(|root| path.starts_with(root)) => Ok(path),
+            _ => Err(McpError::invalid_params(
+                format!(
+                    "{} is outside the allowed write roots: {}",
+                    requested.display(),
+                    self.roots
+                        .iter()
+                        .map(|root| root.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                None,
+            )),
+        }
+    }
+
+    /// The roots with symlinks resolved, created if missing
+    ///
+    /// Missing roots are created accessible to the current user only. A root
+    /// that is a symlink owned by another user is left out, since in a shared
+    /// directory anyone could have put it there.
+    fn canonical_roots(&self) -> Vec<PathBuf> {
+        let roots = if self.roots.is_empty() {
+            vec![self.default_dir()]
+        } else {
+            self.roots.clone()
+        };
+        roots
+            .iter()
+            .filter_map(|root| {
+                let canonical = create_private_dir(root)
+                    .and_then(|()| refuse_foreign_link(root))
+                    .and_then(|()| root.canonicalize());
+                if let Err(e) = &canonical {
+                    tracing::warn!("Write root {} is unusable: {}", root.display(), e);
+                }
+                canonical.ok()
+            })
+            .collect()
+    }
+}
+
+/// Create `dir` and its missing parents with mode 0700
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir)
+}
+
+/// Fail if `root` is a symlink owned by neither the current user nor root
+#[cfg(unix)]
+fn refuse_foreign_link(root: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = std::fs::symlink_metadata(root)?;
+    let owner = metadata.uid();
+    if !metadata.file_type().is_symlink() || owner == 0 || Some(owner) == current_uid() {
+        return Ok(());
+    }
+    Err(std::io::Error::new(
+        std::io::ErrorKind::PermissionDenied,
+        format!("it is a symlink owned by another user (uid {owner})"),
+    ))
+}
+
+#[cfg(not(unix))]
+fn refuse_foreign_link(_root: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// User ID the server runs as, from the owner of a file it creates
+#[cfg(unix)]
+fn current_uid() -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+    if let Ok(metadata) = std::fs::metadata("/proc/self") {
+        return Some(metadata.uid());
+    }
+    let probe = std::env::temp_dir().join(format!(".browsing-uid-{}", std::process::id()));
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+        .ok()?;
+    let uid = file.metadata().ok().map(|metadata| metadata.uid());
+    let _ = std::fs::remove_file(&probe);
+    uid
+}
+
+/// `path` with `..` and symlinks resolved, for a path that need not exist yet
+///
+/// `None` if the part that does not exist has `..` in it, or if the path is
+/// a symlink that points nowhere (writing would create its target).
+fn resolve_lenient(path: &Path) -> Option<PathBuf> {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    let canonical = loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            break canonical;
+        }
+        if std::fs::symlink_metadata(existing).is_ok() {
+            return None;
+        }
+        // `None` for a path ending in `..`
+        missing.push(existing.file_name()?);
+        existing = existing.parent()?;
+    };
+    Some(
+        missing
+            .into_iter()
+            .rev()
+            .fold(canonical, |path, name| path.join(name)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roots() -> (tempfile::TempDir, WriteRoots) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        std::fs::create_dir(&root).unwrap();
+        (dir, WriteRoots::new(vec![root]))
+    }
+
+    #[test]
+    fn test_paths_inside_a_root_are_allowed() {
+        let (dir, roots) = roots();
+        let root = dir.path().join("root").canonicalize().unwrap();
+
+        assert_eq!(
+            roots.file(Path::new("page.txt"), false).unwrap(),
+            root.join("page.txt")
+        );
+        assert_eq!(
+            roots.file(&root.join("new/page.txt"), false).unwrap(),
+            root.join("new/page.txt")
+        );
+        // `..` after a directory that does not exist is refused, then fine once it does
+        assert!(roots.dir(&root.join("a/../images")).is_err());
+        std::fs::create_dir(root.join("a")).unwrap();
+        assert_eq!(
+            roots.dir(&root.join("a/../images")).unwrap(),
+            root.join("images")
+        );
+    }
+
+    #[test]
+    fn test_parent_traversal_is_refused() {
+        let (dir, roots) = roots();
+
+        for path in [
+            "../outside.txt",
+            "sub/../../outside.txt",
+            "../../../../etc/passwd",
+        ] {
+            let error = roots.file(Path::new(path), false).unwrap_err();
+            assert!(
+                error.message.contains("outside the allowed write roots"),
+                "{path}: {}",
+                error.message
+            );
+            assert!(
+                error
+                    .message
+                    .contains(&dir.path().join("root").display().to_string())
+            );
+        }
+        assert!(roots.file(&dir.path().join("outside.txt"), false).is_err());
+        assert!(roots.dir(dir.path()).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_out_of_a_root_are_refused() {
+        let (dir, roots) = roots();
+        let root = dir.path().join("root");
+        let outside = dir.path().join("outside");
+        std::fs::create_dir(&outside).unwrap();
+        std::fs::write(outside.join("secret.txt"), "secret").unwrap();
+
+        // A directory link, an existing file through it, and a dangling file link
+        std::os::unix::fs::symlink(&outside, root.join("escape")).unwrap();
+        std::os::unix::fs::symlink(outside.join("new.txt"), root.join("dangling")).unwrap();
+        assert!(roots.file(Path::new("escape/new.txt"), false).is_err());
+        assert!(roots.file(Path::new("escape/secret.txt"), true).is_err());
+        assert!(roots.dir(Path::new("escape")).is_err());
+        assert!(roots.file(Path::new("dangling"), true).is_err());
+
+        // A link inside the root to elsewhere in the root is fine
+        std::fs::create_dir(root.join("real")).unwrap();
+        std::os::unix::fs::symlink(root.join("real"), root.join("alias")).unwrap();
+        assert_eq!(
+            roots.file(Path::new("alias/page.txt"), false).unwrap(),
+            root.canonicalize().unwrap().join("real/page.txt")
+        );
+    }
+
+    #[test]
+    fn test_existing_files_need_overwrite() {
+        let (dir, roots) = roots();
+        std::fs::write(dir.path().join("root/page.txt"), "old").unwrap();
+
+        let error = roots.file(Path::new("page.txt"), false).unwrap_err();
+        assert!(
+            error.message.contains("overwrite: true"),
+            "{}",
+            error.message
+        );
+        assert!(roots.file(Path::new("page.txt"), true).is_ok());
+        assert!(roots.file(Path::new("other.txt"), false).is_ok());
+    }
+
+    #[test]
+    fn test_missing_roots_are_created() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("sandbox");
+        let roots = WriteRoots::new(vec![root.clone()]);
+
+        assert!(roots.file(Path::new("page.txt"), false).is_ok());
+        assert!(root.is_dir());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&root).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_root_links_owned_by_another_user_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("target");
+        std::fs::create_dir(&target).unwrap();
+        let link = dir.path().join("browsing");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        let roots = WriteRoots::new(vec![link.clone()]);
+
+        // Our own link is followed
+        assert_eq!(
+            roots.file(Path::new("page.txt"), false).unwrap(),
+            target.canonicalize().unwrap().join("page.txt")
+        );
+
+        // Handing the link to another user needs privileges the tests may not have
+        if std::os::unix::fs::lchown(&link, Some(4242), None).is_err() {
+            return;
+        }
+        assert!(roots.file(Path::new("page.txt"), false).is_err());
+    }
+}
@@ -0,0 +1,56 @@
+//! Print a debug report of a saved `DOMSnapshot.captureSnapshot` result
+
+use anyhow::{Context, Result};
+use browsing::dom::SnapshotDebugger;
+use clap::Parser;
+use serde_json::Value;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "browsing-snapshot-debugger")]
+#[command(
+    about = "Summarize a DOMSnapshot.captureSnapshot result to find out why elements are missing from the selector map",
+    long_about = None
+)]
+#[command(version)]
+struct Cli {
+    #[arg(help = "JSON file with the snapshot, or a CDP response wrapping it in \"result\"")]
+    file: PathBuf,
+
+    #[arg(long, help = "Also show the node with this backend node ID")]
+    node: Vec<u64>,
+
+    #[arg(long, help = "Print the summary as JSON")]
+    json: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let content = std::fs::read_to_string(&cli.file)
+        .with_context(|| format!("Failed to read {}", cli.file.display()))?;
+    let mut snapshot: Value = serde_json::from_str(&content)
+        .with_context(|| format!("{} is not valid JSON", cli.file.display()))?;
+    if snapshot.get("documents").is_none() && snapshot["result"].get("documents").is_some() {
+        snapshot = snapshot["result"].take();
+    }
+
+    if cli.json {
+        let summary = SnapshotDebugger::summarize(&snapshot);
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else {
+        SnapshotDebugger::print_document_summary(&snapshot);
+    }
+
+    for backend_node_id in cli.node {
+        match SnapshotDebugger::find_backend_node_id(&snapshot, backend_node_id) {
+            Some(node) => println!(
+                "Node {}:\n{}",
+                backend_node_id,
+                serde_json::to_string_pretty(&node)?
+            ),
+            None => println!("Node {backend_node_id}: not in the snapshot"),
+        }
+    }
+    Ok(())
+}
@@ -1,12 +1,21 @@
 //! Chrome DevTools Protocol (CDP) client implementation
 
+use crate::browser::document_interception::DocumentInterception;
+use crate::browser::frame_contexts::FrameContexts;
+use crate::browser::wire_log::{CdpStats, WireLog, WireLogConfig};
 use crate::error::{BrowsingError, Result};
 use futures_util::{SinkExt, StreamExt};
-use serde_json::Value;
+use serde::Deserialize;
+use serde::de::{DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_json::{Map, Value, json};
 use std::collections::HashMap;
+use std::fmt;
 use std::sync::Arc;
-use tokio::sync::{Mutex, mpsc};
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::{Mutex, broadcast, mpsc, watch};
+use tokio_tungstenite::tungstenite::{self, Message, protocol::WebSocketConfig};
+use tokio_tungstenite::connect_async_with_config;
 
 /// CDP client for WebSocket communication with Chrome
 pub struct CdpClient {
@@ -14,9 +23,83 @@ pub struct CdpClient {
     sender: Arc<Mutex<Option<mpsc::UnboundedSender<Message>>>>,
     receiver: Arc<Mutex<Option<mpsc::UnboundedReceiver<Value>>>>,
     request_id: Arc<Mutex<u64>>,
-    pending_requests: Arc<Mutex<HashMap<u64, mpsc::UnboundedSender<Value>>>>,
+    pending_requests: Arc<Mutex<HashMap<u64, PendingRequest>>>,
+    events: broadcast::Sender<Value>,
+    wire_log: Arc<WireLog>,
+    command_timeout: Option<Duration>,
+    /// Commands that turned on events, replayed after reconnecting
+    subscriptions: Arc<Mutex<Vec<Subscription>>>,
+    /// Whether the WebSocket is open; set to false when the connection drops
+    connected: Arc<watch::Sender<bool>>,
+    /// Set by [`CdpClient::close`] so the intended close is not reconnected
+    closing: Arc<AtomicBool>,
+    reconnect_on_disconnect: bool,
+    /// Held while reconnecting, so concurrent commands reconnect only once
+    reconnecting: Mutex<()>,
+    /// Execution contexts of frames, kept up to date from `Runtime` events
+    frame_contexts: Arc<std::sync::Mutex<FrameContexts>>,
+    /// Layout viewport size of each session, in CSS pixels, until it resizes
+    viewport_sizes: Arc<std::sync::Mutex<HashMap<String, (f64, f64)>>>,
+    /// Rules for the document requests paused in each session
+    document_interception: Arc<DocumentInterception>,
+    max_message_size: usize,
+    max_frame_size: usize,
 }
 
+/// A command that turned on events: method, params and optional session ID
+type Subscription = (String, Value, Option<String>);
+
+/// A command waiting for its response, with the frame to resend after reconnecting
+struct PendingRequest {
+    method: String,
+    response: mpsc::UnboundedSender<Value>,
+    frame: String,
+}
+
+/// A command for [`CdpClient::send_batch`]: method, params and optional session ID
+pub type BatchCommand<'a> = (&'a str, Value, Option<&'a str>);
+
+/// Commands that change the size of a session's viewport, or of every
+/// session's when sent without one
+const RESIZING_COMMANDS: &[&str] = &[
+    "Emulation.setDeviceMetricsOverride",
+    "Emulation.clearDeviceMetricsOverride",
+    "Emulation.setVisibleSize",
+    "Browser.setWindowBounds",
+];
+
+/// Number of unread events kept per subscriber before the oldest are dropped
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// How long a command waits for the connection to come back when reconnecting on its own
+const DEFAULT_RECONNECT_TIMEOUT_MS: u64 = 5_000;
+
+/// Default largest message handled in full, in bytes
+///
+/// Larger messages are kept from dropping the connection: console events
+/// have their long strings truncated, other events are dropped and responses
+/// fail their command, each with a warning. See [`CdpClient::with_max_message_size`].
+pub const MAX_MESSAGE_SIZE: usize = 256 << 20;
+
+/// Default largest frame read from the WebSocket, in bytes
+///
+/// Chrome sends every message as a single frame, and `DOM.getDocument` on a
+/// page with huge inline SVGs or data URLs can exceed tungstenite's defaults
+/// (16 MiB per frame). Past this limit the connection is dropped. Frames are
+/// not compressed: tungstenite 0.24 has no permessage-deflate support, so the
+/// handshake does not offer it.
+pub const MAX_FRAME_SIZE: usize = 1 << 30;
+
+/// Events whose strings can be truncated when over the message size limit
+const TRUNCATABLE_EVENTS: &[&str] = &[
+    "Runtime.consoleAPICalled",
+    "Runtime.exceptionThrown",
+    "Log.entryAdded",
+];
+
+/// Delay between connection attempts while reconnecting
+const RECONNECT_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
 impl CdpClient {
     /// Create a new CDP client with the given WebSocket URL
     pub fn new(url: String) -> Self {
@@ -26,12 +109,187 @@ impl CdpClient {
             receiver: Arc::new(Mutex::new(None)),
             request_id: Arc::new(Mutex::new(0)),
             pending_requests: Arc::new(Mutex::new(HashMap::new())),
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            wire_log: Arc::new(WireLog::default()),
+            command_timeout: None,
+            subscriptions: Arc::new(Mutex::new(Vec::new())),
+            connected: Arc::new(watch::channel(false).0),
+            closing: Arc::new(AtomicBool::new(false)),
+            reconnect_on_disconnect: false,
+            reconnecting: Mutex::new(()),
+            frame_contexts: Arc::new(std::sync::Mutex::new(FrameContexts::new())),
+            viewport_sizes: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            document_interception: Arc::new(DocumentInterception::default()),
+            max_message_size: MAX_MESSAGE_SIZE,
+            max_frame_size: MAX_FRAME_SIZE,
         }
     }
 
+    /// Reconnect when the WebSocket drops instead of failing commands (default: false)
+    ///
+    /// Commands in flight when the connection dropped are sent again once it
+    /// is back, after the commands that enabled events. See [`CdpClient::reconnect`].
+    pub fn with_reconnect_on_disconnect(mut self, reconnect: bool) -> Self {
+        self.reconnect_on_disconnect = reconnect;
+        self
+    }
+
+    /// Fail commands that get no response within `timeout` (default: wait indefinitely)
+    pub fn with_command_timeout(mut self, timeout: Duration) -> Self {
+        self.command_timeout = Some(timeout);
+        self
+    }
+
+    /// Log every command sent by this client (see [`WireLogConfig`])
+    pub fn with_wire_log(mut self, config: WireLogConfig) -> Self {
+        self.wire_log = Arc::new(WireLog::new(config));
+        self
+    }
+
+    /// Handle messages up to `bytes` in full (default: [`MAX_MESSAGE_SIZE`])
+    ///
+    /// Larger messages are truncated or rejected without closing the connection.
+    pub fn with_max_message_size(mut self, bytes: usize) -> Self {
+        self.max_message_size = bytes;
+        self
+    }
+
+    /// Drop the connection on frames over `bytes` (default: [`MAX_FRAME_SIZE`])
+    ///
+    /// Never below the max message size, so that limit applies first.
+    pub fn with_max_frame_size(mut self, bytes: usize) -> Self {
+        self.max_frame_size = bytes;
+        self
+    }
+
+    /// Commands sent so far and sizes of the messages received, per method
+    pub fn stats(&self) -> CdpStats {
+        self.wire_log.stats()
+    }
+
+    /// Subscribe to CDP events (messages without a request ID)
+    pub fn subscribe_events(&self) -> broadcast::Receiver<Value> {
+        self.events.subscribe()
+    }
+
+    /// Main-world execution context of `frame_id` in `session_id`
+    ///
+    /// Known once `Runtime.enable` was sent to the session; Chrome reports the
+    /// existing contexts before it responds.
+    pub fn frame_execution_context(&self, session_id: &str, frame_id: &str) -> Option<i64> {
+        self.frame_contexts
+            .lock()
+            .ok()
+            .and_then(|contexts| contexts.get(session_id, frame_id))
+    }
+
+    /// Layout viewport size of `session_id` last read, if it has not resized since
+    ///
+    /// Forgotten when the session reports `Page.frameResized` (sent once
+    /// `Page.enable` was) and when the viewport is resized through this client.
+    pub(crate) fn cached_viewport_size(&self, session_id: &str) -> Option<(f64, f64)> {
+        self.viewport_sizes
+            .lock()
+            .ok()
+            .and_then(|sizes| sizes.get(session_id).copied())
+    }
+
+    /// Remember the layout viewport size of `session_id`
+    pub(crate) fn cache_viewport_size(&self, session_id: &str, size: (f64, f64)) {
+        if let Ok(mut sizes) = self.viewport_sizes.lock() {
+            sizes.insert(session_id.to_string(), size);
+        }
+    }
+
+    /// Rules for the document requests paused in each session
+    pub(crate) fn document_interception(&self) -> &DocumentInterception {
+        &self.document_interception
+    }
+
+    /// Whether the WebSocket connection is open
+    pub fn is_connected(&self) -> bool {
+        *self.connected.borrow()
+    }
+
     /// Start the WebSocket connection to the browser
     pub async fn start(&mut self) -> Result<()> {
-        let (ws_stream, _) = connect_async(&self.url)
+        self.closing.store(false, Ordering::SeqCst);
+        self.connect().await
+    }
+
+    /// Re-establish a dropped WebSocket connection to the same debug URL
+    ///
+    /// Retries for up to `timeout_ms`, then re-sends the commands that enabled
+    /// events (`*.enable`, target discovery) and the commands still waiting for
+    /// a response. Event subscribers keep their receivers. Does nothing if the
+    /// connection is open. Sessions attached over the old connection are gone
+    /// in the browser, so commands for them fail until the target is attached
+    /// again. Works with Arc via &self.
+    pub async fn reconnect(&self, timeout_ms: u64) -> Result<()> {
+        let _reconnecting = self.reconnecting.lock().await;
+        if self.is_connected() {
+            return Ok(());
+        }
+        if self.closing.load(Ordering::SeqCst) {
+            return Err(disconnected());
+        }
+
+        let connect = async {
+            loop {
+                match self.connect().await {
+                    Ok(()) => break,
+                    Err(e) => {
+                        tracing::debug!("CDP reconnect attempt failed: {}", e);
+                        tokio::time::sleep(RECONNECT_RETRY_INTERVAL).await;
+                    }
+                }
+            }
+        };
+        if tokio::time::timeout(Duration::from_millis(timeout_ms), connect)
+            .await
+            .is_err()
+        {
+            tracing::warn!("Could not reconnect to CDP within {}ms", timeout_ms);
+            return Err(disconnected());
+        }
+        tracing::info!("Reconnected to CDP at {}", self.url);
+
+        let subscriptions = self.subscriptions.lock().await.clone();
+        let mut frames = Vec::with_capacity(subscriptions.len());
+        for (method, params, session_id) in subscriptions {
+            // Answers to these are not awaited; unknown IDs are ignored on receipt
+            let id = self.next_id().await;
+            frames.push(request_frame(id, &method, params, session_id.as_deref()));
+        }
+        let mut pending: Vec<(u64, String)> = self
+            .pending_requests
+            .lock()
+            .await
+            .iter()
+            .map(|(id, request)| (*id, request.frame.clone()))
+            .collect();
+        pending.sort_by_key(|(id, _)| *id);
+        frames.extend(pending.into_iter().map(|(_, frame)| frame));
+
+        if let Some(sender) = self.sender.lock().await.as_ref() {
+            for frame in frames {
+                let _ = sender.send(Message::Text(frame));
+            }
+        }
+        Ok(())
+    }
+
+    /// Open the WebSocket and spawn the task that reads from and writes to it
+    async fn connect(&self) -> Result<()> {
+        // Without TCP_NODELAY, pipelined frames would wait for the previous one to be acknowledged
+        let max_message_size = self.max_message_size;
+        let max_frame_size = self.max_frame_size.max(max_message_size);
+        let config = WebSocketConfig {
+            max_message_size: Some(max_frame_size),
+            max_frame_size: Some(max_frame_size),
+            ..WebSocketConfig::default()
+        };
+        let (ws_stream, _) = connect_async_with_config(&self.url, Some(config), true)
             .await
             .map_err(|e| BrowsingError::Cdp(format!("Failed to connect to CDP: {e}")))?;
 
@@ -43,6 +301,12 @@ impl CdpClient {
         *self.receiver.lock().await = Some(rx_resp);
 
         let pending_requests = Arc::clone(&self.pending_requests);
+        let events = self.events.clone();
+        let frame_contexts = Arc::clone(&self.frame_contexts);
+        let viewport_sizes = Arc::clone(&self.viewport_sizes);
+        let wire_log = Arc::clone(&self.wire_log);
+        let connected = Arc::clone(&self.connected);
+        connected.send_replace(true);
 
         // Spawn task to handle incoming messages
         tokio::spawn(async move {
@@ -63,15 +327,59 @@ impl CdpClient {
                     msg = read.next() => {
                         match msg {
                             Some(Ok(Message::Text(text))) => {
-                                if let Ok(value) = serde_json::from_str::<Value>(&text) {
+                                let size = text.len();
+                                let oversized = size > max_message_size;
+                                let parsed = if oversized {
+                                    parse_oversized(&text, max_message_size)
+                                } else {
+                                    serde_json::from_str::<Value>(&text).ok()
+                                };
+                                if let Some(mut value) = parsed {
                                     if let Some(id_val) = value.get("id").and_then(|v| v.as_u64()) {
-                                        if let Some(tx) = pending_requests.lock().await.remove(&id_val) {
-                                            let _ = tx.send(value);
+                                        if let Some(request) = pending_requests.lock().await.remove(&id_val) {
+                                            wire_log.record_incoming(&request.method, size, oversized);
+                                            if oversized {
+                                                tracing::warn!(
+                                                    "CDP response to {} of {size} bytes is over the {max_message_size} byte limit, failing the command",
+                                                    request.method
+                                                );
+                                                value = oversized_response(id_val, size, max_message_size);
+                                            }
+                                            let _ = request.response.send(value);
+                                        }
+                                    } else if let Some(method) = value.get("method").and_then(|v| v.as_str()) {
+                                        wire_log.record_incoming(method, size, oversized);
+                                        if oversized {
+                                            if !TRUNCATABLE_EVENTS.contains(&method) {
+                                                tracing::warn!(
+                                                    "CDP event {method} of {size} bytes is over the {max_message_size} byte limit, dropping it"
+                                                );
+                                                continue;
+                                            }
+                                            tracing::warn!(
+                                                "CDP event {method} of {size} bytes is over the {max_message_size} byte limit, truncating it"
+                                            );
+                                        }
+                                        let method = value["method"].as_str().unwrap_or_default();
+                                        // Before any response that follows, so `Runtime.enable` callers see the contexts
+                                        if method.starts_with("Runtime.executionContext")
+                                            && let Ok(mut contexts) = frame_contexts.lock()
+                                        {
+                                            contexts.apply_event(&value);
                                         }
+                                        if method == "Page.frameResized" {
+                                            forget_viewport_size(&viewport_sizes, value["sessionId"].as_str());
+                                        }
+                                        // No subscribers is not an error
+                                        let _ = events.send(value);
                                     }
                                 }
                             }
                             Some(Ok(Message::Close(_))) => break,
+                            Some(Err(tungstenite::Error::Capacity(e))) => {
+                                tracing::warn!("CDP frame over the {max_frame_size} byte limit, closing: {e}");
+                                break;
+                            }
                             Some(Err(e)) => {
                                 tracing::debug!("WebSocket closed: {}", e);
                                 break;
@@ -82,6 +390,8 @@ impl CdpClient {
                     }
                 }
             }
+            // Wakes commands waiting for a response, which fail or reconnect
+            connected.send_replace(false);
         });
 
         Ok(())
@@ -99,33 +409,123 @@ impl CdpClient {
         params: Value,
         session_id: Option<&str>,
     ) -> Result<Value> {
-        let mut request_id = self.request_id.lock().await;
-        let id = *request_id;
-        *request_id += 1;
-        drop(request_id);
+        let log = self.wire_log.record(method);
+        let logged_params = log.then(|| params.clone());
+        let started = std::time::Instant::now();
 
-        let mut request = serde_json::json!({
-            "id": id,
-            "method": method,
-            "params": params
-        });
+        let request = self.write_request(method, params, session_id).await;
+        let result = self.await_response(request).await;
 
-        // Add sessionId if provided
-        if let Some(sid) = session_id {
-            request["sessionId"] = serde_json::json!(sid);
+        self.finish_command(method, logged_params, session_id, started, &result);
+        if RESIZING_COMMANDS.contains(&method) {
+            forget_viewport_size(&self.viewport_sizes, session_id);
         }
+        result
+    }
 
-        let (tx, mut rx) = mpsc::unbounded_channel();
-        self.pending_requests.lock().await.insert(id, tx);
+    /// Send several commands without waiting for each response before the next
+    ///
+    /// All frames are written first, then the responses are collected by
+    /// request ID, so the batch costs about one round trip instead of one per
+    /// command. Results are in command order; each command succeeds or fails
+    /// (including timing out) on its own. The browser still executes commands
+    /// of a session in order, so a domain can be enabled earlier in the batch
+    /// than the commands that need it.
+    pub async fn send_batch(&self, commands: Vec<BatchCommand<'_>>) -> Vec<Result<Value>> {
+        let started = std::time::Instant::now();
+        let mut sent = Vec::with_capacity(commands.len());
+        for (method, params, session_id) in commands {
+            let logged_params = self.wire_log.record(method).then(|| params.clone());
+            let request = self.write_request(method, params, session_id).await;
+            sent.push((method, session_id, logged_params, request));
+        }
+
+        let responses = sent
+            .into_iter()
+            .map(|(method, session_id, logged, request)| async move {
+                let result = self.await_response(request).await;
+                self.finish_command(method, logged, session_id, started, &result);
+                result
+            });
+        futures_util::future::join_all(responses).await
+    }
+
+    /// Register a pending request and queue its frame for writing
+    async fn write_request(
+        &self,
+        method: &str,
+        params: Value,
+        session_id: Option<&str>,
+    ) -> Result<(u64, mpsc::UnboundedReceiver<Value>)> {
+        let id = self.next_id().await;
+        self.track_subscription(method, &params, session_id).await;
+        let frame = request_frame(id, method, params, session_id);
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.pending_requests.lock().await.insert(
+            id,
+            PendingRequest {
+                method: method.to_string(),
+                response: tx,
+                frame: frame.clone(),
+            },
+        );
 
         if let Some(sender) = self.sender.lock().await.as_ref() {
-            sender
-                .send(Message::Text(request.to_string()))
-                .map_err(|e| BrowsingError::Cdp(format!("Failed to send command: {e}")))?;
+            // Fails only if the connection dropped; the frame stays pending for a reconnect
+            let _ = sender.send(Message::Text(frame));
         }
+        Ok((id, rx))
+    }
 
-        // Wait for response
-        if let Some(response) = rx.recv().await {
+    async fn next_id(&self) -> u64 {
+        let mut request_id = self.request_id.lock().await;
+        let id = *request_id;
+        *request_id += 1;
+        id
+    }
+
+    /// Remember commands that turn events on, and forget them when turned off
+    async fn track_subscription(&self, method: &str, params: &Value, session_id: Option<&str>) {
+        let session_id = session_id.map(str::to_string);
+        let mut subscriptions = self.subscriptions.lock().await;
+        if let Some(domain) = method.strip_suffix(".disable") {
+            subscriptions.retain(|(m, _, s)| {
+                !(m.strip_suffix(".enable") == Some(domain) && *s == session_id)
+            });
+        } else if method.ends_with(".enable")
+            || matches!(method, "Target.setDiscoverTargets" | "Target.setAutoAttach")
+        {
+            subscriptions.retain(|(m, _, s)| !(m == method && *s == session_id));
+            subscriptions.push((method.to_string(), params.clone(), session_id));
+        }
+    }
+
+    /// Wait for the response to a written request, within the command timeout
+    async fn await_response(
+        &self,
+        request: Result<(u64, mpsc::UnboundedReceiver<Value>)>,
+    ) -> Result<Value> {
+        let (id, mut rx) = request?;
+        let response = match self.command_timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, self.receive(&mut rx)).await {
+                Ok(response) => response,
+                Err(_) => Err(BrowsingError::Cdp(format!(
+                    "No response within {}ms",
+                    timeout.as_millis()
+                ))),
+            },
+            None => self.receive(&mut rx).await,
+        };
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                self.pending_requests.lock().await.remove(&id);
+                return Err(e);
+            }
+        };
+
+        if let Some(response) = response {
             if let Some(error) = response.get("error") {
                 return Err(BrowsingError::Cdp(format!("CDP error: {error}")));
             }
@@ -135,14 +535,206 @@ impl CdpClient {
         Err(BrowsingError::Cdp("No response received".to_string()))
     }
 
+    /// Wait for a response, reconnecting if the connection drops and that is enabled
+    async fn receive(&self, rx: &mut mpsc::UnboundedReceiver<Value>) -> Result<Option<Value>> {
+        loop {
+            tokio::select! {
+                biased;
+                response = rx.recv() => return Ok(response),
+                () = self.wait_for_disconnect() => {
+                    if !self.reconnect_on_disconnect || self.closing.load(Ordering::SeqCst) {
+                        return Err(disconnected());
+                    }
+                    // Re-sends this command once connected again
+                    self.reconnect(DEFAULT_RECONNECT_TIMEOUT_MS).await?;
+                }
+            }
+        }
+    }
+
+    /// Resolve once the WebSocket is not connected
+    async fn wait_for_disconnect(&self) {
+        let mut connected = self.connected.subscribe();
+        let _ = connected.wait_for(|connected| !connected).await;
+    }
+
+    /// Record the outcome of a command in the wire log
+    fn finish_command(
+        &self,
+        method: &str,
+        logged_params: Option<Value>,
+        session_id: Option<&str>,
+        started: std::time::Instant,
+        result: &Result<Value>,
+    ) {
+        if result.is_err() {
+            self.wire_log.record_error(method);
+        }
+        if let Some(params) = logged_params {
+            let outcome = match result {
+                Ok(value) => Ok(value.clone()),
+                Err(e) => Err(e.to_string()),
+            };
+            self.wire_log
+                .log(method, &params, session_id, started.elapsed(), &outcome);
+        }
+    }
+
     /// Gracefully close the WebSocket connection (works with Arc via &self)
     pub async fn close(&self) {
+        self.closing.store(true, Ordering::SeqCst);
         if let Some(sender) = self.sender.lock().await.as_ref() {
             let _ = sender.send(Message::Close(None));
         }
     }
 }
 
+/// JSON frame of a command
+fn request_frame(id: u64, method: &str, params: Value, session_id: Option<&str>) -> String {
+    let mut request = serde_json::json!({
+        "id": id,
+        "method": method,
+        "params": params
+    });
+
+    // Add sessionId if provided
+    if let Some(sid) = session_id {
+        request["sessionId"] = serde_json::json!(sid);
+    }
+    request.to_string()
+}
+
+/// Error for commands that cannot complete because the connection is gone
+fn disconnected() -> BrowsingError {
+    BrowsingError::Browser("CDP disconnected".to_string())
+}
+
+/// Forget the viewport size of `session_id`, or of every session without one
+fn forget_viewport_size(
+    sizes: &std::sync::Mutex<HashMap<String, (f64, f64)>>,
+    session_id: Option<&str>,
+) {
+    if let Ok(mut sizes) = sizes.lock() {
+        match session_id {
+            Some(session_id) => {
+                sizes.remove(session_id);
+            }
+            None => sizes.clear(),
+        }
+    }
+}
+
+/// Error response standing in for a response over the message size limit
+fn oversized_response(id: u64, size: usize, limit: usize) -> Value {
+    json!({
+        "id": id,
+        "error": {
+            "code": -32000,
+            "message": format!("Response of {size} bytes is over the {limit} byte limit")
+        }
+    })
+}
+
+/// ID and method of a CDP message, read without building the rest of it
+#[derive(Deserialize)]
+struct MessageHeader {
+    id: Option<u64>,
+    method: Option<String>,
+}
+
+/// `text`, over the `limit` byte message size limit, parsed without building
+/// it in full
+///
+/// Responses keep only their ID and events only their method, except events
+/// in [`TRUNCATABLE_EVENTS`], whose strings are cut to `limit / 8` bytes as
+/// they are read.
+fn parse_oversized(text: &str, limit: usize) -> Option<Value> {
+    let header: MessageHeader = serde_json::from_str(text).ok()?;
+    match (header.id, header.method) {
+        (Some(id), _) => Some(json!({ "id": id })),
+        (None, Some(method)) if TRUNCATABLE_EVENTS.contains(&method.as_str()) => {
+            let seed = Truncating { max_bytes: limit / 8 };
+            seed.deserialize(&mut serde_json::Deserializer::from_str(text))
+                .ok()
+        }
+        (None, Some(method)) => Some(json!({ "method": method })),
+        (None, None) => None,
+    }
+}
+
+/// Reads a JSON value with strings longer than `max_bytes` cut, at any depth
+#[derive(Clone, Copy)]
+struct Truncating {
+    max_bytes: usize,
+}
+
+impl<'de> DeserializeSeed<'de> for Truncating {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> std::result::Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Truncating {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> std::result::Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> std::result::Result<Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_u64<E>(self, v: u64) -> std::result::Result<Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_f64<E>(self, v: f64) -> std::result::Result<Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_str<E>(self, v: &str) -> std::result::Result<Value, E> {
+        if v.len() <= self.max_bytes {
+            return Ok(Value::String(v.to_string()));
+        }
+        let mut end = self.max_bytes;
+        while !v.is_char_boundary(end) {
+            end -= 1;
+        }
+        Ok(Value::String(format!(
+            "{}...(truncated {} bytes)",
+            &v[..end],
+            v.len() - end
+        )))
+    }
+
+    fn visit_unit<E>(self) -> std::result::Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<Value, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element_seed(self)? {
+            items.push(item);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<Value, A::Error> {
+        let mut object = Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            object.insert(key, map.next_value_seed(self)?);
+        }
+        Ok(Value::Object(object))
+    }
+}
+
 /// CDP session for a specific target
 pub struct CdpSession {
     /// The CDP client instance
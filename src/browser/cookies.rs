@@ -0,0 +1,213 @@
+//! Cookie file import and export
+//!
+//! Supports the Netscape cookie file format used by curl, wget and browser
+//! extensions, and plain JSON in the shape CDP returns from `Network.getAllCookies`.
+
+use crate::error::{BrowsingError, Result};
+use serde_json::{Value, json};
+
+/// Prefix curl uses to mark `HttpOnly` cookies in Netscape files
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+/// File format for [`Browser::export_cookies`](crate::browser::Browser::export_cookies)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CookieExportFormat {
+    /// Tab-separated Netscape cookie file
+    Netscape,
+    /// JSON array of CDP cookie objects
+    Json,
+}
+
+/// Parse a Netscape cookie file into `Network.setCookies` parameters
+///
+/// Cookies that don't apply to subdomains are set by URL so they stay host-only.
+pub fn parse_netscape(content: &str) -> Result<Vec<Value>> {
+    let mut cookies = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+            Some(rest) => (rest, true),
+            None => (line, false),
+        };
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let invalid = |reason: &str| {
+            BrowsingError::Validation(format!("Invalid cookie on line {}: {reason}", number + 1))
+        };
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [
+            domain,
+            include_subdomains,
+            path,
+            secure,
+            expires,
+            name,
+            value,
+        ] = fields[..]
+        else {
+            return Err(invalid(&format!(
+                "expected 7 tab-separated fields, found {}",
+                fields.len()
+            )));
+        };
+        let flag = |field: &str, value: &str| match value {
+            "TRUE" => Ok(true),
+            "FALSE" => Ok(false),
+            _ => Err(invalid(&format!("{field} must be TRUE or FALSE"))),
+        };
+        let include_subdomains = flag("include subdomains", include_subdomains)?;
+        let secure = flag("secure", secure)?;
+        let expires: i64 = expires
+            .parse()
+            .map_err(|_| invalid("expiry must be a Unix timestamp"))?;
+        if name.is_empty() {
+            return Err(invalid("cookie name is empty"));
+        }
+
+        let mut cookie = json!({
+            "name": name,
+            "value": value,
+            "path": path,
+            "secure": secure,
+            "httpOnly": http_only,
+        });
+        let host = domain.trim_start_matches('.');
+        if include_subdomains {
+            cookie["domain"] = json!(format!(".{host}"));
+        } else {
+            let scheme = if secure { "https" } else { "http" };
+            cookie["url"] = json!(format!("{scheme}://{host}{path}"));
+        }
+        // 0 marks a session cookie
+        if expires > 0 {
+            cookie["expires"] = json!(expires);
+        }
+        cookies.push(cookie);
+    }
+    Ok(cookies)
+}
+
+/// Format CDP cookies as a Netscape cookie file
+pub fn format_netscape(cookies: &[Value]) -> String {
+    let mut out = String::from("# Netscape HTTP Cookie File\n\n");
+    for cookie in cookies {
+        let text = |key: &str| cookie[key].as_str().unwrap_or_default();
+        let flag = |value: bool| if value { "TRUE" } else { "FALSE" };
+        let domain = text("domain");
+        let path = match text("path") {
+            "" => "/",
+            path => path,
+        };
+        // Session cookies have `expires` of -1
+        let expires = cookie["expires"]
+            .as_f64()
+            .filter(|e| *e > 0.0)
+            .unwrap_or(0.0);
+
+        if cookie["httpOnly"].as_bool().unwrap_or(false) {
+            out.push_str(HTTP_ONLY_PREFIX);
+        }
+        out.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            domain,
+            flag(domain.starts_with('.')),
+            path,
+            flag(cookie["secure"].as_bool().unwrap_or(false)),
+            expires as i64,
+            text("name"),
+            text("value"),
+        ));
+    }
+    out
+}
+
+/// Format CDP cookies in the requested format
+pub fn format_cookies(cookies: &[Value], format: CookieExportFormat) -> Result<String> {
+    match format {
+        CookieExportFormat::Netscape => Ok(format_netscape(cookies)),
+        CookieExportFormat::Json => Ok(serde_json::to_string_pretty(cookies)?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/cookies/cookies.txt");
+
+    #[test]
+    fn test_parse_netscape_fixture() {
+        let cookies = parse_netscape(FIXTURE).unwrap();
+        assert_eq!(cookies.len(), 3);
+
+        assert_eq!(cookies[0]["name"], "session_token");
+        assert_eq!(cookies[0]["domain"], ".example.com");
+        assert_eq!(cookies[0]["secure"], true);
+        assert_eq!(cookies[0]["expires"], 1893456000);
+        assert_eq!(cookies[0]["httpOnly"], false);
+
+        // Host-only session cookie
+        assert_eq!(cookies[1]["url"], "http://shop.example.com/cart");
+        assert!(cookies[1].get("domain").is_none());
+        assert!(cookies[1].get("expires").is_none());
+
+        // `#HttpOnly_` lines are cookies, not comments; values may contain spaces
+        assert_eq!(cookies[2]["name"], "sid");
+        assert_eq!(cookies[2]["value"], "s%3Dx y");
+        assert_eq!(cookies[2]["httpOnly"], true);
+    }
+
+    #[test]
+    fn test_parse_netscape_rejects_malformed_lines() {
+        let err = parse_netscape("# comment\nexample.com\tTRUE\t/\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{err}");
+
+        let err = parse_netscape("example.com\tyes\t/\tFALSE\t0\tname\tvalue\n").unwrap_err();
+        assert!(err.to_string().contains("TRUE or FALSE"), "{err}");
+
+        let err = parse_netscape("example.com\tTRUE\t/\tFALSE\tsoon\tname\tvalue\n").unwrap_err();
+        assert!(err.to_string().contains("Unix timestamp"), "{err}");
+
+        assert!(
+            parse_netscape("\r\n# only comments\r\n")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_format_netscape_round_trips() {
+        let cookies = vec![
+            json!({
+                "name": "session_token", "value": "abc123", "domain": ".example.com",
+                "path": "/", "expires": 1893456000.5, "secure": true, "httpOnly": true
+            }),
+            json!({
+                "name": "cart_id", "value": "42", "domain": "shop.example.com",
+                "path": "/cart", "expires": -1, "secure": false, "httpOnly": false
+            }),
+        ];
+
+        let text = format_netscape(&cookies);
+        assert!(text.starts_with("# Netscape HTTP Cookie File\n"));
+        assert!(text.contains(
+            "#HttpOnly_.example.com\tTRUE\t/\tTRUE\t1893456000\tsession_token\tabc123\n"
+        ));
+        assert!(text.contains("shop.example.com\tFALSE\t/cart\tFALSE\t0\tcart_id\t42\n"));
+
+        let parsed = parse_netscape(&text).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0]["httpOnly"], true);
+        assert_eq!(parsed[1]["url"], "http://shop.example.com/cart");
+    }
+
+    #[test]
+    fn test_format_json() {
+        let cookies = vec![json!({"name": "a", "value": "1", "domain": "example.com"})];
+        let text = format_cookies(&cookies, CookieExportFormat::Json).unwrap();
+        let parsed: Vec<Value> = serde_json::from_str(&text).unwrap();
+        assert_eq!(parsed, cookies);
+    }
+}
@@ -0,0 +1,228 @@
+//! Breadth-first crawling of a site
+//!
+//! [`crawl`] visits pages through a [`PageVisitor`], following links within
+//! the allowed domains up to a depth and page limit, and visits each URL once.
+//! Every crawled page is sent over a channel as a [`CrawledPage`], so callers
+//! can report progress or build a sitemap or link graph as the crawl runs.
+
+use crate::browser::session::Browser;
+use crate::error::{BrowsingError, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use url::Url;
+
+/// A link on a crawled page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageLink {
+    /// Absolute URL
+    pub url: String,
+    /// Anchor text, whitespace collapsed
+    pub text: String,
+}
+
+/// A page as captured by a [`PageVisitor`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VisitedPage {
+    /// URL after redirects
+    pub url: String,
+    /// Document title
+    pub title: String,
+    /// Start of the page text
+    pub content_preview: String,
+    /// Links of the page, in document order
+    pub links: Vec<PageLink>,
+}
+
+/// Loads pages for a crawl
+#[async_trait]
+pub trait PageVisitor: Send {
+    /// Load `url` and capture it; `None` if it could not be loaded
+    async fn visit(&mut self, url: &str) -> Result<Option<VisitedPage>>;
+}
+
+/// Limits of a crawl
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CrawlOptions {
+    /// Most pages to visit
+    pub max_pages: usize,
+    /// Most links to follow from the start page
+    pub max_depth: u32,
+    /// Only follow links to the start page's host and `allowed_domains`
+    pub same_domain_only: bool,
+    /// Other hosts whose links are followed
+    pub allowed_domains: Vec<String>,
+    /// Wait after each navigation before capturing the page, in ms
+    pub delay_ms: u64,
+}
+
+impl Default for CrawlOptions {
+    fn default() -> Self {
+        Self {
+            max_pages: 30,
+            max_depth: 3,
+            same_domain_only: true,
+            allowed_domains: Vec::new(),
+            delay_ms: 800,
+        }
+    }
+}
+
+/// A crawled page and what it led to
+#[derive(Debug, Clone)]
+pub struct CrawledPage {
+    /// The page as visited
+    pub page: VisitedPage,
+    /// Links from the start page followed to reach it
+    pub depth: u32,
+    /// Links of the page within the allowed domains
+    pub outbound: Vec<PageLink>,
+    /// URLs of `outbound` not seen before, queued for crawling
+    pub discovered: Vec<String>,
+}
+
+/// Crawl breadth-first from `start_url`, sending a [`CrawledPage`] after each page
+///
+/// URLs are visited once, compared without trailing slashes. Stops early if
+/// the receiver of `pages` is dropped.
+pub async fn crawl(
+    visitor: &mut dyn PageVisitor,
+    start_url: &str,
+    options: &CrawlOptions,
+    pages: mpsc::Sender<CrawledPage>,
+) -> Result<()> {
+    let start = Url::parse(start_url)
+        .map_err(|e| BrowsingError::Validation(format!("Invalid URL: {e}")))?;
+    let mut domains = options.allowed_domains.clone();
+    domains.extend(start.host_str().map(str::to_string));
+
+    // URLs visited or queued, without trailing slashes
+    let mut seen: HashSet<String> = HashSet::from([start_url.trim_end_matches('/').to_string()]);
+    let mut queue: VecDeque<(String, u32)> = VecDeque::from([(start_url.to_string(), 0)]);
+    let mut crawled = 0;
+
+    while let Some((url, depth)) = queue.pop_front() {
+        if crawled >= options.max_pages || depth > options.max_depth {
+            continue;
+        }
+        let Some(page) = visitor.visit(&url).await? else {
+            continue;
+        };
+        crawled += 1;
+
+        let outbound: Vec<PageLink> = page
+            .links
+            .iter()
+            .filter(|link| !options.same_domain_only || in_domains(&link.url, &domains))
+            .cloned()
+            .collect();
+
+        let mut discovered = Vec::new();
+        for link in &outbound {
+            if seen.insert(link.url.trim_end_matches('/').to_string()) {
+                queue.push_back((link.url.clone(), depth + 1));
+                discovered.push(link.url.clone());
+            }
+        }
+
+        let crawled_page = CrawledPage {
+            page,
+            depth,
+            outbound,
+            discovered,
+        };
+        if pages.send(crawled_page).await.is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn in_domains(url: &str, domains: &[String]) -> bool {
+    Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|host| domains.iter().any(|d| d == host)))
+        .unwrap_or(false)
+}
+
+/// Title, text preview and links of the browser's current page
+///
+/// `url` is reported if the current URL cannot be read.
+pub async fn capture_page(
+    browser: &Browser,
+    url: &str,
+    preview_chars: usize,
+) -> Result<VisitedPage> {
+    let page = browser.get_page()?;
+    let current_url = browser
+        .get_current_url()
+        .await
+        .unwrap_or_else(|_| url.to_string());
+    let title = page.evaluate("document.title").await.unwrap_or_default();
+    let content_script = format!(
+        "(document.body?.innerText||document.body?.textContent||'').slice(0,{preview_chars})"
+    );
+    let content_preview = page.evaluate(&content_script).await.unwrap_or_default();
+
+    let links_script = r#"
+        (function() {
+            const seen = new Set();
+            const links = [];
+            for (const a of document.querySelectorAll('a[href]')) {
+                if (!a.href || a.href.startsWith('javascript:')) continue;
+                const text = (a.innerText || a.textContent || '').replace(/\s+/g, ' ').trim();
+                const key = a.href + '\n' + text;
+                if (seen.has(key)) continue;
+                seen.add(key);
+                links.push({ url: a.href, text });
+            }
+            return JSON.stringify(links);
+        })()
+    "#;
+    let links_result = page
+        .evaluate(links_script)
+        .await
+        .unwrap_or_else(|_| "[]".to_string());
+    let links = serde_json::from_str(&links_result).unwrap_or_default();
+
+    Ok(VisitedPage {
+        url: current_url,
+        title,
+        content_preview,
+        links,
+    })
+}
+
+/// Crawls with a [`Browser`]: navigate, wait `delay`, then [`capture_page`]
+pub struct BrowserVisitor<'a> {
+    browser: &'a mut Browser,
+    preview_chars: usize,
+    delay: Duration,
+}
+
+impl<'a> BrowserVisitor<'a> {
+    /// Visitor navigating `browser`, waiting `delay` after each navigation
+    pub fn new(browser: &'a mut Browser, preview_chars: usize, delay: Duration) -> Self {
+        Self {
+            browser,
+            preview_chars,
+            delay,
+        }
+    }
+}
+
+#[async_trait]
+impl PageVisitor for BrowserVisitor<'_> {
+    async fn visit(&mut self, url: &str) -> Result<Option<VisitedPage>> {
+        if self.browser.navigate(url).await.is_err() {
+            return Ok(None);
+        }
+        tokio::time::sleep(self.delay).await;
+        capture_page(self.browser, url, self.preview_chars)
+            .await
+            .map(Some)
+    }
+}
@@ -0,0 +1,260 @@
+//! Pausing document requests for every rule that applies to them
+//!
+//! `Fetch.enable` replaces the patterns of an earlier call in the same session
+//! and `Fetch.disable` ends interception for every caller, so users of document
+//! interception share one [`DocumentInterception`] per client. It keeps Fetch
+//! enabled in a session while any rule is registered there, and resumes each
+//! paused request once, as the rules together decide: blocked if any rule
+//! blocks it, otherwise continued with the headers the rules add.
+
+use crate::actor::page::merge_headers;
+use crate::browser::cdp::CdpClient;
+use crate::error::{BrowsingError, Result};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use tokio::task::JoinHandle;
+
+/// What a rule wants done with a paused document request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// Let the request go as it is, as far as this rule is concerned
+    Continue,
+    /// Send the request with these headers added or replaced
+    AddHeaders(HashMap<String, String>),
+    /// Fail the request as blocked by the client
+    Block,
+}
+
+/// A rule for the document requests of a session
+pub trait DocumentRule: Send + Sync {
+    /// What to do with the paused request, the params of `Fetch.requestPaused`
+    fn decide(&self, paused: &Value) -> Decision;
+
+    /// Called once the request was resumed or failed
+    fn resolved(&self, _paused: &Value) {}
+}
+
+/// The document interception rules of every session of a client
+#[derive(Default)]
+pub struct DocumentInterception {
+    sessions: Mutex<HashMap<String, SessionRules>>,
+    next_id: AtomicU64,
+}
+
+struct SessionRules {
+    rules: Vec<(u64, Arc<dyn DocumentRule>)>,
+    dispatcher: JoinHandle<()>,
+}
+
+impl DocumentInterception {
+    /// Rules registered in `session_id`, in the order they were added
+    fn rules(&self, session_id: &str) -> Vec<Arc<dyn DocumentRule>> {
+        self.sessions
+            .lock()
+            .ok()
+            .and_then(|sessions| {
+                let session = sessions.get(session_id)?;
+                Some(
+                    session
+                        .rules
+                        .iter()
+                        .map(|(_, rule)| Arc::clone(rule))
+                        .collect(),
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    /// Remove rule `id`; true if it was the last one of its session
+    fn remove(&self, session_id: &str, id: u64) -> bool {
+        let Ok(mut sessions) = self.sessions.lock() else {
+            return false;
+        };
+        let Some(session) = sessions.get_mut(session_id) else {
+            return false;
+        };
+        session.rules.retain(|(rule_id, _)| *rule_id != id);
+        if !session.rules.is_empty() {
+            return false;
+        }
+        if let Some(session) = sessions.remove(session_id) {
+            session.dispatcher.abort();
+        }
+        true
+    }
+}
+
+/// A registered rule, removed with [`RuleHandle::remove`] or when dropped
+///
+/// Fetch is disabled in the session once its last rule is removed. Dropping
+/// the handle, e.g. because the action that added the rule was cancelled,
+/// removes the rule too and disables Fetch in the background, so a cancelled
+/// navigation does not leave the tab's requests paused.
+pub struct RuleHandle {
+    client: Weak<CdpClient>,
+    session_id: String,
+    id: u64,
+    removed: bool,
+}
+
+impl RuleHandle {
+    /// Remove the rule, disabling Fetch if no other rule is left in the session
+    pub async fn remove(mut self) -> Result<()> {
+        self.removed = true;
+        let Some(client) = self.client.upgrade() else {
+            return Ok(());
+        };
+        if client
+            .document_interception()
+            .remove(&self.session_id, self.id)
+        {
+            client
+                .send_command_with_session("Fetch.disable", json!({}), Some(&self.session_id))
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for RuleHandle {
+    fn drop(&mut self) {
+        if self.removed {
+            return;
+        }
+        let Some(client) = self.client.upgrade() else {
+            return;
+        };
+        if !client
+            .document_interception()
+            .remove(&self.session_id, self.id)
+        {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let session_id = std::mem::take(&mut self.session_id);
+        runtime.spawn(async move {
+            // A rule added since enabled Fetch again for itself
+            if !client.document_interception().rules(&session_id).is_empty() {
+                return;
+            }
+            if let Err(e) = client
+                .send_command_with_session("Fetch.disable", json!({}), Some(&session_id))
+                .await
+            {
+                tracing::debug!("Failed to disable request interception: {}", e);
+            }
+        });
+    }
+}
+
+/// Register `rule` for the document requests of `session_id`
+///
+/// The first rule of a session enables Fetch for document requests there.
+pub async fn add_document_rule(
+    client: &Arc<CdpClient>,
+    session_id: &str,
+    rule: Arc<dyn DocumentRule>,
+) -> Result<RuleHandle> {
+    let interception = client.document_interception();
+    let id = interception.next_id.fetch_add(1, Ordering::Relaxed);
+    let first = {
+        let mut sessions = interception
+            .sessions
+            .lock()
+            .map_err(|_| BrowsingError::Browser("Interception lock poisoned".into()))?;
+        match sessions.get_mut(session_id) {
+            Some(session) => {
+                session.rules.push((id, rule));
+                false
+            }
+            None => {
+                // Subscribe before enabling so no paused request is missed
+                let dispatcher = spawn_dispatcher(client, session_id);
+                sessions.insert(
+                    session_id.to_string(),
+                    SessionRules {
+                        rules: vec![(id, rule)],
+                        dispatcher,
+                    },
+                );
+                true
+            }
+        }
+    };
+    let handle = RuleHandle {
+        client: Arc::downgrade(client),
+        session_id: session_id.to_string(),
+        id,
+        removed: false,
+    };
+
+    if first {
+        client
+            .send_command_with_session(
+                "Fetch.enable",
+                json!({ "patterns": [{ "urlPattern": "*", "resourceType": "Document", "requestStage": "Request" }] }),
+                Some(session_id),
+            )
+            .await?;
+    }
+    Ok(handle)
+}
+
+/// Resume the document requests paused in `session_id` as its rules decide
+fn spawn_dispatcher(client: &Arc<CdpClient>, session_id: &str) -> JoinHandle<()> {
+    let mut events = client.subscribe_events();
+    let client = Arc::downgrade(client);
+    let session_id = session_id.to_string();
+    tokio::spawn(async move {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+            };
+            if event["method"] != "Fetch.requestPaused" || event["sessionId"] != session_id.as_str()
+            {
+                continue;
+            }
+            let Some(client) = client.upgrade() else {
+                return;
+            };
+            let paused = &event["params"];
+            let rules = client.document_interception().rules(&session_id);
+            let decisions: Vec<Decision> = rules.iter().map(|rule| rule.decide(paused)).collect();
+
+            let (method, params) = if decisions.contains(&Decision::Block) {
+                (
+                    "Fetch.failRequest",
+                    json!({ "requestId": paused["requestId"], "errorReason": "BlockedByClient" }),
+                )
+            } else {
+                let mut resume = json!({ "requestId": paused["requestId"] });
+                let mut headers = HashMap::new();
+                for decision in decisions {
+                    if let Decision::AddHeaders(added) = decision {
+                        headers.extend(added);
+                    }
+                }
+                if !headers.is_empty() {
+                    resume["headers"] =
+                        json!(merge_headers(&paused["request"]["headers"], &headers));
+                }
+                ("Fetch.continueRequest", resume)
+            };
+            if let Err(e) = client
+                .send_command_with_session(method, params, Some(&session_id))
+                .await
+            {
+                tracing::debug!("Failed to resume intercepted request: {}", e);
+            }
+            for rule in &rules {
+                rule.resolved(paused);
+            }
+        }
+    })
+}
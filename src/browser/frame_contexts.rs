@@ -0,0 +1,76 @@
+//! Execution contexts of the frames in each session
+//!
+//! Chrome reports a context per frame and world with the `Runtime` domain
+//! enabled. A node inside a same-origin iframe is resolved in its own frame's
+//! main-world context, so scripts called on it see that frame's `window` and
+//! `document` instead of the top-level ones.
+
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Main-world execution context of each frame, by session
+#[derive(Debug, Default)]
+pub struct FrameContexts {
+    /// (session ID, frame ID) to context ID; the session is empty for the browser connection
+    contexts: HashMap<(String, String), i64>,
+}
+
+impl FrameContexts {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Update from a CDP event; events other than `Runtime.executionContext*` are ignored
+    pub fn apply_event(&mut self, event: &Value) {
+        let session_id = event
+            .get("sessionId")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        let params = &event["params"];
+        match event.get("method").and_then(|v| v.as_str()) {
+            Some("Runtime.executionContextCreated") => {
+                let context = &params["context"];
+                let aux = &context["auxData"];
+                // Isolated worlds of extensions and tools are not the page's own
+                if aux["isDefault"].as_bool() != Some(true) {
+                    return;
+                }
+                if let (Some(id), Some(frame_id)) =
+                    (context["id"].as_i64(), aux["frameId"].as_str())
+                {
+                    self.contexts
+                        .insert((session_id.to_string(), frame_id.to_string()), id);
+                }
+            }
+            Some("Runtime.executionContextDestroyed") => {
+                if let Some(id) = params["executionContextId"].as_i64() {
+                    self.contexts
+                        .retain(|(session, _), context| session != session_id || *context != id);
+                }
+            }
+            Some("Runtime.executionContextsCleared") => {
+                self.contexts
+                    .retain(|(session, _), _| session != session_id);
+            }
+            _ => {}
+        }
+    }
+
+    /// Main-world context of `frame_id` in `session_id`, if Chrome reported one
+    pub fn get(&self, session_id: &str, frame_id: &str) -> Option<i64> {
+        self.contexts
+            .get(&(session_id.to_string(), frame_id.to_string()))
+            .copied()
+    }
+
+    /// Number of frames with a known context
+    pub fn len(&self) -> usize {
+        self.contexts.len()
+    }
+
+    /// Whether no context is known
+    pub fn is_empty(&self) -> bool {
+        self.contexts.is_empty()
+    }
+}
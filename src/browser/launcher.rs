@@ -12,6 +12,8 @@ pub struct BrowserLauncher {
     profile: BrowserProfile,
     executable_path: Option<PathBuf>,
     process: Option<tokio::process::Child>,
+    /// Leave the browser running on stop and drop, set by [`Self::detach`]
+    detached: bool,
 }
 
 impl BrowserLauncher {
@@ -21,6 +23,7 @@ impl BrowserLauncher {
             profile,
             executable_path: None,
             process: None,
+            detached: false,
         }
     }
 
@@ -30,6 +33,22 @@ impl BrowserLauncher {
         self
     }
 
+    /// Path of the browser executable, once set or resolved by [`Self::launch`]
+    pub fn executable_path(&self) -> Option<&std::path::Path> {
+        self.executable_path.as_deref()
+    }
+
+    /// Process ID of the running browser
+    pub fn pid(&self) -> Option<u32> {
+        self.process.as_ref().and_then(|process| process.id())
+    }
+
+    /// Keep the browser running after [`Self::stop`] and after this launcher
+    /// is dropped, e.g. for another process to take it over
+    pub fn detach(&mut self) {
+        self.detached = true;
+    }
+
     /// Find browser executable
     pub async fn find_browser_executable(&self) -> Result<PathBuf> {
         // If custom path provided, use it
@@ -156,9 +175,13 @@ impl BrowserLauncher {
             }
         }
 
+        // Headless detection mitigations
+        if self.profile.stealth {
+            args.extend(self.profile.stealth_options.launch_args());
+        }
+
         // Additional common args for automation
         // Note: --no-sandbox is not supported on macOS and modern Chrome
-        // Note: --disable-blink-features is also not supported on modern Chrome
         args.extend(vec![
             "--disable-dev-shm-usage".to_string(),
         ]);
@@ -170,6 +193,7 @@ impl BrowserLauncher {
     pub async fn launch(&mut self) -> Result<String> {
         // Find browser executable
         let browser_path = self.find_browser_executable().await?;
+        self.executable_path = Some(browser_path.clone());
 
         // Find free port
         let debug_port = Self::find_free_port()?;
@@ -241,7 +265,15 @@ impl BrowserLauncher {
     }
 
     /// Get WebSocket debugger URL from CDP HTTP endpoint
-    async fn get_websocket_debugger_url(cdp_http_url: &str) -> Result<String> {
+    pub(crate) async fn get_websocket_debugger_url(cdp_http_url: &str) -> Result<String> {
+        Self::first_page_target(cdp_http_url)
+            .await
+            .map(|(_, ws_url)| ws_url)
+    }
+
+    /// Target ID and WebSocket debugger URL of the first page listed by the
+    /// CDP HTTP endpoint's `/json`
+    pub(crate) async fn first_page_target(cdp_http_url: &str) -> Result<(String, String)> {
         let response = reqwest::get(format!("{cdp_http_url}/json"))
             .await
             .map_err(|e| BrowsingError::Browser(format!("Failed to fetch CDP targets: {e}")))?;
@@ -255,7 +287,8 @@ impl BrowserLauncher {
         for target in targets {
             if target["type"].as_str() == Some("page") {
                 if let Some(ws_url) = target["webSocketDebuggerUrl"].as_str() {
-                    return Ok(ws_url.to_string());
+                    let target_id = target["id"].as_str().unwrap_or_default();
+                    return Ok((target_id.to_string(), ws_url.to_string()));
                 }
             }
         }
@@ -278,8 +311,12 @@ impl BrowserLauncher {
     /// - Temporary directories are left in place for debugging and inspection
     ///
     /// Users are responsible for managing their own user data directories.
+    ///
+    /// A [detached](Self::detach) browser is left running.
     pub async fn stop(&mut self) -> Result<()> {
-        if let Some(mut process) = self.process.take() {
+        if let Some(mut process) = self.process.take()
+            && !self.detached
+        {
             let _ = process.kill().await;
         }
         Ok(())
@@ -290,8 +327,39 @@ impl Drop for BrowserLauncher {
     fn drop(&mut self) {
         // Try to stop browser on drop
         // NOTE: We do NOT clean up user_data_dir - see stop() method for safety policy
-        if let Some(ref mut process) = self.process {
-            let _ = process.kill();
+        if let Some(ref mut process) = self.process
+            && !self.detached
+        {
+            let _ = process.start_kill();
         }
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn is_running(pid: u32) -> bool {
+        std::process::Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+
+    #[tokio::test]
+    async fn test_detached_browser_outlives_its_launcher() {
+        let mut launcher = BrowserLauncher::new(BrowserProfile::default());
+        launcher.process = Some(Command::new("sleep").arg("30").spawn().unwrap());
+        let pid = launcher.pid().unwrap();
+
+        launcher.detach();
+        launcher.stop().await.unwrap();
+        drop(launcher);
+
+        assert!(is_running(pid));
+        std::process::Command::new("kill")
+            .arg(pid.to_string())
+            .status()
+            .unwrap();
+    }
+}
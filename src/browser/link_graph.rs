@@ -0,0 +1,136 @@
+//! Graphs of how a site's pages link to each other
+//!
+//! Built from a [`crawl`]: every crawled page is a node with its title, and
+//! every link within the allowed domains an edge with its anchor text. Pages
+//! that are linked but were not crawled (past the limits, or failing to load)
+//! are nodes without a title.
+
+use crate::browser::crawl::{CrawlOptions, CrawledPage, PageVisitor, crawl};
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tokio::sync::mpsc;
+
+/// Crawled pages the crawl may get ahead of the graph before it waits
+const CRAWL_CHANNEL_CAPACITY: usize = 8;
+
+/// A page in a [`LinkGraph`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkNode {
+    /// Page URL
+    pub url: String,
+    /// Document title; `None` if the page was not crawled
+    pub title: Option<String>,
+}
+
+/// A link in a [`LinkGraph`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkEdge {
+    /// URL of the page with the link
+    pub source: String,
+    /// URL the link points to
+    pub target: String,
+    /// Text of the link
+    pub anchor_text: String,
+}
+
+/// Pages and the links between them
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LinkGraph {
+    /// Pages, in the order they were first seen
+    pub nodes: Vec<LinkNode>,
+    /// Links, in crawl order; the same link text between two pages appears once
+    pub edges: Vec<LinkEdge>,
+    /// Positions in `nodes` by URL without trailing slash
+    #[serde(skip)]
+    index: HashMap<String, usize>,
+    /// Source and target positions in `nodes`, and anchor text, of each edge
+    #[serde(skip)]
+    edge_keys: HashSet<(usize, usize, String)>,
+}
+
+impl LinkGraph {
+    /// Add a crawled page and its outbound links
+    ///
+    /// Edges name their pages by the nodes' URLs, so links differing only in
+    /// a trailing slash or fragment are the same edge.
+    pub fn add_page(&mut self, crawled: &CrawledPage) {
+        let source = self.node(&crawled.page.url);
+        self.nodes[source].title = Some(crawled.page.title.clone());
+
+        for link in &crawled.outbound {
+            let target = self.node(&link.url);
+            if self.edge_keys.insert((source, target, link.text.clone())) {
+                self.edges.push(LinkEdge {
+                    source: self.nodes[source].url.clone(),
+                    target: self.nodes[target].url.clone(),
+                    anchor_text: link.text.clone(),
+                });
+            }
+        }
+    }
+
+    /// Position of the node for `url`, added untitled if new
+    fn node(&mut self, url: &str) -> usize {
+        let url = url.split_once('#').map_or(url, |(page, _)| page);
+        *self.index.entry(node_key(url)).or_insert_with(|| {
+            self.nodes.push(LinkNode {
+                url: url.to_string(),
+                title: None,
+            });
+            self.nodes.len() - 1
+        })
+    }
+
+    /// The graph in Graphviz DOT, nodes labelled with their titles and edges
+    /// with their anchor text
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph links {\n");
+        for node in &self.nodes {
+            let label = node.title.as_deref().unwrap_or(&node.url);
+            dot.push_str(&format!(
+                "  {} [label={}];\n",
+                dot_string(&node.url),
+                dot_string(label)
+            ));
+        }
+        for edge in &self.edges {
+            dot.push_str(&format!(
+                "  {} -> {} [label={}];\n",
+                dot_string(&edge.source),
+                dot_string(&edge.target),
+                dot_string(&edge.anchor_text)
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// `url`, without its fragment, minus any trailing slash
+fn node_key(url: &str) -> String {
+    url.trim_end_matches('/').to_string()
+}
+
+/// `s` as a quoted DOT string
+fn dot_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Crawl from `start_url` with `visitor` and graph the links between the pages
+pub async fn build_link_graph(
+    visitor: &mut dyn PageVisitor,
+    start_url: &str,
+    options: &CrawlOptions,
+) -> Result<LinkGraph> {
+    let (sender, mut pages) = mpsc::channel(CRAWL_CHANNEL_CAPACITY);
+    let collect = async {
+        let mut graph = LinkGraph::default();
+        while let Some(crawled) = pages.recv().await {
+            graph.add_page(&crawled);
+        }
+        graph
+    };
+    let (result, graph) = tokio::join!(crawl(visitor, start_url, options, sender), collect);
+    result.map(|()| graph)
+}
@@ -0,0 +1,240 @@
+//! Web app manifests of progressive web apps (PWAs)
+//!
+//! `Page.getAppManifest` returns the raw manifest text the page links to with
+//! `<link rel="manifest">`. It is parsed here, with relative URLs resolved
+//! against the manifest's own URL as browsers do.
+
+use crate::error::{BrowsingError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
+
+/// An icon or screenshot listed in a manifest
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ManifestIcon {
+    /// Image URL, absolute
+    pub src: String,
+    /// Space-separated sizes, e.g. `192x192 512x512`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sizes: Option<String>,
+    /// MIME type, e.g. `image/png`
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    /// Icon purpose, e.g. `maskable`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
+    /// Screenshot description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Screenshot form factor, `wide` or `narrow`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub form_factor: Option<String>,
+}
+
+/// A page's web app manifest
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WebAppManifest {
+    /// URL the manifest was loaded from
+    #[serde(default)]
+    pub manifest_url: String,
+    /// Full app name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Name for places with little space, e.g. the home screen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_name: Option<String>,
+    /// What the app does
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Page the installed app opens, absolute
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_url: Option<String>,
+    /// Display mode: `fullscreen`, `standalone`, `minimal-ui` or `browser`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display: Option<String>,
+    /// Splash screen background color
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background_color: Option<String>,
+    /// Color of the browser UI around the app
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme_color: Option<String>,
+    /// App icons
+    #[serde(default, deserialize_with = "valid_images")]
+    pub icons: Vec<ManifestIcon>,
+    /// Store categories, e.g. `shopping`
+    #[serde(default)]
+    pub categories: Vec<String>,
+    /// Screenshots for install dialogs and stores
+    #[serde(default, deserialize_with = "valid_images")]
+    pub screenshots: Vec<ManifestIcon>,
+}
+
+/// The images of an `icons` or `screenshots` member that have a `src`
+///
+/// Browsers skip invalid entries rather than rejecting the manifest, and a
+/// member that is not a list counts as empty.
+fn valid_images<'de, D>(deserializer: D) -> std::result::Result<Vec<ManifestIcon>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let Value::Array(images) = Value::deserialize(deserializer)? else {
+        return Ok(Vec::new());
+    };
+    Ok(images
+        .into_iter()
+        .filter_map(|image| serde_json::from_value::<ManifestIcon>(image).ok())
+        .filter(|image| !image.src.trim().is_empty())
+        .collect())
+}
+
+impl WebAppManifest {
+    /// Parse a `Page.getAppManifest` result, `None` if the page has no manifest
+    pub fn from_cdp(result: &Value) -> Result<Option<Self>> {
+        let manifest_url = result["url"].as_str().unwrap_or_default();
+        let data = result["data"].as_str().unwrap_or_default();
+        if manifest_url.is_empty() || data.trim().is_empty() {
+            return Ok(None);
+        }
+        Self::parse(manifest_url, data).map(Some)
+    }
+
+    /// Parse manifest JSON loaded from `manifest_url`
+    pub fn parse(manifest_url: &str, data: &str) -> Result<Self> {
+        let mut manifest: Self = serde_json::from_str(data).map_err(|e| {
+            BrowsingError::Browser(format!("Invalid web app manifest at {manifest_url}: {e}"))
+        })?;
+        manifest.manifest_url = manifest_url.to_string();
+
+        // Relative URLs are relative to the manifest, not the page
+        if let Ok(base) = Url::parse(manifest_url) {
+            let resolve = |url: &mut String| {
+                if let Ok(absolute) = base.join(url) {
+                    *url = absolute.to_string();
+                }
+            };
+            if let Some(ref mut start_url) = manifest.start_url {
+                resolve(start_url);
+            }
+            for image in manifest.icons.iter_mut().chain(&mut manifest.screenshots) {
+                resolve(&mut image.src);
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Name to show, the full name or else the short one
+    pub fn display_name(&self) -> Option<&str> {
+        self.name.as_deref().or(self.short_name.as_deref())
+    }
+
+    /// Whether the installed app runs without browser navigation controls
+    pub fn is_app_like(&self) -> bool {
+        matches!(
+            self.display.as_deref(),
+            Some("standalone" | "fullscreen" | "minimal-ui")
+        )
+    }
+
+    /// One paragraph describing the app for the model's page state
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "This site is an installable web app{}",
+            self.display_name()
+                .map(|name| format!(" ({name})"))
+                .unwrap_or_default()
+        );
+        if let Some(ref display) = self.display {
+            summary.push_str(&format!(", display {display}"));
+        }
+        summary.push('.');
+        if let Some(ref start_url) = self.start_url {
+            summary.push_str(&format!(" Its entry point is {start_url}"));
+            summary.push_str(if self.is_app_like() {
+                "; start there rather than at deep links, since the app routes within the page and may lack back and home links."
+            } else {
+                "."
+            });
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const MANIFEST: &str = r##"{
+        "name": "Shop Example",
+        "short_name": "Shop",
+        "start_url": "/app/?source=pwa",
+        "display": "standalone",
+        "background_color": "#ffffff",
+        "theme_color": "#0a84ff",
+        "icons": [{ "src": "icons/192.png", "sizes": "192x192", "type": "image/png" }],
+        "categories": ["shopping"],
+        "screenshots": [{ "src": "https://cdn.example/wide.png", "form_factor": "wide", "label": "Home" }]
+    }"##;
+
+    #[test]
+    fn test_parse_resolves_urls_against_the_manifest() {
+        let result = json!({
+            "url": "https://shop.example/static/manifest.json",
+            "errors": [],
+            "data": MANIFEST
+        });
+        let manifest = WebAppManifest::from_cdp(&result).unwrap().unwrap();
+
+        assert_eq!(manifest.display_name(), Some("Shop Example"));
+        assert_eq!(
+            manifest.start_url.as_deref(),
+            Some("https://shop.example/app/?source=pwa")
+        );
+        assert_eq!(
+            manifest.icons[0].src,
+            "https://shop.example/static/icons/192.png"
+        );
+        assert_eq!(manifest.icons[0].mime_type.as_deref(), Some("image/png"));
+        assert_eq!(manifest.screenshots[0].src, "https://cdn.example/wide.png");
+        assert_eq!(manifest.categories, ["shopping"]);
+        assert!(manifest.is_app_like());
+        assert_eq!(
+            manifest.summary(),
+            "This site is an installable web app (Shop Example), display standalone. \
+             Its entry point is https://shop.example/app/?source=pwa; start there rather \
+             than at deep links, since the app routes within the page and may lack back \
+             and home links."
+        );
+    }
+
+    #[test]
+    fn test_invalid_images_are_skipped() {
+        let data = r#"{
+            "name": "Shop",
+            "icons": [{ "sizes": "48x48" }, { "src": "" }, 3, { "src": "icon.png" }],
+            "screenshots": { "src": "wide.png" }
+        }"#;
+        let manifest = WebAppManifest::parse("https://shop.example/manifest.json", data).unwrap();
+
+        assert_eq!(manifest.display_name(), Some("Shop"));
+        let icons: Vec<&str> = manifest
+            .icons
+            .iter()
+            .map(|icon| icon.src.as_str())
+            .collect();
+        assert_eq!(icons, ["https://shop.example/icon.png"]);
+        assert!(manifest.screenshots.is_empty());
+    }
+
+    #[test]
+    fn test_no_manifest() {
+        let result = json!({ "url": "", "errors": [] });
+        assert_eq!(WebAppManifest::from_cdp(&result).unwrap(), None);
+    }
+
+    #[test]
+    fn test_invalid_manifest_is_an_error() {
+        let result = json!({ "url": "https://a.example/m.json", "errors": [], "data": "{" });
+        assert!(WebAppManifest::from_cdp(&result).is_err());
+    }
+}
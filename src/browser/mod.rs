@@ -1,20 +1,55 @@
 //! Browser session management
 
+mod cookies;
+mod crawl;
+pub(crate) mod document_interception;
+mod frame_contexts;
+mod link_graph;
+mod manifest;
 mod navigation;
+mod network_conditions;
+mod response_capture;
 mod screenshot;
 mod session_guard;
 mod tab_manager;
+mod target_tracker;
+mod worker_monitor;
 
 pub mod cdp;
 pub mod launcher;
 pub mod profile;
 pub mod session;
+pub mod state;
+pub mod stealth;
 pub mod views;
+pub mod wire_log;
 
-pub use navigation::NavigationManager;
-pub use screenshot::ScreenshotManager;
+pub use cookies::{CookieExportFormat, format_cookies, format_netscape, parse_netscape};
+pub use crawl::{
+    BrowserVisitor, CrawlOptions, CrawledPage, PageLink, PageVisitor, VisitedPage, capture_page,
+    crawl,
+};
+pub use frame_contexts::FrameContexts;
+pub use link_graph::{LinkEdge, LinkGraph, LinkNode, build_link_graph};
+pub use manifest::{ManifestIcon, WebAppManifest};
+pub use navigation::{
+    DEFAULT_REDIRECT_GRACE_MS, MAX_CLIENT_REDIRECTS, MAX_NAVIGATION_HISTORY, MetaRefresh,
+    NavigationManager, NavigationRecord, Redirect,
+};
+pub(crate) use navigation::PENDING_REDIRECT_JS;
+pub use network_conditions::NetworkConditions;
+pub use response_capture::{
+    BodyRequest, CapturedResponse, DEFAULT_MAX_CAPTURED_BODY_BYTES, ResponseCapture,
+    SCRUBBED_HEADERS, scrub_headers,
+};
+pub use screenshot::{IntervalScreenshotHandle, ScreenshotManager};
 pub use tab_manager::TabManager;
+pub use target_tracker::{NewTargetWatcher, TargetActivity, TargetTracker};
+pub use worker_monitor::{WorkerConsoleMessage, WorkerMonitor, WorkerRequest, is_worker_target};
 
 pub use profile::{BrowserProfile, ProxyConfig};
 pub use session::Browser;
+pub use state::{BrowserStateOptions, build_browser_state};
+pub use stealth::StealthOptions;
 pub use views::*;
+pub use wire_log::{CdpStats, MessageSizes, WireLogConfig};
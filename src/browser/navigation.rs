@@ -1,11 +1,208 @@
 //! Navigation management for browser sessions
 //!
-//! This module handles navigation to URLs.
+//! This module handles navigation to URLs and remembers where each one ended
+//! up. Servers often redirect (to a login page, a regional site, a trailing
+//! slash), so the URL asked for and the page the model sees can differ.
+//! Some pages also move on by themselves right after loading, with
+//! `<meta http-equiv="refresh">` or a script setting `location`; waiting for
+//! a load state keeps watching for those (see [`DEFAULT_REDIRECT_GRACE_MS`]).
 
-use crate::actor::Page;
+use crate::actor::{NavigateOptions, Page};
 use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tracing::info;
 
+/// Navigations remembered by a browser, oldest dropped first
+pub const MAX_NAVIGATION_HISTORY: usize = 100;
+
+/// How long after load a navigation that waits for a load state keeps
+/// watching for the page to redirect itself
+pub const DEFAULT_REDIRECT_GRACE_MS: u64 = 1_500;
+
+/// Redirects by the page itself followed after one navigation
+pub const MAX_CLIENT_REDIRECTS: usize = 5;
+
+/// Reads the page's URL, its meta refresh and whether an inline script looks
+/// like it sets `location` when it runs; cheap enough to run after every load
+///
+/// Only assignments outside functions, or in a `setTimeout` callback, count:
+/// a click handler that navigates does not make the page redirect itself.
+pub(crate) const PENDING_REDIRECT_JS: &str = r#"(() => {
+  const meta = document.querySelector('meta[http-equiv="refresh" i][content]');
+  const sets = '(?:window\\.|self\\.|top\\.|document\\.)?location(?:(?:\\.href)?\\s*=[^=]|\\.(?:replace|assign)\\s*\\()';
+  const direct = new RegExp('(?:^|[\\s;{}(])' + sets);
+  const timer = new RegExp('setTimeout\\s*\\(\\s*(?:function\\s*\\([^)]*\\)\\s*\\{|\\([^)]*\\)\\s*=>\\s*\\{?)\\s*' + sets);
+  const outsideFunctions = text => {
+    const opener = /function\b[^{]*\{|=>\s*\{/g;
+    let out = '', from = 0;
+    for (let m; (m = opener.exec(text)); ) {
+      out += text.slice(from, m.index);
+      let depth = 1, i = m.index + m[0].length;
+      for (; i < text.length && depth > 0; i++) {
+        if (text[i] === '{') depth++;
+        else if (text[i] === '}') depth--;
+      }
+      from = opener.lastIndex = i;
+    }
+    return out + text.slice(from);
+  };
+  const script = [...document.scripts].some(s => !s.src &&
+    (direct.test(outsideFunctions(s.text)) || timer.test(s.text)));
+  return { url: location.href, refresh: meta ? meta.content : null, script };
+})()"#;
+
+/// One hop of a redirect chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Redirect {
+    /// URL that answered with the redirect
+    pub url: String,
+    /// HTTP status of the redirect, e.g. 302
+    pub status: u16,
+}
+
+/// Where a navigation was sent and where it landed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NavigationRecord {
+    /// Tab the navigation happened in
+    #[serde(default)]
+    pub target_id: String,
+    /// URL that was asked for
+    pub requested_url: String,
+    /// URL of the document that loaded, after redirects
+    pub final_url: String,
+    /// Redirects followed, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redirects: Vec<Redirect>,
+    /// Pages that sent the browser on by themselves after loading, with a
+    /// meta refresh or a script, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub client_redirects: Vec<String>,
+    /// Canonical URL the page declares with `<link rel="canonical">`, if read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonical_url: Option<String>,
+}
+
+impl NavigationRecord {
+    /// Build a record from the CDP events seen while navigating
+    ///
+    /// The main-frame request of a navigation has the navigation's loader ID
+    /// as its request ID, and each redirect repeats `Network.requestWillBeSent`
+    /// for it with the redirect response attached. Events of other sessions
+    /// and requests are ignored.
+    pub fn from_events(
+        requested_url: &str,
+        loader_id: &str,
+        session_id: &str,
+        events: &[Value],
+    ) -> Self {
+        let mut record = Self {
+            requested_url: requested_url.to_string(),
+            final_url: requested_url.to_string(),
+            ..Default::default()
+        };
+        for event in events {
+            let params = &event["params"];
+            if event["method"] != "Network.requestWillBeSent"
+                || event["sessionId"].as_str().is_some_and(|s| s != session_id)
+                || loader_id.is_empty()
+                || params["requestId"].as_str() != Some(loader_id)
+            {
+                continue;
+            }
+            if let Some(response) = params.get("redirectResponse") {
+                record.redirects.push(Redirect {
+                    url: response["url"].as_str().unwrap_or_default().to_string(),
+                    status: response["status"].as_u64().unwrap_or_default() as u16,
+                });
+            }
+            if let Some(url) = params["request"]["url"].as_str() {
+                record.final_url = url.to_string();
+            }
+        }
+        record
+    }
+
+    /// Whether the page that loaded is not the one asked for
+    pub fn was_redirected(&self) -> bool {
+        !self.redirects.is_empty() || !self.client_redirects.is_empty()
+    }
+
+    /// Record that the page at `final_url` sent the browser on to `url`
+    pub fn push_client_redirect(&mut self, url: impl Into<String>) {
+        let from = std::mem::replace(&mut self.final_url, url.into());
+        self.client_redirects.push(from);
+    }
+
+    /// One line for the model when the navigation did not land where it was
+    /// sent, e.g. `Requested https://a.com, landed on https://b.com/login after 2 redirects`
+    pub fn summary(&self) -> Option<String> {
+        let canonical = self
+            .canonical_url
+            .as_ref()
+            .filter(|canonical| **canonical != self.final_url);
+        if !self.was_redirected() && canonical.is_none() {
+            return None;
+        }
+        let mut summary = if self.was_redirected() {
+            let count = self.redirects.len() + self.client_redirects.len();
+            let mut summary = format!(
+                "Requested {}, landed on {} after {count} redirect{}",
+                self.requested_url,
+                self.final_url,
+                if count == 1 { "" } else { "s" }
+            );
+            if !self.client_redirects.is_empty() {
+                summary.push_str(&format!(
+                    " ({} by the page itself after loading)",
+                    self.client_redirects.len()
+                ));
+            }
+            summary
+        } else {
+            format!("Loaded {}", self.final_url)
+        };
+        if let Some(canonical) = canonical {
+            summary.push_str(&format!("; the page's canonical URL is {canonical}"));
+        }
+        Some(summary)
+    }
+}
+
+/// A `<meta http-equiv="refresh">` that sends the browser to another URL
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetaRefresh {
+    /// Delay before the refresh, in milliseconds
+    pub delay_ms: u64,
+    /// Where it sends the browser, as written in the tag
+    pub url: String,
+}
+
+impl MetaRefresh {
+    /// Parse the tag's `content`, e.g. `3; url=https://example.com/`
+    ///
+    /// A refresh without a URL only reloads the page, so it is not a redirect
+    /// and gives `None`.
+    pub fn parse(content: &str) -> Option<Self> {
+        let (delay, rest) = content
+            .split_once([';', ','])
+            .unwrap_or((content, ""));
+        let delay: f64 = delay.trim().parse().ok().filter(|d: &f64| *d >= 0.0)?;
+        let rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ';' || c == ',');
+        let url = match rest.get(..3).zip(rest.get(3..)) {
+            Some((prefix, after)) if prefix.eq_ignore_ascii_case("url") => {
+                after.trim_start().strip_prefix('=').unwrap_or(rest)
+            }
+            _ => rest,
+        };
+        let url = url.trim().trim_matches(['\'', '"']).trim();
+        (!url.is_empty()).then(|| Self {
+            delay_ms: (delay * 1000.0) as u64,
+            url: url.to_string(),
+        })
+    }
+}
+
 /// Manager for browser navigation operations
 pub struct NavigationManager;
 
@@ -16,10 +213,24 @@ impl NavigationManager {
     }
 
     /// Navigate to the specified URL
-    pub async fn navigate(&self, page: &Page, url: &str) -> Result<()> {
-        page.goto(url).await?;
-        info!("Navigated to: {}", url);
-        Ok(())
+    pub async fn navigate(&self, page: &Page, url: &str) -> Result<NavigationRecord> {
+        self.navigate_with_options(page, url, &NavigateOptions::new())
+            .await
+    }
+
+    /// Navigate with a referrer, one-shot headers or a load state to wait for
+    pub async fn navigate_with_options(
+        &self,
+        page: &Page,
+        url: &str,
+        options: &NavigateOptions,
+    ) -> Result<NavigationRecord> {
+        let record = page.goto_tracked(url, options).await?;
+        match record.summary() {
+            Some(summary) => info!("Navigated: {}", summary),
+            None => info!("Navigated to: {}", url),
+        }
+        Ok(record)
     }
 }
 
@@ -28,3 +239,73 @@ impl Default for NavigationManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(request_id: &str, url: &str, redirect_from: Option<(&str, u16)>) -> Value {
+        let mut params = json!({ "requestId": request_id, "request": { "url": url } });
+        if let Some((from, status)) = redirect_from {
+            params["redirectResponse"] = json!({ "url": from, "status": status });
+        }
+        json!({ "method": "Network.requestWillBeSent", "sessionId": "S1", "params": params })
+    }
+
+    #[test]
+    fn test_record_follows_the_main_request() {
+        let events = [
+            request("L1", "http://example.com/", None),
+            request("R9", "http://example.com/favicon.ico", None),
+            request(
+                "L1",
+                "https://example.com/",
+                Some(("http://example.com/", 301)),
+            ),
+            request(
+                "L1",
+                "https://login.example.com/?next=%2F",
+                Some(("https://example.com/", 302)),
+            ),
+        ];
+
+        let record = NavigationRecord::from_events("http://example.com/", "L1", "S1", &events);
+
+        assert_eq!(record.final_url, "https://login.example.com/?next=%2F");
+        assert_eq!(
+            record.redirects,
+            [
+                Redirect {
+                    url: "http://example.com/".to_string(),
+                    status: 301
+                },
+                Redirect {
+                    url: "https://example.com/".to_string(),
+                    status: 302
+                },
+            ]
+        );
+        assert_eq!(
+            record.summary().as_deref(),
+            Some(
+                "Requested http://example.com/, landed on https://login.example.com/?next=%2F after 2 redirects"
+            )
+        );
+    }
+
+    #[test]
+    fn test_summary_without_redirects() {
+        let mut record = NavigationRecord::from_events("https://a.com/x", "L1", "S1", &[]);
+        assert_eq!(record.summary(), None);
+
+        record.canonical_url = Some("https://a.com/x".to_string());
+        assert_eq!(record.summary(), None);
+
+        record.canonical_url = Some("https://a.com/canonical".to_string());
+        assert_eq!(
+            record.summary().as_deref(),
+            Some("Loaded https://a.com/x; the page's canonical URL is https://a.com/canonical")
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Network throttling and offline emulation
+//!
+//! CDP can set network conditions (`Network.emulateNetworkConditions`) but has
+//! no command to read them back, so the browser remembers what it last set on
+//! each tab.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+/// Emulated network conditions of a tab
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NetworkConditions {
+    /// Whether the network is disconnected
+    pub offline: bool,
+    /// Minimum latency added to each request, in milliseconds
+    pub latency_ms: f64,
+    /// Maximum download speed in bytes per second; negative means unlimited
+    pub download_throughput: f64,
+    /// Maximum upload speed in bytes per second; negative means unlimited
+    pub upload_throughput: f64,
+}
+
+impl NetworkConditions {
+    /// Throttle to `latency_ms` and the given throughputs in bytes per second
+    pub fn new(latency_ms: f64, download_throughput: f64, upload_throughput: f64) -> Self {
+        Self {
+            offline: false,
+            latency_ms,
+            download_throughput,
+            upload_throughput,
+        }
+    }
+
+    /// No throttling
+    pub fn unthrottled() -> Self {
+        Self::new(0.0, -1.0, -1.0)
+    }
+
+    /// Disconnected from the network
+    pub fn offline() -> Self {
+        Self {
+            offline: true,
+            ..Self::unthrottled()
+        }
+    }
+
+    /// Chrome DevTools' "Slow 3G" preset
+    pub fn slow_3g() -> Self {
+        Self::new(2000.0, 50_000.0, 50_000.0)
+    }
+
+    /// Chrome DevTools' "Fast 3G" preset
+    pub fn fast_3g() -> Self {
+        Self::new(562.5, 180_000.0, 84_375.0)
+    }
+
+    /// Whether these conditions differ from an unthrottled network
+    pub fn is_throttled(&self) -> bool {
+        self.offline
+            || self.latency_ms > 0.0
+            || self.download_throughput >= 0.0
+            || self.upload_throughput >= 0.0
+    }
+
+    /// Parameters of `Network.emulateNetworkConditions`
+    pub fn to_cdp_params(&self) -> Value {
+        json!({
+            "offline": self.offline,
+            "latency": self.latency_ms,
+            "downloadThroughput": self.download_throughput,
+            "uploadThroughput": self.upload_throughput,
+        })
+    }
+}
+
+/// Network conditions last set on each tab, by target ID
+#[derive(Debug, Default)]
+pub(crate) struct NetworkConditionsState {
+    by_target: HashMap<String, NetworkConditions>,
+}
+
+impl NetworkConditionsState {
+    /// Record `conditions` as set on `target_id`; unthrottled conditions clear it
+    pub fn set(&mut self, target_id: &str, conditions: NetworkConditions) {
+        if conditions.is_throttled() {
+            self.by_target.insert(target_id.to_string(), conditions);
+        } else {
+            self.by_target.remove(target_id);
+        }
+    }
+
+    /// Conditions of `target_id`, or `None` if it is not throttled
+    pub fn get(&self, target_id: &str) -> Option<NetworkConditions> {
+        self.by_target.get(target_id).copied()
+    }
+
+    /// Forget a tab, e.g. once it is closed
+    pub fn remove(&mut self, target_id: &str) {
+        self.by_target.remove(target_id);
+    }
+
+    /// Forget all tabs
+    pub fn clear(&mut self) {
+        self.by_target.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_are_throttled() {
+        assert!(!NetworkConditions::unthrottled().is_throttled());
+        assert!(NetworkConditions::offline().is_throttled());
+        assert!(NetworkConditions::slow_3g().is_throttled());
+        assert!(NetworkConditions::new(0.0, 1_000_000.0, -1.0).is_throttled());
+    }
+
+    #[test]
+    fn test_cdp_params() {
+        assert_eq!(
+            NetworkConditions::slow_3g().to_cdp_params(),
+            json!({
+                "offline": false,
+                "latency": 2000.0,
+                "downloadThroughput": 50_000.0,
+                "uploadThroughput": 50_000.0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_state_is_tracked_per_tab() {
+        let mut state = NetworkConditionsState::default();
+        assert_eq!(state.get("T1"), None);
+
+        state.set("T1", NetworkConditions::slow_3g());
+        state.set("T2", NetworkConditions::offline());
+        assert_eq!(state.get("T1"), Some(NetworkConditions::slow_3g()));
+        assert!(state.get("T2").unwrap().offline);
+
+        // Setting an unthrottled network is the same as resetting
+        state.set("T1", NetworkConditions::unthrottled());
+        assert_eq!(state.get("T1"), None);
+
+        state.remove("T2");
+        assert_eq!(state.get("T2"), None);
+
+        state.set("T3", NetworkConditions::fast_3g());
+        state.clear();
+        assert_eq!(state.get("T3"), None);
+    }
+}
@@ -1,5 +1,7 @@
 //! Browser profile configuration
 
+use crate::actor::emulation::EmulationSettings;
+use crate::browser::stealth::StealthOptions;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -23,13 +25,31 @@ pub struct BrowserProfile {
     pub headless: Option<bool>,
     /// Path to user data directory
     pub user_data_dir: Option<PathBuf>,
-    /// List of allowed domains
+    /// Domains pages may be loaded from; requests for documents elsewhere,
+    /// including links, redirects and frames, are blocked
     pub allowed_domains: Option<Vec<String>>,
     /// Path to downloads directory
     pub downloads_path: Option<PathBuf>,
     /// Proxy configuration (for enterprise use)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub proxy: Option<ProxyConfig>,
+    /// Attach read-only to service and shared workers to capture their console
+    /// output and requests (diagnostics; actions never target workers)
+    #[serde(default)]
+    pub attach_workers: bool,
+    /// Hide common signs of an automated browser (best-effort, off by default)
+    #[serde(default)]
+    pub stealth: bool,
+    /// Mitigations applied when `stealth` is set
+    #[serde(default)]
+    pub stealth_options: StealthOptions,
+    /// Bypass Chrome's HTTP cache in every tab, for fresh content and
+    /// cold-load timings
+    #[serde(default)]
+    pub disable_cache: bool,
+    /// Color scheme, vision deficiency and Save-Data emulated in every tab
+    #[serde(default, skip_serializing_if = "EmulationSettings::is_default")]
+    pub emulation: EmulationSettings,
 }
 
 impl BrowserProfile {
@@ -50,6 +70,39 @@ impl BrowserProfile {
         self
     }
 
+    /// Capture console output and requests of service and shared workers
+    pub fn with_attach_workers(mut self, attach_workers: bool) -> Self {
+        self.attach_workers = attach_workers;
+        self
+    }
+
+    /// Hide common signs of an automated browser, such as `navigator.webdriver`
+    ///
+    /// Best-effort: see [`crate::browser::stealth`] for what is covered.
+    pub fn with_stealth(mut self, stealth: bool) -> Self {
+        self.stealth = stealth;
+        self
+    }
+
+    /// Enable stealth mode with only the given mitigations
+    pub fn with_stealth_options(mut self, options: StealthOptions) -> Self {
+        self.stealth = true;
+        self.stealth_options = options;
+        self
+    }
+
+    /// Bypass the HTTP cache in every tab
+    pub fn with_disable_cache(mut self, disable_cache: bool) -> Self {
+        self.disable_cache = disable_cache;
+        self
+    }
+
+    /// Emulate `emulation` in every tab unless a tab is given its own settings
+    pub fn with_emulation(mut self, emulation: EmulationSettings) -> Self {
+        self.emulation = emulation;
+        self
+    }
+
     /// Set proxy configuration
     pub fn with_proxy(mut self, proxy: ProxyConfig) -> Self {
         self.proxy = Some(proxy);
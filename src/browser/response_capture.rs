@@ -0,0 +1,357 @@
+//! Capturing JSON API responses
+//!
+//! Single-page apps render from JSON they fetch, and that JSON is usually a
+//! more reliable source than the DOM built from it. With
+//! [`Browser::capture_responses`](crate::Browser::capture_responses), bodies of
+//! responses whose URL matches a [`UrlPattern`] are read with
+//! `Network.getResponseBody` once they finish loading and kept if they parse
+//! as JSON. Bodies over the size limit are skipped rather than cut, since a
+//! cut body is no longer valid JSON.
+//!
+//! Captured responses stay buffered until taken, e.g. by the
+//! `get_captured_responses` action; only the latest `max_bodies` are kept.
+//! Response headers are kept without cookies and credentials (see
+//! [`SCRUBBED_HEADERS`]).
+
+use crate::actor::UrlPattern;
+use crate::browser::cdp::CdpClient;
+use crate::error::Result;
+use base64::Engine as _;
+use base64::engine::general_purpose;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+/// Default limit on the size of a captured body, in bytes
+pub const DEFAULT_MAX_CAPTURED_BODY_BYTES: usize = 256 * 1024;
+
+/// Response headers never kept, compared case-insensitively
+pub const SCRUBBED_HEADERS: &[&str] = &[
+    "set-cookie",
+    "set-cookie2",
+    "cookie",
+    "authorization",
+    "proxy-authorization",
+    "www-authenticate",
+    "proxy-authenticate",
+    "x-api-key",
+    "x-auth-token",
+    "x-csrf-token",
+    "x-xsrf-token",
+];
+
+/// A JSON response body captured from the page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapturedResponse {
+    /// Response URL
+    pub url: String,
+    /// HTTP status code
+    pub status: u16,
+    /// MIME type
+    #[serde(default)]
+    pub mime_type: String,
+    /// Response headers, without [`SCRUBBED_HEADERS`]
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Parsed body
+    pub body: Value,
+    /// Body size in bytes
+    pub size: usize,
+}
+
+/// Headers of a `Network.Response`, without [`SCRUBBED_HEADERS`]
+pub fn scrub_headers(headers: &Value) -> BTreeMap<String, String> {
+    headers
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(name, _)| {
+            !SCRUBBED_HEADERS
+                .iter()
+                .any(|scrubbed| name.eq_ignore_ascii_case(scrubbed))
+        })
+        .map(|(name, value)| {
+            let value = value
+                .as_str()
+                .map_or_else(|| value.to_string(), str::to_string);
+            (name.clone(), value)
+        })
+        .collect()
+}
+
+/// A finished response whose body should be read
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BodyRequest {
+    /// Session the response was received in, `None` for the browser session
+    pub session_id: Option<String>,
+    /// CDP request ID
+    pub request_id: String,
+}
+
+/// Responses matching a URL pattern, captured as they arrive
+#[derive(Debug)]
+pub struct ResponseCapture {
+    pattern: UrlPattern,
+    max_bodies: usize,
+    max_body_bytes: usize,
+    /// Matching responses whose body has not finished loading
+    pending: HashMap<BodyRequest, CapturedResponse>,
+    captured: VecDeque<CapturedResponse>,
+}
+
+impl ResponseCapture {
+    /// Capture up to `max_bodies` JSON bodies of responses matching `url_pattern`
+    pub fn new(url_pattern: &str, max_bodies: usize) -> Result<Self> {
+        Ok(Self {
+            pattern: UrlPattern::new(url_pattern)?,
+            max_bodies: max_bodies.max(1),
+            max_body_bytes: DEFAULT_MAX_CAPTURED_BODY_BYTES,
+            pending: HashMap::new(),
+            captured: VecDeque::new(),
+        })
+    }
+
+    /// Set the size limit of a captured body
+    pub fn with_max_body_bytes(mut self, max_body_bytes: usize) -> Self {
+        self.max_body_bytes = max_body_bytes;
+        self
+    }
+
+    /// The URL pattern as given
+    pub fn pattern(&self) -> &str {
+        self.pattern.as_str()
+    }
+
+    /// Buffered responses, oldest first
+    pub fn responses(&self) -> Vec<CapturedResponse> {
+        self.captured.iter().cloned().collect()
+    }
+
+    /// Take the buffered responses, oldest first, leaving the buffer empty
+    pub fn take_responses(&mut self) -> Vec<CapturedResponse> {
+        self.captured.drain(..).collect()
+    }
+
+    /// One line on what is buffered, e.g.
+    /// `captured 3 JSON responses matching /api/products`
+    pub fn summary(&self) -> String {
+        let count = self.captured.len();
+        format!(
+            "captured {count} JSON response{} matching {}",
+            if count == 1 { "" } else { "s" },
+            self.pattern.as_str()
+        )
+    }
+
+    /// Update state from a CDP event
+    ///
+    /// Returns the response whose body the caller should read next, if any.
+    pub fn apply_event(&mut self, event: &Value) -> Option<BodyRequest> {
+        let method = event.get("method")?.as_str()?;
+        let params = event.get("params")?;
+        let request = BodyRequest {
+            session_id: event
+                .get("sessionId")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            request_id: params.get("requestId")?.as_str()?.to_string(),
+        };
+        match method {
+            "Network.responseReceived" => {
+                let response = params.get("response")?;
+                let url = response.get("url")?.as_str()?;
+                if !self.pattern.matches(url) {
+                    return None;
+                }
+                self.pending.insert(
+                    request,
+                    CapturedResponse {
+                        url: url.to_string(),
+                        status: response["status"].as_u64().unwrap_or_default() as u16,
+                        mime_type: response["mimeType"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_string(),
+                        headers: scrub_headers(&response["headers"]),
+                        body: Value::Null,
+                        size: 0,
+                    },
+                );
+                None
+            }
+            "Network.loadingFinished" => self.pending.contains_key(&request).then_some(request),
+            "Network.loadingFailed" => {
+                self.pending.remove(&request);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Keep the body of `request` from a `Network.getResponseBody` result if
+    /// it is JSON within the size limit; returns whether it was kept
+    pub fn record_body(&mut self, request: &BodyRequest, result: &Value) -> bool {
+        let Some(mut response) = self.pending.remove(request) else {
+            return false;
+        };
+        let body = result["body"].as_str().unwrap_or_default();
+        let bytes = if result["base64Encoded"].as_bool().unwrap_or(false) {
+            match general_purpose::STANDARD.decode(body) {
+                Ok(bytes) => bytes,
+                Err(_) => return false,
+            }
+        } else {
+            body.as_bytes().to_vec()
+        };
+        if bytes.len() > self.max_body_bytes {
+            debug!(
+                "Not capturing {}: {} bytes is over the limit of {}",
+                response.url,
+                bytes.len(),
+                self.max_body_bytes
+            );
+            return false;
+        }
+        let Ok(body) = serde_json::from_slice(&bytes) else {
+            debug!("Not capturing {}: the body is not JSON", response.url);
+            return false;
+        };
+        response.body = body;
+        response.size = bytes.len();
+        if self.captured.len() == self.max_bodies {
+            self.captured.pop_front();
+        }
+        self.captured.push_back(response);
+        true
+    }
+
+    /// Enable network events in `session_id` and capture responses in the background
+    ///
+    /// Responses of other tabs are captured too if their network events are
+    /// enabled. The task ends when the CDP connection closes.
+    pub async fn spawn(
+        capture: Arc<Mutex<Self>>,
+        client: Arc<CdpClient>,
+        session_id: &str,
+    ) -> Result<JoinHandle<()>> {
+        let mut events = client.subscribe_events();
+        client
+            .send_command_with_session("Network.enable", json!({}), Some(session_id))
+            .await?;
+
+        Ok(tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        debug!("Response capture skipped {} events", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                let Some(request) = capture.lock().unwrap().apply_event(&event) else {
+                    continue;
+                };
+                let result = client
+                    .send_command_with_session(
+                        "Network.getResponseBody",
+                        json!({ "requestId": request.request_id }),
+                        request.session_id.as_deref(),
+                    )
+                    .await;
+                let mut capture = capture.lock().unwrap();
+                match result {
+                    Ok(result) => {
+                        capture.record_body(&request, &result);
+                    }
+                    Err(e) => {
+                        debug!("Failed to read a captured response body: {}", e);
+                        capture.pending.remove(&request);
+                    }
+                }
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(method: &str, request_id: &str, params: Value) -> Value {
+        let mut params = params;
+        params["requestId"] = json!(request_id);
+        json!({ "method": method, "sessionId": "S1", "params": params })
+    }
+
+    fn received(request_id: &str, url: &str) -> Value {
+        event(
+            "Network.responseReceived",
+            request_id,
+            json!({ "response": {
+                "url": url, "status": 200, "mimeType": "application/json",
+                "headers": { "Content-Type": "application/json", "Set-Cookie": "sid=1" }
+            } }),
+        )
+    }
+
+    fn finish(capture: &mut ResponseCapture, request_id: &str, body: &str) -> bool {
+        let finished = event("Network.loadingFinished", request_id, json!({}));
+        let Some(request) = capture.apply_event(&finished) else {
+            return false;
+        };
+        capture.record_body(&request, &json!({ "body": body, "base64Encoded": false }))
+    }
+
+    #[test]
+    fn test_only_matching_json_bodies_are_kept() {
+        let mut capture = ResponseCapture::new("/api/products", 2).unwrap();
+        capture.apply_event(&received("R1", "https://shop.test/api/products?page=1"));
+        capture.apply_event(&received("R2", "https://shop.test/style.css"));
+        capture.apply_event(&received("R3", "https://shop.test/api/products?page=2"));
+
+        assert!(finish(&mut capture, "R1", r#"{"items":[1]}"#));
+        assert!(!finish(&mut capture, "R2", "body {}"));
+        assert!(!finish(&mut capture, "R3", "<html>"));
+
+        let responses = capture.responses();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].body, json!({ "items": [1] }));
+        assert_eq!(
+            responses[0].headers.keys().collect::<Vec<_>>(),
+            ["Content-Type"]
+        );
+        assert_eq!(
+            capture.summary(),
+            "captured 1 JSON response matching /api/products"
+        );
+    }
+
+    #[test]
+    fn test_buffer_keeps_the_latest_bodies() {
+        let mut capture = ResponseCapture::new("/api/", 2)
+            .unwrap()
+            .with_max_body_bytes(16);
+        for (id, body) in [
+            ("R1", "1"),
+            ("R2", "2"),
+            ("R3", "3"),
+            ("R4", "[1, 2, 3, 4, 5, 6, 7]"),
+        ] {
+            capture.apply_event(&received(id, "https://shop.test/api/n"));
+            finish(&mut capture, id, body);
+        }
+
+        let bodies: Vec<Value> = capture
+            .take_responses()
+            .into_iter()
+            .map(|r| r.body)
+            .collect();
+        assert_eq!(bodies, [json!(2), json!(3)]);
+        assert!(capture.responses().is_empty());
+    }
+}
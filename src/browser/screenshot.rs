@@ -2,9 +2,15 @@
 //!
 //! This module handles screenshot capture and saving operations.
 
+use crate::actor::{Page, ScreenshotOptions};
 use crate::error::{BrowsingError, Result};
 use base64::{Engine as _, engine::general_purpose};
-use tracing::info;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
 
 /// Manager for screenshot operations
 pub struct ScreenshotManager;
@@ -62,3 +68,98 @@ impl Default for ScreenshotManager {
         Self::new()
     }
 }
+
+/// Background task taking screenshots at a fixed interval
+///
+/// Created by [`Browser::start_interval_screenshots`](crate::Browser::start_interval_screenshots).
+/// The task stops by itself after the requested number of screenshots; dropping
+/// the handle cancels it.
+pub struct IntervalScreenshotHandle {
+    paths: Arc<Mutex<Vec<PathBuf>>>,
+    stop: Option<oneshot::Sender<()>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl IntervalScreenshotHandle {
+    /// Start taking screenshots of `page` into `save_dir`
+    ///
+    /// The first screenshot is taken immediately. Failed captures are logged
+    /// and skipped; they count towards `max_count`.
+    pub fn start(page: Page, interval: Duration, max_count: u32, save_dir: PathBuf) -> Self {
+        let paths = Arc::new(Mutex::new(Vec::new()));
+        let (stop, mut stopped) = oneshot::channel();
+
+        let saved = Arc::clone(&paths);
+        let task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            for _ in 0..max_count {
+                tokio::select! {
+                    _ = &mut stopped => break,
+                    _ = ticker.tick() => {}
+                }
+                match capture(&page, &save_dir).await {
+                    Ok(path) => saved.lock().unwrap().push(path),
+                    Err(e) => warn!("Interval screenshot failed: {}", e),
+                }
+            }
+        });
+
+        Self {
+            paths,
+            stop: Some(stop),
+            task: Some(task),
+        }
+    }
+
+    /// Path of the most recent screenshot
+    pub fn get_latest(&self) -> Option<PathBuf> {
+        self.paths.lock().unwrap().last().cloned()
+    }
+
+    /// Whether all screenshots have been taken (or the task was stopped)
+    pub fn is_finished(&self) -> bool {
+        self.task.as_ref().is_none_or(|task| task.is_finished())
+    }
+
+    /// Wait until all requested screenshots have been taken
+    pub async fn wait(&mut self) -> Result<Vec<PathBuf>> {
+        self.join().await
+    }
+
+    /// Stop taking screenshots and return the paths of all saved screenshots
+    ///
+    /// A screenshot being captured when this is called is still saved.
+    pub async fn stop(&mut self) -> Result<Vec<PathBuf>> {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        self.join().await
+    }
+
+    async fn join(&mut self) -> Result<Vec<PathBuf>> {
+        if let Some(task) = self.task.take() {
+            task.await.map_err(|e| {
+                BrowsingError::Browser(format!("Interval screenshot task failed: {e}"))
+            })?;
+        }
+        Ok(self.paths.lock().unwrap().clone())
+    }
+}
+
+impl Drop for IntervalScreenshotHandle {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+/// Take one screenshot and save it as `screenshot_{timestamp}.png`
+async fn capture(page: &Page, save_dir: &std::path::Path) -> Result<PathBuf> {
+    let timestamp = chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ");
+    let path = save_dir.join(format!("screenshot_{timestamp}.png"));
+    // A fresh name; never write through a file or link already there
+    page.write_screenshot(&path, &ScreenshotOptions::default(), false).await?;
+    Ok(path)
+}
@@ -1,14 +1,37 @@
 //! Browser session management using CDP
 
+use crate::actor::{
+    CheckpointId, EmulationSettings, PageCheckpoint, SavedScreenshot, ScreenshotOptions,
+};
 use crate::browser::cdp::{CdpClient, CdpSession};
-use crate::browser::navigation::NavigationManager;
+use crate::browser::cookies::{CookieExportFormat, format_cookies, parse_netscape};
+use crate::browser::crawl::{BrowserVisitor, CrawlOptions};
+use crate::browser::document_interception::{
+    Decision, DocumentRule, RuleHandle, add_document_rule,
+};
+use crate::browser::link_graph::{LinkGraph, build_link_graph};
+use crate::browser::manifest::WebAppManifest;
+use crate::browser::navigation::{MAX_NAVIGATION_HISTORY, NavigationManager, NavigationRecord};
+use crate::browser::network_conditions::{NetworkConditions, NetworkConditionsState};
 use crate::browser::profile::BrowserProfile;
-use crate::browser::screenshot::ScreenshotManager;
+use crate::browser::response_capture::{CapturedResponse, ResponseCapture};
+use crate::browser::screenshot::{IntervalScreenshotHandle, ScreenshotManager};
+use crate::agent::determinism::{Determinism, apply_determinism};
+use crate::browser::stealth::apply_stealth;
 use crate::browser::tab_manager::TabManager;
+use crate::browser::target_tracker::NewTargetWatcher;
+use crate::browser::views::{BrowserContextId, BrowserSession, BrowserVersionInfo, TabSnapshot};
+use crate::browser::worker_monitor::{
+    WorkerConsoleMessage, WorkerMonitor, WorkerRequest, is_worker_target,
+};
 use crate::error::{BrowsingError, Result};
 use crate::traits::BrowserClient;
 use async_trait::async_trait;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
 
 /// Browser session for managing CDP connections
 pub struct Browser {
@@ -19,12 +42,39 @@ pub struct Browser {
     navigation_manager: NavigationManager,
     screenshot_manager: ScreenshotManager,
     launcher: Option<crate::browser::launcher::BrowserLauncher>,
+    /// Target to attach to on start instead of the first page
+    preferred_target_id: Option<String>,
+    wire_log: Option<crate::browser::wire_log::WireLogConfig>,
+    reconnect_on_disconnect: bool,
+    worker_monitor: Arc<Mutex<WorkerMonitor>>,
+    worker_task: Option<JoinHandle<()>>,
+    /// Saved checkpoints; a checkpoint's ID is its position plus one
+    checkpoints: Vec<PageCheckpoint>,
+    /// Network conditions set on each tab, since CDP cannot report them
+    network_conditions: NetworkConditionsState,
+    /// Recent navigations in every tab, oldest first
+    navigations: Vec<NavigationRecord>,
+    /// Response bodies being captured, set by [`Browser::capture_responses`]
+    response_capture: Option<Arc<Mutex<ResponseCapture>>>,
+    response_capture_task: Option<JoinHandle<()>>,
+    /// Whether the HTTP cache is bypassed, from the profile or [`Browser::set_cache_disabled`]
+    cache_disabled: bool,
+    /// Emulation set on each tab with [`Browser::set_emulation`]; other tabs
+    /// use the profile's
+    emulation: HashMap<String, EmulationSettings>,
+    /// Headers sent with every request of every tab, with [`Browser::set_extra_headers`]
+    extra_headers: HashMap<String, String>,
+    /// Rules blocking document requests outside `allowed_domains`, per tab
+    domain_guards: Mutex<HashMap<String, RuleHandle>>,
+    /// Deterministic behavior given to every tab, with [`Browser::set_determinism`]
+    determinism: Option<Determinism>,
 }
 
 impl Browser {
     /// Create a new Browser session with given profile
     pub fn new(profile: BrowserProfile) -> Self {
         Self {
+            cache_disabled: profile.disable_cache,
             profile,
             cdp_client: None,
             cdp_url: None,
@@ -32,6 +82,20 @@ impl Browser {
             navigation_manager: NavigationManager::new(),
             screenshot_manager: ScreenshotManager::new(),
             launcher: None,
+            preferred_target_id: None,
+            wire_log: None,
+            reconnect_on_disconnect: false,
+            worker_monitor: Arc::new(Mutex::new(WorkerMonitor::new())),
+            worker_task: None,
+            checkpoints: Vec::new(),
+            network_conditions: NetworkConditionsState::default(),
+            navigations: Vec::new(),
+            response_capture: None,
+            response_capture_task: None,
+            emulation: HashMap::new(),
+            extra_headers: HashMap::new(),
+            domain_guards: Mutex::new(HashMap::new()),
+            determinism: None,
         }
     }
 
@@ -41,11 +105,346 @@ impl Browser {
         self
     }
 
+    /// Log CDP commands sent by this browser (takes effect on [`Browser::start`])
+    pub fn with_wire_log(mut self, config: crate::browser::wire_log::WireLogConfig) -> Self {
+        self.wire_log = Some(config);
+        self
+    }
+
+    /// Reconnect when the CDP WebSocket drops (takes effect on [`Browser::start`])
+    ///
+    /// See [`CdpClient::with_reconnect_on_disconnect`].
+    pub fn with_reconnect_on_disconnect(mut self, reconnect: bool) -> Self {
+        self.reconnect_on_disconnect = reconnect;
+        self
+    }
+
+    /// Create a browser that attaches to a session exported by another process
+    ///
+    /// No browser is launched; [`Browser::start`] connects to the exported
+    /// WebSocket URL and re-attaches to the tab that was active at export time.
+    pub fn attach_to_session(session: BrowserSession) -> Result<Browser> {
+        if !(session.cdp_ws_url.starts_with("ws://") || session.cdp_ws_url.starts_with("wss://")) {
+            return Err(BrowsingError::Config(format!(
+                "Invalid CDP WebSocket URL: {}",
+                session.cdp_ws_url
+            )));
+        }
+
+        let mut browser = Browser::new(BrowserProfile::default()).with_cdp_url(session.cdp_ws_url);
+        browser.preferred_target_id = Some(session.active_target_id);
+        Ok(browser)
+    }
+
+    /// Create a browser that connects to Chrome started with `--remote-debugging-port`
+    ///
+    /// The WebSocket URL is discovered from the HTTP endpoint when the browser starts.
+    pub fn from_remote_debugging_port(port: u16) -> Result<Browser> {
+        if port == 0 {
+            return Err(BrowsingError::Config(
+                "Remote debugging port must be non-zero".to_string(),
+            ));
+        }
+        Ok(Browser::new(BrowserProfile::default()).with_cdp_url(format!("http://127.0.0.1:{port}")))
+    }
+
+    /// Connect to Chrome already running with `--remote-debugging-port=<port>`
+    ///
+    /// Lists the targets at `http://host:port/json` and attaches to the first
+    /// page. No browser is launched, and [`Browser::stop`] only disconnects,
+    /// leaving the browser and its tabs running.
+    pub async fn attach(host: &str, port: u16) -> Result<Browser> {
+        if host.trim().is_empty() || port == 0 {
+            return Err(BrowsingError::Config(format!(
+                "Invalid remote debugging address {host}:{port}"
+            )));
+        }
+        let (target_id, ws_url) = crate::browser::launcher::BrowserLauncher::first_page_target(
+            &format!("http://{host}:{port}"),
+        )
+        .await?;
+
+        let mut browser = Browser::new(BrowserProfile::default()).with_cdp_url(ws_url);
+        browser.preferred_target_id = Some(target_id);
+        browser.start().await?;
+        Ok(browser)
+    }
+
+    /// Whether this is connected to a browser it did not launch
+    ///
+    /// [`Browser::stop`] leaves such a browser running.
+    pub fn is_attached(&self) -> bool {
+        self.cdp_client.is_some() && self.launcher.is_none()
+    }
+
+    /// Keep a browser this session launched running after [`Browser::stop`] and
+    /// after the session is dropped
+    ///
+    /// Lets a session exported with [`Browser::export_session`] be attached to
+    /// once this process exits. Attached browsers are never stopped anyway.
+    pub fn detach(&mut self) {
+        if let Some(launcher) = &mut self.launcher {
+            launcher.detach();
+        }
+    }
+
+    /// Capture the connection details, cookies and tabs of this session
+    ///
+    /// The result can be serialized and passed to [`Browser::attach_to_session`]
+    /// in another process. A browser this session launched is killed when the
+    /// session is stopped or dropped, so call [`Browser::detach`] first for it
+    /// to outlive this process.
+    pub async fn export_session(&self) -> Result<BrowserSession> {
+        let cdp_ws_url = self
+            .cdp_url
+            .clone()
+            .ok_or_else(|| BrowsingError::Browser("Browser not started".to_string()))?;
+        let client = self.get_cdp_client()?;
+        let session_id = self.get_session_id()?;
+
+        let cookies = client
+            .send_command_with_session("Network.getAllCookies", serde_json::json!({}), Some(&session_id))
+            .await?
+            .get("cookies")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+
+        let tabs = self
+            .get_tabs(false)
+            .await?
+            .into_iter()
+            .map(TabSnapshot::from)
+            .collect();
+
+        Ok(BrowserSession {
+            cdp_ws_url,
+            active_target_id: self.get_current_target_id()?,
+            session_id,
+            cookies,
+            tabs,
+        })
+    }
+
+    /// Import cookies from a Netscape cookie file, returning how many were set
+    pub async fn import_cookies_from_netscape(&mut self, path: &Path) -> Result<u32> {
+        let content = tokio::fs::read_to_string(path).await?;
+        let cookies = parse_netscape(&content)?;
+        if cookies.is_empty() {
+            return Ok(0);
+        }
+        let count = cookies.len() as u32;
+        self.get_cdp_client()?
+            .send_command_with_session(
+                "Network.setCookies",
+                serde_json::json!({ "cookies": cookies }),
+                Some(&self.get_session_id()?),
+            )
+            .await?;
+        Ok(count)
+    }
+
+    /// Write all browser cookies to a file, returning how many were written
+    pub async fn export_cookies(&self, path: &Path, format: CookieExportFormat) -> Result<u32> {
+        let cookies = self
+            .get_cdp_client()?
+            .send_command_with_session(
+                "Network.getAllCookies",
+                serde_json::json!({}),
+                Some(&self.get_session_id()?),
+            )
+            .await?
+            .get("cookies")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        tokio::fs::write(path, format_cookies(&cookies, format)?).await?;
+        Ok(cookies.len() as u32)
+    }
+
+    /// Save URL, cookies, storage and scroll position of the current page
+    ///
+    /// Use [`Browser::restore`] to return to it, e.g. before trying another
+    /// branch of a checkout flow.
+    pub async fn checkpoint(&mut self) -> Result<CheckpointId> {
+        let checkpoint = self.get_page()?.capture_checkpoint().await?;
+        self.checkpoints.push(checkpoint);
+        Ok(CheckpointId(self.checkpoints.len() as u32))
+    }
+
+    /// Return the current tab to a checkpoint saved with [`Browser::checkpoint`]
+    ///
+    /// The page is re-navigated with its cookies and storage as they were.
+    /// Server-side state (orders placed, forms submitted) is not rolled back.
+    pub async fn restore(&mut self, id: CheckpointId) -> Result<()> {
+        let checkpoint = id
+            .0
+            .checked_sub(1)
+            .and_then(|i| self.checkpoints.get(i as usize))
+            .ok_or_else(|| BrowsingError::Browser(format!("No checkpoint with ID {id}")))?;
+        self.check_navigation_allowed(&checkpoint.url)?;
+        self.get_page()?.restore_checkpoint(checkpoint).await
+    }
+
+    /// Throttle the current tab's network or take it offline
+    ///
+    /// Passing [`NetworkConditions::unthrottled`] is the same as
+    /// [`Browser::reset_network_conditions`].
+    pub async fn set_network_conditions(&mut self, conditions: NetworkConditions) -> Result<()> {
+        let client = self.get_cdp_client()?;
+        let session_id = self.get_session_id()?;
+        client
+            .send_command_with_session("Network.enable", serde_json::json!({}), Some(&session_id))
+            .await?;
+        client
+            .send_command_with_session(
+                "Network.emulateNetworkConditions",
+                conditions.to_cdp_params(),
+                Some(&session_id),
+            )
+            .await?;
+        let target_id = self.get_current_target_id()?;
+        self.network_conditions.set(&target_id, conditions);
+        Ok(())
+    }
+
+    /// Network conditions last set on the current tab, or `None` if it is not throttled
+    pub async fn get_network_conditions(&self) -> Result<Option<NetworkConditions>> {
+        let target_id = self.get_current_target_id()?;
+        Ok(self.network_conditions.get(&target_id))
+    }
+
+    /// Whether the current tab was taken offline
+    pub async fn is_offline(&self) -> bool {
+        matches!(
+            self.get_network_conditions().await,
+            Ok(Some(conditions)) if conditions.offline
+        )
+    }
+
+    /// Remove throttling and offline emulation from the current tab
+    pub async fn reset_network_conditions(&mut self) -> Result<()> {
+        self.set_network_conditions(NetworkConditions::unthrottled()).await
+    }
+
+    /// Emulate a color scheme, vision deficiency or Save-Data in the current tab
+    ///
+    /// Replaces the tab's previous settings, including the profile's
+    /// [`BrowserProfile::emulation`]. The settings are applied again whenever
+    /// the tab is switched to.
+    pub async fn set_emulation(&mut self, settings: EmulationSettings) -> Result<()> {
+        let page = self.get_page()?;
+        page.apply_emulated_media(&settings).await?;
+        let target_id = self.get_current_target_id()?;
+        let headers = self.tab_headers(&settings);
+        page.set_extra_headers(&headers).await?;
+        self.emulation.insert(target_id, settings);
+        Ok(())
+    }
+
+    /// Send `headers` with every request of every tab, replacing the headers
+    /// set before
+    ///
+    /// They are merged with the `Save-Data` header of a tab's emulation, and
+    /// sent from tabs opened or switched to later too.
+    pub async fn set_extra_headers(&mut self, headers: HashMap<String, String>) -> Result<()> {
+        self.extra_headers = headers;
+        let target_id = self.get_current_target_id()?;
+        let headers = self.tab_headers(&self.tab_emulation(&target_id));
+        self.get_page()?.set_extra_headers(&headers).await
+    }
+
+    /// Extra headers of a tab with `emulation`: the browser's, then the emulation's
+    fn tab_headers(&self, emulation: &EmulationSettings) -> HashMap<String, String> {
+        let mut headers = self.extra_headers.clone();
+        headers.extend(emulation.headers());
+        headers
+    }
+
+    /// Emulation settings of the current tab
+    pub fn emulation(&self) -> EmulationSettings {
+        self.get_current_target_id()
+            .ok()
+            .map_or(self.profile.emulation, |target_id| {
+                self.tab_emulation(&target_id)
+            })
+    }
+
+    fn tab_emulation(&self, target_id: &str) -> EmulationSettings {
+        self.emulation
+            .get(target_id)
+            .copied()
+            .unwrap_or(self.profile.emulation)
+    }
+
+    /// Bypass Chrome's HTTP cache, or use it again, in every tab
+    ///
+    /// Tabs opened later follow the setting too. Starts from the profile's
+    /// [`BrowserProfile::disable_cache`].
+    pub async fn set_cache_disabled(&mut self, disabled: bool) -> Result<()> {
+        let client = self.get_cdp_client()?;
+        let current = self.get_session_id()?;
+        set_session_cache_disabled(&client, &current, disabled).await?;
+        self.cache_disabled = disabled;
+        for session in self.tab_manager.sessions().values() {
+            if session.session_id != current
+                && let Err(e) =
+                    set_session_cache_disabled(&client, &session.session_id, disabled).await
+            {
+                tracing::debug!("Failed to update the HTTP cache of a tab: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the HTTP cache is bypassed
+    pub fn is_cache_disabled(&self) -> bool {
+        self.cache_disabled
+    }
+
+    /// Make the current tab behave as `determinism` asks
+    ///
+    /// Tabs opened or switched to later get the same behavior.
+    pub async fn set_determinism(&mut self, determinism: Determinism) -> Result<()> {
+        let client = self.get_cdp_client()?;
+        apply_determinism(&client, &self.get_session_id()?, &determinism).await?;
+        self.determinism = Some(determinism);
+        Ok(())
+    }
+
+    /// Clear Chrome's HTTP cache, for all tabs
+    pub async fn clear_cache(&self) -> Result<()> {
+        let client = self.get_cdp_client()?;
+        let session_id = self.get_session_id()?;
+        client
+            .send_command_with_session("Network.enable", serde_json::json!({}), Some(&session_id))
+            .await?;
+        client
+            .send_command_with_session(
+                "Network.clearBrowserCache",
+                serde_json::json!({}),
+                Some(&session_id),
+            )
+            .await?;
+        Ok(())
+    }
+
     /// Start the browser session (launches browser or connects to existing)
     pub async fn start(&mut self) -> Result<()> {
+        // An HTTP endpoint (e.g. from a remote debugging port) is resolved to its WebSocket URL
+        if let Some(ref cdp_url) = self.cdp_url
+            && (cdp_url.starts_with("http://") || cdp_url.starts_with("https://"))
+        {
+            let ws_url = crate::browser::launcher::BrowserLauncher::get_websocket_debugger_url(
+                cdp_url.trim_end_matches('/'),
+            )
+            .await?;
+            self.cdp_url = Some(ws_url);
+        }
+
         // If cdp_url is provided, connect to existing browser
         if let Some(ref cdp_url) = self.cdp_url {
-            let mut client = CdpClient::new(cdp_url.clone());
+            let mut client = self.new_cdp_client(cdp_url.clone());
             client.start().await?;
             let client_arc = Arc::new(client);
             self.cdp_client = Some(Arc::clone(&client_arc));
@@ -56,11 +455,18 @@ impl Browser {
                 .await?;
 
             if let Some(target_infos) = targets["targetInfos"].as_array() {
-                // Find the first page-type target (not extensions, service workers, etc.)
+                // Prefer the target from an imported session, then the first page-type
+                // target (not extensions, service workers, etc.)
+                let preferred = self.preferred_target_id.take();
                 let page_target = target_infos
                     .iter()
-                    .find(|t| t["type"].as_str() == Some("page"))
-                    .or_else(|| target_infos.first());
+                    .find(|t| preferred.is_some() && t["targetId"].as_str() == preferred.as_deref())
+                    .or_else(|| target_infos.iter().find(|t| t["type"].as_str() == Some("page")))
+                    .or_else(|| {
+                        target_infos
+                            .iter()
+                            .find(|t| !is_worker_target(t["type"].as_str().unwrap_or("")))
+                    });
 
                 if let Some(target) = page_target {
                     if let Some(target_id) = target["targetId"].as_str() {
@@ -89,7 +495,7 @@ impl Browser {
             self.cdp_url = Some(cdp_url.clone());
 
             // Now connect via CDP
-            let mut client = CdpClient::new(cdp_url);
+            let mut client = self.new_cdp_client(cdp_url);
             client.start().await?;
             let client_arc = Arc::new(client);
             self.cdp_client = Some(Arc::clone(&client_arc));
@@ -104,7 +510,11 @@ impl Browser {
                 let page_target = target_infos
                     .iter()
                     .find(|t| t["type"].as_str() == Some("page"))
-                    .or_else(|| target_infos.first());
+                    .or_else(|| {
+                        target_infos
+                            .iter()
+                            .find(|t| !is_worker_target(t["type"].as_str().unwrap_or("")))
+                    });
 
                 if let Some(target) = page_target {
                     if let Some(target_id) = target["targetId"].as_str() {
@@ -121,13 +531,316 @@ impl Browser {
             }
         }
 
+        if let Some(ref client) = self.cdp_client
+            && let Err(e) = self.tab_manager.start_tracking(client).await
+        {
+            tracing::warn!("Failed to start target tracking: {}", e);
+        }
+
+        if let Some(target_id) = self.tab_manager.current_target_id().map(str::to_string) {
+            self.prepare_tab(&target_id).await;
+        }
+
+        if self.profile.attach_workers
+            && let Some(ref client) = self.cdp_client
+        {
+            match WorkerMonitor::spawn(Arc::clone(&self.worker_monitor), Arc::clone(client)).await {
+                Ok(task) => self.worker_task = Some(task),
+                Err(e) => tracing::warn!("Failed to attach to workers: {}", e),
+            }
+        }
+
+        if self.response_capture.is_some()
+            && let Err(e) = self.start_response_capture().await
+        {
+            tracing::warn!("Failed to start capturing responses: {}", e);
+        }
+
+        if crate::error::debug_errors_enabled()
+            && let Ok(info) = self.version_info().await
+        {
+            let fingerprint = crate::version::build_info().fingerprint(Some(&info));
+            crate::error::set_error_fingerprint(Some(fingerprint));
+        }
+
         Ok(())
     }
 
+    /// Apply the settings every tab gets to a new tab
+    async fn prepare_tab(&self, target_id: &str) {
+        self.apply_domain_guard(target_id).await;
+        self.apply_stealth(target_id).await;
+        self.apply_determinism(target_id).await;
+        self.apply_cache_disabled(target_id).await;
+        self.apply_emulation(target_id).await;
+        self.apply_extra_headers(target_id).await;
+    }
+
+    /// Block document requests outside the profile's `allowed_domains` in a tab
+    ///
+    /// Links, redirects, scripts and frames can't leave the allowed domains
+    /// either, not only navigations the agent asks for.
+    async fn apply_domain_guard(&self, target_id: &str) {
+        let Some(domains) = self.profile.allowed_domains.clone() else {
+            return;
+        };
+        if domains.is_empty()
+            || self
+                .domain_guards
+                .lock()
+                .is_ok_and(|guards| guards.contains_key(target_id))
+        {
+            return;
+        }
+        let (Ok(client), Some(session)) =
+            (self.get_cdp_client(), self.tab_manager.get_session(target_id))
+        else {
+            return;
+        };
+        match add_document_rule(&client, &session.session_id, Arc::new(AllowedDomains(domains)))
+            .await
+        {
+            Ok(guard) => {
+                if let Ok(mut guards) = self.domain_guards.lock() {
+                    guards.insert(target_id.to_string(), guard);
+                }
+            }
+            Err(e) => tracing::warn!("Failed to block requests outside allowed_domains: {}", e),
+        }
+    }
+
+    /// Apply a tab's emulation settings to its session, if it has any
+    ///
+    /// The `Save-Data` header is sent by [`Browser::apply_extra_headers`].
+    async fn apply_emulation(&self, target_id: &str) {
+        let settings = self.tab_emulation(target_id);
+        if settings.is_default() {
+            return;
+        }
+        let Ok(page) = self.get_page_for_target(target_id) else {
+            return;
+        };
+        if let Err(e) = page.apply_emulated_media(&settings).await {
+            tracing::warn!("Failed to apply emulation settings: {}", e);
+        }
+    }
+
+    /// Send the browser's extra headers, merged with the tab's, from a tab's session
+    async fn apply_extra_headers(&self, target_id: &str) {
+        let headers = self.tab_headers(&self.tab_emulation(target_id));
+        if headers.is_empty() {
+            return;
+        }
+        let Ok(page) = self.get_page_for_target(target_id) else {
+            return;
+        };
+        if let Err(e) = page.set_extra_headers(&headers).await {
+            tracing::warn!("Failed to set extra request headers: {}", e);
+        }
+    }
+
+    /// Bypass the HTTP cache in a tab's session, if the cache is disabled
+    async fn apply_cache_disabled(&self, target_id: &str) {
+        if !self.cache_disabled {
+            return;
+        }
+        let (Ok(client), Some(session)) =
+            (self.get_cdp_client(), self.tab_manager.get_session(target_id))
+        else {
+            return;
+        };
+        if let Err(e) = set_session_cache_disabled(&client, &session.session_id, true).await {
+            tracing::warn!("Failed to disable the HTTP cache: {}", e);
+        }
+    }
+
+    /// Apply the profile's headless detection mitigations to a tab, if enabled
+    async fn apply_stealth(&self, target_id: &str) {
+        if !self.profile.stealth {
+            return;
+        }
+        let (Ok(client), Some(session)) =
+            (self.get_cdp_client(), self.tab_manager.get_session(target_id))
+        else {
+            return;
+        };
+        if let Err(e) =
+            apply_stealth(&client, &session.session_id, &self.profile.stealth_options).await
+        {
+            tracing::warn!("Failed to apply stealth mitigations: {}", e);
+        }
+    }
+
+    /// Apply the browser's deterministic behavior to a tab, if it has any
+    async fn apply_determinism(&self, target_id: &str) {
+        let Some(ref determinism) = self.determinism else {
+            return;
+        };
+        let (Ok(client), Some(session)) =
+            (self.get_cdp_client(), self.tab_manager.get_session(target_id))
+        else {
+            return;
+        };
+        if let Err(e) = apply_determinism(&client, &session.session_id, determinism).await {
+            tracing::warn!("Failed to make the page deterministic: {}", e);
+        }
+    }
+
+    /// Browser and protocol versions, plus launcher details if this process launched it
+    pub async fn version_info(&self) -> Result<BrowserVersionInfo> {
+        let result = self
+            .get_cdp_client()?
+            .send_command("Browser.getVersion", serde_json::json!({}))
+            .await?;
+        let mut info = BrowserVersionInfo::from_cdp(&result);
+        info.headless = self.profile.headless;
+        if let Some(ref launcher) = self.launcher {
+            info.launched = true;
+            info.executable_path = launcher
+                .executable_path()
+                .map(|path| path.display().to_string());
+            info.pid = launcher.pid();
+        }
+        Ok(info)
+    }
+
+    /// Web app manifest of the current page, `None` if it links none
+    pub async fn get_web_app_manifest(&self) -> Result<Option<WebAppManifest>> {
+        self.get_page()?.get_web_app_manifest().await
+    }
+
+    /// Crawl from the current page within `options` and graph the links between pages
+    ///
+    /// Leaves the browser on the last page crawled.
+    pub async fn build_link_graph(&mut self, options: &CrawlOptions) -> Result<LinkGraph> {
+        let start_url = self.get_current_url().await?;
+        let delay = Duration::from_millis(options.delay_ms);
+        let mut visitor = BrowserVisitor::new(self, 0, delay);
+        build_link_graph(&mut visitor, &start_url, options).await
+    }
+
+    fn new_cdp_client(&self, cdp_url: String) -> CdpClient {
+        let client =
+            CdpClient::new(cdp_url).with_reconnect_on_disconnect(self.reconnect_on_disconnect);
+        match self.wire_log {
+            Some(ref config) => client.with_wire_log(config.clone()),
+            None => client,
+        }
+    }
+
     /// Navigate to the specified URL
+    ///
+    /// Fails without navigating if the URL is outside the profile's `allowed_domains`.
     pub async fn navigate(&mut self, url: &str) -> Result<()> {
+        self.check_navigation_allowed(url)?;
         let page = self.get_page()?;
-        self.navigation_manager.navigate(&page, url).await
+        let record = self.navigation_manager.navigate(&page, url).await?;
+        self.record_navigation(record);
+        Ok(())
+    }
+
+    /// Remember a navigation in the current tab
+    fn record_navigation(&mut self, mut record: NavigationRecord) {
+        record.target_id = self.get_current_target_id().unwrap_or_default();
+        if self.navigations.len() >= MAX_NAVIGATION_HISTORY {
+            self.navigations.remove(0);
+        }
+        self.navigations.push(record);
+    }
+
+    /// Follow redirects the current page makes by itself, for at most
+    /// `grace_ms` per page
+    ///
+    /// Navigations that wait for a load state do this already; this covers
+    /// pages reached by a click or a navigation that did not wait. The hops
+    /// are added to the tab's last navigation if it ended on the page that
+    /// moved on, and recorded as a navigation of their own otherwise.
+    pub async fn follow_client_redirects(&mut self, grace_ms: u64) -> Result<()> {
+        let record = self.get_page()?.follow_client_redirects(grace_ms).await?;
+        if record.client_redirects.is_empty() {
+            return Ok(());
+        }
+        let target_id = self.get_current_target_id().unwrap_or_default();
+        match self
+            .navigations
+            .iter_mut()
+            .rev()
+            .find(|last| last.target_id == target_id)
+        {
+            Some(last) if last.final_url == record.requested_url => {
+                last.client_redirects.extend(record.client_redirects);
+                last.final_url = record.final_url;
+            }
+            _ => self.record_navigation(record),
+        }
+        Ok(())
+    }
+
+    /// Navigations made with [`Browser::navigate`] in any tab, oldest first
+    ///
+    /// Retried navigations appear once per attempt. At most
+    /// [`MAX_NAVIGATION_HISTORY`] are kept.
+    pub fn navigation_history(&self) -> &[NavigationRecord] {
+        &self.navigations
+    }
+
+    /// The last navigation made in the current tab
+    pub fn last_navigation(&self) -> Option<&NavigationRecord> {
+        let target_id = self.tab_manager.current_target_id()?;
+        self.navigations
+            .iter()
+            .rev()
+            .find(|record| record.target_id == target_id)
+    }
+
+    /// Refuse URLs outside the profile's `allowed_domains`, if any are set
+    ///
+    /// This is the hard limit on where an agent can be sent, whatever the
+    /// pages it reads tell the model. `about:blank` is always allowed.
+    fn check_navigation_allowed(&self, url: &str) -> Result<()> {
+        let Some(ref domains) = self.profile.allowed_domains else {
+            return Ok(());
+        };
+        if url == "about:blank" || is_domain_allowed(url, domains) {
+            return Ok(());
+        }
+        Err(BrowsingError::Browser(format!(
+            "Navigation to {url} blocked: domain is not in allowed_domains"
+        )))
+    }
+
+    /// Navigate with a referrer, one-shot headers or a load state to wait for
+    pub async fn navigate_with_options(
+        &mut self,
+        url: &str,
+        options: crate::actor::NavigateOptions,
+    ) -> Result<()> {
+        if options.is_empty() {
+            return self.navigate(url).await;
+        }
+        self.check_navigation_allowed(url)?;
+        let page = self.get_page()?;
+        let record = self
+            .navigation_manager
+            .navigate_with_options(&page, url, &options)
+            .await?;
+        self.record_navigation(record);
+        Ok(())
+    }
+
+    /// Reload the current page, bypassing the HTTP cache with `ignore_cache`,
+    /// and wait for `wait_until` on the new document
+    ///
+    /// The reload is recorded like a navigation to the page's URL.
+    pub async fn reload(
+        &mut self,
+        ignore_cache: bool,
+        wait_until: Option<crate::actor::LoadState>,
+    ) -> Result<()> {
+        let page = self.get_page()?;
+        let record = page.reload_tracked(ignore_cache, wait_until).await?;
+        self.record_navigation(record);
+        Ok(())
     }
 
     /// Get the current page URL
@@ -165,9 +878,20 @@ impl Browser {
     /// - Temporary profiles are preserved for debugging and inspection
     ///
     /// Users are responsible for managing their own browser data directories.
+    ///
+    /// A browser this session attached to (see [`Browser::is_attached`]) is
+    /// disconnected from but left running.
     pub async fn stop(&mut self) -> Result<()> {
         // 1. Clear tab manager first (drops session refs to CDP client)
         self.tab_manager = TabManager::new();
+        self.network_conditions.clear();
+        self.emulation.clear();
+        if let Some(task) = self.worker_task.take() {
+            task.abort();
+        }
+        if let Some(task) = self.response_capture_task.take() {
+            task.abort();
+        }
 
         // 2. Send WebSocket Close frame and drop CDP client so background task exits cleanly
         //    (avoids "Connection reset without closing handshake" ERROR on kill)
@@ -177,7 +901,7 @@ impl Browser {
         }
         tokio::task::yield_now().await; // Let spawned WebSocket task exit
 
-        // 3. Stop launcher (kills browser process)
+        // 3. Stop launcher (kills browser process); attached browsers have none
         if let Some(ref mut launcher) = self.launcher {
             launcher.stop().await?;
         }
@@ -212,6 +936,17 @@ impl Browser {
         Ok(crate::actor::Page::new(client, session_id))
     }
 
+    /// Get a Page actor for the tab `target_id`, without switching to it
+    pub fn get_page_for_target(&self, target_id: &str) -> Result<crate::actor::Page> {
+        let session = self.tab_manager.get_session(target_id).ok_or_else(|| {
+            BrowsingError::Browser(format!("No session for target {target_id}"))
+        })?;
+        Ok(crate::actor::Page::new(
+            Arc::clone(&session.client),
+            session.session_id.clone(),
+        ))
+    }
+
     /// Get the current target ID
     pub fn get_current_target_id(&self) -> Result<String> {
         self.tab_manager
@@ -234,33 +969,285 @@ impl Browser {
             .await
     }
 
+    /// Take a screenshot of the current page and write it to `path`
+    ///
+    /// Unlike [`take_screenshot`](Self::take_screenshot), the image is
+    /// decoded to the file in chunks and never held in memory as a whole.
+    pub async fn screenshot_to_file(
+        &self,
+        path: &Path,
+        options: &ScreenshotOptions,
+    ) -> Result<SavedScreenshot> {
+        let saved = self.get_page()?.screenshot_to_file(path, options).await?;
+        tracing::info!("Screenshot saved to: {}", path.display());
+        Ok(saved)
+    }
+
+    /// Resize the viewport and screenshot the current tab once it has reflowed
+    ///
+    /// Avoids capturing a page that has not yet laid out at the new size, as
+    /// can happen when the two are done separately.
+    pub async fn set_viewport_and_capture(
+        &self,
+        width: u32,
+        height: u32,
+        format: &str,
+        full_page: bool,
+    ) -> Result<Vec<u8>> {
+        self.get_page()?
+            .set_viewport_and_capture(width, height, format, full_page)
+            .await
+    }
+
+    /// Take a screenshot of the current tab every `interval_ms`, up to `max_count` times
+    ///
+    /// Screenshots are saved as `screenshot_{timestamp}.png` in `save_dir`,
+    /// which is created if needed. The tab that is current when this is called
+    /// keeps being captured even if another tab is activated later.
+    pub async fn start_interval_screenshots(
+        &mut self,
+        interval_ms: u64,
+        max_count: u32,
+        save_dir: &Path,
+    ) -> Result<IntervalScreenshotHandle> {
+        if interval_ms == 0 {
+            return Err(BrowsingError::Validation(
+                "Screenshot interval must be greater than zero".to_string(),
+            ));
+        }
+        if max_count == 0 {
+            return Err(BrowsingError::Validation(
+                "Screenshot count must be at least 1".to_string(),
+            ));
+        }
+        let page = self.get_page()?;
+        tokio::fs::create_dir_all(save_dir).await?;
+
+        Ok(IntervalScreenshotHandle::start(
+            page,
+            Duration::from_millis(interval_ms),
+            max_count,
+            save_dir.to_path_buf(),
+        ))
+    }
+
+    /// Capture JSON bodies of responses whose URL matches `url_pattern`
+    ///
+    /// `url_pattern` is a [`UrlPattern`](crate::actor::UrlPattern). Up to
+    /// `max_bodies` of the latest bodies are kept until taken with
+    /// [`Browser::take_captured_responses`]. Capture covers the current tab,
+    /// and starts with [`Browser::start`] if the browser is not running yet.
+    /// Replaces any earlier capture.
+    pub async fn capture_responses(&mut self, url_pattern: &str, max_bodies: usize) -> Result<()> {
+        let capture = ResponseCapture::new(url_pattern, max_bodies)?;
+        self.stop_capturing_responses();
+        self.response_capture = Some(Arc::new(Mutex::new(capture)));
+        if self.tab_manager.current_target_id().is_some() {
+            self.start_response_capture().await?;
+        }
+        Ok(())
+    }
+
+    /// Stop capturing responses, dropping any not yet taken
+    pub fn stop_capturing_responses(&mut self) {
+        self.response_capture = None;
+        if let Some(task) = self.response_capture_task.take() {
+            task.abort();
+        }
+    }
+
+    /// Captured responses not yet taken, oldest first
+    pub fn captured_responses(&self) -> Vec<CapturedResponse> {
+        self.response_capture
+            .as_ref()
+            .map(|capture| capture.lock().unwrap().responses())
+            .unwrap_or_default()
+    }
+
+    /// Take the captured responses, oldest first
+    pub fn take_captured_responses(&self) -> Vec<CapturedResponse> {
+        self.response_capture
+            .as_ref()
+            .map(|capture| capture.lock().unwrap().take_responses())
+            .unwrap_or_default()
+    }
+
+    /// One line on the captured responses, while capturing
+    pub fn response_capture_summary(&self) -> Option<String> {
+        let capture = self.response_capture.as_ref()?;
+        Some(capture.lock().unwrap().summary())
+    }
+
+    async fn start_response_capture(&mut self) -> Result<()> {
+        let Some(ref capture) = self.response_capture else {
+            return Ok(());
+        };
+        let task = ResponseCapture::spawn(
+            Arc::clone(capture),
+            self.get_cdp_client()?,
+            &self.get_session_id()?,
+        )
+        .await?;
+        if let Some(old) = self.response_capture_task.replace(task) {
+            old.abort();
+        }
+        Ok(())
+    }
+
+    /// Console messages logged by service and shared workers, oldest first
+    ///
+    /// Empty unless [`BrowserProfile::attach_workers`] is set.
+    pub fn worker_console_messages(&self) -> Vec<WorkerConsoleMessage> {
+        self.worker_monitor.lock().unwrap().console_messages()
+    }
+
+    /// Requests made by service and shared workers, oldest first
+    ///
+    /// Empty unless [`BrowserProfile::attach_workers`] is set.
+    pub fn worker_requests(&self) -> Vec<WorkerRequest> {
+        self.worker_monitor.lock().unwrap().requests()
+    }
+
     /// Get all open tabs
-    pub async fn get_tabs(&self) -> Result<Vec<crate::browser::views::TabInfo>> {
+    ///
+    /// Set `include_all_targets` to also list background pages, service workers
+    /// and other non-tab targets.
+    pub async fn get_tabs(
+        &self,
+        include_all_targets: bool,
+    ) -> Result<Vec<crate::browser::views::TabInfo>> {
         let client = self.get_cdp_client()?;
-        self.tab_manager.get_tabs(&client).await
+        self.tab_manager.get_tabs(&client, include_all_targets).await
     }
 
     /// Create a new tab
     pub async fn create_new_tab(&mut self, url: Option<&str>) -> Result<String> {
+        if let Some(url) = url {
+            self.check_navigation_allowed(url)?;
+        }
+        let client = self.get_cdp_client()?;
+        let target_id = self.tab_manager.create_tab(&client, url).await?;
+        self.prepare_tab(&target_id).await;
+        Ok(target_id)
+    }
+
+    /// Open `url` in a tab that is not brought to the front
+    ///
+    /// The current tab stays current.
+    pub async fn create_background_tab(&mut self, url: &str) -> Result<String> {
+        self.check_navigation_allowed(url)?;
         let client = self.get_cdp_client()?;
-        self.tab_manager.create_tab(&client, url).await
+        let target_id = self.tab_manager.create_background_tab(&client, url).await?;
+        self.prepare_tab(&target_id).await;
+        Ok(target_id)
     }
 
     /// Switch to a different tab by target ID
     pub async fn switch_to_tab(&mut self, target_id: &str) -> Result<()> {
         let client = self.get_cdp_client()?;
-        self.tab_manager.switch_to_tab(&client, target_id).await
+        self.tab_manager.switch_to_tab(&client, target_id).await?;
+        // Switching attaches a new session, which starts without any of the
+        // tab's scripts, emulation or headers
+        self.prepare_tab(target_id).await;
+        Ok(())
+    }
+
+    /// Create an isolated browser context, sharing no cookies or storage with other contexts
+    pub async fn create_browser_context(&mut self) -> Result<BrowserContextId> {
+        let client = self.get_cdp_client()?;
+        self.tab_manager.create_browser_context(&client).await
+    }
+
+    /// Dispose a browser context and close all of its tabs
+    pub async fn dispose_browser_context(&mut self, context: &BrowserContextId) -> Result<()> {
+        let client = self.get_cdp_client()?;
+        self.tab_manager.dispose_browser_context(&client, context).await
+    }
+
+    /// List all browser contexts except the default one
+    pub async fn list_browser_contexts(&self) -> Result<Vec<BrowserContextId>> {
+        let client = self.get_cdp_client()?;
+        self.tab_manager.list_browser_contexts(&client).await
+    }
+
+    /// Browser context of the current tab; `None` for the default context
+    pub fn current_browser_context(&self) -> Option<&BrowserContextId> {
+        self.tab_manager.current_browser_context()
+    }
+
+    /// Create a tab in a new isolated browser context, returning its target ID and context
+    ///
+    /// The tab is not switched to. Closing it with [`Browser::close_tab`]
+    /// disposes the context.
+    pub async fn create_tab_in_new_context(
+        &mut self,
+        url: Option<&str>,
+    ) -> Result<(String, BrowserContextId)> {
+        if let Some(url) = url {
+            self.check_navigation_allowed(url)?;
+        }
+        let client = self.get_cdp_client()?;
+        let context = self.tab_manager.create_browser_context(&client).await?;
+        match self
+            .tab_manager
+            .create_tab_in_context(&client, url, Some(&context))
+            .await
+        {
+            Ok(target_id) => {
+                self.prepare_tab(&target_id).await;
+                Ok((target_id, context))
+            }
+            Err(e) => {
+                let _ = self.tab_manager.dispose_browser_context(&client, &context).await;
+                Err(e)
+            }
+        }
     }
 
     /// Close a tab by target ID
+    ///
+    /// Closing the last tab of a context created by this browser disposes the
+    /// context too.
     pub async fn close_tab(&mut self, target_id: &str) -> Result<()> {
         let client = self.get_cdp_client()?;
-        self.tab_manager.close_tab(&client, target_id).await
+        let guard = self
+            .domain_guards
+            .lock()
+            .ok()
+            .and_then(|mut guards| guards.remove(target_id));
+        if let Some(guard) = guard {
+            let _ = guard.remove().await;
+        }
+        self.tab_manager.close_tab(&client, target_id).await?;
+        self.network_conditions.remove(target_id);
+        self.emulation.remove(target_id);
+        Ok(())
+    }
+
+    /// Run `trigger` and return the target ID of the page it opens
+    ///
+    /// For actions that open a popup or follow a `target="_blank"` link. Pages
+    /// that were already open don't count. The new page is not switched to;
+    /// see [`BrowserClient::handle_new_window`].
+    pub async fn wait_for_new_target<T>(
+        &self,
+        trigger: impl Future<Output = Result<T>>,
+        timeout_ms: u64,
+    ) -> Result<String> {
+        let client = self.get_cdp_client()?;
+        let mut watcher = NewTargetWatcher::start(&client).await?;
+        trigger.await?;
+        watcher
+            .wait(Duration::from_millis(timeout_ms))
+            .await
+            .ok_or_else(|| {
+                BrowsingError::Browser(format!("No new window opened within {timeout_ms}ms"))
+            })
     }
 
     /// Get target ID from short tab ID (last 4 characters)
     pub async fn get_target_id_from_tab_id(&self, tab_id: &str) -> Result<String> {
-        let tabs = self.get_tabs().await?;
+        let tabs = self.get_tabs(false).await?;
 
         // Try to find target ID ending with tab_id
         for tab in tabs {
@@ -313,7 +1300,7 @@ impl Browser {
             .unwrap_or_else(|_| "Unknown".to_string());
 
         // Get tabs
-        let tabs = self.get_tabs().await.unwrap_or_default();
+        let tabs = self.get_tabs(false).await.unwrap_or_default();
 
         // Get DOM state
         let dom_state = if let Some(dom_service) = dom_service {
@@ -327,6 +1314,7 @@ impl Browser {
                 markdown: None,
                 elements: vec![],
                 selector_map: std::collections::HashMap::new(),
+                truncated_values: vec![],
             }
         };
 
@@ -372,27 +1360,80 @@ impl BrowserClient for Browser {
         self.start().await
     }
 
+    fn profile(&self) -> Option<&BrowserProfile> {
+        Some(&self.profile)
+    }
+
     async fn stop(&mut self) -> Result<()> {
         self.stop().await
     }
 
     async fn navigate(&mut self, url: &str) -> Result<()> {
-        let page = self.get_page()?;
-        self.navigation_manager.navigate(&page, url).await
+        self.navigate(url).await
+    }
+
+    async fn navigate_with_options(
+        &mut self,
+        url: &str,
+        options: &crate::actor::NavigateOptions,
+    ) -> Result<()> {
+        self.navigate_with_options(url, options.clone()).await
+    }
+
+    async fn reload(
+        &mut self,
+        ignore_cache: bool,
+        wait_until: Option<crate::actor::LoadState>,
+    ) -> Result<()> {
+        self.reload(ignore_cache, wait_until).await
     }
 
     async fn get_current_url(&self) -> Result<String> {
         self.get_current_url().await
     }
 
+    async fn last_navigation(&self) -> Option<NavigationRecord> {
+        self.last_navigation().cloned()
+    }
+
+    async fn follow_client_redirects(&mut self, grace_ms: u64) -> Result<()> {
+        self.follow_client_redirects(grace_ms).await
+    }
+
+    fn take_captured_responses(&self) -> Vec<CapturedResponse> {
+        self.take_captured_responses()
+    }
+
+    fn response_capture_summary(&self) -> Option<String> {
+        self.response_capture_summary()
+    }
+
+    async fn set_emulation(&mut self, settings: EmulationSettings) -> Result<()> {
+        self.set_emulation(settings).await
+    }
+
+    async fn set_extra_headers(&mut self, headers: HashMap<String, String>) -> Result<()> {
+        self.set_extra_headers(headers).await
+    }
+
+    async fn set_determinism(&mut self, determinism: Determinism) -> Result<()> {
+        self.set_determinism(determinism).await
+    }
+
+    async fn emulation(&self) -> Option<EmulationSettings> {
+        Some(self.emulation())
+    }
+
     async fn create_tab(&mut self, url: Option<&str>) -> Result<String> {
-        let client = self.get_cdp_client()?;
-        self.tab_manager.create_tab(&client, url).await
+        self.create_new_tab(url).await
+    }
+
+    async fn create_background_tab(&mut self, url: &str) -> Result<String> {
+        self.create_background_tab(url).await
     }
 
     async fn switch_to_tab(&mut self, target_id: &str) -> Result<()> {
-        let client = self.get_cdp_client()?;
-        self.tab_manager.switch_to_tab(&client, target_id).await
+        self.switch_to_tab(target_id).await
     }
 
     async fn close_tab(&mut self, target_id: &str) -> Result<()> {
@@ -401,8 +1442,7 @@ impl BrowserClient for Browser {
     }
 
     async fn get_tabs(&self) -> Result<Vec<crate::browser::views::TabInfo>> {
-        let client = self.get_cdp_client()?;
-        self.tab_manager.get_tabs(&client).await
+        self.get_tabs(false).await
     }
 
     async fn get_target_id_from_tab_id(&self, tab_id: &str) -> Result<String> {
@@ -415,15 +1455,35 @@ impl BrowserClient for Browser {
         Ok(crate::actor::Page::new(client, session_id))
     }
 
+    fn get_page_for_target(&self, target_id: &str) -> Result<crate::actor::Page> {
+        self.get_page_for_target(target_id)
+    }
+
     async fn take_screenshot(&self, path: Option<&str>, full_page: bool) -> Result<Vec<u8>> {
         let page = self.get_page()?;
         self.screenshot_manager.take_screenshot(&page, path, full_page, None, None).await
     }
 
+    async fn screenshot_to_file(&self, path: &Path, full_page: bool) -> Result<SavedScreenshot> {
+        let options = ScreenshotOptions {
+            full_page,
+            ..Default::default()
+        };
+        Browser::screenshot_to_file(self, path, &options).await
+    }
+
     async fn get_current_page_title(&self) -> Result<String> {
         self.get_current_page_title().await
     }
 
+    async fn checkpoint(&mut self) -> Result<CheckpointId> {
+        self.checkpoint().await
+    }
+
+    async fn restore(&mut self, id: CheckpointId) -> Result<()> {
+        self.restore(id).await
+    }
+
     fn get_cdp_client(&self) -> Result<Arc<CdpClient>> {
         self.get_cdp_client()
     }
@@ -435,4 +1495,51 @@ impl BrowserClient for Browser {
     fn get_current_target_id(&self) -> Result<String> {
         self.get_current_target_id()
     }
+
+    async fn version_info(&self) -> Result<BrowserVersionInfo> {
+        self.version_info().await
+    }
+}
+
+/// Bypass the HTTP cache, or use it again, for requests of one session
+async fn set_session_cache_disabled(
+    client: &CdpClient,
+    session_id: &str,
+    disabled: bool,
+) -> Result<()> {
+    client
+        .send_command_with_session("Network.enable", serde_json::json!({}), Some(session_id))
+        .await?;
+    client
+        .send_command_with_session(
+            "Network.setCacheDisabled",
+            serde_json::json!({ "cacheDisabled": disabled }),
+            Some(session_id),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Whether `url` is in one of the `domains` patterns; an empty list allows every URL
+fn is_domain_allowed(url: &str, domains: &[String]) -> bool {
+    domains.is_empty()
+        || domains
+            .iter()
+            .any(|pattern| crate::utils::match_url_with_domain_pattern(url, pattern))
+}
+
+/// Blocks web documents outside the profile's `allowed_domains`
+struct AllowedDomains(Vec<String>);
+
+impl DocumentRule for AllowedDomains {
+    fn decide(&self, paused: &serde_json::Value) -> Decision {
+        let url = paused["request"]["url"].as_str().unwrap_or_default();
+        // Only requests leaving the browser; data: and blob: documents stay local
+        let is_web = url.starts_with("http://") || url.starts_with("https://");
+        if !is_web || is_domain_allowed(url, &self.0) {
+            return Decision::Continue;
+        }
+        tracing::warn!("Blocked request for {}: domain is not in allowed_domains", url);
+        Decision::Block
+    }
 }
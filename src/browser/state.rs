@@ -0,0 +1,85 @@
+//! Browser state for deciding the next action
+//!
+//! [`build_browser_state`] gathers what the agent looks at before each step:
+//! URL, title, open tabs, the serialized DOM with its selector map and, if
+//! asked for, a screenshot. Code that drives [`Tools`](crate::tools::Tools)
+//! without an [`Agent`](crate::agent::Agent) can call it to act on the same
+//! indices, or serialize the summary for a decision maker in another process.
+
+use crate::browser::views::BrowserStateSummary;
+use crate::error::Result;
+use crate::traits::{BrowserClient, DOMProcessor};
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+
+/// What [`build_browser_state`] includes besides the page itself
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BrowserStateOptions {
+    /// Capture a viewport screenshot as base64-encoded PNG (default: false)
+    #[serde(default)]
+    pub include_screenshot: bool,
+}
+
+impl BrowserStateOptions {
+    /// Options with nothing extra included
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Include a viewport screenshot
+    pub fn with_screenshot(mut self, include: bool) -> Self {
+        self.include_screenshot = include;
+        self
+    }
+}
+
+/// Current state of `browser`, with the page serialized by `dom_processor`
+///
+/// Fails only if the DOM cannot be serialized. A URL, title or tab list the
+/// browser cannot report is left empty, and a failed screenshot is omitted.
+/// Indices in `dom_state` are the ones actions take while the page is unchanged.
+pub async fn build_browser_state(
+    browser: &dyn BrowserClient,
+    dom_processor: &dyn DOMProcessor,
+    options: &BrowserStateOptions,
+) -> Result<BrowserStateSummary> {
+    let dom_state = dom_processor.get_serialized_dom().await?;
+    let (url, title) = match browser.get_session_info().await {
+        Ok(info) => (info.url, info.title),
+        Err(_) => (
+            browser.get_current_url().await.unwrap_or_default(),
+            String::new(),
+        ),
+    };
+    let tabs = browser.get_tabs().await.unwrap_or_default();
+
+    let screenshot = if options.include_screenshot {
+        match browser.take_screenshot(None, false).await {
+            Ok(image) => Some(base64::engine::general_purpose::STANDARD.encode(image)),
+            Err(e) => {
+                tracing::debug!("Leaving the screenshot out of the browser state: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let is_pdf_viewer = url.ends_with(".pdf") || title.contains("PDF");
+    Ok(BrowserStateSummary {
+        dom_state,
+        url,
+        title,
+        tabs,
+        screenshot,
+        page_info: None,
+        pixels_above: 0,
+        pixels_below: 0,
+        browser_errors: vec![],
+        is_pdf_viewer,
+        recent_events: None,
+        pending_network_requests: vec![],
+        pagination_buttons: vec![],
+        closed_popup_messages: vec![],
+    })
+}
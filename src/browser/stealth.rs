@@ -0,0 +1,184 @@
+//! Headless detection mitigations
+//!
+//! Some sites serve different content, or none at all, to browsers they
+//! detect as automated. With [`BrowserProfile::stealth`] set, the browser
+//! hides the most common signals: `navigator.webdriver`, empty
+//! `navigator.languages` and `navigator.plugins`, the software WebGL renderer
+//! of headless Chrome, and the `AutomationControlled` blink feature. Each
+//! mitigation can be turned off in [`StealthOptions`].
+//!
+//! This is best-effort and off by default. It only covers well-known checks;
+//! dedicated bot detection looks at far more than these and will still tell
+//! an automated browser apart. The scripts are added to a tab when it is
+//! created, so a tab opened directly at a URL gets them from its next
+//! document on.
+//!
+//! [`BrowserProfile::stealth`]: crate::browser::BrowserProfile::stealth
+
+use crate::browser::cdp::CdpClient;
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Languages reported by `navigator.languages`
+const LANGUAGES: &str = r#"["en-US", "en"]"#;
+
+/// WebGL vendor and renderer of a typical desktop GPU
+const WEBGL_VENDOR: &str = "Intel Inc.";
+const WEBGL_RENDERER: &str = "Intel Iris OpenGL Engine";
+
+/// Launch flag that stops Chrome from flagging itself as automated
+pub const DISABLE_AUTOMATION_CONTROLLED_ARG: &str = "--disable-blink-features=AutomationControlled";
+
+/// Which headless detection mitigations to apply; all are on by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StealthOptions {
+    /// Make `navigator.webdriver` undefined, as in a browser a person uses
+    pub hide_webdriver: bool,
+    /// Report `en-US` and `en` from `navigator.languages` when it is empty
+    pub languages: bool,
+    /// Report the PDF viewer plugins of desktop Chrome from `navigator.plugins`
+    pub plugins: bool,
+    /// Report a desktop GPU as the WebGL vendor and renderer
+    pub webgl_vendor: bool,
+    /// Launch Chrome with the `AutomationControlled` blink feature disabled
+    pub disable_automation_controlled: bool,
+}
+
+impl Default for StealthOptions {
+    fn default() -> Self {
+        Self {
+            hide_webdriver: true,
+            languages: true,
+            plugins: true,
+            webgl_vendor: true,
+            disable_automation_controlled: true,
+        }
+    }
+}
+
+impl StealthOptions {
+    /// Script applying the enabled page mitigations; `None` if there are none
+    pub fn init_script(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.hide_webdriver {
+            parts.push(
+                "Object.defineProperty(Navigator.prototype, 'webdriver', \
+                 { get: () => undefined, configurable: true });"
+                    .to_string(),
+            );
+        }
+        if self.languages {
+            parts.push(format!(
+                "if (!navigator.languages || navigator.languages.length === 0) {{ \
+                 const languages = Object.freeze({LANGUAGES}); \
+                 Object.defineProperty(Navigator.prototype, 'languages', \
+                 {{ get: () => languages, configurable: true }}); }}"
+            ));
+        }
+        if self.plugins {
+            parts.push(PLUGINS_JS.to_string());
+        }
+        if self.webgl_vendor {
+            parts.push(format!(
+                "for (const context of [window.WebGLRenderingContext, window.WebGL2RenderingContext]) {{ \
+                 if (!context) continue; \
+                 const getParameter = context.prototype.getParameter; \
+                 context.prototype.getParameter = function (parameter) {{ \
+                 if (parameter === 37445) return {WEBGL_VENDOR:?}; \
+                 if (parameter === 37446) return {WEBGL_RENDERER:?}; \
+                 return getParameter.call(this, parameter); }}; }}"
+            ));
+        }
+        if parts.is_empty() {
+            return None;
+        }
+        Some(format!("(() => {{\n{}\n}})();", parts.join("\n")))
+    }
+
+    /// Extra Chrome launch flags
+    pub fn launch_args(&self) -> Vec<String> {
+        if self.disable_automation_controlled {
+            vec![DISABLE_AUTOMATION_CONTROLLED_ARG.to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Reports the PDF viewer plugins of desktop Chrome when there are none
+const PLUGINS_JS: &str = "if (navigator.plugins.length === 0) { \
+const names = ['PDF Viewer', 'Chrome PDF Viewer', 'Chromium PDF Viewer', \
+'Microsoft Edge PDF Viewer', 'WebKit built-in PDF']; \
+const plugins = names.map((name) => Object.create(Plugin.prototype, { \
+name: { value: name }, filename: { value: 'internal-pdf-viewer' }, \
+description: { value: 'Portable Document Format' }, length: { value: 0 } })); \
+const list = Object.create(PluginArray.prototype); \
+plugins.forEach((plugin, i) => { list[i] = plugin; }); \
+Object.defineProperties(list, { length: { value: plugins.length }, \
+item: { value: (i) => plugins[i] || null }, \
+namedItem: { value: (name) => plugins.find((p) => p.name === name) || null } }); \
+Object.defineProperty(Navigator.prototype, 'plugins', { get: () => list, configurable: true }); }";
+
+/// Apply the page mitigations of `options` to the tab of `session_id`
+///
+/// The script runs in every later document of the tab, and once in the
+/// current one.
+pub async fn apply_stealth(
+    client: &CdpClient,
+    session_id: &str,
+    options: &StealthOptions,
+) -> Result<()> {
+    let Some(script) = options.init_script() else {
+        return Ok(());
+    };
+    let session = Some(session_id);
+    client
+        .send_command_with_session(
+            "Page.addScriptToEvaluateOnNewDocument",
+            json!({ "source": script }),
+            session,
+        )
+        .await?;
+    client
+        .send_command_with_session("Runtime.evaluate", json!({ "expression": script }), session)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mitigations_are_toggled_individually() {
+        let all = StealthOptions::default().init_script().unwrap();
+        assert!(all.contains("'webdriver'"));
+        assert!(all.contains("'languages'"));
+        assert!(all.contains("'plugins'"));
+        assert!(all.contains("37445"));
+
+        let only_webdriver = StealthOptions {
+            languages: false,
+            plugins: false,
+            webgl_vendor: false,
+            ..Default::default()
+        };
+        let script = only_webdriver.init_script().unwrap();
+        assert!(script.contains("'webdriver'"));
+        assert!(!script.contains("'plugins'"));
+
+        let none = StealthOptions {
+            hide_webdriver: false,
+            ..only_webdriver
+        };
+        assert_eq!(none.init_script(), None);
+        assert_eq!(none.launch_args(), [DISABLE_AUTOMATION_CONTROLLED_ARG]);
+        let none = StealthOptions {
+            disable_automation_controlled: false,
+            ..none
+        };
+        assert!(none.launch_args().is_empty());
+    }
+}
@@ -2,16 +2,28 @@
 //!
 //! This module handles tab creation, switching, and closing operations.
 
+use crate::actor::Page;
+use crate::actor::page::NAVIGATION_TIMEOUT_MS;
 use crate::browser::cdp::{CdpClient, CdpSession};
+use crate::browser::target_tracker::TargetTracker;
+use crate::browser::views::BrowserContextId;
+use crate::browser::worker_monitor::is_worker_target;
 use crate::error::{BrowsingError, Result};
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
 use tracing::info;
 
 /// Manager for browser tab operations
 pub struct TabManager {
     sessions: HashMap<String, CdpSession>,
     current_target_id: Option<String>,
+    tracker: Arc<Mutex<TargetTracker>>,
+    tracker_task: Option<JoinHandle<()>>,
+    /// Browser context of each tab opened in a non-default context
+    tab_contexts: HashMap<String, BrowserContextId>,
+    /// Contexts created through this manager, which it may dispose
+    created_contexts: HashSet<BrowserContextId>,
 }
 
 impl TabManager {
@@ -20,11 +32,32 @@ impl TabManager {
         Self {
             sessions: HashMap::new(),
             current_target_id: None,
+            tracker: Arc::new(Mutex::new(TargetTracker::new())),
+            tracker_task: None,
+            tab_contexts: HashMap::new(),
+            created_contexts: HashSet::new(),
         }
     }
 
+    /// Start tracking loading state, favicons and openers from CDP events
+    pub async fn start_tracking(&mut self, client: &Arc<CdpClient>) -> Result<()> {
+        if let Some(task) = self.tracker_task.take() {
+            task.abort();
+        }
+        let task = TargetTracker::spawn(Arc::clone(&self.tracker), Arc::clone(client)).await?;
+        self.tracker_task = Some(task);
+        Ok(())
+    }
+
     /// Get all open tabs
-    pub async fn get_tabs(&self, client: &Arc<CdpClient>) -> Result<Vec<crate::browser::views::TabInfo>> {
+    ///
+    /// With `include_all_targets`, background pages, service workers and other
+    /// non-tab targets are included as well, which is mainly useful for debugging.
+    pub async fn get_tabs(
+        &self,
+        client: &Arc<CdpClient>,
+        include_all_targets: bool,
+    ) -> Result<Vec<crate::browser::views::TabInfo>> {
         let targets = client
             .send_command("Target.getTargets", serde_json::json!({}))
             .await?;
@@ -35,20 +68,29 @@ impl TabManager {
             .ok_or_else(|| BrowsingError::Browser("No targetInfos in response".to_string()))?;
 
         let mut tabs = Vec::new();
+        let tracker = self.tracker.lock().unwrap();
 
         for target_info in target_infos {
             let target_type = target_info.get("type").and_then(|v| v.as_str()).unwrap_or("");
 
-            if target_type != "page" && target_type != "tab" {
+            if !include_all_targets && target_type != "page" && target_type != "tab" {
                 continue;
             }
 
-            tabs.push(crate::browser::views::TabInfo {
+            let mut tab = crate::browser::views::TabInfo {
                 url: target_info.get("url").and_then(|v| v.as_str()).unwrap_or("").to_string(),
                 title: target_info.get("title").and_then(|v| v.as_str()).unwrap_or("").to_string(),
                 target_id: target_info.get("targetId").and_then(|v| v.as_str()).unwrap_or("").to_string(),
                 parent_target_id: None,
-            });
+                target_type: target_type.to_string(),
+                opener_tab_id: target_info
+                    .get("openerId")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string()),
+                ..Default::default()
+            };
+            tracker.enrich(&mut tab);
+            tabs.push(tab);
         }
 
         Ok(tabs)
@@ -59,9 +101,46 @@ impl TabManager {
         &mut self,
         client: &Arc<CdpClient>,
         url: Option<&str>,
+    ) -> Result<String> {
+        self.create_tab_in_context(client, url, None).await
+    }
+
+    /// Create a new tab, in `context` if given and the default context otherwise
+    pub async fn create_tab_in_context(
+        &mut self,
+        client: &Arc<CdpClient>,
+        url: Option<&str>,
+        context: Option<&BrowserContextId>,
+    ) -> Result<String> {
+        self.open_tab(client, url, context, false).await
+    }
+
+    /// Create a tab without bringing it to the front
+    ///
+    /// For tabs that are read and closed again while the current tab stays
+    /// in front.
+    pub async fn create_background_tab(
+        &mut self,
+        client: &Arc<CdpClient>,
+        url: &str,
+    ) -> Result<String> {
+        self.open_tab(client, Some(url), None, true).await
+    }
+
+    /// Create a target and attach to it, returning once its first navigation
+    /// has committed
+    async fn open_tab(
+        &mut self,
+        client: &Arc<CdpClient>,
+        url: Option<&str>,
+        context: Option<&BrowserContextId>,
+        background: bool,
     ) -> Result<String> {
         let target_url = url.unwrap_or("about:blank");
-        let params = serde_json::json!({ "url": target_url });
+        let mut params = serde_json::json!({ "url": target_url, "background": background });
+        if let Some(context) = context {
+            params["browserContextId"] = serde_json::json!(context.0);
+        }
 
         let result = client.send_command("Target.createTarget", params).await?;
 
@@ -73,14 +152,24 @@ impl TabManager {
             })?
             .to_string();
 
-        // Wait for target to be ready
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-
         // Create session for the new target
         let session = CdpSession::for_target(client.clone(), target_id.clone(), None).await?;
 
+        // The target starts on about:blank, which already counts as loaded
+        if target_url != "about:blank" {
+            let page = Page::new(client.clone(), session.session_id.clone());
+            if let Err(e) = page.wait_for_first_navigation(NAVIGATION_TIMEOUT_MS).await {
+                let params = serde_json::json!({ "targetId": target_id });
+                let _ = client.send_command("Target.closeTarget", params).await;
+                return Err(e);
+            }
+        }
+
         // Add to sessions map
-        self.sessions.insert(target_id.clone(), session);
+        self.insert_session(target_id.clone(), session);
+        if let Some(context) = context {
+            self.tab_contexts.insert(target_id.clone(), context.clone());
+        }
 
         info!("Created new tab with target_id: {}", target_id);
         Ok(target_id)
@@ -97,41 +186,65 @@ impl TabManager {
             .send_command("Target.getTargets", serde_json::json!({}))
             .await?;
 
-        let target_exists = targets
+        let target_info = targets
             .get("targetInfos")
             .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter().any(|t| {
+            .and_then(|arr| {
+                arr.iter().find(|t| {
                     t.get("targetId")
                         .and_then(|v| v.as_str())
                         .map(|id| id == target_id)
                         .unwrap_or(false)
                 })
-            })
-            .unwrap_or(false);
+            });
 
-        if !target_exists {
+        let Some(target_info) = target_info else {
             return Err(BrowsingError::Browser(format!("Target {} not found", target_id)));
+        };
+        let target_type = target_info.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        // Workers are only observed for diagnostics, never driven
+        if is_worker_target(target_type) {
+            return Err(BrowsingError::Browser(format!(
+                "Target {} is a {} and cannot be used as a tab",
+                target_id, target_type
+            )));
         }
 
         // Create or get session for this target
         let session = CdpSession::for_target(client.clone(), target_id.to_string(), None).await?;
 
         // Update current target
-        self.current_target_id = Some(target_id.to_string());
-        self.sessions.insert(target_id.to_string(), session);
+        self.set_current_target_id(target_id.to_string());
+        self.insert_session(target_id.to_string(), session);
+        if let Some(context) = target_info
+            .get("browserContextId")
+            .and_then(|v| v.as_str())
+            .map(|id| BrowserContextId(id.to_string()))
+            .filter(|id| self.created_contexts.contains(id))
+        {
+            self.tab_contexts.insert(target_id.to_string(), context);
+        }
 
         info!("Switched to tab with target_id: {}", target_id);
         Ok(())
     }
 
     /// Close a tab by target ID
+    ///
+    /// If the tab was the last one in a context created by
+    /// [`TabManager::create_browser_context`], the context is disposed as well.
     pub async fn close_tab(&mut self, client: &Arc<CdpClient>, target_id: &str) -> Result<()> {
         let params = serde_json::json!({ "targetId": target_id });
         client.send_command("Target.closeTarget", params).await?;
 
         // Remove from sessions
         self.sessions.remove(target_id);
+        if let Some(context) = self.tab_contexts.remove(target_id)
+            && !self.tab_contexts.values().any(|c| *c == context)
+            && !self.context_has_targets(client, &context, target_id).await?
+        {
+            self.dispose_browser_context(client, &context).await?;
+        }
 
         // If this was the current target, switch to another one
         if self
@@ -147,6 +260,107 @@ impl TabManager {
         Ok(())
     }
 
+    /// Create an isolated browser context
+    pub async fn create_browser_context(
+        &mut self,
+        client: &Arc<CdpClient>,
+    ) -> Result<BrowserContextId> {
+        let result = client
+            .send_command("Target.createBrowserContext", serde_json::json!({}))
+            .await?;
+        let context = result
+            .get("browserContextId")
+            .and_then(|v| v.as_str())
+            .map(|id| BrowserContextId(id.to_string()))
+            .ok_or_else(|| {
+                BrowsingError::Browser(
+                    "No browserContextId in createBrowserContext response".to_string(),
+                )
+            })?;
+        self.created_contexts.insert(context.clone());
+        info!("Created browser context {}", context);
+        Ok(context)
+    }
+
+    /// Dispose a browser context, closing all of its tabs
+    pub async fn dispose_browser_context(
+        &mut self,
+        client: &Arc<CdpClient>,
+        context: &BrowserContextId,
+    ) -> Result<()> {
+        client
+            .send_command(
+                "Target.disposeBrowserContext",
+                serde_json::json!({ "browserContextId": context.0 }),
+            )
+            .await?;
+        self.created_contexts.remove(context);
+        let closed: Vec<String> = self
+            .tab_contexts
+            .iter()
+            .filter(|(_, c)| *c == context)
+            .map(|(target_id, _)| target_id.clone())
+            .collect();
+        for target_id in closed {
+            self.tab_contexts.remove(&target_id);
+            self.sessions.remove(&target_id);
+            if self.current_target_id.as_deref() == Some(target_id.as_str()) {
+                self.current_target_id = None;
+            }
+        }
+        info!("Disposed browser context {}", context);
+        Ok(())
+    }
+
+    /// All browser contexts except the default one
+    pub async fn list_browser_contexts(
+        &self,
+        client: &Arc<CdpClient>,
+    ) -> Result<Vec<BrowserContextId>> {
+        let result = client
+            .send_command("Target.getBrowserContexts", serde_json::json!({}))
+            .await?;
+        let ids = result
+            .get("browserContextIds")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| {
+                BrowsingError::Browser("No browserContextIds in response".to_string())
+            })?;
+        Ok(ids
+            .iter()
+            .filter_map(|id| id.as_str())
+            .map(|id| BrowserContextId(id.to_string()))
+            .collect())
+    }
+
+    /// Whether any target other than `closed`, including popups the page
+    /// opened, is in `context`
+    async fn context_has_targets(
+        &self,
+        client: &Arc<CdpClient>,
+        context: &BrowserContextId,
+        closed: &str,
+    ) -> Result<bool> {
+        let targets = client
+            .send_command("Target.getTargets", serde_json::json!({}))
+            .await?;
+        Ok(targets
+            .get("targetInfos")
+            .and_then(|v| v.as_array())
+            .is_some_and(|infos| {
+                infos.iter().any(|t| {
+                    t.get("targetId").and_then(|v| v.as_str()) != Some(closed)
+                        && t.get("browserContextId").and_then(|v| v.as_str())
+                            == Some(context.0.as_str())
+                })
+            }))
+    }
+
+    /// Browser context of the current tab, if it is not the default one
+    pub fn current_browser_context(&self) -> Option<&BrowserContextId> {
+        self.tab_contexts.get(self.current_target_id.as_ref()?)
+    }
+
     /// Get the current target ID
     pub fn current_target_id(&self) -> Option<&str> {
         self.current_target_id.as_deref()
@@ -154,6 +368,7 @@ impl TabManager {
 
     /// Set the current target ID
     pub fn set_current_target_id(&mut self, target_id: String) {
+        self.tracker.lock().unwrap().mark_active(&target_id);
         self.current_target_id = Some(target_id);
     }
 
@@ -164,6 +379,10 @@ impl TabManager {
 
     /// Insert a session
     pub fn insert_session(&mut self, target_id: String, session: CdpSession) {
+        self.tracker
+            .lock()
+            .unwrap()
+            .register_session(&session.session_id, &target_id);
         self.sessions.insert(target_id, session);
     }
 
@@ -180,6 +399,14 @@ impl TabManager {
     }
 }
 
+impl Drop for TabManager {
+    fn drop(&mut self) {
+        if let Some(task) = self.tracker_task.take() {
+            task.abort();
+        }
+    }
+}
+
 impl Default for TabManager {
     fn default() -> Self {
         Self::new()
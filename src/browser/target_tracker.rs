@@ -0,0 +1,402 @@
+//! Event-driven tracking of per-tab state
+//!
+//! `Target.getTargets` only reports URL and title. The tracker listens to CDP
+//! target and page lifecycle events to maintain the rest of what [`TabInfo`]
+//! exposes: loading state, favicon, opener and last activity.
+
+use crate::browser::cdp::CdpClient;
+use crate::browser::views::TabInfo;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+/// Resolves the favicon of the page, falling back to `/favicon.ico`
+const FAVICON_JS: &str = r#"(() => {
+    const link = document.querySelector('link[rel~="icon"]');
+    if (link && link.href) return link.href;
+    return location.protocol.startsWith('http') ? location.origin + '/favicon.ico' : null;
+})()"#;
+
+/// State of a single target as observed from events
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TargetActivity {
+    /// Whether the main frame is still loading
+    pub is_loading: bool,
+    /// URL of the page favicon
+    pub favicon_url: Option<String>,
+    /// Target ID of the opener
+    pub opener_tab_id: Option<String>,
+    /// Last time the target was switched to or navigated
+    pub last_active_at: Option<DateTime<Utc>>,
+}
+
+/// Tracks target state from CDP events
+#[derive(Debug, Default)]
+pub struct TargetTracker {
+    targets: HashMap<String, TargetActivity>,
+    /// Session ID to target ID, used to attribute session-scoped page events
+    sessions: HashMap<String, String>,
+}
+
+impl TargetTracker {
+    /// Create an empty tracker
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Associate a CDP session with its target
+    pub fn register_session(&mut self, session_id: &str, target_id: &str) {
+        self.sessions
+            .insert(session_id.to_string(), target_id.to_string());
+    }
+
+    /// Record that the target became the active tab
+    pub fn mark_active(&mut self, target_id: &str) {
+        self.targets
+            .entry(target_id.to_string())
+            .or_default()
+            .last_active_at = Some(Utc::now());
+    }
+
+    /// Record the favicon of a target
+    pub fn set_favicon(&mut self, target_id: &str, favicon_url: Option<String>) {
+        self.targets
+            .entry(target_id.to_string())
+            .or_default()
+            .favicon_url = favicon_url;
+    }
+
+    /// Tracked state of a target
+    pub fn activity(&self, target_id: &str) -> Option<&TargetActivity> {
+        self.targets.get(target_id)
+    }
+
+    /// Session ID attached to a target, if any
+    pub fn session_for(&self, target_id: &str) -> Option<&str> {
+        self.sessions
+            .iter()
+            .find(|(_, t)| t.as_str() == target_id)
+            .map(|(s, _)| s.as_str())
+    }
+
+    /// Copy tracked state into a [`TabInfo`]
+    pub fn enrich(&self, tab: &mut TabInfo) {
+        if let Some(activity) = self.targets.get(&tab.target_id) {
+            tab.is_loading = activity.is_loading;
+            tab.favicon_url = activity.favicon_url.clone();
+            // The tab's own target info may name an opener the tracker missed
+            if tab.opener_tab_id.is_none() {
+                tab.opener_tab_id = activity.opener_tab_id.clone();
+            }
+            tab.last_active_at = activity.last_active_at;
+        }
+    }
+
+    /// Update state from a CDP event
+    ///
+    /// Returns the target ID when its main frame finished loading, so the
+    /// caller can refresh data that is only available after load.
+    pub fn apply_event(&mut self, event: &Value) -> Option<String> {
+        let method = event.get("method")?.as_str()?;
+        let params = event.get("params")?;
+
+        match method {
+            "Target.targetCreated" | "Target.targetInfoChanged" => {
+                let info = params.get("targetInfo")?;
+                let target_id = info.get("targetId")?.as_str()?;
+                let entry = self.targets.entry(target_id.to_string()).or_default();
+                if let Some(opener) = info.get("openerId").and_then(|v| v.as_str()) {
+                    entry.opener_tab_id = Some(opener.to_string());
+                }
+                None
+            }
+            "Target.targetDestroyed" => {
+                let target_id = params.get("targetId")?.as_str()?;
+                self.targets.remove(target_id);
+                self.sessions.retain(|_, t| t != target_id);
+                None
+            }
+            "Target.attachedToTarget" => {
+                let session_id = params.get("sessionId")?.as_str()?;
+                let target_id = params.get("targetInfo")?.get("targetId")?.as_str()?;
+                self.register_session(session_id, target_id);
+                None
+            }
+            "Target.detachedFromTarget" => {
+                let session_id = params.get("sessionId")?.as_str()?;
+                self.sessions.remove(session_id);
+                None
+            }
+            "Page.frameStartedLoading" | "Page.frameStoppedLoading" => {
+                let target_id = self.main_frame_target(event, params.get("frameId"))?;
+                let loading = method == "Page.frameStartedLoading";
+                self.targets
+                    .entry(target_id.clone())
+                    .or_default()
+                    .is_loading = loading;
+                (!loading).then_some(target_id)
+            }
+            "Page.lifecycleEvent" => {
+                if params.get("name")?.as_str()? != "load" {
+                    return None;
+                }
+                let target_id = self.main_frame_target(event, params.get("frameId"))?;
+                self.targets
+                    .entry(target_id.clone())
+                    .or_default()
+                    .is_loading = false;
+                Some(target_id)
+            }
+            "Page.frameNavigated" => {
+                let frame = params.get("frame")?;
+                if frame.get("parentId").is_some() {
+                    return None;
+                }
+                let target_id = self.main_frame_target(event, frame.get("id"))?;
+                let entry = self.targets.entry(target_id).or_default();
+                entry.last_active_at = Some(Utc::now());
+                // The old favicon no longer applies to the new document
+                entry.favicon_url = None;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Target of a session-scoped page event, if the frame is its main frame
+    ///
+    /// Chrome uses the target ID as the ID of a page's main frame.
+    fn main_frame_target(&self, event: &Value, frame_id: Option<&Value>) -> Option<String> {
+        let session_id = event.get("sessionId")?.as_str()?;
+        let target_id = self.sessions.get(session_id)?;
+        (frame_id?.as_str()? == target_id).then(|| target_id.clone())
+    }
+
+    /// Subscribe to target discovery and keep the tracker updated in the background
+    ///
+    /// The task ends when the CDP connection closes.
+    pub async fn spawn(
+        tracker: Arc<Mutex<Self>>,
+        client: Arc<CdpClient>,
+    ) -> crate::error::Result<JoinHandle<()>> {
+        let mut events = client.subscribe_events();
+        client
+            .send_command(
+                "Target.setDiscoverTargets",
+                serde_json::json!({ "discover": true }),
+            )
+            .await?;
+
+        Ok(tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        debug!("Target tracker skipped {} events", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                let loaded = tracker.lock().unwrap().apply_event(&event);
+                let Some(target_id) = loaded else {
+                    continue;
+                };
+                let session_id = tracker
+                    .lock()
+                    .unwrap()
+                    .session_for(&target_id)
+                    .map(str::to_string);
+                let Some(session_id) = session_id else {
+                    continue;
+                };
+
+                let params = serde_json::json!({
+                    "expression": FAVICON_JS,
+                    "returnByValue": true,
+                });
+                if let Ok(result) = client
+                    .send_command_with_session("Runtime.evaluate", params, Some(&session_id))
+                    .await
+                {
+                    let favicon = result["result"]["value"].as_str().map(str::to_string);
+                    tracker.lock().unwrap().set_favicon(&target_id, favicon);
+                }
+            }
+        }))
+    }
+}
+
+/// Watches for page targets created after it was started, such as popups and `target="_blank"` links
+pub struct NewTargetWatcher {
+    events: broadcast::Receiver<Value>,
+    known: HashSet<String>,
+}
+
+impl NewTargetWatcher {
+    /// Start watching; targets that already exist are never reported
+    pub async fn start(client: &CdpClient) -> crate::error::Result<Self> {
+        let events = client.subscribe_events();
+        let targets = client
+            .send_command("Target.getTargets", serde_json::json!({}))
+            .await?;
+        let known = targets["targetInfos"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|info| info["targetId"].as_str().map(str::to_string))
+            .collect();
+        // Discovery replays the existing targets, which are already known
+        client
+            .send_command(
+                "Target.setDiscoverTargets",
+                serde_json::json!({ "discover": true }),
+            )
+            .await?;
+        Ok(Self { events, known })
+    }
+
+    /// Target ID of the first new page, or `None` if none appears within `timeout`
+    pub async fn wait(&mut self, timeout: Duration) -> Option<String> {
+        tokio::time::timeout(timeout, async {
+            loop {
+                let event = match self.events.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                };
+                if event["method"] != "Target.targetCreated" {
+                    continue;
+                }
+                let info = &event["params"]["targetInfo"];
+                if info["type"] != "page" {
+                    continue;
+                }
+                if let Some(target_id) = info["targetId"].as_str()
+                    && self.known.insert(target_id.to_string())
+                {
+                    return Some(target_id.to_string());
+                }
+            }
+        })
+        .await
+        .ok()
+        .flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn attach(tracker: &mut TargetTracker, session_id: &str, target_id: &str) {
+        tracker.apply_event(&json!({
+            "method": "Target.attachedToTarget",
+            "params": {
+                "sessionId": session_id,
+                "targetInfo": {"targetId": target_id, "type": "page"},
+                "waitingForDebugger": false
+            }
+        }));
+    }
+
+    #[test]
+    fn test_loading_state_follows_main_frame_events() {
+        let mut tracker = TargetTracker::new();
+        attach(&mut tracker, "S1", "T1");
+
+        let started = json!({
+            "method": "Page.frameStartedLoading",
+            "params": {"frameId": "T1"},
+            "sessionId": "S1"
+        });
+        assert_eq!(tracker.apply_event(&started), None);
+        assert!(tracker.activity("T1").unwrap().is_loading);
+
+        // Subframe loads don't affect the tab
+        let subframe = json!({
+            "method": "Page.frameStoppedLoading",
+            "params": {"frameId": "F2"},
+            "sessionId": "S1"
+        });
+        assert_eq!(tracker.apply_event(&subframe), None);
+        assert!(tracker.activity("T1").unwrap().is_loading);
+
+        let load = json!({
+            "method": "Page.lifecycleEvent",
+            "params": {"frameId": "T1", "loaderId": "L1", "name": "load", "timestamp": 1.0},
+            "sessionId": "S1"
+        });
+        assert_eq!(tracker.apply_event(&load).as_deref(), Some("T1"));
+        assert!(!tracker.activity("T1").unwrap().is_loading);
+    }
+
+    #[test]
+    fn test_opener_and_navigation_update_tab_info() {
+        let mut tracker = TargetTracker::new();
+        attach(&mut tracker, "S2", "T2");
+        tracker.apply_event(&json!({
+            "method": "Target.targetCreated",
+            "params": {"targetInfo": {"targetId": "T2", "type": "page", "openerId": "T1"}}
+        }));
+        tracker.set_favicon("T2", Some("https://example.com/favicon.ico".to_string()));
+
+        let mut tab = TabInfo {
+            target_id: "T2".to_string(),
+            ..Default::default()
+        };
+        tracker.enrich(&mut tab);
+        assert_eq!(tab.opener_tab_id.as_deref(), Some("T1"));
+        assert_eq!(
+            tab.favicon_url.as_deref(),
+            Some("https://example.com/favicon.ico")
+        );
+        assert!(tab.last_active_at.is_none());
+
+        tracker.apply_event(&json!({
+            "method": "Page.frameNavigated",
+            "params": {"frame": {"id": "T2", "url": "https://example.org/"}},
+            "sessionId": "S2"
+        }));
+        tracker.enrich(&mut tab);
+        assert!(tab.last_active_at.is_some());
+        assert!(tab.favicon_url.is_none());
+    }
+
+    #[test]
+    fn test_enrich_keeps_the_opener_of_the_target_info() {
+        let mut tracker = TargetTracker::new();
+        tracker.mark_active("T2");
+
+        let mut tab = TabInfo {
+            target_id: "T2".to_string(),
+            opener_tab_id: Some("T1".to_string()),
+            ..Default::default()
+        };
+        tracker.enrich(&mut tab);
+        assert_eq!(tab.opener_tab_id.as_deref(), Some("T1"));
+        assert!(tab.last_active_at.is_some());
+    }
+
+    #[test]
+    fn test_destroyed_target_is_forgotten() {
+        let mut tracker = TargetTracker::new();
+        attach(&mut tracker, "S1", "T1");
+        tracker.mark_active("T1");
+        assert_eq!(tracker.session_for("T1"), Some("S1"));
+
+        tracker.apply_event(&json!({
+            "method": "Target.targetDestroyed",
+            "params": {"targetId": "T1"}
+        }));
+        assert!(tracker.activity("T1").is_none());
+        assert!(tracker.session_for("T1").is_none());
+    }
+}
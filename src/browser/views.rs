@@ -1,5 +1,6 @@
 //! Browser view types
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -16,6 +17,141 @@ pub struct TabInfo {
     /// Parent target ID if this is a nested tab
     #[serde(alias = "parent_tab_id")]
     pub parent_target_id: Option<String>,
+    /// CDP target type ("page" for regular tabs)
+    #[serde(default = "default_target_type")]
+    pub target_type: String,
+    /// Whether the main frame is still loading
+    #[serde(default)]
+    pub is_loading: bool,
+    /// URL of the page favicon
+    #[serde(default)]
+    pub favicon_url: Option<String>,
+    /// Target ID of the tab that opened this one
+    #[serde(default)]
+    pub opener_tab_id: Option<String>,
+    /// When the tab was last switched to or navigated
+    #[serde(default)]
+    pub last_active_at: Option<DateTime<Utc>>,
+}
+
+fn default_target_type() -> String {
+    "page".to_string()
+}
+
+impl Default for TabInfo {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            title: String::new(),
+            target_id: String::new(),
+            parent_target_id: None,
+            target_type: default_target_type(),
+            is_loading: false,
+            favicon_url: None,
+            opener_tab_id: None,
+            last_active_at: None,
+        }
+    }
+}
+
+impl TabInfo {
+    /// Short tab ID (last 4 characters of the target ID)
+    pub fn tab_id(&self) -> &str {
+        let start = self.target_id.len().saturating_sub(4);
+        self.target_id.get(start..).unwrap_or(&self.target_id)
+    }
+
+    /// Compact one-line description for tab summaries
+    ///
+    /// For example: `#1a2b Example Domain (https://example.com) [loading, opened by #9f8e]`
+    pub fn summary(&self) -> String {
+        let mut flags = Vec::new();
+        if self.target_type != "page" {
+            flags.push(self.target_type.clone());
+        }
+        if self.is_loading {
+            flags.push("loading".to_string());
+        }
+        if let Some(ref opener) = self.opener_tab_id {
+            let start = opener.len().saturating_sub(4);
+            flags.push(format!("opened by #{}", opener.get(start..).unwrap_or(opener)));
+        }
+
+        let title = if self.title.is_empty() {
+            "(untitled)"
+        } else {
+            self.title.as_str()
+        };
+        let mut line = format!("#{} {} ({})", self.tab_id(), title, self.url);
+        if !flags.is_empty() {
+            line.push_str(&format!(" [{}]", flags.join(", ")));
+        }
+        line
+    }
+}
+
+/// Snapshot of a tab captured for session handoff
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TabSnapshot {
+    /// Target ID of the tab
+    pub target_id: String,
+    /// URL of the tab
+    pub url: String,
+    /// Title of the tab
+    pub title: String,
+}
+
+impl From<TabInfo> for TabSnapshot {
+    fn from(tab: TabInfo) -> Self {
+        Self {
+            target_id: tab.target_id,
+            url: tab.url,
+            title: tab.title,
+        }
+    }
+}
+
+/// Identifier of an isolated browser context (an incognito-like profile)
+///
+/// Tabs in different contexts share no cookies, storage or cache.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct BrowserContextId(pub String);
+
+impl std::fmt::Display for BrowserContextId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Everything needed to attach another process to a running browser
+///
+/// Produced by [`Browser::export_session`](crate::browser::Browser::export_session)
+/// and consumed by [`Browser::attach_to_session`](crate::browser::Browser::attach_to_session).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BrowserSession {
+    /// WebSocket URL of the CDP endpoint
+    pub cdp_ws_url: String,
+    /// Target ID of the tab that was active when exported
+    pub active_target_id: String,
+    /// CDP session ID of the exporting process (informational)
+    pub session_id: String,
+    /// Cookies as returned by CDP, usable with `Network.setCookies`
+    pub cookies: Vec<serde_json::Value>,
+    /// Open tabs at export time
+    pub tabs: Vec<TabSnapshot>,
+}
+
+impl BrowserSession {
+    /// Serialize the session as JSON
+    pub fn to_json(&self) -> crate::error::Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Parse a session from JSON
+    pub fn from_json(json: &str) -> crate::error::Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
 }
 
 /// Comprehensive page size and scroll information
@@ -98,6 +234,60 @@ impl SessionInfo {
     }
 }
 
+/// What to do when an action opens a new window or tab
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NewWindowHandling {
+    /// Leave the new window open in the background and stay on the current tab
+    #[default]
+    Ignore,
+    /// Switch to the new window
+    AutoSwitch,
+    /// Close the new window and stay on the current tab
+    AutoClose,
+}
+
+/// Browser and protocol versions, for bug reports
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BrowserVersionInfo {
+    /// Browser name and version (e.g. `HeadlessChrome/120.0.6099.71`)
+    pub product: String,
+    /// DevTools protocol version (e.g. `1.3`)
+    pub protocol_version: String,
+    /// Browser revision
+    pub revision: String,
+    /// Default user agent
+    pub user_agent: String,
+    /// V8 version
+    pub js_version: String,
+    /// Whether the browser was launched by this process rather than connected to
+    pub launched: bool,
+    /// Path of the launched browser executable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub executable_path: Option<String>,
+    /// Process ID of the launched browser
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    /// Whether the browser profile requested headless mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub headless: Option<bool>,
+}
+
+impl BrowserVersionInfo {
+    /// Build from a `Browser.getVersion` result, without launcher details
+    pub fn from_cdp(result: &serde_json::Value) -> Self {
+        let field = |name: &str| result[name].as_str().unwrap_or_default().to_string();
+        Self {
+            product: field("product"),
+            protocol_version: field("protocolVersion"),
+            revision: field("revision"),
+            user_agent: field("userAgent"),
+            js_version: field("jsVersion"),
+            ..Default::default()
+        }
+    }
+}
+
 /// The summary of the browser's current state designed for an LLM to process
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BrowserStateSummary {
@@ -145,6 +335,14 @@ pub struct BrowserStateHistory {
     pub interacted_element: Vec<Option<crate::dom::views::DOMInteractedElement>>,
     /// Path to screenshot file
     pub screenshot_path: Option<String>,
+    /// Last navigation of the tab, when it was redirected or declares a
+    /// different canonical URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub navigation: Option<crate::browser::NavigationRecord>,
+    /// Color scheme, vision deficiency and Save-Data emulated in the tab,
+    /// when any is
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emulation: Option<crate::actor::EmulationSettings>,
 }
 
 impl BrowserStateHistory {
@@ -0,0 +1,358 @@
+//! Debug logging of CDP commands
+//!
+//! When enabled, every command sent by [`CdpClient`](crate::browser::cdp::CdpClient)
+//! is logged at debug level under the `browsing::cdp::wire` target with its
+//! method, redacted and truncated params, duration and result size. Commands
+//! are counted per method, and the sizes of received messages recorded per
+//! method, whether or not logging is enabled.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::debug;
+
+/// Replacement for redacted values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Configuration for the CDP wire log
+#[derive(Debug, Clone)]
+pub struct WireLogConfig {
+    /// Whether commands are logged
+    pub enabled: bool,
+    /// Fraction of high-frequency commands to log (0.0 to 1.0)
+    pub sample_rate: f64,
+    /// Param names whose values are replaced with [`REDACTED`], at any depth
+    /// (case-insensitive)
+    pub redact_params: Vec<String>,
+    /// Maximum length of logged string values
+    pub max_value_len: usize,
+    /// Methods subject to `sample_rate`; all others are always logged
+    pub sampled_methods: Vec<String>,
+}
+
+impl Default for WireLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sample_rate: 0.1,
+            redact_params: [
+                // Typed text and form values
+                "text",
+                "value",
+                "password",
+                // Cookies and auth headers
+                "cookie",
+                "cookies",
+                "authorization",
+                "proxy-authorization",
+            ]
+            .map(String::from)
+            .to_vec(),
+            max_value_len: 200,
+            sampled_methods: [
+                "Input.dispatchMouseEvent",
+                "Input.dispatchKeyEvent",
+                "Input.dispatchTouchEvent",
+                "Runtime.evaluate",
+                "DOM.describeNode",
+            ]
+            .map(String::from)
+            .to_vec(),
+        }
+    }
+}
+
+impl WireLogConfig {
+    /// Default configuration with logging enabled
+    pub fn enabled() -> Self {
+        Self {
+            enabled: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// Upper bounds of the [`MessageSizes::histogram`] buckets, in bytes
+pub const MESSAGE_SIZE_BUCKETS: [usize; 5] = [1 << 10, 16 << 10, 256 << 10, 4 << 20, 64 << 20];
+
+/// Number of commands sent and sizes of messages received, per method
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CdpStats {
+    /// Total number of commands
+    pub total: u64,
+    /// Commands sent per method
+    pub by_method: HashMap<String, u64>,
+    /// Commands that returned a CDP error, per method
+    pub errors_by_method: HashMap<String, u64>,
+    /// Sizes of received responses (under the command's method) and events
+    #[serde(default)]
+    pub incoming_by_method: HashMap<String, MessageSizes>,
+}
+
+/// Sizes of the messages received for one method
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MessageSizes {
+    /// Number of messages
+    pub count: u64,
+    /// Sum of their sizes, in bytes
+    pub total_bytes: u64,
+    /// Size of the largest, in bytes
+    pub max_bytes: u64,
+    /// Messages over the size limit, truncated or rejected
+    pub oversized: u64,
+    /// Message counts per size bucket: up to each of [`MESSAGE_SIZE_BUCKETS`],
+    /// then larger
+    pub histogram: [u64; MESSAGE_SIZE_BUCKETS.len() + 1],
+}
+
+impl MessageSizes {
+    fn record(&mut self, bytes: usize, oversized: bool) {
+        self.count += 1;
+        self.total_bytes += bytes as u64;
+        self.max_bytes = self.max_bytes.max(bytes as u64);
+        self.oversized += u64::from(oversized);
+        let bucket = MESSAGE_SIZE_BUCKETS
+            .iter()
+            .position(|bound| bytes <= *bound)
+            .unwrap_or(MESSAGE_SIZE_BUCKETS.len());
+        self.histogram[bucket] += 1;
+    }
+}
+
+/// Wire log state shared by a client
+#[derive(Debug, Default)]
+pub(crate) struct WireLog {
+    config: WireLogConfig,
+    stats: Mutex<CdpStats>,
+}
+
+impl WireLog {
+    pub(crate) fn new(config: WireLogConfig) -> Self {
+        Self {
+            config,
+            stats: Mutex::new(CdpStats::default()),
+        }
+    }
+
+    /// Count a command and decide whether it should be logged
+    pub(crate) fn record(&self, method: &str) -> bool {
+        let mut stats = self.stats.lock().unwrap();
+        stats.total += 1;
+        let count = stats.by_method.entry(method.to_string()).or_default();
+        *count += 1;
+
+        if !self.config.enabled {
+            return false;
+        }
+        if !self.config.sampled_methods.iter().any(|m| m == method) {
+            return true;
+        }
+        // Deterministic sampling: log the 1st, (n+1)th, (2n+1)th... call
+        let rate = self.config.sample_rate.clamp(0.0, 1.0);
+        if rate == 0.0 {
+            return false;
+        }
+        let every = (1.0 / rate).round().max(1.0) as u64;
+        (*count - 1).is_multiple_of(every)
+    }
+
+    /// Count a command that returned an error
+    pub(crate) fn record_error(&self, method: &str) {
+        let mut stats = self.stats.lock().unwrap();
+        *stats
+            .errors_by_method
+            .entry(method.to_string())
+            .or_default() += 1;
+    }
+
+    /// Record the size of a received message
+    pub(crate) fn record_incoming(&self, method: &str, bytes: usize, oversized: bool) {
+        let mut stats = self.stats.lock().unwrap();
+        stats
+            .incoming_by_method
+            .entry(method.to_string())
+            .or_default()
+            .record(bytes, oversized);
+    }
+
+    pub(crate) fn stats(&self) -> CdpStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Params with sensitive values redacted and long strings truncated
+    pub(crate) fn sanitize(&self, params: &Value) -> Value {
+        match params {
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(key, value)| {
+                        let redact = self
+                            .config
+                            .redact_params
+                            .iter()
+                            .any(|r| r.eq_ignore_ascii_case(key));
+                        let value = if redact && !value.is_null() {
+                            Value::String(REDACTED.to_string())
+                        } else {
+                            self.sanitize(value)
+                        };
+                        (key.clone(), value)
+                    })
+                    .collect(),
+            ),
+            Value::Array(items) => Value::Array(items.iter().map(|v| self.sanitize(v)).collect()),
+            Value::String(s) => Value::String(self.truncate(s)),
+            other => other.clone(),
+        }
+    }
+
+    fn truncate(&self, s: &str) -> String {
+        let len = s.chars().count();
+        if len <= self.config.max_value_len {
+            return s.to_string();
+        }
+        let kept: String = s.chars().take(self.config.max_value_len).collect();
+        format!("{kept}...(+{} chars)", len - self.config.max_value_len)
+    }
+
+    /// Log a completed command
+    pub(crate) fn log(
+        &self,
+        method: &str,
+        params: &Value,
+        session_id: Option<&str>,
+        duration: Duration,
+        result: &Result<Value, String>,
+    ) {
+        let params = self.sanitize(params).to_string();
+        let duration_ms = duration.as_secs_f64() * 1000.0;
+        match result {
+            Ok(value) => debug!(
+                target: "browsing::cdp::wire",
+                method,
+                session_id,
+                params = %params,
+                duration_ms,
+                result_bytes = value.to_string().len(),
+                "CDP command"
+            ),
+            Err(error) => debug!(
+                target: "browsing::cdp::wire",
+                method,
+                session_id,
+                params = %params,
+                duration_ms,
+                error = %error,
+                "CDP command failed"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_redacts_sensitive_params() {
+        let log = WireLog::new(WireLogConfig::enabled());
+
+        let typed = log.sanitize(&json!({"text": "hunter2"}));
+        assert_eq!(typed, json!({"text": REDACTED}));
+
+        let cookies = log.sanitize(&json!({
+            "cookies": [{"name": "sid", "value": "abc123", "domain": "example.com"}]
+        }));
+        assert_eq!(cookies, json!({"cookies": REDACTED}));
+
+        let cookie =
+            log.sanitize(&json!({"name": "sid", "value": "abc123", "domain": "example.com"}));
+        assert_eq!(cookie["value"], REDACTED);
+        assert_eq!(cookie["domain"], "example.com");
+
+        let headers = log.sanitize(&json!({
+            "headers": {"Authorization": "Bearer secret-token", "Accept": "text/html"}
+        }));
+        assert_eq!(headers["headers"]["Authorization"], REDACTED);
+        assert_eq!(headers["headers"]["Accept"], "text/html");
+
+        // Form values passed as call arguments are nested in arrays
+        let call = log.sanitize(&json!({
+            "functionDeclaration": "function(v) {}",
+            "arguments": [{"value": "4111 1111 1111 1111"}]
+        }));
+        assert!(!call.to_string().contains("4111"));
+    }
+
+    #[test]
+    fn test_custom_redaction_list() {
+        let log = WireLog::new(WireLogConfig {
+            redact_params: vec!["expression".to_string()],
+            ..WireLogConfig::enabled()
+        });
+        let params = log.sanitize(&json!({"expression": "secret()", "text": "visible"}));
+        assert_eq!(params, json!({"expression": REDACTED, "text": "visible"}));
+    }
+
+    #[test]
+    fn test_truncates_long_values() {
+        let log = WireLog::new(WireLogConfig {
+            max_value_len: 5,
+            ..WireLogConfig::enabled()
+        });
+        let params = log.sanitize(&json!({"url": "https://example.com"}));
+        assert_eq!(params["url"], "https...(+14 chars)");
+    }
+
+    #[test]
+    fn test_sampling_does_not_affect_counts() {
+        let log = WireLog::new(WireLogConfig {
+            sample_rate: 0.1,
+            ..WireLogConfig::enabled()
+        });
+
+        let logged = (0..100)
+            .filter(|_| log.record("Input.dispatchMouseEvent"))
+            .count();
+        assert_eq!(logged, 10);
+        assert!((0..5).all(|_| log.record("Page.navigate")));
+
+        let stats = log.stats();
+        assert_eq!(stats.total, 105);
+        assert_eq!(stats.by_method["Input.dispatchMouseEvent"], 100);
+        assert_eq!(stats.by_method["Page.navigate"], 5);
+    }
+
+    #[test]
+    fn test_disabled_log_still_counts() {
+        let log = WireLog::new(WireLogConfig::default());
+        assert!(!log.record("Page.navigate"));
+        log.record_error("Page.navigate");
+
+        let stats = log.stats();
+        assert_eq!(stats.by_method["Page.navigate"], 1);
+        assert_eq!(stats.errors_by_method["Page.navigate"], 1);
+    }
+
+    #[test]
+    fn test_incoming_size_histogram() {
+        let log = WireLog::new(WireLogConfig::default());
+        log.record_incoming("DOM.getDocument", 100, false);
+        log.record_incoming("DOM.getDocument", 1 << 10, false);
+        log.record_incoming("DOM.getDocument", 5 << 20, false);
+        log.record_incoming("Runtime.consoleAPICalled", 100 << 20, true);
+
+        let stats = log.stats();
+        let document = &stats.incoming_by_method["DOM.getDocument"];
+        assert_eq!(document.count, 3);
+        assert_eq!(document.total_bytes, 100 + (1 << 10) + (5 << 20));
+        assert_eq!(document.max_bytes, 5 << 20);
+        assert_eq!(document.oversized, 0);
+        assert_eq!(document.histogram, [2, 0, 0, 0, 1, 0]);
+        let console = &stats.incoming_by_method["Runtime.consoleAPICalled"];
+        assert_eq!(console.histogram, [0, 0, 0, 0, 0, 1]);
+        assert_eq!(console.oversized, 1);
+    }
+}
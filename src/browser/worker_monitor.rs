@@ -0,0 +1,501 @@
+//! Read-only diagnostics for worker targets
+//!
+//! Service workers and shared workers run outside any page, so their console
+//! output and the requests they make (e.g. from `fetch` event handlers) are
+//! invisible to page sessions. When [`BrowserProfile::attach_workers`] is set,
+//! the monitor attaches to every worker target and records both.
+//!
+//! Worker sessions are only used to enable the `Runtime` and `Network`
+//! domains. They are never registered with the tab manager, so no action can
+//! be routed to a worker.
+//!
+//! [`BrowserProfile::attach_workers`]: crate::browser::BrowserProfile::attach_workers
+
+use crate::browser::cdp::CdpClient;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+/// Target types the monitor attaches to
+pub const WORKER_TARGET_TYPES: &[&str] = &["service_worker", "shared_worker"];
+
+/// Number of console messages kept before the oldest are dropped
+const MAX_CONSOLE_MESSAGES: usize = 1000;
+
+/// Number of requests kept before the oldest are dropped
+const MAX_REQUESTS: usize = 1000;
+
+/// Whether a CDP target type is a worker the monitor attaches to
+pub fn is_worker_target(target_type: &str) -> bool {
+    WORKER_TARGET_TYPES.contains(&target_type)
+}
+
+/// A console message logged by a worker
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkerConsoleMessage {
+    /// Target ID of the worker
+    pub target_id: String,
+    /// Worker type (`service_worker` or `shared_worker`)
+    pub target_type: String,
+    /// Worker script URL
+    pub worker_url: String,
+    /// Console API type (`log`, `warning`, `error`, ...), or `error` for uncaught exceptions
+    pub level: String,
+    /// Message text, with arguments separated by spaces
+    pub text: String,
+    /// When the message was received
+    pub timestamp: DateTime<Utc>,
+}
+
+/// A network request made by a worker
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkerRequest {
+    /// Target ID of the worker
+    pub target_id: String,
+    /// Worker type (`service_worker` or `shared_worker`)
+    pub target_type: String,
+    /// CDP request ID
+    pub request_id: String,
+    /// Requested URL
+    pub url: String,
+    /// HTTP method
+    pub method: String,
+    /// Resource type reported by the browser
+    pub resource_type: Option<String>,
+    /// HTTP status, once a response was received
+    pub status: Option<u16>,
+    /// Error text if the request failed
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct WorkerTarget {
+    target_id: String,
+    target_type: String,
+    url: String,
+}
+
+/// Follow-up command for the monitor task
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorkerCommand {
+    /// Attach to a newly discovered worker target
+    Attach(String),
+    /// Enable console and network events on a worker session
+    Enable(String),
+}
+
+/// Console messages and requests captured from worker targets
+#[derive(Debug, Default)]
+pub struct WorkerMonitor {
+    /// Worker session ID to worker
+    sessions: HashMap<String, WorkerTarget>,
+    /// Targets an attach was requested for
+    attaching: HashSet<String>,
+    console: VecDeque<WorkerConsoleMessage>,
+    requests: VecDeque<WorkerRequest>,
+}
+
+impl WorkerMonitor {
+    /// Create an empty monitor
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Captured console messages, oldest first
+    pub fn console_messages(&self) -> Vec<WorkerConsoleMessage> {
+        self.console.iter().cloned().collect()
+    }
+
+    /// Captured requests, oldest first
+    pub fn requests(&self) -> Vec<WorkerRequest> {
+        self.requests.iter().cloned().collect()
+    }
+
+    /// Whether a CDP session belongs to a worker
+    pub fn is_worker_session(&self, session_id: &str) -> bool {
+        self.sessions.contains_key(session_id)
+    }
+
+    /// Request an attach to a worker target, unless one was already requested
+    fn attach(&mut self, target_info: &Value) -> Option<WorkerCommand> {
+        let target_type = target_info.get("type")?.as_str()?;
+        if !is_worker_target(target_type) {
+            return None;
+        }
+        let target_id = target_info.get("targetId")?.as_str()?;
+        self.attaching
+            .insert(target_id.to_string())
+            .then(|| WorkerCommand::Attach(target_id.to_string()))
+    }
+
+    /// Update state from a CDP event
+    ///
+    /// Returns the command the caller should send next, if any.
+    pub fn apply_event(&mut self, event: &Value) -> Option<WorkerCommand> {
+        let method = event.get("method")?.as_str()?;
+        let params = event.get("params")?;
+
+        match method {
+            "Target.targetCreated" => self.attach(params.get("targetInfo")?),
+            "Target.attachedToTarget" => {
+                let info = params.get("targetInfo")?;
+                let target_type = info.get("type")?.as_str()?;
+                if !is_worker_target(target_type) {
+                    return None;
+                }
+                let session_id = params.get("sessionId")?.as_str()?;
+                let target_id = info.get("targetId")?.as_str()?;
+                self.attaching.insert(target_id.to_string());
+                self.sessions.insert(
+                    session_id.to_string(),
+                    WorkerTarget {
+                        target_id: target_id.to_string(),
+                        target_type: target_type.to_string(),
+                        url: info
+                            .get("url")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or_default()
+                            .to_string(),
+                    },
+                );
+                Some(WorkerCommand::Enable(session_id.to_string()))
+            }
+            "Target.targetDestroyed" => {
+                let target_id = params.get("targetId")?.as_str()?;
+                self.attaching.remove(target_id);
+                self.sessions.retain(|_, w| w.target_id != target_id);
+                None
+            }
+            "Target.detachedFromTarget" => {
+                let session_id = params.get("sessionId")?.as_str()?;
+                if let Some(worker) = self.sessions.remove(session_id) {
+                    self.attaching.remove(&worker.target_id);
+                }
+                None
+            }
+            "Runtime.consoleAPICalled" => {
+                let worker = self.worker(event)?;
+                let text = params
+                    .get("args")
+                    .and_then(|v| v.as_array())
+                    .map(|args| args.iter().map(remote_object_text).collect::<Vec<_>>())
+                    .unwrap_or_default()
+                    .join(" ");
+                let level = params
+                    .get("type")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("log")
+                    .to_string();
+                self.push_console(worker, level, text);
+                None
+            }
+            "Runtime.exceptionThrown" => {
+                let worker = self.worker(event)?;
+                let details = params.get("exceptionDetails")?;
+                let text = details
+                    .get("exception")
+                    .and_then(|e| e.get("description"))
+                    .or_else(|| details.get("text"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Uncaught exception")
+                    .to_string();
+                self.push_console(worker, "error".to_string(), text);
+                None
+            }
+            "Network.requestWillBeSent" => {
+                let worker = self.worker(event)?;
+                let request = params.get("request")?;
+                if self.requests.len() == MAX_REQUESTS {
+                    self.requests.pop_front();
+                }
+                self.requests.push_back(WorkerRequest {
+                    target_id: worker.target_id,
+                    target_type: worker.target_type,
+                    request_id: params.get("requestId")?.as_str()?.to_string(),
+                    url: request.get("url")?.as_str()?.to_string(),
+                    method: request
+                        .get("method")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("GET")
+                        .to_string(),
+                    resource_type: params
+                        .get("type")
+                        .and_then(|v| v.as_str())
+                        .map(str::to_string),
+                    status: None,
+                    error: None,
+                });
+                None
+            }
+            "Network.responseReceived" => {
+                let status = params.get("response")?.get("status")?.as_u64()?;
+                let request = self.request_mut(event)?;
+                request.status = u16::try_from(status).ok();
+                None
+            }
+            "Network.loadingFailed" => {
+                let error = params
+                    .get("errorText")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Failed")
+                    .to_string();
+                self.request_mut(event)?.error = Some(error);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Worker that sent a session-scoped event
+    fn worker(&self, event: &Value) -> Option<WorkerTarget> {
+        let session_id = event.get("sessionId")?.as_str()?;
+        self.sessions.get(session_id).cloned()
+    }
+
+    fn request_mut(&mut self, event: &Value) -> Option<&mut WorkerRequest> {
+        let worker = self.worker(event)?;
+        let request_id = event.get("params")?.get("requestId")?.as_str()?;
+        self.requests
+            .iter_mut()
+            .rev()
+            .find(|r| r.request_id == request_id && r.target_id == worker.target_id)
+    }
+
+    fn push_console(&mut self, worker: WorkerTarget, level: String, text: String) {
+        if self.console.len() == MAX_CONSOLE_MESSAGES {
+            self.console.pop_front();
+        }
+        self.console.push_back(WorkerConsoleMessage {
+            target_id: worker.target_id,
+            target_type: worker.target_type,
+            worker_url: worker.url,
+            level,
+            text,
+            timestamp: Utc::now(),
+        });
+    }
+
+    /// Attach to existing and future workers and record their events in the background
+    ///
+    /// Relies on target discovery being enabled (see
+    /// [`TargetTracker::spawn`](crate::browser::TargetTracker::spawn)). The task
+    /// ends when the CDP connection closes.
+    pub async fn spawn(
+        monitor: Arc<Mutex<Self>>,
+        client: Arc<CdpClient>,
+    ) -> crate::error::Result<JoinHandle<()>> {
+        let mut events = client.subscribe_events();
+        let targets = client
+            .send_command("Target.getTargets", serde_json::json!({}))
+            .await?;
+        let existing: Vec<WorkerCommand> = {
+            let mut monitor = monitor.lock().unwrap();
+            targets["targetInfos"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|info| monitor.attach(info))
+                .collect()
+        };
+
+        Ok(tokio::spawn(async move {
+            for command in existing {
+                run_command(&client, command).await;
+            }
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        debug!("Worker monitor skipped {} events", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                let command = monitor.lock().unwrap().apply_event(&event);
+                if let Some(command) = command {
+                    run_command(&client, command).await;
+                }
+            }
+        }))
+    }
+}
+
+async fn run_command(client: &CdpClient, command: WorkerCommand) {
+    match command {
+        WorkerCommand::Attach(target_id) => {
+            let params = serde_json::json!({ "targetId": target_id, "flatten": true });
+            if let Err(e) = client.send_command("Target.attachToTarget", params).await {
+                debug!("Failed to attach to worker {}: {}", target_id, e);
+            }
+        }
+        WorkerCommand::Enable(session_id) => {
+            for method in ["Runtime.enable", "Network.enable"] {
+                if let Err(e) = client
+                    .send_command_with_session(method, serde_json::json!({}), Some(&session_id))
+                    .await
+                {
+                    debug!("{} failed for worker session {}: {}", method, session_id, e);
+                }
+            }
+        }
+    }
+}
+
+/// Text of a console argument, like DevTools prints it
+fn remote_object_text(arg: &Value) -> String {
+    match arg.get("value") {
+        Some(Value::String(s)) => s.clone(),
+        Some(value) if !value.is_null() => value.to_string(),
+        _ => arg
+            .get("unserializableValue")
+            .or_else(|| arg.get("description"))
+            .and_then(|v| v.as_str())
+            .unwrap_or_else(|| arg.get("type").and_then(|v| v.as_str()).unwrap_or(""))
+            .to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn attached(
+        monitor: &mut WorkerMonitor,
+        session_id: &str,
+        target_id: &str,
+        kind: &str,
+    ) -> Option<WorkerCommand> {
+        monitor.apply_event(&json!({
+            "method": "Target.attachedToTarget",
+            "params": {
+                "sessionId": session_id,
+                "targetInfo": {"targetId": target_id, "type": kind, "url": "https://example.com/sw.js"},
+                "waitingForDebugger": false
+            }
+        }))
+    }
+
+    #[test]
+    fn test_attaches_only_to_workers_once() {
+        let mut monitor = WorkerMonitor::new();
+        let created = |id: &str, kind: &str| {
+            json!({
+                "method": "Target.targetCreated",
+                "params": {"targetInfo": {"targetId": id, "type": kind, "url": ""}}
+            })
+        };
+
+        assert_eq!(monitor.apply_event(&created("P1", "page")), None);
+        assert_eq!(
+            monitor.apply_event(&created("W1", "service_worker")),
+            Some(WorkerCommand::Attach("W1".to_string()))
+        );
+        assert_eq!(monitor.apply_event(&created("W1", "service_worker")), None);
+        assert_eq!(
+            monitor.apply_event(&created("W2", "shared_worker")),
+            Some(WorkerCommand::Attach("W2".to_string()))
+        );
+
+        // Page sessions are left to the tab manager
+        assert_eq!(attached(&mut monitor, "S1", "P1", "page"), None);
+        assert!(!monitor.is_worker_session("S1"));
+        assert_eq!(
+            attached(&mut monitor, "S2", "W1", "service_worker"),
+            Some(WorkerCommand::Enable("S2".to_string()))
+        );
+        assert!(monitor.is_worker_session("S2"));
+    }
+
+    #[test]
+    fn test_captures_worker_console_only() {
+        let mut monitor = WorkerMonitor::new();
+        attached(&mut monitor, "SW", "W1", "service_worker");
+
+        let console = |session_id: &str| {
+            json!({
+                "method": "Runtime.consoleAPICalled",
+                "params": {
+                    "type": "log",
+                    "args": [
+                        {"type": "string", "value": "cache ready"},
+                        {"type": "number", "value": 3},
+                        {"type": "object", "description": "Object"}
+                    ],
+                    "executionContextId": 1,
+                    "timestamp": 1.0
+                },
+                "sessionId": session_id
+            })
+        };
+        monitor.apply_event(&console("SW"));
+        monitor.apply_event(&console("PAGE"));
+        monitor.apply_event(&json!({
+            "method": "Runtime.exceptionThrown",
+            "params": {
+                "timestamp": 2.0,
+                "exceptionDetails": {"text": "Uncaught", "exception": {"description": "TypeError: x is undefined"}}
+            },
+            "sessionId": "SW"
+        }));
+
+        let messages = monitor.console_messages();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].text, "cache ready 3 Object");
+        assert_eq!(messages[0].level, "log");
+        assert_eq!(messages[0].target_type, "service_worker");
+        assert_eq!(messages[0].worker_url, "https://example.com/sw.js");
+        assert_eq!(messages[1].level, "error");
+        assert_eq!(messages[1].text, "TypeError: x is undefined");
+    }
+
+    #[test]
+    fn test_captures_worker_requests() {
+        let mut monitor = WorkerMonitor::new();
+        attached(&mut monitor, "SW", "W1", "service_worker");
+
+        monitor.apply_event(&json!({
+            "method": "Network.requestWillBeSent",
+            "params": {"requestId": "R1", "request": {"url": "https://example.com/api", "method": "POST"}, "type": "Fetch"},
+            "sessionId": "SW"
+        }));
+        monitor.apply_event(&json!({
+            "method": "Network.requestWillBeSent",
+            "params": {"requestId": "R2", "request": {"url": "https://example.com/offline", "method": "GET"}},
+            "sessionId": "SW"
+        }));
+        monitor.apply_event(&json!({
+            "method": "Network.responseReceived",
+            "params": {"requestId": "R1", "response": {"status": 201}},
+            "sessionId": "SW"
+        }));
+        monitor.apply_event(&json!({
+            "method": "Network.loadingFailed",
+            "params": {"requestId": "R2", "errorText": "net::ERR_INTERNET_DISCONNECTED"},
+            "sessionId": "SW"
+        }));
+
+        let requests = monitor.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].status, Some(201));
+        assert_eq!(requests[0].resource_type.as_deref(), Some("Fetch"));
+        assert_eq!(
+            requests[1].error.as_deref(),
+            Some("net::ERR_INTERNET_DISCONNECTED")
+        );
+
+        // Events stop being attributed once the worker is gone
+        monitor.apply_event(&json!({
+            "method": "Target.targetDestroyed",
+            "params": {"targetId": "W1"}
+        }));
+        assert!(!monitor.is_worker_session("SW"));
+    }
+}
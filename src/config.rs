@@ -1,12 +1,23 @@
 //! Configuration management for browsing-rs
+//!
+//! Configuration is assembled in layers with [`ConfigBuilder`]: built-in
+//! defaults, then a JSON file, then environment variables, then command-line
+//! arguments. Each layer only overrides the values it sets.
 
+use crate::agent::credentials::{CredentialsProvider, EnvCredentialsProvider};
 use crate::browser::profile::BrowserProfile;
+use crate::error::{BrowsingError, Result};
+use crate::tools::{CapabilityProfile, EvaluatePolicy};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::path::{Path, PathBuf};
-use tracing::warn;
+use std::sync::Arc;
+use tracing::{info, warn};
 
 /// Configuration for LLM
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LlmConfig {
     /// API key for the LLM service
     pub api_key: Option<String>,
@@ -29,61 +40,497 @@ pub struct AgentConfig {
     pub system_prompt: Option<String>,
 }
 
+impl Default for AgentConfig {
+    fn default() -> Self {
+        Self {
+            max_steps: Some(100),
+            use_vision: None,
+            system_prompt: None,
+        }
+    }
+}
+
+/// Which actions the model may use
+///
+/// A [`CapabilityProfile`] sets the baseline; the lists adjust it for one
+/// deployment. For example, `{"profile": "safe", "allow_actions": ["submit_form"]}`
+/// is the safe profile with form submission back.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolsConfig {
+    /// Capability profile: `safe`, `standard` or `full`
+    #[serde(default)]
+    pub profile: CapabilityProfile,
+    /// Actions to leave out in addition to the profile's
+    #[serde(default)]
+    pub exclude_actions: Vec<String>,
+    /// Actions the profile leaves out that should be available anyway
+    #[serde(default)]
+    pub allow_actions: Vec<String>,
+    /// What `evaluate` may run, instead of the profile's policy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evaluate_policy: Option<EvaluatePolicy>,
+    /// Whether actions may write files, instead of the profile's setting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_file_writes: Option<bool>,
+    /// Directories MCP tools may write files in (default: [`default_write_root`])
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_write_roots: Vec<PathBuf>,
+    /// Retry a click that changed nothing (e.g. because an overlay caught it)
+    /// once, with a JS click or, see `keyboard_fallback`, from the keyboard
+    /// (default: true)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub click_fallback: Option<bool>,
+    /// Retry buttons and links a click changed nothing on by pressing Enter
+    /// instead of with a JS click
+    #[serde(default)]
+    pub keyboard_fallback: bool,
+    /// Describe actions that would change the page instead of executing them
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Actions executed even in a dry run, e.g. `navigate` to reach the page to inspect
+    #[serde(default)]
+    pub force_execute: Vec<String>,
+}
+
+impl ToolsConfig {
+    /// Configuration for a profile without adjustments
+    pub fn from_profile(profile: CapabilityProfile) -> Self {
+        Self {
+            profile,
+            ..Default::default()
+        }
+    }
+
+    /// Actions left out: the profile's, less `allow_actions`, plus `exclude_actions`
+    pub fn excluded_actions(&self) -> Vec<String> {
+        let mut excluded: Vec<String> = self
+            .profile
+            .excluded_actions()
+            .iter()
+            .filter(|action| !self.allow_actions.iter().any(|allowed| allowed == *action))
+            .map(|action| action.to_string())
+            .collect();
+        for action in &self.exclude_actions {
+            if !excluded.contains(action) {
+                excluded.push(action.clone());
+            }
+        }
+        excluded
+    }
+
+    /// What `evaluate` may run
+    pub fn evaluate_policy(&self) -> EvaluatePolicy {
+        self.evaluate_policy
+            .unwrap_or_else(|| self.profile.evaluate_policy())
+    }
+
+    /// Whether actions may write files
+    pub fn allows_file_writes(&self) -> bool {
+        self.allow_file_writes
+            .unwrap_or_else(|| self.profile.allows_file_writes())
+    }
+
+    /// Directories files may be written in: `allowed_write_roots`, or the default sandbox
+    pub fn write_roots(&self) -> Vec<PathBuf> {
+        if self.allowed_write_roots.is_empty() {
+            vec![default_write_root()]
+        } else {
+            self.allowed_write_roots.clone()
+        }
+    }
+}
+
+/// Sandbox directory files are written in when no write roots are configured
+///
+/// A `browsing` directory in a directory only the current user can write:
+/// `$XDG_RUNTIME_DIR`, else the user's cache directory (`$XDG_CACHE_HOME`,
+/// `~/.cache`, or `%LOCALAPPDATA%` on Windows). The shared temp directory is
+/// only used, with the user name in the path, when none of these is known.
+pub fn default_write_root() -> PathBuf {
+    let env_dir = |name: &str| {
+        std::env::var_os(name)
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+    };
+    let user_dir = env_dir("XDG_RUNTIME_DIR")
+        .or_else(|| env_dir("XDG_CACHE_HOME"))
+        .or_else(|| {
+            if cfg!(windows) {
+                env_dir("LOCALAPPDATA")
+            } else {
+                std::env::home_dir().map(|home| home.join(".cache"))
+            }
+        });
+    match user_dir {
+        Some(dir) => dir.join("browsing"),
+        None => {
+            let user = std::env::var("USER")
+                .or_else(|_| std::env::var("USERNAME"))
+                .unwrap_or_default();
+            std::env::temp_dir().join(format!("browsing-{user}"))
+        }
+    }
+}
+
+/// Where the agent looks up secrets it was not given directly
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialsSource {
+    /// Only secrets given with [`Agent::with_secrets`](crate::agent::Agent::with_secrets)
+    #[default]
+    None,
+    /// Environment variables, see [`EnvCredentialsProvider`]
+    Env,
+}
+
+/// Credentials provider for `<secret>` placeholders in actions
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CredentialsConfig {
+    /// Where secrets come from: `none` or `env`
+    #[serde(default)]
+    pub provider: CredentialsSource,
+    /// Prefix of the environment variables read by the `env` provider
+    /// (default: `BROWSING_SECRET_`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_prefix: Option<String>,
+}
+
+impl CredentialsConfig {
+    /// The configured provider, for [`Agent::with_credentials_provider`](crate::agent::Agent::with_credentials_provider)
+    pub fn provider(&self) -> Option<Arc<dyn CredentialsProvider>> {
+        match self.provider {
+            CredentialsSource::None => None,
+            CredentialsSource::Env => Some(Arc::new(match &self.env_prefix {
+                Some(prefix) => EnvCredentialsProvider::with_prefix(prefix.clone()),
+                None => EnvCredentialsProvider::new(),
+            })),
+        }
+    }
+}
+
 /// Main configuration structure (streamlined)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
     /// Browser profile configuration (unified)
+    #[serde(default)]
     pub browser_profile: BrowserProfile,
     /// LLM configuration
+    #[serde(default)]
     pub llm: LlmConfig,
     /// Agent configuration
+    #[serde(default)]
     pub agent: AgentConfig,
+    /// Actions available to the model
+    #[serde(default)]
+    pub tools: ToolsConfig,
+    /// Append an environment fingerprint to error messages (see
+    /// [`set_debug_errors`](crate::error::set_debug_errors))
+    #[serde(default)]
+    pub debug_errors: bool,
+    /// Reconnect to the browser when the CDP WebSocket drops instead of failing
+    /// every later command (see [`CdpClient::reconnect`](crate::browser::cdp::CdpClient::reconnect))
+    #[serde(default)]
+    pub reconnect_on_disconnect: bool,
+    /// Where secrets for `<secret>` placeholders are looked up
+    #[serde(default)]
+    pub credentials: CredentialsConfig,
 }
 
-impl Config {
-    /// Creates a Config from environment variables
-    pub fn from_env() -> Self {
-        // Load .env file if present
-        let _ = dotenv::dotenv();
+/// A configuration value that differs from its default
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigDiff {
+    /// Dotted path of the value (e.g. `llm.model`)
+    pub key: String,
+    /// Default value, rendered as JSON
+    pub default: String,
+    /// Effective value, rendered as JSON (secrets are masked)
+    pub value: String,
+}
 
-        Self {
-            browser_profile: BrowserProfile {
-                headless: std::env::var("BROWSER_USE_HEADLESS")
-                    .ok()
-                    .and_then(|v| v.parse().ok()),
-                user_data_dir: std::env::var("BROWSER_USE_USER_DATA_DIR")
-                    .ok()
-                    .map(PathBuf::from),
-                allowed_domains: std::env::var("BROWSER_USE_ALLOWED_DOMAINS")
-                    .ok()
-                    .map(|s| s.split(',').map(|s| s.trim().to_string()).collect()),
-                downloads_path: std::env::var("BROWSER_USE_DOWNLOADS_PATH")
-                    .ok()
-                    .map(PathBuf::from),
-                proxy: None, // TODO: Parse from env vars
-            },
-            llm: LlmConfig {
-                api_key: std::env::var("LLM_API_KEY").ok(),
-                model: std::env::var("LLM_MODEL").ok(),
-                temperature: std::env::var("LLM_TEMPERATURE")
-                    .ok()
-                    .and_then(|v| v.parse().ok()),
-                max_tokens: std::env::var("LLM_MAX_TOKENS")
-                    .ok()
-                    .and_then(|v| v.parse().ok()),
-            },
-            agent: AgentConfig {
-                max_steps: std::env::var("BROWSER_USE_MAX_STEPS")
-                    .ok()
-                    .and_then(|v| v.parse().ok())
-                    .or(Some(100)),
-                use_vision: std::env::var("BROWSER_USE_VISION")
-                    .ok()
-                    .and_then(|v| v.parse().ok()),
-                system_prompt: None,
+impl fmt::Display for ConfigDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {} (default: {})", self.key, self.value, self.default)
+    }
+}
+
+/// Kind of value a setting holds, used to parse environment and argument strings
+#[derive(Clone, Copy)]
+enum SettingKind {
+    Bool,
+    Integer,
+    Float,
+    Text,
+    List,
+}
+
+/// A setting that can be supplied as an environment variable or argument
+struct Setting {
+    /// Argument name, without the leading `--`
+    arg: &'static str,
+    /// Environment variable name
+    env: &'static str,
+    /// Path of the value in the serialized [`Config`]
+    path: &'static [&'static str],
+    kind: SettingKind,
+}
+
+const SETTINGS: &[Setting] = &[
+    Setting {
+        arg: "headless",
+        env: "BROWSER_USE_HEADLESS",
+        path: &["browser_profile", "headless"],
+        kind: SettingKind::Bool,
+    },
+    Setting {
+        arg: "user-data-dir",
+        env: "BROWSER_USE_USER_DATA_DIR",
+        path: &["browser_profile", "user_data_dir"],
+        kind: SettingKind::Text,
+    },
+    Setting {
+        arg: "allowed-domains",
+        env: "BROWSER_USE_ALLOWED_DOMAINS",
+        path: &["browser_profile", "allowed_domains"],
+        kind: SettingKind::List,
+    },
+    Setting {
+        arg: "downloads-path",
+        env: "BROWSER_USE_DOWNLOADS_PATH",
+        path: &["browser_profile", "downloads_path"],
+        kind: SettingKind::Text,
+    },
+    Setting {
+        arg: "proxy-server",
+        env: "BROWSER_USE_PROXY_SERVER",
+        path: &["browser_profile", "proxy", "server"],
+        kind: SettingKind::Text,
+    },
+    Setting {
+        arg: "proxy-bypass",
+        env: "BROWSER_USE_PROXY_BYPASS",
+        path: &["browser_profile", "proxy", "bypass"],
+        kind: SettingKind::Text,
+    },
+    Setting {
+        arg: "proxy-username",
+        env: "BROWSER_USE_PROXY_USERNAME",
+        path: &["browser_profile", "proxy", "username"],
+        kind: SettingKind::Text,
+    },
+    Setting {
+        arg: "proxy-password",
+        env: "BROWSER_USE_PROXY_PASSWORD",
+        path: &["browser_profile", "proxy", "password"],
+        kind: SettingKind::Text,
+    },
+    Setting {
+        arg: "attach-workers",
+        env: "BROWSER_USE_ATTACH_WORKERS",
+        path: &["browser_profile", "attach_workers"],
+        kind: SettingKind::Bool,
+    },
+    Setting {
+        arg: "api-key",
+        env: "LLM_API_KEY",
+        path: &["llm", "api_key"],
+        kind: SettingKind::Text,
+    },
+    Setting {
+        arg: "model",
+        env: "LLM_MODEL",
+        path: &["llm", "model"],
+        kind: SettingKind::Text,
+    },
+    Setting {
+        arg: "temperature",
+        env: "LLM_TEMPERATURE",
+        path: &["llm", "temperature"],
+        kind: SettingKind::Float,
+    },
+    Setting {
+        arg: "max-tokens",
+        env: "LLM_MAX_TOKENS",
+        path: &["llm", "max_tokens"],
+        kind: SettingKind::Integer,
+    },
+    Setting {
+        arg: "max-steps",
+        env: "BROWSER_USE_MAX_STEPS",
+        path: &["agent", "max_steps"],
+        kind: SettingKind::Integer,
+    },
+    Setting {
+        arg: "vision",
+        env: "BROWSER_USE_VISION",
+        path: &["agent", "use_vision"],
+        kind: SettingKind::Bool,
+    },
+    Setting {
+        arg: "system-prompt",
+        env: "BROWSER_USE_SYSTEM_PROMPT",
+        path: &["agent", "system_prompt"],
+        kind: SettingKind::Text,
+    },
+    Setting {
+        arg: "tools-profile",
+        env: "BROWSING_TOOLS_PROFILE",
+        path: &["tools", "profile"],
+        kind: SettingKind::Text,
+    },
+    Setting {
+        arg: "exclude-actions",
+        env: "BROWSING_EXCLUDE_ACTIONS",
+        path: &["tools", "exclude_actions"],
+        kind: SettingKind::List,
+    },
+    Setting {
+        arg: "allowed-write-roots",
+        env: "BROWSING_ALLOWED_WRITE_ROOTS",
+        path: &["tools", "allowed_write_roots"],
+        kind: SettingKind::List,
+    },
+    Setting {
+        arg: "click-fallback",
+        env: "BROWSING_CLICK_FALLBACK",
+        path: &["tools", "click_fallback"],
+        kind: SettingKind::Bool,
+    },
+    Setting {
+        arg: "keyboard-fallback",
+        env: "BROWSING_KEYBOARD_FALLBACK",
+        path: &["tools", "keyboard_fallback"],
+        kind: SettingKind::Bool,
+    },
+    Setting {
+        arg: "dry-run",
+        env: "BROWSING_DRY_RUN",
+        path: &["tools", "dry_run"],
+        kind: SettingKind::Bool,
+    },
+    Setting {
+        arg: "force-execute",
+        env: "BROWSING_FORCE_EXECUTE",
+        path: &["tools", "force_execute"],
+        kind: SettingKind::List,
+    },
+    Setting {
+        arg: "debug-errors",
+        env: "BROWSING_DEBUG_ERRORS",
+        path: &["debug_errors"],
+        kind: SettingKind::Bool,
+    },
+    Setting {
+        arg: "reconnect-on-disconnect",
+        env: "BROWSING_RECONNECT_ON_DISCONNECT",
+        path: &["reconnect_on_disconnect"],
+        kind: SettingKind::Bool,
+    },
+    Setting {
+        arg: "credentials-provider",
+        env: "BROWSING_CREDENTIALS_PROVIDER",
+        path: &["credentials", "provider"],
+        kind: SettingKind::Text,
+    },
+    Setting {
+        arg: "credentials-env-prefix",
+        env: "BROWSING_CREDENTIALS_ENV_PREFIX",
+        path: &["credentials", "env_prefix"],
+        kind: SettingKind::Text,
+    },
+];
+
+/// Dotted paths of values that are masked when printed
+const SECRET_KEYS: &[&str] = &["llm.api_key", "browser_profile.proxy.password"];
+
+impl Setting {
+    fn parse(&self, raw: &str, source: &str) -> Result<Value> {
+        let invalid = |expected: &str| {
+            BrowsingError::Config(format!(
+                "Invalid value '{raw}' for {source}: expected {expected}"
+            ))
+        };
+        let raw = raw.trim();
+        Ok(match self.kind {
+            SettingKind::Bool => match raw.to_lowercase().as_str() {
+                "true" | "1" | "yes" | "on" => Value::Bool(true),
+                "false" | "0" | "no" | "off" => Value::Bool(false),
+                _ => return Err(invalid("a boolean")),
             },
+            SettingKind::Integer => json!(raw.parse::<u32>().map_err(|_| invalid("an integer"))?),
+            SettingKind::Float => json!(raw.parse::<f64>().map_err(|_| invalid("a number"))?),
+            SettingKind::Text => json!(raw),
+            SettingKind::List => json!(
+                raw.split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .collect::<Vec<_>>()
+            ),
+        })
+    }
+
+    /// Wrap a value in nested objects following the setting's path
+    fn overlay(&self, value: Value) -> Value {
+        self.path
+            .iter()
+            .rev()
+            .fold(value, |value, key| json!({ *key: value }))
+    }
+}
+
+/// Deep-merge `overlay` into `base`; `null` values in the overlay are ignored
+fn merge_json(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (_, Value::Null) => {}
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                merge_json(base.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Flatten nested objects into dotted keys; arrays are kept as leaves
+fn flatten_json(prefix: &str, value: &Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                flatten_json(&path, value, out);
+            }
+        }
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
         }
     }
+}
+
+/// Mask a secret, keeping only its last four characters when it is long enough
+fn mask_secret(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    if chars.len() <= 8 {
+        "****".to_string()
+    } else {
+        format!("****{}", chars[chars.len() - 4..].iter().collect::<String>())
+    }
+}
+
+impl Config {
+    /// Creates a Config from defaults and environment variables
+    ///
+    /// Invalid values are logged and the defaults are used instead. Use
+    /// [`ConfigBuilder`] to combine files, environment and arguments, and to
+    /// surface invalid values as errors.
+    pub fn from_env() -> Self {
+        ConfigBuilder::new().from_env().build().unwrap_or_else(|e| {
+            warn!("Invalid configuration in environment, using defaults: {}", e);
+            Self::default()
+        })
+    }
 
     /// Loads configuration from a file
     pub fn load_from_file<P: AsRef<Path>>(
@@ -94,8 +541,240 @@ impl Config {
             return Ok(Self::from_env());
         }
 
-        let content = std::fs::read_to_string(path)?;
-        let config: Config = serde_json::from_str(&content)?;
+        Ok(ConfigBuilder::new().from_file(path).build()?)
+    }
+
+    /// Merge `overlay` on top of this configuration and validate the result
+    ///
+    /// `overlay` has the same shape as the serialized configuration but may be
+    /// partial; only the values it sets (and that are not `null`) are replaced.
+    pub fn validate_and_merge(self, overlay: Value) -> Result<Self> {
+        let config = self.merge(overlay)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn merge(self, overlay: Value) -> Result<Self> {
+        let mut merged = serde_json::to_value(self)?;
+        merge_json(&mut merged, overlay);
+        serde_json::from_value(merged)
+            .map_err(|e| BrowsingError::Config(format!("Invalid configuration: {e}")))
+    }
+
+    /// Check that values are within their valid ranges
+    pub fn validate(&self) -> Result<()> {
+        if let Some(temperature) = self.llm.temperature
+            && !(0.0..=2.0).contains(&temperature)
+        {
+            return Err(BrowsingError::Config(format!(
+                "llm.temperature must be between 0 and 2, got {temperature}"
+            )));
+        }
+        if self.llm.max_tokens == Some(0) {
+            return Err(BrowsingError::Config(
+                "llm.max_tokens must be greater than 0".to_string(),
+            ));
+        }
+        if self.agent.max_steps == Some(0) {
+            return Err(BrowsingError::Config(
+                "agent.max_steps must be greater than 0".to_string(),
+            ));
+        }
+        if let Some(ref proxy) = self.browser_profile.proxy
+            && proxy.server.trim().is_empty()
+        {
+            return Err(BrowsingError::Config(
+                "browser_profile.proxy.server is required when a proxy is configured".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Serialized configuration with secrets masked
+    pub fn to_masked_json(&self) -> Value {
+        let mut value = serde_json::to_value(self).unwrap_or(Value::Null);
+        for key in SECRET_KEYS {
+            let pointer = format!("/{}", key.replace('.', "/"));
+            if let Some(secret) = value.pointer_mut(&pointer)
+                && let Some(s) = secret.as_str()
+            {
+                *secret = Value::String(mask_secret(s));
+            }
+        }
+        value
+    }
+
+    /// Log the effective configuration, masking API keys and passwords
+    pub fn print_effective_config(&self) {
+        let rendered = serde_json::to_string_pretty(&self.to_masked_json()).unwrap_or_default();
+        info!("Effective configuration:\n{}", rendered);
+    }
+
+    /// Values that differ from [`Config::default`], sorted by key
+    pub fn diff_with_defaults(&self) -> Vec<ConfigDiff> {
+        let mut defaults = BTreeMap::new();
+        flatten_json("", &Config::default().to_masked_json(), &mut defaults);
+        let mut current = BTreeMap::new();
+        flatten_json("", &self.to_masked_json(), &mut current);
+
+        let keys: std::collections::BTreeSet<&String> =
+            defaults.keys().chain(current.keys()).collect();
+        keys.into_iter()
+            .filter_map(|key| {
+                let default = defaults.get(key).unwrap_or(&Value::Null);
+                let value = current.get(key).unwrap_or(&Value::Null);
+                (default != value).then(|| ConfigDiff {
+                    key: key.clone(),
+                    default: default.to_string(),
+                    value: value.to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Layered configuration builder
+///
+/// Layers are applied in call order, each overriding the values set by
+/// earlier ones. The conventional order is file, then environment, then
+/// arguments:
+///
+/// ```no_run
+/// use browsing::config::ConfigBuilder;
+///
+/// let args: Vec<String> = std::env::args().skip(1).collect();
+/// let config = ConfigBuilder::new()
+///     .from_file("browsing.json")
+///     .from_env()
+///     .from_args(&args)
+///     .build()?;
+/// # Ok::<(), browsing::error::BrowsingError>(())
+/// ```
+///
+/// Errors from any layer are reported by [`build`](Self::build).
+#[derive(Debug, Default)]
+pub struct ConfigBuilder {
+    layers: Vec<Value>,
+    errors: Vec<String>,
+}
+
+// The `from_*` names describe the layer being applied, not a conversion
+#[allow(clippy::wrong_self_convention)]
+impl ConfigBuilder {
+    /// Start from the built-in defaults
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a JSON configuration file
+    pub fn from_file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        let path = path.as_ref();
+        let layer = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_str::<Value>(&content).map_err(|e| e.to_string()));
+        match layer {
+            Ok(layer) if layer.is_object() => self.layers.push(layer),
+            Ok(_) => self.errors.push(format!(
+                "Config file {} must contain a JSON object",
+                path.display()
+            )),
+            Err(e) => self
+                .errors
+                .push(format!("Failed to read config file {}: {}", path.display(), e)),
+        }
+        self
+    }
+
+    /// Apply environment variables (and a `.env` file, if present)
+    pub fn from_env(self) -> Self {
+        let _ = dotenv::dotenv();
+        self.from_vars(std::env::vars())
+    }
+
+    /// Apply variables as if they came from the environment
+    pub fn from_vars<I, K, V>(mut self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let vars: BTreeMap<String, String> = vars
+            .into_iter()
+            .map(|(k, v)| (k.as_ref().to_string(), v.as_ref().to_string()))
+            .collect();
+
+        let mut layer = Value::Object(Map::new());
+        for setting in SETTINGS {
+            if let Some(raw) = vars.get(setting.env) {
+                match setting.parse(raw, setting.env) {
+                    Ok(value) => merge_json(&mut layer, setting.overlay(value)),
+                    Err(e) => self.errors.push(e.to_string()),
+                }
+            }
+        }
+        self.layers.push(layer);
+        self
+    }
+
+    /// Apply command-line style arguments
+    ///
+    /// Accepts `--name value` and `--name=value`. Boolean settings may be given
+    /// as bare flags (`--headless`).
+    pub fn from_args(mut self, args: &[String]) -> Self {
+        let mut layer = Value::Object(Map::new());
+        let mut iter = args.iter().peekable();
+
+        while let Some(arg) = iter.next() {
+            let Some(flag) = arg.strip_prefix("--") else {
+                self.errors.push(format!("Unexpected argument '{arg}'"));
+                continue;
+            };
+            let (name, inline_value) = match flag.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (flag, None),
+            };
+            let Some(setting) = SETTINGS.iter().find(|s| s.arg == name) else {
+                self.errors.push(format!("Unknown configuration argument '--{name}'"));
+                continue;
+            };
+
+            let raw = match inline_value {
+                Some(value) => value,
+                // A bare boolean flag only consumes the next argument if it is a value
+                None if matches!(setting.kind, SettingKind::Bool) => iter
+                    .next_if(|next| !next.starts_with("--"))
+                    .cloned()
+                    .unwrap_or_else(|| "true".to_string()),
+                None => match iter.next() {
+                    Some(value) => value.clone(),
+                    None => {
+                        self.errors.push(format!("Missing value for '--{name}'"));
+                        continue;
+                    }
+                },
+            };
+
+            match setting.parse(&raw, &format!("--{name}")) {
+                Ok(value) => merge_json(&mut layer, setting.overlay(value)),
+                Err(e) => self.errors.push(e.to_string()),
+            }
+        }
+
+        self.layers.push(layer);
+        self
+    }
+
+    /// Merge all layers over the defaults and validate the result
+    pub fn build(self) -> Result<Config> {
+        if !self.errors.is_empty() {
+            return Err(BrowsingError::Config(self.errors.join("; ")));
+        }
+
+        let mut config = Config::default();
+        for layer in self.layers {
+            config = config.merge(layer)?;
+        }
+        config.validate()?;
         Ok(config)
     }
 }
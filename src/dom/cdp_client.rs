@@ -3,7 +3,9 @@
 //! This module provides a wrapper around CDP operations for DOM extraction.
 
 use crate::browser::cdp::CdpClient;
-use crate::error::Result;
+use crate::dom::enhanced_snapshot::SnapshotOptions;
+use crate::dom::views::DOMRect;
+use crate::error::{BrowsingError, Result};
 use serde_json::Value;
 use std::sync::Arc;
 
@@ -19,46 +21,45 @@ impl DOMCDPClient {
         Self { client, session_id }
     }
 
-    /// Get all trees (snapshot, DOM tree, AX tree, device pixel ratio) for a target
+    /// Get all trees (snapshot, DOM tree, AX tree, device pixel ratio) and the
+    /// layout viewport for a target
+    ///
+    /// The snapshot is captured with `snapshot_options`, which its parser
+    /// needs to be given too. The viewport is `None` when the browser did not
+    /// report one.
     pub async fn get_all_trees(
         &self,
-        target_id: &str,
-    ) -> Result<(Value, Value, Value, f64)> {
+        _target_id: &str,
+        snapshot_options: &SnapshotOptions,
+    ) -> Result<(Value, Value, Value, f64, Option<DOMRect>)> {
         let session_id = self.session_id.as_deref();
 
-        // Enable required domains first
-        let _ = self.client.send_command_with_session(
-            "DOMSnapshot.enable",
-            serde_json::json!({}),
-            session_id,
-        ).await;
-
-        let _ = self.client.send_command_with_session(
-            "DOM.enable",
-            serde_json::json!({}),
-            session_id,
-        ).await;
-
-        // Required computed styles for snapshot
-        let required_computed_styles = vec![
-            "display", "visibility", "opacity", "overflow", "overflow-x",
-            "overflow-y", "position", "z-index", "transform", "transform-origin",
-        ];
-
         // Use DOMSnapshot.captureSnapshot (current method, not deprecated)
-        let snapshot_params = serde_json::json!({
-            "computedStyles": required_computed_styles,
-            "includePaintOrder": true,
-            "includeDOMRects": true,
-            "includeBlendedBackgroundColors": false,
-            "includeTextColorOpacities": false,
+        let snapshot_params = snapshot_options.capture_params();
+
+        // Get DOM tree using DOM.getDocument
+        let dom_tree_params = serde_json::json!({
+            "depth": -1,
+            "pierce": true
         });
 
-        let snapshot_result = self.client.send_command_with_session(
-            "DOMSnapshot.captureSnapshot",
-            snapshot_params,
-            session_id,
-        ).await.unwrap_or_else(|e| {
+        // The domains are enabled ahead of the commands that need them; the
+        // browser runs the batch in order, so nothing waits on a round trip
+        let mut results = self.client.send_batch(vec![
+            ("DOMSnapshot.enable", serde_json::json!({}), session_id),
+            ("DOM.enable", serde_json::json!({}), session_id),
+            ("DOMSnapshot.captureSnapshot", snapshot_params, session_id),
+            ("DOM.getDocument", dom_tree_params, session_id),
+            ("Accessibility.getFullAXTree", serde_json::json!({}), session_id),
+            ("Page.getLayoutMetrics", serde_json::json!({}), session_id),
+        ]).await.into_iter().skip(2);
+        let mut next = || {
+            results
+                .next()
+                .unwrap_or_else(|| Err(BrowsingError::Cdp("Missing batch result".to_string())))
+        };
+
+        let snapshot_result = next().unwrap_or_else(|e| {
             tracing::warn!("DOMSnapshot.captureSnapshot failed: {}, using empty snapshot", e);
             serde_json::json!({
                 "documents": [],
@@ -66,75 +67,19 @@ impl DOMCDPClient {
             })
         });
 
-        // Get DOM tree using DOM.getDocument
-        let dom_tree_params = serde_json::json!({
-            "depth": -1,
-            "pierce": true
-        });
-
-        let dom_tree_result = self.client.send_command_with_session(
-            "DOM.getDocument",
-            dom_tree_params,
-            session_id,
-        ).await.unwrap_or_else(|e| {
+        let dom_tree_result = next().unwrap_or_else(|e| {
             tracing::warn!("DOM.getDocument failed: {}, using fallback", e);
-            serde_json::json!({
-                "root": {
-                    "nodeId": 1,
-                    "backendNodeId": 1,
-                    "nodeType": 9,
-                    "nodeName": "#document",
-                    "localName": "",
-                    "nodeValue": "",
-                    "childNodeCount": 0,
-                    "children": []
-                }
-            })
+            serde_json::json!({ "root": empty_document() })
         });
 
         // Get accessibility tree
-        let ax_tree_result = self.client.send_command_with_session(
-            "Accessibility.getFullAXTree",
-            serde_json::json!({}),
-            session_id,
-        ).await.unwrap_or_else(|_| serde_json::json!({"nodes": []}));
-
-        let viewport_ratio = self.get_viewport_ratio(target_id).await.unwrap_or(1.0);
-
-        Ok((snapshot_result, dom_tree_result, ax_tree_result, viewport_ratio))
-    }
-
-    /// Get viewport ratio (device pixel ratio)
-    async fn get_viewport_ratio(&self, _target_id: &str) -> Result<f64> {
-        let session_id = self.session_id.as_deref();
+        let ax_tree_result = next().unwrap_or_else(|_| serde_json::json!({"nodes": []}));
 
-        // Get layout metrics
-        let metrics = self.client.send_command_with_session(
-            "Page.getLayoutMetrics",
-            serde_json::json!({}),
-            session_id,
-        ).await?;
-
-        // Extract device pixel ratio
-        if let Some(visual_viewport) = metrics.get("visualViewport") {
-            if let Some(css_visual_viewport) = metrics.get("cssVisualViewport") {
-                let device_width = visual_viewport
-                    .get("clientWidth")
-                    .and_then(|v| v.as_f64())
-                    .unwrap_or(1920.0);
-                let css_width = css_visual_viewport
-                    .get("clientWidth")
-                    .and_then(|v| v.as_f64())
-                    .unwrap_or(1920.0);
-
-                if css_width > 0.0 {
-                    return Ok(device_width / css_width);
-                }
-            }
-        }
+        let metrics = next().ok();
+        let viewport_ratio = metrics.as_ref().map(viewport_ratio).unwrap_or(1.0);
+        let viewport = metrics.as_ref().and_then(layout_viewport);
 
-        // Fallback to default
-        Ok(1.0)
+        Ok((snapshot_result, dom_tree_result, ax_tree_result, viewport_ratio, viewport))
     }
 
     /// Get the CDP client
@@ -148,3 +93,61 @@ impl DOMCDPClient {
         self.session_id.as_deref()
     }
 }
+
+/// Viewport ratio (device pixel ratio) from a `Page.getLayoutMetrics` result
+fn viewport_ratio(metrics: &Value) -> f64 {
+    if let Some(visual_viewport) = metrics.get("visualViewport") {
+        if let Some(css_visual_viewport) = metrics.get("cssVisualViewport") {
+            let device_width = visual_viewport
+                .get("clientWidth")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(1920.0);
+            let css_width = css_visual_viewport
+                .get("clientWidth")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(1920.0);
+
+            if css_width > 0.0 {
+                return device_width / css_width;
+            }
+        }
+    }
+
+    // Fallback to default
+    1.0
+}
+
+/// Layout viewport in document CSS pixels from a `Page.getLayoutMetrics` result
+///
+/// Snapshot bounds are document-relative, so the rect is placed at the scroll
+/// offset rather than the origin.
+fn layout_viewport(metrics: &Value) -> Option<DOMRect> {
+    let viewport = metrics
+        .get("cssLayoutViewport")
+        .or_else(|| metrics.get("layoutViewport"))?;
+    let field = |name: &str| viewport.get(name).and_then(|v| v.as_f64());
+    let (width, height) = (field("clientWidth")?, field("clientHeight")?);
+    if width <= 0.0 || height <= 0.0 {
+        return None;
+    }
+    Some(DOMRect::new(
+        field("pageX").unwrap_or(0.0),
+        field("pageY").unwrap_or(0.0),
+        width,
+        height,
+    ))
+}
+
+/// Root node of a document with no content, for pages that have none yet
+pub(crate) fn empty_document() -> Value {
+    serde_json::json!({
+        "nodeId": 1,
+        "backendNodeId": 1,
+        "nodeType": 9,
+        "nodeName": "#document",
+        "localName": "",
+        "nodeValue": "",
+        "childNodeCount": 0,
+        "children": []
+    })
+}
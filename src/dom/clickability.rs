@@ -0,0 +1,426 @@
+//! Heuristic ranking of interactive elements for a task
+//!
+//! When the model is unsure what to do next, [`ClickabilityPredictor`] suggests
+//! the elements most likely to move the task forward. Scores combine:
+//!
+//! - keyword overlap between the task and the element's text and labels (TF-IDF)
+//! - how well the element type fits the verbs in the task ("enter" → inputs)
+//! - prominence on the page (size and vertical position)
+//! - proximity to the element clicked in the previous step, if that changed the page
+
+use crate::dom::views::{DOMInteractedElement, SerializedDOMState};
+use std::collections::{HashMap, HashSet};
+
+const TEXT_WEIGHT: f64 = 0.5;
+const TYPE_WEIGHT: f64 = 0.2;
+const PROMINENCE_WEIGHT: f64 = 0.2;
+const ADJACENCY_WEIGHT: f64 = 0.1;
+
+/// Multiplier applied to the score of disabled elements
+const DISABLED_PENALTY: f64 = 0.2;
+
+/// How many indices away from the last click still count as adjacent
+const ADJACENCY_RANGE: u32 = 3;
+
+/// Attributes that describe what an element does
+const LABEL_ATTRIBUTES: &[&str] = &[
+    "aria-label",
+    "ax_name",
+    "title",
+    "placeholder",
+    "alt",
+    "name",
+    "id",
+    "value",
+];
+
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "as", "at", "by", "for", "from", "in", "into", "is", "it", "me", "my", "of",
+    "on", "or", "the", "then", "this", "to", "with", "your",
+];
+
+/// Kind of element a task verb points at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum ElementKind {
+    Button,
+    Input,
+    Link,
+    Choice,
+}
+
+/// Task verbs and the element kinds they usually act on
+const VERB_KINDS: &[(&str, &[ElementKind])] = &[
+    ("submit", &[ElementKind::Button]),
+    ("send", &[ElementKind::Button]),
+    ("confirm", &[ElementKind::Button]),
+    ("save", &[ElementKind::Button]),
+    ("buy", &[ElementKind::Button]),
+    ("checkout", &[ElementKind::Button]),
+    ("login", &[ElementKind::Button]),
+    ("sign", &[ElementKind::Button]),
+    ("press", &[ElementKind::Button]),
+    ("add", &[ElementKind::Button]),
+    ("enter", &[ElementKind::Input]),
+    ("type", &[ElementKind::Input]),
+    ("fill", &[ElementKind::Input]),
+    ("write", &[ElementKind::Input]),
+    ("input", &[ElementKind::Input]),
+    ("search", &[ElementKind::Input, ElementKind::Button]),
+    ("open", &[ElementKind::Link]),
+    ("go", &[ElementKind::Link]),
+    ("visit", &[ElementKind::Link]),
+    ("navigate", &[ElementKind::Link]),
+    ("follow", &[ElementKind::Link]),
+    ("read", &[ElementKind::Link]),
+    ("view", &[ElementKind::Link]),
+    ("select", &[ElementKind::Choice]),
+    ("choose", &[ElementKind::Choice]),
+    ("pick", &[ElementKind::Choice]),
+    ("check", &[ElementKind::Choice]),
+    ("tick", &[ElementKind::Choice]),
+    ("accept", &[ElementKind::Choice, ElementKind::Button]),
+    ("agree", &[ElementKind::Choice, ElementKind::Button]),
+];
+
+/// Ranks interactive elements by how likely they are the next thing to act on
+#[derive(Debug, Clone, Default)]
+pub struct ClickabilityPredictor {
+    /// Element clicked in the previous step, and whether the page changed after
+    last_click: Option<(u32, bool)>,
+}
+
+impl ClickabilityPredictor {
+    /// Create a predictor without interaction history
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the element clicked in the previous step
+    pub fn record_click(&mut self, index: u32, page_changed: bool) {
+        self.last_click = Some((index, page_changed));
+    }
+
+    /// Score every element in the selector map, highest confidence first
+    ///
+    /// Confidences are in `0.0..=1.0`. Ties are broken by element index.
+    pub fn predict(&self, state: &SerializedDOMState, task: &str) -> Vec<(u32, f64)> {
+        let elements: Vec<&DOMInteractedElement> = state.selector_map.values().collect();
+        if elements.is_empty() {
+            return vec![];
+        }
+
+        let documents: Vec<Vec<String>> = elements.iter().map(|e| element_terms(e)).collect();
+        let idf = inverse_document_frequency(&documents);
+        let task_terms = tokenize(task);
+        let task_vector = tfidf(&task_terms, &idf);
+        let task_kinds: HashSet<ElementKind> = task_terms
+            .iter()
+            .flat_map(|term| {
+                VERB_KINDS
+                    .iter()
+                    .filter(move |(verb, _)| verb == term)
+                    .flat_map(|(_, kinds)| kinds.iter().copied())
+            })
+            .collect();
+        let max_area = elements
+            .iter()
+            .filter_map(|e| e.bounds.map(|b| b.width * b.height))
+            .fold(0.0, f64::max);
+
+        let mut ranked: Vec<(u32, f64)> = elements
+            .iter()
+            .zip(&documents)
+            .map(|(element, terms)| {
+                let text = cosine(&task_vector, &tfidf(terms, &idf));
+                let kind = match element_kind(element) {
+                    Some(kind) if task_kinds.contains(&kind) => 1.0,
+                    _ => 0.0,
+                };
+                let score = TEXT_WEIGHT * text
+                    + TYPE_WEIGHT * kind
+                    + PROMINENCE_WEIGHT * prominence(element, max_area)
+                    + ADJACENCY_WEIGHT * self.adjacency(element.index);
+
+                let penalty = if element.attributes.contains_key("disabled") {
+                    DISABLED_PENALTY
+                } else {
+                    1.0
+                };
+                (element.index, (score * penalty).clamp(0.0, 1.0))
+            })
+            .collect();
+
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked
+    }
+
+    /// Boost for elements next to the last click, if that click changed the page
+    fn adjacency(&self, index: u32) -> f64 {
+        match self.last_click {
+            Some((clicked, true)) if clicked != index => {
+                let distance = clicked.abs_diff(index);
+                if distance <= ADJACENCY_RANGE {
+                    1.0 - distance as f64 / (ADJACENCY_RANGE + 1) as f64
+                } else {
+                    0.0
+                }
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+/// Rank the interactive elements of `state` for `task`, without interaction history
+pub fn predict_next_action(state: &SerializedDOMState, task: &str) -> Vec<(u32, f64)> {
+    ClickabilityPredictor::new().predict(state, task)
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| word.len() > 1 && !STOP_WORDS.contains(&word.as_str()))
+        .map(|word| stem(&word))
+        .collect()
+}
+
+/// Minimal suffix stripping so "passwords" matches "password"
+fn stem(word: &str) -> String {
+    for suffix in ["ing", "ed", "es", "s"] {
+        if let Some(stem) = word.strip_suffix(suffix)
+            && stem.len() >= 3
+            && !stem.ends_with('s')
+        {
+            return stem.to_string();
+        }
+    }
+    word.to_string()
+}
+
+fn element_terms(element: &DOMInteractedElement) -> Vec<String> {
+    let mut text = element.text.clone().unwrap_or_default();
+    for attribute in LABEL_ATTRIBUTES {
+        if let Some(value) = element.attributes.get(*attribute) {
+            text.push(' ');
+            // Split identifiers like "user_email" or "signIn"
+            text.push_str(&split_identifier(value));
+        }
+    }
+    tokenize(&text)
+}
+
+fn split_identifier(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut previous_lower = false;
+    for c in value.chars() {
+        if c == '_' || c == '-' {
+            out.push(' ');
+        } else {
+            if c.is_uppercase() && previous_lower {
+                out.push(' ');
+            }
+            out.push(c);
+        }
+        previous_lower = c.is_lowercase();
+    }
+    out
+}
+
+fn inverse_document_frequency(documents: &[Vec<String>]) -> HashMap<String, f64> {
+    let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+    for terms in documents {
+        let unique: HashSet<&str> = terms.iter().map(String::as_str).collect();
+        for term in unique {
+            *document_frequency.entry(term).or_default() += 1;
+        }
+    }
+    let n = documents.len() as f64;
+    document_frequency
+        .into_iter()
+        .map(|(term, df)| (term.to_string(), ((n + 1.0) / (df as f64 + 1.0)).ln() + 1.0))
+        .collect()
+}
+
+/// TF-IDF vector; terms that appear in no element are dropped
+fn tfidf(terms: &[String], idf: &HashMap<String, f64>) -> HashMap<String, f64> {
+    let mut vector: HashMap<String, f64> = HashMap::new();
+    for term in terms {
+        if let Some(weight) = idf.get(term) {
+            *vector.entry(term.clone()).or_default() += weight;
+        }
+    }
+    vector
+}
+
+fn cosine(a: &HashMap<String, f64>, b: &HashMap<String, f64>) -> f64 {
+    let dot: f64 = a
+        .iter()
+        .filter_map(|(term, x)| b.get(term).map(|y| x * y))
+        .sum();
+    let norm = |v: &HashMap<String, f64>| v.values().map(|x| x * x).sum::<f64>().sqrt();
+    let denominator = norm(a) * norm(b);
+    if denominator == 0.0 {
+        0.0
+    } else {
+        dot / denominator
+    }
+}
+
+fn element_kind(element: &DOMInteractedElement) -> Option<ElementKind> {
+    let attribute = |name: &str| element.attributes.get(name).map(|v| v.to_lowercase());
+    match attribute("role").as_deref() {
+        Some("button") => return Some(ElementKind::Button),
+        Some("link") => return Some(ElementKind::Link),
+        Some("textbox" | "searchbox" | "combobox") => return Some(ElementKind::Input),
+        Some("checkbox" | "radio" | "option" | "switch") => return Some(ElementKind::Choice),
+        _ => {}
+    }
+
+    match element.tag.to_lowercase().as_str() {
+        "button" => Some(ElementKind::Button),
+        "a" => Some(ElementKind::Link),
+        "textarea" => Some(ElementKind::Input),
+        "select" | "option" => Some(ElementKind::Choice),
+        "input" => match attribute("type").as_deref() {
+            Some("submit" | "button" | "reset" | "image") => Some(ElementKind::Button),
+            Some("checkbox" | "radio") => Some(ElementKind::Choice),
+            _ => Some(ElementKind::Input),
+        },
+        _ if element.attributes.contains_key("contenteditable") => Some(ElementKind::Input),
+        _ => None,
+    }
+}
+
+/// Larger elements higher up the page are more prominent
+fn prominence(element: &DOMInteractedElement, max_area: f64) -> f64 {
+    let Some(bounds) = element.bounds else {
+        return 0.0;
+    };
+    let size = if max_area > 0.0 {
+        (bounds.width * bounds.height / max_area).sqrt()
+    } else {
+        0.0
+    };
+    let position = 1.0 / (1.0 + bounds.y.max(0.0) / 1000.0);
+    0.6 * size + 0.4 * position
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct Fixture {
+        state: SerializedDOMState,
+        tasks: Vec<FixtureTask>,
+    }
+
+    #[derive(Deserialize)]
+    struct FixtureTask {
+        task: String,
+        expected: u32,
+    }
+
+    fn fixture(json: &str) -> Fixture {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_login_page_tasks() {
+        let fixture = fixture(include_str!(
+            "../../tests/fixtures/clickability/login_page.json"
+        ));
+        for case in fixture.tasks {
+            let ranked = predict_next_action(&fixture.state, &case.task);
+            assert_eq!(
+                ranked[0].0, case.expected,
+                "task {:?} ranked {:?}",
+                case.task, ranked
+            );
+        }
+    }
+
+    #[test]
+    fn test_product_page_tasks() {
+        let fixture = fixture(include_str!(
+            "../../tests/fixtures/clickability/product_page.json"
+        ));
+        for case in fixture.tasks {
+            let ranked = predict_next_action(&fixture.state, &case.task);
+            assert_eq!(
+                ranked[0].0, case.expected,
+                "task {:?} ranked {:?}",
+                case.task, ranked
+            );
+        }
+    }
+
+    #[test]
+    fn test_confidences_are_normalized_and_sorted() {
+        let fixture = fixture(include_str!(
+            "../../tests/fixtures/clickability/login_page.json"
+        ));
+        let ranked = predict_next_action(&fixture.state, "sign in with my email");
+
+        assert_eq!(ranked.len(), fixture.state.selector_map.len());
+        assert!(ranked.iter().all(|(_, c)| (0.0..=1.0).contains(c)));
+        assert!(ranked.windows(2).all(|w| w[0].1 >= w[1].1));
+    }
+
+    #[test]
+    fn test_adjacent_elements_after_page_change() {
+        let fixture = fixture(include_str!(
+            "../../tests/fixtures/clickability/product_page.json"
+        ));
+        // A task that matches nothing leaves only prominence and adjacency
+        let task = "continue";
+        let baseline = predict_next_action(&fixture.state, task);
+        let score =
+            |ranked: &[(u32, f64)], index: u32| ranked.iter().find(|(i, _)| *i == index).unwrap().1;
+
+        let mut predictor = ClickabilityPredictor::new();
+        predictor.record_click(4, true);
+        let ranked = predictor.predict(&fixture.state, task);
+        assert!(score(&ranked, 5) > score(&baseline, 5));
+        assert!(score(&ranked, 3) > score(&baseline, 3));
+        assert_eq!(score(&ranked, 4), score(&baseline, 4));
+
+        // No boost if the click didn't change anything
+        predictor.record_click(4, false);
+        assert_eq!(predictor.predict(&fixture.state, task), baseline);
+    }
+
+    #[test]
+    fn test_disabled_elements_rank_low() {
+        let mut fixture = fixture(include_str!(
+            "../../tests/fixtures/clickability/login_page.json"
+        ));
+        let submit = fixture.state.selector_map.get_mut(&3).unwrap();
+        submit
+            .attributes
+            .insert("disabled".to_string(), String::new());
+
+        let ranked = predict_next_action(&fixture.state, "click the sign in button");
+        assert_ne!(ranked[0].0, 3);
+    }
+
+    #[test]
+    fn test_empty_state() {
+        let state = SerializedDOMState {
+            html: None,
+            text: None,
+            markdown: None,
+            elements: vec![],
+            selector_map: HashMap::new(),
+            truncated_values: vec![],
+        };
+        assert!(predict_next_action(&state, "click submit").is_empty());
+    }
+
+    #[test]
+    fn test_tokenize_and_split_identifiers() {
+        assert_eq!(tokenize("Enter the Passwords"), vec!["enter", "password"]);
+        assert_eq!(split_identifier("user_email"), "user email");
+        assert_eq!(split_identifier("signIn"), "sign In");
+    }
+}
@@ -2,6 +2,7 @@
 
 use crate::dom::views::{DOMRect, EnhancedSnapshotNode};
 use crate::error::Result;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 
@@ -19,6 +20,67 @@ pub const REQUIRED_COMPUTED_STYLES: &[&str] = &[
     "background-color",
 ];
 
+/// What `DOMSnapshot.captureSnapshot` is asked to capture
+///
+/// Visibility and interactivity detection read the styles in
+/// [`REQUIRED_COMPUTED_STYLES`]; a shorter list is faster to capture, but
+/// elements whose styles are missing are treated as visible and only
+/// recognised as interactive by their tag, role and attributes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnapshotOptions {
+    /// CSS properties to capture for each laid-out node, e.g. `display`
+    pub computed_styles: Vec<String>,
+    /// Capture each node's background color blended with those behind it
+    pub include_blended_background_colors: bool,
+    /// Capture each text node's color opacity
+    pub include_text_color_opacities: bool,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        Self {
+            computed_styles: REQUIRED_COMPUTED_STYLES
+                .iter()
+                .map(|style| style.to_string())
+                .collect(),
+            include_blended_background_colors: false,
+            include_text_color_opacities: false,
+        }
+    }
+}
+
+impl SnapshotOptions {
+    /// Capture these CSS properties instead of [`REQUIRED_COMPUTED_STYLES`]
+    pub fn with_computed_styles<I, S>(mut self, styles: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.computed_styles = styles.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Capture blended background colors and text color opacities, as
+    /// contrast checks and occlusion detection need
+    pub fn with_color_capture(mut self, enabled: bool) -> Self {
+        self.include_blended_background_colors = enabled;
+        self.include_text_color_opacities = enabled;
+        self
+    }
+
+    /// Params of the `DOMSnapshot.captureSnapshot` command
+    pub fn capture_params(&self) -> Value {
+        serde_json::json!({
+            "computedStyles": self.computed_styles,
+            "includePaintOrder": true,
+            "includeDOMRects": true,
+            "includeBlendedBackgroundColors": self.include_blended_background_colors,
+            "includeTextColorOpacities": self.include_text_color_opacities,
+        })
+    }
+}
+
 /// Parse rare boolean data from snapshot
 fn parse_rare_boolean_data(rare_data: &Value, index: usize) -> Option<bool> {
     if let Some(indices) = rare_data.get("index").and_then(|v| v.as_array()) {
@@ -28,23 +90,37 @@ fn parse_rare_boolean_data(rare_data: &Value, index: usize) -> Option<bool> {
 }
 
 /// Parse computed styles from layout tree using string indices
-fn parse_computed_styles(strings: &[String], style_indices: &[usize]) -> HashMap<String, String> {
-    let mut styles = HashMap::new();
-    for (i, &style_index) in style_indices.iter().enumerate() {
-        if i < REQUIRED_COMPUTED_STYLES.len() && style_index < strings.len() {
-            styles.insert(
-                REQUIRED_COMPUTED_STYLES[i].to_string(),
-                strings[style_index].clone(),
-            );
-        }
-    }
-    styles
+///
+/// The snapshot lists each node's values in the order the styles were
+/// requested, so `requested` must be the list the snapshot was captured with.
+fn parse_computed_styles(
+    strings: &[String],
+    style_indices: &[usize],
+    requested: &[String],
+) -> HashMap<String, String> {
+    requested
+        .iter()
+        .zip(style_indices)
+        .filter_map(|(style, &index)| Some((style.clone(), strings.get(index)?.clone())))
+        .collect()
 }
 
 /// Build a lookup table of backend node ID to enhanced snapshot data
+///
+/// For snapshots captured with the default [`SnapshotOptions`].
 pub fn build_snapshot_lookup(
     snapshot: &Value,
     device_pixel_ratio: f64,
+) -> Result<HashMap<u64, EnhancedSnapshotNode>> {
+    build_snapshot_lookup_with_options(snapshot, device_pixel_ratio, &SnapshotOptions::default())
+}
+
+/// Build a lookup table of backend node ID to enhanced snapshot data, for a
+/// snapshot captured with `options`
+pub fn build_snapshot_lookup_with_options(
+    snapshot: &Value,
+    device_pixel_ratio: f64,
+    options: &SnapshotOptions,
 ) -> Result<HashMap<u64, EnhancedSnapshotNode>> {
     let mut snapshot_lookup: HashMap<u64, EnhancedSnapshotNode> = HashMap::new();
 
@@ -111,6 +187,8 @@ pub fn build_snapshot_lookup(
             let mut client_rects = None;
             let mut scroll_rects = None;
             let mut stacking_contexts = None;
+            let mut blended_background_color = None;
+            let mut text_color_opacity = None;
 
             if let Some(layout) = layout {
                 if let Some(&layout_idx) = layout_index_map.get(&snapshot_index) {
@@ -144,7 +222,11 @@ pub fn build_snapshot_lookup(
                                     .iter()
                                     .filter_map(|v| v.as_u64().map(|v| v as usize))
                                     .collect();
-                                computed_styles = parse_computed_styles(&strings, &indices);
+                                computed_styles = parse_computed_styles(
+                                    &strings,
+                                    &indices,
+                                    &options.computed_styles,
+                                );
                                 cursor_style = computed_styles.get("cursor").cloned();
                             }
                         }
@@ -192,6 +274,20 @@ pub fn build_snapshot_lookup(
                         }
                     }
 
+                    // Extract colors, present when requested
+                    blended_background_color = layout
+                        .get("blendedBackgroundColors")
+                        .and_then(|v| v.as_array())
+                        .and_then(|colors| colors.get(layout_idx))
+                        .and_then(|v| v.as_u64())
+                        .and_then(|index| strings.get(index as usize))
+                        .cloned();
+                    text_color_opacity = layout
+                        .get("textColorOpacities")
+                        .and_then(|v| v.as_array())
+                        .and_then(|opacities| opacities.get(layout_idx))
+                        .and_then(|v| v.as_f64());
+
                     // Extract stacking contexts
                     if let Some(stacking_contexts_obj) = layout.get("stackingContexts") {
                         if let Some(index_array) = stacking_contexts_obj
@@ -224,6 +320,8 @@ pub fn build_snapshot_lookup(
                     computed_styles: computed_styles_opt,
                     paint_order,
                     stacking_contexts,
+                    blended_background_color,
+                    text_color_opacity,
                 },
             );
         }
@@ -1,10 +1,14 @@
 //! DOM parsing and serialization
 
 mod ax_node;
+mod clickability;
 mod cdp_client;
 mod html_converter;
+mod node_filter;
 mod processor;
+mod snapshot_debugger;
 mod tree_builder;
+mod visibility;
 
 pub mod enhanced_snapshot;
 pub mod serializer;
@@ -15,8 +19,13 @@ pub mod views;
 mod serializer_test;
 
 pub use ax_node::build_enhanced_ax_node;
-pub use enhanced_snapshot::build_snapshot_lookup;
+pub use clickability::{ClickabilityPredictor, predict_next_action};
+pub use enhanced_snapshot::{SnapshotOptions, build_snapshot_lookup};
+pub use node_filter::NodeCategory;
 pub use processor::DOMProcessorImpl;
-pub use serializer::DOMTreeSerializer;
+pub use serializer::{DOMTreeSerializer, NO_ELEMENTS_IN_CATEGORIES, SerializationOptions};
+pub use snapshot_debugger::{DocumentSummary, SnapshotDebugger, SnapshotSummary};
 pub use service::DomService;
+pub use tree_builder::DEFAULT_MAX_VALUE_LEN;
+pub use visibility::InvisibleElementFilter;
 pub use views::*;
@@ -0,0 +1,282 @@
+//! Element categories for focused page states
+//!
+//! A form-filling task has no use for the article text around the form, and an
+//! extraction task rarely needs the site menu. Restricting the page state to the
+//! relevant categories keeps element indices stable while cutting the tokens
+//! spent on the rest of the page.
+//!
+//! Categories are applied to the DOM tree while it is serialized
+//! ([`SerializationOptions::node_categories`](crate::dom::SerializationOptions::node_categories)):
+//! a matching element is kept with everything inside it, an element that only
+//! contains matches is left out but its matching descendants are kept, and
+//! everything else is dropped.
+
+use crate::dom::serializer::SimplifiedNode;
+use crate::dom::views::{EnhancedDOMTreeNode, NodeType};
+use serde::{Deserialize, Serialize};
+
+/// Tags of form controls and other elements acted on directly
+const INTERACTIVE_TAGS: &[&str] = &[
+    "a", "button", "input", "select", "textarea", "option", "label", "summary", "details",
+];
+
+/// Roles of custom widgets acted on directly
+const INTERACTIVE_ROLES: &[&str] = &[
+    "button",
+    "link",
+    "checkbox",
+    "radio",
+    "textbox",
+    "searchbox",
+    "combobox",
+    "listbox",
+    "option",
+    "menuitem",
+    "menuitemcheckbox",
+    "menuitemradio",
+    "tab",
+    "switch",
+    "slider",
+    "spinbutton",
+];
+
+/// Tags of text and structured data
+const DATA_CONTENT_TAGS: &[&str] = &[
+    "p",
+    "table",
+    "thead",
+    "tbody",
+    "tr",
+    "td",
+    "th",
+    "caption",
+    "ul",
+    "ol",
+    "li",
+    "dl",
+    "dt",
+    "dd",
+    "article",
+    "section",
+    "blockquote",
+    "pre",
+    "code",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+];
+
+/// Tags of site navigation landmarks; elements inside them count too
+const NAVIGATION_TAGS: &[&str] = &["nav", "header", "menu", "footer"];
+
+/// Roles of site navigation landmarks
+const NAVIGATION_ROLES: &[&str] = &["navigation", "banner", "menu", "menubar", "contentinfo"];
+
+/// Tags of embedded media
+const MEDIA_TAGS: &[&str] = &["img", "picture", "video", "audio", "svg", "canvas"];
+
+/// Category of elements to include in the page state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeCategory {
+    /// Buttons, inputs, links, selects and widgets with an interactive role
+    Interactive,
+    /// Paragraphs, tables, lists, articles and headings
+    DataContent,
+    /// Navigation landmarks (`nav`, `header`, `menu`) and everything inside them
+    Navigation,
+    /// Images, video and audio
+    Media,
+    /// Every element
+    All,
+}
+
+impl NodeCategory {
+    /// Whether the element `node` belongs to this category, `inside_navigation`
+    /// telling whether it is in a navigation landmark
+    pub fn matches(self, node: &EnhancedDOMTreeNode, inside_navigation: bool) -> bool {
+        if node.node_type != NodeType::ElementNode {
+            return false;
+        }
+        let tag = node.tag_name();
+        let role = node
+            .attributes
+            .get("role")
+            .map(|r| r.trim().to_ascii_lowercase());
+        let has_role = |roles: &[&str]| role.as_deref().is_some_and(|r| roles.contains(&r));
+
+        match self {
+            NodeCategory::Interactive => {
+                INTERACTIVE_TAGS.contains(&tag.as_str()) || has_role(INTERACTIVE_ROLES)
+            }
+            NodeCategory::DataContent => DATA_CONTENT_TAGS.contains(&tag.as_str()),
+            NodeCategory::Navigation => inside_navigation || is_navigation_landmark(node),
+            NodeCategory::Media => MEDIA_TAGS.contains(&tag.as_str()) || has_role(&["img"]),
+            NodeCategory::All => true,
+        }
+    }
+}
+
+/// Whether `node` is a navigation landmark, by tag or role
+pub(crate) fn is_navigation_landmark(node: &EnhancedDOMTreeNode) -> bool {
+    node.node_type == NodeType::ElementNode
+        && (NAVIGATION_TAGS.contains(&node.tag_name().as_str())
+            || node.attributes.get("role").is_some_and(|r| {
+                NAVIGATION_ROLES.contains(&r.trim().to_ascii_lowercase().as_str())
+            }))
+}
+
+/// Hide everything below `simplified` outside `categories`, returning whether
+/// anything is left to show
+///
+/// Matching elements keep their whole subtree. Other elements are hidden
+/// themselves, so only their matching descendants show, and text outside a
+/// matching element is dropped. Indices are untouched.
+pub(crate) fn retain_categories(
+    simplified: &mut SimplifiedNode,
+    categories: &[NodeCategory],
+    inside_navigation: bool,
+) -> bool {
+    let node = &simplified.original_node;
+    let inside_navigation = inside_navigation || is_navigation_landmark(node);
+    if categories
+        .iter()
+        .any(|category| category.matches(node, inside_navigation))
+    {
+        return simplified.should_display || has_shown(simplified);
+    }
+
+    let mut kept = false;
+    for child in &mut simplified.children {
+        kept |= retain_categories(child, categories, inside_navigation);
+    }
+    simplified
+        .children
+        .retain(|child| child.should_display || has_shown(child));
+    simplified.should_display = false;
+    kept
+}
+
+/// Whether any descendant of `simplified` is shown
+fn has_shown(simplified: &SimplifiedNode) -> bool {
+    simplified
+        .children
+        .iter()
+        .any(|child| child.should_display || has_shown(child))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(
+        tag: &str,
+        attributes: &[(&str, &str)],
+        children: Vec<SimplifiedNode>,
+    ) -> SimplifiedNode {
+        let mut element = EnhancedDOMTreeNode::new(
+            1,
+            1,
+            NodeType::ElementNode,
+            tag.to_uppercase(),
+            String::new(),
+            "T1".to_string(),
+        );
+        element.attributes = attributes
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        SimplifiedNode {
+            children,
+            ..SimplifiedNode::new(element)
+        }
+    }
+
+    fn text(value: &str) -> SimplifiedNode {
+        let text = EnhancedDOMTreeNode::new(
+            1,
+            1,
+            NodeType::TextNode,
+            "#text".to_string(),
+            value.to_string(),
+            "T1".to_string(),
+        );
+        SimplifiedNode::new(text)
+    }
+
+    /// Tags of the elements shown below `simplified`, in document order
+    fn shown(simplified: &SimplifiedNode, tags: &mut Vec<String>) {
+        if simplified.should_display {
+            tags.push(match simplified.original_node.node_type {
+                NodeType::TextNode => simplified.original_node.node_value.clone(),
+                _ => simplified.original_node.tag_name(),
+            });
+        }
+        for child in &simplified.children {
+            shown(child, tags);
+        }
+    }
+
+    fn page() -> SimplifiedNode {
+        node(
+            "body",
+            &[],
+            vec![
+                node("header", &[], vec![node("a", &[], vec![text("Home")])]),
+                node(
+                    "div",
+                    &[("role", "navigation")],
+                    vec![node("span", &[], vec![text("Docs")])],
+                ),
+                node(
+                    "main",
+                    &[],
+                    vec![
+                        node(
+                            "p",
+                            &[],
+                            vec![text("Intro"), node("b", &[], vec![text("bold")])],
+                        ),
+                        node("img", &[("alt", "Chart")], vec![]),
+                        node("div", &[("role", "button")], vec![text("Go")]),
+                        text("loose text"),
+                    ],
+                ),
+            ],
+        )
+    }
+
+    fn retained(categories: &[NodeCategory]) -> Vec<String> {
+        let mut tree = page();
+        retain_categories(&mut tree, categories, false);
+        let mut tags = Vec::new();
+        shown(&tree, &mut tags);
+        tags
+    }
+
+    #[test]
+    fn test_categories() {
+        assert_eq!(
+            retained(&[NodeCategory::Interactive]),
+            ["a", "Home", "div", "Go"]
+        );
+        assert_eq!(
+            retained(&[NodeCategory::DataContent]),
+            ["p", "Intro", "b", "bold"]
+        );
+        // Everything inside a landmark counts, by tag or by role
+        assert_eq!(
+            retained(&[NodeCategory::Navigation]),
+            ["header", "a", "Home", "div", "span", "Docs"]
+        );
+        assert_eq!(retained(&[NodeCategory::Media]), ["img"]);
+        assert_eq!(
+            retained(&[NodeCategory::Media, NodeCategory::DataContent]),
+            ["p", "Intro", "b", "bold", "img"]
+        );
+        assert!(retained(&[NodeCategory::All]).contains(&"loose text".to_string()));
+    }
+}
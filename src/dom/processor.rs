@@ -3,11 +3,12 @@
 //! This module provides the main DOMProcessor implementation.
 
 use super::cdp_client::DOMCDPClient;
+use super::enhanced_snapshot::SnapshotOptions;
 use super::html_converter::HTMLConverter;
-use super::tree_builder::DOMTreeBuilder;
+use super::tree_builder::{DEFAULT_MAX_VALUE_LEN, DOMTreeBuilder};
 use super::views::SerializedDOMState;
 use crate::browser::cdp::CdpClient;
-use crate::dom::serializer::DOMTreeSerializer;
+use crate::dom::serializer::{DOMTreeSerializer, SerializationOptions};
 use crate::dom::views::DOMInteractedElement;
 use crate::error::Result;
 use crate::traits::DOMProcessor;
@@ -19,6 +20,9 @@ use std::sync::Arc;
 pub struct DOMProcessorImpl {
     cdp_client: Option<Arc<DOMCDPClient>>,
     current_target_id: Option<String>,
+    max_value_len: usize,
+    serialization_options: SerializationOptions,
+    snapshot_options: SnapshotOptions,
 }
 
 impl DOMProcessorImpl {
@@ -27,6 +31,9 @@ impl DOMProcessorImpl {
         Self {
             cdp_client: None,
             current_target_id: None,
+            max_value_len: DEFAULT_MAX_VALUE_LEN,
+            serialization_options: SerializationOptions::default(),
+            snapshot_options: SnapshotOptions::default(),
         }
     }
 
@@ -42,6 +49,26 @@ impl DOMProcessorImpl {
         self
     }
 
+    /// Truncate attribute and text values longer than `max_value_len` bytes
+    ///
+    /// Defaults to [`DEFAULT_MAX_VALUE_LEN`](crate::dom::DEFAULT_MAX_VALUE_LEN).
+    pub fn with_max_value_len(mut self, max_value_len: usize) -> Self {
+        self.max_value_len = max_value_len;
+        self
+    }
+
+    /// Set the options used to serialize the page state
+    pub fn with_serialization_options(mut self, options: SerializationOptions) -> Self {
+        self.serialization_options = options;
+        self
+    }
+
+    /// Set what the DOM snapshot captures: computed styles and colors
+    pub fn with_snapshot_options(mut self, options: SnapshotOptions) -> Self {
+        self.snapshot_options = options;
+        self
+    }
+
     /// Extract page content from HTML
     pub fn extract_page_content(&self, html: &str) -> Result<String> {
         HTMLConverter::extract_page_content(html)
@@ -57,27 +84,13 @@ impl Default for DOMProcessorImpl {
 #[async_trait]
 impl DOMProcessor for DOMProcessorImpl {
     async fn get_serialized_dom(&self) -> Result<SerializedDOMState> {
-        let cdp_client = self
-            .cdp_client
-            .as_ref()
-            .ok_or_else(|| crate::error::BrowsingError::Dom("No CDP client available".to_string()))?;
-
-        let tree_builder =
-            DOMTreeBuilder::new(Arc::clone(cdp_client), self.current_target_id.clone());
-        let enhanced_dom_tree = tree_builder.build_tree().await?;
-
-        // Serialize the tree
-        let serializer = DOMTreeSerializer::new(enhanced_dom_tree.clone());
-        let (serialized_state, _timing_info) = serializer.serialize_accessible_elements();
-
+        let (serialized_state, _, _) = self.get_serialized_dom_tree_internal(None).await?;
         Ok(serialized_state)
     }
 
     async fn get_page_state_string(&self) -> Result<String> {
         let (serialized_state, _, _) = self.get_serialized_dom_tree_internal(None).await?;
-        Ok(serialized_state
-            .llm_representation(None)
-            .unwrap_or_else(|| "Empty DOM tree".to_string()))
+        Ok(serialized_state.page_state())
     }
 
     async fn get_selector_map(&self) -> Result<HashMap<u32, DOMInteractedElement>> {
@@ -100,12 +113,21 @@ impl DOMProcessorImpl {
         let tree_builder = DOMTreeBuilder::new(
             Arc::clone(cdp_client),
             target_id.or(self.current_target_id.as_deref()).map(|s| s.to_string()),
-        );
+        )
+        .with_max_value_len(self.max_value_len)
+        .with_snapshot_options(self.snapshot_options.clone());
         let enhanced_dom_tree = tree_builder.build_tree().await?;
+        let truncated_values = tree_builder.warnings();
+        report_truncated_values(&truncated_values);
 
         // Serialize the tree
-        let serializer = crate::dom::serializer::DOMTreeSerializer::new(enhanced_dom_tree.clone());
-        let (serialized_state, timing_info) = serializer.serialize_accessible_elements();
+        let mut serializer = DOMTreeSerializer::new(enhanced_dom_tree.clone())
+            .with_options(self.serialization_options.clone());
+        if let Some(viewport) = tree_builder.viewport() {
+            serializer = serializer.with_viewport(viewport);
+        }
+        let (mut serialized_state, timing_info) = serializer.serialize_accessible_elements();
+        serialized_state.truncated_values = truncated_values;
 
         Ok((serialized_state, enhanced_dom_tree, timing_info))
     }
@@ -118,3 +140,14 @@ impl DOMProcessorImpl {
         self.get_serialized_dom_tree_internal(target_id).await
     }
 }
+
+/// Log the values the builder truncated, once per snapshot
+fn report_truncated_values(warnings: &[String]) {
+    if let Some(first) = warnings.first() {
+        tracing::warn!(
+            "{} oversized DOM values truncated; first: {}",
+            warnings.len(),
+            first
+        );
+    }
+}
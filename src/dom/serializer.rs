@@ -1,10 +1,51 @@
 //! DOM serializer for LLM representation
 
 use crate::dom::views::{
-    DOMInteractedElement, EnhancedDOMTreeNode, NodeType, DEFAULT_INCLUDE_ATTRIBUTES,
+    DOMInteractedElement, DOMRect, EnhancedDOMTreeNode, NodeType, SerializedElement,
+    DEFAULT_INCLUDE_ATTRIBUTES, MAX_ELEMENT_TEXT_CHARS,
 };
+use crate::dom::node_filter::{NodeCategory, retain_categories};
+use crate::dom::visibility::InvisibleElementFilter;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// Header shown when the page itself does not scroll but containers inside it do
+pub const INNER_SCROLL_NOTE: &str = "Note: the page itself does not scroll. To see more content, \
+scroll the containers marked [scrollable N] with scroll and container_index=N.";
+
+/// Items of a collapsed run of repeated siblings that are still shown in full
+pub const COLLAPSED_RUN_SHOWN: usize = 2;
+
+/// Page state when [`SerializationOptions::node_categories`] leave nothing to show
+pub const NO_ELEMENTS_IN_CATEGORIES: &str = "No elements in the selected categories";
+
+/// Options for [`DOMTreeSerializer`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SerializationOptions {
+    /// Collapse runs of sibling subtrees with the same structure (tags and
+    /// classes), such as search result cards: the first
+    /// [`COLLAPSED_RUN_SHOWN`] are shown in full and the rest as a single
+    /// "… N more similar items (indices A–B)" line. Their indices stay in the
+    /// selector map, so they can still be clicked.
+    pub collapse_repeated_siblings: bool,
+    /// Shortest run of repeated siblings that is collapsed
+    pub repeated_siblings_threshold: usize,
+    /// Show only elements in these categories; empty shows everything. Every
+    /// element keeps its index in the selector map.
+    pub node_categories: Vec<NodeCategory>,
+}
+
+impl Default for SerializationOptions {
+    fn default() -> Self {
+        Self {
+            collapse_repeated_siblings: false,
+            repeated_siblings_threshold: 5,
+            node_categories: Vec::new(),
+        }
+    }
+}
+
 /// Simplified node for serialization
 #[derive(Debug, Clone)]
 pub struct SimplifiedNode {
@@ -18,6 +59,10 @@ pub struct SimplifiedNode {
     pub is_interactive: bool,
     /// Interactive index if applicable
     pub interactive_index: Option<u32>,
+    /// Number of the form this node is or belongs to, for forms and interactive elements
+    pub form_id: Option<u32>,
+    /// Whether this node is a scroll container inside the page
+    pub is_scrollable: bool,
 }
 
 impl SimplifiedNode {
@@ -29,6 +74,8 @@ impl SimplifiedNode {
             should_display: true,
             is_interactive: false,
             interactive_index: None,
+            form_id: None,
+            is_scrollable: false,
         }
     }
 }
@@ -41,6 +88,16 @@ pub struct DOMTreeSerializer {
     interactive_counter: u32,
     /// Map of selectors
     selector_map: HashMap<u32, DOMInteractedElement>,
+    /// Structured entries for the interactive elements, in index order
+    elements: Vec<SerializedElement>,
+    /// Filter for visually imperceptible elements
+    invisible_filter: InvisibleElementFilter,
+    /// Form numbers by backend node ID, counted from 1 in document order
+    form_numbers: HashMap<u64, u32>,
+    /// Form numbers by the form's `id`, for controls with a `form` attribute
+    form_ids: HashMap<String, u32>,
+    /// Serialization options
+    options: SerializationOptions,
 }
 
 impl DOMTreeSerializer {
@@ -50,45 +107,103 @@ impl DOMTreeSerializer {
             root_node,
             interactive_counter: 1,
             selector_map: HashMap::new(),
+            elements: Vec::new(),
+            invisible_filter: InvisibleElementFilter::new(),
+            form_numbers: HashMap::new(),
+            form_ids: HashMap::new(),
+            options: SerializationOptions::default(),
         }
     }
 
+    /// Set the serialization options
+    pub fn with_options(mut self, options: SerializationOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Hide elements lying outside the given viewport
+    pub fn with_viewport(mut self, viewport: DOMRect) -> Self {
+        self.invisible_filter = self.invisible_filter.with_viewport(viewport);
+        self
+    }
+
     /// Serialize accessible elements and build selector map
     pub fn serialize_accessible_elements(mut self) -> (SerializedDOMState, HashMap<String, f64>) {
         // Reset state
         self.interactive_counter = 1;
         self.selector_map.clear();
+        self.elements.clear();
 
         // Create simplified tree
         let simplified_tree = self._create_simplified_tree(&self.root_node);
 
+        // Number forms first, as controls may refer to a later form by id
+        self._number_forms(&simplified_tree);
+
         // Assign interactive indices (need mutable reference)
         let mut simplified_tree_mut = simplified_tree;
-        self._assign_interactive_indices(&mut simplified_tree_mut);
+        self._assign_interactive_indices(&mut simplified_tree_mut, None, None);
+        let is_blank = Self::is_blank(&simplified_tree_mut);
+
+        // Filter after indexing too, so indices are the same with or without categories
+        let categories = &self.options.node_categories;
+        let filtered = !categories.is_empty() && !categories.contains(&NodeCategory::All);
+        if filtered {
+            retain_categories(&mut simplified_tree_mut, categories, false);
+        }
+
+        // Collapse repeated siblings after indexing, so every item keeps its index
+        if self.options.collapse_repeated_siblings {
+            Self::_collapse_repeated_siblings(
+                &mut simplified_tree_mut,
+                self.options
+                    .repeated_siblings_threshold
+                    .max(COLLAPSED_RUN_SHOWN + 1),
+            );
+        }
         let simplified_tree = simplified_tree_mut;
 
-        // Serialize to string
-        let serialized_string =
-            Self::serialize_tree(&simplified_tree, DEFAULT_INCLUDE_ATTRIBUTES, 0);
+        // Serialize to string; a bare html/head/body skeleton (about:blank) has nothing to show
+        let mut serialized_string = if is_blank {
+            String::new()
+        } else {
+            Self::serialize_tree(&simplified_tree, DEFAULT_INCLUDE_ATTRIBUTES, 0)
+        };
+        if filtered && !is_blank && serialized_string.trim().is_empty() {
+            serialized_string = NO_ELEMENTS_IN_CATEGORIES.to_string();
+        }
+
+        // Inner-scroll apps: scrolling the page would do nothing
+        if Self::is_document_scrollable(&self.root_node) == Some(false)
+            && self.elements.iter().any(|e| e.is_scrollable)
+        {
+            serialized_string = format!("{INNER_SCROLL_NOTE}\n{serialized_string}");
+        }
 
         let serialized_state = SerializedDOMState {
             html: None,
             text: Some(serialized_string.clone()),
             markdown: Some(serialized_string),
-            elements: vec![],
+            elements: self.elements,
             selector_map: self.selector_map,
+            truncated_values: vec![],
         };
 
         (serialized_state, HashMap::new())
     }
 
+    /// Serialize the interactive elements as structured entries
+    ///
+    /// Indices are the ones [`serialize_accessible_elements`](Self::serialize_accessible_elements)
+    /// shows in the text page state.
+    pub fn to_json_elements(self) -> Vec<SerializedElement> {
+        self.serialize_accessible_elements().0.elements
+    }
+
     /// Create simplified tree from enhanced DOM tree
     fn _create_simplified_tree(&self, node: &EnhancedDOMTreeNode) -> SimplifiedNode {
         let mut simplified = SimplifiedNode::new(node.clone());
 
-        // Determine if node should be displayed
-        simplified.should_display = self._should_display_node(node);
-
         // Process children
         if let Some(ref children) = node.children_nodes {
             for child in children {
@@ -111,11 +226,16 @@ impl DOMTreeSerializer {
             simplified.children.push(doc_simplified);
         }
 
+        // Determine if node should be displayed (needs children for focus checks)
+        simplified.should_display = self._should_display_node(&simplified);
+
         simplified
     }
 
     /// Check if node should be displayed
-    fn _should_display_node(&self, node: &EnhancedDOMTreeNode) -> bool {
+    fn _should_display_node(&self, simplified: &SimplifiedNode) -> bool {
+        let node = &simplified.original_node;
+
         // Skip disabled elements
         if let Some(attrs) = node.attributes.get("disabled") {
             if attrs.as_str() == "true" || attrs.as_str() == "disabled" {
@@ -123,20 +243,9 @@ impl DOMTreeSerializer {
             }
         }
 
-        // Skip hidden elements
-        if let Some(ref snapshot) = node.snapshot_node {
-            if let Some(ref styles) = snapshot.computed_styles {
-                if let Some(display) = styles.get("display") {
-                    if display == "none" {
-                        return false;
-                    }
-                }
-                if let Some(visibility) = styles.get("visibility") {
-                    if visibility == "hidden" {
-                        return false;
-                    }
-                }
-            }
+        // Skip hidden and visually imperceptible elements
+        if self.invisible_filter.filter(simplified) {
+            return false;
         }
 
         // Skip script and style tags
@@ -151,12 +260,50 @@ impl DOMTreeSerializer {
         true
     }
 
-    /// Assign interactive indices to clickable elements
-    fn _assign_interactive_indices(&mut self, simplified: &mut SimplifiedNode) {
+    /// Number `form` elements from 1 in document order, hidden ones included
+    fn _number_forms(&mut self, simplified: &SimplifiedNode) {
+        let node = &simplified.original_node;
+        if node.node_type == NodeType::ElementNode && node.tag_name() == "form" {
+            let number = self.form_numbers.len() as u32 + 1;
+            self.form_numbers.insert(node.backend_node_id, number);
+            if let Some(id) = node.attributes.get("id") {
+                self.form_ids.entry(id.clone()).or_insert(number);
+            }
+        }
+        for child in &simplified.children {
+            self._number_forms(child);
+        }
+    }
+
+    /// Assign interactive indices to clickable elements, inside the numbered `form` if any
+    ///
+    /// `frame` is the ID of the iframe document the element is in, if not the top-level one.
+    fn _assign_interactive_indices(
+        &mut self,
+        simplified: &mut SimplifiedNode,
+        form: Option<u32>,
+        frame: Option<&str>,
+    ) {
+        let form = self
+            .form_numbers
+            .get(&simplified.original_node.backend_node_id)
+            .copied()
+            .or(form);
+        if form.is_some() && simplified.original_node.tag_name() == "form" {
+            simplified.form_id = form;
+        }
+
+        // The only child of a frame owner is its content document
+        let owner_frame = simplified.original_node.frame_id.clone();
+        let child_frame = match simplified.original_node.content_document {
+            Some(_) => owner_frame.as_deref().or(frame),
+            None => frame,
+        };
+
         if !simplified.should_display {
             // Still process children
             for child in &mut simplified.children {
-                self._assign_interactive_indices(child);
+                self._assign_interactive_indices(child, form, child_frame);
             }
             return;
         }
@@ -170,13 +317,24 @@ impl DOMTreeSerializer {
             .and_then(|s| s.is_clickable)
             .unwrap_or(false)
             || self._is_interactive_element(node);
+        let is_scrollable = Self::_is_scroll_container(node);
 
-        if is_clickable {
+        if is_clickable || is_scrollable {
             let index = self.interactive_counter;
             self.interactive_counter += 1;
 
+            // A `form` attribute associates a control with a form elsewhere on the page
+            let form_id = node
+                .attributes
+                .get("form")
+                .and_then(|id| self.form_ids.get(id))
+                .copied()
+                .or(form);
+
             simplified.is_interactive = true;
             simplified.interactive_index = Some(index);
+            simplified.form_id = form_id;
+            simplified.is_scrollable = is_scrollable;
 
             // Create interacted element
             let interacted = DOMInteractedElement {
@@ -186,14 +344,138 @@ impl DOMTreeSerializer {
                 text: self._get_element_text(node),
                 attributes: node.attributes.clone(),
                 selector: None, // TODO: Generate XPath selector
+                bounds: node.snapshot_node.as_ref().and_then(|s| s.bounds),
+                form_id,
+                frame_id: frame.map(str::to_string),
             };
 
             self.selector_map.insert(index, interacted);
+            self.elements.push(Self::_element_entry(simplified, index, form_id));
         }
 
         // Process children
         for child in &mut simplified.children {
-            self._assign_interactive_indices(child);
+            self._assign_interactive_indices(child, form, child_frame);
+        }
+    }
+
+    /// Structured entry for an interactive element
+    fn _element_entry(simplified: &SimplifiedNode, index: u32, form_id: Option<u32>) -> SerializedElement {
+        let node = &simplified.original_node;
+        let ax = node.ax_node.as_ref();
+        let non_empty = |value: Option<&String>| value.filter(|v| !v.trim().is_empty()).cloned();
+
+        let mut text = String::new();
+        Self::_collect_text(simplified, &mut text);
+        let text = match text.char_indices().nth(MAX_ELEMENT_TEXT_CHARS) {
+            Some((end, _)) => format!("{}…", &text[..end]),
+            None => text,
+        };
+        let text = Some(text).filter(|t| !t.is_empty()).or_else(|| {
+            ["value", "placeholder"]
+                .iter()
+                .find_map(|attr| non_empty(node.attributes.get(*attr)))
+        });
+
+        SerializedElement {
+            index,
+            tag: node.tag_name(),
+            role: non_empty(node.attributes.get("role"))
+                .or_else(|| non_empty(ax.and_then(|ax| ax.role.as_ref()))),
+            name: non_empty(ax.and_then(|ax| ax.name.as_ref()))
+                .or_else(|| non_empty(node.attributes.get("aria-label")))
+                .or_else(|| non_empty(node.attributes.get("alt")))
+                .or_else(|| non_empty(node.attributes.get("title"))),
+            text,
+            rect: node.snapshot_node.as_ref().and_then(|s| s.bounds),
+            href: non_empty(node.attributes.get("href")),
+            form_id,
+            is_visible: node.is_visible.unwrap_or(simplified.should_display),
+            is_scrollable: simplified.is_scrollable,
+        }
+    }
+
+    /// Whether `node` is an element inside the page whose content scrolls
+    ///
+    /// Chrome's `isScrollable` is used when present. Otherwise the content
+    /// must overflow the element's client area on an axis whose `overflow`
+    /// lets the user scroll it.
+    fn _is_scroll_container(node: &EnhancedDOMTreeNode) -> bool {
+        if node.node_type != NodeType::ElementNode
+            || matches!(node.tag_name().as_str(), "html" | "body")
+        {
+            return false;
+        }
+        if let Some(is_scrollable) = node.is_scrollable {
+            return is_scrollable;
+        }
+        Self::_overflows(node, &["auto", "scroll", "overlay"])
+    }
+
+    /// Whether the content of `node` overflows its client area on an axis
+    /// whose `overflow` is one of `scrolling`
+    fn _overflows(node: &EnhancedDOMTreeNode, scrolling: &[&str]) -> bool {
+        let Some(snapshot) = node.snapshot_node.as_ref() else {
+            return false;
+        };
+        let (Some(client), Some(scroll)) = (snapshot.client_rects, snapshot.scroll_rects) else {
+            return false;
+        };
+        let styles = snapshot.computed_styles.as_ref();
+        let overflow = |axis: &str| {
+            styles
+                .and_then(|s| s.get(axis).or_else(|| s.get("overflow")))
+                .map(String::as_str)
+                .unwrap_or("visible")
+        };
+        (scroll.height > client.height + 1.0 && scrolling.contains(&overflow("overflow-y")))
+            || (scroll.width > client.width + 1.0 && scrolling.contains(&overflow("overflow-x")))
+    }
+
+    /// Whether the document itself scrolls, `None` without layout data for it
+    ///
+    /// The document scrolls through the root (`#document`, `html`) or, in
+    /// quirks mode and some layouts, `body`. Their `overflow` is `visible`
+    /// unless the page hides it.
+    fn is_document_scrollable(root: &EnhancedDOMTreeNode) -> Option<bool> {
+        let mut measured = false;
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            let is_root = node.node_type == NodeType::DocumentNode
+                || matches!(node.tag_name().as_str(), "html" | "body");
+            if !is_root {
+                continue;
+            }
+            let has_rects = node
+                .snapshot_node
+                .as_ref()
+                .is_some_and(|s| s.client_rects.is_some() && s.scroll_rects.is_some());
+            if has_rects {
+                measured = true;
+                if node.is_scrollable == Some(true)
+                    || Self::_overflows(node, &["visible", "auto", "scroll", "overlay"])
+                {
+                    return Some(true);
+                }
+            }
+            stack.extend(node.children_nodes.iter().flatten());
+        }
+        measured.then_some(false)
+    }
+
+    /// Append the displayed text below `simplified`, separated by single spaces
+    fn _collect_text(simplified: &SimplifiedNode, text: &mut String) {
+        let node = &simplified.original_node;
+        if node.node_type == NodeType::TextNode && simplified.should_display {
+            for word in node.node_value.split_whitespace() {
+                if !text.is_empty() {
+                    text.push(' ');
+                }
+                text.push_str(word);
+            }
+        }
+        for child in &simplified.children {
+            Self::_collect_text(child, text);
         }
     }
 
@@ -272,9 +554,18 @@ impl DOMTreeSerializer {
                     parts.push(attrs_str);
                 }
 
-                // Add index if interactive
+                // Add index if interactive; scroll containers take `container_index`
                 if let Some(index) = node.interactive_index {
-                    parts.push(format!("[{index}]"));
+                    if node.is_scrollable {
+                        parts.push(format!("[scrollable {index}]"));
+                    } else {
+                        parts.push(format!("[{index}]"));
+                    }
+                }
+
+                // Which form a control submits, so the right submit button can be found
+                if let Some(form_id) = node.form_id {
+                    parts.push(format!("form=#{form_id}"));
                 }
 
                 formatted_text.push(format!("{}{}", depth_str, parts.join(" ")));
@@ -307,6 +598,113 @@ impl DOMTreeSerializer {
         formatted_text.join("\n")
     }
 
+    /// Replace the tail of each run of structurally identical siblings below
+    /// `simplified` with a summary line, returning the subtree's signature
+    ///
+    /// Signatures are computed before anything below is collapsed, so nested
+    /// runs do not make otherwise identical items differ.
+    fn _collapse_repeated_siblings(simplified: &mut SimplifiedNode, threshold: usize) -> String {
+        let child_signatures: Vec<String> = simplified
+            .children
+            .iter_mut()
+            .map(|child| Self::_collapse_repeated_siblings(child, threshold))
+            .collect();
+
+        let node = &simplified.original_node;
+        let signature = match node.node_type {
+            NodeType::ElementNode => {
+                let mut classes: Vec<&str> = node
+                    .attributes
+                    .get("class")
+                    .map(|c| c.split_whitespace().collect())
+                    .unwrap_or_default();
+                classes.sort_unstable();
+                format!(
+                    "{}.{}({})",
+                    node.tag_name(),
+                    classes.join("."),
+                    child_signatures.join(",")
+                )
+            }
+            NodeType::TextNode => "#text".to_string(),
+            _ => format!("#other({})", child_signatures.join(",")),
+        };
+
+        // Runs of element siblings with one signature; blank text between them is skipped
+        let mut runs: Vec<Vec<usize>> = Vec::new();
+        let mut run: Vec<usize> = Vec::new();
+        for (i, child) in simplified.children.iter().enumerate() {
+            let child_node = &child.original_node;
+            let blank = child_node.node_value.trim().is_empty();
+            if child_node.node_type == NodeType::TextNode && blank {
+                continue;
+            }
+            let continues = child_node.node_type == NodeType::ElementNode
+                && run
+                    .last()
+                    .is_some_and(|&last| child_signatures[last] == child_signatures[i]);
+            if !continues {
+                runs.push(std::mem::take(&mut run));
+            }
+            if child_node.node_type == NodeType::ElementNode {
+                run.push(i);
+            }
+        }
+        runs.push(run);
+
+        // Replace from the back, so earlier positions stay valid
+        for run in runs.into_iter().rev().filter(|run| run.len() >= threshold) {
+            let first_hidden = run[COLLAPSED_RUN_SHOWN];
+            let last = run[run.len() - 1];
+            let hidden: Vec<SimplifiedNode> =
+                simplified.children.drain(first_hidden..=last).collect();
+            let mut indices = Vec::new();
+            for item in &hidden {
+                Self::_collect_indices(item, &mut indices);
+            }
+            let mut summary_text =
+                format!("… {} more similar items", run.len() - COLLAPSED_RUN_SHOWN);
+            if let (Some(low), Some(high)) = (indices.iter().min(), indices.iter().max()) {
+                summary_text.push_str(&format!(" (indices {low}–{high})"));
+            }
+            let mut summary_node = hidden[0].original_node.clone();
+            summary_node.node_type = NodeType::TextNode;
+            summary_node.node_name = "#text".to_string();
+            summary_node.node_value = summary_text;
+            summary_node.attributes.clear();
+            simplified
+                .children
+                .insert(first_hidden, SimplifiedNode::new(summary_node));
+        }
+
+        signature
+    }
+
+    /// Interactive indices in the subtree of `simplified`
+    fn _collect_indices(simplified: &SimplifiedNode, indices: &mut Vec<u32>) {
+        indices.extend(simplified.interactive_index);
+        for child in &simplified.children {
+            Self::_collect_indices(child, indices);
+        }
+    }
+
+    /// Whether the tree holds no text, interactive elements or tags beyond the document skeleton
+    fn is_blank(node: &SimplifiedNode) -> bool {
+        let children_blank = || node.children.iter().all(Self::is_blank);
+        if !node.should_display {
+            return children_blank();
+        }
+        match node.original_node.node_type {
+            NodeType::ElementNode => {
+                node.interactive_index.is_none()
+                    && matches!(node.original_node.tag_name().as_str(), "html" | "head" | "body")
+                    && children_blank()
+            }
+            NodeType::TextNode => node.original_node.node_value.trim().len() <= 1,
+            _ => children_blank(),
+        }
+    }
+
     /// Serialize children only
     fn _serialize_children(
         node: &SimplifiedNode,
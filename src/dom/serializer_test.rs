@@ -45,6 +45,7 @@ mod tests {
             markdown: None,
             elements: vec![],
             selector_map: HashMap::new(),
+            truncated_values: vec![],
         };
 
         assert_eq!(state.text, Some("test".to_string()));
@@ -59,6 +60,7 @@ mod tests {
             markdown: Some("# Test".to_string()),
             elements: vec![],
             selector_map: HashMap::new(),
+            truncated_values: vec![],
         };
 
         // Should prefer markdown
@@ -74,6 +76,7 @@ mod tests {
             markdown: None,
             elements: vec![],
             selector_map: HashMap::new(),
+            truncated_values: vec![],
         };
 
         // Should fallback to text
@@ -89,10 +92,64 @@ mod tests {
             markdown: None,
             elements: vec![],
             selector_map: HashMap::new(),
+            truncated_values: vec![],
         };
 
         // Should fallback to HTML
         let repr = state.llm_representation(None);
         assert_eq!(repr, Some("<div>test</div>".to_string()));
     }
+
+    #[test]
+    fn test_to_json_elements_matches_selector_map() {
+        let mut root = create_test_dom_node();
+        let mut button = EnhancedDOMTreeNode::new(
+            2,
+            2,
+            NodeType::ElementNode,
+            "BUTTON".to_string(),
+            "".to_string(),
+            "target-1".to_string(),
+        );
+        button.children_nodes = Some(vec![EnhancedDOMTreeNode::new(
+            3,
+            3,
+            NodeType::TextNode,
+            "#text".to_string(),
+            "  Add to\n cart ".to_string(),
+            "target-1".to_string(),
+        )]);
+        root.children_nodes = Some(vec![button]);
+
+        let (state, _) = DOMTreeSerializer::new(root.clone()).serialize_accessible_elements();
+        let elements = DOMTreeSerializer::new(root).to_json_elements();
+
+        assert_eq!(elements, state.elements);
+        assert_eq!(elements.len(), 1);
+        assert_eq!(elements[0].index, 1);
+        assert_eq!(elements[0].tag, "button");
+        assert_eq!(elements[0].text.as_deref(), Some("Add to cart"));
+        assert!(state.selector_map.contains_key(&1));
+    }
+
+    #[test]
+    fn test_chrome_scrollable_flag_marks_container() {
+        let mut root = create_test_dom_node();
+        let mut list = EnhancedDOMTreeNode::new(
+            2,
+            2,
+            NodeType::ElementNode,
+            "UL".to_string(),
+            "".to_string(),
+            "target-1".to_string(),
+        );
+        list.is_scrollable = Some(true);
+        root.children_nodes = Some(vec![list]);
+
+        let (state, _) = DOMTreeSerializer::new(root).serialize_accessible_elements();
+
+        // Without layout data for the document there is no note about it
+        assert_eq!(state.text.as_deref(), Some("div\n\tul [scrollable 1]"));
+        assert!(state.elements[0].is_scrollable);
+    }
 }
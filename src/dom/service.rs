@@ -2,8 +2,8 @@
 
 use crate::browser::{Browser, cdp::CdpClient};
 use crate::dom::ax_node::build_enhanced_ax_node;
-use crate::dom::cdp_client::DOMCDPClient;
-use crate::dom::enhanced_snapshot::build_snapshot_lookup;
+use crate::dom::cdp_client::{DOMCDPClient, empty_document};
+use crate::dom::enhanced_snapshot::{SnapshotOptions, build_snapshot_lookup_with_options};
 use crate::dom::html_converter::HTMLConverter;
 use crate::dom::serializer::DOMTreeSerializer;
 use crate::dom::views::{
@@ -36,6 +36,8 @@ pub struct DomService {
     /// Maximum iframe depth to handle
     #[allow(dead_code)]
     max_iframe_depth: usize,
+    /// What the DOM snapshot captures
+    snapshot_options: SnapshotOptions,
 }
 
 impl DomService {
@@ -50,6 +52,7 @@ impl DomService {
             paint_order_filtering: true,
             max_iframes: 100,
             max_iframe_depth: 5,
+            snapshot_options: SnapshotOptions::default(),
         }
     }
 
@@ -82,6 +85,31 @@ impl DomService {
         self
     }
 
+    /// Sets what the DOM snapshot captures
+    pub fn with_snapshot_options(mut self, options: SnapshotOptions) -> Self {
+        self.snapshot_options = options;
+        self
+    }
+
+    /// Sets the computed styles the DOM snapshot captures
+    ///
+    /// Defaults to [`REQUIRED_COMPUTED_STYLES`](crate::dom::enhanced_snapshot::REQUIRED_COMPUTED_STYLES).
+    pub fn with_computed_styles<I, S>(mut self, styles: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.snapshot_options = self.snapshot_options.with_computed_styles(styles);
+        self
+    }
+
+    /// Sets whether the DOM snapshot captures blended background colors and
+    /// text color opacities
+    pub fn with_color_capture(mut self, enabled: bool) -> Self {
+        self.snapshot_options = self.snapshot_options.with_color_capture(enabled);
+        self
+    }
+
     /// Extract page content from HTML
     pub async fn extract_page_content(&self, html: &str) -> Result<String> {
         HTMLConverter::extract_page_content(html)
@@ -111,8 +139,8 @@ impl DomService {
             BrowsingError::Dom("No CDP client available".to_string())
         })?;
         let dom_cdp = DOMCDPClient::new(Arc::clone(cdp), self.session_id.clone());
-        let (snapshot, dom_tree, ax_tree, device_pixel_ratio) =
-            dom_cdp.get_all_trees(target_id).await?;
+        let (snapshot, dom_tree, ax_tree, device_pixel_ratio, _viewport) =
+            dom_cdp.get_all_trees(target_id, &self.snapshot_options).await?;
 
         // Build AX tree lookup
         let mut ax_tree_lookup: HashMap<u64, Value> = HashMap::new();
@@ -126,15 +154,18 @@ impl DomService {
         }
 
         // Build snapshot lookup
-        let snapshot_lookup = build_snapshot_lookup(&snapshot, device_pixel_ratio)?;
+        let snapshot_lookup = build_snapshot_lookup_with_options(
+            &snapshot,
+            device_pixel_ratio,
+            &self.snapshot_options,
+        )?;
 
         // Build enhanced DOM tree node lookup (memoization)
         let mut enhanced_dom_tree_node_lookup: HashMap<u64, EnhancedDOMTreeNode> = HashMap::new();
 
-        // Get root node from DOM tree
-        let root_node = dom_tree
-            .get("root")
-            .ok_or_else(|| BrowsingError::Dom("No root node in DOM tree".to_string()))?;
+        // Get root node from DOM tree; a page that has no document yet is empty
+        let root_node = dom_tree.get("root").cloned().unwrap_or_else(empty_document);
+        let root_node = &root_node;
 
         // Recursively construct enhanced nodes
         let enhanced_root = self._construct_enhanced_node(
@@ -352,9 +383,7 @@ impl DomService {
     /// Get page state as string for LLM consumption
     pub async fn get_page_state_string(&self) -> Result<String> {
         let (serialized_state, _, _) = self.get_serialized_dom_tree(None).await?;
-        Ok(serialized_state
-            .llm_representation(None)
-            .unwrap_or_else(|| "Empty DOM tree".to_string()))
+        Ok(serialized_state.page_state())
     }
 
     /// Get selector map (index -> element mapping)
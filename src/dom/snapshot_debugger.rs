@@ -0,0 +1,477 @@
+//! Human-readable introspection of raw `DOMSnapshot.captureSnapshot` results
+//!
+//! Snapshots are column-oriented and store every string once in a shared
+//! table, so working out why an element is missing from the selector map by
+//! reading the JSON is slow. [`SnapshotDebugger`] summarizes a snapshot (tags,
+//! missing fields, computed style coverage) and decodes single nodes.
+
+use crate::dom::enhanced_snapshot::REQUIRED_COMPUTED_STYLES;
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Node fields the snapshot parser and tree builder rely on
+const REQUIRED_NODE_FIELDS: &[&str] = &["backendNodeId", "nodeName", "nodeType", "parentIndex"];
+
+/// Layout fields the snapshot parser relies on
+const REQUIRED_LAYOUT_FIELDS: &[&str] = &["nodeIndex", "bounds", "styles"];
+
+/// `nodeType` of element nodes
+const ELEMENT_NODE: u64 = 1;
+
+/// Statistics of one document of a snapshot
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DocumentSummary {
+    /// Document URL, if recorded
+    pub url: Option<String>,
+    /// Number of nodes of any type
+    pub node_count: usize,
+    /// Number of element nodes
+    pub element_count: usize,
+    /// Number of nodes in the layout tree
+    pub layout_node_count: usize,
+    /// Element nodes without a layout node; these never get bounds or styles
+    pub elements_without_layout: usize,
+    /// Required fields absent from the document, e.g. `layout.styles`
+    pub missing_fields: Vec<String>,
+}
+
+/// Statistics of a whole snapshot
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SnapshotSummary {
+    /// Size of the shared string table
+    pub string_count: usize,
+    /// Per-document statistics, in snapshot order
+    pub documents: Vec<DocumentSummary>,
+    /// Element count per tag name across all documents
+    pub tag_counts: BTreeMap<String, usize>,
+    /// Layout nodes with a non-empty value, per required computed style
+    pub style_coverage: Vec<(String, usize)>,
+    /// Required top-level fields absent from the snapshot
+    pub missing_fields: Vec<String>,
+}
+
+impl SnapshotSummary {
+    /// Total layout nodes across all documents
+    pub fn layout_node_count(&self) -> usize {
+        self.documents.iter().map(|d| d.layout_node_count).sum()
+    }
+}
+
+impl fmt::Display for SnapshotSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Snapshot: {} documents, {} strings",
+            self.documents.len(),
+            self.string_count
+        )?;
+        if !self.missing_fields.is_empty() {
+            writeln!(f, "  missing fields: {}", self.missing_fields.join(", "))?;
+        }
+
+        for (i, document) in self.documents.iter().enumerate() {
+            writeln!(
+                f,
+                "Document {} ({}): {} nodes, {} elements, {} layout nodes, {} elements without layout",
+                i,
+                document.url.as_deref().unwrap_or("unknown URL"),
+                document.node_count,
+                document.element_count,
+                document.layout_node_count,
+                document.elements_without_layout
+            )?;
+            if !document.missing_fields.is_empty() {
+                writeln!(
+                    f,
+                    "  missing fields: {}",
+                    document.missing_fields.join(", ")
+                )?;
+            }
+        }
+
+        if !self.tag_counts.is_empty() {
+            writeln!(f, "Tags:")?;
+            let mut tags: Vec<_> = self.tag_counts.iter().collect();
+            tags.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+            for (tag, count) in tags {
+                writeln!(f, "  {tag:<12} {count}")?;
+            }
+        }
+
+        let layout_nodes = self.layout_node_count();
+        writeln!(f, "Style coverage (of {layout_nodes} layout nodes):")?;
+        for (style, count) in &self.style_coverage {
+            let percent = if layout_nodes == 0 {
+                0.0
+            } else {
+                *count as f64 * 100.0 / layout_nodes as f64
+            };
+            writeln!(f, "  {style:<18} {count} ({percent:.0}%)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Summaries and node lookups of raw CDP snapshots
+pub struct SnapshotDebugger;
+
+impl SnapshotDebugger {
+    /// Collect statistics of a `DOMSnapshot.captureSnapshot` result
+    pub fn summarize(snapshot: &Value) -> SnapshotSummary {
+        let strings = strings(snapshot);
+        let mut missing_fields = Vec::new();
+        for field in ["documents", "strings"] {
+            if snapshot.get(field).is_none() {
+                missing_fields.push(field.to_string());
+            }
+        }
+
+        let mut tag_counts = BTreeMap::new();
+        let mut style_coverage = vec![0; REQUIRED_COMPUTED_STYLES.len()];
+        let documents = documents(snapshot)
+            .iter()
+            .map(|document| {
+                summarize_document(document, &strings, &mut tag_counts, &mut style_coverage)
+            })
+            .collect();
+
+        SnapshotSummary {
+            string_count: strings.len(),
+            documents,
+            tag_counts,
+            style_coverage: REQUIRED_COMPUTED_STYLES
+                .iter()
+                .map(|style| style.to_string())
+                .zip(style_coverage)
+                .collect(),
+            missing_fields,
+        }
+    }
+
+    /// Print the summary of a snapshot to stdout
+    pub fn print_document_summary(snapshot: &Value) {
+        print!("{}", Self::summarize(snapshot));
+    }
+
+    /// Decode the node with `backend_node_id`, with its attributes and layout
+    ///
+    /// Returns `None` if no document of the snapshot has the node.
+    pub fn find_backend_node_id(snapshot: &Value, backend_node_id: u64) -> Option<Value> {
+        let strings = strings(snapshot);
+        documents(snapshot)
+            .iter()
+            .enumerate()
+            .find_map(|(document_index, document)| {
+                let nodes = document.get("nodes")?;
+                let node_index = nodes
+                    .get("backendNodeId")?
+                    .as_array()?
+                    .iter()
+                    .position(|id| id.as_u64() == Some(backend_node_id))?;
+                Some(describe_node(
+                    document,
+                    document_index,
+                    node_index,
+                    backend_node_id,
+                    &strings,
+                ))
+            })
+    }
+}
+
+fn documents(snapshot: &Value) -> &[Value] {
+    snapshot
+        .get("documents")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn strings(snapshot: &Value) -> Vec<&str> {
+    snapshot
+        .get("strings")
+        .and_then(|v| v.as_array())
+        .map(|arr| arr.iter().map(|v| v.as_str().unwrap_or_default()).collect())
+        .unwrap_or_default()
+}
+
+/// Look up a string table index; `-1` and out-of-range indices have no string
+fn string_at<'a>(strings: &[&'a str], index: &Value) -> Option<&'a str> {
+    index
+        .as_u64()
+        .and_then(|i| strings.get(i as usize))
+        .copied()
+}
+
+/// The `index`-th entry of a column of `object`
+fn column<'a>(object: &'a Value, name: &str, index: usize) -> Option<&'a Value> {
+    object.get(name)?.as_array()?.get(index)
+}
+
+fn summarize_document(
+    document: &Value,
+    strings: &[&str],
+    tag_counts: &mut BTreeMap<String, usize>,
+    style_coverage: &mut [usize],
+) -> DocumentSummary {
+    let empty = Value::Null;
+    let nodes = document.get("nodes").unwrap_or(&empty);
+    let layout = document.get("layout");
+
+    let mut missing_fields = Vec::new();
+    if document.get("nodes").is_none() {
+        missing_fields.push("nodes".to_string());
+    } else {
+        for field in REQUIRED_NODE_FIELDS {
+            if nodes.get(field).is_none() {
+                missing_fields.push(format!("nodes.{field}"));
+            }
+        }
+    }
+    match layout {
+        Some(layout) => {
+            for field in REQUIRED_LAYOUT_FIELDS {
+                if layout.get(field).is_none() {
+                    missing_fields.push(format!("layout.{field}"));
+                }
+            }
+        }
+        None => missing_fields.push("layout".to_string()),
+    }
+
+    let node_types = nodes
+        .get("nodeType")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let node_names = nodes
+        .get("nodeName")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let node_count = node_types.len().max(node_names.len());
+
+    let layout_nodes: Vec<usize> = layout
+        .and_then(|layout| layout.get("nodeIndex"))
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_u64())
+                .map(|v| v as usize)
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut has_layout = vec![false; node_count];
+    for &node_index in &layout_nodes {
+        if let Some(flag) = has_layout.get_mut(node_index) {
+            *flag = true;
+        }
+    }
+
+    let mut element_count = 0;
+    let mut elements_without_layout = 0;
+    for (i, node_type) in node_types.iter().enumerate() {
+        if node_type.as_u64() != Some(ELEMENT_NODE) {
+            continue;
+        }
+        element_count += 1;
+        if !has_layout[i] {
+            elements_without_layout += 1;
+        }
+        let tag = node_names
+            .get(i)
+            .and_then(|name| string_at(strings, name))
+            .unwrap_or("?");
+        *tag_counts.entry(tag.to_uppercase()).or_insert(0) += 1;
+    }
+
+    if let Some(styles) = layout
+        .and_then(|layout| layout.get("styles"))
+        .and_then(|v| v.as_array())
+    {
+        for style_indices in styles.iter().filter_map(|v| v.as_array()) {
+            for (count, index) in style_coverage.iter_mut().zip(style_indices) {
+                if string_at(strings, index).is_some_and(|value| !value.is_empty()) {
+                    *count += 1;
+                }
+            }
+        }
+    }
+
+    DocumentSummary {
+        url: document
+            .get("documentURL")
+            .and_then(|index| string_at(strings, index))
+            .map(str::to_string),
+        node_count,
+        element_count,
+        layout_node_count: layout_nodes.len(),
+        elements_without_layout,
+        missing_fields,
+    }
+}
+
+fn describe_node(
+    document: &Value,
+    document_index: usize,
+    node_index: usize,
+    backend_node_id: u64,
+    strings: &[&str],
+) -> Value {
+    let nodes = &document["nodes"];
+    let string_column =
+        |name: &str| column(nodes, name, node_index).and_then(|index| string_at(strings, index));
+
+    let mut attributes = Map::new();
+    if let Some(pairs) = column(nodes, "attributes", node_index).and_then(|v| v.as_array()) {
+        for pair in pairs.chunks(2) {
+            if let [name, value] = pair
+                && let Some(name) = string_at(strings, name)
+            {
+                let value = string_at(strings, value).unwrap_or_default();
+                attributes.insert(name.to_string(), json!(value));
+            }
+        }
+    }
+
+    let is_clickable = nodes
+        .get("isClickable")
+        .and_then(|rare| rare.get("index"))
+        .and_then(|v| v.as_array())
+        .is_some_and(|indices| {
+            indices
+                .iter()
+                .any(|i| i.as_u64() == Some(node_index as u64))
+        });
+
+    let layout = document.get("layout").and_then(|layout| {
+        let layout_index = layout
+            .get("nodeIndex")?
+            .as_array()?
+            .iter()
+            .position(|i| i.as_u64() == Some(node_index as u64))?;
+        let styles: Map<String, Value> = column(layout, "styles", layout_index)
+            .and_then(|v| v.as_array())
+            .map(|indices| {
+                REQUIRED_COMPUTED_STYLES
+                    .iter()
+                    .zip(indices)
+                    .filter_map(|(style, index)| {
+                        string_at(strings, index).map(|value| (style.to_string(), json!(value)))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Some(json!({
+            "layout_index": layout_index,
+            "bounds": column(layout, "bounds", layout_index),
+            "styles": styles,
+            "paint_order": column(layout, "paintOrders", layout_index),
+        }))
+    });
+
+    json!({
+        "document": document_index,
+        "document_url": document.get("documentURL").and_then(|index| string_at(strings, index)),
+        "node_index": node_index,
+        "backend_node_id": backend_node_id,
+        "node_type": column(nodes, "nodeType", node_index),
+        "node_name": string_column("nodeName"),
+        "node_value": string_column("nodeValue"),
+        "parent_index": column(nodes, "parentIndex", node_index),
+        "attributes": attributes,
+        "is_clickable": is_clickable,
+        "layout": layout,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `<html><body><a href="/docs">Docs</a><input type="hidden"></body></html>`
+    fn snapshot() -> Value {
+        json!({
+            "strings": [
+                "https://example.com/", "HTML", "BODY", "A", "href", "/docs", "#text", "Docs",
+                "INPUT", "type", "hidden", "block", "visible", "1", "inline", "pointer", ""
+            ],
+            "documents": [{
+                "documentURL": 0,
+                "nodes": {
+                    "backendNodeId": [10, 11, 12, 13, 14],
+                    "nodeName": [1, 2, 3, 6, 8],
+                    "nodeType": [1, 1, 1, 3, 1],
+                    "nodeValue": [-1, -1, -1, 7, -1],
+                    "parentIndex": [-1, 0, 1, 2, 1],
+                    "attributes": [[], [], [4, 5], [], [9, 10]],
+                    "isClickable": { "index": [2] }
+                },
+                "layout": {
+                    "nodeIndex": [0, 1, 2, 3],
+                    "bounds": [[0, 0, 800, 600], [8, 8, 784, 20], [8, 8, 40, 20], [8, 8, 40, 20]],
+                    "styles": [[11, 12, 13], [11, 12, 13], [14, 12, 13, 16, 16, 16, 15], []]
+                }
+            }]
+        })
+    }
+
+    #[test]
+    fn test_summarize() {
+        let summary = SnapshotDebugger::summarize(&snapshot());
+
+        assert_eq!(summary.string_count, 17);
+        let document = &summary.documents[0];
+        assert_eq!(document.url.as_deref(), Some("https://example.com/"));
+        assert_eq!(document.node_count, 5);
+        assert_eq!(document.element_count, 4);
+        assert_eq!(document.layout_node_count, 4);
+        // The hidden input has no layout node
+        assert_eq!(document.elements_without_layout, 1);
+        assert!(document.missing_fields.is_empty());
+        assert_eq!(summary.tag_counts["INPUT"], 1);
+        assert_eq!(summary.tag_counts.get("#TEXT"), None);
+        assert_eq!(summary.style_coverage[0], ("display".to_string(), 3));
+        // Empty strings do not count as a value
+        assert_eq!(summary.style_coverage[3], ("overflow".to_string(), 0));
+        assert_eq!(summary.style_coverage[6], ("cursor".to_string(), 1));
+    }
+
+    #[test]
+    fn test_missing_fields() {
+        let summary = SnapshotDebugger::summarize(&json!({
+            "documents": [{ "nodes": { "nodeName": [], "nodeType": [] } }]
+        }));
+
+        assert_eq!(summary.missing_fields, ["strings"]);
+        assert_eq!(
+            summary.documents[0].missing_fields,
+            ["nodes.backendNodeId", "nodes.parentIndex", "layout"]
+        );
+        let report = summary.to_string();
+        assert!(report.contains("missing fields: nodes.backendNodeId, nodes.parentIndex, layout"));
+        assert!(report.contains("Style coverage (of 0 layout nodes):"));
+    }
+
+    #[test]
+    fn test_find_backend_node_id() {
+        let node = SnapshotDebugger::find_backend_node_id(&snapshot(), 12).unwrap();
+
+        assert_eq!(node["node_name"], "A");
+        assert_eq!(node["node_index"], 2);
+        assert_eq!(node["parent_index"], 1);
+        assert_eq!(node["attributes"], json!({ "href": "/docs" }));
+        assert_eq!(node["is_clickable"], true);
+        assert_eq!(node["layout"]["bounds"], json!([8, 8, 40, 20]));
+        assert_eq!(node["layout"]["styles"]["display"], "inline");
+        assert_eq!(node["layout"]["styles"]["cursor"], "pointer");
+
+        let text = SnapshotDebugger::find_backend_node_id(&snapshot(), 13).unwrap();
+        assert_eq!(text["node_value"], "Docs");
+        let hidden = SnapshotDebugger::find_backend_node_id(&snapshot(), 14).unwrap();
+        assert_eq!(hidden["layout"], Value::Null);
+        assert!(SnapshotDebugger::find_backend_node_id(&snapshot(), 99).is_none());
+    }
+}
@@ -3,20 +3,32 @@
 //! This module handles the construction of enhanced DOM trees from CDP data.
 
 use crate::dom::ax_node::build_enhanced_ax_node;
-use crate::dom::cdp_client::DOMCDPClient;
-use crate::dom::enhanced_snapshot::build_snapshot_lookup;
+use crate::dom::cdp_client::{DOMCDPClient, empty_document};
+use crate::dom::enhanced_snapshot::{SnapshotOptions, build_snapshot_lookup_with_options};
 use crate::dom::views::{
-    EnhancedAXNode, EnhancedDOMTreeNode, EnhancedSnapshotNode, NodeType,
+    DOMRect, EnhancedAXNode, EnhancedDOMTreeNode, EnhancedSnapshotNode, NodeType,
 };
 use crate::error::{BrowsingError, Result};
 use serde_json::Value;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+
+/// Longest attribute or text value kept by default, in bytes
+///
+/// Inline SVG paths and data URLs can run to megabytes; nothing past this is
+/// useful to the model.
+pub const DEFAULT_MAX_VALUE_LEN: usize = 64 * 1024;
 
 /// Builder for enhanced DOM trees
 pub struct DOMTreeBuilder {
     cdp_client: Arc<DOMCDPClient>,
     current_target_id: Option<String>,
+    max_value_len: usize,
+    snapshot_options: SnapshotOptions,
+    /// Values truncated while building, one line each
+    warnings: Mutex<Vec<String>>,
+    /// Layout viewport reported alongside the last build
+    viewport: Mutex<Option<DOMRect>>,
 }
 
 impl DOMTreeBuilder {
@@ -25,9 +37,35 @@ impl DOMTreeBuilder {
         Self {
             cdp_client,
             current_target_id,
+            max_value_len: DEFAULT_MAX_VALUE_LEN,
+            snapshot_options: SnapshotOptions::default(),
+            warnings: Mutex::new(Vec::new()),
+            viewport: Mutex::new(None),
         }
     }
 
+    /// Truncate attribute and text values longer than `max_value_len` bytes
+    pub fn with_max_value_len(mut self, max_value_len: usize) -> Self {
+        self.max_value_len = max_value_len;
+        self
+    }
+
+    /// Capture the snapshot with `options`
+    pub fn with_snapshot_options(mut self, options: SnapshotOptions) -> Self {
+        self.snapshot_options = options;
+        self
+    }
+
+    /// Warnings about values truncated by the last build
+    pub fn warnings(&self) -> Vec<String> {
+        self.warnings.lock().unwrap().clone()
+    }
+
+    /// Layout viewport of the page at the last build, in document CSS pixels
+    pub fn viewport(&self) -> Option<DOMRect> {
+        *self.viewport.lock().unwrap()
+    }
+
     /// Build enhanced DOM tree for the current target
     pub async fn build_tree(&self) -> Result<EnhancedDOMTreeNode> {
         let target = self.current_target_id.clone().ok_or_else(|| {
@@ -39,8 +77,12 @@ impl DOMTreeBuilder {
 
     /// Build enhanced DOM tree for a specific target ID
     async fn build_tree_by_target(&self, target_id: &str) -> Result<EnhancedDOMTreeNode> {
-        let (snapshot, dom_tree, ax_tree, _device_pixel_ratio) =
-            self.cdp_client.get_all_trees(target_id).await?;
+        self.warnings.lock().unwrap().clear();
+        let (snapshot, dom_tree, ax_tree, _device_pixel_ratio, viewport) = self
+            .cdp_client
+            .get_all_trees(target_id, &self.snapshot_options)
+            .await?;
+        *self.viewport.lock().unwrap() = viewport;
 
         // Build AX tree lookup
         let mut ax_tree_lookup: HashMap<u64, Value> = HashMap::new();
@@ -55,16 +97,16 @@ impl DOMTreeBuilder {
         }
 
         // Build snapshot lookup
-        let snapshot_lookup = build_snapshot_lookup(&snapshot, 1.0)?;
+        let snapshot_lookup =
+            build_snapshot_lookup_with_options(&snapshot, 1.0, &self.snapshot_options)?;
 
         // Build enhanced DOM tree node lookup (memoization)
         let mut enhanced_dom_tree_node_lookup: HashMap<u64, EnhancedDOMTreeNode> =
             HashMap::new();
 
-        // Get root node from DOM tree
-        let root_node = dom_tree
-            .get("root")
-            .ok_or_else(|| BrowsingError::Dom("No root node in DOM tree".to_string()))?;
+        // Get root node from DOM tree; a page that has no document yet is empty
+        let root_node = dom_tree.get("root").cloned().unwrap_or_else(empty_document);
+        let root_node = &root_node;
 
         // Recursively construct enhanced nodes
         let context = BuildContext {
@@ -96,9 +138,12 @@ impl DOMTreeBuilder {
             return Ok(existing.clone());
         }
 
-        let attributes = self.parse_attributes(node);
+        let attributes = self.parse_attributes(node, backend_node_id);
         let node_type = self.get_node_type(node);
         let (node_name, node_value) = self.get_node_basic_info(node);
+        let node_value = self.truncate_value(node_value, || {
+            format!("{node_name} value of node {backend_node_id}")
+        });
 
         // Get AX node and snapshot data
         let ax_node = context
@@ -127,6 +172,12 @@ impl DOMTreeBuilder {
         // Process children
         enhanced_node.children_nodes = self.process_children(node, context, node_lookup)?;
 
+        // Process content document (same-origin iframe)
+        if let Some(content_doc) = node.get("contentDocument") {
+            let content_doc_node = self.construct_enhanced_node(content_doc, context, node_lookup)?;
+            enhanced_node.content_document = Some(Box::new(content_doc_node));
+        }
+
         // Update lookup with final node
         node_lookup.insert(node_id, enhanced_node.clone());
 
@@ -149,13 +200,20 @@ impl DOMTreeBuilder {
     }
 
     /// Parse attributes from CDP node data
-    fn parse_attributes(&self, node: &Value) -> HashMap<String, String> {
+    fn parse_attributes(&self, node: &Value, backend_node_id: u64) -> HashMap<String, String> {
         let mut attributes = HashMap::new();
         if let Some(attrs) = node.get("attributes").and_then(|v| v.as_array()) {
             for chunk in attrs.chunks(2) {
                 if chunk.len() == 2 {
                     if let (Some(key), Some(val)) = (chunk[0].as_str(), chunk[1].as_str()) {
-                        attributes.insert(key.to_string(), val.to_string());
+                        let val = self.truncate_value(val.to_string(), || {
+                            let tag = node.get("nodeName").and_then(|v| v.as_str()).unwrap_or("");
+                            format!(
+                                "{key} attribute of <{}> node {backend_node_id}",
+                                tag.to_lowercase()
+                            )
+                        });
+                        attributes.insert(key.to_string(), val);
                     }
                 }
             }
@@ -163,6 +221,25 @@ impl DOMTreeBuilder {
         attributes
     }
 
+    /// Cut `value` to `max_value_len` bytes, recording a warning naming `what` was cut
+    fn truncate_value(&self, value: String, what: impl FnOnce() -> String) -> String {
+        if value.len() <= self.max_value_len {
+            return value;
+        }
+        let mut end = self.max_value_len;
+        while !value.is_char_boundary(end) {
+            end -= 1;
+        }
+        let warning = format!(
+            "Truncated the {} from {} to {} bytes",
+            what(),
+            value.len(),
+            end
+        );
+        self.warnings.lock().unwrap().push(warning);
+        format!("{}… [truncated {} bytes]", &value[..end], value.len() - end)
+    }
+
     /// Get node type from CDP node data
     fn get_node_type(&self, node: &Value) -> NodeType {
         let node_type_val = node.get("nodeType").and_then(|v| v.as_u64()).unwrap_or(1);
@@ -256,3 +333,67 @@ struct BuildContext {
     snapshot_lookup: HashMap<u64, EnhancedSnapshotNode>,
     target_id: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser::cdp::CdpClient;
+    use serde_json::json;
+
+    fn builder() -> DOMTreeBuilder {
+        let client = Arc::new(CdpClient::new("ws://127.0.0.1:9/devtools".to_string()));
+        DOMTreeBuilder::new(Arc::new(DOMCDPClient::new(client, None)), None)
+    }
+
+    #[test]
+    fn test_huge_attribute_is_truncated_with_a_warning() {
+        let path = "M0 0L1 1".repeat(10 * 1024 * 1024 / 8);
+        let node = json!({
+            "nodeId": 1,
+            "backendNodeId": 42,
+            "nodeType": 1,
+            "nodeName": "PATH",
+            "attributes": ["d", path, "fill", "red"],
+            "children": [{
+                "nodeId": 2,
+                "backendNodeId": 43,
+                "nodeType": 3,
+                "nodeName": "#text",
+                "nodeValue": "é".repeat(DEFAULT_MAX_VALUE_LEN),
+            }],
+        });
+        let context = BuildContext {
+            ax_tree_lookup: HashMap::new(),
+            snapshot_lookup: HashMap::new(),
+            target_id: "T1".to_string(),
+        };
+
+        let builder = builder();
+        let tree = builder
+            .construct_enhanced_node(&node, &context, &mut HashMap::new())
+            .unwrap();
+
+        let d = &tree.attributes["d"];
+        assert!(d.len() < DEFAULT_MAX_VALUE_LEN + 64);
+        assert!(d.starts_with("M0 0L1 1"));
+        assert!(d.ends_with(&format!(
+            "… [truncated {} bytes]",
+            path.len() - DEFAULT_MAX_VALUE_LEN
+        )));
+        assert_eq!(tree.attributes["fill"], "red");
+        // Cut on a character boundary
+        let text = &tree.children_nodes.as_ref().unwrap()[0].node_value;
+        assert!(text.starts_with(&"é".repeat(DEFAULT_MAX_VALUE_LEN / 2)));
+
+        let warnings = builder.warnings();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(
+            warnings[0],
+            format!(
+                "Truncated the d attribute of <path> node 42 from {} to {DEFAULT_MAX_VALUE_LEN} bytes",
+                path.len()
+            )
+        );
+        assert!(warnings[1].starts_with("Truncated the #text value of node 43"));
+    }
+}
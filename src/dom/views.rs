@@ -1,5 +1,6 @@
 //! DOM view types
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
@@ -75,6 +76,15 @@ pub struct DOMInteractedElement {
     pub attributes: HashMap<String, String>,
     /// CSS selector of the element
     pub selector: Option<String>,
+    /// Bounding box of the element, in document coordinates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bounds: Option<DOMRect>,
+    /// Number of the form the element belongs to, counted from 1 in document order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub form_id: Option<u32>,
+    /// Frame the element is in, `None` for the top-level document
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame_id: Option<String>,
 }
 
 impl DOMInteractedElement {
@@ -84,6 +94,20 @@ impl DOMInteractedElement {
     }
 }
 
+/// Page state given to the model for a page with nothing on it, such as `about:blank`
+pub const EMPTY_PAGE_STATE: &str = "Empty page — navigate somewhere to begin";
+
+/// Whether `url` is the placeholder a tab shows before anything was navigated to
+pub fn is_blank_page_url(url: &str) -> bool {
+    let url = url.trim();
+    url.is_empty()
+        || url == "about:blank"
+        || url.starts_with("about:blank#")
+        || url.starts_with("about:blank?")
+        || url.starts_with("chrome://newtab")
+        || url.starts_with("chrome://new-tab-page")
+}
+
 /// Serialized DOM state for LLM processing
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SerializedDOMState {
@@ -93,10 +117,13 @@ pub struct SerializedDOMState {
     pub text: Option<String>,
     /// Markdown representation of the DOM
     pub markdown: Option<String>,
-    /// List of DOM elements
-    pub elements: Vec<DOMElement>,
+    /// Interactive elements as structured entries, in index order
+    pub elements: Vec<SerializedElement>,
     /// Selector map for DOM elements
     pub selector_map: HashMap<u32, DOMInteractedElement>,
+    /// Attribute and text values cut short while reading the page, one line each
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub truncated_values: Vec<String>,
 }
 
 impl SerializedDOMState {
@@ -114,6 +141,60 @@ impl SerializedDOMState {
         }
         None
     }
+
+    /// Whether the page has neither interactive elements nor text
+    pub fn is_empty(&self) -> bool {
+        self.selector_map.is_empty()
+            && self
+                .llm_representation(None)
+                .is_none_or(|text| text.trim().is_empty())
+    }
+
+    /// Page state for the model, or [`EMPTY_PAGE_STATE`] if the page is empty
+    pub fn page_state(&self) -> String {
+        match self.llm_representation(None) {
+            Some(text) if !self.is_empty() => text,
+            _ => EMPTY_PAGE_STATE.to_string(),
+        }
+    }
+}
+
+/// Longest `text` kept in a [`SerializedElement`]; longer text is truncated
+pub const MAX_ELEMENT_TEXT_CHARS: usize = 200;
+
+/// An interactive element in machine-readable form
+///
+/// Produced by the same traversal as the text page state, so `index` matches
+/// the `[index]` shown there and the keys of the selector map.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SerializedElement {
+    /// Index of the element, as used by actions
+    pub index: u32,
+    /// Lowercase HTML tag
+    pub tag: String,
+    /// ARIA role, explicit or computed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    /// Accessible name, e.g. from `aria-label`, `alt` or a `<label>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Visible text inside the element, or its value or placeholder
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Bounding box in document coordinates, if laid out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rect: Option<DOMRect>,
+    /// Link target, for elements with an `href`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub href: Option<String>,
+    /// Number of the form the element belongs to, counted from 1 in document order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub form_id: Option<u32>,
+    /// Whether the element is visible
+    pub is_visible: bool,
+    /// Whether the element is a scroll container, scrolled with `container_index`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_scrollable: bool,
 }
 
 /// DOM element representation
@@ -165,7 +246,7 @@ pub enum NodeType {
 }
 
 /// DOM rectangle for bounding boxes
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct DOMRect {
     /// X coordinate
     pub x: f64,
@@ -236,6 +317,13 @@ pub struct EnhancedSnapshotNode {
     pub paint_order: Option<i32>,
     /// Stacking contexts
     pub stacking_contexts: Option<i32>,
+    /// Background color blended with what is behind it, if captured (see
+    /// [`SnapshotOptions`](crate::dom::enhanced_snapshot::SnapshotOptions))
+    #[serde(default)]
+    pub blended_background_color: Option<String>,
+    /// Opacity of the text color, if captured
+    #[serde(default)]
+    pub text_color_opacity: Option<f64>,
 }
 
 /// Enhanced DOM tree node combining DOM, AX, and Snapshot data
@@ -0,0 +1,298 @@
+//! Detection of visually imperceptible elements
+//!
+//! Pages often keep elements in the DOM that a user cannot see: zero-sized
+//! boxes, fully transparent overlays, or content pushed off-screen. These add
+//! noise to the LLM representation without giving the agent anything to act on.
+
+use crate::dom::serializer::SimplifiedNode;
+use crate::dom::views::{DOMRect, EnhancedDOMTreeNode};
+
+/// Extra space around the viewport before an element counts as off-screen
+const OFF_SCREEN_MARGIN: f64 = 100.0;
+
+/// Filter for elements that are present in the DOM but invisible to the user
+#[derive(Debug, Clone, Default)]
+pub struct InvisibleElementFilter {
+    /// Viewport used to detect off-screen elements to the right or below
+    ///
+    /// Elements above or to the left of the document origin are always treated
+    /// as off-screen, since scrolling can never bring them into view.
+    pub viewport: Option<DOMRect>,
+}
+
+impl InvisibleElementFilter {
+    /// Create a filter without a viewport
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the viewport used for the off-screen check
+    pub fn with_viewport(mut self, viewport: DOMRect) -> Self {
+        self.viewport = Some(viewport);
+        self
+    }
+
+    /// Returns `true` if the element is invisible and should be hidden
+    ///
+    /// Transparent elements are kept when they, or one of their descendants, can
+    /// receive focus, since styled-over native controls rely on this pattern.
+    pub fn filter(&self, node: &SimplifiedNode) -> bool {
+        let Some(ref snapshot) = node.original_node.snapshot_node else {
+            return false;
+        };
+
+        if let Some(ref styles) = snapshot.computed_styles {
+            let style = |name: &str| styles.get(name).map(|v| v.trim());
+
+            if style("display") == Some("none") || style("visibility") == Some("hidden") {
+                return true;
+            }
+
+            let transparent = style("opacity")
+                .and_then(|v| v.parse::<f64>().ok())
+                .is_some_and(|opacity| opacity == 0.0);
+            if transparent && !Self::has_focusable(node) {
+                return true;
+            }
+
+            if style("pointer-events") == Some("none") && !Self::is_focusable(&node.original_node) {
+                return true;
+            }
+        }
+
+        if let Some(ref bounds) = snapshot.bounds {
+            if bounds.width == 0.0 && bounds.height == 0.0 {
+                return true;
+            }
+            if self.is_off_screen(bounds) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Whether the bounding box lies entirely outside the viewport plus margin
+    fn is_off_screen(&self, bounds: &DOMRect) -> bool {
+        if bounds.x + bounds.width < -OFF_SCREEN_MARGIN
+            || bounds.y + bounds.height < -OFF_SCREEN_MARGIN
+        {
+            return true;
+        }
+
+        self.viewport.is_some_and(|viewport| {
+            bounds.x > viewport.x + viewport.width + OFF_SCREEN_MARGIN
+                || bounds.y > viewport.y + viewport.height + OFF_SCREEN_MARGIN
+        })
+    }
+
+    /// Whether the node itself can receive keyboard focus
+    fn is_focusable(node: &EnhancedDOMTreeNode) -> bool {
+        if node.attributes.contains_key("disabled") {
+            return false;
+        }
+        if let Some(tabindex) = node.attributes.get("tabindex") {
+            return tabindex.trim().parse::<i32>().is_ok_and(|i| i >= 0);
+        }
+        if node
+            .attributes
+            .get("contenteditable")
+            .is_some_and(|v| v != "false")
+        {
+            return true;
+        }
+
+        match node.tag_name().as_str() {
+            "a" => node.attributes.contains_key("href"),
+            "button" | "input" | "select" | "textarea" => {
+                node.attributes.get("type").map(|t| t.as_str()) != Some("hidden")
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the node or any of its descendants can receive focus
+    fn has_focusable(node: &SimplifiedNode) -> bool {
+        Self::is_focusable(&node.original_node) || node.children.iter().any(Self::has_focusable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dom::views::{EnhancedSnapshotNode, NodeType};
+    use std::collections::HashMap;
+
+    fn element(tag: &str, attrs: &[(&str, &str)]) -> EnhancedDOMTreeNode {
+        let mut node = EnhancedDOMTreeNode::new(
+            1,
+            1,
+            NodeType::ElementNode,
+            tag.to_uppercase(),
+            String::new(),
+            "target-1".to_string(),
+        );
+        node.attributes = attrs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        node
+    }
+
+    fn with_snapshot(
+        mut node: EnhancedDOMTreeNode,
+        styles: &[(&str, &str)],
+        bounds: Option<DOMRect>,
+    ) -> SimplifiedNode {
+        node.snapshot_node = Some(EnhancedSnapshotNode {
+            is_clickable: None,
+            cursor_style: None,
+            bounds,
+            client_rects: None,
+            scroll_rects: None,
+            computed_styles: Some(
+                styles
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect::<HashMap<_, _>>(),
+            ),
+            paint_order: None,
+            stacking_contexts: None,
+            blended_background_color: None,
+            text_color_opacity: None,
+        });
+        SimplifiedNode::new(node)
+    }
+
+    fn visible_bounds() -> Option<DOMRect> {
+        Some(DOMRect::new(10.0, 10.0, 100.0, 20.0))
+    }
+
+    #[test]
+    fn test_visible_element_is_kept() {
+        let node = with_snapshot(element("div", &[]), &[("opacity", "1")], visible_bounds());
+        assert!(!InvisibleElementFilter::new().filter(&node));
+    }
+
+    #[test]
+    fn test_node_without_snapshot_is_kept() {
+        let node = SimplifiedNode::new(element("div", &[]));
+        assert!(!InvisibleElementFilter::new().filter(&node));
+    }
+
+    #[test]
+    fn test_hidden_styles() {
+        let filter = InvisibleElementFilter::new();
+        for styles in [
+            [("display", "none")],
+            [("visibility", "hidden")],
+            [("opacity", "0")],
+            [("opacity", "0.0")],
+        ] {
+            let node = with_snapshot(element("div", &[]), &styles, visible_bounds());
+            assert!(filter.filter(&node), "{styles:?} should be invisible");
+        }
+    }
+
+    #[test]
+    fn test_zero_size() {
+        let filter = InvisibleElementFilter::new();
+        let node = with_snapshot(
+            element("div", &[]),
+            &[],
+            Some(DOMRect::new(10.0, 10.0, 0.0, 0.0)),
+        );
+        assert!(filter.filter(&node));
+
+        // A zero-width line is still a visible box
+        let node = with_snapshot(
+            element("hr", &[]),
+            &[],
+            Some(DOMRect::new(10.0, 10.0, 0.0, 1.0)),
+        );
+        assert!(!filter.filter(&node));
+    }
+
+    #[test]
+    fn test_transparent_focusable_element_is_kept() {
+        let filter = InvisibleElementFilter::new();
+
+        let checkbox = with_snapshot(
+            element("input", &[("type", "checkbox")]),
+            &[("opacity", "0")],
+            visible_bounds(),
+        );
+        assert!(!filter.filter(&checkbox));
+
+        let mut wrapper = with_snapshot(element("div", &[]), &[("opacity", "0")], visible_bounds());
+        let mut inner = SimplifiedNode::new(element("span", &[]));
+        inner
+            .children
+            .push(SimplifiedNode::new(element("a", &[("href", "/next")])));
+        wrapper.children.push(inner);
+        assert!(!filter.filter(&wrapper));
+
+        let hidden_input = with_snapshot(
+            element("input", &[("type", "hidden")]),
+            &[("opacity", "0")],
+            visible_bounds(),
+        );
+        assert!(filter.filter(&hidden_input));
+    }
+
+    #[test]
+    fn test_pointer_events_none() {
+        let filter = InvisibleElementFilter::new();
+
+        let overlay = with_snapshot(
+            element("div", &[]),
+            &[("pointer-events", "none")],
+            visible_bounds(),
+        );
+        assert!(filter.filter(&overlay));
+
+        let focusable = with_snapshot(
+            element("div", &[("tabindex", "0")]),
+            &[("pointer-events", "none")],
+            visible_bounds(),
+        );
+        assert!(!filter.filter(&focusable));
+
+        let disabled = with_snapshot(
+            element("button", &[("disabled", "")]),
+            &[("pointer-events", "none")],
+            visible_bounds(),
+        );
+        assert!(filter.filter(&disabled));
+    }
+
+    #[test]
+    fn test_off_screen() {
+        let filter = InvisibleElementFilter::new();
+        let far_left = with_snapshot(
+            element("a", &[("href", "#main")]),
+            &[],
+            Some(DOMRect::new(-9999.0, 0.0, 100.0, 20.0)),
+        );
+        assert!(filter.filter(&far_left));
+
+        // Within the margin counts as on-screen
+        let near_edge = with_snapshot(
+            element("div", &[]),
+            &[],
+            Some(DOMRect::new(-150.0, 0.0, 100.0, 20.0)),
+        );
+        assert!(!filter.filter(&near_edge));
+
+        // Below the fold is only off-screen when a viewport is known
+        let below = with_snapshot(
+            element("div", &[]),
+            &[],
+            Some(DOMRect::new(0.0, 2000.0, 100.0, 20.0)),
+        );
+        assert!(!filter.filter(&below));
+        let filter = filter.with_viewport(DOMRect::new(0.0, 0.0, 1280.0, 720.0));
+        assert!(filter.filter(&below));
+        assert!(!filter.filter(&near_edge));
+    }
+}
@@ -1,62 +1,103 @@
 //! Error types for browsing
 
+use std::sync::RwLock;
 use thiserror::Error;
 
+/// Environment fingerprint appended to error messages when debug errors are on
+static FINGERPRINT: RwLock<Option<String>> = RwLock::new(None);
+
 /// Error types for browsing
 #[derive(Error, Debug)]
 pub enum BrowsingError {
     /// Configuration error
-    #[error("Configuration error: {0}")]
+    #[error("Configuration error: {0}{}", fingerprint_suffix())]
     Config(String),
 
     /// IO error
-    #[error("IO error: {0}")]
+    #[error("IO error: {0}{}", fingerprint_suffix())]
     Io(#[from] std::io::Error),
 
     /// JSON error
-    #[error("JSON error: {0}")]
+    #[error("JSON error: {0}{}", fingerprint_suffix())]
     Json(#[from] serde_json::Error),
 
     /// HTTP error
-    #[error("HTTP error: {0}")]
+    #[error("HTTP error: {0}{}", fingerprint_suffix())]
     Http(#[from] reqwest::Error),
 
     /// URL parse error
-    #[error("URL parse error: {0}")]
+    #[error("URL parse error: {0}{}", fingerprint_suffix())]
     Url(#[from] url::ParseError),
 
     /// Browser error
-    #[error("Browser error: {0}")]
+    #[error("Browser error: {0}{}", fingerprint_suffix())]
     Browser(String),
 
     /// Chrome DevTools Protocol error
-    #[error("CDP error: {0}")]
+    #[error("CDP error: {0}{}", fingerprint_suffix())]
     Cdp(String),
 
     /// LLM error
-    #[error("LLM error: {0}")]
+    #[error("LLM error: {0}{}", fingerprint_suffix())]
     Llm(String),
 
     /// Agent error
-    #[error("Agent error: {0}")]
+    #[error("Agent error: {0}{}", fingerprint_suffix())]
     Agent(String),
 
     /// DOM error
-    #[error("DOM error: {0}")]
+    #[error("DOM error: {0}{}", fingerprint_suffix())]
     Dom(String),
 
     /// Tool error
-    #[error("Tool error: {0}")]
+    #[error("Tool error: {0}{}", fingerprint_suffix())]
     Tool(String),
 
+    /// Element has no geometry to interact with (hidden, zero-size or detached)
+    #[error("Element not visible: backend node {backend_node_id} {reason}{}", fingerprint_suffix())]
+    NotVisible {
+        /// Backend node ID of the element
+        backend_node_id: u32,
+        /// Why no click point could be found
+        reason: String,
+    },
+
     /// Validation error
-    #[error("Validation error: {0}")]
+    #[error("Validation error: {0}{}", fingerprint_suffix())]
     Validation(String),
 }
 
 /// Result type alias for browsing
 pub type Result<T> = std::result::Result<T, BrowsingError>;
 
+/// Append a one-line environment fingerprint (crate version, git revision, OS)
+/// to every error message, to make bug reports self-describing
+///
+/// Enabled by the `debug_errors` configuration flag. Once a browser has
+/// started, its version is included as well.
+pub fn set_debug_errors(enabled: bool) {
+    set_error_fingerprint(enabled.then(|| crate::version::build_info().fingerprint(None)));
+}
+
+/// Whether error messages carry an environment fingerprint
+pub fn debug_errors_enabled() -> bool {
+    FINGERPRINT.read().map(|f| f.is_some()).unwrap_or(false)
+}
+
+/// Replace the fingerprint appended to error messages (`None` disables it)
+pub fn set_error_fingerprint(fingerprint: Option<String>) {
+    if let Ok(mut current) = FINGERPRINT.write() {
+        *current = fingerprint;
+    }
+}
+
+fn fingerprint_suffix() -> String {
+    match FINGERPRINT.read().ok().as_deref() {
+        Some(Some(fingerprint)) => format!(" [{fingerprint}]"),
+        _ => String::new(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::BrowsingError;
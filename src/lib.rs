@@ -14,6 +14,7 @@ pub mod tokens;
 pub mod tools;
 pub mod traits;
 pub mod utils;
+pub mod version;
 pub mod views;
 
 pub use error::{BrowsingError, Result};
@@ -22,9 +23,10 @@ pub use error::{BrowsingError, Result};
 pub use actor::{Element, Mouse, Page};
 pub use agent::Agent;
 pub use browser::Browser;
-pub use config::Config;
+pub use config::{Config, ConfigBuilder};
 pub use llm::{ChatInvokeCompletion, ChatInvokeUsage, ChatMessage, ChatModel};
 pub use traits::{BrowserClient, DOMProcessor};
+pub use version::{BuildInfo, EnvironmentInfo, build_info};
 
 /// Initialize the library (sets up logging, etc.)
 pub fn init() {
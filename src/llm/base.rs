@@ -58,6 +58,12 @@ pub trait ChatModel: Send + Sync {
         self.model()
     }
 
+    /// Most tokens the model takes in one call, prompt and answer together,
+    /// if known
+    fn context_window(&self) -> Option<usize> {
+        None
+    }
+
     /// Chat with the model (non-streaming)
     async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>>;
 
@@ -69,7 +75,7 @@ pub trait ChatModel: Send + Sync {
 }
 
 /// Usage information for a chat model invocation
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ChatInvokeUsage {
     /// Number of prompt tokens used
     pub prompt_tokens: u32,
@@ -118,3 +124,132 @@ impl<T> ChatInvokeCompletion<T> {
         self
     }
 }
+
+/// What a model was called for, to tell the calls of a mixed-model run apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallPurpose {
+    /// Choosing the next actions
+    ActionSelection,
+    /// Answering a question about page content
+    Extraction,
+    /// Planning the task ahead
+    Planning,
+    /// Checking a result
+    Validation,
+}
+
+impl CallPurpose {
+    /// Name of the purpose, as serialized
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ActionSelection => "action_selection",
+            Self::Extraction => "extraction",
+            Self::Planning => "planning",
+            Self::Validation => "validation",
+        }
+    }
+}
+
+/// One model call: which model answered, what for, how fast and at what cost
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LlmCall {
+    /// Provider of the model, e.g. `openai`
+    pub provider: String,
+    /// Name of the model
+    pub model: String,
+    /// What the model was called for
+    pub purpose: CallPurpose,
+    /// Time until the answer arrived, in milliseconds
+    pub latency_ms: u64,
+    /// Tokens of the call, if the provider reported them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<ChatInvokeUsage>,
+    /// Estimated cost of the call, if token prices are set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+}
+
+impl LlmCall {
+    /// Record a call `model` answered with `completion` after `latency`
+    pub fn new<T>(
+        model: &(impl ChatModel + ?Sized),
+        purpose: CallPurpose,
+        latency: std::time::Duration,
+        completion: &ChatInvokeCompletion<T>,
+    ) -> Self {
+        Self {
+            provider: model.provider().to_string(),
+            model: model.model().to_string(),
+            purpose,
+            latency_ms: latency.as_millis() as u64,
+            usage: completion.usage.clone(),
+            cost: None,
+        }
+    }
+
+    /// `provider/model`, naming the model in summaries
+    pub fn model_key(&self) -> String {
+        format!("{}/{}", self.provider, self.model)
+    }
+}
+
+/// A model whose calls are timed and recorded under one purpose
+///
+/// Lets code that only takes a `&dyn ChatModel`, such as extraction in
+/// [`Tools::act_with_llm`](crate::tools::Tools::act_with_llm), report the
+/// calls it made. Streamed answers carry no usage and are not recorded.
+pub struct RecordedModel<'a> {
+    inner: &'a dyn ChatModel,
+    purpose: CallPurpose,
+    calls: std::sync::Mutex<Vec<LlmCall>>,
+}
+
+impl<'a> RecordedModel<'a> {
+    /// Record the calls made to `inner` as `purpose`
+    pub fn new(inner: &'a dyn ChatModel, purpose: CallPurpose) -> Self {
+        Self {
+            inner,
+            purpose,
+            calls: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Calls made so far, oldest first
+    pub fn into_calls(self) -> Vec<LlmCall> {
+        self.calls.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl ChatModel for RecordedModel<'_> {
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    fn provider(&self) -> &str {
+        self.inner.provider()
+    }
+
+    fn context_window(&self) -> Option<usize> {
+        self.inner.context_window()
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        let started = std::time::Instant::now();
+        let completion = self.inner.chat(messages).await?;
+        let call = LlmCall::new(self.inner, self.purpose, started.elapsed(), &completion);
+        self.calls
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(call);
+        Ok(completion)
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        self.inner.chat_stream(messages).await
+    }
+}
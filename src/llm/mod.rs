@@ -2,4 +2,7 @@
 
 pub mod base;
 
-pub use base::{ChatInvokeCompletion, ChatInvokeUsage, ChatMessage, ChatModel};
+pub use base::{
+    CallPurpose, ChatInvokeCompletion, ChatInvokeUsage, ChatMessage, ChatModel, LlmCall,
+    RecordedModel,
+};
@@ -1,5 +1,9 @@
 //! Logging configuration for browsing-rs
 
+pub mod agent_logger;
+
+pub use agent_logger::{AgentLogger, LogEntry, parse_agent_log};
+
 use tracing_subscriber::fmt;
 use tracing_subscriber::{EnvFilter, Registry, layer::SubscriberExt, util::SubscriberInitExt};
 
@@ -0,0 +1,231 @@
+//! JSON Lines audit logging for agent actions
+//!
+//! Every executed action is written as a single JSON object per line, so the
+//! resulting file can be queried with `jq` or loaded back with [`parse_agent_log`].
+
+use crate::agent::views::ActionResult;
+use crate::browser::views::BrowserStateHistory;
+use crate::error::Result;
+use crate::tools::views::ActionModel;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// A single audit log entry describing one executed action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    /// Time the action was logged
+    pub timestamp: DateTime<Utc>,
+    /// Agent step the action belongs to
+    pub step: u32,
+    /// Type of the action (e.g. "click", "navigate")
+    pub action_type: String,
+    /// Parameters the action was invoked with
+    pub params: HashMap<String, serde_json::Value>,
+    /// Whether the action completed without error
+    pub success: bool,
+    /// Content extracted by the action, if any
+    pub extracted_content: Option<String>,
+    /// Diagnostics the action recorded for logs rather than the model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug_info: Option<String>,
+    /// Page URL after the step
+    pub url: String,
+    /// Number of open tabs after the step
+    pub tab_count: usize,
+    /// ID of the agent run the action belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+}
+
+impl LogEntry {
+    /// Build a log entry from an executed action and the resulting browser state
+    pub fn new(
+        step: u32,
+        action: &ActionModel,
+        result: &ActionResult,
+        state: &BrowserStateHistory,
+    ) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            step,
+            action_type: action.action_type.clone(),
+            params: action.params.clone(),
+            success: result.error.is_none() && result.success != Some(false),
+            extracted_content: result.extracted_content.clone(),
+            debug_info: result.debug_info.clone(),
+            url: state.url.clone(),
+            tab_count: state.tabs.len(),
+            run_id: None,
+        }
+    }
+}
+
+/// Appends agent actions to a `.jsonl` audit file
+pub struct AgentLogger {
+    writer: BufWriter<File>,
+    run_id: Option<String>,
+}
+
+impl AgentLogger {
+    /// Open (or create) the log file at `path` in append mode
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+            run_id: None,
+        })
+    }
+
+    /// Tag every entry with the ID of the agent run
+    pub fn with_run_id(mut self, run_id: impl Into<String>) -> Self {
+        self.run_id = Some(run_id.into());
+        self
+    }
+
+    /// Write one entry for an executed action and flush it to disk
+    pub fn log_action(
+        &mut self,
+        step: u32,
+        action: &ActionModel,
+        result: &ActionResult,
+        state: &BrowserStateHistory,
+    ) -> Result<()> {
+        let mut entry = LogEntry::new(step, action, result, state);
+        entry.run_id = self.run_id.clone();
+        serde_json::to_writer(&mut self.writer, &entry)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Read all entries from an agent log file, skipping blank lines
+pub fn parse_agent_log(path: &Path) -> Result<Vec<LogEntry>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        entries.push(serde_json::from_str(&line)?);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser::views::TabInfo;
+
+    fn state(url: &str, tabs: usize) -> BrowserStateHistory {
+        BrowserStateHistory {
+            url: url.to_string(),
+            title: "Example".to_string(),
+            tabs: (0..tabs)
+                .map(|i| TabInfo {
+                    url: url.to_string(),
+                    title: String::new(),
+                    target_id: format!("target-{i}"),
+                    parent_target_id: None,
+                    ..Default::default()
+                })
+                .collect(),
+            interacted_element: vec![],
+            screenshot_path: None,
+            navigation: None,
+            emulation: None,
+        }
+    }
+
+    fn action(action_type: &str, params: serde_json::Value) -> ActionModel {
+        ActionModel {
+            action_type: action_type.to_string(),
+            params: serde_json::from_value(params).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_log_and_parse_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent.jsonl");
+
+        let mut logger = AgentLogger::new(&path).unwrap();
+        logger
+            .log_action(
+                1,
+                &action("navigate", serde_json::json!({"url": "https://example.com"})),
+                &ActionResult::success_with_memory("Navigated"),
+                &state("https://example.com", 1),
+            )
+            .unwrap();
+        logger
+            .log_action(
+                2,
+                &action("click", serde_json::json!({"index": 3})),
+                &ActionResult {
+                    error: Some("Element not found".to_string()),
+                    ..Default::default()
+                },
+                &state("https://example.com", 2),
+            )
+            .unwrap();
+
+        let entries = parse_agent_log(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action_type, "navigate");
+        assert!(entries[0].success);
+        assert_eq!(entries[0].extracted_content.as_deref(), Some("Navigated"));
+        assert_eq!(entries[1].step, 2);
+        assert!(!entries[1].success);
+        assert_eq!(entries[1].params.get("index"), Some(&serde_json::json!(3)));
+        assert_eq!(entries[1].tab_count, 2);
+    }
+
+    #[test]
+    fn test_each_entry_is_one_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent.jsonl");
+
+        let mut logger = AgentLogger::new(&path).unwrap();
+        for step in 1..=3 {
+            logger
+                .log_action(
+                    step,
+                    &action("done", serde_json::json!({"text": "multi\nline"})),
+                    &ActionResult::done("multi\nline"),
+                    &state("about:blank", 1),
+                )
+                .unwrap();
+        }
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), 3);
+        for line in content.lines() {
+            assert!(serde_json::from_str::<serde_json::Value>(line).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_entries_carry_run_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent.jsonl");
+
+        let mut logger = AgentLogger::new(&path).unwrap().with_run_id("run-1");
+        logger
+            .log_action(
+                1,
+                &action("wait", serde_json::json!({"seconds": 1})),
+                &ActionResult::default(),
+                &state("about:blank", 1),
+            )
+            .unwrap();
+
+        let entries = parse_agent_log(&path).unwrap();
+        assert_eq!(entries[0].run_id.as_deref(), Some("run-1"));
+    }
+}
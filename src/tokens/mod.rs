@@ -2,4 +2,4 @@
 
 pub mod views;
 
-pub use views::UsageSummary;
+pub use views::{ModelUsage, TokenPricing, UsageSummary};
@@ -1,5 +1,6 @@
 //! Token usage and cost tracking views
 
+use crate::llm::base::{CallPurpose, LlmCall};
 use serde::{Deserialize, Serialize};
 
 /// Summary of token usage
@@ -13,4 +14,102 @@ pub struct UsageSummary {
     pub total_tokens: Option<u32>,
     /// Estimated cost
     pub cost: Option<f64>,
+    /// Average completion tokens per step, to compare reasoning efforts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg_completion_tokens_per_step: Option<f64>,
+    /// Usage of each model the run called, in order of first use
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub by_model: Vec<ModelUsage>,
+}
+
+/// Calls to one model over a run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelUsage {
+    /// Provider of the model
+    pub provider: String,
+    /// Name of the model
+    pub model: String,
+    /// Number of calls
+    pub calls: u32,
+    /// Prompt tokens
+    pub prompt_tokens: u32,
+    /// Completion tokens
+    pub completion_tokens: u32,
+    /// Total tokens
+    pub total_tokens: u32,
+    /// Time spent waiting for answers, in milliseconds
+    pub latency_ms: u64,
+    /// Estimated cost, if token prices are known
+    pub cost: Option<f64>,
+    /// What the model was called for, in order of first use
+    pub purposes: Vec<CallPurpose>,
+}
+
+impl ModelUsage {
+    /// Add `call` to the usage of the model that made it in `breakdown`
+    pub fn record(breakdown: &mut Vec<ModelUsage>, call: &LlmCall) {
+        let index = match breakdown
+            .iter()
+            .position(|m| m.provider == call.provider && m.model == call.model)
+        {
+            Some(index) => index,
+            None => {
+                breakdown.push(ModelUsage {
+                    provider: call.provider.clone(),
+                    model: call.model.clone(),
+                    ..Default::default()
+                });
+                breakdown.len() - 1
+            }
+        };
+        let entry = &mut breakdown[index];
+        entry.calls += 1;
+        entry.latency_ms += call.latency_ms;
+        if let Some(usage) = &call.usage {
+            entry.prompt_tokens += usage.prompt_tokens;
+            entry.completion_tokens += usage.completion_tokens;
+            entry.total_tokens += usage.total_tokens;
+        }
+        if let Some(cost) = call.cost {
+            entry.cost = Some(entry.cost.unwrap_or(0.0) + cost);
+        }
+        if !entry.purposes.contains(&call.purpose) {
+            entry.purposes.push(call.purpose);
+        }
+    }
+
+    /// One line for reports, e.g. `openai/gpt-4o: 3 calls, 4200 tokens, 2.1s, $0.0150`
+    pub fn summary(&self) -> String {
+        let mut line = format!(
+            "{}/{}: {} call{}, {} tokens, {:.1}s",
+            self.provider,
+            self.model,
+            self.calls,
+            if self.calls == 1 { "" } else { "s" },
+            self.total_tokens,
+            self.latency_ms as f64 / 1000.0
+        );
+        if let Some(cost) = self.cost {
+            line.push_str(&format!(", ${cost:.4}"));
+        }
+        line
+    }
+}
+
+/// Prices of a model's tokens, for estimating the cost of a run
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TokenPricing {
+    /// Price of one million prompt tokens
+    pub prompt_per_million: f64,
+    /// Price of one million completion tokens
+    pub completion_per_million: f64,
+}
+
+impl TokenPricing {
+    /// Cost of `prompt_tokens` and `completion_tokens`
+    pub fn cost(&self, prompt_tokens: u32, completion_tokens: u32) -> f64 {
+        (f64::from(prompt_tokens) * self.prompt_per_million
+            + f64::from(completion_tokens) * self.completion_per_million)
+            / 1_000_000.0
+    }
 }
@@ -0,0 +1,214 @@
+//! Dry-run mode for the built-in actions
+//!
+//! Under [`ToolsConfig::dry_run`](crate::config::ToolsConfig::dry_run),
+//! actions that change the page or the browser — navigation, clicks, typing,
+//! form submission, uploads, `evaluate`, opening background tabs and closing
+//! tabs — are not executed, nor are those that write files: `save_page`, and
+//! `extract_images` with `download`. Each returns a result describing what it
+//! would have done instead: the target element's index, tag, text and
+//! selector, the URLs, the value that would have been typed, or the files
+//! that would have been written. Read-only actions run as usual, and actions listed
+//! in `force_execute` run even though they would change something.
+//!
+//! Values typed into password fields are shown as `***`. Secrets filled into
+//! `<secret>` placeholders are masked by the agent, as in every result.
+//! Custom actions are not known to be read-only or not, so they always run.
+
+use crate::actor::images::DEFAULT_DOWNLOAD_LIMIT;
+use crate::agent::views::ActionResult;
+use crate::dom::views::DOMInteractedElement;
+use crate::tools::handlers::saved_page_file_name;
+use crate::tools::views::ActionModel;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+/// Metadata key set on results of actions skipped by a dry run
+pub const DRY_RUN_METADATA_KEY: &str = "dry_run";
+
+/// Built-in actions that change the page or the browser or write files,
+/// skipped by a dry run; `extract_images` only when it downloads
+pub const MUTATING_ACTIONS: &[&str] = &[
+    "search",
+    "navigate",
+    "restore_checkpoint",
+    "click",
+    "click_descendant",
+    "activate",
+    "input",
+    "send_keys",
+    "form_autofill",
+    "submit_form",
+    "select_dropdown",
+    "revert_last",
+    "upload_file",
+    "evaluate",
+    "open_and_extract",
+    "close",
+    "save_page",
+    "extract_images",
+];
+
+/// Characters of element text shown in a description
+const MAX_TEXT_CHARS: usize = 60;
+
+/// Whether a dry run skips `action`
+pub fn is_mutating(action: &ActionModel) -> bool {
+    let downloads = || {
+        action
+            .params
+            .get("download")
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+    };
+    MUTATING_ACTIONS.contains(&action.action_type.as_str())
+        && (action.action_type != "extract_images" || downloads())
+}
+
+/// Result standing in for `action` in a dry run, describing what it would do
+pub fn describe(
+    action: &ActionModel,
+    selector_map: Option<&HashMap<u32, DOMInteractedElement>>,
+) -> ActionResult {
+    let params = &action.params;
+    let text = |key: &str| params.get(key).and_then(Value::as_str).unwrap_or_default();
+    let index = params
+        .get("index")
+        .and_then(Value::as_u64)
+        .map(|i| i as u32);
+    let target = index.and_then(|index| selector_map?.get(&index));
+    let element = || describe_element(index, target);
+
+    let description = match action.action_type.as_str() {
+        "search" => format!("search the web for \"{}\"", text("query")),
+        "navigate" => format!("navigate to {}", text("url")),
+        "restore_checkpoint" => format!(
+            "restore checkpoint {}",
+            params.get("checkpoint_id").unwrap_or(&Value::Null)
+        ),
+        "click" => format!("click {}", element()),
+        "click_descendant" => {
+            let filters: Vec<String> = ["role", "text", "css"]
+                .into_iter()
+                .filter(|key| !text(key).is_empty())
+                .map(|key| format!("{key} \"{}\"", text(key)))
+                .collect();
+            format!(
+                "click the element matching {} inside {}",
+                filters.join(", "),
+                element()
+            )
+        }
+        "activate" => format!("activate {} with Enter or Space", element()),
+        "input" => {
+            let value = if target.is_some_and(is_password_field) {
+                "***"
+            } else {
+                text("text")
+            };
+            format!("type \"{value}\" into {}", element())
+        }
+        "send_keys" => format!("press {}", text("keys")),
+        "form_autofill" => {
+            let mut fields: Vec<&String> = params
+                .get("values")
+                .and_then(Value::as_object)
+                .map(|values| values.keys().collect())
+                .unwrap_or_default();
+            fields.sort();
+            let fields: Vec<&str> = fields.into_iter().map(String::as_str).collect();
+            format!("fill the form fields {}", fields.join(", "))
+        }
+        "submit_form" => format!("submit the form containing {}", element()),
+        "select_dropdown" => format!("select \"{}\" in {}", text("text"), element()),
+        "revert_last" => "revert the last reversible change".to_string(),
+        "upload_file" => format!("upload {} to {}", text("path"), element()),
+        "evaluate" => format!("run JavaScript: {}", text("expression")),
+        "open_and_extract" => {
+            let list = |key: &str| {
+                params
+                    .get(key)
+                    .and_then(Value::as_array)
+                    .cloned()
+                    .unwrap_or_default()
+            };
+            let mut pages: Vec<String> = list("urls")
+                .iter()
+                .filter_map(|url| url.as_str().map(str::to_string))
+                .collect();
+            for index in list("indices").iter().filter_map(Value::as_u64) {
+                let index = index as u32;
+                let link = selector_map.and_then(|map| map.get(&index));
+                pages.push(match link.and_then(|link| link.attributes.get("href")) {
+                    Some(href) => href.clone(),
+                    None => describe_element(Some(index), link),
+                });
+            }
+            format!(
+                "open {} in background tabs and extract \"{}\"",
+                pages.join(", "),
+                text("query")
+            )
+        }
+        "close" => format!("close tab {}", text("tab_id")),
+        "save_page" => format!(
+            "save the page as {} in the pages directory of the artifacts directory",
+            saved_page_file_name(params.get("filename").and_then(Value::as_str))
+        ),
+        "extract_images" => format!(
+            "download the {} largest images as image-<index> files in the images \
+             directory of the artifacts directory, replacing files of the same name",
+            params
+                .get("limit")
+                .and_then(Value::as_u64)
+                .map_or(DEFAULT_DOWNLOAD_LIMIT, |n| n as usize)
+        ),
+        other => format!("run {other}"),
+    };
+    let description = format!("Dry run: would {description}; the page was not changed");
+
+    let mut metadata = HashMap::from([
+        (DRY_RUN_METADATA_KEY.to_string(), json!(true)),
+        ("action".to_string(), json!(action.action_type)),
+    ]);
+    if let Some(selector) = target.and_then(|element| element.selector.as_ref()) {
+        metadata.insert("selector".to_string(), json!(selector));
+    }
+    ActionResult {
+        metadata: Some(metadata),
+        ..ActionResult::success_with_memory(description)
+    }
+}
+
+/// `element 12 <button> "Add to cart" (form#cart > button)`, or as much of it as is known
+fn describe_element(index: Option<u32>, element: Option<&DOMInteractedElement>) -> String {
+    let Some(index) = index else {
+        return "an element".to_string();
+    };
+    let Some(element) = element else {
+        return format!("element {index}");
+    };
+    let mut description = format!("element {index} <{}>", element.tag);
+    let text = element
+        .text
+        .as_deref()
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .or_else(|| element.attributes.get("aria-label").map(String::as_str))
+        .or_else(|| element.attributes.get("placeholder").map(String::as_str));
+    if let Some(text) = text {
+        let shown: String = text.chars().take(MAX_TEXT_CHARS).collect();
+        let ellipsis = if shown.len() < text.len() { "…" } else { "" };
+        description.push_str(&format!(" \"{shown}{ellipsis}\""));
+    }
+    if let Some(selector) = &element.selector {
+        description.push_str(&format!(" ({selector})"));
+    }
+    description
+}
+
+fn is_password_field(element: &DOMInteractedElement) -> bool {
+    element
+        .attributes
+        .get("type")
+        .is_some_and(|kind| kind.eq_ignore_ascii_case("password"))
+}
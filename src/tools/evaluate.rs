@@ -0,0 +1,260 @@
+//! Policy for the `evaluate` action
+//!
+//! Running model-written JavaScript is not acceptable in every deployment.
+//! [`EvaluatePolicy::ReadOnly`] keeps `evaluate` for reading the page, such as
+//! counting rows or pulling text out of a table, while refusing scripts that
+//! look like they change it.
+//!
+//! The read-only mode is a best-effort guard, not a sandbox:
+//!
+//! - The check is a conservative scan of the source text, not a JavaScript
+//!   parser. It rejects some harmless scripts (e.g. `i++` in a loop or
+//!   `Object.assign` on a local object) and cannot see through every form of
+//!   indirection.
+//! - Scripts run in a fresh isolated world, so they cannot read or clobber the
+//!   page's globals. The DOM is shared with the page, so common DOM, storage,
+//!   history and network mutators are replaced with functions that throw
+//!   before the script runs. Mutators not in that list still work.
+//! - Reading is not harmless either: a read-only script can still see
+//!   everything in the DOM, including form values.
+//!
+//! Use [`EvaluatePolicy::Disabled`] where model-written JavaScript must never
+//! run.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+/// Name of the isolated worlds read-only scripts run in
+pub const READ_ONLY_WORLD_NAME: &str = "browsing-read-only";
+
+/// What the `evaluate` action may run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvaluatePolicy {
+    /// Refuse every expression
+    Disabled,
+    /// Refuse expressions that look like they change the page, and run the
+    /// rest in an isolated world with common mutators disabled
+    ReadOnly,
+    /// Run expressions in the page, apart from a short list of patterns
+    /// such as `document.cookie` and `fetch(`
+    #[default]
+    Full,
+}
+
+/// Calls that change the page, storage, history or talk to the network
+static MUTATING_CALL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\b(appendChild|removeChild|insertBefore|replaceChild|replaceWith|replaceChildren|insertAdjacentHTML|insertAdjacentElement|insertAdjacentText|setAttribute|setAttributeNS|removeAttribute|toggleAttribute|attachShadow|add|toggle|append|prepend|remove|before|after|click|submit|requestSubmit|reset|focus|blur|select|dispatchEvent|write|writeln|execCommand|setItem|removeItem|clear|pushState|replaceState|back|forward|go|postMessage|sendBeacon|fetch|open|close|reload|assign|defineProperty|defineProperties|setPrototypeOf|setProperty|removeProperty|showModal|showPopover|play|pause|eval|Function|setTimeout|setInterval|requestAnimationFrame|import)\s*\(",
+    )
+    .unwrap()
+});
+
+/// Globals that reach outside the page or run code indirectly
+static MUTATING_GLOBAL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\b(XMLHttpRequest|WebSocket|EventSource|Worker|SharedWorker|BroadcastChannel|Image|Audio|indexedDB|caches|serviceWorker|Reflect|document\s*\.\s*cookie|location|delete|with)\b",
+    )
+    .unwrap()
+});
+
+/// Declarations whose `=` only binds a local name
+static DECLARATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(const|let|var)\s*([\w$]+|\{[^=]*\}|\[[^=]*\])\s*$").unwrap());
+
+/// Why `expression` might change the page, if it might
+///
+/// String literal contents and comments are ignored; everything else is
+/// scanned for assignments, increments, `delete`, mutating calls and network
+/// or storage globals. Declarations (`const x = …`) are allowed.
+pub fn read_only_violation(expression: &str) -> Option<String> {
+    let code = strip_strings_and_comments(expression);
+
+    if let Some(m) = MUTATING_CALL.captures(&code) {
+        return Some(format!("calls {}()", &m[1]));
+    }
+    if let Some(m) = MUTATING_GLOBAL.captures(&code) {
+        return Some(format!(
+            "uses {}",
+            m[1].split_whitespace().collect::<String>()
+        ));
+    }
+    if code.contains("++") || code.contains("--") {
+        return Some("increments or decrements a value".to_string());
+    }
+    if code.contains("](") {
+        return Some("calls a computed property".to_string());
+    }
+
+    let chars: Vec<char> = code.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        if c != '=' {
+            continue;
+        }
+        let next = chars.get(i + 1).copied();
+        let prev = i.checked_sub(1).map(|p| chars[p]);
+        let before_prev = i.checked_sub(2).map(|p| chars[p]);
+        // `==`, `===` and `=>` (the later `=` of `==` is skipped by `prev`)
+        if matches!(next, Some('=' | '>')) || prev == Some('=') {
+            continue;
+        }
+        // `!=`, `<=` and `>=`, but not the shift assignments `<<=` and `>>=`
+        if prev == Some('!') || (matches!(prev, Some('<' | '>')) && before_prev != prev) {
+            continue;
+        }
+        let before: String = chars[..i].iter().collect();
+        if DECLARATION.is_match(&before) {
+            continue;
+        }
+        return Some("assigns a value".to_string());
+    }
+    None
+}
+
+/// Blank out the contents of quoted strings and drop comments
+///
+/// Template literals are kept as code, since `${…}` can run anything.
+fn strip_strings_and_comments(expression: &str) -> String {
+    let mut code = String::with_capacity(expression.len());
+    let mut chars = expression.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                code.push(c);
+                while let Some(s) = chars.next() {
+                    if s == '\\' {
+                        chars.next();
+                    } else if s == c {
+                        break;
+                    }
+                }
+                code.push(c);
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for s in chars.by_ref() {
+                    if s == '\n' {
+                        code.push('\n');
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for s in chars.by_ref() {
+                    if prev == '*' && s == '/' {
+                        break;
+                    }
+                    prev = s;
+                }
+                code.push(' ');
+            }
+            _ => code.push(c),
+        }
+    }
+    code
+}
+
+/// Replaces common mutators with functions that throw, in the isolated world
+/// it runs in
+///
+/// Isolated worlds have their own prototypes, so the page itself is not
+/// affected.
+pub(crate) const READ_ONLY_SHIMS_JS: &str = r#"
+(() => {
+    const deny = name => function () {
+        throw new Error(`${name} is not allowed in read-only evaluate`);
+    };
+    const methods = (proto, names) => {
+        for (const name of names) {
+            if (proto && name in proto) {
+                Object.defineProperty(proto, name, { value: deny(name), configurable: false, writable: false });
+            }
+        }
+    };
+    const setters = (proto, names) => {
+        for (const name of names) {
+            const descriptor = proto && Object.getOwnPropertyDescriptor(proto, name);
+            if (descriptor && descriptor.set) {
+                Object.defineProperty(proto, name, { get: descriptor.get, set: deny(name), configurable: false });
+            }
+        }
+    };
+    methods(Node.prototype, ['appendChild', 'removeChild', 'insertBefore', 'replaceChild']);
+    methods(Element.prototype, [
+        'setAttribute', 'setAttributeNS', 'removeAttribute', 'toggleAttribute', 'attachShadow',
+        'append', 'prepend', 'remove', 'before', 'after', 'replaceWith', 'replaceChildren',
+        'insertAdjacentHTML', 'insertAdjacentElement', 'insertAdjacentText',
+    ]);
+    methods(HTMLElement.prototype, ['click', 'focus', 'blur', 'showPopover']);
+    methods(window.HTMLFormElement && HTMLFormElement.prototype, ['submit', 'requestSubmit', 'reset']);
+    methods(window.HTMLDialogElement && HTMLDialogElement.prototype, ['show', 'showModal', 'close']);
+    methods(window.HTMLMediaElement && HTMLMediaElement.prototype, ['play', 'pause', 'load']);
+    methods(EventTarget.prototype, ['dispatchEvent']);
+    methods(Document.prototype, ['write', 'writeln', 'open', 'close', 'execCommand']);
+    methods(Storage.prototype, ['setItem', 'removeItem', 'clear']);
+    methods(History.prototype, ['pushState', 'replaceState', 'back', 'forward', 'go']);
+    methods(CSSStyleDeclaration.prototype, ['setProperty', 'removeProperty']);
+    methods(DOMTokenList.prototype, ['add', 'remove', 'toggle', 'replace']);
+    setters(Node.prototype, ['textContent', 'nodeValue']);
+    setters(Element.prototype, ['innerHTML', 'outerHTML', 'className', 'id']);
+    setters(HTMLElement.prototype, ['innerText', 'outerText', 'hidden', 'title']);
+    setters(window.HTMLInputElement && HTMLInputElement.prototype, ['value', 'checked']);
+    setters(window.HTMLTextAreaElement && HTMLTextAreaElement.prototype, ['value']);
+    setters(window.HTMLSelectElement && HTMLSelectElement.prototype, ['value', 'selectedIndex']);
+    setters(window.HTMLOptionElement && HTMLOptionElement.prototype, ['selected']);
+    setters(Document.prototype, ['cookie', 'title', 'body']);
+    for (const name of ['fetch', 'open', 'close', 'postMessage', 'alert', 'confirm', 'prompt', 'XMLHttpRequest', 'WebSocket']) {
+        window[name] = deny(name);
+    }
+})()
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_only_expressions_pass() {
+        for expression in [
+            "document.title",
+            "document.querySelectorAll('tr').length",
+            "Array.from(document.querySelectorAll('a')).map(a => a.href)",
+            "(() => { const rows = document.querySelectorAll('tr'); return rows.length; })()",
+            "document.body.innerText.includes('a = b')",
+            "window.scrollY === 0 && document.readyState !== 'loading'",
+            "[1, 2].filter(n => n >= 2 && n <= 3)",
+            "/* set x = 1 */ document.title // x = 2",
+        ] {
+            assert_eq!(read_only_violation(expression), None, "{expression}");
+        }
+    }
+
+    #[test]
+    fn test_mutating_expressions_are_rejected() {
+        for (expression, reason) in [
+            ("document.title = 'x'", "assigns a value"),
+            ("document.body.innerHTML += '<p>'", "assigns a value"),
+            ("x <<= 1", "assigns a value"),
+            ("let i = 0; i++", "increments or decrements a value"),
+            (
+                "document.body.appendChild(document.createElement('p'))",
+                "calls appendChild()",
+            ),
+            ("document.querySelector('form').submit()", "calls submit()"),
+            ("el.classList.add('active')", "calls add()"),
+            ("el['click']()", "calls a computed property"),
+            ("delete window.app", "uses delete"),
+            ("location.href", "uses location"),
+            ("new XMLHttpRequest()", "uses XMLHttpRequest"),
+            ("Reflect.set(window, 'a', 1)", "uses Reflect"),
+        ] {
+            assert_eq!(
+                read_only_violation(expression).as_deref(),
+                Some(reason),
+                "{expression}"
+            );
+        }
+    }
+}
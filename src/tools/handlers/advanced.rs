@@ -1,17 +1,56 @@
 //! Advanced action handlers
 
 use super::Handler;
+use crate::actor::page::EVALUATE_TIMEOUT_MS;
+use crate::actor::response::{DEFAULT_MAX_RESPONSE_BODY_BYTES, ResponseMarker, WaitForResponseOptions};
+use crate::agent::sanitize::wrap_untrusted;
 use crate::agent::views::ActionResult;
 use crate::error::{BrowsingError, Result};
+use crate::tools::evaluate::{EvaluatePolicy, READ_ONLY_SHIMS_JS, READ_ONLY_WORLD_NAME, read_only_violation};
 use crate::tools::views::{ActionContext, ActionParams};
 use async_trait::async_trait;
 use serde_json::json;
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::Mutex;
 use tracing::info;
 
+/// Metadata key under which `wait_for_response` returns the response it saw
+pub const RESPONSE_METADATA_KEY: &str = "response";
+
+/// Longest `wait_for_response` timeout the model may ask for, in seconds
+const MAX_RESPONSE_WAIT_SECONDS: u64 = 60;
+
+/// Metadata key under which `get_captured_responses` returns the full responses
+pub const CAPTURED_RESPONSES_METADATA_KEY: &str = "captured_responses";
+
+/// Characters of captured bodies shown to the model; the rest is in metadata
+const MAX_CAPTURED_CONTENT_CHARS: usize = 20_000;
+
 /// Handler for advanced browser actions
 /// Handles done, evaluate, upload_file, and other advanced operations
-pub struct AdvancedHandler;
+#[derive(Default)]
+pub struct AdvancedHandler {
+    evaluate_policy: EvaluatePolicy,
+    response_marker: Arc<Mutex<Option<ResponseMarker>>>,
+}
+
+impl AdvancedHandler {
+    /// Create a handler whose evaluate action follows `evaluate_policy`
+    pub fn new(evaluate_policy: EvaluatePolicy) -> Self {
+        Self {
+            evaluate_policy,
+            response_marker: Arc::default(),
+        }
+    }
+
+    /// Let `wait_for_response` see the responses since the marker, if one is set
+    pub fn with_response_marker(mut self, response_marker: Arc<Mutex<Option<ResponseMarker>>>) -> Self {
+        self.response_marker = response_marker;
+        self
+    }
+}
 
 #[async_trait]
 impl Handler for AdvancedHandler {
@@ -21,6 +60,8 @@ impl Handler for AdvancedHandler {
             "evaluate" => self.evaluate(params, context).await,
             "upload_file" => self.upload_file(params, context).await,
             "wait" => self.wait(params).await,
+            "wait_for_response" => self.wait_for_response(params, context).await,
+            "get_captured_responses" => self.get_captured_responses(context),
             _ => Err(BrowsingError::Tool("Unknown advanced action".into())),
         }
     }
@@ -36,6 +77,17 @@ impl AdvancedHandler {
     async fn evaluate(&self, params: &ActionParams<'_>, context: &mut ActionContext<'_>) -> Result<ActionResult> {
         let expression = params.get_required_str("expression")?;
 
+        if self.evaluate_policy == EvaluatePolicy::Disabled {
+            return Err(BrowsingError::Tool("evaluate is disabled by the evaluate policy".into()));
+        }
+        if self.evaluate_policy == EvaluatePolicy::ReadOnly
+            && let Some(violation) = read_only_violation(expression)
+        {
+            return Err(BrowsingError::Tool(format!(
+                "Read-only evaluate policy rejected the expression: it {violation}"
+            )));
+        }
+
         let dangerous_patterns = [
             "document.cookie", "localStorage.", "sessionStorage.", "window.location",
             "fetch(", "XMLHttpRequest", "eval(", "Function(", "setTimeout(", "setInterval(",
@@ -49,7 +101,14 @@ impl AdvancedHandler {
         }
 
         let page = context.browser.get_page()?;
-        let result = page.evaluate(expression).await?;
+        let result = if self.evaluate_policy == EvaluatePolicy::ReadOnly {
+            // A fresh world per call, so one script can't leave state for the next
+            let world = page.create_isolated_world(READ_ONLY_WORLD_NAME).await?;
+            page.evaluate_in_context(READ_ONLY_SHIMS_JS, world).await?;
+            page.evaluate_in_context(expression, world).await?
+        } else {
+            page.evaluate_with_timeout(expression, EVALUATE_TIMEOUT_MS).await?
+        };
 
         let memory = format!("Evaluated JavaScript: {}", expression);
         info!("💻 {}", memory);
@@ -122,8 +181,109 @@ impl AdvancedHandler {
 
         tokio::time::sleep(tokio::time::Duration::from_secs(actual_seconds)).await;
 
-        let memory = format!("Waited for {} seconds", seconds);
+        let memory = format!("Waited for {} seconds", actual_seconds);
         info!("🕒 {}", memory);
-        Ok(ActionResult::success_with_memory(memory))
+        let mut result = ActionResult {
+            // A pause tells later steps nothing; it is still a recent result
+            include_in_memory: false,
+            ..ActionResult::success_with_memory(memory)
+        };
+        if seconds > actual_seconds {
+            result = result.with_debug_info(format!(
+                "Requested {} seconds, capped at {}",
+                seconds, actual_seconds
+            ));
+        }
+        Ok(result)
+    }
+
+    async fn wait_for_response(&self, params: &ActionParams<'_>, context: &mut ActionContext<'_>) -> Result<ActionResult> {
+        let url = params.get_required_str("url")?;
+        let seconds = params
+            .get_optional_u64("timeout")
+            .unwrap_or(10)
+            .min(MAX_RESPONSE_WAIT_SECONDS);
+        let options = WaitForResponseOptions {
+            timeout_ms: seconds * 1000,
+            include_body: params.get_optional_bool("include_body"),
+            max_body_bytes: params
+                .get_optional_u64("max_body_bytes")
+                .map_or(DEFAULT_MAX_RESPONSE_BODY_BYTES, |bytes| {
+                    (bytes as usize).min(DEFAULT_MAX_RESPONSE_BODY_BYTES)
+                }),
+        };
+
+        let page = context.browser.get_page()?;
+        let mut marker = self.response_marker.lock().await;
+        let response = match marker.as_mut() {
+            Some(marker) => page.wait_for_response_since(marker, url, &options).await?,
+            None => page.wait_for_response_with_options(url, &options).await?,
+        };
+
+        let memory = format!("Received response {}", response.summary());
+        info!("📨 {}", memory);
+        let content = match response.body {
+            // The body comes from the site, so it is as untrusted as the page
+            Some(ref body) if !response.base64_encoded => format!(
+                "{memory}{}\n{}",
+                if response.body_truncated { " (body truncated)" } else { "" },
+                wrap_untrusted(body)
+            ),
+            Some(_) => format!("{memory} (binary body, base64 in metadata)"),
+            None => memory.clone(),
+        };
+        Ok(ActionResult {
+            extracted_content: Some(content),
+            long_term_memory: Some(memory),
+            metadata: Some(HashMap::from([(
+                RESPONSE_METADATA_KEY.to_string(),
+                serde_json::to_value(&response)?,
+            )])),
+            ..Default::default()
+        })
+    }
+
+    fn get_captured_responses(&self, context: &mut ActionContext<'_>) -> Result<ActionResult> {
+        let Some(summary) = context.browser.response_capture_summary() else {
+            return Err(BrowsingError::Tool(
+                "No responses are being captured".to_string(),
+            ));
+        };
+        let responses = context.browser.take_captured_responses();
+        let memory = format!("Read {} captured response(s)", responses.len());
+        info!("📨 {} ({})", memory, summary);
+
+        let mut bodies = String::new();
+        for response in &responses {
+            bodies.push_str(&format!(
+                "{} {} {}\n",
+                response.status,
+                response.url,
+                serde_json::to_string(&response.body)?
+            ));
+        }
+        let truncated = bodies.chars().count() > MAX_CAPTURED_CONTENT_CHARS;
+        if truncated {
+            bodies = bodies.chars().take(MAX_CAPTURED_CONTENT_CHARS).collect();
+        }
+        let content = if responses.is_empty() {
+            "No responses captured since they were last read".to_string()
+        } else {
+            // The bodies come from the site, so they are as untrusted as the page
+            format!(
+                "{memory}{}\n{}",
+                if truncated { " (truncated; full bodies in metadata)" } else { "" },
+                wrap_untrusted(&bodies)
+            )
+        };
+        Ok(ActionResult {
+            extracted_content: Some(content),
+            long_term_memory: Some(memory),
+            metadata: Some(HashMap::from([(
+                CAPTURED_RESPONSES_METADATA_KEY.to_string(),
+                serde_json::to_value(&responses)?,
+            )])),
+            ..Default::default()
+        })
     }
 }
@@ -0,0 +1,219 @@
+//! Assertion action handlers
+
+use super::Handler;
+use crate::actor::UrlPattern;
+use crate::actor::page::EVALUATE_TIMEOUT_MS;
+use crate::agent::assertions::AssertionOutcome;
+use crate::agent::views::ActionResult;
+use crate::error::{BrowsingError, Result};
+use crate::tools::views::{ActionContext, ActionParams};
+use async_trait::async_trait;
+use serde_json::json;
+use tracing::info;
+
+/// Longest page line quoted in a failed `assert_text_present`
+const MAX_QUOTED_LINE_CHARS: usize = 120;
+
+/// Handler for assertion actions
+/// Handles assert_text_present, assert_element_exists, and assert_url_matches
+pub struct AssertionHandler;
+
+#[async_trait]
+impl Handler for AssertionHandler {
+    async fn handle(
+        &self,
+        params: &ActionParams<'_>,
+        context: &mut ActionContext<'_>,
+    ) -> Result<ActionResult> {
+        let outcome = match params.get_action_type().unwrap_or("unknown") {
+            "assert_text_present" => self.text_present(params, context).await?,
+            "assert_element_exists" => self.element_exists(params, context).await?,
+            "assert_url_matches" => self.url_matches(params, context).await?,
+            _ => return Err(BrowsingError::Tool("Unknown assertion action".into())),
+        };
+        if outcome.passed {
+            info!("✅ {}", outcome.message);
+        } else {
+            info!("❌ {}", outcome.message);
+        }
+        Ok(outcome.into_result())
+    }
+}
+
+impl AssertionHandler {
+    async fn text_present(
+        &self,
+        params: &ActionParams<'_>,
+        context: &mut ActionContext<'_>,
+    ) -> Result<AssertionOutcome> {
+        let text = params.get_required_str("text")?;
+        let ignore_case = params.get_optional_bool("ignore_case");
+        let page = context.browser.get_page()?;
+        let script = "(document.body || document.documentElement).innerText || ''";
+        let page_text = page
+            .evaluate_with_timeout(script, EVALUATE_TIMEOUT_MS)
+            .await?;
+
+        let normalize = |s: &str| {
+            let s = s.split_whitespace().collect::<Vec<_>>().join(" ");
+            if ignore_case { s.to_lowercase() } else { s }
+        };
+        let expected = format!("page text containing {:?}", text);
+        if normalize(&page_text).contains(&normalize(text)) {
+            return Ok(AssertionOutcome::pass(
+                "assert_text_present",
+                format!("Text {:?} is on the page", text),
+            ));
+        }
+        let actual = match closest_line(&page_text, text) {
+            Some(line) => format!("not found; closest line: {:?}", line),
+            None => format!(
+                "not found in {} characters of page text",
+                page_text.chars().count()
+            ),
+        };
+        Ok(AssertionOutcome::fail(
+            "assert_text_present",
+            &expected,
+            &actual,
+        ))
+    }
+
+    async fn element_exists(
+        &self,
+        params: &ActionParams<'_>,
+        context: &mut ActionContext<'_>,
+    ) -> Result<AssertionOutcome> {
+        if let Some(selector) = params.inner().get("selector").and_then(|v| v.as_str()) {
+            let page = context.browser.get_page()?;
+            let script = format!(
+                "document.querySelectorAll({}).length",
+                serde_json::to_string(selector)?
+            );
+            let count = page
+                .evaluate_with_timeout(&script, EVALUATE_TIMEOUT_MS)
+                .await?;
+            let count: usize = count.trim().parse().unwrap_or(0);
+            let expected = format!("an element matching {:?}", selector);
+            return Ok(if count > 0 {
+                AssertionOutcome::pass(
+                    "assert_element_exists",
+                    format!("{count} element(s) match {:?}", selector),
+                )
+            } else {
+                AssertionOutcome::fail("assert_element_exists", &expected, "no element matches")
+            });
+        }
+
+        let index = params.get_required_u32("index").map_err(|_| {
+            BrowsingError::Tool("assert_element_exists needs 'selector' or 'index'".to_string())
+        })?;
+        let expected = format!("element [{index}] on the page");
+        let Some(element) = context.selector_map.and_then(|map| map.get(&index)) else {
+            return Ok(AssertionOutcome::fail(
+                "assert_element_exists",
+                &expected,
+                &format!("no element [{index}] in the page state"),
+            ));
+        };
+        let backend_node_id = element.backend_node_id.unwrap_or(index);
+        let connected = self.is_connected(context, backend_node_id).await;
+        Ok(if connected {
+            AssertionOutcome::pass("assert_element_exists", format!("Element [{index}] exists"))
+        } else {
+            AssertionOutcome::fail(
+                "assert_element_exists",
+                &expected,
+                &format!("element [{index}] is no longer on the page"),
+            )
+        })
+    }
+
+    /// Whether the node is still in its document
+    async fn is_connected(&self, context: &mut ActionContext<'_>, backend_node_id: u32) -> bool {
+        let (Ok(client), Ok(info)) = (
+            context.browser.get_cdp_client(),
+            context.browser.get_session_info().await,
+        ) else {
+            return false;
+        };
+        let session_id = Some(info.session_id.as_str());
+        let Ok(resolved) = client
+            .send_command_with_session(
+                "DOM.resolveNode",
+                json!({ "backendNodeId": backend_node_id }),
+                session_id,
+            )
+            .await
+        else {
+            return false;
+        };
+        let Some(object_id) = resolved["object"]["objectId"].as_str() else {
+            return false;
+        };
+        let connected = client
+            .send_command_with_session(
+                "Runtime.callFunctionOn",
+                json!({
+                    "objectId": object_id,
+                    "functionDeclaration": "function() { return this.isConnected; }",
+                    "returnByValue": true,
+                }),
+                session_id,
+            )
+            .await
+            .map(|result| result["result"]["value"].as_bool().unwrap_or(false));
+        let _ = client
+            .send_command_with_session(
+                "Runtime.releaseObject",
+                json!({ "objectId": object_id }),
+                session_id,
+            )
+            .await;
+        connected.unwrap_or(false)
+    }
+
+    async fn url_matches(
+        &self,
+        params: &ActionParams<'_>,
+        context: &mut ActionContext<'_>,
+    ) -> Result<AssertionOutcome> {
+        let pattern = UrlPattern::new(params.get_required_str("pattern")?)?;
+        let url = context.browser.get_current_url().await?;
+        Ok(if pattern.matches(&url) {
+            AssertionOutcome::pass(
+                "assert_url_matches",
+                format!("URL {} matches {:?}", url, pattern.as_str()),
+            )
+        } else {
+            AssertionOutcome::fail(
+                "assert_url_matches",
+                &format!("URL matching {:?}", pattern.as_str()),
+                &format!("{:?}", url),
+            )
+        })
+    }
+}
+
+/// The page line sharing the most words with `text`, if any shares one
+fn closest_line<'a>(page_text: &'a str, text: &str) -> Option<&'a str> {
+    let words: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+    page_text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let line_words: Vec<String> = line.split_whitespace().map(str::to_lowercase).collect();
+            let shared = words.iter().filter(|w| line_words.contains(w)).count();
+            (shared, line)
+        })
+        .filter(|(shared, _)| *shared > 0)
+        // The first of equally close lines
+        .rev()
+        .max_by_key(|(shared, _)| *shared)
+        .map(|(_, line)| {
+            line.char_indices()
+                .nth(MAX_QUOTED_LINE_CHARS)
+                .map_or(line, |(at, _)| &line[..at])
+        })
+}
@@ -1,12 +1,14 @@
 //! Content action handlers
 
 use super::Handler;
+use crate::actor::{Element, FALLBACK_VIEWPORT_SIZE};
+use crate::actor::page::EVALUATE_TIMEOUT_MS;
 use crate::agent::views::ActionResult;
 use crate::error::{BrowsingError, Result};
 use crate::tools::views::{ActionContext, ActionParams};
 use async_trait::async_trait;
 use serde_json::json;
-use tracing::info;
+use tracing::{info, warn};
 
 /// Handler for content extraction actions
 /// Handles extract_links, extract_images, find_text, and other content operations
@@ -17,6 +19,10 @@ impl Handler for ContentHandler {
     async fn handle(&self, params: &ActionParams<'_>, context: &mut ActionContext<'_>) -> Result<ActionResult> {
         match params.get_action_type().unwrap_or("unknown") {
             "scroll" => self.scroll(params, context).await,
+            "scroll_to_element" => {
+                let index = params.get_required_u32("index")?;
+                self.scroll_to_element(index, params, context).await
+            }
             "find_text" => self.find_text(params, context).await,
             "dropdown_options" => self.dropdown_options(params, context).await,
             "select_dropdown" => self.select_dropdown(params, context).await,
@@ -30,17 +36,91 @@ impl ContentHandler {
         let down = params.get_optional_bool("down");
         let pages = params.get_optional_f64("pages").unwrap_or(1.0);
 
+        if let Some(index) = params.get_optional_u64("container_index") {
+            return self.scroll_container(index as u32, down, pages, params, context).await;
+        }
+        if let Some(index) = params.get_optional_u64("index") {
+            return self.scroll_to_element(index as u32, params, context).await;
+        }
+
         let mut page = context.browser.get_page()?;
+        let viewport = page.get_viewport_size().await.unwrap_or_else(|e| {
+            warn!(
+                "Failed to read the viewport size, assuming {}px: {}",
+                FALLBACK_VIEWPORT_SIZE.height, e
+            );
+            FALLBACK_VIEWPORT_SIZE
+        });
         let mouse = page.mouse().await;
-        let viewport_height = 1000.0;
-        let delta_y = if down { pages * viewport_height } else { -pages * viewport_height };
+        let delta_y = if down { pages * viewport.height } else { -pages * viewport.height };
 
-        mouse.scroll(0.0, 0.0, None, Some(delta_y)).await?;
+        mouse
+            .scroll(viewport.width / 2.0, viewport.height / 2.0, None, Some(delta_y))
+            .await?;
 
         let direction = if down { "down" } else { "up" };
         let memory = format!("Scrolled {} {} pages", direction, pages);
-        info!("📜 {}", memory);
-        Ok(ActionResult::success_with_memory(memory))
+        let debug_info = format!("delta_y: {}px", delta_y);
+        info!("📜 {} ({})", memory, debug_info);
+        Ok(ActionResult::success_with_memory(memory).with_debug_info(debug_info))
+    }
+
+    /// Scroll an element marked `[scrollable N]` instead of the window
+    async fn scroll_container(
+        &self,
+        index: u32,
+        down: bool,
+        pages: f64,
+        params: &ActionParams<'_>,
+        context: &mut ActionContext<'_>,
+    ) -> Result<ActionResult> {
+        let backend_node_id = params.backend_node_id_from_index(index, context.selector_map);
+        let page = context.browser.get_page()?;
+        let element = page
+            .get_element(backend_node_id)
+            .await
+            .with_frame(params.frame_id_from_index(index, context.selector_map));
+        let position = element.scroll_by_pages(if down { pages } else { -pages }).await?;
+
+        let direction = if down { "down" } else { "up" };
+        let mut memory = format!("Scrolled container {index} {direction} {pages} pages");
+        if down && position.at_bottom() {
+            memory.push_str("; it is at the bottom of its content");
+        } else if !down && position.at_top() {
+            memory.push_str("; it is at the top of its content");
+        }
+        let debug_info = format!("backend_node_id: {}", backend_node_id);
+        info!("📜 {} ({})", memory, debug_info);
+        Ok(ActionResult::success_with_memory(memory).with_debug_info(debug_info))
+    }
+
+    /// Bring the element at `index` into view, in whichever containers hold it
+    async fn scroll_to_element(
+        &self,
+        index: u32,
+        params: &ActionParams<'_>,
+        context: &mut ActionContext<'_>,
+    ) -> Result<ActionResult> {
+        if !context.selector_map.is_some_and(|map| map.contains_key(&index)) {
+            return Err(BrowsingError::Tool(format!("Element index {} not found", index)));
+        }
+        let backend_node_id = params.backend_node_id_from_index(index, context.selector_map);
+        let frame_id = params.frame_id_from_index(index, context.selector_map);
+        let mut debug_info = format!("backend_node_id: {}", backend_node_id);
+        if let Some(ref frame_id) = frame_id {
+            debug_info.push_str(&format!(", frame_id: {}", frame_id));
+        }
+        let page = context.browser.get_page()?;
+        let element = page.get_element(backend_node_id).await.with_frame(frame_id);
+        let scrolled = element.scroll_into_view().await?;
+
+        let memory = if scrolled {
+            format!("Scrolled element {index} into view")
+        } else {
+            format!("Element {index} was already in view")
+        };
+        info!("📜 {} ({})", memory, debug_info);
+        Ok(ActionResult::success_with_memory(memory).with_debug_info(debug_info))
     }
 
     async fn find_text(&self, params: &ActionParams<'_>, context: &mut ActionContext<'_>) -> Result<ActionResult> {
@@ -66,7 +146,7 @@ impl ContentHandler {
             json!(text)
         );
 
-        let result = page.evaluate(&script).await?;
+        let result = page.evaluate_with_timeout(&script, EVALUATE_TIMEOUT_MS).await?;
         let found = result.trim() == "true";
 
         if found {
@@ -89,50 +169,17 @@ impl ContentHandler {
 
     async fn dropdown_options(&self, params: &ActionParams<'_>, context: &mut ActionContext<'_>) -> Result<ActionResult> {
         let index = params.get_required_u32("index")?;
-        let element = context.selector_map.and_then(|map| map.get(&index))
-            .ok_or_else(|| BrowsingError::Tool(format!("Element index {} not found", index)))?;
-
-        let page = context.browser.get_page()?;
-        let backend_node_id = element.backend_node_id.ok_or_else(|| {
-            BrowsingError::Tool(format!("Element index {} has no backend_node_id", index))
-        })?;
-
-        let script = format!(
-            r#"(function() {{
-                const nodeId = {};
-                const node = document.querySelector(`[data-backend-node-id="${{nodeId}}"]`) ||
-                             Array.from(document.querySelectorAll('select')).find(el => {{
-                                 const rect = el.getBoundingClientRect();
-                                 return rect.width > 0 && rect.height > 0;
-                             }}) || document.querySelector('select');
-                if (!node && document.querySelector('select')) {{
-                    const select = document.querySelector('select');
-                    const options = Array.from(select.options).map(opt => ({{ value: opt.value, text: opt.text, selected: opt.selected }}));
-                    return JSON.stringify(options);
-                }}
-                if (node && node.tagName === 'SELECT') {{
-                    const options = Array.from(node.options).map(opt => ({{ value: opt.value, text: opt.text, selected: opt.selected }}));
-                    return JSON.stringify(options);
-                }}
-                return JSON.stringify([]);
-            }})()"#,
-            backend_node_id
-        );
-
-        let result = page.evaluate(&script).await?;
-        let options: Vec<serde_json::Value> = serde_json::from_str(&result).unwrap_or_default();
+        let element = self.select_element(index, params, context).await?;
+        let options = element.select_options().await?;
 
         let options_text = options.iter().enumerate()
-            .map(|(i, opt)| {
-                let value = opt.get("value").and_then(|v| v.as_str()).unwrap_or("");
-                let text = opt.get("text").and_then(|v| v.as_str()).unwrap_or("");
-                format!("{}. {} (value: {})", i + 1, text, value)
-            })
+            .map(|(i, opt)| format!("{}. {} (value: {})", i + 1, opt.text, opt.value))
             .collect::<Vec<_>>()
             .join("\n");
 
-        let memory = format!("Dropdown options for index {}:\n{}", index, options_text);
-        info!("📋 {}", memory);
+        // The options themselves are read once from the extracted content
+        let memory = format!("Listed {} options of dropdown {}", options.len(), index);
+        info!("📋 {}:\n{}", memory, options_text);
         Ok(ActionResult {
             extracted_content: Some(options_text),
             long_term_memory: Some(memory),
@@ -143,56 +190,33 @@ impl ContentHandler {
     async fn select_dropdown(&self, params: &ActionParams<'_>, context: &mut ActionContext<'_>) -> Result<ActionResult> {
         let index = params.get_required_u32("index")?;
         let text = params.get_required_str("text")?;
+        let element = self.select_element(index, params, context).await?;
+        let option = element.select_option(text).await?;
+
+        let message = format!("Selected option: {} (value: {})", option.text, option.value);
+        let memory = format!("Selected dropdown option '{}' at index {}", text, index);
+        info!("✅ {}", memory);
+        Ok(ActionResult {
+            extracted_content: Some(message),
+            long_term_memory: Some(memory),
+            ..Default::default()
+        })
+    }
 
+    /// The `<select>` at `index`, resolved in its own frame
+    async fn select_element(
+        &self,
+        index: u32,
+        params: &ActionParams<'_>,
+        context: &mut ActionContext<'_>,
+    ) -> Result<Element> {
         let element = context.selector_map.and_then(|map| map.get(&index))
             .ok_or_else(|| BrowsingError::Tool(format!("Element index {} not found", index)))?;
-
-        let page = context.browser.get_page()?;
         let backend_node_id = element.backend_node_id.ok_or_else(|| {
             BrowsingError::Tool(format!("Element index {} has no backend_node_id", index))
         })?;
-
-        let script = format!(
-            r#"(function() {{
-                const nodeId = {};
-                const searchText = {};
-                const node = document.querySelector(`[data-backend-node-id="${{nodeId}}"]`) ||
-                             Array.from(document.querySelectorAll('select')).find(el => {{
-                                 const rect = el.getBoundingClientRect();
-                                 return rect.width > 0 && rect.height > 0;
-                             }}) || document.querySelector('select');
-                if (!node || node.tagName !== 'SELECT') {{
-                    return {{ success: false, error: 'Element is not a select dropdown' }};
-                }}
-                const options = Array.from(node.options);
-                const option = options.find(opt => opt.text.trim() === searchText || opt.value === searchText || opt.text.includes(searchText));
-                if (!option) {{
-                    return {{ success: false, error: `Option "${{searchText}}" not found` }};
-                }}
-                node.value = option.value;
-                node.dispatchEvent(new Event('change', {{ bubbles: true }}));
-                node.dispatchEvent(new Event('input', {{ bubbles: true }}));
-                return {{ success: true, message: `Selected option: ${{option.text}} (value: ${{option.value}})` }};
-            }})()"#,
-            backend_node_id,
-            json!(text)
-        );
-
-        let result = page.evaluate(&script).await?;
-        let result_obj: serde_json::Value = serde_json::from_str(&result).unwrap_or(serde_json::json!({}));
-
-        if result_obj.get("success").and_then(|v| v.as_bool()).unwrap_or(false) {
-            let message = result_obj.get("message").and_then(|v| v.as_str()).unwrap_or("Selected option");
-            let memory = format!("Selected dropdown option '{}' at index {}", text, index);
-            info!("✅ {}", memory);
-            Ok(ActionResult {
-                extracted_content: Some(message.to_string()),
-                long_term_memory: Some(memory),
-                ..Default::default()
-            })
-        } else {
-            let error = result_obj.get("error").and_then(|v| v.as_str()).unwrap_or("Failed to select dropdown option");
-            Err(BrowsingError::Tool(error.to_string()))
-        }
+        let frame_id = params.frame_id_from_index(index, context.selector_map);
+        let page = context.browser.get_page()?;
+        Ok(page.get_element(backend_node_id).await.with_frame(frame_id))
     }
 }
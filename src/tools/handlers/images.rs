@@ -0,0 +1,115 @@
+//! Image extraction action handler
+
+use super::Handler;
+use crate::actor::images::DEFAULT_DOWNLOAD_LIMIT;
+use crate::actor::{ImageInfo, ImageListOptions};
+use crate::agent::views::ActionResult;
+use crate::error::{BrowsingError, Result};
+use crate::tools::views::{ActionContext, ActionParams};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tracing::info;
+
+/// Metadata key under which `extract_images` returns the images as JSON
+pub const IMAGES_METADATA_KEY: &str = "images";
+
+/// Handler for the extract_images action
+pub struct ImagesHandler {
+    artifacts_dir: PathBuf,
+    allow_file_writes: bool,
+}
+
+impl ImagesHandler {
+    /// Create a handler that saves downloaded images under `artifacts_dir/images`
+    pub fn new(artifacts_dir: PathBuf) -> Self {
+        Self {
+            artifacts_dir,
+            allow_file_writes: true,
+        }
+    }
+
+    /// Set whether `download` may save images
+    pub fn with_file_writes(mut self, allowed: bool) -> Self {
+        self.allow_file_writes = allowed;
+        self
+    }
+}
+
+#[async_trait]
+impl Handler for ImagesHandler {
+    async fn handle(&self, params: &ActionParams<'_>, context: &mut ActionContext<'_>) -> Result<ActionResult> {
+        match params.get_action_type().unwrap_or("unknown") {
+            "extract_images" => self.extract_images(params, context).await,
+            _ => Err(BrowsingError::Tool("Unknown images action".into())),
+        }
+    }
+}
+
+impl ImagesHandler {
+    async fn extract_images(&self, params: &ActionParams<'_>, context: &mut ActionContext<'_>) -> Result<ActionResult> {
+        let defaults = ImageListOptions::default();
+        let options = ImageListOptions {
+            min_width: params.get_optional_u64("min_width").map_or(defaults.min_width, |w| w as u32),
+            min_height: params.get_optional_u64("min_height").map_or(defaults.min_height, |h| h as u32),
+            include_data_uris: params.get_optional_bool("include_data_uris"),
+        };
+        let download = params.get_optional_bool("download");
+        if download && !self.allow_file_writes {
+            return Err(BrowsingError::Tool(
+                "Saving files is disabled by configuration; call extract_images without download"
+                    .into(),
+            ));
+        }
+        let page = context.browser.get_page()?;
+        let mut images = page.list_images(&options).await?;
+
+        let mut memory = format!("Found {} images", images.len());
+        let mut debug_info = None;
+        if download {
+            let limit = params
+                .get_optional_u64("limit")
+                .map_or(DEFAULT_DOWNLOAD_LIMIT, |n| n as usize);
+            let dir = self.artifacts_dir.join("images");
+            // The artifacts directory is the agent's own; a repeated call refreshes the files
+            let saved = page.download_images(&mut images, &dir, limit, true).await?;
+            memory.push_str(&format!(", saved {}", saved));
+            debug_info = Some(format!("saved to {}", dir.display()));
+        }
+        info!("🖼️ {}", memory);
+
+        let content = if images.is_empty() {
+            "No images matched".to_string()
+        } else {
+            images.iter().map(describe).collect::<Vec<_>>().join("\n")
+        };
+        Ok(ActionResult {
+            extracted_content: Some(content),
+            long_term_memory: Some(memory),
+            debug_info,
+            metadata: Some(HashMap::from([(
+                IMAGES_METADATA_KEY.to_string(),
+                serde_json::to_value(&images)?,
+            )])),
+            ..Default::default()
+        })
+    }
+}
+
+/// One line per image, e.g. `[3] https://… 800x400 (natural 1600x800) lazy "Caption"`
+fn describe(image: &ImageInfo) -> String {
+    let mut line = format!(
+        "[{}] {} {}x{} (natural {}x{})",
+        image.index, image.src, image.width, image.height, image.natural_width, image.natural_height
+    );
+    if let Some(loading) = &image.loading {
+        line.push_str(&format!(" {loading}"));
+    }
+    if let Some(caption) = &image.caption {
+        line.push_str(&format!(" \"{caption}\""));
+    }
+    if let Some(path) = &image.saved_path {
+        line.push_str(&format!(" saved to {}", path.display()));
+    }
+    line
+}
@@ -1,23 +1,115 @@
 //! Interaction action handlers
 
 use super::Handler;
+use crate::actor::page::RequestWatch;
+use crate::actor::{
+    ActivationKey, DescendantLocator, Element, Page, PageFingerprint, PointerType, needs_ime,
+};
 use crate::agent::views::ActionResult;
+use crate::browser::{NewTargetWatcher, NewWindowHandling};
 use crate::error::{BrowsingError, Result};
 use crate::tools::views::{ActionContext, ActionParams};
 use async_trait::async_trait;
+use serde_json::json;
+use std::collections::HashMap;
 use tracing::info;
 
+/// How long after a click a window it opened is looked for
+const NEW_WINDOW_WAIT_MS: u64 = 300;
+
+/// How long after submitting a form a navigation or XHR is looked for
+const SUBMIT_SETTLE_MS: u64 = 500;
+
+/// How long after a click its effect on the page is looked for
+const CLICK_SETTLE_MS: u64 = 100;
+
+/// Longest wait for requests a click started before deciding it changed nothing
+const CLICK_NETWORK_IDLE_MS: u64 = 2_000;
+
+/// How long a clicked element is waited on to leave its loading state
+const RESPONSE_WAIT_MS: u64 = 10_000;
+
+/// Metadata key of a click's result naming the strategy that changed the
+/// page: `mouse`, `pointer`, `js_click`, `keyboard_enter`, `keyboard_space`,
+/// or `none` if nothing did
+pub const CLICK_STRATEGY_METADATA_KEY: &str = "click_strategy";
+
+/// Roles whose elements are activated from the keyboard when a click changed nothing
+const KEYBOARD_FALLBACK_ROLES: &[&str] = &["button", "link"];
+
+/// What a click did, for the action's result
+struct ClickOutcome {
+    /// Appended to the action's memory
+    note: String,
+    /// Strategy that changed the page, if the effect was checked
+    strategy: Option<&'static str>,
+}
+
+impl ClickOutcome {
+    fn metadata(&self) -> Option<HashMap<String, serde_json::Value>> {
+        self.strategy.map(|strategy| {
+            HashMap::from([(CLICK_STRATEGY_METADATA_KEY.to_string(), json!(strategy))])
+        })
+    }
+}
+
 /// Handler for user interaction actions
-/// Handles click, input, and send_keys operations
-pub struct InteractionHandler;
+/// Handles click, click_descendant, activate, input, send_keys, form_autofill and submit_form operations
+#[derive(Default)]
+pub struct InteractionHandler {
+    /// What clicks do with windows they open
+    new_window_handling: NewWindowHandling,
+    /// Whether clicks are pointer event sequences rather than mouse events
+    pointer_events: bool,
+    /// Whether a click that changed nothing is retried with a JS click
+    click_fallback: bool,
+    /// Whether a button or link a click did not activate is activated from
+    /// the keyboard instead of with a JS click
+    keyboard_fallback: bool,
+}
+
+impl InteractionHandler {
+    /// Create a handler that treats windows opened by clicks as `new_window_handling` says
+    pub fn new(new_window_handling: NewWindowHandling) -> Self {
+        Self {
+            new_window_handling,
+            pointer_events: false,
+            click_fallback: true,
+            keyboard_fallback: false,
+        }
+    }
+
+    /// Click with pointer event sequences (see [`Element::pointer_click`])
+    pub fn with_pointer_events(mut self, enabled: bool) -> Self {
+        self.pointer_events = enabled;
+        self
+    }
+
+    /// Retry clicks that changed nothing with `el.click()` (see [`Element::js_click`])
+    pub fn with_click_fallback(mut self, enabled: bool) -> Self {
+        self.click_fallback = enabled;
+        self
+    }
+
+    /// Focus buttons and links that clicks changed nothing on and press
+    /// Enter, instead of a JS click, for widgets that only listen for key events
+    pub fn with_keyboard_fallback(mut self, enabled: bool) -> Self {
+        self.keyboard_fallback = enabled;
+        self
+    }
+}
 
 #[async_trait]
 impl Handler for InteractionHandler {
     async fn handle(&self, params: &ActionParams<'_>, context: &mut ActionContext<'_>) -> Result<ActionResult> {
         match params.get_action_type().unwrap_or("unknown") {
             "click" => self.click(params, context).await,
+            "click_descendant" => self.click_descendant(params, context).await,
+            "activate" => self.activate(params, context).await,
             "input" => self.input(params, context).await,
             "send_keys" => self.send_keys(params, context).await,
+            "form_autofill" => self.form_autofill(params, context).await,
+            "submit_form" => self.submit_form(params, context).await,
             _ => Err(BrowsingError::Tool("Unknown interaction action".into())),
         }
     }
@@ -29,12 +121,303 @@ impl InteractionHandler {
         let backend_node_id = params.backend_node_id_from_index(index, context.selector_map);
 
         let page = context.browser.get_page()?;
-        let element = page.get_element(backend_node_id).await;
-        element.click(crate::actor::mouse::MouseButton::Left, 1, None).await?;
+        let element = page
+            .get_element(backend_node_id)
+            .await
+            .with_frame(params.frame_id_from_index(index, context.selector_map));
+        let outcome = match self.click_element(&element, context).await {
+            Ok(outcome) => outcome,
+            // Tell the model what went wrong instead of aborting the step
+            Err(BrowsingError::NotVisible { reason, .. }) => {
+                let message = format!(
+                    "Element {} {}. Scroll to reveal it, or use a different index.",
+                    index, reason
+                );
+                info!("⚠️ {}", message);
+                return Ok(ActionResult {
+                    error: Some(message),
+                    long_term_memory: Some(format!("Could not click element {}: not visible", index)),
+                    debug_info: Some(format!("backend_node_id: {}", backend_node_id)),
+                    ..Default::default()
+                });
+            }
+            Err(e) => return Err(e),
+        };
+        let loading = if params.get_optional_bool("wait_for_response") {
+            wait_for_response(&element).await
+        } else {
+            String::new()
+        };
 
-        let memory = format!("Clicked element {} (backend_node_id: {})", index, backend_node_id);
-        info!("🖱️ {}", memory);
-        Ok(ActionResult::success_with_memory(memory))
+        let memory = format!("Clicked element {}{}{}", index, outcome.note, loading);
+        let debug_info = format!("backend_node_id: {}", backend_node_id);
+        info!("🖱️ {} ({})", memory, debug_info);
+        Ok(ActionResult {
+            metadata: outcome.metadata(),
+            ..ActionResult::success_with_memory(memory).with_debug_info(debug_info)
+        })
+    }
+
+    /// Click the one element inside an indexed container matching role, text and/or css
+    async fn click_descendant(
+        &self,
+        params: &ActionParams<'_>,
+        context: &mut ActionContext<'_>,
+    ) -> Result<ActionResult> {
+        let index = params.get_required_u32("index")?;
+        let locator = DescendantLocator {
+            role: params.get_required_str("role").ok().map(str::to_string),
+            text: params.get_required_str("text").ok().map(str::to_string),
+            css: params.get_required_str("css").ok().map(str::to_string),
+        };
+        if locator.is_empty() {
+            return Err(BrowsingError::Tool(
+                "click_descendant needs at least one of 'role', 'text' or 'css'".to_string(),
+            ));
+        }
+        let container_id = params.backend_node_id_from_index(index, context.selector_map);
+
+        let page = context.browser.get_page()?;
+        let frame_id = params.frame_id_from_index(index, context.selector_map);
+        let container = page.get_element(container_id).await.with_frame(frame_id.clone());
+        let matches = container.find_descendants(&locator).await?;
+        let target = match matches.as_slice() {
+            [target] => target,
+            [] => {
+                let message = format!("No element matching {locator} inside element {index}");
+                info!("⚠️ {}", message);
+                return Ok(ActionResult {
+                    error: Some(message.clone()),
+                    long_term_memory: Some(message),
+                    ..Default::default()
+                });
+            }
+            candidates => {
+                let texts: Vec<String> =
+                    candidates.iter().map(|m| format!("\"{}\"", m.text)).collect();
+                let message = format!(
+                    "{} elements match {locator} inside element {index}: {}. Refine with role, text or css.",
+                    candidates.len(),
+                    texts.join(", ")
+                );
+                info!("⚠️ {}", message);
+                return Ok(ActionResult {
+                    error: Some(message),
+                    long_term_memory: Some(format!(
+                        "Ambiguous click inside element {index}: {} matches for {locator}",
+                        candidates.len()
+                    )),
+                    ..Default::default()
+                });
+            }
+        };
+
+        let element = page.get_element(target.backend_node_id).await.with_frame(frame_id);
+        let outcome = match self.click_element(&element, context).await {
+            Ok(outcome) => outcome,
+            Err(BrowsingError::NotVisible { reason, .. }) => {
+                let message = format!(
+                    "Element matching {locator} inside element {index} {reason}. Scroll to reveal it, or refine the locator."
+                );
+                info!("⚠️ {}", message);
+                return Ok(ActionResult {
+                    error: Some(message),
+                    long_term_memory: Some(format!(
+                        "Could not click {locator} inside element {index}: not visible"
+                    )),
+                    ..Default::default()
+                });
+            }
+            Err(e) => return Err(e),
+        };
+
+        let memory = format!(
+            "Clicked \"{}\" ({locator}) inside element {index}{}",
+            target.text, outcome.note
+        );
+        let debug_info = format!("backend_node_id: {}", target.backend_node_id);
+        info!("🖱️ {} ({})", memory, debug_info);
+        Ok(ActionResult {
+            metadata: outcome.metadata(),
+            ..ActionResult::success_with_memory(memory).with_debug_info(debug_info)
+        })
+    }
+
+    /// Left-click `element` and, if that changed nothing, try one fallback:
+    /// the keyboard for buttons and links when enabled, else a JS click
+    ///
+    /// The page is fingerprinted before and after the click, once requests
+    /// the click started have finished; without a fallback, or if the page
+    /// cannot be fingerprinted (e.g. while it navigates), the effect is not
+    /// checked.
+    async fn click_element(
+        &self,
+        element: &Element,
+        context: &mut ActionContext<'_>,
+    ) -> Result<ClickOutcome> {
+        let page = context.browser.get_page()?;
+        let before = match self.click_fallback || self.keyboard_fallback {
+            true => page.fingerprint().await.ok(),
+            false => None,
+        };
+        let mut requests = match before {
+            Some(_) => page.watch_requests().await.ok(),
+            None => None,
+        };
+        let new_window = self.press_and_handle_window(element, context).await?;
+        let mut outcome = ClickOutcome {
+            note: new_window.clone().unwrap_or_default(),
+            strategy: None,
+        };
+        let Some(before) = before else {
+            return Ok(outcome);
+        };
+
+        let pressed = if self.pointer_events { "pointer" } else { "mouse" };
+        if new_window.is_some() || changed_since(&page, &before, requests.as_mut()).await {
+            outcome.strategy = Some(pressed);
+            return Ok(outcome);
+        }
+        let keyboard_role = match self.keyboard_fallback {
+            true => element
+                .role()
+                .await
+                .ok()
+                .flatten()
+                .filter(|role| KEYBOARD_FALLBACK_ROLES.contains(&role.as_str())),
+            false => None,
+        };
+        let fallback = if let Some(role) = keyboard_role {
+            info!("⌨️ Click changed nothing, activating the {} from the keyboard", role);
+            // Only the role's first key, so a click is retried at most once
+            let key = ActivationKey::for_role(&role)[0];
+            if press_activation_keys(element, &page, &[key], requests.as_mut())
+                .await?
+                .is_some()
+            {
+                outcome.strategy = Some(keyboard_strategy(key));
+                outcome.note += &format!(
+                    "; the click changed nothing, so the {role} was focused and activated with {key}, which did"
+                );
+                return Ok(outcome);
+            }
+            Some(format!("pressing {key}"))
+        } else if self.click_fallback {
+            info!("🖱️ Click changed nothing, retrying with a JS click");
+            element.js_click().await?;
+            if changed_since(&page, &before, requests.as_mut()).await {
+                outcome.strategy = Some("js_click");
+                outcome.note += "; the click changed nothing, so it was retried as a JS click, which did";
+                return Ok(outcome);
+            }
+            Some("a JS click".to_string())
+        } else {
+            None
+        };
+
+        outcome.strategy = Some("none");
+        let what = match fallback {
+            Some(fallback) => format!("neither the click nor {fallback} changed"),
+            None => "the click did not change".to_string(),
+        };
+        outcome.note += &format!(
+            "; {what} the page (URL, focus, DOM or scroll). \
+             Something may cover the element, or it may not be the right one"
+        );
+        Ok(outcome)
+    }
+
+    /// Focus an element and press the keys that activate it for its role
+    ///
+    /// For widgets that only respond to the keyboard. Reports the key that
+    /// changed the page, like a click's strategy.
+    async fn activate(&self, params: &ActionParams<'_>, context: &mut ActionContext<'_>) -> Result<ActionResult> {
+        let index = params.get_required_u32("index")?;
+        let backend_node_id = params.backend_node_id_from_index(index, context.selector_map);
+
+        let page = context.browser.get_page()?;
+        let element = page
+            .get_element(backend_node_id)
+            .await
+            .with_frame(params.frame_id_from_index(index, context.selector_map));
+        let role = element.role().await?;
+        let keys = ActivationKey::for_role(role.as_deref().unwrap_or_default());
+        let described = match &role {
+            Some(role) => format!("element {index} ({role})"),
+            None => format!("element {index}"),
+        };
+
+        let mut requests = page.watch_requests().await.ok();
+        let (memory, strategy) = match press_activation_keys(&element, &page, keys, requests.as_mut()).await? {
+            Some(key) => (
+                format!("Activated {described} with {key}"),
+                keyboard_strategy(key),
+            ),
+            None => {
+                let pressed: Vec<String> = keys.iter().map(ToString::to_string).collect();
+                (
+                    format!(
+                        "Focused {described} and pressed {}, but the page did not change (URL, focus, DOM or scroll)",
+                        pressed.join(" then ")
+                    ),
+                    "none",
+                )
+            }
+        };
+        info!("⌨️ {}", memory);
+        Ok(ActionResult {
+            metadata: Some(HashMap::from([(
+                CLICK_STRATEGY_METADATA_KEY.to_string(),
+                json!(strategy),
+            )])),
+            ..ActionResult::success_with_memory(memory)
+        })
+    }
+
+    /// Left-click `element` and apply the new window handling to a window it opens
+    ///
+    /// Returns a note for the action's memory if a window was opened.
+    async fn press_and_handle_window(
+        &self,
+        element: &Element,
+        context: &mut ActionContext<'_>,
+    ) -> Result<Option<String>> {
+        let handling = self.new_window_handling;
+        if handling == NewWindowHandling::Ignore {
+            self.press(element).await?;
+            return Ok(None);
+        }
+
+        let client = context.browser.get_cdp_client()?;
+        let mut watcher = NewTargetWatcher::start(&client).await?;
+        self.press(element).await?;
+        let Some(target_id) = watcher
+            .wait(std::time::Duration::from_millis(NEW_WINDOW_WAIT_MS))
+            .await
+        else {
+            return Ok(None);
+        };
+
+        context.browser.handle_new_window(&target_id, handling).await?;
+        let tab_id = &target_id[target_id.len().saturating_sub(4)..];
+        let outcome = match handling {
+            NewWindowHandling::AutoSwitch => "now switched to",
+            NewWindowHandling::AutoClose => "which was closed",
+            NewWindowHandling::Ignore => "left open",
+        };
+        info!("🪟 Click opened tab #{}, {}", tab_id, outcome);
+        Ok(Some(format!("; it opened tab #{tab_id}, {outcome}")))
+    }
+
+    /// Left-click `element`, with a mouse pointer sequence in pointer events mode
+    async fn press(&self, element: &Element) -> Result<()> {
+        if self.pointer_events {
+            element.pointer_click(PointerType::Mouse).await
+        } else {
+            element
+                .click(crate::actor::mouse::MouseButton::Left, 1, None)
+                .await
+        }
     }
 
     async fn input(&self, params: &ActionParams<'_>, context: &mut ActionContext<'_>) -> Result<ActionResult> {
@@ -42,13 +425,31 @@ impl InteractionHandler {
         let text = params.get_required_str("text")?;
         let backend_node_id = params.backend_node_id_from_index(index, context.selector_map);
 
+        // CJK and Hangul text goes through IME composition unless told otherwise
+        let ime = params
+            .inner()
+            .get("ime")
+            .and_then(|v| v.as_bool())
+            .unwrap_or_else(|| needs_ime(text));
+
         let page = context.browser.get_page()?;
-        let element = page.get_element(backend_node_id).await;
-        element.fill(text).await?;
+        let element = page
+            .get_element(backend_node_id)
+            .await
+            .with_frame(params.frame_id_from_index(index, context.selector_map));
+        if ime {
+            element.input_with_ime(text).await?;
+        } else {
+            element.fill(text).await?;
+        }
 
-        let memory = format!("Input text into element {} (backend_node_id: {})", index, backend_node_id);
-        info!("⌨️ {}", memory);
-        Ok(ActionResult::success_with_memory(memory))
+        let mut memory = format!("Input text into element {}", index);
+        if ime {
+            memory.push_str(" using IME composition");
+        }
+        let debug_info = format!("backend_node_id: {}", backend_node_id);
+        info!("⌨️ {} ({})", memory, debug_info);
+        Ok(ActionResult::success_with_memory(memory).with_debug_info(debug_info))
     }
 
     async fn send_keys(&self, params: &ActionParams<'_>, context: &mut ActionContext<'_>) -> Result<ActionResult> {
@@ -60,8 +461,233 @@ impl InteractionHandler {
             tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
         }
 
+        // The keys may be a secret, which only the agent knows to mask
         let memory = format!("Sent keys: {}", keys);
-        info!("⌨️ {}", memory);
+        info!("⌨️ Sent {} keys", keys.split_whitespace().count());
         Ok(ActionResult::success_with_memory(memory))
     }
+
+    async fn form_autofill(&self, params: &ActionParams<'_>, context: &mut ActionContext<'_>) -> Result<ActionResult> {
+        let values = params
+            .inner()
+            .get("values")
+            .and_then(|v| v.as_object())
+            .ok_or_else(|| BrowsingError::Tool("Missing 'values' parameter".to_string()))?;
+
+        let page = context.browser.get_page()?;
+        let forms = page.get_all_forms().await?;
+
+        // Use the requested form, or the one matching the most keys
+        let form = match params.get_optional_u64("form_index") {
+            Some(i) => forms.get(i as usize).ok_or_else(|| {
+                BrowsingError::Tool(format!("Form index {} out of range ({} forms)", i, forms.len()))
+            })?,
+            None => forms
+                .iter()
+                .max_by_key(|f| values.keys().filter(|k| f.find_field(k).is_some()).count())
+                .ok_or_else(|| BrowsingError::Tool("No forms found on the page".to_string()))?,
+        };
+
+        let mut filled = Vec::new();
+        let mut missing = Vec::new();
+        for (key, value) in values {
+            let value = match value {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            match form.find_field(key) {
+                Some(field) => match page.fill_form_field(field, &value).await {
+                    Ok(()) => filled.push(key.as_str()),
+                    Err(e) => missing.push(format!("{} ({})", key, e)),
+                },
+                None => missing.push(key.clone()),
+            }
+        }
+
+        if filled.is_empty() {
+            return Err(BrowsingError::Tool(format!("No form fields matched: {}", missing.join(", "))));
+        }
+
+        let mut memory = format!("Filled form fields: {}", filled.join(", "));
+        if !missing.is_empty() {
+            memory.push_str(&format!("; not filled: {}", missing.join(", ")));
+        }
+        info!("📝 {}", memory);
+        Ok(ActionResult::success_with_memory(memory))
+    }
+
+    /// Submit the form of the indexed element and report what followed
+    async fn submit_form(
+        &self,
+        params: &ActionParams<'_>,
+        context: &mut ActionContext<'_>,
+    ) -> Result<ActionResult> {
+        let index = params.get_required_u32("index")?;
+        let backend_node_id = params.backend_node_id_from_index(index, context.selector_map);
+        let form = context
+            .selector_map
+            .and_then(|map| map.get(&index))
+            .and_then(|element| element.form_id)
+            .map(|form_id| format!(" #{form_id}"))
+            .unwrap_or_default();
+
+        let client = context.browser.get_cdp_client()?;
+        let session_id = context.browser.get_session_info().await?.session_id;
+        let mut events = client.subscribe_events();
+        // XHRs only show up with the network domain enabled
+        let _ = client
+            .send_command_with_session("Network.enable", serde_json::json!({}), Some(&session_id))
+            .await;
+
+        let page = context.browser.get_page()?;
+        let element = page
+            .get_element(backend_node_id)
+            .await
+            .with_frame(params.frame_id_from_index(index, context.selector_map));
+        let submission = match element.submit_form().await {
+            Ok(submission) => submission,
+            Err(BrowsingError::Validation(message) | BrowsingError::Dom(message)) => {
+                let message = format!("Could not submit the form of element {index}: {message}");
+                info!("⚠️ {}", message);
+                return Ok(ActionResult {
+                    error: Some(message.clone()),
+                    long_term_memory: Some(message),
+                    ..Default::default()
+                });
+            }
+            Err(e) => return Err(e),
+        };
+
+        let mut navigated_to = None;
+        let mut xhr_count = 0;
+        let deadline =
+            tokio::time::Instant::now() + std::time::Duration::from_millis(SUBMIT_SETTLE_MS);
+        while navigated_to.is_none() {
+            let Ok(Ok(event)) = tokio::time::timeout_at(deadline, events.recv()).await else {
+                break;
+            };
+            if event.get("sessionId").and_then(|v| v.as_str()) != Some(session_id.as_str()) {
+                continue;
+            }
+            let params = &event["params"];
+            match event["method"].as_str().unwrap_or_default() {
+                "Page.frameNavigated" if params["frame"].get("parentId").is_none() => {
+                    navigated_to = params["frame"]["url"].as_str().map(str::to_string);
+                }
+                "Network.requestWillBeSent"
+                    if matches!(params["type"].as_str(), Some("XHR" | "Fetch")) =>
+                {
+                    xhr_count += 1;
+                }
+                _ => {}
+            }
+        }
+
+        let effect = match (navigated_to, xhr_count) {
+            (Some(url), _) => format!("the page navigated to {url}"),
+            (None, 0) => "no navigation or XHR followed".to_string(),
+            (None, 1) => "an XHR was sent".to_string(),
+            (None, n) => format!("{n} XHRs were sent"),
+        };
+        let memory = format!(
+            "Submitted form{form} of element {index} with {submission}; {effect}"
+        );
+        info!("📨 {}", memory);
+        Ok(ActionResult::success_with_memory(memory))
+    }
+}
+
+/// Focus `element` and press `keys` in turn until one changes the page
+///
+/// The page is fingerprinted after focusing, so the focus change itself does
+/// not count. Returns the key that changed the page, if any did.
+async fn press_activation_keys(
+    element: &Element,
+    page: &Page,
+    keys: &[ActivationKey],
+    mut requests: Option<&mut RequestWatch>,
+) -> Result<Option<ActivationKey>> {
+    element.focus().await?;
+    let Ok(focused) = page.fingerprint().await else {
+        // Without a fingerprint the effect cannot be checked; press the first key only
+        element.press_activation_key(keys[0]).await?;
+        return Ok(Some(keys[0]));
+    };
+    for &key in keys {
+        element.press_activation_key(key).await?;
+        if changed_since(page, &focused, requests.as_deref_mut()).await {
+            return Ok(Some(key));
+        }
+    }
+    Ok(None)
+}
+
+/// Click strategy reported for activating with `key`
+fn keyboard_strategy(key: ActivationKey) -> &'static str {
+    match key {
+        ActivationKey::Enter => "keyboard_enter",
+        ActivationKey::Space => "keyboard_space",
+    }
+}
+
+/// Whether the page differs from `before` once a click had time to take effect
+///
+/// If it looks unchanged, requests seen by `requests` are waited on and the
+/// page checked again, since a response may still change it. A page that can
+/// no longer be fingerprinted, e.g. because it is navigating away, counts as
+/// changed.
+async fn changed_since(
+    page: &Page,
+    before: &PageFingerprint,
+    requests: Option<&mut RequestWatch>,
+) -> bool {
+    let settle = std::time::Duration::from_millis(CLICK_SETTLE_MS);
+    tokio::time::sleep(settle).await;
+    let changed = || async {
+        page.fingerprint()
+            .await
+            .map_or(true, |after| after.changed_since(before))
+    };
+    if changed().await {
+        return true;
+    }
+    let Some(requests) = requests else {
+        return false;
+    };
+    if !requests.wait_until_idle(CLICK_NETWORK_IDLE_MS).await {
+        return false;
+    }
+    tokio::time::sleep(settle).await;
+    changed().await
+}
+
+/// Wait for a clicked element to leave its loading state, `aria-busy="true"`
+/// or `disabled`
+///
+/// Returns a note for the action's memory if it was loading.
+async fn wait_for_response(element: &Element) -> String {
+    for (attribute, loading_value) in [("aria-busy", Some("true")), ("disabled", None)] {
+        let Ok(value) = element.attribute(attribute).await else {
+            return String::new();
+        };
+        let loading = match loading_value {
+            Some(loading_value) => value.as_deref() == Some(loading_value),
+            None => value.is_some(),
+        };
+        if !loading {
+            continue;
+        }
+        info!("⏳ Waiting for the clicked element to stop being {attribute}");
+        return match element
+            .wait_for_attribute_change(attribute, None, RESPONSE_WAIT_MS)
+            .await
+        {
+            Ok(_) => "; waited for it to finish loading".to_string(),
+            Err(_) => format!(
+                "; it was still loading ({attribute}) after {}s",
+                RESPONSE_WAIT_MS / 1000
+            ),
+        };
+    }
+    String::new()
 }
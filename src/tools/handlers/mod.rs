@@ -3,16 +3,23 @@
 //! This module contains individual action handlers organized by functionality.
 
 mod advanced;
+mod assertions;
 mod content;
 pub mod extract;
+mod images;
 mod interaction;
 mod navigation;
+mod snapshot;
 mod tabs;
 
-pub use advanced::AdvancedHandler;
+pub use advanced::{AdvancedHandler, CAPTURED_RESPONSES_METADATA_KEY, RESPONSE_METADATA_KEY};
+pub use assertions::AssertionHandler;
 pub use content::ContentHandler;
-pub use interaction::InteractionHandler;
+pub use images::{IMAGES_METADATA_KEY, ImagesHandler};
+pub use interaction::{CLICK_STRATEGY_METADATA_KEY, InteractionHandler};
 pub use navigation::NavigationHandler;
+pub use snapshot::{SAVED_PAGE_METADATA_KEY, SnapshotHandler};
+pub(crate) use snapshot::file_name as saved_page_file_name;
 pub use tabs::TabsHandler;
 
 use crate::agent::views::ActionResult;
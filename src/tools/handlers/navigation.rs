@@ -1,16 +1,38 @@
 //! Navigation action handlers
 //!
-//! Handlers for search and navigate actions.
+//! Handlers for search, navigate and checkpoint actions.
 
 use super::Handler;
+use crate::actor::checkpoint::SERVER_STATE_NOTE;
+use crate::actor::{
+    CheckpointId, ColorScheme, EmulationSettings, LoadState, NavigateOptions, VisionDeficiency,
+};
 use crate::agent::views::ActionResult;
 use crate::error::{BrowsingError, Result};
+use crate::tools::search::{DEFAULT_SEARCH_ENGINES, SearchEngine, search_with_fallback};
 use crate::tools::views::{ActionContext, ActionParams};
+use crate::traits::BrowserClient;
 use async_trait::async_trait;
 use tracing::info;
 
 /// Handler for navigation actions
-pub struct NavigationHandler;
+pub struct NavigationHandler {
+    /// Engines tried in order by the search action
+    search_engines: Vec<SearchEngine>,
+}
+
+impl NavigationHandler {
+    /// Create a handler that searches with the given engine fallback chain
+    pub fn new(search_engines: Vec<SearchEngine>) -> Self {
+        Self { search_engines }
+    }
+}
+
+impl Default for NavigationHandler {
+    fn default() -> Self {
+        Self::new(DEFAULT_SEARCH_ENGINES.to_vec())
+    }
+}
 
 #[async_trait]
 impl Handler for NavigationHandler {
@@ -24,6 +46,8 @@ impl Handler for NavigationHandler {
         match action_type {
             "search" => self.search(params, context).await,
             "navigate" => self.navigate(params, context).await,
+            "save_checkpoint" => self.save_checkpoint(context).await,
+            "restore_checkpoint" => self.restore_checkpoint(params, context).await,
             _ => Err(BrowsingError::Tool(format!(
                 "Unknown navigation action: {action_type}"
             ))),
@@ -32,48 +56,209 @@ impl Handler for NavigationHandler {
 }
 
 impl NavigationHandler {
-    /// Search the web using a search engine
+    /// Search the web, falling back to the next engine on block or consent pages
     async fn search(&self, params: &ActionParams<'_>, context: &mut ActionContext<'_>) -> Result<ActionResult> {
         let query = params.get_required_str("query")?;
-        let engine = params
-            .get_required_str("engine")
-            .unwrap_or("duckduckgo");
-
-        let encoded_query = urlencoding::encode(query);
-        let search_url = match engine.to_lowercase().as_str() {
-            "duckduckgo" => format!("https://duckduckgo.com/?q={encoded_query}"),
-            "google" => format!("https://www.google.com/search?q={encoded_query}&udm=14"),
-            "bing" => format!("https://www.bing.com/search?q={encoded_query}"),
-            _ => {
-                return Err(BrowsingError::Tool(format!(
-                    "Unsupported search engine: {}. Options: duckduckgo, google, bing",
-                    engine
-                )))
+        let site = params.get_required_str("site").ok();
+        let num_results = params.get_optional_u64("num_results").map(|n| n as u32);
+
+        // An explicitly requested engine is tried first, then the configured chain
+        let mut engines = Vec::new();
+        if let Ok(engine) = params.get_required_str("engine") {
+            engines.push(SearchEngine::from_name(engine)?);
+        }
+        for engine in &self.search_engines {
+            if !engines.contains(engine) {
+                engines.push(*engine);
             }
-        };
+        }
 
-        context.browser.navigate(&search_url).await?;
-        let memory = format!("Searched {} for '{}'", engine, query);
-        info!("🔍 {}", memory);
-        Ok(ActionResult::success_with_memory(memory))
+        let mut loader: &mut dyn BrowserClient = &mut *context.browser;
+        let outcome = search_with_fallback(&mut loader, &engines, query, site, num_results).await?;
+
+        let memory = outcome.memory(query);
+        let mut result = ActionResult::success_with_memory(&memory);
+        match outcome.debug_info() {
+            Some(debug_info) => {
+                info!("🔍 {} ({})", memory, debug_info);
+                result = result.with_debug_info(debug_info);
+            }
+            None => info!("🔍 {}", memory),
+        }
+        Ok(result)
     }
 
     /// Navigate to a URL
     async fn navigate(&self, params: &ActionParams<'_>, context: &mut ActionContext<'_>) -> Result<ActionResult> {
         let url = params.get_required_str("url")?;
         let new_tab = params.get_optional_bool("new_tab");
+        let options = navigate_options(params)?;
+        let current = context.browser.emulation().await.unwrap_or_default();
+        let emulation = emulation_settings(params, current)?;
+        let emulated = emulation
+            .map(|settings| format!(" with {}", settings.summary()))
+            .unwrap_or_default();
+        // The settings are the model's own; the history keeps them, the prompt needn't
+        let with_emulation = |result: ActionResult| match emulation {
+            Some(settings) => result.with_debug_info(format!("emulation: {}", settings.summary())),
+            None => result,
+        };
+
+        // Navigating to the page already loaded may be served from the cache;
+        // a reload ignoring it fetches everything again
+        if params.get_optional_bool("bypass_cache")
+            && !new_tab
+            && let Ok(current_url) = context.browser.get_current_url().await
+            && same_url(&current_url, url)
+        {
+            if let Some(settings) = emulation {
+                context.browser.set_emulation(settings).await?;
+            }
+            context.browser.reload(true, options.wait_until).await?;
+            let memory = format!("Reloaded {} bypassing the cache", url);
+            info!("🔄 Reloaded {} bypassing the cache{}", url_for_log(url), emulated);
+            return Ok(with_emulation(ActionResult::success_with_memory(memory)));
+        }
 
         if new_tab {
-            let target_id = context.browser.create_tab(Some(url)).await?;
+            // A new tab can't carry options or emulation, so it is opened blank
+            // and navigated
+            let open_blank = !options.is_empty() || emulation.is_some();
+            let initial_url = if open_blank { "about:blank" } else { url };
+            let target_id = context.browser.create_tab(Some(initial_url)).await?;
             context.browser.switch_to_tab(&target_id).await?;
+            if let Some(settings) = emulation {
+                context.browser.set_emulation(settings).await?;
+            }
+            if open_blank {
+                context.browser.navigate_with_options(url, &options).await?;
+            }
             let memory = format!("Opened new tab with URL {}", url);
-            info!("🔗 {}", memory);
-            Ok(ActionResult::success_with_memory(memory))
+            info!("🔗 Opened new tab with URL {}{}", url_for_log(url), emulated);
+            Ok(with_emulation(ActionResult::success_with_memory(memory)))
         } else {
-            context.browser.navigate(url).await?;
+            if let Some(settings) = emulation {
+                context.browser.set_emulation(settings).await?;
+            }
+            context.browser.navigate_with_options(url, &options).await?;
             let memory = format!("Navigated to {}", url);
-            info!("🔗 {}", memory);
-            Ok(ActionResult::success_with_memory(memory))
+            info!("🔗 Navigated to {}{}", url_for_log(url), emulated);
+            Ok(with_emulation(ActionResult::success_with_memory(memory)))
         }
     }
+
+    /// Save the page state so a later branch can start from it
+    async fn save_checkpoint(&self, context: &mut ActionContext<'_>) -> Result<ActionResult> {
+        let id = context.browser.checkpoint().await?;
+        let url = context.browser.get_current_url().await.unwrap_or_default();
+        let memory = format!(
+            "Saved checkpoint {id} at {url}; restoring it resets URL, cookies, storage and scroll, but {SERVER_STATE_NOTE}"
+        );
+        info!("📌 {}", memory);
+        Ok(ActionResult::success_with_memory(memory))
+    }
+
+    /// Return to a saved checkpoint
+    async fn restore_checkpoint(
+        &self,
+        params: &ActionParams<'_>,
+        context: &mut ActionContext<'_>,
+    ) -> Result<ActionResult> {
+        let id = CheckpointId(params.get_required_u32("checkpoint_id")?);
+        context.browser.restore(id).await?;
+        let url = context.browser.get_current_url().await.unwrap_or_default();
+        let memory = format!("Restored checkpoint {id} at {url}; {SERVER_STATE_NOTE}");
+        info!("⏪ {}", memory);
+        Ok(ActionResult::success_with_memory(memory))
+    }
+}
+
+/// `url` with any password in it masked, for logs
+fn url_for_log(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(mut parsed) if parsed.password().is_some() => {
+            let _ = parsed.set_password(Some("***"));
+            parsed.to_string()
+        }
+        _ => url.to_string(),
+    }
+}
+
+/// Whether two URLs are the same once parsed, e.g. `https://a.test` and `https://a.test/`
+fn same_url(a: &str, b: &str) -> bool {
+    match (url::Url::parse(a), url::Url::parse(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Read `referrer`, `headers` and `wait_until` from navigate parameters
+/// `current` with the color_scheme, vision_deficiency and save_data params applied,
+/// or `None` if none is given
+fn emulation_settings(
+    params: &ActionParams<'_>,
+    current: EmulationSettings,
+) -> Result<Option<EmulationSettings>> {
+    let inner = params.inner();
+    let given = |key: &str| inner.get(key).filter(|v| !v.is_null()).is_some();
+    if !["color_scheme", "vision_deficiency", "save_data"]
+        .into_iter()
+        .any(given)
+    {
+        return Ok(None);
+    }
+
+    let mut settings = current;
+    if let Ok(name) = params.get_required_str("color_scheme") {
+        settings.color_scheme = match name {
+            "no-preference" | "none" => None,
+            _ => Some(ColorScheme::from_name(name).ok_or_else(|| {
+                BrowsingError::Tool(format!(
+                    "Invalid color_scheme '{name}': expected dark, light or none"
+                ))
+            })?),
+        };
+    }
+    if let Ok(name) = params.get_required_str("vision_deficiency") {
+        settings.vision_deficiency = VisionDeficiency::from_name(name).ok_or_else(|| {
+            BrowsingError::Tool(format!(
+                "Invalid vision_deficiency '{name}': expected none, blurred_vision, \
+                 reduced_contrast, achromatopsia, deuteranopia, protanopia or tritanopia"
+            ))
+        })?;
+    }
+    if given("save_data") {
+        settings.save_data = params.get_optional_bool("save_data");
+    }
+    Ok(Some(settings))
+}
+
+fn navigate_options(params: &ActionParams<'_>) -> Result<NavigateOptions> {
+    let mut options = NavigateOptions::new();
+    options.referrer = params.get_required_str("referrer").ok().map(str::to_string);
+
+    if let Some(headers) = params.inner().get("headers").filter(|v| !v.is_null()) {
+        let headers = headers
+            .as_object()
+            .ok_or_else(|| BrowsingError::Tool("'headers' must be an object".to_string()))?;
+        for (name, value) in headers {
+            let value = match value {
+                serde_json::Value::String(value) => value.clone(),
+                other => other.to_string(),
+            };
+            options.headers.insert(name.clone(), value);
+        }
+    }
+
+    options.wait_until = match params.get_required_str("wait_until").ok() {
+        None | Some("none") => None,
+        Some("domcontentloaded") => Some(LoadState::DomContentLoaded),
+        Some("load") => Some(LoadState::Load),
+        Some(other) => {
+            return Err(BrowsingError::Tool(format!(
+                "Invalid wait_until '{other}': expected none, domcontentloaded or load"
+            )));
+        }
+    };
+    Ok(options)
 }
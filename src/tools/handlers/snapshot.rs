@@ -0,0 +1,88 @@
+//! Page snapshot action handler
+
+use super::Handler;
+use crate::agent::views::ActionResult;
+use crate::error::{BrowsingError, Result};
+use crate::tools::views::{ActionContext, ActionParams};
+use async_trait::async_trait;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Metadata key under which `save_page` returns the saved file's path and size
+pub const SAVED_PAGE_METADATA_KEY: &str = "saved_page";
+
+/// Handler for the save_page action
+pub struct SnapshotHandler {
+    artifacts_dir: PathBuf,
+    allow_file_writes: bool,
+}
+
+impl SnapshotHandler {
+    /// Create a handler that saves pages under `artifacts_dir/pages`
+    pub fn new(artifacts_dir: PathBuf) -> Self {
+        Self {
+            artifacts_dir,
+            allow_file_writes: true,
+        }
+    }
+
+    /// Set whether pages may be saved
+    pub fn with_file_writes(mut self, allowed: bool) -> Self {
+        self.allow_file_writes = allowed;
+        self
+    }
+}
+
+#[async_trait]
+impl Handler for SnapshotHandler {
+    async fn handle(&self, params: &ActionParams<'_>, context: &mut ActionContext<'_>) -> Result<ActionResult> {
+        match params.get_action_type().unwrap_or("unknown") {
+            "save_page" => self.save_page(params, context).await,
+            _ => Err(BrowsingError::Tool("Unknown snapshot action".into())),
+        }
+    }
+}
+
+impl SnapshotHandler {
+    async fn save_page(&self, params: &ActionParams<'_>, context: &mut ActionContext<'_>) -> Result<ActionResult> {
+        if !self.allow_file_writes {
+            return Err(BrowsingError::Tool("Saving files is disabled by configuration".into()));
+        }
+        let path = self
+            .artifacts_dir
+            .join("pages")
+            .join(file_name(params.get_required_str("filename").ok()));
+        let size = context.browser.get_page()?.save_mhtml(&path).await?;
+
+        let memory = format!("Saved page to {}", path.display());
+        let debug_info = format!("{} bytes", size);
+        info!("💾 {} ({})", memory, debug_info);
+        Ok(ActionResult {
+            extracted_content: Some(format!("{} ({})", memory, debug_info)),
+            long_term_memory: Some(memory),
+            debug_info: Some(debug_info),
+            metadata: Some(HashMap::from([(
+                SAVED_PAGE_METADATA_KEY.to_string(),
+                json!({ "path": path, "size": size }),
+            )])),
+            ..Default::default()
+        })
+    }
+}
+
+/// File name for a saved page: the last component of `requested` with an
+/// `.mhtml` extension, or a timestamped name
+pub(crate) fn file_name(requested: Option<&str>) -> String {
+    let name = requested
+        .and_then(|name| Path::new(name).file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| format!("page-{}", chrono::Utc::now().format("%Y%m%dT%H%M%S%.3f")));
+    if name.ends_with(".mhtml") || name.ends_with(".mht") {
+        name
+    } else {
+        format!("{name}.mhtml")
+    }
+}
+
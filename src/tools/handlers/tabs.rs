@@ -1,14 +1,17 @@
 //! Tab management action handlers
 
 use super::Handler;
+use crate::agent::page_group::SWITCHED_TAB_METADATA_KEY;
+use crate::agent::tab_hygiene::PINNED_TAB_METADATA_KEY;
 use crate::agent::views::ActionResult;
 use crate::error::{BrowsingError, Result};
 use crate::tools::views::{ActionContext, ActionParams};
 use async_trait::async_trait;
+use std::collections::HashMap;
 use tracing::info;
 
 /// Handler for tab management actions
-/// Handles switch, close, and create operations
+/// Handles switch, close, keep_tab, and create operations
 pub struct TabsHandler;
 
 #[async_trait]
@@ -17,6 +20,7 @@ impl Handler for TabsHandler {
         match params.get_action_type().unwrap_or("unknown") {
             "switch" => self.switch_tab(params, context).await,
             "close" => self.close_tab(params, context).await,
+            "keep_tab" => self.keep_tab(params, context).await,
             _ => Err(BrowsingError::Tool("Unknown tabs action".into())),
         }
     }
@@ -31,7 +35,14 @@ impl TabsHandler {
         let current_url = context.browser.get_current_url().await.unwrap_or_default();
         let memory = format!("Switched to tab #{} (URL: {})", tab_id, current_url);
         info!("🔄 {}", memory);
-        Ok(ActionResult::success_with_memory(memory))
+        let mut result = ActionResult::success_with_memory(memory)
+            .with_debug_info(format!("target_id: {}", target_id));
+        // Lets the agent update its tab overview before the next step
+        result.metadata = Some(HashMap::from([(
+            SWITCHED_TAB_METADATA_KEY.to_string(),
+            serde_json::Value::String(target_id),
+        )]));
+        Ok(result)
     }
 
     async fn close_tab(&self, params: &ActionParams<'_>, context: &mut ActionContext<'_>) -> Result<ActionResult> {
@@ -42,7 +53,23 @@ impl TabsHandler {
         let current_url = context.browser.get_current_url().await.unwrap_or_default();
         let memory = format!("Closed tab #{}, now on {}", tab_id, current_url);
         info!("❌ {}", memory);
-        Ok(ActionResult::success_with_memory(memory))
+        Ok(ActionResult::success_with_memory(memory)
+            .with_debug_info(format!("target_id: {}", target_id)))
+    }
+
+    async fn keep_tab(&self, params: &ActionParams<'_>, context: &mut ActionContext<'_>) -> Result<ActionResult> {
+        let tab_id = params.get_required_str("tab_id")?;
+        let target_id = self.get_target_id_from_tab_id(context, tab_id).await?;
+
+        let memory = format!("Pinned tab #{}", tab_id);
+        info!("📌 {}", memory);
+        let mut result = ActionResult::success_with_memory(memory)
+            .with_debug_info(format!("target_id: {}", target_id));
+        result.metadata = Some(HashMap::from([(
+            PINNED_TAB_METADATA_KEY.to_string(),
+            serde_json::Value::String(target_id),
+        )]));
+        Ok(result)
     }
 
     async fn get_target_id_from_tab_id(&self, context: &mut ActionContext<'_>, tab_id: &str) -> Result<String> {
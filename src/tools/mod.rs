@@ -1,12 +1,19 @@
 //! Tools and actions registry
 
+pub mod dry_run;
+pub mod evaluate;
 pub mod handlers;
+pub mod profile;
 pub mod registry;
+pub mod search;
 pub mod service;
+pub mod undo;
 pub mod views;
 
 #[cfg(test)]
 mod service_test;
 
+pub use evaluate::EvaluatePolicy;
+pub use profile::CapabilityProfile;
 pub use service::Tools;
 pub use views::{ActionModel, ActionRegistry, RegisteredAction};
@@ -0,0 +1,61 @@
+//! Capability profiles for the built-in actions
+//!
+//! Deployments trust the model to different degrees. A profile names a level:
+//! which actions are left out of the registry (and so out of the prompt), what
+//! `evaluate` may run and whether actions may write files. Select one with
+//! [`ToolsConfig`](crate::config::ToolsConfig), which can also adjust it with
+//! custom action lists.
+
+use crate::tools::evaluate::EvaluatePolicy;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Named capability level for the built-in actions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CapabilityProfile {
+    /// Read-only browsing for research: no JavaScript, no uploads, no form
+    /// submission and no files written
+    Safe,
+    /// Everyday automation: forms and files work, `evaluate` is read-only
+    /// and uploads are disabled
+    Standard,
+    /// Every action, with `evaluate` running in the page
+    #[default]
+    Full,
+}
+
+impl CapabilityProfile {
+    /// Built-in actions the profile leaves out
+    pub fn excluded_actions(self) -> &'static [&'static str] {
+        match self {
+            Self::Safe => &["evaluate", "upload_file", "form_autofill", "submit_form"],
+            Self::Standard => &["upload_file"],
+            Self::Full => &[],
+        }
+    }
+
+    /// What `evaluate` may run under the profile
+    pub fn evaluate_policy(self) -> EvaluatePolicy {
+        match self {
+            Self::Safe => EvaluatePolicy::Disabled,
+            Self::Standard => EvaluatePolicy::ReadOnly,
+            Self::Full => EvaluatePolicy::Full,
+        }
+    }
+
+    /// Whether actions may write files, e.g. `extract_images` downloads
+    pub fn allows_file_writes(self) -> bool {
+        !matches!(self, Self::Safe)
+    }
+}
+
+impl fmt::Display for CapabilityProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Safe => "safe",
+            Self::Standard => "standard",
+            Self::Full => "full",
+        })
+    }
+}
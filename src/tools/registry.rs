@@ -1,5 +1,6 @@
 //! Action registry implementation
 
+use crate::browser::BrowserProfile;
 use crate::tools::views::{ActionHandler, ActionRegistry, RegisteredAction};
 use std::sync::Arc;
 
@@ -36,10 +37,31 @@ impl Registry {
             description,
             domains,
             handler: None,
+            condition: None,
         };
         self.registry.actions.insert(name, action);
     }
 
+    /// Registers an action that is only available when `condition` holds for
+    /// the browser profile, e.g. an action that needs a visible browser
+    ///
+    /// Unavailable actions are refused by [`Tools::act`](crate::tools::Tools::act)
+    /// and left out of the action descriptions in the system prompt.
+    pub fn register_action_with_condition<F>(
+        &mut self,
+        name: String,
+        description: String,
+        domains: Option<Vec<String>>,
+        condition: F,
+    ) where
+        F: Fn(&BrowserProfile) -> bool + Send + Sync + 'static,
+    {
+        self.register_action(name.clone(), description, domains);
+        if let Some(action) = self.registry.actions.get_mut(&name) {
+            action.condition = Some(Arc::new(condition));
+        }
+    }
+
     /// Register a custom action with a handler
     pub fn register_custom_action<H: ActionHandler + 'static>(
         &mut self,
@@ -57,10 +79,16 @@ impl Registry {
             description,
             domains,
             handler: Some(Arc::new(handler)),
+            condition: None,
         };
         self.registry.actions.insert(name, action);
     }
 
+    /// Check if an action was excluded by configuration
+    pub fn is_excluded(&self, name: &str) -> bool {
+        self.exclude_actions.iter().any(|a| a == name)
+    }
+
     /// Check if an action has a custom handler
     pub fn has_custom_handler(&self, name: &str) -> bool {
         self.registry
@@ -70,6 +98,14 @@ impl Registry {
             .is_some()
     }
 
+    /// Check if an action is registered but unavailable with `profile`
+    pub fn is_unavailable(&self, name: &str, profile: Option<&BrowserProfile>) -> bool {
+        self.registry
+            .actions
+            .get(name)
+            .is_some_and(|a| !a.is_available(profile))
+    }
+
     /// Get the custom handler for an action
     pub fn get_handler(&self, name: &str) -> Option<Arc<dyn ActionHandler>> {
         self.registry
@@ -78,3 +114,68 @@ impl Registry {
             .and_then(|a| a.handler.clone())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> Registry {
+        let mut registry = Registry::new(vec![]);
+        registry.register_action("navigate".to_string(), "Navigate to a URL".to_string(), None);
+        registry.register_action_with_condition(
+            "drag_window".to_string(),
+            "Drag the browser window".to_string(),
+            None,
+            |profile| profile.headless == Some(false),
+        );
+        registry
+    }
+
+    #[test]
+    fn test_conditions_follow_the_profile() {
+        let registry = registry();
+        let headless = BrowserProfile::new().with_headless(true);
+        let visible = BrowserProfile::new().with_headless(false);
+
+        assert!(registry.is_unavailable("drag_window", Some(&headless)));
+        assert!(!registry.is_unavailable("drag_window", Some(&visible)));
+        assert!(!registry.is_unavailable("navigate", Some(&headless)));
+        // Without a profile the condition cannot be checked
+        assert!(!registry.is_unavailable("drag_window", None));
+        assert!(!registry.is_unavailable("unknown", Some(&headless)));
+    }
+
+    #[test]
+    fn test_unavailable_actions_are_not_described() {
+        let registry = registry();
+        let headless = BrowserProfile::new().with_headless(true);
+        let visible = BrowserProfile::new().with_headless(false);
+
+        assert_eq!(
+            registry
+                .registry
+                .available_for(Some(&headless))
+                .get_prompt_description(None),
+            "navigate: Navigate to a URL"
+        );
+        assert_eq!(
+            registry
+                .registry
+                .available_for(Some(&visible))
+                .get_prompt_description(None),
+            "drag_window: Drag the browser window\nnavigate: Navigate to a URL"
+        );
+    }
+
+    #[test]
+    fn test_excluded_conditional_action_is_not_registered() {
+        let mut registry = Registry::new(vec!["drag_window".to_string()]);
+        registry.register_action_with_condition(
+            "drag_window".to_string(),
+            "Drag the browser window".to_string(),
+            None,
+            |_| true,
+        );
+        assert!(registry.registry.actions.is_empty());
+    }
+}
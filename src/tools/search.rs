@@ -0,0 +1,376 @@
+//! Web search with fallback between engines
+//!
+//! Search engines frequently answer headless browsers with a consent wall or a
+//! CAPTCHA. After each navigation the loaded page is checked for such obstacles,
+//! and the next engine in the chain is tried instead.
+
+use crate::actor::page::EVALUATE_TIMEOUT_MS;
+use crate::error::{BrowsingError, Result};
+use crate::traits::BrowserClient;
+use async_trait::async_trait;
+
+/// Default order in which engines are tried
+pub const DEFAULT_SEARCH_ENGINES: [SearchEngine; 3] = [
+    SearchEngine::DuckDuckGo,
+    SearchEngine::Bing,
+    SearchEngine::Brave,
+];
+
+/// Maximum number of characters of page text inspected for obstacles
+const OBSTACLE_TEXT_LIMIT: usize = 5000;
+
+/// Phrases in the page text that identify a block or consent page
+const OBSTACLE_PHRASES: &[(&str, &str)] = &[
+    (
+        "our systems have detected unusual traffic",
+        "unusual traffic check",
+    ),
+    ("before you continue to google", "consent wall"),
+    ("bots use duckduckgo too", "bot challenge"),
+    ("complete the following challenge", "bot challenge"),
+    ("solve the challenge below", "bot challenge"),
+    ("verify you are human", "bot challenge"),
+    ("are you a robot", "bot challenge"),
+];
+
+/// URL fragments that identify a block or consent page
+const OBSTACLE_URLS: &[(&str, &str)] = &[
+    ("google.com/sorry", "unusual traffic check"),
+    ("consent.google.", "consent wall"),
+    ("consent.yahoo.", "consent wall"),
+];
+
+/// Supported search engines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchEngine {
+    /// DuckDuckGo
+    DuckDuckGo,
+    /// Microsoft Bing
+    Bing,
+    /// Brave Search
+    Brave,
+    /// Google
+    Google,
+}
+
+impl SearchEngine {
+    /// Parse an engine from its name (case-insensitive)
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "duckduckgo" => Ok(SearchEngine::DuckDuckGo),
+            "bing" => Ok(SearchEngine::Bing),
+            "brave" => Ok(SearchEngine::Brave),
+            "google" => Ok(SearchEngine::Google),
+            _ => Err(BrowsingError::Tool(format!(
+                "Unsupported search engine: {}. Options: duckduckgo, bing, brave, google",
+                name
+            ))),
+        }
+    }
+
+    /// Lowercase name of the engine
+    pub fn name(&self) -> &'static str {
+        match self {
+            SearchEngine::DuckDuckGo => "duckduckgo",
+            SearchEngine::Bing => "bing",
+            SearchEngine::Brave => "brave",
+            SearchEngine::Google => "google",
+        }
+    }
+
+    /// Build the results URL for a query
+    ///
+    /// `site` is added as a `site:` operator, which every engine understands.
+    /// `num_results` is passed on to engines that accept a result count
+    /// (Google and Bing) and ignored by the others.
+    pub fn search_url(&self, query: &str, site: Option<&str>, num_results: Option<u32>) -> String {
+        let query = match site {
+            Some(site) => format!("{query} site:{site}"),
+            None => query.to_string(),
+        };
+        let q = urlencoding::encode(&query);
+        match (self, num_results) {
+            (SearchEngine::DuckDuckGo, _) => format!("https://duckduckgo.com/?q={q}"),
+            (SearchEngine::Bing, None) => format!("https://www.bing.com/search?q={q}"),
+            (SearchEngine::Bing, Some(n)) => format!("https://www.bing.com/search?q={q}&count={n}"),
+            (SearchEngine::Brave, _) => format!("https://search.brave.com/search?q={q}"),
+            (SearchEngine::Google, None) => format!("https://www.google.com/search?q={q}&udm=14"),
+            (SearchEngine::Google, Some(n)) => {
+                format!("https://www.google.com/search?q={q}&udm=14&num={n}")
+            }
+        }
+    }
+}
+
+/// Detect a consent wall, CAPTCHA, or bot check from a page's URL and content
+///
+/// `content` may be either page text or raw HTML. Returns a short description
+/// of the obstacle, if any.
+pub fn detect_search_obstacle(url: &str, content: &str) -> Option<&'static str> {
+    let url = url.to_lowercase();
+    if let Some((_, reason)) = OBSTACLE_URLS.iter().find(|(p, _)| url.contains(p)) {
+        return Some(reason);
+    }
+
+    let content = content.to_lowercase();
+    OBSTACLE_PHRASES
+        .iter()
+        .find(|(p, _)| content.contains(p))
+        .map(|(_, reason)| *reason)
+}
+
+/// Loads a search results page and reports where it ended up
+#[async_trait]
+pub(crate) trait SearchPageLoader: Send {
+    /// Navigate to `url` and return the final URL and page text
+    async fn load(&mut self, url: &str) -> Result<(String, String)>;
+}
+
+#[async_trait]
+impl SearchPageLoader for &mut dyn BrowserClient {
+    async fn load(&mut self, url: &str) -> Result<(String, String)> {
+        self.navigate(url).await?;
+
+        let final_url = self
+            .get_current_url()
+            .await
+            .unwrap_or_else(|_| url.to_string());
+        let expression = format!(
+            "(document.title + '\\n' + (document.body ? document.body.innerText : '')).slice(0, {})",
+            OBSTACLE_TEXT_LIMIT
+        );
+        // Pages we cannot inspect are assumed to be usable
+        let text = match self.get_page() {
+            Ok(page) => page.evaluate_with_timeout(&expression, EVALUATE_TIMEOUT_MS).await.unwrap_or_default(),
+            Err(_) => String::new(),
+        };
+        Ok((final_url, text))
+    }
+}
+
+/// Result of a search that may have fallen back to other engines
+#[derive(Debug, Clone)]
+pub struct SearchOutcome {
+    /// Engine whose results page was loaded
+    pub engine: SearchEngine,
+    /// Final URL of the results page
+    pub url: String,
+    /// Engines that were skipped, with the obstacle each one served
+    pub blocked: Vec<(SearchEngine, &'static str)>,
+}
+
+impl SearchOutcome {
+    /// Memory line describing the search
+    pub fn memory(&self, query: &str) -> String {
+        format!("Searched {} for '{}'", self.engine.name(), query)
+    }
+
+    /// The engines skipped before this one, `None` if there were none
+    pub fn debug_info(&self) -> Option<String> {
+        if self.blocked.is_empty() {
+            return None;
+        }
+        let skipped: Vec<String> = self
+            .blocked
+            .iter()
+            .map(|(engine, reason)| format!("{} ({})", engine.name(), reason))
+            .collect();
+        Some(format!("skipped blocked {}", skipped.join(", ")))
+    }
+}
+
+/// Try each engine in turn until one serves a results page without an obstacle
+pub(crate) async fn search_with_fallback<L: SearchPageLoader>(
+    loader: &mut L,
+    engines: &[SearchEngine],
+    query: &str,
+    site: Option<&str>,
+    num_results: Option<u32>,
+) -> Result<SearchOutcome> {
+    let mut blocked = Vec::new();
+    for &engine in engines {
+        let (url, content) = loader
+            .load(&engine.search_url(query, site, num_results))
+            .await?;
+        match detect_search_obstacle(&url, &content) {
+            Some(reason) => {
+                tracing::warn!("{} blocked ({}), trying next engine", engine.name(), reason);
+                blocked.push((engine, reason));
+            }
+            None => {
+                return Ok(SearchOutcome {
+                    engine,
+                    url,
+                    blocked,
+                });
+            }
+        }
+    }
+
+    Err(BrowsingError::Tool(format!(
+        "All search engines blocked the search for '{}': {}",
+        query,
+        blocked
+            .iter()
+            .map(|(engine, reason)| format!("{} ({})", engine.name(), reason))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GOOGLE_CONSENT: &str = include_str!("../../tests/fixtures/search/google_consent.html");
+    const GOOGLE_SORRY: &str = include_str!("../../tests/fixtures/search/google_sorry.html");
+    const DUCKDUCKGO_ANOMALY: &str =
+        include_str!("../../tests/fixtures/search/duckduckgo_anomaly.html");
+    const BING_CHALLENGE: &str = include_str!("../../tests/fixtures/search/bing_challenge.html");
+    const RESULTS: &str = include_str!("../../tests/fixtures/search/results.html");
+
+    /// Serves saved pages by host instead of hitting the network
+    struct FixtureLoader {
+        pages: Vec<(&'static str, &'static str)>,
+        visited: Vec<String>,
+    }
+
+    #[async_trait]
+    impl SearchPageLoader for FixtureLoader {
+        async fn load(&mut self, url: &str) -> Result<(String, String)> {
+            self.visited.push(url.to_string());
+            let content = self
+                .pages
+                .iter()
+                .find(|(host, _)| url.contains(host))
+                .map(|(_, page)| *page)
+                .unwrap_or(RESULTS);
+            Ok((url.to_string(), content.to_string()))
+        }
+    }
+
+    #[test]
+    fn test_detects_fixtures() {
+        assert_eq!(
+            detect_search_obstacle("https://www.google.com/search?q=rust", GOOGLE_CONSENT),
+            Some("consent wall")
+        );
+        assert_eq!(
+            detect_search_obstacle("https://www.google.com/search?q=rust", GOOGLE_SORRY),
+            Some("unusual traffic check")
+        );
+        assert_eq!(
+            detect_search_obstacle("https://duckduckgo.com/?q=rust", DUCKDUCKGO_ANOMALY),
+            Some("bot challenge")
+        );
+        assert_eq!(
+            detect_search_obstacle("https://www.bing.com/search?q=rust", BING_CHALLENGE),
+            Some("bot challenge")
+        );
+        assert_eq!(
+            detect_search_obstacle("https://www.google.com/sorry/index?continue=x", ""),
+            Some("unusual traffic check")
+        );
+    }
+
+    #[test]
+    fn test_results_mentioning_captcha_are_not_blocked() {
+        assert_eq!(
+            detect_search_obstacle("https://duckduckgo.com/?q=rust+captcha+solver", RESULTS),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_next_engine() {
+        let mut loader = FixtureLoader {
+            pages: vec![("duckduckgo.com", DUCKDUCKGO_ANOMALY)],
+            visited: vec![],
+        };
+        let outcome =
+            search_with_fallback(&mut loader, &DEFAULT_SEARCH_ENGINES, "rust", None, None)
+                .await
+                .unwrap();
+
+        assert_eq!(outcome.engine, SearchEngine::Bing);
+        assert_eq!(loader.visited.len(), 2);
+        assert_eq!(outcome.memory("rust"), "Searched bing for 'rust'");
+        assert_eq!(
+            outcome.debug_info().as_deref(),
+            Some("skipped blocked duckduckgo (bot challenge)")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_custom_order() {
+        let mut loader = FixtureLoader {
+            pages: vec![("google.com", GOOGLE_CONSENT), ("bing.com", BING_CHALLENGE)],
+            visited: vec![],
+        };
+        let engines = [
+            SearchEngine::Google,
+            SearchEngine::Bing,
+            SearchEngine::Brave,
+        ];
+        let outcome = search_with_fallback(&mut loader, &engines, "rust", None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(outcome.engine, SearchEngine::Brave);
+        assert_eq!(
+            outcome.blocked,
+            vec![
+                (SearchEngine::Google, "consent wall"),
+                (SearchEngine::Bing, "bot challenge")
+            ]
+        );
+        assert!(outcome.url.starts_with("https://search.brave.com/"));
+    }
+
+    #[tokio::test]
+    async fn test_all_engines_blocked() {
+        let mut loader = FixtureLoader {
+            pages: vec![
+                ("duckduckgo.com", DUCKDUCKGO_ANOMALY),
+                ("bing.com", BING_CHALLENGE),
+                ("brave.com", GOOGLE_SORRY),
+            ],
+            visited: vec![],
+        };
+        let err = search_with_fallback(&mut loader, &DEFAULT_SEARCH_ENGINES, "rust", None, None)
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("All search engines blocked"));
+        assert_eq!(loader.visited.len(), 3);
+    }
+
+    #[test]
+    fn test_search_url_site_and_num_results() {
+        assert_eq!(
+            SearchEngine::Google.search_url("tokio", Some("docs.rs"), Some(20)),
+            "https://www.google.com/search?q=tokio%20site%3Adocs.rs&udm=14&num=20"
+        );
+        assert_eq!(
+            SearchEngine::Bing.search_url("tokio", None, Some(5)),
+            "https://www.bing.com/search?q=tokio&count=5"
+        );
+        assert_eq!(
+            SearchEngine::DuckDuckGo.search_url("tokio", Some("github.com"), Some(5)),
+            "https://duckduckgo.com/?q=tokio%20site%3Agithub.com"
+        );
+        assert_eq!(
+            SearchEngine::Brave.search_url("tokio", None, None),
+            "https://search.brave.com/search?q=tokio"
+        );
+    }
+
+    #[test]
+    fn test_engine_from_name() {
+        assert_eq!(
+            SearchEngine::from_name("Brave").unwrap(),
+            SearchEngine::Brave
+        );
+        assert!(SearchEngine::from_name("altavista").is_err());
+    }
+}
@@ -1,11 +1,21 @@
 //! Tools service for action registry
 
+use crate::actor::ResponseMarker;
+use crate::agent::sanitize::InjectionDetector;
 use crate::agent::views::ActionResult;
+use crate::browser::NewWindowHandling;
+use crate::config::ToolsConfig;
 use crate::error::{BrowsingError, Result};
 use crate::traits::BrowserClient;
-use crate::tools::handlers::{AdvancedHandler, ContentHandler, InteractionHandler, NavigationHandler, TabsHandler, Handler};
+use crate::tools::dry_run;
+use crate::tools::evaluate::EvaluatePolicy;
+use crate::tools::handlers::{AdvancedHandler, AssertionHandler, ContentHandler, ImagesHandler, InteractionHandler, NavigationHandler, SnapshotHandler, TabsHandler, Handler};
 use crate::tools::registry::Registry;
+use crate::tools::search::{DEFAULT_SEARCH_ENGINES, SearchEngine};
+use crate::tools::undo::{self, UndoStack};
 use crate::tools::views::{ActionContext, ActionModel, ActionParams};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 
 /// Tools registry for agent actions
 pub struct Tools {
@@ -13,6 +23,35 @@ pub struct Tools {
     pub registry: Registry,
     /// Whether to display files in done text
     pub display_files_in_done_text: bool,
+    /// Search engines tried in order when one blocks automation
+    pub search_engines: Vec<SearchEngine>,
+    /// What the click actions do with windows they open
+    pub new_window_handling: NewWindowHandling,
+    /// Whether clicks are dispatched as pointer event sequences
+    pub pointer_events_mode: bool,
+    /// Whether a click that changed nothing is retried with a JS click
+    pub click_fallback: bool,
+    /// Whether a button or link a click changed nothing on is activated with
+    /// Enter instead of a JS click
+    pub keyboard_fallback: bool,
+    /// Directory for files saved by actions, e.g. downloaded images; defaults
+    /// to `browsing-artifacts` in the system temp directory
+    pub artifacts_dir: Option<PathBuf>,
+    /// What the evaluate action may run
+    pub evaluate_policy: EvaluatePolicy,
+    /// Whether actions may write files, e.g. images downloaded by `extract_images`
+    pub allow_file_writes: bool,
+    /// Whether actions that would change the page are described instead of executed
+    pub dry_run: bool,
+    /// Actions executed even in a dry run
+    pub force_execute: Vec<String>,
+    /// Scans page text given to the model by the extract actions for
+    /// injected instructions; `None` skips the scan
+    pub injection_detector: Option<InjectionDetector>,
+    /// Recent reversible changes, for `revert_last`
+    undo_stack: Mutex<UndoStack>,
+    /// Where `wait_for_response` starts looking for responses, if marked
+    response_marker: Arc<tokio::sync::Mutex<Option<ResponseMarker>>>,
 }
 
 impl Tools {
@@ -26,32 +65,151 @@ impl Tools {
         Self {
             registry,
             display_files_in_done_text: true,
+            search_engines: DEFAULT_SEARCH_ENGINES.to_vec(),
+            new_window_handling: NewWindowHandling::Ignore,
+            pointer_events_mode: false,
+            click_fallback: true,
+            keyboard_fallback: false,
+            artifacts_dir: None,
+            evaluate_policy: EvaluatePolicy::Full,
+            allow_file_writes: true,
+            dry_run: false,
+            force_execute: Vec::new(),
+            injection_detector: Some(InjectionDetector::default()),
+            undo_stack: Mutex::new(UndoStack::default()),
+            response_marker: Arc::default(),
         }
     }
 
+    /// Creates a tools registry limited to what `config` allows
+    ///
+    /// Excluded actions are not registered, so they are also left out of the
+    /// action descriptions given to the model.
+    pub fn from_config(config: &ToolsConfig) -> Self {
+        Self::new(config.excluded_actions())
+            .with_evaluate_policy(config.evaluate_policy())
+            .with_file_writes(config.allows_file_writes())
+            .with_click_fallback(config.click_fallback.unwrap_or(true))
+            .with_keyboard_fallback(config.keyboard_fallback)
+            .with_dry_run(config.dry_run, config.force_execute.clone())
+    }
+
+    /// Set the search engine fallback order
+    pub fn with_search_engines(mut self, search_engines: Vec<SearchEngine>) -> Self {
+        self.search_engines = search_engines;
+        self
+    }
+
+    /// Set what the click actions do with windows they open
+    pub fn with_new_window_handling(mut self, handling: NewWindowHandling) -> Self {
+        self.new_window_handling = handling;
+        self
+    }
+
+    /// Set whether clicks are dispatched as pointer event sequences
+    pub fn with_pointer_events_mode(mut self, enabled: bool) -> Self {
+        self.pointer_events_mode = enabled;
+        self
+    }
+
+    /// Set the detector the extract actions scan page text with, or `None` to skip the scan
+    pub fn with_injection_detector(mut self, detector: Option<InjectionDetector>) -> Self {
+        self.injection_detector = detector;
+        self
+    }
+
+    /// Set whether a click that changed nothing is retried with a JS click
+    pub fn with_click_fallback(mut self, enabled: bool) -> Self {
+        self.click_fallback = enabled;
+        self
+    }
+
+    /// Set whether a button or link a click changed nothing on is activated
+    /// from the keyboard instead of with a JS click
+    pub fn with_keyboard_fallback(mut self, enabled: bool) -> Self {
+        self.keyboard_fallback = enabled;
+        self
+    }
+
+    /// Set the directory for files saved by actions
+    pub fn with_artifacts_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.artifacts_dir = Some(dir.into());
+        self
+    }
+
+    /// Set what the evaluate action may run
+    pub fn with_evaluate_policy(mut self, policy: EvaluatePolicy) -> Self {
+        self.evaluate_policy = policy;
+        self
+    }
+
+    /// Set whether actions may write files
+    pub fn with_file_writes(mut self, allowed: bool) -> Self {
+        self.allow_file_writes = allowed;
+        self
+    }
+
+    /// Describe actions that would change the page instead of executing them,
+    /// apart from those in `force_execute`; see [`crate::tools::dry_run`]
+    pub fn with_dry_run(mut self, enabled: bool, force_execute: Vec<String>) -> Self {
+        self.dry_run = enabled;
+        self.force_execute = force_execute;
+        self
+    }
+
+    /// Whether a dry run skips `action`
+    pub fn skips_in_dry_run(&self, action: &ActionModel) -> bool {
+        let forced = self.force_execute.contains(&action.action_type);
+        self.dry_run && dry_run::is_mutating(action) && !forced
+    }
+
     fn register_default_actions(registry: &mut Registry) {
         // Register basic navigation actions
         registry.register_action(
             "search".to_string(),
-            "Search the web using a search engine".to_string(),
+            "Search the web for query; optional engine (duckduckgo, bing, brave, google), site, and num_results. Falls back to another engine if blocked".to_string(),
             None,
         );
 
         registry.register_action(
             "navigate".to_string(),
-            "Navigate to a URL".to_string(),
+            "Navigate to a URL; optional referrer, headers (sent with this request only), wait_until (none, domcontentloaded, load), bypass_cache to reload the current URL without the HTTP cache, and color_scheme (dark, light, none), vision_deficiency (e.g. deuteranopia, blurred_vision) and save_data to emulate for this tab from now on".to_string(),
+            None,
+        );
+
+        registry.register_action(
+            "save_checkpoint".to_string(),
+            "Save URL, cookies, storage and scroll position of the page; returns a checkpoint_id to restore before trying another branch".to_string(),
+            None,
+        );
+
+        registry.register_action(
+            "restore_checkpoint".to_string(),
+            "Return to a saved checkpoint by checkpoint_id; server-side changes (orders, submitted forms) are not undone".to_string(),
             None,
         );
 
         registry.register_action(
             "click".to_string(),
-            "Click an element by index".to_string(),
+            "Click an element by index; set wait_for_response to wait until a loading button (aria-busy or disabled) is ready again".to_string(),
+            None,
+        );
+
+        registry.register_action(
+            "click_descendant".to_string(),
+            "Click the element inside container index matching role, text, and/or css; reports candidates if several match".to_string(),
+            None,
+        );
+
+        registry.register_action(
+            "activate".to_string(),
+            "Focus element index and press Enter or Space as its role calls for, for widgets that ignore clicks; reports which key changed the page".to_string(),
             None,
         );
 
         registry.register_action(
             "input".to_string(),
-            "Input text into a field".to_string(),
+            "Input text into a field; non-ASCII text such as Japanese or emoji is typed through IME composition (ime: false to set the value directly)".to_string(),
             None,
         );
 
@@ -61,6 +219,18 @@ impl Tools {
             None,
         );
 
+        registry.register_action(
+            "form_autofill".to_string(),
+            "Fill several form fields at once; values maps field name, label, or placeholder to the value, optional form_index".to_string(),
+            None,
+        );
+
+        registry.register_action(
+            "submit_form".to_string(),
+            "Submit the form containing element index (any field or button marked form=#N); reports whether the page navigated or sent an XHR".to_string(),
+            None,
+        );
+
         registry.register_action(
             "switch".to_string(),
             "Switch to another open tab by tab_id".to_string(),
@@ -73,9 +243,21 @@ impl Tools {
             None,
         );
 
+        registry.register_action(
+            "keep_tab".to_string(),
+            "Pin a tab by tab_id so it is never closed automatically".to_string(),
+            None,
+        );
+
         registry.register_action(
             "scroll".to_string(),
-            "Scroll the page up or down by pages".to_string(),
+            "Scroll the page up or down by pages, or the element marked [scrollable N] with container_index=N; with index, scroll that element into view instead".to_string(),
+            None,
+        );
+
+        registry.register_action(
+            "scroll_to_element".to_string(),
+            "Scroll the element with the given index into view, inside scrollable containers and iframes too; does nothing if it is already visible".to_string(),
             None,
         );
 
@@ -85,6 +267,36 @@ impl Tools {
             None,
         );
 
+        registry.register_action(
+            "wait_for_response".to_string(),
+            "Wait until the page receives a response whose URL matches url (a substring, a glob with *, or /regex/), e.g. the XHR a click started; put it in the same step as the click so a fast response is not missed. Reports status and MIME type. Optional timeout in seconds (default 10), include_body to read the body, max_body_bytes".to_string(),
+            None,
+        );
+
+        registry.register_action(
+            "get_captured_responses".to_string(),
+            "Read the JSON API responses captured since the last read, when response capture is on; often more reliable than extracting the same data from the page".to_string(),
+            None,
+        );
+
+        registry.register_action(
+            "assert_text_present".to_string(),
+            "Check that text is on the page; fails with the expected and actual text if not. Optional ignore_case".to_string(),
+            None,
+        );
+
+        registry.register_action(
+            "assert_element_exists".to_string(),
+            "Check that an element exists, by CSS selector or by index; fails with an explanation if not".to_string(),
+            None,
+        );
+
+        registry.register_action(
+            "assert_url_matches".to_string(),
+            "Check that the current URL matches pattern (a substring, a glob with *, or /regex/); fails with the actual URL if not".to_string(),
+            None,
+        );
+
         registry.register_action(
             "send_keys".to_string(),
             "Send keyboard keys (Enter, Escape, Tab, etc.)".to_string(),
@@ -115,17 +327,86 @@ impl Tools {
             None,
         );
 
+        registry.register_action(
+            "revert_last".to_string(),
+            "Undo the most recent reversible change after a mistake: text typed with input, a checkbox toggled by click, an option chosen with select_dropdown, or a page scroll. Reports what was restored. Navigation and other clicks are not reversible, and nothing before them can be reverted".to_string(),
+            None,
+        );
+
         registry.register_action(
             "upload_file".to_string(),
             "Upload files to file inputs".to_string(),
             None,
         );
 
+        registry.register_action(
+            "remember".to_string(),
+            "Store a fact under key (params: key, value) to use in later steps; with site: true it is kept for the current site in later runs, if site memory is enabled".to_string(),
+            None,
+        );
+
+        registry.register_action(
+            "recall".to_string(),
+            "Look up remembered facts by key, or search them by keywords (params: key or query); omit both to list stored keys".to_string(),
+            None,
+        );
+
+        registry.register_action(
+            "ask_human".to_string(),
+            "Ask the human running you a question and wait for the reply (params: question; screenshot: true to show them the page). Only for what you cannot find out yourself, e.g. a 2FA code or a choice the task leaves open".to_string(),
+            None,
+        );
+
+        registry.register_action(
+            "extract_images".to_string(),
+            "List images with rendered and natural size, loading attribute and caption; 1x1 tracking pixels and data: URIs are skipped. Optional min_width, min_height, include_data_uris, and download (saves the largest `limit` images, default 5)".to_string(),
+            None,
+        );
+
+        registry.register_action(
+            "save_page".to_string(),
+            "Save the page as it looks now, with styles and images, to an MHTML file for the record. Optional filename".to_string(),
+            None,
+        );
+
         registry.register_action(
             "extract".to_string(),
-            "LLM extracts structured data from page markdown. Use when: on right page, know what to extract, haven't called before on same page+query.".to_string(),
+            "LLM extracts structured data from page markdown. Use when: on right page, know what to extract, haven't called before on same page+query. Set trigger_lazy_load to scroll through the page first so lazy-loaded images and sections are included. To get several fields at once, pass queries (a list, or an object of named queries) instead of query: they are answered in one call as a JSON object keyed by query name, null where the page has no answer".to_string(),
             None,
         );
+
+        registry.register_action(
+            "open_and_extract".to_string(),
+            "Open several links in background tabs, extract from each and close them, all in one step, e.g. to compare the top search results. Give urls and/or indices of links, and query (or queries, as for extract); optional concurrency (tabs open at once, default 3, max 8). Returns one result per page in order; a page that fails gets an error and the rest still run. Stays on the current tab".to_string(),
+            None,
+        );
+    }
+
+    /// Directory for files saved by actions
+    fn artifacts_dir(&self) -> PathBuf {
+        self.artifacts_dir
+            .clone()
+            .unwrap_or_else(|| std::env::temp_dir().join("browsing-artifacts"))
+    }
+
+    /// Keep the current tab's responses from now on for `wait_for_response`
+    ///
+    /// Call it before the actions that send the request, so a response that
+    /// arrives before the wait starts is still seen. Without a marker the
+    /// wait sees only responses received after it starts.
+    pub async fn mark_responses(&self, browser_session: &dyn BrowserClient) {
+        let marker = match browser_session.get_page() {
+            Ok(page) => page.mark_responses().await,
+            Err(e) => Err(e),
+        };
+        *self.response_marker.lock().await = marker
+            .inspect_err(|e| tracing::debug!("Not marking responses: {}", e))
+            .ok();
+    }
+
+    /// Drop the marker set by [`Tools::mark_responses`]
+    pub async fn clear_response_marker(&self) {
+        *self.response_marker.lock().await = None;
     }
 
     /// Executes an action
@@ -153,6 +434,28 @@ impl Tools {
     ) -> Result<ActionResult> {
         let action_type = action.action_type.as_str();
 
+        // Built-ins are dispatched below without consulting the registry, so
+        // refuse excluded and unregistered actions up front
+        if self.registry.is_excluded(action_type) {
+            return Err(BrowsingError::Tool(format!(
+                "Action disabled by configuration: {action_type}"
+            )));
+        }
+        if !self.registry.registry.actions.contains_key(action_type) {
+            return Err(BrowsingError::Tool(format!(
+                "Unknown action type: {action_type}"
+            )));
+        }
+        if self.registry.is_unavailable(action_type, browser_session.profile()) {
+            return Err(BrowsingError::Tool(format!(
+                "Action not available in current configuration: {action_type}"
+            )));
+        }
+
+        if self.skips_in_dry_run(&action) {
+            return Ok(dry_run::describe(&action, selector_map));
+        }
+
         // Check if this is a custom action with a handler
         if let Some(handler) = self.registry.get_handler(action_type) {
             let params = ActionParams::new(&action.params).with_action_type(action.action_type.clone());
@@ -163,6 +466,11 @@ impl Tools {
             return handler.execute(&params, &mut context).await;
         }
 
+        if action_type == "revert_last" {
+            return self.revert_last(browser_session).await;
+        }
+        let undo_entry = undo::capture(&action, browser_session, selector_map).await;
+
         // Use new handler-based dispatch for built-in actions
         let params = ActionParams::new(&action.params).with_action_type(action.action_type.clone());
         let mut context = ActionContext {
@@ -170,32 +478,109 @@ impl Tools {
             selector_map,
         };
 
-        match action_type {
+        let result = match action_type {
             // Navigation actions
-            "search" | "navigate" => {
-                NavigationHandler.handle(&params, &mut context).await
+            "search" | "navigate" | "save_checkpoint" | "restore_checkpoint" => {
+                NavigationHandler::new(self.search_engines.clone())
+                    .handle(&params, &mut context)
+                    .await
             }
             // Interaction actions
-            "click" | "input" | "send_keys" => {
-                InteractionHandler.handle(&params, &mut context).await
+            "click" | "click_descendant" | "activate" | "input" | "send_keys"
+            | "form_autofill" | "submit_form" => {
+                InteractionHandler::new(self.new_window_handling)
+                    .with_pointer_events(self.pointer_events_mode)
+                    .with_click_fallback(self.click_fallback)
+                    .with_keyboard_fallback(self.keyboard_fallback)
+                    .handle(&params, &mut context)
+                    .await
             }
             // Tab actions
-            "switch" | "close" => {
+            "switch" | "close" | "keep_tab" => {
                 TabsHandler.handle(&params, &mut context).await
             }
             // Content actions
-            "scroll" | "find_text" | "dropdown_options" | "select_dropdown" => {
+            "scroll" | "scroll_to_element" | "find_text" | "dropdown_options"
+            | "select_dropdown" => {
                 ContentHandler.handle(&params, &mut context).await
             }
+            // Image actions
+            "extract_images" => {
+                ImagesHandler::new(self.artifacts_dir())
+                    .with_file_writes(self.allow_file_writes)
+                    .handle(&params, &mut context)
+                    .await
+            }
+            // Snapshot actions
+            "save_page" => {
+                SnapshotHandler::new(self.artifacts_dir())
+                    .with_file_writes(self.allow_file_writes)
+                    .handle(&params, &mut context)
+                    .await
+            }
             // Advanced actions
-            "done" | "evaluate" | "upload_file" | "wait" => {
-                AdvancedHandler.handle(&params, &mut context).await
+            "done" | "evaluate" | "upload_file" | "wait" | "wait_for_response"
+            | "get_captured_responses" => {
+                AdvancedHandler::new(self.evaluate_policy)
+                    .with_response_marker(self.response_marker.clone())
+                    .handle(&params, &mut context)
+                    .await
+            }
+            // Assertion actions
+            "assert_text_present" | "assert_element_exists" | "assert_url_matches" => {
+                AssertionHandler.handle(&params, &mut context).await
             }
             // Extract action (requires LLM)
-            "extract" => crate::tools::handlers::extract::handle_extract(action, browser_session, llm).await,
+            "extract" => {
+                crate::tools::handlers::extract::handle_extract(
+                    action,
+                    browser_session,
+                    llm,
+                    self.injection_detector.as_ref(),
+                )
+                .await
+            }
+            "open_and_extract" => {
+                crate::tools::handlers::extract::handle_open_and_extract(
+                    action,
+                    browser_session,
+                    selector_map,
+                    llm,
+                    self.injection_detector.as_ref(),
+                )
+                .await
+            }
+            // Memory actions and ask_human (served by the agent)
+            "remember" | "recall" | "set_memory" | "get_memory" | "ask_human" => Err(BrowsingError::Tool(format!(
+                "{action_type} is only available to an agent"
+            ))),
             _ => Err(BrowsingError::Tool(format!(
                 "Unknown action type: {action_type}"
             ))),
+        };
+
+        if let Some(entry) = undo_entry
+            && result.as_ref().is_ok_and(|result| result.error.is_none())
+        {
+            let entry = undo::confirm(entry, browser_session).await;
+            self.undo_stack.lock().unwrap().push(entry);
+        }
+        result
+    }
+
+    /// Undo the most recent reversible change
+    async fn revert_last(&self, browser_session: &mut dyn BrowserClient) -> Result<ActionResult> {
+        let entry = self.undo_stack.lock().unwrap().pop()?;
+        match undo::revert(&entry, browser_session).await {
+            Ok(memory) => {
+                tracing::info!("↩️ {}", memory);
+                Ok(ActionResult::success_with_memory(memory))
+            }
+            Err(e) => {
+                // Left for another attempt
+                self.undo_stack.lock().unwrap().push(entry);
+                Err(e)
+            }
         }
     }
 
@@ -0,0 +1,347 @@
+//! Undo stack for the `revert_last` action
+//!
+//! [`Tools`](crate::tools::Tools) remembers what the last few reversible
+//! actions changed so a model that notices a mistake can put it back instead
+//! of improvising: text typed with `input`, a checkbox toggled by `click`, the
+//! option chosen with `select_dropdown`, and window scrolls, including those of
+//! `scroll_to_element`. The previous state is read just before the action runs.
+//!
+//! Navigation, other clicks, key presses, form submission and the like cannot
+//! be undone reliably. They are recorded as barriers: `revert_last` reports
+//! them as non-reversible, and nothing before them can be reverted either,
+//! since the page may no longer be the one the earlier change was made on.
+//! Scrolls of containers and actions that failed are not recorded.
+
+use crate::actor::ControlState;
+use crate::dom::views::DOMInteractedElement;
+use crate::error::{BrowsingError, Result};
+use crate::tools::views::{ActionModel, ActionParams};
+use crate::traits::BrowserClient;
+use std::collections::{HashMap, VecDeque};
+
+/// Number of changes remembered; older ones are forgotten
+pub const MAX_UNDO_ENTRIES: usize = 20;
+
+/// Built-in actions whose effect `revert_last` cannot undo
+pub const IRREVERSIBLE_ACTIONS: &[&str] = &[
+    "search",
+    "navigate",
+    "restore_checkpoint",
+    "click",
+    "click_descendant",
+    "activate",
+    "send_keys",
+    "form_autofill",
+    "submit_form",
+    "upload_file",
+    "evaluate",
+    "switch",
+    "close",
+];
+
+/// A change `revert_last` can undo, or a barrier it cannot get past
+#[derive(Debug, Clone, PartialEq)]
+pub enum UndoEntry {
+    /// A form control changed by `action`
+    Control {
+        /// Action that changed it
+        action: String,
+        /// Index of the element when the action ran
+        index: u32,
+        /// Backend node ID of the element
+        backend_node_id: u32,
+        /// Frame the element is in, if not the main frame
+        frame_id: Option<String>,
+        /// State before the action
+        previous: ControlState,
+    },
+    /// The window scrolled
+    Scroll {
+        /// Horizontal offset before the scroll
+        x: f64,
+        /// Vertical offset before the scroll
+        y: f64,
+    },
+    /// An action that cannot be undone
+    Irreversible {
+        /// What the action did, e.g. `navigation to https://example.com`
+        description: String,
+    },
+}
+
+/// Recent changes, newest last
+#[derive(Debug, Default)]
+pub struct UndoStack {
+    entries: VecDeque<UndoEntry>,
+}
+
+impl UndoStack {
+    /// Record a change; an irreversible one makes earlier changes unreachable,
+    /// so they are dropped
+    pub fn push(&mut self, entry: UndoEntry) {
+        if matches!(entry, UndoEntry::Irreversible { .. }) {
+            self.entries.clear();
+        }
+        self.entries.push_back(entry);
+        if self.entries.len() > MAX_UNDO_ENTRIES {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Take the newest change to revert it
+    ///
+    /// An irreversible action is left in place and returned as an error, so
+    /// asking again gives the same answer.
+    pub fn pop(&mut self) -> Result<UndoEntry> {
+        match self.entries.back() {
+            None => Err(BrowsingError::Tool(
+                "Nothing to revert: no reversible action has been taken".to_string(),
+            )),
+            Some(UndoEntry::Irreversible { description }) => Err(BrowsingError::Tool(format!(
+                "The last action, {description}, cannot be reverted: navigation, clicks and \
+                 similar actions are not reversible, and nothing before it can be reverted either"
+            ))),
+            Some(_) => Ok(self.entries.pop_back().expect("checked above")),
+        }
+    }
+
+    /// Newest change, if any
+    pub fn last(&self) -> Option<&UndoEntry> {
+        self.entries.back()
+    }
+
+    /// Number of changes remembered
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether nothing is remembered
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// What `action` is about to change, read before it runs
+///
+/// `None` for actions that change nothing `revert_last` cares about.
+pub async fn capture(
+    action: &ActionModel,
+    browser: &mut dyn BrowserClient,
+    selector_map: Option<&HashMap<u32, DOMInteractedElement>>,
+) -> Option<UndoEntry> {
+    let action_type = action.action_type.as_str();
+    let params = ActionParams::new(&action.params);
+    let index = params.get_required_u32("index").ok();
+    let target = index.and_then(|index| selector_map?.get(&index));
+
+    let control = match action_type {
+        "input" | "select_dropdown" => index,
+        "click" if target.is_some_and(is_checkbox) => index,
+        _ => None,
+    };
+    if let Some(index) = control {
+        let backend_node_id = params.backend_node_id_from_index(index, selector_map);
+        let frame_id = params.frame_id_from_index(index, selector_map);
+        let previous = match browser.get_page() {
+            Ok(page) => {
+                page.get_element(backend_node_id)
+                    .await
+                    .with_frame(frame_id.clone())
+                    .control_state()
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        return Some(match previous {
+            Ok(previous) if previous != ControlState::Other => UndoEntry::Control {
+                action: action_type.to_string(),
+                index,
+                backend_node_id,
+                frame_id,
+                previous,
+            },
+            _ => irreversible(action),
+        });
+    }
+
+    if action_type == "scroll" || action_type == "scroll_to_element" {
+        if action
+            .params
+            .get("container_index")
+            .is_some_and(|v| !v.is_null())
+        {
+            return None;
+        }
+        return Some(match scroll_offset(browser).await {
+            Some((x, y)) => UndoEntry::Scroll { x, y },
+            None => irreversible(action),
+        });
+    }
+
+    IRREVERSIBLE_ACTIONS
+        .contains(&action_type)
+        .then(|| irreversible(action))
+}
+
+/// The entry to record once the action captured as `entry` succeeded
+///
+/// A click that left the checkbox as it was changed something else, so it
+/// counts as an ordinary click.
+pub async fn confirm(entry: UndoEntry, browser: &mut dyn BrowserClient) -> UndoEntry {
+    let UndoEntry::Control {
+        ref action,
+        index,
+        backend_node_id,
+        ref frame_id,
+        ref previous,
+    } = entry
+    else {
+        return entry;
+    };
+    if action != "click" {
+        return entry;
+    }
+    let current = match browser.get_page() {
+        Ok(page) => {
+            page.get_element(backend_node_id)
+                .await
+                .with_frame(frame_id.clone())
+                .control_state()
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    match current {
+        Ok(current) if current != *previous => entry,
+        _ => UndoEntry::Irreversible {
+            description: format!("click on element {index}"),
+        },
+    }
+}
+
+/// Undo `entry`, returning a description of what was restored
+pub async fn revert(entry: &UndoEntry, browser: &mut dyn BrowserClient) -> Result<String> {
+    let page = browser.get_page()?;
+    match entry {
+        UndoEntry::Control {
+            action,
+            index,
+            backend_node_id,
+            frame_id,
+            previous,
+        } => {
+            let element = page
+                .get_element(*backend_node_id)
+                .await
+                .with_frame(frame_id.clone());
+            let current = element.control_state().await?;
+            element.restore_control_state(previous).await?;
+            Ok(format!(
+                "Reverted {action} on element {index}: restored {previous} (was {current})"
+            ))
+        }
+        UndoEntry::Scroll { x, y } => {
+            page.evaluate(&format!("window.scrollTo({x}, {y})")).await?;
+            Ok(format!("Reverted scroll: back to {x}, {y}"))
+        }
+        UndoEntry::Irreversible { description } => Err(BrowsingError::Tool(format!(
+            "The last action, {description}, cannot be reverted"
+        ))),
+    }
+}
+
+/// Window scroll offset, `None` if it cannot be read
+async fn scroll_offset(browser: &mut dyn BrowserClient) -> Option<(f64, f64)> {
+    let page = browser.get_page().ok()?;
+    let offset = page
+        .evaluate("[window.scrollX, window.scrollY].join(',')")
+        .await
+        .ok()?;
+    let (x, y) = offset.split_once(',')?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+fn irreversible(action: &ActionModel) -> UndoEntry {
+    let param = |key: &str| {
+        action
+            .params
+            .get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let index = action
+        .params
+        .get("index")
+        .and_then(|v| v.as_u64())
+        .map(|i| format!(" on element {i}"))
+        .unwrap_or_default();
+    let description = match action.action_type.as_str() {
+        "navigate" => format!("navigation to {}", param("url")),
+        "search" => format!("search for \"{}\"", param("query")),
+        "click" | "click_descendant" | "activate" => format!("click{index}"),
+        "send_keys" => format!("key press {}", param("keys")),
+        "submit_form" => format!("form submission{index}"),
+        "switch" => "tab switch".to_string(),
+        "close" => "closing a tab".to_string(),
+        other => format!("{other}{index}"),
+    };
+    UndoEntry::Irreversible { description }
+}
+
+fn is_checkbox(element: &DOMInteractedElement) -> bool {
+    element.tag.eq_ignore_ascii_case("input")
+        && element
+            .attributes
+            .get("type")
+            .is_some_and(|kind| kind.eq_ignore_ascii_case("checkbox"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn irreversible(description: &str) -> UndoEntry {
+        UndoEntry::Irreversible {
+            description: description.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_irreversible_entry_blocks_earlier_changes() {
+        let mut stack = UndoStack::default();
+        stack.push(UndoEntry::Scroll { x: 0.0, y: 0.0 });
+        stack.push(irreversible("click on element 4"));
+        assert_eq!(stack.len(), 1);
+
+        let error = stack.pop().unwrap_err().to_string();
+        assert!(
+            error.contains("click on element 4, cannot be reverted"),
+            "{error}"
+        );
+        // Still there for the next attempt
+        assert!(stack.pop().is_err());
+
+        stack.push(UndoEntry::Scroll { x: 0.0, y: 120.0 });
+        assert_eq!(stack.pop().unwrap(), UndoEntry::Scroll { x: 0.0, y: 120.0 });
+    }
+
+    #[test]
+    fn test_oldest_entries_are_forgotten() {
+        let mut stack = UndoStack::default();
+        for y in 0..=MAX_UNDO_ENTRIES {
+            stack.push(UndoEntry::Scroll {
+                x: 0.0,
+                y: y as f64,
+            });
+        }
+        assert_eq!(stack.len(), MAX_UNDO_ENTRIES);
+        assert_eq!(
+            stack.last(),
+            Some(&UndoEntry::Scroll {
+                x: 0.0,
+                y: MAX_UNDO_ENTRIES as f64
+            })
+        );
+    }
+}
@@ -1,15 +1,17 @@
 //! Tool action view types
 
 use crate::traits::BrowserClient;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// Base model for dynamically created action models
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct ActionModel {
-    /// Type of action
+    /// Name of the action
     pub action_type: String,
-    /// Parameters for the action
+    /// Parameters of the action
     pub params: HashMap<String, serde_json::Value>,
 }
 
@@ -142,8 +144,20 @@ impl<'a> ActionParams<'a> {
         }
         index
     }
+
+    /// Frame of the element at `index`, `None` if it is in the top-level document
+    pub fn frame_id_from_index(
+        &self,
+        index: u32,
+        selector_map: Option<&HashMap<u32, crate::dom::views::DOMInteractedElement>>,
+    ) -> Option<String> {
+        selector_map?.get(&index)?.frame_id.clone()
+    }
 }
 
+/// Predicate deciding whether an action is available with a browser profile
+pub type ActionCondition = std::sync::Arc<dyn Fn(&crate::browser::BrowserProfile) -> bool + Send + Sync>;
+
 /// Model for a registered action
 #[derive(Clone)]
 pub struct RegisteredAction {
@@ -155,6 +169,8 @@ pub struct RegisteredAction {
     pub domains: Option<Vec<String>>,
     /// Handler for the action
     pub handler: Option<std::sync::Arc<dyn ActionHandler>>,
+    /// Browser configurations the action is available in; `None` for all
+    pub condition: Option<ActionCondition>,
 }
 
 // Manual Debug implementation since we can't derive it due to trait object
@@ -172,6 +188,14 @@ impl std::fmt::Debug for RegisteredAction {
                     "None"
                 },
             )
+            .field(
+                "condition",
+                &if self.condition.is_some() {
+                    "Some(condition)"
+                } else {
+                    "None"
+                },
+            )
             .finish()
     }
 }
@@ -181,6 +205,17 @@ impl RegisteredAction {
     pub fn prompt_description(&self) -> String {
         format!("{}: {}", self.name, self.description)
     }
+
+    /// Whether the action can be used with a browser configured by `profile`
+    ///
+    /// Conditions cannot be checked without a profile, e.g. for mock browsers,
+    /// so every action counts as available then.
+    pub fn is_available(&self, profile: Option<&crate::browser::BrowserProfile>) -> bool {
+        match (&self.condition, profile) {
+            (Some(condition), Some(profile)) => condition(profile),
+            _ => true,
+        }
+    }
 }
 
 /// Model representing the action registry
@@ -213,29 +248,42 @@ impl ActionRegistry {
         false
     }
 
-    /// Gets the description for use in prompts
+    /// A copy without the actions that are unavailable with `profile`
+    pub fn available_for(&self, profile: Option<&crate::browser::BrowserProfile>) -> Self {
+        Self {
+            actions: self
+                .actions
+                .iter()
+                .filter(|(_, action)| action.is_available(profile))
+                .map(|(name, action)| (name.clone(), action.clone()))
+                .collect(),
+        }
+    }
+
+    /// Gets the description for use in prompts, sorted by action name
     pub fn get_prompt_description(&self, page_url: Option<&str>) -> String {
-        if page_url.is_none() {
+        let mut actions: Vec<&RegisteredAction> = if let Some(page_url) = page_url {
+            // Only include filtered actions for the current page URL
+            self.actions
+                .values()
+                .filter(|action| {
+                    if action.domains.is_none() {
+                        return false; // Skip actions with no filters
+                    }
+                    Self::_match_domains(&action.domains, page_url)
+                })
+                .collect()
+        } else {
             // For system prompt, include only actions with no filters
-            return self
-                .actions
+            self.actions
                 .values()
                 .filter(|action| action.domains.is_none())
-                .map(|action| action.prompt_description())
-                .collect::<Vec<_>>()
-                .join("\n");
-        }
+                .collect()
+        };
 
-        let page_url = page_url.unwrap();
-        // Only include filtered actions for the current page URL
-        self.actions
-            .values()
-            .filter(|action| {
-                if action.domains.is_none() {
-                    return false; // Skip actions with no filters
-                }
-                Self::_match_domains(&action.domains, page_url)
-            })
+        actions.sort_by(|a, b| a.name.cmp(&b.name));
+        actions
+            .iter()
             .map(|action| action.prompt_description())
             .collect::<Vec<_>>()
             .join("\n")
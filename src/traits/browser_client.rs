@@ -3,11 +3,18 @@
 //! This trait defines the interface for browser operations, enabling
 //! mock implementations for testing and alternative browser backends.
 
-use crate::actor::Page;
+use crate::agent::determinism::{Determinism, apply_determinism};
+use crate::actor::{
+    CheckpointId, EmulationSettings, LoadState, NavigateOptions, Page, SavedScreenshot,
+};
 use crate::browser::cdp::CdpClient;
-use crate::browser::views::{SessionInfo, TabInfo};
-use crate::error::Result;
+use crate::browser::{CapturedResponse, NavigationRecord, WebAppManifest};
+use crate::browser::profile::BrowserProfile;
+use crate::browser::views::{BrowserVersionInfo, NewWindowHandling, SessionInfo, TabInfo};
+use crate::error::{BrowsingError, Result};
 use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 
 /// Trait for browser client operations
@@ -28,12 +35,109 @@ pub trait BrowserClient: Send + Sync {
     /// Navigate to the specified URL
     async fn navigate(&mut self, url: &str) -> Result<()>;
 
+    /// Navigate with a referrer, one-shot headers or a load state to wait for
+    async fn navigate_with_options(&mut self, url: &str, options: &NavigateOptions) -> Result<()> {
+        if options.is_empty() {
+            return self.navigate(url).await;
+        }
+        self.get_page()?.goto_with_options(url, options).await
+    }
+
+    /// Reload the current page, bypassing the HTTP cache with `ignore_cache`,
+    /// and wait for `wait_until` on the new document
+    ///
+    /// Defaults to reloading without recording the navigation.
+    async fn reload(&mut self, ignore_cache: bool, wait_until: Option<LoadState>) -> Result<()> {
+        self.get_page()?
+            .reload_tracked(ignore_cache, wait_until)
+            .await
+            .map(|_| ())
+    }
+
     /// Get the current page URL
     async fn get_current_url(&self) -> Result<String>;
 
+    /// Get the current page URL, or with `canonical` the URL the page declares
+    /// with `<link rel="canonical">` when it has one
+    async fn get_url(&self, canonical: bool) -> Result<String> {
+        if canonical && let Some(url) = self.get_page()?.canonical_url().await? {
+            return Ok(url);
+        }
+        self.get_current_url().await
+    }
+
+    /// The last navigation made in the current tab, with its redirects
+    ///
+    /// Defaults to `None`, for mocks and browsers that do not track navigations.
+    async fn last_navigation(&self) -> Option<NavigationRecord> {
+        None
+    }
+
+    /// Follow redirects the current page makes by itself, for at most
+    /// `grace_ms` per page, e.g. after a click loaded a page with a meta refresh
+    ///
+    /// Defaults to following them without recording the navigation.
+    async fn follow_client_redirects(&mut self, grace_ms: u64) -> Result<()> {
+        self.get_page()?
+            .follow_client_redirects(grace_ms)
+            .await
+            .map(|_| ())
+    }
+
+    /// Take the responses captured by [`Browser::capture_responses`], oldest first
+    ///
+    /// [`Browser::capture_responses`]: crate::Browser::capture_responses
+    fn take_captured_responses(&self) -> Vec<CapturedResponse> {
+        Vec::new()
+    }
+
+    /// One line on the captured responses, while capturing
+    fn response_capture_summary(&self) -> Option<String> {
+        None
+    }
+
+    /// Emulate a color scheme, vision deficiency or Save-Data in the current tab
+    ///
+    /// Defaults to applying the settings to the current page without
+    /// remembering them.
+    async fn set_emulation(&mut self, settings: EmulationSettings) -> Result<()> {
+        self.get_page()?.apply_emulation(&settings).await
+    }
+
+    /// Send `headers` with every request, replacing the headers set before
+    ///
+    /// Defaults to setting them on the current page only.
+    async fn set_extra_headers(&mut self, headers: HashMap<String, String>) -> Result<()> {
+        self.get_page()?.set_extra_headers(&headers).await
+    }
+
+    /// Make the current tab, and the tabs used after it, behave as `determinism` asks
+    ///
+    /// Defaults to applying it to the current page only.
+    async fn set_determinism(&mut self, determinism: Determinism) -> Result<()> {
+        let client = self.get_cdp_client()?;
+        let session = self.get_session_info().await?;
+        apply_determinism(&client, &session.session_id, &determinism).await
+    }
+
+    /// Emulation settings of the current tab
+    ///
+    /// Defaults to `None`, for mocks and browsers that do not track them.
+    async fn emulation(&self) -> Option<EmulationSettings> {
+        None
+    }
+
     /// Create a new tab with optional URL
     async fn create_tab(&mut self, url: Option<&str>) -> Result<String>;
 
+    /// Open `url` in a tab that is not brought to the front, e.g. to read it
+    /// while the current tab stays in front
+    ///
+    /// Defaults to [`create_tab`](Self::create_tab).
+    async fn create_background_tab(&mut self, url: &str) -> Result<String> {
+        self.create_tab(Some(url)).await
+    }
+
     /// Switch to a different tab by target ID
     async fn switch_to_tab(&mut self, target_id: &str) -> Result<()>;
 
@@ -49,6 +153,17 @@ pub trait BrowserClient: Send + Sync {
     /// Get a Page actor for the current session
     fn get_page(&self) -> Result<Page>;
 
+    /// Get a Page actor for the tab `target_id`, without switching to it
+    ///
+    /// For working in background tabs, e.g. those opened by
+    /// [`create_tab`](Self::create_tab). Defaults to an error, for mocks and
+    /// browsers that only drive the current tab.
+    fn get_page_for_target(&self, target_id: &str) -> Result<Page> {
+        Err(BrowsingError::Browser(format!(
+            "No page for target {target_id}: this browser only drives the current tab"
+        )))
+    }
+
     /// Take a screenshot of the current page
     async fn take_screenshot(
         &self,
@@ -56,6 +171,17 @@ pub trait BrowserClient: Send + Sync {
         full_page: bool,
     ) -> Result<Vec<u8>>;
 
+    /// Take a screenshot of the current page and write it to `path`
+    ///
+    /// The default goes through [`take_screenshot`](Self::take_screenshot);
+    /// [`Browser`](crate::Browser) decodes the image to the file in chunks.
+    async fn screenshot_to_file(&self, path: &Path, full_page: bool) -> Result<SavedScreenshot> {
+        let image = self
+            .take_screenshot(Some(&path.to_string_lossy()), full_page)
+            .await?;
+        Ok(SavedScreenshot::from_image(path, &image))
+    }
+
     /// Get streamlined session information (URL, title, target ID, session ID)
     async fn get_session_info(&self) -> Result<SessionInfo> {
         Ok(SessionInfo {
@@ -66,6 +192,19 @@ pub trait BrowserClient: Send + Sync {
         })
     }
 
+    /// Switch to or close a window an action opened, as `handling` says
+    async fn handle_new_window(
+        &mut self,
+        target_id: &str,
+        handling: NewWindowHandling,
+    ) -> Result<()> {
+        match handling {
+            NewWindowHandling::Ignore => Ok(()),
+            NewWindowHandling::AutoSwitch => self.switch_to_tab(target_id).await,
+            NewWindowHandling::AutoClose => self.close_tab(target_id).await,
+        }
+    }
+
     /// Get the current page title
     #[deprecated(since = "0.1.2", note = "Use get_session_info() instead")]
     async fn get_current_page_title(&self) -> Result<String>;
@@ -80,4 +219,43 @@ pub trait BrowserClient: Send + Sync {
     /// Get the current target ID
     #[deprecated(since = "0.1.2", note = "Use get_session_info() instead")]
     fn get_current_target_id(&self) -> Result<String>;
+
+    /// Profile the browser was configured with, if known
+    ///
+    /// Decides which conditional actions are available. Defaults to `None`,
+    /// for mocks and browsers not configured through a profile.
+    fn profile(&self) -> Option<&BrowserProfile> {
+        None
+    }
+
+    /// Get browser and protocol versions
+    async fn version_info(&self) -> Result<BrowserVersionInfo> {
+        let result = self
+            .get_cdp_client()?
+            .send_command("Browser.getVersion", serde_json::json!({}))
+            .await?;
+        Ok(BrowserVersionInfo::from_cdp(&result))
+    }
+
+    /// Web app manifest of the current page, `None` if it links none
+    async fn get_web_app_manifest(&self) -> Result<Option<WebAppManifest>> {
+        self.get_page()?.get_web_app_manifest().await
+    }
+
+    /// Save URL, cookies, storage and scroll position of the current page
+    async fn checkpoint(&mut self) -> Result<CheckpointId> {
+        Err(BrowsingError::Browser(
+            "Checkpoints are not supported by this browser client".to_string(),
+        ))
+    }
+
+    /// Return the current tab to a saved checkpoint
+    ///
+    /// Only client-side state is restored; server-side changes made since the
+    /// checkpoint remain.
+    async fn restore(&mut self, id: CheckpointId) -> Result<()> {
+        Err(BrowsingError::Browser(format!(
+            "Cannot restore checkpoint {id}: checkpoints are not supported by this browser client"
+        )))
+    }
 }
@@ -0,0 +1,81 @@
+//! Version and environment information for bug reports
+
+use crate::browser::views::BrowserVersionInfo;
+use serde::{Deserialize, Serialize};
+
+/// How this crate was built
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    /// Crate version
+    pub version: String,
+    /// Short git revision of the build, or `unknown` outside a git checkout
+    pub git_hash: String,
+    /// Enabled cargo features
+    pub features: Vec<String>,
+    /// Operating system the crate was compiled for
+    pub os: String,
+    /// CPU architecture the crate was compiled for
+    pub arch: String,
+}
+
+impl BuildInfo {
+    /// One-line summary of the build and, if known, the browser
+    ///
+    /// e.g. `browsing 0.1.2 (3081cb9) linux/x86_64; Chrome/120.0.6099.71 CDP 1.3`
+    pub fn fingerprint(&self, browser: Option<&BrowserVersionInfo>) -> String {
+        let mut line = format!(
+            "browsing {} ({}) {}/{}",
+            self.version, self.git_hash, self.os, self.arch
+        );
+        if !self.features.is_empty() {
+            line.push_str(&format!(" [{}]", self.features.join(",")));
+        }
+        if let Some(browser) = browser {
+            line.push_str(&format!(
+                "; {} CDP {}",
+                browser.product, browser.protocol_version
+            ));
+        }
+        line
+    }
+}
+
+/// Crate build and browser versions, attached to saved histories and MCP stats
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnvironmentInfo {
+    /// How the crate was built
+    pub build: BuildInfo,
+    /// Browser versions, if a browser was running
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub browser: Option<BrowserVersionInfo>,
+}
+
+impl EnvironmentInfo {
+    /// Environment of this process, with the given browser details
+    pub fn new(browser: Option<BrowserVersionInfo>) -> Self {
+        Self {
+            build: build_info(),
+            browser,
+        }
+    }
+
+    /// One-line summary, see [`BuildInfo::fingerprint`]
+    pub fn fingerprint(&self) -> String {
+        self.build.fingerprint(self.browser.as_ref())
+    }
+}
+
+/// Information about this build of the crate
+pub fn build_info() -> BuildInfo {
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        git_hash: env!("BROWSING_GIT_HASH").to_string(),
+        features: env!("BROWSING_FEATURES")
+            .split(',')
+            .filter(|f| !f.is_empty())
+            .map(str::to_string)
+            .collect(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+    }
+}
@@ -0,0 +1,150 @@
+//! Tests for task context given to the agent's model
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::agent::service::Agent;
+use browsing::agent::views::AgentSettings;
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use common::{FakePageBrowser, fake_cdp};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Model that records the messages it is sent and finishes immediately with `done_text`
+#[derive(Clone, Default)]
+struct RecordingLLM {
+    messages: Arc<Mutex<Vec<ChatMessage>>>,
+    done_text: String,
+}
+
+#[async_trait]
+impl ChatModel for RecordingLLM {
+    fn model(&self) -> &str {
+        "recording"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        self.messages.lock().unwrap().extend_from_slice(messages);
+        Ok(ChatInvokeCompletion {
+            completion: json!({
+                "action": [{ "action_type": "done", "params": { "text": self.done_text } }]
+            })
+            .to_string(),
+            thinking: None,
+            redacted_thinking: None,
+            usage: None,
+            stop_reason: None,
+        })
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+fn context(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+async fn run_agent(configure: impl FnOnce(Agent<RecordingLLM>) -> Agent<RecordingLLM>) -> String {
+    run_agent_with(RecordingLLM::default(), configure).await
+}
+
+/// Run one step with `llm`, returning the system message it was sent
+async fn run_agent_with(
+    llm: RecordingLLM,
+    configure: impl FnOnce(Agent<RecordingLLM>) -> Agent<RecordingLLM>,
+) -> String {
+    let (client, _) = fake_cdp(Box::new(|method, _| match method {
+        // An empty page is enough to build a page state
+        "DOM.getDocument" => Ok(json!({
+            "root": { "nodeId": 1, "backendNodeId": 1, "nodeType": 9, "nodeName": "#document" }
+        })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let agent = Agent::new(
+        "Buy a coffee".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        llm.clone(),
+    )
+    .with_max_steps(1);
+    configure(agent).run().await.unwrap();
+
+    let messages = llm.messages.lock().unwrap();
+    assert_eq!(messages[0].role, "system");
+    messages[0].content.clone()
+}
+
+#[tokio::test]
+async fn test_task_context_is_in_system_message() {
+    let system = run_agent(|agent| {
+        agent.with_task_context(context(&[("store", "Berlin Mitte"), ("currency", "EUR")]))
+    })
+    .await;
+
+    assert!(
+        system.ends_with("<context>\ncurrency: EUR\nstore: Berlin Mitte\n</context>"),
+        "{system}"
+    );
+}
+
+#[tokio::test]
+async fn test_sensitive_context_is_given_to_the_model() {
+    let system = run_agent(|agent| {
+        agent
+            .with_settings(AgentSettings::default())
+            .with_task_context(context(&[("store", "Berlin Mitte")]))
+            .with_sensitive_context(context(&[("password", "hunter2")]))
+    })
+    .await;
+
+    assert!(
+        system.ends_with(
+            "<context>\nstore: Berlin Mitte\n</context>\n\n<context>\npassword: hunter2\n</context>"
+        ),
+        "{system}"
+    );
+}
+
+#[tokio::test]
+async fn test_sensitive_values_are_masked_in_action_log() {
+    let log_file = std::env::temp_dir().join(format!(
+        "browsing-sensitive-context-{}.jsonl",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&log_file);
+    let llm = RecordingLLM {
+        done_text: "Signed in as ada with hunter2".to_string(),
+        ..Default::default()
+    };
+    let settings = AgentSettings {
+        log_file: Some(log_file.clone()),
+        ..Default::default()
+    };
+    run_agent_with(llm, |agent| {
+        agent
+            .with_settings(settings)
+            .with_sensitive_context(context(&[("user", "ada"), ("password", "hunter2")]))
+    })
+    .await;
+
+    let log = std::fs::read_to_string(&log_file).unwrap();
+    std::fs::remove_file(&log_file).unwrap();
+    assert!(!log.contains("hunter2") && !log.contains("ada "), "{log}");
+    assert!(log.contains("Signed in as *** with ***"), "{log}");
+}
@@ -189,6 +189,9 @@ async fn test_agent_history_tracking() {
     
     // Create mock history
     let history = AgentHistoryList {
+        run_id: None,
+        seed: None,
+        output_version: 0,
         history: vec![AgentHistory {
             model_output: None,
             result: vec![ActionResult {
@@ -204,11 +207,17 @@ async fn test_agent_history_tracking() {
                 tabs: vec![],
                 interacted_element: vec![],
                 screenshot_path: None,
+                navigation: None,
+                emulation: None,
             },
             metadata: None,
             state_message: None,
         }],
         usage: None,
+        environment: None,
+        assertions: Default::default(),
+        report_path: None,
+        stop_reason: None,
     };
     
     // History should be trackable
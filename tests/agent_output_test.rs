@@ -0,0 +1,188 @@
+//! Tests for the agent output schema: validation, feedback and old histories
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::agent::output_schema::AGENT_OUTPUT_VERSION;
+use browsing::agent::service::Agent;
+use browsing::agent::views::{AgentHistoryList, AgentOutput};
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use common::{FakePageBrowser, fake_cdp};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+
+/// Model that first replies without an `action` field, then finishes,
+/// recording the messages it was sent
+#[derive(Clone, Default)]
+struct ForgetfulLLM {
+    messages: Arc<Mutex<Vec<Vec<ChatMessage>>>>,
+}
+
+#[async_trait]
+impl ChatModel for ForgetfulLLM {
+    fn model(&self) -> &str {
+        "forgetful"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        let mut recorded = self.messages.lock().unwrap();
+        recorded.push(messages.to_vec());
+        let reply = match recorded.len() {
+            1 => json!({ "next_goal": "Finish" }),
+            _ => json!({
+                "next_goal": "Finish",
+                "action": [{ "action_type": "done", "params": { "text": "Done" } }]
+            }),
+        };
+        Ok(ChatInvokeCompletion::new(reply.to_string()))
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+#[test]
+fn test_valid_output() {
+    let output = AgentOutput::validate(&json!({
+        "thinking": "The search box is element [3].",
+        "next_goal": "Search",
+        "action": [{ "action_type": "input", "params": { "index": 3, "text": "laptop" } }]
+    }))
+    .unwrap();
+    assert_eq!(output.action[0].action_type, "input");
+    assert_eq!(output.action[0].params["index"], 3);
+    assert!(output.memory.is_none());
+}
+
+#[test]
+fn test_missing_fields_are_named() {
+    let problems = AgentOutput::validate(&json!({
+        "next_goal": "Search",
+        "action": [{ "params": { "index": 3 } }]
+    }))
+    .unwrap_err();
+    assert_eq!(problems, vec!["missing field `action[0].action_type`"]);
+
+    let problems = AgentOutput::validate(&json!({ "next_goal": "Search" })).unwrap_err();
+    assert_eq!(problems, vec!["missing field `action`"]);
+}
+
+#[test]
+fn test_extra_fields_and_wrong_types_are_rejected() {
+    let problems = AgentOutput::validate(&json!({
+        "current_state": { "next_goal": "Search" },
+        "next_goal": 3,
+        "action": [{ "action_type": "wait", "params": {}, "index": 1 }]
+    }))
+    .unwrap_err();
+    assert_eq!(
+        problems,
+        vec![
+            "unexpected field `action[0].index`",
+            "unexpected field `current_state`",
+            "`next_goal` should be string or null, got number",
+        ]
+    );
+
+    let problems = AgentOutput::validate(&json!([])).unwrap_err();
+    assert_eq!(problems, vec!["the reply should be object, got array"]);
+}
+
+#[test]
+fn test_schema_describes_fields() {
+    let schema = AgentOutput::json_schema();
+    assert_eq!(schema["required"], json!(["action"]));
+    assert_eq!(schema["additionalProperties"], json!(false));
+    assert_eq!(
+        schema["properties"]["action"]["items"]["required"],
+        json!(["action_type", "params"])
+    );
+    assert!(schema["properties"]["next_goal"]["description"].is_string());
+}
+
+#[test]
+fn test_histories_saved_before_versioning_load() {
+    let output = json!({
+        "next_goal": "Finish",
+        "action": [{ "action_type": "done", "params": { "text": "Done" } }]
+    });
+    let legacy = json!({
+        "history": [{
+            "model_output": output.to_string(),
+            "result": [],
+            "state": {
+                "url": "https://example.com",
+                "title": "Example",
+                "tabs": [],
+                "interacted_element": [],
+                "screenshot_path": null
+            },
+            "metadata": null,
+            "state_message": null
+        }, {
+            "result": [],
+            "state": {
+                "url": "https://example.com",
+                "title": "Example",
+                "tabs": [],
+                "interacted_element": [],
+                "screenshot_path": null
+            },
+            "metadata": null,
+            "state_message": null
+        }],
+        "usage": null
+    });
+
+    let history: AgentHistoryList = serde_json::from_value(legacy).unwrap();
+    assert_eq!(history.output_version, 0);
+    let model_output = history.history[0].model_output.as_ref().unwrap();
+    assert_eq!(model_output.action[0].action_type, "done");
+    assert!(history.history[1].model_output.is_none());
+
+    // Saved again, the output is an object
+    let saved = serde_json::to_value(&history).unwrap();
+    assert_eq!(saved["history"][0]["model_output"]["action"], output["action"]);
+}
+
+#[tokio::test]
+async fn test_invalid_output_is_fed_back_to_the_model() {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let llm = ForgetfulLLM::default();
+    let history = Agent::new(
+        "Finish".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        llm.clone(),
+    )
+    .with_max_steps(3)
+    .run()
+    .await
+    .unwrap();
+
+    assert_eq!(history.output_version, AGENT_OUTPUT_VERSION);
+    assert!(history.is_done());
+    assert!(history.history[0].model_output.is_none());
+    let error = history.history[0].result[0].error.as_deref().unwrap();
+    assert!(error.contains("missing field `action`"), "{error}");
+
+    let messages = llm.messages.lock().unwrap();
+    assert_eq!(messages.len(), 2);
+    assert!(
+        messages[0][0].content.contains("matching this JSON schema"),
+        "{}",
+        messages[0][0].content
+    );
+    let state = &messages[1][1].content;
+    assert!(state.contains("missing field `action`"), "{state}");
+}
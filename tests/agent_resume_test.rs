@@ -0,0 +1,219 @@
+//! Tests for saving an agent mid-task and resuming it
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::agent::SavedAgentState;
+use browsing::agent::service::Agent;
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatInvokeUsage, ChatMessage, ChatModel};
+use common::{FakePageBrowser, fake_cdp};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+
+/// Model that answers with `outputs` in turn, 100 prompt and 10 completion tokens each
+#[derive(Clone)]
+struct ScriptedLLM {
+    outputs: Arc<Mutex<Vec<Value>>>,
+}
+
+impl ScriptedLLM {
+    fn new(outputs: Vec<Value>) -> Self {
+        Self {
+            outputs: Arc::new(Mutex::new(outputs)),
+        }
+    }
+}
+
+#[async_trait]
+impl ChatModel for ScriptedLLM {
+    fn model(&self) -> &str {
+        "scripted"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, _messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        let output = self.outputs.lock().unwrap().remove(0);
+        Ok(ChatInvokeCompletion {
+            completion: output.to_string(),
+            thinking: None,
+            redacted_thinking: None,
+            usage: Some(ChatInvokeUsage {
+                prompt_tokens: 100,
+                prompt_cached_tokens: None,
+                prompt_cache_creation_tokens: None,
+                prompt_image_tokens: None,
+                completion_tokens: 10,
+                total_tokens: 110,
+            }),
+            stop_reason: None,
+        })
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+fn wait() -> Value {
+    json!({
+        "evaluation_previous_goal": "Waiting for results",
+        "memory": "Searching",
+        "next_goal": "Wait",
+        "action": [{ "action_type": "wait", "params": { "seconds": 0 } }]
+    })
+}
+
+fn remember() -> Value {
+    json!({
+        "action": [{ "action_type": "remember", "params": { "key": "order", "value": "A-17" } }]
+    })
+}
+
+fn done() -> Value {
+    json!({ "action": [{ "action_type": "done", "params": { "text": "Order A-17" } }] })
+}
+
+fn cart_cookie() -> Value {
+    json!({ "name": "session", "value": "abc", "domain": "localhost", "path": "/" })
+}
+
+fn empty_document() -> Value {
+    json!({ "root": { "nodeId": 1, "backendNodeId": 1, "nodeType": 9, "nodeName": "#document" } })
+}
+
+fn state_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!(
+        "browsing-resume-{name}-{}.json",
+        std::process::id()
+    ))
+}
+
+#[tokio::test]
+async fn test_resumed_agent_continues_where_it_stopped() {
+    // First process: three steps, then the step limit
+    let (client, _) = fake_cdp(Box::new(|method, _| match method {
+        "DOM.getDocument" => Ok(empty_document()),
+        "Runtime.evaluate" => Ok(json!({ "result": { "value": {
+            "url": "http://localhost:8080/cart",
+            "origin": "http://localhost:8080",
+            "local_storage": { "cart": "2 items" },
+            "session_storage": {},
+            "scroll_x": 0,
+            "scroll_y": 0
+        } } })),
+        "Network.getAllCookies" => Ok(json!({ "cookies": [cart_cookie()] })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let mut agent = Agent::new(
+        "Find the order number".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        ScriptedLLM::new(vec![wait(), remember(), wait()]),
+    )
+    .with_max_steps(3);
+    let first = agent.run().await.unwrap();
+    assert_eq!(first.history.len(), 3);
+    let run_id = agent.run_id().to_string();
+
+    let path = state_path("continue");
+    agent.save_state(&path).await.unwrap();
+    drop(agent);
+    // Cookies and storage are readable by the owner only
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    let saved = SavedAgentState::load(&path).unwrap();
+    assert_eq!(saved.steps_taken(), 3);
+    assert_eq!(saved.usage.total_tokens, 330);
+    let page = saved.page.as_ref().unwrap();
+    assert_eq!(page.cookies, [cart_cookie()]);
+    assert_eq!(page.local_storage["cart"], "2 items");
+
+    // Second process: a new agent from the file finishes the task
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "DOM.getDocument" => Ok(empty_document()),
+        "Runtime.evaluate" => Ok(json!({ "result": { "value": "complete" } })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let mut resumed = Agent::resume(
+        &path,
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        ScriptedLLM::new(vec![wait(), done()]),
+    )
+    .unwrap()
+    .with_max_steps(10);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(resumed.run_id(), run_id);
+    assert_eq!(resumed.memory().get("order"), Some("A-17"));
+
+    let history = resumed.run().await.unwrap();
+
+    let numbers: Vec<u32> = history
+        .history
+        .iter()
+        .map(|item| item.metadata.as_ref().unwrap().step_number)
+        .collect();
+    assert_eq!(numbers, [1, 2, 3, 4, 5]);
+    assert_eq!(history.run_id.as_deref(), Some(run_id.as_str()));
+    assert!(history.is_done());
+    assert_eq!(history.usage.as_ref().unwrap().total_tokens, Some(550));
+
+    // The page was reloaded with the saved cookies and storage
+    let received = received.lock().unwrap();
+    let set_cookies = received
+        .iter()
+        .find(|(method, _, _)| method == "Network.setCookies")
+        .unwrap();
+    assert_eq!(set_cookies.1["cookies"], json!([cart_cookie()]));
+    let seed = received
+        .iter()
+        .find(|(method, _, _)| method == "Page.addScriptToEvaluateOnNewDocument")
+        .unwrap();
+    assert!(seed.1["source"].as_str().unwrap().contains("2 items"));
+    assert!(received.iter().any(|(method, params, _)| {
+        method == "Page.navigate" && params["url"] == "http://localhost:8080/cart"
+    }));
+}
+
+#[tokio::test]
+async fn test_saved_state_of_another_version_is_rejected() {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let agent = Agent::new(
+        "Find the order number".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        ScriptedLLM::new(vec![]),
+    );
+    let path = state_path("version");
+    agent.save_state(&path).await.unwrap();
+    let mut saved: Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    saved["version"] = json!(99);
+    std::fs::write(&path, saved.to_string()).unwrap();
+
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let resumed = Agent::resume(
+        &path,
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        ScriptedLLM::new(vec![]),
+    );
+    std::fs::remove_file(&path).unwrap();
+
+    let error = resumed.err().unwrap().to_string();
+    assert!(error.contains("version 99"), "{error}");
+}
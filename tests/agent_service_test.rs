@@ -106,6 +106,8 @@ fn test_agent_history_creation() {
             tabs: vec![],
             interacted_element: vec![],
             screenshot_path: None,
+            navigation: None,
+            emulation: None,
         },
         metadata: None,
         state_message: None,
@@ -127,8 +129,15 @@ fn test_agent_history_step_validation() {
 #[test]
 fn test_agent_history_list_creation() {
     let history_list = AgentHistoryList {
+        run_id: None,
+        seed: None,
+        output_version: 0,
         history: vec![],
         usage: None,
+        environment: None,
+        assertions: Default::default(),
+        report_path: None,
+        stop_reason: None,
     };
 
     assert!(history_list.history.is_empty());
@@ -145,9 +154,14 @@ fn test_agent_history_list_with_entries() {
         tabs: vec![],
         interacted_element: vec![],
         screenshot_path: None,
+        navigation: None,
+        emulation: None,
     };
 
     let history_list = AgentHistoryList {
+        run_id: None,
+        seed: None,
+        output_version: 0,
         history: vec![
             AgentHistory {
                 model_output: None,
@@ -165,6 +179,10 @@ fn test_agent_history_list_with_entries() {
             },
         ],
         usage: None,
+        environment: None,
+        assertions: Default::default(),
+        report_path: None,
+        stop_reason: None,
     };
 
     assert_eq!(history_list.history.len(), 2);
@@ -233,6 +251,8 @@ fn test_usage_summary_creation() {
         completion_tokens: Some(500),
         total_tokens: Some(1500),
         cost: Some(0.003),
+        avg_completion_tokens_per_step: None,
+        by_model: vec![],
     };
 
     assert_eq!(summary.prompt_tokens, Some(1000));
@@ -248,6 +268,8 @@ fn test_usage_summary_none_values() {
         completion_tokens: None,
         total_tokens: None,
         cost: None,
+        avg_completion_tokens_per_step: None,
+        by_model: vec![],
     };
 
     assert!(summary.prompt_tokens.is_none());
@@ -351,6 +373,9 @@ fn test_agent_state_with_selector_map() {
             text: Some("Click".to_string()),
             attributes: HashMap::new(),
             selector: None,
+            bounds: None,
+            form_id: None,
+            frame_id: None,
         },
     );
 
@@ -0,0 +1,199 @@
+//! Tests for streaming agent steps with Agent::run_stream
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::agent::service::Agent;
+use browsing::agent::stream::{STEP_EVENT_BUFFER, StepEvent};
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatInvokeUsage, ChatMessage, ChatModel};
+use common::{FakePageBrowser, fake_cdp};
+use futures_util::StreamExt;
+use serde_json::json;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Model that remembers a fact each step and finishes after `steps` steps
+#[derive(Clone)]
+struct CountingLLM {
+    steps: usize,
+    calls: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl ChatModel for CountingLLM {
+    fn model(&self) -> &str {
+        "counting"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, _messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+        let completion = if call < self.steps {
+            json!({
+                "next_goal": format!("Remember fact {call}"),
+                "action": [{
+                    "action_type": "remember",
+                    "params": { "key": format!("fact_{call}"), "value": "x" }
+                }]
+            })
+        } else {
+            json!({
+                "next_goal": "Finish",
+                "action": [{ "action_type": "done", "params": { "text": "ok" } }]
+            })
+        };
+        Ok(
+            ChatInvokeCompletion::new(completion.to_string()).with_usage(ChatInvokeUsage {
+                prompt_tokens: 100,
+                prompt_cached_tokens: None,
+                prompt_cache_creation_tokens: None,
+                prompt_image_tokens: None,
+                completion_tokens: 10,
+                total_tokens: 110,
+            }),
+        )
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+/// Model whose every call fails
+struct BrokenLLM;
+
+#[async_trait]
+impl ChatModel for BrokenLLM {
+    fn model(&self) -> &str {
+        "broken"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, _messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        Err(BrowsingError::Llm("model unavailable".to_string()))
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+async fn agent(steps: usize) -> (Agent<CountingLLM>, Arc<AtomicUsize>) {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let llm = CountingLLM {
+        steps,
+        calls: Arc::clone(&calls),
+    };
+    let agent = Agent::new(
+        "Remember some facts".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        llm,
+    )
+    .with_max_steps(steps as u32 + 5);
+    (agent, calls)
+}
+
+#[tokio::test]
+async fn test_stream_yields_each_step_then_run_complete() {
+    let (agent, _) = agent(3).await;
+    let events: Vec<StepEvent> = agent.run_stream().collect().await;
+
+    assert_eq!(events.len(), 4);
+    for (i, event) in events[..3].iter().enumerate() {
+        let StepEvent::Step(update) = event else {
+            panic!("expected a step, got {event:?}");
+        };
+        assert_eq!(update.step, i as u32 + 1);
+        assert_eq!(update.actions.len(), 1);
+        assert_eq!(update.results.len(), 1);
+        assert!(update.is_success());
+        assert_eq!(update.usage.as_ref().unwrap().total_tokens, 110);
+        assert_eq!(update.url, "https://example.com");
+    }
+    let StepEvent::Step(first) = &events[0] else {
+        unreachable!()
+    };
+    assert_eq!(first.summary, "Remember fact 1");
+    assert_eq!(first.actions[0].action_type, "remember");
+
+    let StepEvent::RunComplete { history, error } = &events[3] else {
+        panic!("expected the run to complete, got {:?}", events[3]);
+    };
+    assert_eq!(*error, None);
+    assert_eq!(history.number_of_steps(), 3);
+    assert_eq!(history.usage.as_ref().unwrap().total_tokens, Some(330));
+}
+
+#[tokio::test]
+async fn test_slow_consumer_pauses_the_run_without_deadlock() {
+    let steps = STEP_EVENT_BUFFER * 3;
+    let (agent, calls) = agent(steps).await;
+    let mut stream = Box::pin(agent.run_stream());
+
+    let mut seen = 0;
+    let run = async {
+        while let Some(event) = stream.next().await {
+            match event {
+                StepEvent::Step(update) => {
+                    seen += 1;
+                    assert_eq!(update.step as usize, seen);
+                    // The run is at most a full buffer, plus the step being
+                    // sent, ahead of what has been read
+                    assert!(calls.load(Ordering::SeqCst) <= seen + STEP_EVENT_BUFFER + 1);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                }
+                StepEvent::HumanInputRequested(question) => {
+                    panic!("unexpected question: {question:?}")
+                }
+                StepEvent::RunComplete { history, error } => {
+                    assert_eq!(error, None);
+                    assert_eq!(history.number_of_steps(), steps);
+                    return;
+                }
+            }
+        }
+        panic!("stream ended without RunComplete");
+    };
+    tokio::time::timeout(Duration::from_secs(30), run)
+        .await
+        .expect("slow consumer deadlocked the run");
+    assert_eq!(seen, steps);
+    assert_eq!(calls.load(Ordering::SeqCst), steps);
+}
+
+#[tokio::test]
+async fn test_failed_run_still_completes_the_stream() {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let agent = Agent::new(
+        "Remember some facts".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        BrokenLLM,
+    );
+
+    let events: Vec<StepEvent> = agent.run_stream().collect().await;
+
+    assert_eq!(events.len(), 1);
+    let StepEvent::RunComplete { history, error } = &events[0] else {
+        panic!("expected the run to complete, got {:?}", events[0]);
+    };
+    assert!(error.as_deref().unwrap().contains("model unavailable"));
+    assert_eq!(history.number_of_steps(), 0);
+}
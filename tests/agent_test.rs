@@ -13,6 +13,8 @@ fn test_action_result_creation() {
         attachments: None,
         images: None,
         long_term_memory: Some("Memory".to_string()),
+        include_in_memory: true,
+        debug_info: None,
         extracted_content: Some("Test content".to_string()),
         include_extracted_content_only_once: false,
         metadata: None,
@@ -38,6 +40,8 @@ fn test_usage_summary() {
         completion_tokens: Some(50),
         total_tokens: Some(150),
         cost: None,
+        avg_completion_tokens_per_step: None,
+        by_model: vec![],
     };
 
     assert_eq!(usage.prompt_tokens, Some(100));
@@ -47,8 +51,15 @@ fn test_usage_summary() {
 #[test]
 fn test_agent_history_list_creation() {
     let history = AgentHistoryList {
+        run_id: None,
+        seed: None,
+        output_version: 0,
         history: vec![],
         usage: None,
+        environment: None,
+        assertions: Default::default(),
+        report_path: None,
+        stop_reason: None,
     };
 
     assert!(history.history.is_empty());
@@ -76,6 +87,8 @@ fn test_action_result_serialization() {
         attachments: None,
         images: None,
         long_term_memory: Some("Memory".to_string()),
+        include_in_memory: false,
+        debug_info: Some("backend_node_id: 7".to_string()),
         extracted_content: Some("Test".to_string()),
         include_extracted_content_only_once: false,
         metadata: None,
@@ -86,4 +99,6 @@ fn test_action_result_serialization() {
 
     assert_eq!(deserialized.extracted_content, result.extracted_content);
     assert_eq!(deserialized.is_done, result.is_done);
+    assert!(!deserialized.include_in_memory);
+    assert_eq!(deserialized.debug_info, result.debug_info);
 }
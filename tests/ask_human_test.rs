@@ -0,0 +1,192 @@
+//! Tests for asking a human with the ask_human action
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::agent::human::HUMAN_INPUT_METADATA_KEY;
+use browsing::agent::service::Agent;
+use browsing::agent::views::{AgentHistoryList, AgentSettings};
+use browsing::agent::{AgentHandle, HumanExchange, StepEvent};
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use browsing::tools::Tools;
+use common::{FakePageBrowser, fake_cdp};
+use futures_util::StreamExt;
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const QUESTION: &str = "Which code was sent to your phone?";
+
+/// Model that asks for a code, then finishes, recording the messages it was sent
+#[derive(Clone, Default)]
+struct AskingLLM {
+    messages: Arc<Mutex<Vec<Vec<ChatMessage>>>>,
+}
+
+#[async_trait]
+impl ChatModel for AskingLLM {
+    fn model(&self) -> &str {
+        "asking"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        let mut recorded = self.messages.lock().unwrap();
+        recorded.push(messages.to_vec());
+        let action = if recorded.len() == 1 {
+            json!({ "action_type": "ask_human", "params": { "question": QUESTION } })
+        } else {
+            json!({ "action_type": "done", "params": { "text": "Logged in" } })
+        };
+        Ok(ChatInvokeCompletion::new(
+            json!({ "next_goal": "Log in", "action": [action] }).to_string(),
+        ))
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+async fn agent(timeout_secs: u32) -> (Agent<AskingLLM>, AskingLLM) {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let llm = AskingLLM::default();
+    let agent = Agent::new(
+        "Log in with the code sent to my phone".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        llm.clone(),
+    )
+    .with_settings(AgentSettings {
+        human_input_timeout: timeout_secs,
+        ..Default::default()
+    })
+    .with_max_steps(3);
+    (agent, llm)
+}
+
+/// The exchange recorded by the first step's `ask_human`
+fn exchange(history: &AgentHistoryList) -> HumanExchange {
+    let metadata = history.history[0].result[0].metadata.as_ref().unwrap();
+    serde_json::from_value(metadata[HUMAN_INPUT_METADATA_KEY].clone()).unwrap()
+}
+
+/// State message of the second step
+fn second_state(llm: &AskingLLM) -> String {
+    llm.messages.lock().unwrap()[1][1].content.clone()
+}
+
+/// Answer the next question with `answer`
+fn answer_with(handle: AgentHandle, answer: &'static str) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let question = handle.next_question().await;
+        assert_eq!(question.question, QUESTION);
+        assert_eq!(question.step, 1);
+        assert!(handle.provide_human_input(answer));
+    })
+}
+
+#[tokio::test]
+async fn test_answer_is_shown_in_the_next_prompt() {
+    let (mut agent, llm) = agent(30).await;
+    let answering = answer_with(agent.handle(), "481516");
+
+    let history = agent.run().await.unwrap();
+    answering.await.unwrap();
+
+    assert_eq!(
+        exchange(&history),
+        HumanExchange {
+            question: QUESTION.to_string(),
+            answer: Some("481516".to_string()),
+        }
+    );
+    let state = second_state(&llm);
+    assert!(
+        state.contains(&format!("Human's reply (to \"{QUESTION}\"): 481516")),
+        "{state}"
+    );
+    assert!(history.is_done());
+}
+
+#[tokio::test]
+async fn test_unanswered_question_times_out() {
+    let (mut agent, llm) = agent(1).await;
+    let handle = agent.handle();
+
+    let run = tokio::time::timeout(Duration::from_secs(10), agent.run());
+    let history = run.await.expect("the run waited forever").unwrap();
+
+    assert_eq!(exchange(&history).answer, None);
+    assert!(second_state(&llm).contains("(no answer"));
+    // The question is withdrawn, so a late answer goes nowhere
+    assert_eq!(handle.pending_question(), None);
+    assert!(!handle.provide_human_input("too late"));
+}
+
+#[tokio::test]
+async fn test_no_handle_means_no_wait() {
+    let (mut agent, _) = agent(300).await;
+
+    let run = tokio::time::timeout(Duration::from_secs(10), agent.run());
+    let history = run.await.expect("the run waited for nobody").unwrap();
+
+    assert_eq!(exchange(&history).answer, None);
+    assert!(history.is_done());
+}
+
+#[tokio::test]
+async fn test_question_is_streamed() {
+    let (mut agent, _) = agent(30).await;
+    // Answer from another task: the run only moves on while the stream is polled
+    let answering = answer_with(agent.handle(), "481516");
+    let mut events = Box::pin(agent.run_stream());
+
+    let mut asked = false;
+    while let Some(event) = events.next().await {
+        match event {
+            StepEvent::HumanInputRequested(question) => {
+                assert_eq!(question.question, QUESTION);
+                asked = true;
+            }
+            StepEvent::Step(update) => {
+                if update.step == 1 {
+                    assert!(asked);
+                    let memory = update.results[0].long_term_memory.as_deref().unwrap();
+                    assert!(memory.contains("481516"), "{memory}");
+                }
+            }
+            StepEvent::RunComplete { error, .. } => assert_eq!(error, None),
+        }
+    }
+    assert!(asked);
+    answering.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_tools_alone_cannot_ask() {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let mut browser = FakePageBrowser { client };
+    let action = serde_json::from_value(json!({
+        "action_type": "ask_human",
+        "params": { "question": QUESTION }
+    }))
+    .unwrap();
+
+    let err = Tools::default()
+        .act(action, &mut browser, None)
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("only available to an agent"),
+        "{err}"
+    );
+}
@@ -0,0 +1,250 @@
+//! Tests for the assertion actions
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::agent::assertions::ASSERTION_METADATA_KEY;
+use browsing::agent::service::Agent;
+use browsing::agent::views::{ActionResult, AgentHistoryList};
+use browsing::agent::{AssertionOutcome, AssertionSummary};
+use browsing::dom::DOMProcessorImpl;
+use browsing::dom::views::DOMInteractedElement;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use browsing::tools::Tools;
+use common::{FakePageBrowser, fake_cdp};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+const PAGE_TEXT: &str = "Your cart\n\nOrder   pending\nTotal: $42.00";
+
+/// Run `action_type` with `params` on a fake page at https://example.com showing
+/// [`PAGE_TEXT`], where `#cart` matches one element and element 7 is detached
+async fn assert_on_page(action_type: &str, params: Value) -> ActionResult {
+    let (client, _) = fake_cdp(Box::new(|method, _| match method {
+        "Runtime.evaluate" => Ok(json!({ "result": { "value": PAGE_TEXT } })),
+        "DOM.resolveNode" => Ok(json!({ "object": { "objectId": "node-1" } })),
+        "Runtime.callFunctionOn" => Ok(json!({ "result": { "value": false } })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let mut browser = FakePageBrowser { client };
+    let selector_map = HashMap::from([(
+        7,
+        DOMInteractedElement {
+            index: 7,
+            backend_node_id: Some(70),
+            tag: "button".to_string(),
+            text: Some("Pay".to_string()),
+            attributes: HashMap::new(),
+            selector: None,
+            bounds: None,
+            form_id: None,
+            frame_id: None,
+        },
+    )]);
+    let action =
+        serde_json::from_value(json!({ "action_type": action_type, "params": params })).unwrap();
+    Tools::default()
+        .act(action, &mut browser, Some(&selector_map))
+        .await
+        .unwrap()
+}
+
+fn outcome(result: &ActionResult) -> AssertionOutcome {
+    AssertionOutcome::from_result(result).unwrap()
+}
+
+#[tokio::test]
+async fn test_text_present_ignores_whitespace() {
+    let result = assert_on_page("assert_text_present", json!({ "text": "Order pending" })).await;
+
+    assert_eq!(result.success, Some(true));
+    assert!(outcome(&result).passed);
+    assert!(result.error.is_none());
+}
+
+#[tokio::test]
+async fn test_missing_text_fails_with_the_closest_line() {
+    let result = assert_on_page("assert_text_present", json!({ "text": "Order confirmed" })).await;
+
+    assert_eq!(result.success, Some(false));
+    assert_eq!(
+        result.error.as_deref(),
+        Some(
+            "assert_text_present failed\n\
+             - expected: page text containing \"Order confirmed\"\n\
+             + actual:   not found; closest line: \"Order   pending\""
+        )
+    );
+    let metadata = result.metadata.unwrap();
+    assert_eq!(metadata[ASSERTION_METADATA_KEY]["passed"], false);
+
+    let result = assert_on_page(
+        "assert_text_present",
+        json!({ "text": "YOUR CART", "ignore_case": true }),
+    )
+    .await;
+    assert!(outcome(&result).passed);
+}
+
+#[tokio::test]
+async fn test_url_matches() {
+    let result = assert_on_page("assert_url_matches", json!({ "pattern": "*example.com*" })).await;
+    assert!(outcome(&result).passed);
+
+    let result = assert_on_page("assert_url_matches", json!({ "pattern": "/checkout" })).await;
+    assert_eq!(
+        result.error.as_deref(),
+        Some(
+            "assert_url_matches failed\n\
+             - expected: URL matching \"/checkout\"\n\
+             + actual:   \"https://example.com\""
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_element_exists_by_selector_and_index() {
+    // The fake page answers the selector count with its text, which is no count
+    let result = assert_on_page("assert_element_exists", json!({ "selector": "#cart" })).await;
+    assert_eq!(
+        result.error.as_deref(),
+        Some(
+            "assert_element_exists failed\n\
+             - expected: an element matching \"#cart\"\n\
+             + actual:   no element matches"
+        )
+    );
+
+    let result = assert_on_page("assert_element_exists", json!({ "index": 7 })).await;
+    assert!(
+        result
+            .error
+            .unwrap()
+            .ends_with("+ actual:   element [7] is no longer on the page")
+    );
+
+    let result = assert_on_page("assert_element_exists", json!({ "index": 8 })).await;
+    assert!(
+        result
+            .error
+            .unwrap()
+            .ends_with("+ actual:   no element [8] in the page state")
+    );
+}
+
+#[tokio::test]
+async fn test_element_exists_needs_a_target() {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let mut browser = FakePageBrowser { client };
+    let action = serde_json::from_value(json!({
+        "action_type": "assert_element_exists",
+        "params": {}
+    }))
+    .unwrap();
+
+    let err = Tools::default()
+        .act(action, &mut browser, None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("'selector' or 'index'"), "{err}");
+}
+
+/// Model that sends the scripted actions one step at a time, then finishes
+#[derive(Clone, Default)]
+struct ScriptedLLM {
+    actions: Arc<Mutex<Vec<Value>>>,
+}
+
+#[async_trait]
+impl ChatModel for ScriptedLLM {
+    fn model(&self) -> &str {
+        "scripted"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, _messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        let mut actions = self.actions.lock().unwrap();
+        let action = if actions.is_empty() {
+            json!({ "action_type": "done", "params": { "text": "Checked" } })
+        } else {
+            actions.remove(0)
+        };
+        Ok(ChatInvokeCompletion::new(
+            json!({ "next_goal": "Check the page", "action": [action] }).to_string(),
+        ))
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn test_run_summarizes_assertions() {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let llm = ScriptedLLM {
+        actions: Arc::new(Mutex::new(vec![
+            json!({ "action_type": "assert_url_matches", "params": { "pattern": "example.com" } }),
+            json!({ "action_type": "assert_url_matches", "params": { "pattern": "/checkout" } }),
+            json!({ "action_type": "assert_url_matches", "params": { "pattern": "https://*" } }),
+        ])),
+    };
+
+    let history = Agent::new(
+        "Check the checkout page".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        llm,
+    )
+    .with_max_steps(5)
+    .run()
+    .await
+    .unwrap();
+
+    let summary = &history.assertions;
+    assert_eq!(summary.total(), 3);
+    assert_eq!(
+        summary.passed.iter().map(|a| a.step).collect::<Vec<_>>(),
+        [1, 3]
+    );
+    assert_eq!(summary.failed.len(), 1);
+    assert_eq!(summary.failed[0].step, 2);
+    assert!(summary.failed[0].message.contains("/checkout"));
+    assert!(!summary.all_passed());
+    // A failed assertion is reported, not fatal
+    assert!(history.is_done());
+
+    // The summary is saved with the history
+    let saved = serde_json::to_string(&history).unwrap();
+    let loaded: AgentHistoryList = serde_json::from_str(&saved).unwrap();
+    assert_eq!(loaded.assertions, *summary);
+}
+
+#[test]
+fn test_histories_without_assertions_omit_the_summary() {
+    let history = AgentHistoryList {
+        run_id: None,
+        seed: None,
+        output_version: 0,
+        history: vec![],
+        usage: None,
+        environment: None,
+        assertions: AssertionSummary::default(),
+        report_path: None,
+        stop_reason: None,
+    };
+
+    let saved = serde_json::to_value(&history).unwrap();
+    assert!(saved.get("assertions").is_none());
+    let loaded: AgentHistoryList = serde_json::from_value(saved).unwrap();
+    assert!(loaded.assertions.is_empty());
+}
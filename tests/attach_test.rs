@@ -0,0 +1,77 @@
+//! Tests for attaching to a browser started with --remote-debugging-port
+
+mod common;
+
+use browsing::browser::Browser;
+use common::{HttpResponse, fake_cdp_with_latency, methods, serve_http};
+use serde_json::json;
+use std::time::Duration;
+
+/// Serve `body` as the response to every HTTP request, returning the port
+async fn serve_json(body: String) -> u16 {
+    let url = serve_http(move |_| HttpResponse::ok("application/json", body.clone())).await;
+    url::Url::parse(&url).unwrap().port().unwrap()
+}
+
+#[tokio::test]
+async fn test_attach_connects_to_the_first_page() {
+    let (ws_url, received) = fake_cdp_with_latency(
+        Box::new(|method, _| match method {
+            "Target.getTargets" => Ok(json!({ "targetInfos": [
+                { "targetId": "W1", "type": "service_worker", "url": "https://example.com/sw.js" },
+                { "targetId": "P1", "type": "page", "url": "https://example.com" },
+                { "targetId": "P2", "type": "page", "url": "https://other.com" },
+            ]})),
+            "Target.attachToTarget" => Ok(json!({ "sessionId": "S2" })),
+            _ => Ok(json!({})),
+        }),
+        Duration::ZERO,
+    )
+    .await;
+    let port = serve_json(
+        json!([
+            { "id": "W1", "type": "service_worker", "webSocketDebuggerUrl": "ws://unused" },
+            { "id": "P2", "type": "page", "webSocketDebuggerUrl": ws_url },
+            { "id": "P1", "type": "page", "webSocketDebuggerUrl": "ws://unused" },
+        ])
+        .to_string(),
+    )
+    .await;
+
+    let mut browser = Browser::attach("127.0.0.1", port).await.unwrap();
+
+    assert!(browser.is_attached());
+    assert_eq!(browser.get_current_target_id().unwrap(), "P2");
+    let attach = received
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(method, _, _)| method == "Target.attachToTarget")
+        .map(|(_, params, _)| params["targetId"].clone());
+    assert_eq!(attach, Some(json!("P2")));
+
+    // Stopping only disconnects: nothing asks the browser to close
+    browser.stop().await.unwrap();
+    assert!(!browser.is_attached());
+    assert!(!methods(&received).contains(&"Browser.close".to_string()));
+}
+
+#[tokio::test]
+async fn test_attach_rejects_invalid_addresses() {
+    assert!(Browser::attach("", 9222).await.is_err());
+    assert!(Browser::attach("127.0.0.1", 0).await.is_err());
+}
+
+#[tokio::test]
+async fn test_attach_without_pages_fails() {
+    let port = serve_json(json!([{ "id": "W1", "type": "service_worker" }]).to_string()).await;
+
+    let error = Browser::attach("127.0.0.1", port).await.err().unwrap();
+
+    assert!(error.to_string().contains("No WebSocket debugger URL"));
+}
+
+#[test]
+fn test_new_browser_is_not_attached() {
+    assert!(!Browser::new(Default::default()).is_attached());
+}
@@ -0,0 +1,145 @@
+//! Tests for page audits against a scripted CDP endpoint
+
+mod common;
+
+use browsing::actor::{AuditSeverity, AuditType, Page};
+use common::{fake_cdp_with_events, methods};
+use serde_json::{Value, json};
+
+fn issue_added(session: &str, issue: Value) -> Value {
+    json!({ "method": "Audits.issueAdded", "params": { "issue": issue }, "sessionId": session })
+}
+
+fn missing_label(node_id: u64) -> Value {
+    json!({
+        "code": "GenericIssue",
+        "details": { "genericIssueDetails": {
+            "errorType": "FormLabelForNameError",
+            "violatingNodeId": node_id,
+            "violatingNodeAttribute": "for"
+        } }
+    })
+}
+
+fn blocked_script() -> Value {
+    json!({
+        "code": "ContentSecurityPolicyIssue",
+        "details": { "contentSecurityPolicyIssueDetails": {
+            "blockedURL": "https://cdn.example/x.js",
+            "violatedDirective": "script-src-elem",
+            "isReportOnly": false,
+            "contentSecurityPolicyViolationType": "kURLViolation"
+        } }
+    })
+}
+
+fn rejected_cookie() -> Value {
+    json!({
+        "code": "CookieIssue",
+        "details": { "cookieIssueDetails": {
+            "cookie": { "name": "sid", "domain": "example.com", "path": "/" },
+            "cookieWarningReasons": [],
+            "cookieExclusionReasons": ["ExcludeSameSiteNoneInsecure"],
+            "operation": "SetCookie"
+        } }
+    })
+}
+
+/// Page whose `Audits.enable` replays the given issues, with one for another session
+async fn audited_page(issues: Vec<Value>) -> (Page, common::Received) {
+    let (client, received) = fake_cdp_with_events(
+        Box::new(|method, _| match method {
+            "DOM.resolveNode" => Ok(json!({ "object": { "objectId": "node" } })),
+            "Runtime.callFunctionOn" => {
+                Ok(json!({ "result": { "value": "form > label:nth-of-type(2)" } }))
+            }
+            _ => Ok(json!({})),
+        }),
+        Box::new(move |method, _| {
+            if method != "Audits.enable" {
+                return vec![];
+            }
+            let mut events: Vec<Value> = issues
+                .iter()
+                .map(|issue| issue_added("S1", issue.clone()))
+                .collect();
+            events.push(issue_added("OTHER", rejected_cookie()));
+            events
+        }),
+    )
+    .await;
+    (Page::new(client, "S1".to_string()), received)
+}
+
+#[tokio::test]
+async fn test_accessibility_audit_resolves_element_selector() {
+    let (page, received) =
+        audited_page(vec![missing_label(42), blocked_script(), rejected_cookie()]).await;
+
+    let issues = page.run_accessibility_audit().await.unwrap();
+
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].code, "FormLabelForNameError");
+    assert_eq!(
+        issues[0].element_selector.as_deref(),
+        Some("form > label:nth-of-type(2)")
+    );
+    let received = received.lock().unwrap();
+    let resolve = received
+        .iter()
+        .find(|(m, _, _)| m == "DOM.resolveNode")
+        .unwrap();
+    assert_eq!(resolve.1["backendNodeId"], 42);
+    assert!(received.iter().any(|(m, _, _)| m == "Audits.disable"));
+}
+
+#[tokio::test]
+async fn test_csp_audit() {
+    let (page, _) = audited_page(vec![missing_label(42), blocked_script()]).await;
+
+    let issues = page.run_content_security_policy_audit().await.unwrap();
+
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].violated_directive, "script-src-elem");
+    assert_eq!(
+        issues[0].blocked_url.as_deref(),
+        Some("https://cdn.example/x.js")
+    );
+    assert_eq!(issues[0].severity, AuditSeverity::Error);
+    assert!(issues[0].element_selector.is_none());
+}
+
+#[tokio::test]
+async fn test_full_audit_lists_errors_first_and_ignores_other_sessions() {
+    let (page, received) =
+        audited_page(vec![missing_label(42), rejected_cookie(), blocked_script()]).await;
+
+    let issues = page.run_audit(AuditType::All).await.unwrap();
+
+    let codes: Vec<&str> = issues.iter().map(|i| i.code.as_str()).collect();
+    assert_eq!(
+        codes,
+        [
+            "ExcludeSameSiteNoneInsecure",
+            "kURLViolation",
+            "FormLabelForNameError"
+        ]
+    );
+    let serialized = serde_json::to_value(&issues[0]).unwrap();
+    assert_eq!(
+        serialized,
+        json!({
+            "code": "ExcludeSameSiteNoneInsecure",
+            "description": "Cookie \"sid\" was rejected: ExcludeSameSiteNoneInsecure",
+            "element_selector": null,
+            "severity": "error"
+        })
+    );
+    assert_eq!(methods(&received)[0], "Audits.enable");
+}
+
+#[tokio::test]
+async fn test_audit_of_clean_page_is_empty() {
+    let (page, _) = audited_page(vec![]).await;
+    assert!(page.run_audit(AuditType::All).await.unwrap().is_empty());
+}
@@ -0,0 +1,130 @@
+//! Tests for callbacks from page JavaScript to Rust
+
+mod common;
+
+use browsing::actor::Page;
+use common::{Received, fake_cdp_with_events};
+use serde_json::{Value, json};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+const RAW_NAME: &str = "__browsingBinding_reportMutations";
+
+fn binding_called(session_id: &str, name: &str, payload: &str) -> Value {
+    json!({
+        "method": "Runtime.bindingCalled",
+        "sessionId": session_id,
+        "params": { "name": name, "payload": payload, "executionContextId": 1 }
+    })
+}
+
+/// Page in session S1 whose first `Runtime.evaluate` is followed by binding calls
+async fn page_with_calls() -> (Page, Received) {
+    let (client, received) = fake_cdp_with_events(
+        Box::new(|method, _| match method {
+            "Page.addScriptToEvaluateOnNewDocument" => Ok(json!({ "identifier": "7" })),
+            _ => Ok(json!({})),
+        }),
+        Box::new(|method, params| {
+            let installing = params["expression"]
+                .as_str()
+                .is_some_and(|e| e.contains("window[\"reportMutations\"] ="));
+            if method != "Runtime.evaluate" || !installing {
+                return vec![];
+            }
+            vec![
+                binding_called("S1", RAW_NAME, r#"{"added":20}"#),
+                binding_called("S1", RAW_NAME, "not json"),
+                binding_called("OTHER", RAW_NAME, r#"{"added":1}"#),
+                binding_called("S1", "__browsingBinding_other", r#"{"added":2}"#),
+                binding_called("S1", RAW_NAME, r#"{"added":3}"#),
+            ]
+        }),
+    )
+    .await;
+    (Page::new(client, "S1".to_string()), received)
+}
+
+#[tokio::test]
+async fn test_binding_calls_reach_the_handler() {
+    let (page, received) = page_with_calls().await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    let handle = page
+        .expose_binding("reportMutations", move |payload| {
+            let tx = tx.clone();
+            async move {
+                let _ = tx.send(payload);
+            }
+        })
+        .await
+        .unwrap();
+    assert_eq!(handle.name(), "reportMutations");
+
+    let mut calls = Vec::new();
+    for _ in 0..2 {
+        let call = tokio::time::timeout(Duration::from_secs(2), rx.recv()).await;
+        calls.push(call.unwrap().unwrap());
+    }
+    // Invalid payloads, other sessions and other bindings are ignored
+    assert_eq!(calls, vec![json!({ "added": 20 }), json!({ "added": 3 })]);
+    assert!(
+        tokio::time::timeout(Duration::from_millis(100), rx.recv())
+            .await
+            .is_err()
+    );
+
+    let received = received.lock().unwrap();
+    let (_, added, session) = received
+        .iter()
+        .find(|(method, _, _)| method == "Runtime.addBinding")
+        .unwrap();
+    assert_eq!(added["name"], RAW_NAME);
+    assert_eq!(session.as_deref(), Some("S1"));
+    // The function is defined again in every new document
+    let (_, script, _) = received
+        .iter()
+        .find(|(method, _, _)| method == "Page.addScriptToEvaluateOnNewDocument")
+        .unwrap();
+    assert!(
+        script["source"]
+            .as_str()
+            .unwrap()
+            .contains("JSON.stringify")
+    );
+}
+
+#[tokio::test]
+async fn test_remove_binding() {
+    let (page, received) = page_with_calls().await;
+    let handle = page
+        .expose_binding("reportMutations", |_| async {})
+        .await
+        .unwrap();
+
+    handle.remove().await.unwrap();
+
+    let received = received.lock().unwrap();
+    let params = |method: &str| {
+        received
+            .iter()
+            .find(|(m, _, _)| m == method)
+            .map(|(_, params, _)| params.clone())
+            .unwrap()
+    };
+    assert_eq!(params("Runtime.removeBinding")["name"], RAW_NAME);
+    assert_eq!(
+        params("Page.removeScriptToEvaluateOnNewDocument")["identifier"],
+        "7"
+    );
+    assert!(received.iter().any(|(m, p, _)| m == "Runtime.evaluate"
+        && p["expression"] == "delete window[\"reportMutations\"]"));
+}
+
+#[tokio::test]
+async fn test_invalid_binding_name() {
+    let (page, received) = page_with_calls().await;
+
+    assert!(page.expose_binding("a-b", |_| async {}).await.is_err());
+    assert!(received.lock().unwrap().is_empty());
+}
@@ -0,0 +1,150 @@
+//! Tests for the page state before anything was navigated to
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::agent::service::Agent;
+use browsing::agent::views::AgentSettings;
+use browsing::dom::{
+    DOMProcessorImpl, EMPTY_PAGE_STATE, NodeCategory, SerializedDOMState, is_blank_page_url,
+};
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use common::{FakePageBrowser, fake_cdp};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Model that records the messages it is sent and finishes right away
+#[derive(Clone, Default)]
+struct RecordingLLM {
+    messages: Arc<Mutex<Vec<ChatMessage>>>,
+}
+
+#[async_trait]
+impl ChatModel for RecordingLLM {
+    fn model(&self) -> &str {
+        "recording"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        self.messages.lock().unwrap().extend_from_slice(messages);
+        Ok(ChatInvokeCompletion {
+            completion:
+                json!({ "action": [{ "action_type": "done", "params": { "text": "ok" } }] })
+                    .to_string(),
+            thinking: None,
+            redacted_thinking: None,
+            usage: None,
+            stop_reason: None,
+        })
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+/// `DOM.getDocument` result for `about:blank`: an HTML element with empty head and body
+fn blank_document() -> Value {
+    let element = |id: u32, name: &str, children: Vec<Value>| {
+        json!({
+            "nodeId": id, "backendNodeId": id, "nodeType": 1,
+            "nodeName": name, "localName": name.to_lowercase(), "attributes": [],
+            "children": children
+        })
+    };
+    json!({ "root": {
+        "nodeId": 1, "backendNodeId": 1, "nodeType": 9, "nodeName": "#document",
+        "documentURL": "about:blank",
+        "children": [element(2, "HTML", vec![element(3, "HEAD", vec![]), element(4, "BODY", vec![])])]
+    } })
+}
+
+/// Run one agent step with `document` as the DOM, returning the user message
+async fn prompt_before_navigation(document: Value, settings: AgentSettings) -> String {
+    let (client, _) = fake_cdp(Box::new(move |method, _| match method {
+        "DOM.getDocument" => Ok(document.clone()),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let llm = RecordingLLM::default();
+    let history = Agent::new(
+        "Find the opening hours".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        llm.clone(),
+    )
+    .with_settings(settings)
+    .with_max_steps(1)
+    .run()
+    .await
+    .unwrap();
+    assert!(history.history[0].result.iter().all(|r| r.error.is_none()));
+
+    let messages = llm.messages.lock().unwrap();
+    messages[1].content.clone()
+}
+
+#[test]
+fn test_blank_page_urls() {
+    for url in [
+        "",
+        "about:blank",
+        "about:blank#popup",
+        "chrome://newtab/",
+        "chrome://new-tab-page/",
+    ] {
+        assert!(is_blank_page_url(url), "{url}");
+    }
+    for url in ["https://example.com/", "about:blankets", "data:text/html,"] {
+        assert!(!is_blank_page_url(url), "{url}");
+    }
+}
+
+#[test]
+fn test_empty_serialized_state() {
+    let mut state = SerializedDOMState {
+        html: Some("  \n".to_string()),
+        text: None,
+        markdown: None,
+        elements: vec![],
+        selector_map: HashMap::new(),
+        truncated_values: vec![],
+    };
+    assert!(state.is_empty());
+    assert_eq!(state.page_state(), EMPTY_PAGE_STATE);
+
+    state.html = Some("Opening hours: 9-17".to_string());
+    assert!(!state.is_empty());
+    assert_eq!(state.page_state(), "Opening hours: 9-17");
+}
+
+#[tokio::test]
+async fn test_agent_step_on_about_blank() {
+    let prompt = prompt_before_navigation(blank_document(), AgentSettings::default()).await;
+    assert!(prompt.contains(EMPTY_PAGE_STATE), "{prompt}");
+}
+
+#[tokio::test]
+async fn test_agent_step_without_document() {
+    let prompt = prompt_before_navigation(json!({}), AgentSettings::default()).await;
+    assert!(prompt.contains(EMPTY_PAGE_STATE), "{prompt}");
+}
+
+#[tokio::test]
+async fn test_agent_step_on_about_blank_with_categories() {
+    let settings = AgentSettings {
+        dom_node_categories: vec![NodeCategory::Interactive],
+        ..Default::default()
+    };
+    let prompt = prompt_before_navigation(blank_document(), settings).await;
+    assert!(prompt.contains(EMPTY_PAGE_STATE), "{prompt}");
+}
@@ -0,0 +1,80 @@
+//! Tests for isolated browser contexts
+
+mod common;
+
+use browsing::browser::{Browser, BrowserContextId, BrowserProfile};
+use common::{Received, fake_cdp_with_latency};
+use serde_json::json;
+use std::time::Duration;
+
+/// Browser with tab T1 in the default context; new targets are T2 in context C1
+async fn connected_browser() -> (Browser, Received) {
+    let (url, received) = fake_cdp_with_latency(
+        Box::new(|method, call| match method {
+            "Target.getTargets" => Ok(json!({
+                "targetInfos": [
+                    { "targetId": "T1", "type": "page", "url": "about:blank", "browserContextId": "DEFAULT" },
+                    { "targetId": "T2", "type": "page", "url": "https://example.com/", "browserContextId": "C1" }
+                ]
+            })),
+            "Target.attachToTarget" => Ok(json!({ "sessionId": format!("S{call}") })),
+            "Target.createBrowserContext" => Ok(json!({ "browserContextId": "C1" })),
+            "Target.getBrowserContexts" => Ok(json!({ "browserContextIds": ["C1"] })),
+            "Target.createTarget" => Ok(json!({ "targetId": "T2" })),
+            _ => Ok(json!({})),
+        }),
+        Duration::ZERO,
+    )
+    .await;
+    let mut browser = Browser::new(BrowserProfile::default()).with_cdp_url(url);
+    browser.start().await.unwrap();
+    (browser, received)
+}
+
+fn sent(received: &Received, method: &str) -> Vec<serde_json::Value> {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(m, _, _)| m == method)
+        .map(|(_, params, _)| params.clone())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_tab_in_new_context() {
+    let (mut browser, received) = connected_browser().await;
+    assert_eq!(browser.current_browser_context(), None);
+
+    let (target_id, context) = browser
+        .create_tab_in_new_context(Some("https://example.com/"))
+        .await
+        .unwrap();
+    assert_eq!(target_id, "T2");
+    assert_eq!(context, BrowserContextId("C1".to_string()));
+    let created = sent(&received, "Target.createTarget");
+    assert_eq!(created[0]["browserContextId"], "C1");
+    assert_eq!(created[0]["url"], "https://example.com/");
+    assert_eq!(
+        browser.list_browser_contexts().await.unwrap(),
+        vec![context.clone()]
+    );
+
+    browser.switch_to_tab("T2").await.unwrap();
+    assert_eq!(browser.current_browser_context(), Some(&context));
+
+    browser.close_tab("T2").await.unwrap();
+    let disposed = sent(&received, "Target.disposeBrowserContext");
+    assert_eq!(disposed, vec![json!({ "browserContextId": "C1" })]);
+    assert_eq!(browser.current_browser_context(), None);
+}
+
+#[tokio::test]
+async fn test_closing_default_context_tab_keeps_contexts() {
+    let (mut browser, received) = connected_browser().await;
+    browser.create_tab_in_new_context(None).await.unwrap();
+
+    browser.close_tab("T1").await.unwrap();
+
+    assert!(sent(&received, "Target.disposeBrowserContext").is_empty());
+}
@@ -28,6 +28,11 @@ async fn test_browser_profile_configuration() {
         allowed_domains: Some(vec!["example.com".to_string()]),
         downloads_path: Some("/tmp/downloads".into()),
         proxy: None,
+        attach_workers: false,
+        stealth: false,
+        stealth_options: Default::default(),
+        disable_cache: false,
+        emulation: Default::default(),
     };
     
     let browser = Browser::new(profile);
@@ -133,6 +138,11 @@ fn test_browser_profile_validation() {
         allowed_domains: Some(vec![]), // Empty domain list might be invalid
         downloads_path: None,
         proxy: None,
+        attach_workers: false,
+        stealth: false,
+        stealth_options: Default::default(),
+        disable_cache: false,
+        emulation: Default::default(),
     };
     
     // Profile creation should succeed (validation happens at use time)
@@ -153,6 +163,11 @@ async fn test_browser_concurrent_sessions() {
                 allowed_domains: None,
                 downloads_path: None,
                 proxy: None,
+                attach_workers: false,
+                stealth: false,
+                stealth_options: Default::default(),
+                disable_cache: false,
+                emulation: Default::default(),
             };
             Browser::new(profile)
         })
@@ -0,0 +1,180 @@
+//! Tests for building the browser state outside the agent
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::agent::service::Agent;
+use browsing::browser::views::BrowserStateSummary;
+use browsing::browser::{BrowserStateOptions, build_browser_state};
+use browsing::dom::views::{DOMInteractedElement, SerializedDOMState};
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use browsing::traits::DOMProcessor;
+use common::{FakePageBrowser, fake_cdp};
+use serde_json::json;
+use std::collections::HashMap;
+
+/// DOM processor serving a page with a search box as element 1
+struct MockDOMProcessor {
+    fail: bool,
+}
+
+impl MockDOMProcessor {
+    fn state() -> SerializedDOMState {
+        let search = DOMInteractedElement {
+            index: 1,
+            backend_node_id: Some(10),
+            tag: "input".to_string(),
+            text: None,
+            attributes: HashMap::from([("name".to_string(), "q".to_string())]),
+            selector: None,
+            bounds: None,
+            form_id: None,
+            frame_id: None,
+        };
+        SerializedDOMState {
+            html: None,
+            text: Some("[1]<input name=q />".to_string()),
+            markdown: None,
+            elements: vec![],
+            selector_map: HashMap::from([(1, search)]),
+            truncated_values: vec![],
+        }
+    }
+}
+
+#[async_trait]
+impl DOMProcessor for MockDOMProcessor {
+    async fn get_serialized_dom(&self) -> Result<SerializedDOMState> {
+        if self.fail {
+            return Err(BrowsingError::Dom("Page crashed".to_string()));
+        }
+        Ok(Self::state())
+    }
+
+    async fn get_page_state_string(&self) -> Result<String> {
+        Ok(Self::state().text.unwrap_or_default())
+    }
+
+    async fn get_selector_map(&self) -> Result<HashMap<u32, DOMInteractedElement>> {
+        Ok(Self::state().selector_map)
+    }
+}
+
+/// Model that finishes the task right away
+#[derive(Clone)]
+struct DoneLLM;
+
+#[async_trait]
+impl ChatModel for DoneLLM {
+    fn model(&self) -> &str {
+        "done"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, _messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        Ok(ChatInvokeCompletion {
+            completion: json!({
+                "action": [{ "action_type": "done", "params": { "text": "Finished", "success": true } }]
+            })
+            .to_string(),
+            thinking: None,
+            redacted_thinking: None,
+            usage: None,
+            stop_reason: None,
+        })
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+async fn browser() -> FakePageBrowser {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    FakePageBrowser { client }
+}
+
+#[tokio::test]
+async fn test_state_has_page_and_selector_map() {
+    let browser = browser().await;
+    let state = build_browser_state(
+        &browser,
+        &MockDOMProcessor { fail: false },
+        &BrowserStateOptions::new(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(state.url, "https://example.com");
+    assert_eq!(state.title, "Example");
+    assert!(state.tabs.is_empty());
+    assert!(state.screenshot.is_none());
+    assert_eq!(state.dom_state.selector_map[&1].backend_node_id, Some(10));
+    assert_eq!(state.dom_state.page_state(), "[1]<input name=q />");
+}
+
+#[tokio::test]
+async fn test_screenshot_is_included_on_request() {
+    let browser = browser().await;
+    let options = BrowserStateOptions::new().with_screenshot(true);
+    let state = build_browser_state(&browser, &MockDOMProcessor { fail: false }, &options)
+        .await
+        .unwrap();
+
+    // The fake browser's screenshot is empty
+    assert_eq!(state.screenshot.as_deref(), Some(""));
+}
+
+#[tokio::test]
+async fn test_state_round_trips_through_json() {
+    let browser = browser().await;
+    let state = build_browser_state(
+        &browser,
+        &MockDOMProcessor { fail: false },
+        &BrowserStateOptions::new(),
+    )
+    .await
+    .unwrap();
+
+    let json = serde_json::to_string(&state).unwrap();
+    let shipped: BrowserStateSummary = serde_json::from_str(&json).unwrap();
+    assert_eq!(shipped.url, state.url);
+    assert_eq!(shipped.dom_state.selector_map[&1].tag, "input");
+    assert!(!json.contains("\"screenshot\""));
+}
+
+#[tokio::test]
+async fn test_dom_failure_is_an_error() {
+    let browser = browser().await;
+    let result = build_browser_state(
+        &browser,
+        &MockDOMProcessor { fail: true },
+        &BrowserStateOptions::new(),
+    )
+    .await;
+
+    assert!(matches!(result, Err(BrowsingError::Dom(_))));
+}
+
+#[tokio::test]
+async fn test_agent_history_records_the_page_title() {
+    let history = Agent::new(
+        "Search for blenders".to_string(),
+        Box::new(browser().await),
+        Box::new(MockDOMProcessor { fail: false }),
+        DoneLLM,
+    )
+    .with_max_steps(1)
+    .run()
+    .await
+    .unwrap();
+
+    assert_eq!(history.history[0].state.title, "Example");
+}
@@ -9,6 +9,7 @@ fn test_tab_info_creation() {
         title: "Example".to_string(),
         target_id: "target-123".to_string(),
         parent_target_id: None,
+        ..Default::default()
     };
 
     assert_eq!(tab.url, "https://example.com");
@@ -23,6 +24,7 @@ fn test_tab_info_serialization() {
         title: "Example".to_string(),
         target_id: "target-123".to_string(),
         parent_target_id: None,
+        ..Default::default()
     };
 
     let json_str = serde_json::to_string(&tab).unwrap();
@@ -32,6 +34,74 @@ fn test_tab_info_serialization() {
     assert_eq!(deserialized.title, tab.title);
 }
 
+#[test]
+fn test_tab_info_enriched_round_trip() {
+    let tab = TabInfo {
+        url: "https://example.com/docs".to_string(),
+        title: "Docs".to_string(),
+        target_id: "target-abcd".to_string(),
+        is_loading: true,
+        favicon_url: Some("https://example.com/favicon.ico".to_string()),
+        opener_tab_id: Some("target-1234".to_string()),
+        last_active_at: Some(
+            chrono::DateTime::parse_from_rfc3339("2026-10-15T12:00:00Z")
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+        ),
+        ..Default::default()
+    };
+
+    let json = serde_json::to_value(&tab).unwrap();
+    assert_eq!(json["is_loading"], true);
+    assert_eq!(json["target_type"], "page");
+
+    let deserialized: TabInfo = serde_json::from_value(json).unwrap();
+    assert_eq!(deserialized.is_loading, tab.is_loading);
+    assert_eq!(deserialized.favicon_url, tab.favicon_url);
+    assert_eq!(deserialized.opener_tab_id, tab.opener_tab_id);
+    assert_eq!(deserialized.last_active_at, tab.last_active_at);
+}
+
+#[test]
+fn test_tab_info_deserializes_without_new_fields() {
+    let tab: TabInfo = serde_json::from_str(
+        r#"{"url":"https://example.com","title":"Example","target_id":"t1","parent_target_id":null}"#,
+    )
+    .unwrap();
+
+    assert_eq!(tab.target_type, "page");
+    assert!(!tab.is_loading);
+    assert!(tab.favicon_url.is_none());
+    assert!(tab.last_active_at.is_none());
+}
+
+#[test]
+fn test_tab_info_summary() {
+    let tab = TabInfo {
+        url: "https://example.com".to_string(),
+        title: "Example".to_string(),
+        target_id: "target-abcd".to_string(),
+        is_loading: true,
+        opener_tab_id: Some("target-1234".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        tab.summary(),
+        "#abcd Example (https://example.com) [loading, opened by #1234]"
+    );
+
+    let worker = TabInfo {
+        url: "https://example.com/sw.js".to_string(),
+        target_id: "worker-9f8e".to_string(),
+        target_type: "service_worker".to_string(),
+        ..Default::default()
+    };
+    assert_eq!(
+        worker.summary(),
+        "#9f8e (untitled) (https://example.com/sw.js) [service_worker]"
+    );
+}
+
 #[tokio::test]
 async fn test_browser_headless_startup() {
     use browsing::browser::{Browser, BrowserProfile};
@@ -43,6 +113,11 @@ async fn test_browser_headless_startup() {
         allowed_domains: None,
         downloads_path: None,
         proxy: None,
+        attach_workers: false,
+        stealth: false,
+        stealth_options: Default::default(),
+        disable_cache: false,
+        emulation: Default::default(),
     };
     
     let mut browser = Browser::new(profile);
@@ -77,6 +152,11 @@ async fn test_browser_basic_workflow() {
         allowed_domains: None,
         downloads_path: None,
         proxy: None,
+        attach_workers: false,
+        stealth: false,
+        stealth_options: Default::default(),
+        disable_cache: false,
+        emulation: Default::default(),
     };
     
     let mut browser = Browser::new(profile);
@@ -112,6 +192,7 @@ fn test_browser_state_summary_structure() {
         markdown: None,
         elements: vec![],
         selector_map: HashMap::new(),
+        truncated_values: vec![],
     };
 
     let summary = BrowserStateSummary {
@@ -135,3 +216,89 @@ fn test_browser_state_summary_structure() {
     assert_eq!(summary.title, "Example");
     assert!(!summary.is_pdf_viewer);
 }
+
+fn exported_session() -> browsing::browser::views::BrowserSession {
+    use browsing::browser::views::{BrowserSession, TabSnapshot};
+
+    BrowserSession {
+        cdp_ws_url: "ws://127.0.0.1:9222/devtools/page/ABCD".to_string(),
+        active_target_id: "ABCD".to_string(),
+        session_id: "session-1".to_string(),
+        cookies: vec![serde_json::json!({
+            "name": "sid",
+            "value": "abc123",
+            "domain": ".example.com",
+            "path": "/",
+            "httpOnly": true,
+            "secure": true
+        })],
+        tabs: vec![
+            TabSnapshot {
+                target_id: "ABCD".to_string(),
+                url: "https://example.com".to_string(),
+                title: "Example".to_string(),
+            },
+            TabSnapshot {
+                target_id: "EFGH".to_string(),
+                url: "https://example.org".to_string(),
+                title: "Other".to_string(),
+            },
+        ],
+    }
+}
+
+#[test]
+fn test_browser_session_json_roundtrip() {
+    use browsing::browser::views::BrowserSession;
+
+    let session = exported_session();
+    let json = session.to_json().unwrap();
+    assert!(json.contains("\"cdp_ws_url\":\"ws://127.0.0.1:9222/devtools/page/ABCD\""));
+
+    let restored = BrowserSession::from_json(&json).unwrap();
+    assert_eq!(restored, session);
+    assert_eq!(restored.cookies[0]["name"], "sid");
+    assert_eq!(restored.tabs.len(), 2);
+}
+
+#[test]
+fn test_browser_session_from_invalid_json() {
+    use browsing::browser::views::BrowserSession;
+
+    assert!(BrowserSession::from_json("{\"cdp_ws_url\": \"ws://x\"}").is_err());
+}
+
+#[test]
+fn test_tab_snapshot_from_tab_info() {
+    use browsing::browser::views::TabSnapshot;
+
+    let tab = TabInfo {
+        url: "https://example.com".to_string(),
+        title: "Example".to_string(),
+        target_id: "target-123".to_string(),
+        parent_target_id: Some("opener".to_string()),
+        ..Default::default()
+    };
+    let snapshot = TabSnapshot::from(tab);
+    assert_eq!(snapshot.target_id, "target-123");
+    assert_eq!(snapshot.url, "https://example.com");
+}
+
+#[test]
+fn test_attach_to_session_rejects_non_websocket_url() {
+    use browsing::browser::Browser;
+
+    let mut session = exported_session();
+    assert!(Browser::attach_to_session(session.clone()).is_ok());
+
+    session.cdp_ws_url = "http://127.0.0.1:9222".to_string();
+    assert!(Browser::attach_to_session(session).is_err());
+}
+
+#[test]
+fn test_from_remote_debugging_port() {
+    use browsing::browser::Browser;
+
+    assert!(Browser::from_remote_debugging_port(9222).is_ok());
+    assert!(Browser::from_remote_debugging_port(0).is_err());
+}
@@ -0,0 +1,288 @@
+//! Tests for controlling Chrome's HTTP cache
+
+mod common;
+
+use browsing::browser::{Browser, BrowserProfile};
+use browsing::tools::Tools;
+use common::{
+    FakePageBrowser, HttpResponse, Received, fake_cdp_with_events, fake_cdp_with_latency, methods,
+    serve_http,
+};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Browser connected to a fake endpoint with one page, target T1 in session
+/// S1; new tabs get target T2 in session S2
+async fn connected_browser(profile: BrowserProfile) -> (Browser, Received) {
+    let (url, received) = fake_cdp_with_latency(
+        Box::new(|method, call| match method {
+            "Target.getTargets" => Ok(json!({
+                "targetInfos": [{ "targetId": "T1", "type": "page", "url": "about:blank" }]
+            })),
+            "Target.createTarget" => Ok(json!({ "targetId": "T2" })),
+            "Target.attachToTarget" => Ok(json!({ "sessionId": format!("S{call}") })),
+            _ => Ok(json!({})),
+        }),
+        Duration::ZERO,
+    )
+    .await;
+    let mut browser = Browser::new(profile).with_cdp_url(url);
+    browser.start().await.unwrap();
+    (browser, received)
+}
+
+/// `cacheDisabled` of each `Network.setCacheDisabled`, with its session
+fn cache_settings(received: &Received) -> Vec<(Value, Option<String>)> {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, _, _)| method == "Network.setCacheDisabled")
+        .map(|(_, params, session)| (params["cacheDisabled"].clone(), session.clone()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_profile_disables_the_cache_in_every_tab() {
+    let profile = BrowserProfile::new().with_disable_cache(true);
+    let (mut browser, received) = connected_browser(profile).await;
+    assert!(browser.is_cache_disabled());
+
+    browser.create_new_tab(None).await.unwrap();
+
+    assert_eq!(
+        cache_settings(&received),
+        [
+            (json!(true), Some("S1".to_string())),
+            (json!(true), Some("S2".to_string()))
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_cache_is_used_by_default() {
+    let (mut browser, received) = connected_browser(BrowserProfile::default()).await;
+    browser.create_new_tab(None).await.unwrap();
+
+    assert!(!browser.is_cache_disabled());
+    assert!(cache_settings(&received).is_empty());
+}
+
+#[tokio::test]
+async fn test_set_cache_disabled_reaches_open_and_later_tabs() {
+    let (mut browser, received) = connected_browser(BrowserProfile::default()).await;
+
+    browser.set_cache_disabled(true).await.unwrap();
+    browser.create_new_tab(None).await.unwrap();
+    browser.set_cache_disabled(false).await.unwrap();
+
+    let mut settings = cache_settings(&received);
+    // Tabs other than the current one are updated in no particular order
+    settings[2..].sort_by_key(|(_, session)| session.clone());
+    let s1 = Some("S1".to_string());
+    let s2 = Some("S2".to_string());
+    assert_eq!(
+        settings,
+        [
+            (json!(true), s1.clone()),
+            (json!(true), s2.clone()),
+            (json!(false), s1),
+            (json!(false), s2)
+        ]
+    );
+    assert!(!browser.is_cache_disabled());
+}
+
+#[tokio::test]
+async fn test_clear_cache() {
+    let (browser, received) = connected_browser(BrowserProfile::default()).await;
+
+    browser.clear_cache().await.unwrap();
+
+    let cleared: Vec<_> = received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, _, _)| method == "Network.clearBrowserCache")
+        .map(|(_, _, session)| session.clone())
+        .collect();
+    assert_eq!(cleared, [Some("S1".to_string())]);
+}
+
+/// Main frame of the fake page, loaded by `loader_id`
+fn main_frame(loader_id: &str) -> Value {
+    json!({ "id": "F1", "loaderId": loader_id, "url": "https://example.com/" })
+}
+
+/// Answers for a page at https://example.com/ whose reload commits a new
+/// document, after a subframe navigates
+fn reloading_page(method: &str, _: usize) -> std::result::Result<Value, String> {
+    match method {
+        "Page.getFrameTree" => Ok(json!({ "frameTree": { "frame": main_frame("L1") } })),
+        "Runtime.evaluate" => Ok(json!({ "result": { "value": "complete" } })),
+        _ => Ok(json!({})),
+    }
+}
+
+/// Events of the reload in session `session_id`
+fn reload_events(session_id: &'static str) -> common::EventScript {
+    Box::new(move |method, _| {
+        if method != "Page.reload" {
+            return vec![];
+        }
+        let navigated = |frame: Value| {
+            json!({
+                "method": "Page.frameNavigated",
+                "sessionId": session_id,
+                "params": { "frame": frame }
+            })
+        };
+        vec![
+            navigated(
+                json!({ "id": "F2", "parentId": "F1", "loaderId": "L9", "url": "about:blank" }),
+            ),
+            navigated(main_frame("L2")),
+        ]
+    })
+}
+
+/// Run the navigate action with `params` on a fake page at https://example.com
+async fn navigate(params: Value) -> (String, Received) {
+    let (client, received) =
+        fake_cdp_with_events(Box::new(reloading_page), reload_events("S1")).await;
+    let mut browser = FakePageBrowser { client };
+    let action =
+        serde_json::from_value(json!({ "action_type": "navigate", "params": params })).unwrap();
+    let result = Tools::default()
+        .act(action, &mut browser, None)
+        .await
+        .unwrap();
+    (result.long_term_memory.unwrap_or_default(), received)
+}
+
+#[tokio::test]
+async fn test_bypass_cache_reloads_the_current_url() {
+    let (memory, received) =
+        navigate(json!({ "url": "https://example.com/", "bypass_cache": true })).await;
+
+    assert_eq!(memory, "Reloaded https://example.com/ bypassing the cache");
+    let reloads: Vec<_> = received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, _, _)| method == "Page.reload")
+        .map(|(_, params, session)| (params["ignoreCache"].clone(), session.clone()))
+        .collect();
+    assert_eq!(reloads, [(json!(true), Some("S1".to_string()))]);
+    assert!(!methods(&received).contains(&"Page.navigate".to_string()));
+}
+
+#[tokio::test]
+async fn test_bypass_cache_waits_for_the_new_document() {
+    let (_, received) = navigate(json!({
+        "url": "https://example.com/",
+        "bypass_cache": true,
+        "wait_until": "domcontentloaded"
+    }))
+    .await;
+
+    // The load state is read once the reload has committed
+    let methods = methods(&received);
+    let reload = methods.iter().position(|m| m == "Page.reload").unwrap();
+    let frame_tree = methods
+        .iter()
+        .position(|m| m == "Page.getFrameTree")
+        .unwrap();
+    assert!(frame_tree < reload, "{methods:?}");
+    assert!(
+        methods[reload..].contains(&"Runtime.evaluate".to_string()),
+        "{methods:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_reload_is_recorded_as_a_navigation() {
+    let (url, _) = common::fake_cdp_url_with_events(
+        Box::new(|method, call| match method {
+            "Target.getTargets" => Ok(json!({
+                "targetInfos": [{ "targetId": "T1", "type": "page", "url": "https://example.com/" }]
+            })),
+            "Target.attachToTarget" => Ok(json!({ "sessionId": format!("S{call}") })),
+            _ => reloading_page(method, call),
+        }),
+        reload_events("S1"),
+    )
+    .await;
+    let mut browser = Browser::new(BrowserProfile::default()).with_cdp_url(url);
+    browser.start().await.unwrap();
+
+    browser.reload(true, None).await.unwrap();
+
+    let record = browser.last_navigation().unwrap();
+    assert_eq!(record.target_id, "T1");
+    assert_eq!(record.final_url, "https://example.com/");
+}
+
+#[tokio::test]
+async fn test_bypass_cache_navigates_to_other_urls() {
+    let (memory, received) =
+        navigate(json!({ "url": "https://example.com/other", "bypass_cache": true })).await;
+
+    assert_eq!(memory, "Navigated to https://example.com/other");
+    assert!(!methods(&received).contains(&"Page.reload".to_string()));
+}
+
+#[tokio::test]
+async fn test_navigating_to_the_same_url_keeps_the_cache_by_default() {
+    let (_, received) = navigate(json!({ "url": "https://example.com/" })).await;
+
+    assert!(!methods(&received).contains(&"Page.reload".to_string()));
+}
+
+/// Serve a page with a cacheable stylesheet, counting stylesheet requests
+async fn serve_counting_fixture() -> (String, Arc<AtomicUsize>) {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&hits);
+    let url = serve_http(move |request| {
+        let response = if request.path() == "/style.css" {
+            counter.fetch_add(1, Ordering::SeqCst);
+            HttpResponse::ok("text/css", "body { color: teal; }")
+        } else {
+            HttpResponse::html(
+                "<html><head><link rel=\"stylesheet\" href=\"/style.css\"></head><body>Hi</body></html>",
+            )
+        };
+        response.with_header("Cache-Control", "max-age=3600")
+    })
+    .await;
+    (url, hits)
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_disabled_cache_refetches_resources() {
+    let (url, hits) = serve_counting_fixture().await;
+    let profile = BrowserProfile::new()
+        .with_headless(true)
+        .with_disable_cache(true);
+    let mut browser = Browser::new(profile);
+    browser.start().await.unwrap();
+
+    browser.navigate(&url).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    browser.navigate(&url).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+    // With the cache back on, the stylesheet comes from the cache
+    browser.set_cache_disabled(false).await.unwrap();
+    browser.navigate(&url).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    browser.navigate(&url).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+    browser.stop().await.unwrap();
+}
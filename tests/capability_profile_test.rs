@@ -0,0 +1,153 @@
+//! Tests for capability profiles and the tools config
+
+mod common;
+
+use browsing::config::{ConfigBuilder, ToolsConfig};
+use browsing::tools::{CapabilityProfile, EvaluatePolicy, Tools};
+use common::{FakePageBrowser, fake_cdp, methods};
+use serde_json::{Value, json};
+
+const RESTRICTED: [&str; 4] = ["evaluate", "upload_file", "form_autofill", "submit_form"];
+
+fn registered(tools: &Tools) -> Vec<&str> {
+    RESTRICTED
+        .into_iter()
+        .filter(|action| tools.registry.registry.actions.contains_key(*action))
+        .collect()
+}
+
+async fn act(tools: &Tools, action_type: &str, params: Value) -> browsing::error::Result<()> {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let mut browser = FakePageBrowser { client };
+    let action =
+        serde_json::from_value(json!({ "action_type": action_type, "params": params })).unwrap();
+    tools.act(action, &mut browser, None).await.map(|_| ())
+}
+
+#[test]
+fn test_profiles_register_the_expected_actions() {
+    let safe = Tools::from_config(&ToolsConfig::from_profile(CapabilityProfile::Safe));
+    let standard = Tools::from_config(&ToolsConfig::from_profile(CapabilityProfile::Standard));
+    let full = Tools::from_config(&ToolsConfig::from_profile(CapabilityProfile::Full));
+
+    assert!(registered(&safe).is_empty());
+    assert_eq!(safe.evaluate_policy, EvaluatePolicy::Disabled);
+    assert!(!safe.allow_file_writes);
+
+    assert_eq!(
+        registered(&standard),
+        ["evaluate", "form_autofill", "submit_form"]
+    );
+    assert_eq!(standard.evaluate_policy, EvaluatePolicy::ReadOnly);
+    assert!(standard.allow_file_writes);
+
+    assert_eq!(registered(&full), RESTRICTED);
+    assert_eq!(full.evaluate_policy, EvaluatePolicy::Full);
+    assert!(full.allow_file_writes);
+
+    // Reading and navigating work everywhere
+    for tools in [&safe, &standard, &full] {
+        for action in [
+            "navigate",
+            "click",
+            "scroll",
+            "extract",
+            "extract_images",
+            "done",
+        ] {
+            assert!(
+                tools.registry.registry.actions.contains_key(action),
+                "{action}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_excluded_actions_are_left_out_of_the_prompt() {
+    let safe = Tools::from_config(&ToolsConfig::from_profile(CapabilityProfile::Safe));
+    let prompt = safe.registry.registry.get_prompt_description(None);
+
+    assert!(prompt.contains("navigate"));
+    for action in RESTRICTED {
+        assert!(
+            !prompt.contains(&format!("{action}:")),
+            "{action} in {prompt}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_safe_profile_refuses_restricted_actions() {
+    let safe = Tools::from_config(&ToolsConfig::from_profile(CapabilityProfile::Safe));
+
+    for action in RESTRICTED {
+        let error = act(&safe, action, json!({})).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("Tool error: Action disabled by configuration: {action}")
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_safe_profile_refuses_image_downloads() {
+    let (client, received) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let mut browser = FakePageBrowser { client };
+    let safe = Tools::from_config(&ToolsConfig::from_profile(CapabilityProfile::Safe));
+    let action = serde_json::from_value(json!({
+        "action_type": "extract_images",
+        "params": { "download": true }
+    }))
+    .unwrap();
+
+    let error = safe.act(action, &mut browser, None).await.unwrap_err();
+
+    assert!(error.to_string().contains("Saving files is disabled"));
+    assert!(methods(&received).is_empty());
+}
+
+#[test]
+fn test_custom_lists_adjust_the_profile() {
+    let config = ToolsConfig {
+        profile: CapabilityProfile::Safe,
+        allow_actions: vec!["submit_form".to_string()],
+        exclude_actions: vec!["search".to_string(), "evaluate".to_string()],
+        allow_file_writes: Some(true),
+        ..Default::default()
+    };
+    let tools = Tools::from_config(&config);
+
+    assert_eq!(registered(&tools), ["submit_form"]);
+    assert!(!tools.registry.registry.actions.contains_key("search"));
+    assert_eq!(
+        config.excluded_actions(),
+        ["evaluate", "upload_file", "form_autofill", "search"]
+    );
+    assert!(tools.allow_file_writes);
+    assert_eq!(tools.evaluate_policy, EvaluatePolicy::Disabled);
+}
+
+#[test]
+fn test_profile_from_config_layers() {
+    let config = ConfigBuilder::new()
+        .from_vars([("BROWSING_TOOLS_PROFILE", "standard")])
+        .from_args(&[
+            "--exclude-actions".to_string(),
+            "navigate, search".to_string(),
+        ])
+        .build()
+        .unwrap();
+
+    assert_eq!(config.tools.profile, CapabilityProfile::Standard);
+    assert_eq!(config.tools.exclude_actions, ["navigate", "search"]);
+    assert_eq!(
+        config.tools.excluded_actions(),
+        ["upload_file", "navigate", "search"]
+    );
+
+    let invalid = ConfigBuilder::new()
+        .from_vars([("BROWSING_TOOLS_PROFILE", "admin")])
+        .build();
+    assert!(invalid.is_err());
+}
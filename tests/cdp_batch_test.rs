@@ -0,0 +1,126 @@
+//! Tests for pipelined CDP commands against a scripted endpoint with latency
+
+mod common;
+
+use browsing::browser::cdp::CdpClient;
+use common::{fake_cdp_with_latency, methods};
+use serde_json::json;
+use std::time::{Duration, Instant};
+
+const LATENCY: Duration = Duration::from_millis(20);
+
+async fn client(url: String) -> CdpClient {
+    let mut client = CdpClient::new(url);
+    client.start().await.unwrap();
+    client
+}
+
+fn echo() -> common::Responder {
+    Box::new(|method, call| Ok(json!({ "method": method, "call": call })))
+}
+
+#[tokio::test]
+async fn test_batch_costs_one_round_trip() {
+    let (url, received) = fake_cdp_with_latency(echo(), LATENCY).await;
+    let client = client(url).await;
+    let commands = [
+        "DOM.enable",
+        "DOM.getDocument",
+        "Accessibility.getFullAXTree",
+        "Page.getLayoutMetrics",
+    ];
+
+    let started = Instant::now();
+    for method in commands {
+        client
+            .send_command_with_session(method, json!({}), Some("S1"))
+            .await
+            .unwrap();
+    }
+    let sequential = started.elapsed();
+
+    let started = Instant::now();
+    let results = client
+        .send_batch(
+            commands
+                .iter()
+                .map(|method| (*method, json!({}), Some("S1")))
+                .collect(),
+        )
+        .await;
+    let batched = started.elapsed();
+
+    assert!(sequential >= LATENCY * 4, "{sequential:?}");
+    assert!(
+        batched < sequential / 2,
+        "batch took {batched:?}, sequential {sequential:?}"
+    );
+    // Results come back in command order, matched by request ID
+    let answered: Vec<_> = results
+        .into_iter()
+        .map(|result| result.unwrap()["method"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(answered, commands);
+    assert_eq!(methods(&received).len(), 8);
+}
+
+#[tokio::test]
+async fn test_batch_keeps_errors_per_command() {
+    let (url, _) = fake_cdp_with_latency(
+        Box::new(|method, _| match method {
+            "DOM.getBoxModel" => Err("Could not compute box model.".to_string()),
+            _ => Ok(json!({ "ok": method })),
+        }),
+        LATENCY,
+    )
+    .await;
+    let client = client(url).await;
+
+    let results = client
+        .send_batch(vec![
+            (
+                "DOM.getContentQuads",
+                json!({ "backendNodeId": 1 }),
+                Some("S1"),
+            ),
+            ("DOM.getBoxModel", json!({ "backendNodeId": 1 }), Some("S1")),
+            ("Page.getLayoutMetrics", json!({}), Some("S1")),
+        ])
+        .await;
+
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().unwrap()["ok"], "DOM.getContentQuads");
+    let error = results[1].as_ref().unwrap_err().to_string();
+    assert!(error.contains("Could not compute box model."), "{error}");
+    assert_eq!(results[2].as_ref().unwrap()["ok"], "Page.getLayoutMetrics");
+}
+
+#[tokio::test]
+async fn test_batch_times_out_per_command() {
+    let (url, _) = fake_cdp_with_latency(echo(), Duration::from_millis(300)).await;
+    let client = client(url)
+        .await
+        .with_command_timeout(Duration::from_millis(50));
+
+    let started = Instant::now();
+    let results = client
+        .send_batch(vec![
+            ("DOM.enable", json!({}), None),
+            ("DOM.getDocument", json!({}), None),
+        ])
+        .await;
+
+    assert!(started.elapsed() < Duration::from_millis(300));
+    for result in &results {
+        let error = result.as_ref().unwrap_err().to_string();
+        assert!(error.contains("No response within 50ms"), "{error}");
+    }
+}
+
+#[tokio::test]
+async fn test_empty_batch() {
+    let (url, received) = fake_cdp_with_latency(echo(), LATENCY).await;
+    let client = client(url).await;
+    assert!(client.send_batch(vec![]).await.is_empty());
+    assert!(methods(&received).is_empty());
+}
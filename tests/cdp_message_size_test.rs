@@ -0,0 +1,118 @@
+//! Tests for CDP messages over the size limit
+
+mod common;
+
+use browsing::browser::cdp::CdpClient;
+use common::fake_cdp_url_with_events;
+use serde_json::{Value, json};
+
+const LIMIT: usize = 4096;
+
+/// Client with a [`LIMIT`] byte message limit, sending a 10 000 character console
+/// message and a large network event on `Runtime.enable`, and answering
+/// `DOM.getDocument` with a large document
+async fn client() -> CdpClient {
+    let big = "x".repeat(10_000);
+    let document = json!({ "root": { "nodeName": big.clone() } });
+    let (url, _) = fake_cdp_url_with_events(
+        Box::new(move |method, _| match method {
+            "DOM.getDocument" => Ok(document.clone()),
+            _ => Ok(json!({})),
+        }),
+        Box::new(move |method, _| match method {
+            "Runtime.enable" => vec![
+                json!({ "method": "Network.dataReceived", "params": { "data": big.clone() } }),
+                json!({
+                    "method": "Runtime.consoleAPICalled",
+                    "params": { "type": "log", "args": [{ "type": "string", "value": big.clone() }] }
+                }),
+            ],
+            _ => vec![],
+        }),
+    )
+    .await;
+    let mut client = CdpClient::new(url).with_max_message_size(LIMIT);
+    client.start().await.unwrap();
+    client
+}
+
+#[tokio::test]
+async fn test_oversized_console_event_is_truncated() {
+    let client = client().await;
+    let mut events = client.subscribe_events();
+
+    client
+        .send_command("Runtime.enable", json!({}))
+        .await
+        .unwrap();
+
+    // The network event is dropped, so the console message is the first event
+    let event: Value = events.recv().await.unwrap();
+    assert_eq!(event["method"], "Runtime.consoleAPICalled");
+    let value = event["params"]["args"][0]["value"].as_str().unwrap();
+    assert!(value.len() < LIMIT, "{}", value.len());
+    assert!(value.ends_with("...(truncated 9488 bytes)"), "{value}");
+    assert!(events.try_recv().is_err());
+
+    // The connection is still up
+    assert!(client.is_connected());
+    client.send_command("Page.enable", json!({})).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_oversized_response_fails_only_its_command() {
+    let client = client().await;
+
+    let error = client
+        .send_command("DOM.getDocument", json!({}))
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("over the 4096 byte limit"),
+        "{error}"
+    );
+
+    assert!(client.is_connected());
+    client.send_command("Page.enable", json!({})).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_stats_record_incoming_sizes_per_method() {
+    let client = client().await;
+    client
+        .send_command("Runtime.enable", json!({}))
+        .await
+        .unwrap();
+    let _ = client.send_command("DOM.getDocument", json!({})).await;
+
+    let stats = client.stats();
+    let incoming = &stats.incoming_by_method;
+    assert_eq!(incoming["Runtime.enable"].count, 1);
+    assert_eq!(incoming["Runtime.enable"].oversized, 0);
+    for method in [
+        "DOM.getDocument",
+        "Network.dataReceived",
+        "Runtime.consoleAPICalled",
+    ] {
+        assert_eq!(incoming[method].count, 1, "{method}");
+        assert_eq!(incoming[method].oversized, 1, "{method}");
+        assert!(incoming[method].max_bytes > 10_000, "{method}");
+        assert_eq!(incoming[method].histogram[1], 1, "{method}");
+    }
+}
+
+#[tokio::test]
+async fn test_frames_under_the_frame_limit_do_not_drop_the_connection() {
+    let (url, _) = fake_cdp_url_with_events(
+        Box::new(|_, _| Ok(json!({ "data": "x".repeat(20 << 20) }))),
+        Box::new(|_, _| vec![]),
+    )
+    .await;
+    let mut client = CdpClient::new(url);
+    client.start().await.unwrap();
+
+    // Over tungstenite's 16 MiB default, under the default limits
+    let result = client.send_command("IO.read", json!({})).await.unwrap();
+    assert_eq!(result["data"].as_str().unwrap().len(), 20 << 20);
+    assert!(client.is_connected());
+}
@@ -0,0 +1,176 @@
+//! Tests for reconnecting the CDP client after the WebSocket drops
+
+use browsing::browser::cdp::CdpClient;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Commands received, as (connection number, method, sessionId)
+type Received = Arc<Mutex<Vec<(usize, String, Option<String>)>>>;
+
+/// Endpoint that drops the first connection when it receives `Test.crash`
+///
+/// Later connections answer every command with the connection number, and
+/// send a `Page.loadEventFired` event before answering `Test.crash`. If
+/// `accept_again` is false, nothing is accepted after the first connection.
+async fn dropping_cdp(accept_again: bool) -> (String, Received) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let received: Received = Arc::new(Mutex::new(vec![]));
+
+    let log = Arc::clone(&received);
+    tokio::spawn(async move {
+        let mut connection = 0;
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            connection += 1;
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            while let Some(Ok(Message::Text(text))) = ws.next().await {
+                let request: Value = serde_json::from_str(&text).unwrap();
+                let method = request["method"].as_str().unwrap().to_string();
+                log.lock().unwrap().push((
+                    connection,
+                    method.clone(),
+                    request["sessionId"].as_str().map(str::to_string),
+                ));
+                if method == "Test.crash" {
+                    if connection == 1 {
+                        // Gone without answering, as if Chrome crashed
+                        break;
+                    }
+                    let event = json!({ "method": "Page.loadEventFired", "params": {} });
+                    ws.send(Message::Text(event.to_string())).await.unwrap();
+                }
+                let response =
+                    json!({ "id": request["id"], "result": { "connection": connection } });
+                ws.send(Message::Text(response.to_string())).await.unwrap();
+            }
+            drop(ws);
+            if !accept_again {
+                return;
+            }
+        }
+    });
+
+    (url, received)
+}
+
+async fn client(url: String, reconnect: bool) -> CdpClient {
+    let mut client = CdpClient::new(url).with_reconnect_on_disconnect(reconnect);
+    client.start().await.unwrap();
+    client
+}
+
+#[tokio::test]
+async fn test_disconnect_fails_commands() {
+    let (url, _) = dropping_cdp(true).await;
+    let client = client(url, false).await;
+
+    let started = Instant::now();
+    let error = client
+        .send_command("Test.crash", json!({}))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("CDP disconnected"), "{error}");
+
+    // Later commands fail right away instead of waiting for a response
+    let error = client
+        .send_command("Page.getLayoutMetrics", json!({}))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("CDP disconnected"), "{error}");
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert!(!client.is_connected());
+}
+
+#[tokio::test]
+async fn test_reconnect_resumes_pending_command() {
+    let (url, received) = dropping_cdp(true).await;
+    let client = client(url, true).await;
+    let mut events = client.subscribe_events();
+    client
+        .send_command_with_session("Page.enable", json!({}), Some("S1"))
+        .await
+        .unwrap();
+    client
+        .send_command_with_session("Network.enable", json!({}), Some("S1"))
+        .await
+        .unwrap();
+    client
+        .send_command_with_session("Network.disable", json!({}), Some("S1"))
+        .await
+        .unwrap();
+
+    let result = client.send_command("Test.crash", json!({})).await.unwrap();
+
+    assert_eq!(result["connection"], 2);
+    assert!(client.is_connected());
+    // Event subscriptions are replayed before the command that was in flight
+    let on_second: Vec<(String, Option<String>)> = received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(connection, _, _)| *connection == 2)
+        .map(|(_, method, session)| (method.clone(), session.clone()))
+        .collect();
+    assert_eq!(
+        on_second,
+        [
+            ("Page.enable".to_string(), Some("S1".to_string())),
+            ("Test.crash".to_string(), None)
+        ]
+    );
+    // Existing subscribers receive events from the new connection
+    let event = tokio::time::timeout(Duration::from_secs(1), events.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(event["method"], "Page.loadEventFired");
+}
+
+#[tokio::test]
+async fn test_explicit_reconnect() {
+    let (url, _) = dropping_cdp(true).await;
+    let client = client(url, false).await;
+    assert!(client.send_command("Test.crash", json!({})).await.is_err());
+
+    client.reconnect(1_000).await.unwrap();
+
+    let result = client
+        .send_command("Page.getLayoutMetrics", json!({}))
+        .await
+        .unwrap();
+    assert_eq!(result["connection"], 2);
+    // Reconnecting an open connection does nothing
+    client.reconnect(1_000).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_reconnect_times_out() {
+    let (url, _) = dropping_cdp(false).await;
+    let client = client(url, false).await;
+    assert!(client.send_command("Test.crash", json!({})).await.is_err());
+
+    let started = Instant::now();
+    let error = client.reconnect(300).await.unwrap_err();
+
+    assert!(error.to_string().contains("CDP disconnected"), "{error}");
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+#[tokio::test]
+async fn test_close_is_not_reconnected() {
+    let (url, received) = dropping_cdp(true).await;
+    let client = client(url, true).await;
+    client.send_command("Page.enable", json!({})).await.unwrap();
+
+    client.close().await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    assert!(client.send_command("Page.enable", json!({})).await.is_err());
+    assert!(client.reconnect(300).await.is_err());
+    assert_eq!(received.lock().unwrap().len(), 1);
+}
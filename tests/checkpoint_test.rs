@@ -0,0 +1,169 @@
+//! Tests for page state checkpoints
+
+mod common;
+
+use browsing::actor::{CheckpointId, Page, PageCheckpoint};
+use browsing::browser::{Browser, BrowserProfile};
+use browsing::tools::Tools;
+use browsing::tools::views::ActionModel;
+use common::{FakePageBrowser, HttpResponse, fake_cdp, methods, serve_http};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+fn cart_cookie() -> serde_json::Value {
+    json!({ "name": "session", "value": "abc", "domain": "localhost", "path": "/" })
+}
+
+#[tokio::test]
+async fn test_capture_checkpoint() {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Runtime.evaluate" => Ok(json!({ "result": { "value": {
+            "url": "http://localhost:8080/cart",
+            "origin": "http://localhost:8080",
+            "local_storage": { "cart": "2 items" },
+            "session_storage": { "step": "cart" },
+            "scroll_x": 0,
+            "scroll_y": 640
+        } } })),
+        "Network.getAllCookies" => Ok(json!({ "cookies": [cart_cookie()] })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    let checkpoint = page.capture_checkpoint().await.unwrap();
+
+    assert_eq!(checkpoint.url, "http://localhost:8080/cart");
+    assert_eq!(checkpoint.local_storage["cart"], "2 items");
+    assert_eq!(checkpoint.session_storage["step"], "cart");
+    assert_eq!(checkpoint.scroll_y, 640.0);
+    assert_eq!(checkpoint.cookies, [cart_cookie()]);
+    assert!(
+        received
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|(_, _, session)| session.as_deref() == Some("S1"))
+    );
+}
+
+#[tokio::test]
+async fn test_restore_checkpoint_seeds_storage_before_navigating() {
+    let (client, received) = fake_cdp(Box::new(|method, call| match (method, call) {
+        ("Page.addScriptToEvaluateOnNewDocument", _) => Ok(json!({ "identifier": "7" })),
+        ("Runtime.evaluate", 1) => Ok(json!({ "result": { "value": "complete" } })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+    let checkpoint = PageCheckpoint {
+        url: "http://localhost:8080/cart".into(),
+        origin: "http://localhost:8080".into(),
+        cookies: vec![cart_cookie()],
+        local_storage: BTreeMap::from([("cart".into(), "2 items".into())]),
+        session_storage: BTreeMap::new(),
+        scroll_x: 0.0,
+        scroll_y: 640.0,
+    };
+
+    page.restore_checkpoint(&checkpoint).await.unwrap();
+
+    assert_eq!(
+        methods(&received),
+        [
+            "Network.clearBrowserCookies",
+            "Network.setCookies",
+            "Page.addScriptToEvaluateOnNewDocument",
+            "Page.navigate",
+            "Runtime.evaluate",
+            "Page.removeScriptToEvaluateOnNewDocument",
+            "Runtime.evaluate",
+        ]
+    );
+    let received = received.lock().unwrap();
+    assert_eq!(received[1].1["cookies"], json!([cart_cookie()]));
+    let seed = received[2].1["source"].as_str().unwrap();
+    assert!(seed.contains(r#"{"cart":"2 items"}"#), "{seed}");
+    assert_eq!(received[3].1["url"], "http://localhost:8080/cart");
+    assert_eq!(received[5].1["identifier"], "7");
+    assert_eq!(received[6].1["expression"], "window.scrollTo(0, 640)");
+}
+
+#[tokio::test]
+async fn test_checkpoint_actions_need_browser_support() {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let mut browser = FakePageBrowser { client };
+    let tools = Tools::new(vec![]);
+    let action: ActionModel = serde_json::from_value(json!({
+        "action_type": "restore_checkpoint",
+        "params": { "checkpoint_id": 1 }
+    }))
+    .unwrap();
+
+    let error = tools.act(action, &mut browser, None).await.unwrap_err();
+    assert!(error.to_string().contains("not supported"), "{error}");
+}
+
+/// Serve the cart and checkout fixture pages on localhost
+async fn serve_fixture() -> String {
+    serve_http(|request| {
+        HttpResponse::html(if request.path().starts_with("/checkout") {
+            include_str!("fixtures/checkpoint/checkout.html")
+        } else {
+            include_str!("fixtures/checkpoint/cart.html")
+        })
+    })
+    .await
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_restore_returns_to_cart_after_checkout() {
+    let base = serve_fixture().await;
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.unwrap();
+    browser.navigate(&base).await.unwrap();
+    let page = browser.get_page().unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    page.evaluate("localStorage.setItem('cart', '2 items'); sessionStorage.setItem('step', 'cart'); window.scrollTo(0, 400)")
+        .await
+        .unwrap();
+
+    let id = browser.checkpoint().await.unwrap();
+    assert_eq!(id, CheckpointId(1));
+
+    browser.navigate(&format!("{base}checkout")).await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    assert_eq!(
+        page.evaluate("localStorage.getItem('cart')").await.unwrap(),
+        "ordered"
+    );
+
+    browser.restore(id).await.unwrap();
+
+    assert_eq!(browser.get_current_url().await.unwrap(), base);
+    // The page script saw the restored storage when it ran
+    assert_eq!(
+        page.evaluate("document.getElementById('items').textContent")
+            .await
+            .unwrap(),
+        "2 items"
+    );
+    assert_eq!(
+        page.evaluate("sessionStorage.getItem('step')")
+            .await
+            .unwrap(),
+        "cart"
+    );
+    assert!(
+        !page
+            .evaluate("document.cookie")
+            .await
+            .unwrap()
+            .contains("order=placed")
+    );
+    assert_eq!(page.evaluate("window.scrollY").await.unwrap(), "400");
+    assert!(browser.restore(CheckpointId(2)).await.is_err());
+
+    browser.stop().await.unwrap();
+}
@@ -0,0 +1,186 @@
+//! Tests for retrying clicks that change nothing
+
+mod common;
+
+use browsing::agent::views::ActionResult;
+use browsing::browser::{Browser, BrowserProfile};
+use browsing::config::ToolsConfig;
+use browsing::tools::Tools;
+use browsing::tools::handlers::CLICK_STRATEGY_METADATA_KEY;
+use common::{EventScript, FakePageBrowser, Received, fake_cdp_with_events, serve_html};
+use serde_json::{Value, json};
+use std::time::Duration;
+
+const OVERLAY: &str = include_str!("fixtures/click_fallback/overlay.html");
+
+/// Fingerprint of a page after `mutations` DOM mutations
+fn fingerprint(mutations: u64) -> Value {
+    json!({ "result": { "value": {
+        "url": "https://shop.example/", "focused": null, "bodyChildren": 2,
+        "scrollX": 0, "scrollY": 0, "mutations": mutations
+    } } })
+}
+
+/// Click element 7 on a page whose fingerprints have `mutations`, in call order
+async fn click(tools: Tools, mutations: &'static [u64]) -> (ActionResult, Received) {
+    click_with_events(tools, mutations, Box::new(|_, _| vec![])).await
+}
+
+/// [`click`], with the endpoint also sending `events`
+async fn click_with_events(
+    tools: Tools,
+    mutations: &'static [u64],
+    events: EventScript,
+) -> (ActionResult, Received) {
+    let (client, received) = fake_cdp_with_events(
+        Box::new(move |method, call| match method {
+            "Runtime.evaluate" => Ok(fingerprint(mutations[(call - 1).min(mutations.len() - 1)])),
+            "Page.getLayoutMetrics" => {
+                Ok(json!({ "layoutViewport": { "clientWidth": 1280, "clientHeight": 720 } }))
+            }
+            "DOM.getContentQuads" => Ok(json!({ "quads": [[10, 10, 90, 10, 90, 30, 10, 30]] })),
+            "DOM.resolveNode" => Ok(json!({ "object": { "objectId": "button-1" } })),
+            _ => Ok(json!({})),
+        }),
+        events,
+    )
+    .await;
+    let mut browser = FakePageBrowser { client };
+    let action =
+        serde_json::from_value(json!({ "action_type": "click", "params": { "index": 7 } }))
+            .unwrap();
+    let result = tools.act(action, &mut browser, None).await.unwrap();
+    (result, received)
+}
+
+fn js_clicks(received: &Received) -> usize {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, params, _)| {
+            method == "Runtime.callFunctionOn"
+                && params["functionDeclaration"]
+                    .as_str()
+                    .is_some_and(|f| f.contains("this.click()"))
+        })
+        .count()
+}
+
+fn strategy(result: &ActionResult) -> Option<&Value> {
+    result.metadata.as_ref()?.get(CLICK_STRATEGY_METADATA_KEY)
+}
+
+#[tokio::test]
+async fn test_click_that_changes_the_page_is_not_retried() {
+    let (result, received) = click(Tools::default(), &[0, 1]).await;
+
+    assert_eq!(strategy(&result), Some(&json!("mouse")));
+    assert_eq!(js_clicks(&received), 0);
+}
+
+#[tokio::test]
+async fn test_intercepted_click_is_retried_with_js_click() {
+    // The mouse click changes nothing; the JS click adds two nodes
+    let (result, received) = click(Tools::default(), &[0, 0, 2]).await;
+
+    assert!(result.error.is_none(), "{:?}", result.error);
+    assert_eq!(strategy(&result), Some(&json!("js_click")));
+    assert_eq!(js_clicks(&received), 1);
+    let memory = result.long_term_memory.unwrap();
+    assert!(
+        memory.ends_with("retried as a JS click, which did"),
+        "{memory}"
+    );
+    // The JS click targets the same node as the mouse click
+    let received = received.lock().unwrap();
+    let (_, resolved, _) = received
+        .iter()
+        .find(|(method, _, _)| method == "DOM.resolveNode")
+        .unwrap();
+    assert_eq!(resolved["backendNodeId"], 7);
+}
+
+#[tokio::test]
+async fn test_reports_when_no_strategy_changes_the_page() {
+    let (result, received) = click(Tools::default(), &[0]).await;
+
+    assert_eq!(strategy(&result), Some(&json!("none")));
+    assert_eq!(js_clicks(&received), 1);
+    assert!(
+        result
+            .long_term_memory
+            .unwrap()
+            .contains("neither the click nor a JS click changed the page")
+    );
+}
+
+#[tokio::test]
+async fn test_requests_started_by_the_click_are_waited_on() {
+    // The click starts a request; the page only changes once it finished
+    let events: EventScript = Box::new(|method, params| {
+        let network = |event: &str| json!({ "method": event, "params": { "requestId": "R1" }, "sessionId": "S1" });
+        match method {
+            "Input.dispatchMouseEvent" if params["type"] == "mouseReleased" => {
+                vec![network("Network.requestWillBeSent")]
+            }
+            "Runtime.evaluate" => vec![network("Network.loadingFinished")],
+            _ => vec![],
+        }
+    });
+    let (result, received) = click_with_events(Tools::default(), &[0, 0, 1], events).await;
+
+    assert_eq!(strategy(&result), Some(&json!("mouse")));
+    assert_eq!(js_clicks(&received), 0);
+}
+
+#[tokio::test]
+async fn test_fallback_opt_out() {
+    let tools = Tools::from_config(&ToolsConfig {
+        click_fallback: Some(false),
+        ..Default::default()
+    });
+    let (result, received) = click(tools, &[0]).await;
+
+    assert_eq!(strategy(&result), None);
+    assert_eq!(js_clicks(&received), 0);
+    assert!(
+        received
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|(method, _, _)| method != "Runtime.evaluate")
+    );
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_button_under_invisible_overlay() {
+    let url = serve_html(OVERLAY).await;
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.unwrap();
+    browser.navigate(&url).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let page = browser.get_page().unwrap();
+    let button = page.get_elements_by_css_selector("#buy").await.unwrap();
+    let index = button[0].backend_node_id();
+    let action = serde_json::from_value(json!({
+        "action_type": "click",
+        "params": { "index": index }
+    }))
+    .unwrap();
+
+    let result = Tools::default()
+        .act(action, &mut browser, None)
+        .await
+        .unwrap();
+
+    assert_eq!(strategy(&result), Some(&json!("js_click")), "{result:?}");
+    let added = page
+        .evaluate("document.getElementById('added')?.textContent ?? ''")
+        .await
+        .unwrap();
+    assert_eq!(added, "Added to cart");
+
+    browser.stop().await.unwrap();
+}
@@ -0,0 +1,297 @@
+//! Tests for following redirects that pages make by themselves after loading
+
+mod common;
+
+use browsing::actor::{LoadState, NavigateOptions, Page};
+use browsing::browser::{Browser, BrowserProfile, MetaRefresh};
+use common::{fake_cdp_url_with_events, fake_cdp_with_events, methods};
+use regex::Regex;
+use serde_json::{Value, json};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const META_REFRESH: &str = include_str!("fixtures/navigation/meta_refresh.html");
+
+/// `content` of the fixture's meta refresh
+fn refresh_content() -> String {
+    Regex::new(r#"http-equiv="refresh" content="([^"]+)""#)
+        .unwrap()
+        .captures(META_REFRESH)
+        .unwrap()[1]
+        .to_string()
+}
+
+fn evaluated(value: Value) -> Value {
+    json!({ "result": { "type": "object", "value": value } })
+}
+
+/// Result of the pending redirect check on the page at `url`
+fn check(url: &str, refresh: Option<&str>, script: bool) -> Value {
+    evaluated(json!({ "url": url, "refresh": refresh, "script": script }))
+}
+
+fn frame_navigated(url: &str) -> Value {
+    json!({
+        "method": "Page.frameNavigated",
+        "sessionId": "S1",
+        "params": { "frame": { "id": "F1", "loaderId": "L2", "url": url } }
+    })
+}
+
+/// Navigate to `https://shop.example/` waiting for DOMContentLoaded, with
+/// `checks` answering the pending redirect checks in order and `navigated`
+/// sent when the page domain is enabled
+async fn navigate(
+    checks: Vec<Value>,
+    navigated: Option<&'static str>,
+    options: NavigateOptions,
+) -> (browsing::browser::NavigationRecord, common::Received) {
+    let (client, received) = fake_cdp_with_events(
+        Box::new(move |method, call| match method {
+            "Page.navigate" => Ok(json!({ "frameId": "F1", "loaderId": "L1" })),
+            // The ready state and the checks alternate
+            "Runtime.evaluate" if call % 2 == 1 => Ok(evaluated(json!("complete"))),
+            "Runtime.evaluate" => Ok(checks[(call / 2 - 1).min(checks.len() - 1)].clone()),
+            _ => Ok(json!({})),
+        }),
+        Box::new(move |method, _| match (method, navigated) {
+            ("Page.enable", Some(url)) => vec![frame_navigated(url)],
+            _ => vec![],
+        }),
+    )
+    .await;
+    let page = Page::new(client, "S1".to_string());
+    let record = page
+        .goto_tracked(
+            "https://shop.example/",
+            &options.with_wait_until(LoadState::DomContentLoaded),
+        )
+        .await
+        .unwrap();
+    (record, received)
+}
+
+#[test]
+fn test_meta_refresh_parsing() {
+    assert_eq!(
+        MetaRefresh::parse(&refresh_content()),
+        Some(MetaRefresh {
+            delay_ms: 0,
+            url: "https://shop.example/en/".to_string()
+        })
+    );
+    assert_eq!(
+        MetaRefresh::parse("2.5;url = /login"),
+        Some(MetaRefresh {
+            delay_ms: 2500,
+            url: "/login".to_string()
+        })
+    );
+    assert_eq!(
+        MetaRefresh::parse("5, https://example.com/").map(|r| r.url),
+        Some("https://example.com/".to_string())
+    );
+    // A plain reload is not a redirect
+    assert_eq!(MetaRefresh::parse("30"), None);
+    assert_eq!(MetaRefresh::parse("soon; url=/x"), None);
+}
+
+#[tokio::test]
+async fn test_meta_refresh_is_followed() {
+    let content = refresh_content();
+    let (record, received) = navigate(
+        vec![
+            check("https://shop.example/", Some(&content), false),
+            check("https://shop.example/en/", None, false),
+        ],
+        Some("https://shop.example/en/"),
+        NavigateOptions::new(),
+    )
+    .await;
+
+    assert_eq!(record.final_url, "https://shop.example/en/");
+    assert_eq!(record.client_redirects, ["https://shop.example/"]);
+    assert_eq!(
+        record.summary().as_deref(),
+        Some(
+            "Requested https://shop.example/, landed on https://shop.example/en/ after 1 redirect \
+             (1 by the page itself after loading)"
+        )
+    );
+    // The new page was waited for and checked in turn
+    let methods = methods(&received);
+    assert_eq!(
+        methods.iter().filter(|m| *m == "Runtime.evaluate").count(),
+        4
+    );
+    assert_eq!(methods.iter().filter(|m| *m == "Page.enable").count(), 1);
+}
+
+#[tokio::test]
+async fn test_redirect_before_the_check_is_recorded() {
+    // The refresh fired while the load state was awaited
+    let (record, received) = navigate(
+        vec![
+            check("https://shop.example/en/", None, false),
+            check("https://shop.example/en/", None, false),
+        ],
+        None,
+        NavigateOptions::new(),
+    )
+    .await;
+
+    assert_eq!(record.final_url, "https://shop.example/en/");
+    assert_eq!(record.client_redirects, ["https://shop.example/"]);
+    assert!(!methods(&received).contains(&"Page.enable".to_string()));
+}
+
+#[tokio::test]
+async fn test_pages_without_pending_redirect_are_not_held() {
+    let started = Instant::now();
+    let (record, received) = navigate(
+        vec![check("https://shop.example/", Some("60"), false)],
+        None,
+        NavigateOptions::new(),
+    )
+    .await;
+    assert!(started.elapsed() < Duration::from_millis(500));
+    assert!(!record.was_redirected());
+    assert!(!methods(&received).contains(&"Page.enable".to_string()));
+
+    // A refresh due after the grace period is not waited for either
+    let (record, received) = navigate(
+        vec![check(
+            "https://shop.example/",
+            Some("10; url=https://shop.example/en/"),
+            false,
+        )],
+        None,
+        NavigateOptions::new(),
+    )
+    .await;
+    assert!(!record.was_redirected());
+    assert!(!methods(&received).contains(&"Page.enable".to_string()));
+}
+
+#[tokio::test]
+async fn test_script_that_does_not_redirect_waits_out_the_grace_period() {
+    let started = Instant::now();
+    let (record, _) = navigate(
+        vec![check("https://shop.example/", None, true)],
+        None,
+        NavigateOptions::new().with_redirect_grace(100),
+    )
+    .await;
+    assert!(started.elapsed() >= Duration::from_millis(100));
+    assert!(!record.was_redirected());
+}
+
+#[tokio::test]
+async fn test_zero_grace_period_skips_the_check() {
+    let (record, received) = navigate(
+        vec![check(
+            "https://shop.example/",
+            Some(&refresh_content()),
+            false,
+        )],
+        Some("https://shop.example/en/"),
+        NavigateOptions::new().with_redirect_grace(0),
+    )
+    .await;
+    assert!(!record.was_redirected());
+    let evaluations = methods(&received)
+        .into_iter()
+        .filter(|m| m == "Runtime.evaluate")
+        .count();
+    assert_eq!(evaluations, 1);
+}
+
+#[tokio::test]
+async fn test_page_reached_without_waiting_is_followed() {
+    // The page a click led to refreshes itself to another
+    let content = refresh_content();
+    let (client, received) = fake_cdp_with_events(
+        Box::new(move |method, call| match (method, call) {
+            ("Runtime.evaluate", 1) => Ok(check("https://shop.example/", Some(&content), false)),
+            ("Runtime.evaluate", 2) => Ok(evaluated(json!("complete"))),
+            ("Runtime.evaluate", _) => Ok(check("https://shop.example/en/", None, false)),
+            _ => Ok(json!({})),
+        }),
+        Box::new(|method, _| match method {
+            "Page.enable" => vec![frame_navigated("https://shop.example/en/")],
+            _ => vec![],
+        }),
+    )
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    let record = page.follow_client_redirects(1_000).await.unwrap();
+
+    assert_eq!(record.requested_url, "https://shop.example/");
+    assert_eq!(record.final_url, "https://shop.example/en/");
+    assert_eq!(record.client_redirects, ["https://shop.example/"]);
+    assert_eq!(
+        methods(&received)
+            .iter()
+            .filter(|m| *m == "Runtime.evaluate")
+            .count(),
+        3
+    );
+}
+
+#[tokio::test]
+async fn test_redirect_after_a_navigation_without_waiting_extends_it() {
+    let navigated = Arc::new(AtomicBool::new(false));
+    let evaluations = Arc::new(AtomicUsize::new(0));
+    let redirected_at = Arc::new(Mutex::new(None));
+    let content = refresh_content();
+    let (url, _) = fake_cdp_url_with_events(
+        Box::new({
+            let navigated = navigated.clone();
+            let redirected_at = redirected_at.clone();
+            move |method, _| match method {
+                "Target.getTargets" => Ok(json!({
+                    "targetInfos": [{ "targetId": "T1", "type": "page", "url": "about:blank" }]
+                })),
+                "Target.attachToTarget" => Ok(json!({ "sessionId": "S1" })),
+                "Page.navigate" => {
+                    navigated.store(true, Ordering::SeqCst);
+                    Ok(json!({ "frameId": "F1", "loaderId": "L1" }))
+                }
+                "Page.enable" if navigated.load(Ordering::SeqCst) => {
+                    *redirected_at.lock().unwrap() = Some(evaluations.load(Ordering::SeqCst));
+                    Ok(json!({}))
+                }
+                "Runtime.evaluate" => {
+                    let n = evaluations.fetch_add(1, Ordering::SeqCst) + 1;
+                    Ok(match *redirected_at.lock().unwrap() {
+                        None => check("https://shop.example/", Some(&content), false),
+                        Some(at) if n == at + 1 => evaluated(json!("complete")),
+                        Some(_) => check("https://shop.example/en/", None, false),
+                    })
+                }
+                _ => Ok(json!({})),
+            }
+        }),
+        Box::new(move |method, _| match method {
+            "Page.enable" if navigated.load(Ordering::SeqCst) => {
+                vec![frame_navigated("https://shop.example/en/")]
+            }
+            _ => vec![],
+        }),
+    )
+    .await;
+    let mut browser = Browser::new(BrowserProfile::new()).with_cdp_url(url);
+    browser.start().await.unwrap();
+
+    browser.navigate("https://shop.example/").await.unwrap();
+    assert!(!browser.last_navigation().unwrap().was_redirected());
+    browser.follow_client_redirects(1_000).await.unwrap();
+
+    assert_eq!(browser.navigation_history().len(), 1);
+    let record = browser.last_navigation().unwrap();
+    assert_eq!(record.requested_url, "https://shop.example/");
+    assert_eq!(record.final_url, "https://shop.example/en/");
+    assert_eq!(record.client_redirects, ["https://shop.example/"]);
+}
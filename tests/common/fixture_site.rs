@@ -0,0 +1,64 @@
+//! A site of fixture pages served from memory, for crawl tests
+//!
+//! Shared by the crawl-based integration tests and the MCP server's sitemap
+//! tests, which include this file by path.
+
+use async_trait::async_trait;
+use browsing::browser::{PageLink, PageVisitor, VisitedPage};
+use browsing::error::Result;
+use url::Url;
+
+/// Visits the pages of a fixture site without a browser
+pub struct FixtureSite {
+    /// HTML of the page at a URL path; `None` for a page that fails to load
+    pages: fn(&str) -> Option<&'static str>,
+    /// URLs visited, in order
+    pub visits: Vec<String>,
+}
+
+impl FixtureSite {
+    /// Serve the pages `pages` returns for each path
+    pub fn new(pages: fn(&str) -> Option<&'static str>) -> Self {
+        Self {
+            pages,
+            visits: Vec::new(),
+        }
+    }
+}
+
+/// Links of `html` with their text, resolved against `base`
+pub fn links(html: &str, base: &Url) -> Vec<PageLink> {
+    html.split("<a href=\"")
+        .skip(1)
+        .filter_map(|rest| {
+            let (href, rest) = rest.split_once("\">")?;
+            let (text, _) = rest.split_once("</a>")?;
+            Some(PageLink {
+                url: base.join(href).ok()?.to_string(),
+                text: text.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[async_trait]
+impl PageVisitor for FixtureSite {
+    async fn visit(&mut self, url: &str) -> Result<Option<VisitedPage>> {
+        self.visits.push(url.to_string());
+        let parsed = Url::parse(url).unwrap();
+        let Some(html) = (self.pages)(parsed.path()) else {
+            return Ok(None);
+        };
+        let title = html
+            .split("<title>")
+            .nth(1)
+            .and_then(|rest| rest.split("</title>").next())
+            .unwrap_or_default();
+        Ok(Some(VisitedPage {
+            url: url.to_string(),
+            title: title.to_string(),
+            content_preview: String::new(),
+            links: links(html, &parsed),
+        }))
+    }
+}
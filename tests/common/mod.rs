@@ -0,0 +1,363 @@
+//! Shared helpers for integration tests
+
+#![allow(dead_code)]
+
+use browsing::actor::Page;
+use browsing::browser::cdp::CdpClient;
+use browsing::browser::views::TabInfo;
+use browsing::error::Result;
+use browsing::traits::BrowserClient;
+use futures_util::{SinkExt, StreamExt};
+use regex::Regex;
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+pub mod fixture_site;
+
+pub type Responder = Box<dyn Fn(&str, usize) -> std::result::Result<Value, String> + Send>;
+
+/// Commands received by the fake endpoint, as (method, params, sessionId)
+pub type Received = Arc<Mutex<Vec<(String, Value, Option<String>)>>>;
+
+/// Events to send before answering a command, from its (method, params)
+pub type EventScript = Box<dyn Fn(&str, &Value) -> Vec<Value> + Send>;
+
+/// Serve CDP over a local WebSocket, answering each command with `respond(method, call_number)`
+pub async fn fake_cdp(respond: Responder) -> (Arc<CdpClient>, Received) {
+    fake_cdp_with_events(respond, Box::new(|_, _| vec![])).await
+}
+
+/// Like [`fake_cdp`], also sending `events(method, params)` before each response
+pub async fn fake_cdp_with_events(
+    respond: Responder,
+    events: EventScript,
+) -> (Arc<CdpClient>, Received) {
+    let (url, received) = serve_fake_cdp(respond, events, Duration::ZERO).await;
+    let mut client = CdpClient::new(url);
+    client.start().await.unwrap();
+    (Arc::new(client), received)
+}
+
+/// Serve CDP like [`fake_cdp`], answering each command `latency` after it arrives
+///
+/// Returns the WebSocket URL so tests can configure their own [`CdpClient`].
+/// Commands are read as they arrive, so pipelined commands overlap their latency.
+pub async fn fake_cdp_with_latency(respond: Responder, latency: Duration) -> (String, Received) {
+    serve_fake_cdp(respond, Box::new(|_, _| vec![]), latency).await
+}
+
+/// Serve CDP like [`fake_cdp_with_events`], returning the WebSocket URL
+///
+/// For tests that configure their own [`CdpClient`].
+pub async fn fake_cdp_url_with_events(
+    respond: Responder,
+    events: EventScript,
+) -> (String, Received) {
+    serve_fake_cdp(respond, events, Duration::ZERO).await
+}
+
+async fn serve_fake_cdp(
+    respond: Responder,
+    events: EventScript,
+    latency: Duration,
+) -> (String, Received) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let received: Received = Arc::new(Mutex::new(vec![]));
+
+    let log = Arc::clone(&received);
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        stream.set_nodelay(true).unwrap();
+        let (mut sink, mut ws) = tokio_tungstenite::accept_async(stream)
+            .await
+            .unwrap()
+            .split();
+        let (outgoing, mut queued) = mpsc::unbounded_channel::<Message>();
+        tokio::spawn(async move {
+            while let Some(message) = queued.recv().await {
+                if sink.send(message).await.is_err() {
+                    break;
+                }
+            }
+        });
+        while let Some(Ok(Message::Text(text))) = ws.next().await {
+            let request: Value = serde_json::from_str(&text).unwrap();
+            let method = request["method"].as_str().unwrap().to_string();
+            let call = {
+                let mut log = log.lock().unwrap();
+                log.push((
+                    method.clone(),
+                    request["params"].clone(),
+                    request["sessionId"].as_str().map(str::to_string),
+                ));
+                log.iter().filter(|(m, _, _)| *m == method).count()
+            };
+            for event in events(&method, &request["params"]) {
+                let _ = outgoing.send(Message::Text(event.to_string()));
+            }
+            let response = match respond(&method, call) {
+                Ok(result) => json!({ "id": request["id"], "result": result }),
+                Err(message) => {
+                    json!({ "id": request["id"], "error": { "code": -32000, "message": message } })
+                }
+            };
+            let response = Message::Text(response.to_string());
+            if latency.is_zero() {
+                let _ = outgoing.send(response);
+            } else {
+                let outgoing = outgoing.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(latency).await;
+                    let _ = outgoing.send(response);
+                });
+            }
+        }
+    });
+
+    (url, received)
+}
+
+/// Decode the HTML entities used in fixture pages
+pub fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+/// Elements without a closing tag
+const VOID_ELEMENTS: &[&str] = &["br", "hr", "img", "input", "link", "meta"];
+
+/// `DOM.getDocument` result for a well-formed fixture page
+///
+/// Attributes must be written as `name="value"`.
+pub fn document_from_html(html: &str) -> Value {
+    let tag = Regex::new(r#"<(/?)([a-z0-9]+)((?:\s+[a-z-]+="[^"]*")*)\s*>"#).unwrap();
+    let attribute = Regex::new(r#"([a-z-]+)="([^"]*)""#).unwrap();
+    let mut next_id = 1;
+    let mut new_node = |node_type: u32, name: &str| {
+        next_id += 1;
+        json!({ "nodeId": next_id, "backendNodeId": next_id, "nodeType": node_type, "nodeName": name })
+    };
+    let append = |stack: &mut Vec<Value>, node: Value| {
+        stack.last_mut().unwrap()["children"]
+            .as_array_mut()
+            .unwrap()
+            .push(node);
+    };
+
+    let mut stack = vec![json!({
+        "nodeId": 1, "backendNodeId": 1, "nodeType": 9, "nodeName": "#document", "children": []
+    })];
+    let mut last = 0;
+    for caps in tag.captures_iter(html) {
+        let whole = caps.get(0).unwrap();
+        let text = html[last..whole.start()].trim();
+        if !text.is_empty() {
+            let mut node = new_node(3, "#text");
+            node["nodeValue"] = json!(decode_entities(text));
+            append(&mut stack, node);
+        }
+        last = whole.end();
+
+        if &caps[1] == "/" {
+            let node = stack.pop().unwrap();
+            append(&mut stack, node);
+        } else {
+            let mut node = new_node(1, &caps[2].to_uppercase());
+            node["localName"] = json!(&caps[2]);
+            node["attributes"] = attribute
+                .captures_iter(&caps[3])
+                .flat_map(|a| [a[1].to_string(), decode_entities(&a[2])])
+                .collect();
+            node["children"] = json!([]);
+            if VOID_ELEMENTS.contains(&&caps[2]) {
+                append(&mut stack, node);
+            } else {
+                stack.push(node);
+            }
+        }
+    }
+    json!({ "root": stack.pop().unwrap() })
+}
+
+/// Methods received by the fake endpoint, in order
+pub fn methods(received: &Received) -> Vec<String> {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(method, _, _)| method.clone())
+        .collect()
+}
+
+/// Browser whose page is backed by the fake endpoint
+pub struct FakePageBrowser {
+    pub client: Arc<CdpClient>,
+}
+
+#[async_trait::async_trait]
+impl BrowserClient for FakePageBrowser {
+    async fn start(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn navigate(&mut self, _url: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_current_url(&self) -> Result<String> {
+        Ok("https://example.com".to_string())
+    }
+
+    async fn create_tab(&mut self, _url: Option<&str>) -> Result<String> {
+        Ok("T1".to_string())
+    }
+
+    async fn switch_to_tab(&mut self, _target_id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn close_tab(&mut self, _target_id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_tabs(&self) -> Result<Vec<TabInfo>> {
+        Ok(vec![])
+    }
+
+    async fn get_target_id_from_tab_id(&self, tab_id: &str) -> Result<String> {
+        Ok(tab_id.to_string())
+    }
+
+    fn get_page(&self) -> Result<Page> {
+        Ok(Page::new(Arc::clone(&self.client), "S1".to_string()))
+    }
+
+    async fn take_screenshot(&self, _path: Option<&str>, _full_page: bool) -> Result<Vec<u8>> {
+        Ok(vec![])
+    }
+
+    async fn get_current_page_title(&self) -> Result<String> {
+        Ok("Example".to_string())
+    }
+
+    fn get_cdp_client(&self) -> Result<Arc<CdpClient>> {
+        Ok(Arc::clone(&self.client))
+    }
+
+    fn get_session_id(&self) -> Result<String> {
+        Ok("S1".to_string())
+    }
+
+    fn get_current_target_id(&self) -> Result<String> {
+        Ok("T1".to_string())
+    }
+}
+
+/// A request received by [`serve_http`]
+pub struct HttpRequest {
+    /// Request line and headers
+    pub head: String,
+}
+
+impl HttpRequest {
+    /// Path and query of the request line
+    pub fn path(&self) -> &str {
+        self.head.split_whitespace().nth(1).unwrap_or("/")
+    }
+}
+
+/// A response for [`serve_http`] to send
+pub struct HttpResponse {
+    status: &'static str,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// `200 OK` with a `body` of `content_type`
+    pub fn ok(content_type: &str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: "200 OK",
+            headers: vec![("Content-Type", content_type.to_string())],
+            body: body.into(),
+        }
+    }
+
+    /// `200 OK` with an HTML `body`
+    pub fn html(body: impl Into<Vec<u8>>) -> Self {
+        Self::ok("text/html; charset=utf-8", body)
+    }
+
+    /// `302 Found` to `location`
+    pub fn redirect(location: &str) -> Self {
+        Self {
+            status: "302 Found",
+            headers: vec![("Location", location.to_string())],
+            body: vec![],
+        }
+    }
+
+    pub fn with_status(mut self, status: &'static str) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn with_header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+}
+
+/// Serve HTTP on localhost, answering each request with `respond`
+///
+/// Returns the base URL, `http://localhost:<port>/`. Each connection
+/// carries one request.
+pub async fn serve_http(respond: impl Fn(&HttpRequest) -> HttpResponse + Send + 'static) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!(
+        "http://localhost:{}/",
+        listener.local_addr().unwrap().port()
+    );
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            // Read the whole head, so closing the connection does not reset it
+            let mut buf = [0u8; 8192];
+            let mut n = 0;
+            while n < buf.len() && !buf[..n].windows(4).any(|w| w == b"\r\n\r\n") {
+                match stream.read(&mut buf[n..]).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) => n += read,
+                }
+            }
+            let request = HttpRequest {
+                head: String::from_utf8_lossy(&buf[..n]).to_string(),
+            };
+            let response = respond(&request);
+            let mut head = format!("HTTP/1.1 {}\r\n", response.status);
+            for (name, value) in &response.headers {
+                head.push_str(&format!("{name}: {value}\r\n"));
+            }
+            head.push_str(&format!(
+                "Content-Length: {}\r\nConnection: close\r\n\r\n",
+                response.body.len()
+            ));
+            let _ = stream.write_all(head.as_bytes()).await;
+            let _ = stream.write_all(&response.body).await;
+        }
+    });
+    url
+}
+
+/// Serve `html` at every path on localhost, returning the base URL
+pub async fn serve_html(html: &'static str) -> String {
+    serve_http(move |_| HttpResponse::html(html)).await
+}
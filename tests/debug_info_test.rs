@@ -0,0 +1,151 @@
+//! Tests for keeping action diagnostics out of prompts
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::agent::memory::AgentMemory;
+use browsing::agent::service::Agent;
+use browsing::agent::views::{ActionResult, AgentHistoryList};
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use common::{FakePageBrowser, fake_cdp};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+
+/// Model that types into element 5, waits, then finishes, recording the
+/// messages it was sent
+#[derive(Clone, Default)]
+struct RecordingLLM {
+    messages: Arc<Mutex<Vec<Vec<ChatMessage>>>>,
+}
+
+#[async_trait]
+impl ChatModel for RecordingLLM {
+    fn model(&self) -> &str {
+        "recording"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        let mut recorded = self.messages.lock().unwrap();
+        recorded.push(messages.to_vec());
+        let action = match recorded.len() {
+            1 => json!({ "action_type": "input", "params": { "index": 5, "text": "Berlin" } }),
+            2 => json!({ "action_type": "wait", "params": { "seconds": 0 } }),
+            _ => json!({ "action_type": "done", "params": { "text": "Done" } }),
+        };
+        Ok(ChatInvokeCompletion::new(
+            json!({ "next_goal": "Continue", "action": [action] }).to_string(),
+        ))
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+/// Run the agent with [`RecordingLLM`], returning its history and the messages of each call
+async fn run() -> (AgentHistoryList, Vec<Vec<ChatMessage>>) {
+    let (client, _) = fake_cdp(Box::new(|method, _| match method {
+        "DOM.resolveNode" => Ok(json!({ "object": { "objectId": "input-5" } })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let llm = RecordingLLM::default();
+    let history = Agent::new(
+        "Enter the city".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        llm.clone(),
+    )
+    .with_max_steps(4)
+    .run()
+    .await
+    .unwrap();
+    let messages = llm.messages.lock().unwrap().clone();
+    (history, messages)
+}
+
+#[tokio::test]
+async fn test_debug_info_is_kept_out_of_prompts() {
+    let (history, messages) = run().await;
+
+    // The history has the diagnostics
+    let input = &history.history[0].result[0];
+    assert!(input.error.is_none(), "{:?}", input.error);
+    assert_eq!(
+        input.long_term_memory.as_deref(),
+        Some("Input text into element 5")
+    );
+    assert_eq!(input.debug_info.as_deref(), Some("backend_node_id: 5"));
+
+    // The prompts do not
+    assert_eq!(messages.len(), 3);
+    for prompt in messages.iter().flatten() {
+        assert!(
+            !prompt.content.contains("backend_node_id"),
+            "{}",
+            prompt.content
+        );
+    }
+    let state = &messages[2][1].content;
+    let (_, recent) = state.split_once("Recent results:\n").unwrap();
+    assert!(recent.contains("Input text into element 5"), "{state}");
+}
+
+#[tokio::test]
+async fn test_results_left_out_of_memory_are_still_recent_results() {
+    let (_, messages) = run().await;
+
+    let state = &messages[2][1].content;
+    let (memory, recent) = state.split_once("Recent results:\n").unwrap();
+    assert!(!memory.contains("Waited for 0 seconds"), "{state}");
+    assert!(recent.contains("Waited for 0 seconds"), "{state}");
+}
+
+#[test]
+fn test_memory_skips_results_not_included_in_memory() {
+    let mut memory = AgentMemory::new();
+    let result = |text: &str, include_in_memory: bool| {
+        ActionResult {
+            long_term_memory: Some(text.to_string()),
+            include_in_memory,
+            ..Default::default()
+        }
+        .with_debug_info(format!("diagnostics for {text}"))
+    };
+
+    memory.record_results(&[result("Clicked element 3", true), result("Waited", false)]);
+
+    assert_eq!(memory.short_term, ["Clicked element 3"]);
+    assert!(!memory.prompt_section().contains("diagnostics"));
+}
+
+#[test]
+fn test_results_without_the_new_fields_deserialize() {
+    let result: ActionResult = serde_json::from_value(json!({
+        "is_done": false,
+        "success": true,
+        "judgement": null,
+        "error": null,
+        "attachments": null,
+        "images": null,
+        "long_term_memory": "Navigated to https://example.com",
+        "extracted_content": null,
+        "include_extracted_content_only_once": false,
+        "metadata": null
+    }))
+    .unwrap();
+
+    assert!(result.include_in_memory);
+    assert!(result.debug_info.is_none());
+    let serialized: Value = serde_json::to_value(&result).unwrap();
+    assert!(serialized.get("debug_info").is_none());
+}
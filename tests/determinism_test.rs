@@ -0,0 +1,246 @@
+//! Tests for deterministic runs
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::agent::Determinism;
+use browsing::agent::determinism::apply_determinism;
+use browsing::agent::service::Agent;
+use browsing::agent::views::AgentSettings;
+use browsing::browser::{Browser, BrowserProfile};
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use browsing::traits::BrowserClient;
+use common::{FakePageBrowser, Received, document_from_html, fake_cdp, fake_cdp_with_latency};
+use serde_json::{Value, json};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+const TWO_FORMS: &str = include_str!("fixtures/forms/two_forms.html");
+
+/// Model that follows a fixed script: remember a fact, scroll, then finish
+#[derive(Clone, Default)]
+struct ScriptedLLM {
+    calls: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl ChatModel for ScriptedLLM {
+    fn model(&self) -> &str {
+        "scripted"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, _messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        let action = match self.calls.fetch_add(1, Ordering::SeqCst) {
+            0 => {
+                json!({ "action_type": "remember", "params": { "key": "shop", "value": "two forms" } })
+            }
+            1 => json!({ "action_type": "scroll", "params": { "down": true } }),
+            _ => json!({ "action_type": "done", "params": { "text": "ok" } }),
+        };
+        Ok(ChatInvokeCompletion::new(
+            json!({ "next_goal": "Follow the script", "action": [action] }).to_string(),
+        ))
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+/// Run the script over the fixture page, returning the saved history and
+/// the commands sent
+async fn scripted_run(determinism: Option<Determinism>) -> (Value, Received) {
+    let document = document_from_html(TWO_FORMS);
+    let (client, received) = fake_cdp(Box::new(move |method, _| match method {
+        "DOM.getDocument" => Ok(document.clone()),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let settings = AgentSettings {
+        determinism,
+        ..Default::default()
+    };
+    let history = Agent::new(
+        "Look around the shop".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        ScriptedLLM::default(),
+    )
+    .with_settings(settings)
+    .with_max_steps(5)
+    .run()
+    .await
+    .unwrap();
+    (serde_json::to_value(&history).unwrap(), received)
+}
+
+/// `history` without the run ID and step timings
+fn without_timestamps(mut history: Value) -> Value {
+    history.as_object_mut().unwrap().remove("run_id");
+    for item in history["history"].as_array_mut().unwrap() {
+        let metadata = item["metadata"].as_object_mut().unwrap();
+        metadata.remove("step_start_time");
+        metadata.remove("step_end_time");
+    }
+    history
+}
+
+fn sent(received: &Received, method: &str) -> Vec<Value> {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(m, _, _)| m == method)
+        .map(|(_, params, _)| params.clone())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_seeded_runs_produce_identical_histories() {
+    let (first, _) = scripted_run(Some(Determinism::new(7))).await;
+    let (second, received) = scripted_run(Some(Determinism::new(7))).await;
+
+    assert_eq!(first["history"].as_array().unwrap().len(), 3);
+    assert_eq!(first["seed"], json!(7));
+    assert_eq!(without_timestamps(first), without_timestamps(second));
+
+    let scripts = sent(&received, "Page.addScriptToEvaluateOnNewDocument");
+    assert_eq!(scripts.len(), 1);
+    let source = scripts[0]["source"].as_str().unwrap();
+    assert_eq!(source, Determinism::new(7).init_script());
+    assert_eq!(
+        sent(&received, "Emulation.setEmulatedMedia"),
+        [json!({ "features": [{ "name": "prefers-reduced-motion", "value": "reduce" }] })]
+    );
+}
+
+#[tokio::test]
+async fn test_runs_are_not_seeded_by_default() {
+    let (history, received) = scripted_run(None).await;
+
+    assert_eq!(history.get("seed"), None);
+    assert!(sent(&received, "Page.addScriptToEvaluateOnNewDocument").is_empty());
+    assert!(sent(&received, "Emulation.setEmulatedMedia").is_empty());
+}
+
+#[tokio::test]
+async fn test_every_tab_gets_the_scripts_of_the_first() {
+    let (url, received) = fake_cdp_with_latency(
+        Box::new(|method, call| match method {
+            "Target.getTargets" => Ok(json!({
+                "targetInfos": [
+                    { "targetId": "T1", "type": "page", "url": "about:blank" },
+                    { "targetId": "T2", "type": "page", "url": "about:blank" }
+                ]
+            })),
+            "Target.createTarget" => Ok(json!({ "targetId": "T2" })),
+            "Target.attachToTarget" => Ok(json!({ "sessionId": format!("S{call}") })),
+            _ => Ok(json!({})),
+        }),
+        Duration::ZERO,
+    )
+    .await;
+    let mut browser = Browser::new(BrowserProfile::new().with_stealth(true)).with_cdp_url(url);
+    browser.start().await.unwrap();
+    browser.set_determinism(Determinism::new(7)).await.unwrap();
+
+    let target_id = browser.create_new_tab(None).await.unwrap();
+    browser.switch_to_tab(&target_id).await.unwrap();
+
+    let scripts: Vec<(String, bool)> = received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, _, _)| method == "Page.addScriptToEvaluateOnNewDocument")
+        .map(|(_, params, session)| {
+            let seeded = params["source"] == Determinism::new(7).init_script();
+            (session.clone().unwrap(), seeded)
+        })
+        .collect();
+    // The first tab, the new tab, and the new tab's session after switching
+    // each get both the stealth and the determinism script
+    for seeded in [false, true] {
+        let sessions: BTreeSet<_> = scripts
+            .iter()
+            .filter(|(_, s)| *s == seeded)
+            .map(|(session, _)| session)
+            .collect();
+        assert_eq!(sessions.len(), 3, "{scripts:?}");
+    }
+}
+
+#[tokio::test]
+async fn test_apply_without_animation_changes() {
+    let (client, received) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let determinism = Determinism {
+        disable_animations: false,
+        ..Determinism::new(1)
+    };
+
+    apply_determinism(&client, "S1", &determinism)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        common::methods(&received),
+        ["Page.addScriptToEvaluateOnNewDocument", "Runtime.evaluate"]
+    );
+}
+
+/// Random numbers, time and motion preference seen by a fresh browser
+async fn page_behavior(determinism: &Determinism) -> String {
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.unwrap();
+    let session = browser.get_session_info().await.unwrap();
+    apply_determinism(
+        &browser.get_cdp_client().unwrap(),
+        &session.session_id,
+        determinism,
+    )
+    .await
+    .unwrap();
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/forms/two_forms.html");
+    browser
+        .navigate(&format!("file://{}", fixture.display()))
+        .await
+        .unwrap();
+    let behavior = browser
+        .get_page()
+        .unwrap()
+        .evaluate(
+            "JSON.stringify([Math.random(), Math.random(), Date.now(), new Date().getFullYear(), \
+             matchMedia('(prefers-reduced-motion: reduce)').matches])",
+        )
+        .await
+        .unwrap();
+    browser.stop().await.unwrap();
+    behavior
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_seeded_pages_behave_the_same() {
+    let determinism = Determinism::new(7);
+
+    let first = page_behavior(&determinism).await;
+    let second = page_behavior(&determinism).await;
+
+    assert_eq!(first, second);
+    let values: Vec<Value> = serde_json::from_str(&first).unwrap();
+    assert_eq!(values[2], json!(determinism.frozen_time_ms));
+    assert_eq!(values[3], json!(2024));
+    assert_eq!(values[4], json!(true));
+    assert_ne!(first, page_behavior(&Determinism::new(8)).await);
+}
@@ -0,0 +1,114 @@
+//! Tests for the structured JSON elements list of the page state
+
+mod common;
+
+use browsing::dom::{DOMProcessorImpl, SerializedDOMState, SerializedElement};
+use browsing::traits::DOMProcessor;
+use common::{document_from_html, fake_cdp};
+use serde_json::json;
+
+const TWO_FORMS: &str = include_str!("fixtures/forms/two_forms.html");
+
+async fn serialize_fixture() -> SerializedDOMState {
+    let (client, _) = fake_cdp(Box::new(|method, _| match method {
+        "DOM.getDocument" => Ok(document_from_html(TWO_FORMS)),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    DOMProcessorImpl::new()
+        .with_cdp_client(client, "S1".to_string())
+        .with_target_id("T1".to_string())
+        .get_serialized_dom()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_elements_share_indices_with_text_state() {
+    let state = serialize_fixture().await;
+    let text = state.page_state();
+
+    let indices: Vec<u32> = state.elements.iter().map(|e| e.index).collect();
+    let mut expected: Vec<u32> = state.selector_map.keys().copied().collect();
+    expected.sort();
+    assert_eq!(indices, expected);
+    assert!(!indices.is_empty());
+
+    for element in &state.elements {
+        assert!(text.contains(&format!("[{}]", element.index)));
+        let interacted = &state.selector_map[&element.index];
+        assert_eq!(element.tag, interacted.tag);
+        assert_eq!(element.form_id, interacted.form_id);
+        assert!(element.is_visible);
+    }
+}
+
+#[tokio::test]
+async fn test_elements_describe_controls() {
+    let state = serialize_fixture().await;
+    let find = |tag: &str, text: &str| {
+        state
+            .elements
+            .iter()
+            .find(|e| e.tag == tag && e.text.as_deref() == Some(text))
+            .unwrap_or_else(|| panic!("no {tag} with text {text}: {:?}", state.elements))
+    };
+
+    let sign_in = find("button", "Sign in");
+    assert_eq!(sign_in.form_id, Some(2));
+    let email = find("input", "Email");
+    assert_eq!(email.form_id, Some(2));
+    assert_eq!(find("button", "Search").form_id, Some(1));
+
+    let help = find("a", "Need help?");
+    assert_eq!(help.href.as_deref(), Some("/help"));
+    assert_eq!(help.form_id, None);
+}
+
+#[test]
+fn test_element_serde() {
+    let element = SerializedElement {
+        index: 3,
+        tag: "a".to_string(),
+        role: Some("link".to_string()),
+        name: None,
+        text: Some("Need help?".to_string()),
+        rect: Some(browsing::dom::DOMRect::new(10.0, 20.0, 80.0, 16.0)),
+        href: Some("/help".to_string()),
+        form_id: None,
+        is_visible: true,
+        is_scrollable: false,
+    };
+
+    let value = serde_json::to_value(&element).unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "index": 3,
+            "tag": "a",
+            "role": "link",
+            "text": "Need help?",
+            "rect": { "x": 10.0, "y": 20.0, "width": 80.0, "height": 16.0 },
+            "href": "/help",
+            "is_visible": true
+        })
+    );
+    let parsed: SerializedElement = serde_json::from_value(value).unwrap();
+    assert_eq!(parsed, element);
+
+    let schema = serde_json::to_value(schemars::schema_for!(SerializedElement)).unwrap();
+    let properties = schema["properties"].as_object().unwrap();
+    for field in [
+        "index",
+        "tag",
+        "role",
+        "name",
+        "text",
+        "rect",
+        "href",
+        "form_id",
+        "is_visible",
+    ] {
+        assert!(properties.contains_key(field), "{field}");
+    }
+}
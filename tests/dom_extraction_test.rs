@@ -1,5 +1,7 @@
 //! DOM extraction and serialization tests
 
+mod common;
+
 use browsing::dom::serializer::SimplifiedNode;
 use browsing::dom::service::DomService;
 use browsing::dom::views::{
@@ -175,6 +177,7 @@ fn test_dom_state_serialization() {
         markdown: Some("# Test".to_string()),
         elements: vec![],
         selector_map: HashMap::new(),
+        truncated_values: vec![],
     };
     
     // Should be serializable to JSON
@@ -273,4 +276,90 @@ fn test_dom_node_with_children() {
     let children = parent_node.children_nodes.as_ref().unwrap();
     assert_eq!(children.len(), 1);
     assert_eq!(children[0].node_name, "SPAN");
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_truncated_values_are_reported_on_the_state() {
+    use browsing::dom::DOMProcessorImpl;
+    use browsing::traits::DOMProcessor;
+
+    let long = "x".repeat(200);
+    let html = format!(r#"<html><body><a href="/{long}">Docs</a><p>{long}</p></body></html>"#);
+    let (client, _) = common::fake_cdp(Box::new(move |method, _| match method {
+        "DOM.getDocument" => Ok(common::document_from_html(&html)),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let processor = DOMProcessorImpl::new()
+        .with_cdp_client(client, "S1".to_string())
+        .with_target_id("T1".to_string())
+        .with_max_value_len(100);
+
+    let state = processor.get_serialized_dom().await.unwrap();
+    assert_eq!(state.truncated_values.len(), 2, "{:?}", state.truncated_values);
+
+    let (state, _, _) = processor.get_serialized_dom_tree(Some("T1")).await.unwrap();
+    assert_eq!(state.truncated_values.len(), 2);
+}
+
+#[tokio::test]
+async fn test_elements_below_the_layout_viewport_are_hidden() {
+    use browsing::dom::DOMProcessorImpl;
+    use browsing::traits::DOMProcessor;
+    use serde_json::Value;
+
+    fn backend_id(node: &Value, id: &str) -> Option<u64> {
+        if node["attributes"][1] == id {
+            return node["backendNodeId"].as_u64();
+        }
+        node["children"].as_array()?.iter().find_map(|child| backend_id(child, id))
+    }
+
+    let document = common::document_from_html(
+        r#"<html><body><button id="top">Top</button><button id="footer">Footer</button></body></html>"#,
+    );
+    let top = backend_id(&document["root"], "top").unwrap();
+    let footer = backend_id(&document["root"], "footer").unwrap();
+    let snapshot = json!({
+        "strings": [],
+        "documents": [{
+            "nodes": { "backendNodeId": [top, footer] },
+            "layout": {
+                "nodeIndex": [0, 1],
+                "bounds": [[10, 10, 80, 24], [10, 3000, 80, 24]],
+                "styles": [[], []]
+            }
+        }]
+    });
+    let labels = |scroll_y: f64| {
+        let (document, snapshot) = (document.clone(), snapshot.clone());
+        async move {
+            let (client, _) = common::fake_cdp(Box::new(move |method, _| match method {
+                "DOM.getDocument" => Ok(document.clone()),
+                "DOMSnapshot.captureSnapshot" => Ok(snapshot.clone()),
+                "Page.getLayoutMetrics" => Ok(json!({
+                    "cssLayoutViewport": {
+                        "pageX": 0, "pageY": scroll_y, "clientWidth": 1280, "clientHeight": 720
+                    }
+                })),
+                _ => Ok(json!({})),
+            }))
+            .await;
+            let state = DOMProcessorImpl::new()
+                .with_cdp_client(client, "S1".to_string())
+                .with_target_id("T1".to_string())
+                .get_serialized_dom()
+                .await
+                .unwrap();
+            state
+                .selector_map
+                .values()
+                .filter_map(|element| element.attributes.get("id").cloned())
+                .collect::<Vec<_>>()
+        }
+    };
+
+    assert_eq!(labels(0.0).await, vec!["top".to_string()]);
+    let scrolled = labels(2500.0).await;
+    assert!(scrolled.contains(&"footer".to_string()), "{scrolled:?}");
+}
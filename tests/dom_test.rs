@@ -28,6 +28,7 @@ fn test_serialized_dom_state_llm_representation() {
         markdown: Some("# Test".to_string()),
         elements: vec![],
         selector_map: HashMap::new(),
+        truncated_values: vec![],
     };
 
     // Should prefer markdown
@@ -44,6 +45,7 @@ fn test_serialized_dom_state_fallbacks() {
         markdown: None,
         elements: vec![],
         selector_map: HashMap::new(),
+        truncated_values: vec![],
     };
 
     let repr = state.llm_representation(None);
@@ -56,6 +58,7 @@ fn test_serialized_dom_state_fallbacks() {
         markdown: None,
         elements: vec![],
         selector_map: HashMap::new(),
+        truncated_values: vec![],
     };
 
     let repr = state.llm_representation(None);
@@ -0,0 +1,375 @@
+//! Tests for describing mutating actions instead of executing them
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::agent::service::Agent;
+use browsing::agent::views::ActionResult;
+use browsing::config::ToolsConfig;
+use browsing::dom::DOMProcessorImpl;
+use browsing::dom::views::DOMInteractedElement;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use browsing::tools::Tools;
+use browsing::tools::dry_run::DRY_RUN_METADATA_KEY;
+use common::{FakePageBrowser, fake_cdp, methods};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+fn element(index: u32, tag: &str, text: &str, attributes: &[(&str, &str)]) -> DOMInteractedElement {
+    DOMInteractedElement {
+        index,
+        backend_node_id: Some(100 + index),
+        tag: tag.to_string(),
+        text: Some(text.to_string()),
+        attributes: attributes
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        selector: Some(format!("#el-{index}")),
+        bounds: None,
+        form_id: None,
+        frame_id: None,
+    }
+}
+
+fn selector_map() -> HashMap<u32, DOMInteractedElement> {
+    HashMap::from([
+        (
+            1,
+            element(1, "button", "Place order", &[("type", "submit")]),
+        ),
+        (
+            2,
+            element(2, "input", "", &[("type", "password"), ("name", "pw")]),
+        ),
+        (
+            3,
+            element(
+                3,
+                "input",
+                "",
+                &[("type", "email"), ("placeholder", "Email")],
+            ),
+        ),
+        (4, element(4, "input", "", &[("type", "file")])),
+    ])
+}
+
+fn dry_run_tools(force_execute: &[&str]) -> Tools {
+    Tools::from_config(&ToolsConfig {
+        dry_run: true,
+        force_execute: force_execute.iter().map(|a| a.to_string()).collect(),
+        ..Default::default()
+    })
+}
+
+fn action(action_type: &str, params: Value) -> browsing::tools::ActionModel {
+    serde_json::from_value(json!({ "action_type": action_type, "params": params })).unwrap()
+}
+
+fn responder() -> common::Responder {
+    Box::new(|method, _| match method {
+        "DOM.getContentQuads" => Ok(json!({ "quads": [[10, 10, 90, 10, 90, 30, 10, 30]] })),
+        "Page.getLayoutMetrics" => {
+            Ok(json!({ "layoutViewport": { "clientWidth": 1280, "clientHeight": 720 } }))
+        }
+        "Runtime.evaluate" => Ok(json!({ "result": { "value": 0 } })),
+        _ => Ok(json!({})),
+    })
+}
+
+fn content(result: &ActionResult) -> &str {
+    result.extracted_content.as_deref().unwrap()
+}
+
+#[tokio::test]
+async fn test_mutating_actions_are_described_without_cdp_commands() {
+    let (client, received) = fake_cdp(responder()).await;
+    let mut browser = FakePageBrowser { client };
+    let tools = dry_run_tools(&[]);
+    let map = selector_map();
+
+    let mut results = vec![];
+    for (action_type, params) in [
+        (
+            "navigate",
+            json!({ "url": "https://shop.example/checkout" }),
+        ),
+        ("click", json!({ "index": 1 })),
+        ("input", json!({ "index": 3, "text": "ada@example.com" })),
+        ("input", json!({ "index": 2, "text": "hunter2" })),
+        ("send_keys", json!({ "keys": "Enter" })),
+        ("upload_file", json!({ "index": 4, "path": "/tmp/cv.pdf" })),
+        (
+            "evaluate",
+            json!({ "expression": "document.forms[0].submit()" }),
+        ),
+        ("submit_form", json!({ "index": 3 })),
+    ] {
+        let result = tools
+            .act(action(action_type, params), &mut browser, Some(&map))
+            .await
+            .unwrap();
+        assert!(result.error.is_none(), "{action_type}: {:?}", result.error);
+        assert_eq!(
+            result.metadata.as_ref().unwrap()[DRY_RUN_METADATA_KEY],
+            true
+        );
+        results.push(result);
+    }
+
+    assert!(methods(&received).is_empty(), "{:?}", methods(&received));
+    assert_eq!(
+        content(&results[0]),
+        "Dry run: would navigate to https://shop.example/checkout; the page was not changed"
+    );
+    assert_eq!(
+        content(&results[1]),
+        "Dry run: would click element 1 <button> \"Place order\" (#el-1); the page was not changed"
+    );
+    assert_eq!(results[1].metadata.as_ref().unwrap()["selector"], "#el-1");
+    assert!(
+        content(&results[2])
+            .contains("type \"ada@example.com\" into element 3 <input> \"Email\" (#el-3)")
+    );
+    // Values for password fields are masked
+    assert!(content(&results[3]).contains("type \"***\" into element 2"));
+    assert!(!content(&results[3]).contains("hunter2"));
+    assert!(content(&results[4]).contains("press Enter"));
+    assert!(content(&results[5]).contains("upload /tmp/cv.pdf to element 4 <input>"));
+    assert!(content(&results[6]).contains("run JavaScript: document.forms[0].submit()"));
+    assert!(content(&results[7]).contains("submit the form containing element 3"));
+}
+
+#[tokio::test]
+async fn test_open_and_extract_lists_the_pages_without_opening_them() {
+    let (client, received) = fake_cdp(responder()).await;
+    let mut browser = FakePageBrowser { client };
+    let mut map = selector_map();
+    map.insert(
+        5,
+        element(
+            5,
+            "a",
+            "Pricing",
+            &[("href", "https://shop.example/pricing")],
+        ),
+    );
+
+    let result = dry_run_tools(&[])
+        .act(
+            action(
+                "open_and_extract",
+                json!({
+                    "urls": ["https://shop.example/faq"],
+                    "indices": [5, 1],
+                    "query": "shipping costs"
+                }),
+            ),
+            &mut browser,
+            Some(&map),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        content(&result),
+        "Dry run: would open https://shop.example/faq, https://shop.example/pricing, \
+         element 1 <button> \"Place order\" (#el-1) in background tabs and extract \
+         \"shipping costs\"; the page was not changed"
+    );
+    let sent = methods(&received);
+    assert!(
+        !sent
+            .iter()
+            .any(|method| method == "Target.createTarget" || method == "Page.navigate"),
+        "{sent:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_file_writes_are_described_with_their_paths() {
+    let (client, received) = fake_cdp(responder()).await;
+    let mut browser = FakePageBrowser { client };
+    let tools = dry_run_tools(&[]);
+
+    let saved = tools
+        .act(
+            action("save_page", json!({ "filename": "checkout" })),
+            &mut browser,
+            None,
+        )
+        .await
+        .unwrap();
+    let downloaded = tools
+        .act(
+            action("extract_images", json!({ "download": true, "limit": 2 })),
+            &mut browser,
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert!(methods(&received).is_empty(), "{:?}", methods(&received));
+    assert!(
+        content(&saved).contains("save the page as checkout.mhtml in the pages directory"),
+        "{}",
+        content(&saved)
+    );
+    assert!(
+        content(&downloaded).contains("download the 2 largest images"),
+        "{}",
+        content(&downloaded)
+    );
+
+    // Listing images without downloading writes nothing, so it runs
+    let _ = tools
+        .act(action("extract_images", json!({})), &mut browser, None)
+        .await;
+    assert!(
+        methods(&received)
+            .iter()
+            .any(|method| method == "Runtime.evaluate")
+    );
+}
+
+#[tokio::test]
+async fn test_read_only_and_forced_actions_still_run() {
+    let (client, received) = fake_cdp(responder()).await;
+    let mut browser = FakePageBrowser { client };
+    let tools = dry_run_tools(&["click"]);
+    let map = selector_map();
+
+    let found = tools
+        .act(
+            action("find_text", json!({ "text": "Total" })),
+            &mut browser,
+            Some(&map),
+        )
+        .await
+        .unwrap();
+    assert!(
+        found
+            .metadata
+            .is_none_or(|m| !m.contains_key(DRY_RUN_METADATA_KEY))
+    );
+    assert!(!methods(&received).is_empty());
+
+    let clicked = tools
+        .act(
+            action("click", json!({ "index": 1 })),
+            &mut browser,
+            Some(&map),
+        )
+        .await
+        .unwrap();
+    assert!(clicked.error.is_none(), "{:?}", clicked.error);
+    assert!(!content(&clicked).starts_with("Dry run"));
+    assert!(
+        methods(&received)
+            .iter()
+            .any(|method| method == "Input.dispatchMouseEvent")
+    );
+
+    // Not forced, so still described
+    received.lock().unwrap().clear();
+    let typed = tools
+        .act(
+            action("input", json!({ "index": 3, "text": "ada@example.com" })),
+            &mut browser,
+            Some(&map),
+        )
+        .await
+        .unwrap();
+    assert!(content(&typed).starts_with("Dry run"));
+    assert!(methods(&received).is_empty());
+}
+
+/// Model that types a secret, then finishes, recording the system message
+#[derive(Clone, Default)]
+struct TypingLLM {
+    calls: Arc<Mutex<u32>>,
+    system: Arc<Mutex<String>>,
+}
+
+#[async_trait]
+impl ChatModel for TypingLLM {
+    fn model(&self) -> &str {
+        "typing"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        *self.system.lock().unwrap() = messages[0].content.clone();
+        let mut calls = self.calls.lock().unwrap();
+        *calls += 1;
+        let action = if *calls == 1 {
+            json!({ "action_type": "input", "params": { "index": 5, "text": "<secret>password</secret>" } })
+        } else {
+            json!({ "action_type": "done", "params": { "text": "Planned" } })
+        };
+        Ok(ChatInvokeCompletion::new(
+            json!({ "action": [action] }).to_string(),
+        ))
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn test_agent_is_told_about_the_dry_run_and_secrets_stay_masked() {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "DOM.getDocument" => Ok(json!({
+            "root": { "nodeId": 1, "backendNodeId": 1, "nodeType": 9, "nodeName": "#document" }
+        })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let llm = TypingLLM::default();
+    let history = Agent::new(
+        "Sign in".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        llm.clone(),
+    )
+    .with_tools_config(&ToolsConfig {
+        dry_run: true,
+        force_execute: vec!["navigate".to_string()],
+        ..Default::default()
+    })
+    .with_secrets(HashMap::from([(
+        "password".to_string(),
+        "hunter2".to_string(),
+    )]))
+    .with_max_steps(3)
+    .run()
+    .await
+    .unwrap();
+
+    let system = llm.system.lock().unwrap().clone();
+    assert!(system.contains("<dry_run>"), "{system}");
+    assert!(system.contains("executed normally: navigate"), "{system}");
+
+    let typed = &history.history[0].result[0];
+    assert_eq!(
+        typed.extracted_content.as_deref(),
+        Some("Dry run: would type \"***\" into element 5; the page was not changed")
+    );
+    assert!(!serde_json::to_string(&history).unwrap().contains("hunter2"));
+    assert!(history.is_done());
+    assert!(
+        !methods(&received)
+            .iter()
+            .any(|method| method.starts_with("Input."))
+    );
+}
@@ -0,0 +1,153 @@
+//! Tests for waiting on element attribute changes and click's wait_for_response
+
+mod common;
+
+use browsing::actor::Element;
+use browsing::tools::Tools;
+use common::{EventScript, FakePageBrowser, Responder, fake_cdp_with_events};
+use serde_json::{Value, json};
+use std::time::Instant;
+
+fn layout_metrics() -> Value {
+    json!({ "layoutViewport": { "clientWidth": 1280, "clientHeight": 720 } })
+}
+
+/// Answers for node 42, pushed to the frontend as node 7 with `attributes`
+fn node_with(attributes: Value) -> Responder {
+    Box::new(move |method, _| match method {
+        "DOM.pushNodesByBackendIdsToFrontend" => Ok(json!({ "nodeIds": [7] })),
+        "DOM.getAttributes" => Ok(json!({ "attributes": attributes })),
+        "Page.getLayoutMetrics" => Ok(layout_metrics()),
+        "DOM.getContentQuads" => Ok(json!({
+            "quads": [[100.0, 200.0, 300.0, 200.0, 300.0, 240.0, 100.0, 240.0]]
+        })),
+        _ => Ok(json!({})),
+    })
+}
+
+/// Sends `events` for the page session just before answering `DOM.getAttributes`
+fn after_reading_attributes(events: Vec<(&'static str, Value)>) -> EventScript {
+    Box::new(move |method, _| {
+        if method != "DOM.getAttributes" {
+            return vec![];
+        }
+        events
+            .iter()
+            .map(
+                |(method, params)| json!({ "method": method, "params": params, "sessionId": "S1" }),
+            )
+            .collect()
+    })
+}
+
+#[tokio::test]
+async fn test_waits_for_target_value() {
+    let (client, _) = fake_cdp_with_events(
+        node_with(json!(["class", "btn", "aria-busy", "true"])),
+        after_reading_attributes(vec![
+            // Another node, then an unrelated attribute, then the change
+            (
+                "DOM.attributeModified",
+                json!({ "nodeId": 8, "name": "aria-busy", "value": "false" }),
+            ),
+            (
+                "DOM.attributeModified",
+                json!({ "nodeId": 7, "name": "class", "value": "btn done" }),
+            ),
+            (
+                "DOM.attributeModified",
+                json!({ "nodeId": 7, "name": "aria-busy", "value": "false" }),
+            ),
+        ]),
+    )
+    .await;
+    let element = Element::new(client, "S1".to_string(), 42);
+
+    let value = element
+        .wait_for_attribute_change("aria-busy", Some("false"), 1_000)
+        .await
+        .unwrap();
+    assert_eq!(value, "false");
+}
+
+#[tokio::test]
+async fn test_removed_attribute_counts_as_a_change() {
+    let (client, received) = fake_cdp_with_events(
+        node_with(json!(["disabled", ""])),
+        after_reading_attributes(vec![(
+            "DOM.attributeRemoved",
+            json!({ "nodeId": 7, "name": "disabled" }),
+        )]),
+    )
+    .await;
+    let element = Element::new(client, "S1".to_string(), 42);
+
+    let value = element
+        .wait_for_attribute_change("disabled", None, 1_000)
+        .await
+        .unwrap();
+    assert_eq!(value, "");
+    let received = received.lock().unwrap();
+    let (_, push, session) = received
+        .iter()
+        .find(|(method, _, _)| method == "DOM.pushNodesByBackendIdsToFrontend")
+        .unwrap();
+    assert_eq!(push["backendNodeIds"], json!([42]));
+    assert_eq!(session.as_deref(), Some("S1"));
+}
+
+#[tokio::test]
+async fn test_current_target_value_returns_at_once_and_timeout_fails() {
+    let (client, _) = fake_cdp_with_events(
+        node_with(json!(["aria-busy", "true"])),
+        after_reading_attributes(vec![]),
+    )
+    .await;
+    let element = Element::new(client, "S1".to_string(), 42);
+
+    let value = element
+        .wait_for_attribute_change("aria-busy", Some("true"), 1_000)
+        .await
+        .unwrap();
+    assert_eq!(value, "true");
+
+    let started = Instant::now();
+    let err = element
+        .wait_for_attribute_change("aria-busy", None, 50)
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("did not change within 50ms"),
+        "{err}"
+    );
+    assert!(started.elapsed().as_millis() < 1_000);
+}
+
+#[tokio::test]
+async fn test_click_waits_for_loading_button() {
+    let (client, _) = fake_cdp_with_events(
+        node_with(json!(["type", "submit", "aria-busy", "true"])),
+        after_reading_attributes(vec![(
+            "DOM.attributeModified",
+            json!({ "nodeId": 7, "name": "aria-busy", "value": "false" }),
+        )]),
+    )
+    .await;
+    let mut browser = FakePageBrowser { client };
+    let action = serde_json::from_value(json!({
+        "action_type": "click",
+        "params": { "index": 42, "wait_for_response": true }
+    }))
+    .unwrap();
+
+    let result = Tools::default()
+        .act(action, &mut browser, None)
+        .await
+        .unwrap();
+
+    let memory = result.long_term_memory.unwrap();
+    assert!(
+        memory.ends_with("; waited for it to finish loading"),
+        "{memory}"
+    );
+}
@@ -0,0 +1,416 @@
+//! Tests for element click geometry against a scripted CDP endpoint
+
+mod common;
+
+use browsing::actor::mouse::MouseButton;
+use browsing::actor::Element;
+use browsing::error::BrowsingError;
+use browsing::tools::Tools;
+use common::{FakePageBrowser, Received, fake_cdp, methods};
+use serde_json::{Value, json};
+
+fn layout_metrics() -> Value {
+    json!({ "layoutViewport": { "clientWidth": 1280, "clientHeight": 720 } })
+}
+
+fn quads(quad: [f64; 8]) -> Value {
+    json!({ "quads": [quad] })
+}
+
+fn mouse_events(received: &Received) -> Vec<Value> {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, _, _)| method == "Input.dispatchMouseEvent")
+        .map(|(_, params, _)| params.clone())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_click_uses_content_quad_center() {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Page.getLayoutMetrics" => Ok(layout_metrics()),
+        "DOM.getContentQuads" => Ok(quads([
+            100.0, 200.0, 300.0, 200.0, 300.0, 240.0, 100.0, 240.0,
+        ])),
+        _ => Ok(json!({})),
+    }))
+    .await;
+
+    let element = Element::new(client, "S1".to_string(), 42);
+    element.click(MouseButton::Left, 1, None).await.unwrap();
+
+    let events = mouse_events(&received);
+    assert_eq!(events.len(), 3);
+    assert!(events.iter().all(|e| e["x"] == 200.0 && e["y"] == 220.0));
+    // Commands are sent to the element's page session
+    assert!(
+        received
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|(_, _, session)| session.as_deref() == Some("S1"))
+    );
+}
+
+#[tokio::test]
+async fn test_click_falls_back_to_box_model() {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Page.getLayoutMetrics" => Ok(layout_metrics()),
+        "DOM.getContentQuads" => Ok(json!({ "quads": [] })),
+        "DOM.getBoxModel" => Ok(json!({
+            "model": {
+                "content": [10.0, 10.0, 50.0, 10.0, 50.0, 30.0, 10.0, 30.0],
+                "width": 40,
+                "height": 20
+            }
+        })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+
+    let element = Element::new(client, "S1".to_string(), 42);
+    element.click(MouseButton::Left, 1, None).await.unwrap();
+
+    let events = mouse_events(&received);
+    assert_eq!(events.len(), 3);
+    assert_eq!(
+        (events[0]["x"].as_f64(), events[0]["y"].as_f64()),
+        (Some(30.0), Some(20.0))
+    );
+}
+
+#[tokio::test]
+async fn test_click_scrolls_offscreen_element_and_remeasures() {
+    let (client, received) = fake_cdp(Box::new(|method, call| match method {
+        "Page.getLayoutMetrics" => Ok(layout_metrics()),
+        // Below the fold until scrolled into view
+        "DOM.getContentQuads" if call == 1 => Ok(quads([
+            100.0, 2000.0, 200.0, 2000.0, 200.0, 2040.0, 100.0, 2040.0,
+        ])),
+        "DOM.getContentQuads" => Ok(quads([
+            100.0, 300.0, 200.0, 300.0, 200.0, 340.0, 100.0, 340.0,
+        ])),
+        _ => Ok(json!({})),
+    }))
+    .await;
+
+    let element = Element::new(client, "S1".to_string(), 42);
+    element.click(MouseButton::Left, 1, None).await.unwrap();
+
+    assert!(methods(&received).contains(&"DOM.scrollIntoViewIfNeeded".to_string()));
+    let events = mouse_events(&received);
+    assert_eq!(
+        (events[0]["x"].as_f64(), events[0]["y"].as_f64()),
+        (Some(150.0), Some(320.0))
+    );
+}
+
+#[tokio::test]
+async fn test_click_display_none_element_is_refused() {
+    // What Chrome answers for an element with `display: none`
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Page.getLayoutMetrics" => Ok(layout_metrics()),
+        "DOM.getContentQuads" => Err("Could not compute content quads.".to_string()),
+        "DOM.getBoxModel" => Err("Could not compute box model.".to_string()),
+        "DOM.scrollIntoViewIfNeeded" => Err("Node does not have a layout object".to_string()),
+        _ => Ok(json!({})),
+    }))
+    .await;
+
+    let element = Element::new(client, "S1".to_string(), 42);
+    let err = element.click(MouseButton::Left, 1, None).await.unwrap_err();
+
+    assert!(
+        matches!(
+            err,
+            BrowsingError::NotVisible {
+                backend_node_id: 42,
+                ..
+            }
+        ),
+        "unexpected error: {err}"
+    );
+    assert!(
+        mouse_events(&received).is_empty(),
+        "must not click anywhere"
+    );
+    // Geometry was re-queried after trying to scroll
+    let methods = methods(&received);
+    assert_eq!(
+        methods.iter().filter(|m| *m == "DOM.getBoxModel").count(),
+        2
+    );
+}
+
+#[tokio::test]
+async fn test_click_detached_node_is_refused() {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Page.getLayoutMetrics" => Ok(layout_metrics()),
+        m if m.starts_with("DOM.") => Err("No node with given id found".to_string()),
+        _ => Ok(json!({})),
+    }))
+    .await;
+
+    let element = Element::new(client, "S1".to_string(), 7);
+    let err = element.click(MouseButton::Left, 1, None).await.unwrap_err();
+
+    assert!(matches!(
+        err,
+        BrowsingError::NotVisible {
+            backend_node_id: 7,
+            ..
+        }
+    ));
+    assert!(
+        mouse_events(&received).is_empty(),
+        "must not click anywhere"
+    );
+}
+
+#[tokio::test]
+async fn test_click_zero_size_element_is_refused() {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Page.getLayoutMetrics" => Ok(layout_metrics()),
+        "DOM.getContentQuads" => Ok(quads([5.0; 8])),
+        "DOM.getBoxModel" => {
+            Ok(json!({ "model": { "content": [5.0, 5.0, 5.0, 5.0, 5.0, 5.0, 5.0, 5.0], "width": 0, "height": 0 } }))
+        }
+        _ => Ok(json!({})),
+    }))
+    .await;
+
+    let element = Element::new(client, "S1".to_string(), 42);
+    assert!(element.click(MouseButton::Left, 1, None).await.is_err());
+    assert!(mouse_events(&received).is_empty());
+}
+
+#[tokio::test]
+async fn test_click_action_reports_invisible_element_to_model() {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Page.getLayoutMetrics" => Ok(layout_metrics()),
+        m if m.starts_with("DOM.") => Err("Could not compute box model.".to_string()),
+        _ => Ok(json!({})),
+    }))
+    .await;
+
+    let mut browser = FakePageBrowser { client };
+    let action =
+        serde_json::from_value(json!({ "action_type": "click", "params": { "index": 5 } }))
+            .unwrap();
+    let result = Tools::default()
+        .act(action, &mut browser, None)
+        .await
+        .unwrap();
+
+    let error = result.error.unwrap();
+    assert!(error.starts_with("Element 5 "), "{error}");
+    assert!(error.contains("different index"), "{error}");
+    assert!(result.long_term_memory.is_some());
+    assert!(mouse_events(&received).is_empty());
+}
+
+/// Answer a descendant lookup with the given (backend node ID, text) matches
+fn descendant_lookup(matches: Vec<(u64, &'static str)>) -> common::Responder {
+    Box::new(move |method, call| match method {
+        "Page.getLayoutMetrics" => Ok(layout_metrics()),
+        "DOM.resolveNode" => Ok(json!({ "object": { "objectId": "container" } })),
+        "Runtime.callFunctionOn" if call == 1 => Ok(json!({ "result": { "objectId": "found" } })),
+        "Runtime.callFunctionOn" => Ok(json!({
+            "result": { "value": matches.iter().map(|(_, text)| *text).collect::<Vec<_>>() }
+        })),
+        "Runtime.getProperties" => {
+            let mut properties: Vec<Value> = (0..matches.len())
+                .map(|i| json!({ "name": i.to_string(), "value": { "objectId": format!("match-{i}") } }))
+                .collect();
+            properties.push(json!({ "name": "length", "value": { "value": matches.len() } }));
+            Ok(json!({ "result": properties }))
+        }
+        "DOM.describeNode" => Ok(json!({ "node": { "backendNodeId": matches[call - 1].0 } })),
+        "DOM.getContentQuads" => Ok(quads([
+            100.0, 200.0, 300.0, 200.0, 300.0, 240.0, 100.0, 240.0,
+        ])),
+        _ => Ok(json!({})),
+    })
+}
+
+fn click_descendant(params: Value) -> browsing::tools::views::ActionModel {
+    serde_json::from_value(json!({ "action_type": "click_descendant", "params": params })).unwrap()
+}
+
+#[tokio::test]
+async fn test_click_descendant_clicks_unique_match_in_container() {
+    let (client, received) = fake_cdp(descendant_lookup(vec![(702, "Add to cart")])).await;
+
+    let mut browser = FakePageBrowser { client };
+    let action = click_descendant(json!({ "index": 7, "role": "button", "text": "add to cart" }));
+    let result = Tools::default()
+        .act(action, &mut browser, None)
+        .await
+        .unwrap();
+
+    assert!(result.error.is_none(), "{:?}", result.error);
+    let memory = result.long_term_memory.unwrap();
+    assert!(memory.contains("inside element 7"), "{memory}");
+
+    let received = received.lock().unwrap();
+    let param = |method: &str| {
+        received
+            .iter()
+            .find(|(m, _, _)| m == method)
+            .map(|(_, params, _)| params.clone())
+            .unwrap()
+    };
+    // The lookup is scoped to the container and carries the locator
+    assert_eq!(param("DOM.resolveNode")["backendNodeId"], 7);
+    let lookup = param("Runtime.callFunctionOn");
+    assert_eq!(lookup["objectId"], "container");
+    assert_eq!(
+        lookup["arguments"][0]["value"],
+        json!({ "role": "button", "text": "add to cart", "css": null })
+    );
+    // The match, not the container, is clicked
+    assert_eq!(param("DOM.getContentQuads")["backendNodeId"], 702);
+    let clicks = received
+        .iter()
+        .filter(|(m, _, _)| m == "Input.dispatchMouseEvent")
+        .count();
+    assert_eq!(clicks, 3);
+    assert!(
+        received
+            .iter()
+            .any(|(m, _, _)| m == "Runtime.releaseObjectGroup")
+    );
+}
+
+#[tokio::test]
+async fn test_click_descendant_reports_ambiguous_candidates() {
+    let (client, received) = fake_cdp(descendant_lookup(vec![
+        (702, "Add to cart"),
+        (705, "Add to wishlist"),
+    ]))
+    .await;
+
+    let mut browser = FakePageBrowser { client };
+    let action = click_descendant(json!({ "index": 7, "role": "button" }));
+    let result = Tools::default()
+        .act(action, &mut browser, None)
+        .await
+        .unwrap();
+
+    let error = result.error.unwrap();
+    assert!(error.starts_with("2 elements match role=button"), "{error}");
+    assert!(
+        error.contains("\"Add to cart\", \"Add to wishlist\""),
+        "{error}"
+    );
+    assert!(mouse_events(&received).is_empty());
+}
+
+#[tokio::test]
+async fn test_click_descendant_reports_no_match() {
+    let (client, received) = fake_cdp(descendant_lookup(vec![])).await;
+
+    let mut browser = FakePageBrowser { client };
+    let action = click_descendant(json!({ "index": 7, "css": "button.buy" }));
+    let result = Tools::default()
+        .act(action, &mut browser, None)
+        .await
+        .unwrap();
+
+    let error = result.error.unwrap();
+    assert_eq!(error, "No element matching css=button.buy inside element 7");
+    assert!(mouse_events(&received).is_empty());
+}
+
+#[tokio::test]
+async fn test_click_descendant_requires_a_locator() {
+    let (client, received) = fake_cdp(descendant_lookup(vec![])).await;
+
+    let mut browser = FakePageBrowser { client };
+    let action = click_descendant(json!({ "index": 7 }));
+    let err = Tools::default()
+        .act(action, &mut browser, None)
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("'role', 'text' or 'css'"), "{err}");
+    assert!(received.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_find_descendants_in_sibling_cards() {
+    use base64::Engine;
+    use browsing::actor::DescendantLocator;
+    use browsing::browser::{Browser, BrowserProfile};
+
+    let html = include_str!("fixtures/clickability/cards.html");
+    let url = format!(
+        "data:text/html;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(html)
+    );
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.expect("Browser should start");
+    browser.navigate(&url).await.unwrap();
+    let page = browser.get_page().unwrap();
+    let card = |id: &'static str| {
+        let page = &page;
+        async move {
+            page.get_elements_by_css_selector(id)
+                .await
+                .unwrap()
+                .remove(0)
+        }
+    };
+    let add_to_cart = DescendantLocator::new()
+        .with_role("button")
+        .with_text("add to cart");
+
+    // The hidden duplicate in card 2 is skipped
+    let matches = card("#card-2")
+        .await
+        .find_descendants(&add_to_cart)
+        .await
+        .unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].text, "Add to cart");
+    page.get_element(matches[0].backend_node_id)
+        .await
+        .click(MouseButton::Left, 1, None)
+        .await
+        .unwrap();
+    let status = page
+        .evaluate("document.getElementById('status').textContent")
+        .await
+        .unwrap();
+    assert!(status.contains("added SP-200"), "{status}");
+
+    // Text inside a nested span resolves to the innermost element
+    let matches = card("#card-3")
+        .await
+        .find_descendants(&DescendantLocator::new().with_text("add to cart"))
+        .await
+        .unwrap();
+    assert_eq!(matches.len(), 1);
+
+    // Explicit and implicit roles both count
+    let matches = card("#card-3")
+        .await
+        .find_descendants(&DescendantLocator::new().with_role("button"))
+        .await
+        .unwrap();
+    let texts: Vec<_> = matches.iter().map(|m| m.text.as_str()).collect();
+    assert_eq!(texts, ["Add to cart", "Add to wishlist"]);
+
+    // Across the whole list the identical buttons are ambiguous
+    let matches = card("#products")
+        .await
+        .find_descendants(&add_to_cart)
+        .await
+        .unwrap();
+    assert_eq!(matches.len(), 3);
+
+    browser.stop().await.unwrap();
+}
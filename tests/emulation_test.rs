@@ -0,0 +1,259 @@
+//! Tests for color scheme, vision deficiency and Save-Data emulation
+
+mod common;
+
+use browsing::actor::{ColorScheme, EmulationSettings, Page, VisionDeficiency};
+use browsing::agent::views::ActionResult;
+use browsing::browser::{Browser, BrowserProfile};
+use browsing::tools::Tools;
+use common::{FakePageBrowser, Received, fake_cdp, fake_cdp_with_latency, methods, serve_html};
+use serde_json::{Value, json};
+use std::time::Duration;
+
+const COLOR_SCHEME_HTML: &str = include_str!("fixtures/emulation/color_scheme.html");
+
+/// Params and session of each `method` command, in order
+fn commands(received: &Received, method: &str) -> Vec<(Value, Option<String>)> {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(m, _, _)| m == method)
+        .map(|(_, params, session)| (params.clone(), session.clone()))
+        .collect()
+}
+
+fn dark() -> EmulationSettings {
+    EmulationSettings {
+        color_scheme: Some(ColorScheme::Dark),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_page_wrappers_send_emulation_commands() {
+    let (client, received) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let page = Page::new(client, "S1".to_string());
+
+    page.set_prefers_color_scheme(ColorScheme::Dark)
+        .await
+        .unwrap();
+    page.emulate_vision_deficiency(VisionDeficiency::Deuteranopia)
+        .await
+        .unwrap();
+    page.set_save_data(true).await.unwrap();
+
+    let media = commands(&received, "Emulation.setEmulatedMedia");
+    assert_eq!(
+        media[0].0["features"][0],
+        json!({ "name": "prefers-color-scheme", "value": "dark" })
+    );
+    assert_eq!(media[0].1.as_deref(), Some("S1"));
+    assert_eq!(
+        commands(&received, "Emulation.setEmulatedVisionDeficiency")[0].0,
+        json!({ "type": "deuteranopia" })
+    );
+    assert_eq!(
+        commands(&received, "Network.setExtraHTTPHeaders")[0].0,
+        json!({ "headers": { "Save-Data": "on" } })
+    );
+}
+
+#[tokio::test]
+async fn test_apply_emulation_sets_features_together() {
+    let (client, received) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let page = Page::new(client, "S1".to_string());
+    let settings = EmulationSettings {
+        color_scheme: Some(ColorScheme::Light),
+        vision_deficiency: VisionDeficiency::BlurredVision,
+        save_data: true,
+    };
+
+    page.apply_emulation(&settings).await.unwrap();
+
+    assert_eq!(
+        commands(&received, "Emulation.setEmulatedMedia")[0].0["features"],
+        json!([
+            { "name": "prefers-color-scheme", "value": "light" },
+            { "name": "prefers-reduced-data", "value": "reduce" },
+        ])
+    );
+    assert_eq!(
+        commands(&received, "Emulation.setEmulatedVisionDeficiency")[0].0,
+        json!({ "type": "blurredVision" })
+    );
+    assert_eq!(
+        commands(&received, "Network.setExtraHTTPHeaders")[0].0,
+        json!({ "headers": { "Save-Data": "on" } })
+    );
+}
+
+/// Browser connected to a fake endpoint with one page, target T1 in session
+/// S1; new tabs get target T2, attached as S{call}
+async fn connected_browser(profile: BrowserProfile) -> (Browser, Received) {
+    let (url, received) = fake_cdp_with_latency(
+        Box::new(|method, call| match method {
+            "Target.getTargets" => Ok(json!({
+                "targetInfos": [
+                    { "targetId": "T1", "type": "page", "url": "about:blank" },
+                    { "targetId": "T2", "type": "page", "url": "about:blank" }
+                ]
+            })),
+            "Target.createTarget" => Ok(json!({ "targetId": "T2" })),
+            "Target.attachToTarget" => Ok(json!({ "sessionId": format!("S{call}") })),
+            _ => Ok(json!({})),
+        }),
+        Duration::ZERO,
+    )
+    .await;
+    let mut browser = Browser::new(profile).with_cdp_url(url);
+    browser.start().await.unwrap();
+    (browser, received)
+}
+
+#[tokio::test]
+async fn test_profile_emulation_reaches_every_tab() {
+    let profile = BrowserProfile::new().with_emulation(dark());
+    let (mut browser, received) = connected_browser(profile).await;
+
+    let target_id = browser.create_new_tab(None).await.unwrap();
+    browser.switch_to_tab(&target_id).await.unwrap();
+
+    let sessions: Vec<_> = commands(&received, "Emulation.setEmulatedMedia")
+        .into_iter()
+        .map(|(_, session)| session.unwrap())
+        .collect();
+    // The first tab, the new tab, and the new tab's session after switching
+    assert_eq!(sessions.len(), 3, "{sessions:?}");
+    assert_eq!(sessions[0], "S1");
+    assert_ne!(sessions[1], sessions[2]);
+    assert_eq!(browser.emulation(), dark());
+}
+
+#[tokio::test]
+async fn test_no_emulation_commands_by_default() {
+    let (mut browser, received) = connected_browser(BrowserProfile::default()).await;
+    browser.create_new_tab(None).await.unwrap();
+
+    assert!(commands(&received, "Emulation.setEmulatedMedia").is_empty());
+    assert!(browser.emulation().is_default());
+}
+
+#[tokio::test]
+async fn test_set_emulation_is_kept_per_tab() {
+    let (mut browser, received) = connected_browser(BrowserProfile::default()).await;
+    let first = browser.get_current_target_id().unwrap();
+
+    browser.set_emulation(dark()).await.unwrap();
+    let second = browser.create_new_tab(None).await.unwrap();
+    browser.switch_to_tab(&second).await.unwrap();
+    assert!(browser.emulation().is_default());
+
+    received.lock().unwrap().clear();
+    browser.switch_to_tab(&first).await.unwrap();
+    assert_eq!(browser.emulation(), dark());
+    assert_eq!(commands(&received, "Emulation.setEmulatedMedia").len(), 1);
+}
+
+/// Run the navigate action with `params` on a fake page
+async fn navigate(params: Value) -> (ActionResult, Received) {
+    let (client, received) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let mut browser = FakePageBrowser { client };
+    let action =
+        serde_json::from_value(json!({ "action_type": "navigate", "params": params })).unwrap();
+    let result = Tools::default()
+        .act(action, &mut browser, None)
+        .await
+        .unwrap();
+    assert!(result.error.is_none(), "{:?}", result.error);
+    (result, received)
+}
+
+#[tokio::test]
+async fn test_navigate_applies_emulation_before_loading() {
+    let (result, received) = navigate(json!({
+        "url": "https://example.com/pricing",
+        // Sent with Page.navigate rather than the fake browser's no-op navigate
+        "referrer": "https://example.com/",
+        "color_scheme": "dark",
+        "vision_deficiency": "protanopia",
+        "save_data": true
+    }))
+    .await;
+
+    assert_eq!(
+        result.long_term_memory.as_deref(),
+        Some("Navigated to https://example.com/pricing")
+    );
+    assert_eq!(
+        result.debug_info.as_deref(),
+        Some("emulation: dark color scheme, protanopia, Save-Data")
+    );
+    let sent = methods(&received);
+    let position = |method: &str| sent.iter().position(|m| m == method).unwrap();
+    assert!(position("Emulation.setEmulatedMedia") < position("Page.navigate"));
+    assert!(position("Network.setExtraHTTPHeaders") < position("Page.navigate"));
+    assert_eq!(
+        commands(&received, "Emulation.setEmulatedVisionDeficiency")[0].0,
+        json!({ "type": "protanopia" })
+    );
+}
+
+#[tokio::test]
+async fn test_navigate_without_emulation_params_leaves_emulation_alone() {
+    let (result, received) = navigate(json!({ "url": "https://example.com/pricing" })).await;
+
+    assert_eq!(
+        result.long_term_memory.as_deref(),
+        Some("Navigated to https://example.com/pricing")
+    );
+    assert_eq!(result.debug_info, None);
+    assert!(
+        !methods(&received)
+            .iter()
+            .any(|method| method.starts_with("Emulation."))
+    );
+}
+
+#[tokio::test]
+async fn test_navigate_rejects_unknown_color_scheme() {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let mut browser = FakePageBrowser { client };
+    let action = serde_json::from_value(json!({
+        "action_type": "navigate",
+        "params": { "url": "https://example.com/", "color_scheme": "sepia" }
+    }))
+    .unwrap();
+    let result = Tools::default().act(action, &mut browser, None).await;
+
+    let error = match result {
+        Ok(result) => result.error.unwrap(),
+        Err(e) => e.to_string(),
+    };
+    assert!(error.contains("Invalid color_scheme 'sepia'"), "{error}");
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_prefers_color_scheme_flips_match_media() {
+    let url = serve_html(COLOR_SCHEME_HTML).await;
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.unwrap();
+    browser.navigate(&url).await.unwrap();
+    let query = "matchMedia('(prefers-color-scheme: dark)').matches";
+    let page = browser.get_page().unwrap();
+
+    page.set_prefers_color_scheme(ColorScheme::Light)
+        .await
+        .unwrap();
+    assert_eq!(page.evaluate(query).await.unwrap(), "false");
+
+    page.set_prefers_color_scheme(ColorScheme::Dark)
+        .await
+        .unwrap();
+    assert_eq!(page.evaluate(query).await.unwrap(), "true");
+    let shown = "document.getElementById('scheme').textContent";
+    assert_eq!(page.evaluate(shown).await.unwrap(), "dark");
+
+    browser.stop().await.unwrap();
+}
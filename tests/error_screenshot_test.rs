@@ -0,0 +1,221 @@
+//! Tests for screenshots of failed actions
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::actor::Page;
+use browsing::agent::error_screenshot::ERROR_SCREENSHOT_METADATA_KEY;
+use browsing::agent::service::Agent;
+use browsing::agent::views::{AgentHistoryList, AgentSettings};
+use browsing::browser::cdp::CdpClient;
+use browsing::browser::views::TabInfo;
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use browsing::traits::BrowserClient;
+use common::{Received, fake_cdp};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+
+/// Model that answers every step with the same actions
+#[derive(Clone)]
+struct ScriptedLLM {
+    actions: Value,
+}
+
+#[async_trait]
+impl ChatModel for ScriptedLLM {
+    fn model(&self) -> &str {
+        "scripted"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, _messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        Ok(ChatInvokeCompletion {
+            completion: json!({ "action": self.actions }).to_string(),
+            thinking: None,
+            redacted_thinking: None,
+            usage: None,
+            stop_reason: None,
+        })
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+/// Browser on the fake endpoint that records screenshot paths without writing files
+struct ScreenshotBrowser {
+    client: Arc<CdpClient>,
+    screenshots: Arc<Mutex<Vec<Option<String>>>>,
+}
+
+#[async_trait]
+impl BrowserClient for ScreenshotBrowser {
+    async fn start(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn navigate(&mut self, _url: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_current_url(&self) -> Result<String> {
+        Ok("https://example.com".to_string())
+    }
+
+    async fn create_tab(&mut self, _url: Option<&str>) -> Result<String> {
+        Ok("T1".to_string())
+    }
+
+    async fn switch_to_tab(&mut self, _target_id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn close_tab(&mut self, _target_id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_tabs(&self) -> Result<Vec<TabInfo>> {
+        Ok(vec![])
+    }
+
+    async fn get_target_id_from_tab_id(&self, tab_id: &str) -> Result<String> {
+        Ok(tab_id.to_string())
+    }
+
+    fn get_page(&self) -> Result<Page> {
+        Ok(Page::new(Arc::clone(&self.client), "S1".to_string()))
+    }
+
+    async fn take_screenshot(&self, path: Option<&str>, _full_page: bool) -> Result<Vec<u8>> {
+        self.screenshots
+            .lock()
+            .unwrap()
+            .push(path.map(str::to_string));
+        Ok(vec![])
+    }
+
+    async fn get_current_page_title(&self) -> Result<String> {
+        Ok("Example".to_string())
+    }
+
+    fn get_cdp_client(&self) -> Result<Arc<CdpClient>> {
+        Ok(Arc::clone(&self.client))
+    }
+
+    fn get_session_id(&self) -> Result<String> {
+        Ok("S1".to_string())
+    }
+
+    fn get_current_target_id(&self) -> Result<String> {
+        Ok("T1".to_string())
+    }
+}
+
+/// Click element 5, which has no layout, twice per step for two steps
+async fn run_failing_clicks(
+    settings: AgentSettings,
+) -> (AgentHistoryList, Vec<Option<String>>, Received) {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Page.getLayoutMetrics" => {
+            Ok(json!({ "layoutViewport": { "clientWidth": 1280, "clientHeight": 720 } }))
+        }
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let screenshots = Arc::new(Mutex::new(vec![]));
+    let browser = ScreenshotBrowser {
+        client,
+        screenshots: Arc::clone(&screenshots),
+    };
+    let click = json!({ "action_type": "click", "params": { "index": 5 } });
+    let llm = ScriptedLLM {
+        actions: json!([click, click]),
+    };
+
+    let history = Agent::new(
+        "Open the first result".to_string(),
+        Box::new(browser),
+        Box::new(DOMProcessorImpl::new()),
+        llm,
+    )
+    .with_settings(settings)
+    .with_max_steps(2)
+    .run()
+    .await
+    .unwrap();
+    let screenshots = screenshots.lock().unwrap().clone();
+    (history, screenshots, received)
+}
+
+#[tokio::test]
+async fn test_failed_action_is_screenshotted_once_per_step() {
+    let dir = tempfile::tempdir().unwrap();
+    let settings = AgentSettings {
+        screenshot_on_error: true,
+        artifacts_dir: Some(dir.path().to_path_buf()),
+        ..Default::default()
+    };
+
+    let (history, screenshots, received) = run_failing_clicks(settings).await;
+
+    let expected = |step: u32| {
+        dir.path()
+            .join(format!("step-00{step}-click-5.png"))
+            .to_string_lossy()
+            .into_owned()
+    };
+    assert_eq!(screenshots, [Some(expected(1)), Some(expected(2))]);
+    for (step, item) in history.history.iter().enumerate() {
+        let path = expected(step as u32 + 1);
+        let [first, second] = item.result.as_slice() else {
+            panic!("expected two results, got {:?}", item.result);
+        };
+        assert!(first.error.is_some() && second.error.is_some());
+        assert_eq!(
+            first.metadata.as_ref().unwrap()[ERROR_SCREENSHOT_METADATA_KEY],
+            json!(path)
+        );
+        // The retry in the same step is not captured again
+        assert!(second.metadata.is_none());
+        assert_eq!(item.state.screenshot_path.as_deref(), Some(path.as_str()));
+    }
+
+    // The failing action is drawn on the page for the capture, then removed
+    let expressions: Vec<String> = received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, _, _)| method == "Runtime.evaluate")
+        .filter_map(|(_, params, _)| params["expression"].as_str().map(str::to_string))
+        .filter(|expression| expression.contains("__browsing_error_annotation"))
+        .collect();
+    assert_eq!(expressions.len(), 4);
+    assert!(
+        expressions[0].contains("Step 1: click [5] failed: Element 5"),
+        "{}",
+        expressions[0]
+    );
+    assert!(expressions[1].contains(".remove()"));
+}
+
+#[tokio::test]
+async fn test_no_screenshots_by_default() {
+    let (history, screenshots, _) = run_failing_clicks(AgentSettings::default()).await;
+
+    assert!(screenshots.is_empty());
+    assert!(
+        history
+            .history
+            .iter()
+            .all(|item| item.state.screenshot_path.is_none())
+    );
+}
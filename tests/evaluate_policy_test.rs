@@ -0,0 +1,177 @@
+//! Tests for the evaluate policy
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::agent::service::Agent;
+use browsing::agent::views::AgentSettings;
+use browsing::config::ToolsConfig;
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use browsing::tools::{EvaluatePolicy, Tools};
+use common::{FakePageBrowser, Received, fake_cdp, methods};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+
+const READ_ONLY: &str = "document.querySelectorAll('tr').length";
+const MUTATING: &str = "document.body.appendChild(document.createElement('p'))";
+
+async fn evaluate(policy: EvaluatePolicy, expression: &str) -> (Result<Option<String>>, Received) {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Page.getFrameTree" => Ok(json!({ "frameTree": { "frame": { "id": "F1" } } })),
+        "Page.createIsolatedWorld" => Ok(json!({ "executionContextId": 7 })),
+        "Runtime.evaluate" => Ok(json!({ "result": { "value": 3 } })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let mut browser = FakePageBrowser { client };
+    let action = serde_json::from_value(json!({
+        "action_type": "evaluate",
+        "params": { "expression": expression }
+    }))
+    .unwrap();
+
+    let result = Tools::default()
+        .with_evaluate_policy(policy)
+        .act(action, &mut browser, None)
+        .await
+        .map(|r| r.extracted_content);
+    (result, received)
+}
+
+#[tokio::test]
+async fn test_disabled_refuses_everything() {
+    for expression in [READ_ONLY, MUTATING] {
+        let (result, received) = evaluate(EvaluatePolicy::Disabled, expression).await;
+
+        assert!(matches!(
+            result,
+            Err(BrowsingError::Tool(ref msg)) if msg == "evaluate is disabled by the evaluate policy"
+        ));
+        assert!(methods(&received).is_empty());
+    }
+}
+
+#[tokio::test]
+async fn test_read_only_runs_reads_in_an_isolated_world() {
+    let (result, received) = evaluate(EvaluatePolicy::ReadOnly, READ_ONLY).await;
+
+    assert_eq!(result.unwrap().as_deref(), Some("3"));
+    assert_eq!(
+        methods(&received),
+        [
+            "Page.getFrameTree",
+            "Page.createIsolatedWorld",
+            "Runtime.evaluate",
+            "Runtime.evaluate"
+        ]
+    );
+    let received = received.lock().unwrap();
+    assert_eq!(received[1].1["frameId"], "F1");
+    // The shims run first, then the expression, both in the new world
+    assert!(
+        received[2].1["expression"]
+            .as_str()
+            .unwrap()
+            .contains("read-only evaluate")
+    );
+    assert_eq!(received[3].1["expression"], READ_ONLY);
+    for (_, params, session) in &received[2..] {
+        assert_eq!(params["contextId"], 7);
+        assert_eq!(session.as_deref(), Some("S1"));
+    }
+}
+
+#[tokio::test]
+async fn test_read_only_rejects_mutations() {
+    let (result, received) = evaluate(EvaluatePolicy::ReadOnly, MUTATING).await;
+
+    let Err(BrowsingError::Tool(msg)) = result else {
+        panic!("expected a policy violation, got {result:?}");
+    };
+    assert_eq!(
+        msg,
+        "Read-only evaluate policy rejected the expression: it calls appendChild()"
+    );
+    assert!(methods(&received).is_empty());
+}
+
+#[tokio::test]
+async fn test_full_runs_in_the_page() {
+    for expression in [READ_ONLY, MUTATING] {
+        let (result, received) = evaluate(EvaluatePolicy::Full, expression).await;
+
+        assert_eq!(result.unwrap().as_deref(), Some("3"));
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].1["expression"], expression);
+        assert!(received[0].1.get("contextId").is_none());
+    }
+}
+
+/// Model that runs `MUTATING`, then finishes
+#[derive(Clone, Default)]
+struct EvaluatingLLM {
+    calls: Arc<Mutex<u32>>,
+}
+
+#[async_trait]
+impl ChatModel for EvaluatingLLM {
+    fn model(&self) -> &str {
+        "evaluating"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, _messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        let mut calls = self.calls.lock().unwrap();
+        *calls += 1;
+        let action = if *calls == 1 {
+            json!({ "action_type": "evaluate", "params": { "expression": MUTATING } })
+        } else {
+            json!({ "action_type": "done", "params": { "text": "Done" } })
+        };
+        Ok(ChatInvokeCompletion::new(
+            json!({ "action": [action] }).to_string(),
+        ))
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn test_tools_config_policy_survives_later_settings() {
+    let (client, _) = fake_cdp(Box::new(|method, _| match method {
+        "DOM.getDocument" => Ok(json!({
+            "root": { "nodeId": 1, "backendNodeId": 1, "nodeType": 9, "nodeName": "#document" }
+        })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let history = Agent::new(
+        "Add a paragraph".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        EvaluatingLLM::default(),
+    )
+    .with_tools_config(&ToolsConfig {
+        evaluate_policy: Some(EvaluatePolicy::ReadOnly),
+        ..Default::default()
+    })
+    .with_settings(AgentSettings::default())
+    .with_max_steps(3)
+    .run()
+    .await
+    .unwrap();
+
+    let error = history.history[0].result[0].error.as_deref().unwrap();
+    assert!(error.contains("Read-only evaluate policy"), "{error}");
+}
@@ -0,0 +1,76 @@
+//! Tests for JavaScript evaluation with a timeout
+
+mod common;
+
+use browsing::actor::Page;
+use browsing::browser::cdp::CdpClient;
+use browsing::error::BrowsingError;
+use common::{fake_cdp, fake_cdp_with_latency, methods};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_slow_evaluation_is_terminated() {
+    // Every command takes 300ms, like a page stuck in a long script
+    let (url, received) = fake_cdp_with_latency(
+        Box::new(|_, _| Ok(json!({ "result": { "value": "done" } }))),
+        Duration::from_millis(300),
+    )
+    .await;
+    let mut client = CdpClient::new(url);
+    client.start().await.unwrap();
+    let page = Page::new(Arc::new(client), "S1".to_string());
+
+    let err = page
+        .evaluate_with_timeout("while (true) {}", 50)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        err,
+        BrowsingError::Dom(ref msg) if msg == "JavaScript evaluation timed out after 50ms"
+    ));
+    assert_eq!(
+        methods(&received),
+        ["Runtime.evaluate", "Runtime.terminateExecution"]
+    );
+    let received = received.lock().unwrap();
+    assert_eq!(received[1].2.as_deref(), Some("S1"));
+}
+
+#[tokio::test]
+async fn test_fast_evaluation_returns_its_value() {
+    let (client, received) = fake_cdp(Box::new(|_, _| {
+        Ok(json!({ "result": { "value": "Example Domain" } }))
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    assert_eq!(
+        page.evaluate_with_timeout("document.title", 1000)
+            .await
+            .unwrap(),
+        "Example Domain"
+    );
+    assert_eq!(
+        page.evaluate_safe("document.title")
+            .await
+            .unwrap()
+            .as_deref(),
+        Some("Example Domain")
+    );
+    assert_eq!(methods(&received), ["Runtime.evaluate", "Runtime.evaluate"]);
+}
+
+#[tokio::test]
+async fn test_script_errors_are_not_timeouts() {
+    let (client, _) = fake_cdp(Box::new(|_, _| {
+        Ok(json!({ "exceptionDetails": { "text": "Uncaught ReferenceError" } }))
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    // An evaluation that fails is an error even for evaluate_safe
+    assert!(page.evaluate_safe("missing()").await.is_err());
+}
@@ -0,0 +1,76 @@
+//! Tests for filling forms with form_autofill
+
+mod common;
+
+use browsing::browser::{Browser, BrowserProfile};
+use browsing::tools::Tools;
+use browsing::traits::BrowserClient;
+use common::serve_html;
+use serde_json::json;
+use std::time::Duration;
+
+const SHIPPING_FORM: &str = r#"<html><body><form id="shipping">
+<input type="text" name="name">
+<input type="radio" name="speed" value="standard" id="standard" checked><label for="standard">Standard</label>
+<input type="radio" name="speed" value="express" id="express"><label for="express">Express</label>
+<input type="radio" name="speed" value="overnight" id="overnight"><label for="overnight">Overnight</label>
+</form></body></html>"#;
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_radio_is_chosen_by_value_or_label() {
+    let url = serve_html(SHIPPING_FORM).await;
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.unwrap();
+    browser.navigate(&url).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let page = browser.get_page().unwrap();
+    let checked = || async {
+        page.evaluate("document.querySelector('input[name=speed]:checked').value")
+            .await
+            .unwrap()
+    };
+    let autofill = |values: serde_json::Value| {
+        serde_json::from_value(json!({
+            "action_type": "form_autofill",
+            "params": { "values": values }
+        }))
+        .unwrap()
+    };
+    let tools = Tools::default();
+
+    tools
+        .act(
+            autofill(json!({ "name": "Ada", "speed": "express" })),
+            &mut browser,
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(checked().await, "express");
+
+    tools
+        .act(
+            autofill(json!({ "name": "Ada", "speed": "Overnight" })),
+            &mut browser,
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(checked().await, "overnight");
+
+    // No radio of the group matches, so nothing changes
+    let result = tools
+        .act(
+            autofill(json!({ "name": "Ada", "speed": "by drone" })),
+            &mut browser,
+            None,
+        )
+        .await
+        .unwrap();
+    let memory = result.long_term_memory.unwrap_or_default();
+    assert!(memory.contains("not filled: speed"), "{memory}");
+    assert_eq!(checked().await, "overnight");
+
+    browser.stop().await.unwrap();
+}
@@ -0,0 +1,192 @@
+//! Tests for form membership in the serialized DOM and the submit_form action
+
+mod common;
+
+use browsing::dom::{DOMInteractedElement, DOMProcessorImpl};
+use browsing::tools::Tools;
+use browsing::tools::views::ActionModel;
+use browsing::traits::DOMProcessor;
+use common::{
+    FakePageBrowser, Responder, document_from_html, fake_cdp, fake_cdp_with_events, methods,
+};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+const TWO_FORMS: &str = include_str!("fixtures/forms/two_forms.html");
+
+/// Serialized page and selector map of the two-forms fixture
+async fn serialize_fixture() -> (String, HashMap<u32, DOMInteractedElement>) {
+    let (client, _) = fake_cdp(Box::new(|method, _| match method {
+        "DOM.getDocument" => Ok(document_from_html(TWO_FORMS)),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let state = DOMProcessorImpl::new()
+        .with_cdp_client(client, "S1".to_string())
+        .with_target_id("T1".to_string())
+        .get_serialized_dom()
+        .await
+        .unwrap();
+    (state.page_state(), state.selector_map)
+}
+
+/// Submit the form of element 3 (the email field) with `respond` answering CDP
+async fn submit(respond: Responder, events: Vec<Value>) -> (Option<String>, Option<String>) {
+    let (_, selector_map) = serialize_fixture().await;
+    let (client, received) = fake_cdp_with_events(
+        respond,
+        Box::new(move |method, params| {
+            let submits = params["functionDeclaration"]
+                .as_str()
+                .is_some_and(|f| f.contains("requestSubmit"));
+            if method == "Runtime.callFunctionOn" && submits {
+                events.clone()
+            } else {
+                vec![]
+            }
+        }),
+    )
+    .await;
+    let mut browser = FakePageBrowser { client };
+    let action: ActionModel = serde_json::from_value(json!({
+        "action_type": "submit_form",
+        "params": { "index": 3 }
+    }))
+    .unwrap();
+
+    let result = Tools::default()
+        .act(action, &mut browser, Some(&selector_map))
+        .await
+        .unwrap();
+    assert!(methods(&received).contains(&"Network.enable".to_string()));
+    (result.long_term_memory, result.error)
+}
+
+/// Answers `DOM.resolveNode` and `requestSubmit()` with `status`
+fn submitting(status: &'static str) -> Responder {
+    Box::new(move |method, _| match method {
+        "DOM.resolveNode" => Ok(json!({ "object": { "objectId": "email-1" } })),
+        "Runtime.callFunctionOn" => Ok(json!({ "result": { "value": {
+            "status": status, "field": "email", "message": "Please include an '@'."
+        } } })),
+        _ => Ok(json!({})),
+    })
+}
+
+fn event(method: &str, params: Value) -> Value {
+    json!({ "method": method, "params": params, "sessionId": "S1" })
+}
+
+#[tokio::test]
+async fn test_elements_are_marked_with_their_form() {
+    let (state, selector_map) = serialize_fixture().await;
+
+    let form_of = |text: &str| {
+        selector_map
+            .values()
+            .find(|element| element.text.as_deref() == Some(text))
+            .unwrap_or_else(|| panic!("no element {text}"))
+            .form_id
+    };
+    assert_eq!(form_of("Search products"), Some(1));
+    assert_eq!(form_of("Email"), Some(2));
+    assert_eq!(form_of("Password"), Some(2));
+    // Associated through its `form` attribute, outside the form element
+    let remember = selector_map
+        .values()
+        .find(|element| element.attributes.get("name").map(String::as_str) == Some("remember"))
+        .unwrap();
+    assert_eq!(remember.form_id, Some(2));
+    let help = selector_map
+        .values()
+        .find(|element| element.tag == "a")
+        .unwrap();
+    assert_eq!(help.form_id, None);
+
+    let line = |needle: &str| {
+        state
+            .lines()
+            .find(|line| line.contains(needle))
+            .unwrap_or_else(|| panic!("no line with {needle} in {state}"))
+            .trim()
+            .to_string()
+    };
+    assert!(line("Search products").ends_with("form=#1"), "{state}");
+    assert!(line("Password").ends_with("form=#2"), "{state}");
+    assert_eq!(line("a ["), "a [7]");
+}
+
+#[tokio::test]
+async fn test_submit_form_reports_xhr() {
+    let events = vec![event(
+        "Network.requestWillBeSent",
+        json!({ "requestId": "1", "type": "XHR", "request": { "url": "https://example.com/session" } }),
+    )];
+
+    let (memory, error) = submit(submitting("submitted"), events).await;
+
+    assert!(error.is_none(), "{error:?}");
+    assert_eq!(
+        memory.as_deref(),
+        Some("Submitted form #2 of element 3 with requestSubmit(); an XHR was sent")
+    );
+}
+
+#[tokio::test]
+async fn test_submit_form_reports_navigation() {
+    let events = vec![
+        // Subframes navigating do not count
+        event(
+            "Page.frameNavigated",
+            json!({ "frame": { "id": "F2", "parentId": "T1", "url": "https://ads.example/" } }),
+        ),
+        event(
+            "Page.frameNavigated",
+            json!({ "frame": { "id": "T1", "url": "https://example.com/account" } }),
+        ),
+    ];
+
+    let (memory, _) = submit(submitting("submitted"), events).await;
+
+    assert!(
+        memory
+            .unwrap()
+            .ends_with("the page navigated to https://example.com/account")
+    );
+}
+
+#[tokio::test]
+async fn test_submit_form_falls_back_to_submit_button() {
+    let respond: Responder = Box::new(|method, call| match (method, call) {
+        ("DOM.resolveNode", _) => Ok(json!({ "object": { "objectId": "email-1" } })),
+        ("Runtime.callFunctionOn", 1) => {
+            Ok(json!({ "result": { "value": { "status": "unsupported" } } }))
+        }
+        ("Runtime.callFunctionOn", _) => Ok(json!({ "result": { "objectId": "button-1" } })),
+        ("DOM.describeNode", _) => Ok(json!({ "node": { "backendNodeId": 42 } })),
+        ("DOM.getContentQuads", _) => Ok(json!({ "quads": [[10, 10, 90, 10, 90, 30, 10, 30]] })),
+        _ => Ok(json!({})),
+    });
+
+    let (memory, error) = submit(respond, vec![]).await;
+
+    assert!(error.is_none(), "{error:?}");
+    assert_eq!(
+        memory.as_deref(),
+        Some(
+            "Submitted form #2 of element 3 with its submit button; no navigation or XHR followed"
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_form_is_not_submitted() {
+    let (memory, error) = submit(submitting("invalid"), vec![]).await;
+
+    let error = error.unwrap();
+    assert!(
+        error.contains("field 'email' is invalid: Please include an '@'."),
+        "{error}"
+    );
+    assert_eq!(memory.as_deref(), Some(error.as_str()));
+}
@@ -0,0 +1,330 @@
+//! Tests for acting on elements inside same-origin iframes
+
+mod common;
+
+use browsing::agent::views::ActionResult;
+use browsing::browser::{Browser, BrowserProfile, FrameContexts};
+use browsing::dom::DOMProcessorImpl;
+use browsing::dom::views::DOMInteractedElement;
+use browsing::tools::Tools;
+use browsing::traits::{BrowserClient, DOMProcessor};
+use common::{
+    FakePageBrowser, HttpResponse, Received, fake_cdp, fake_cdp_with_events, methods, serve_http,
+};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::time::Duration;
+
+const OUTER: &str = include_str!("fixtures/iframe/outer.html");
+const FRAME: &str = include_str!("fixtures/iframe/frame.html");
+
+fn element(id: u64, name: &str, attributes: Value, children: Vec<Value>) -> Value {
+    json!({
+        "nodeId": id, "backendNodeId": id, "nodeType": 1, "nodeName": name.to_uppercase(),
+        "localName": name, "attributes": attributes, "children": children,
+    })
+}
+
+/// A page with a search box and an iframe (frame F2) holding a card field
+fn document_with_iframe() -> Value {
+    let mut iframe = element(5, "iframe", json!(["id", "payment"]), vec![]);
+    iframe["frameId"] = json!("F2");
+    iframe["contentDocument"] = json!({
+        "nodeId": 10, "backendNodeId": 10, "nodeType": 9, "nodeName": "#document",
+        "children": [element(11, "html", json!([]), vec![
+            element(12, "body", json!([]), vec![
+                element(13, "input", json!(["id", "card"]), vec![]),
+            ]),
+        ])],
+    });
+    json!({ "root": {
+        "nodeId": 1, "backendNodeId": 1, "nodeType": 9, "nodeName": "#document",
+        "children": [element(2, "html", json!([]), vec![
+            element(3, "body", json!([]), vec![
+                element(4, "input", json!(["id", "search"]), vec![]),
+                iframe,
+            ]),
+        ])],
+    } })
+}
+
+fn context_created(id: i64, frame_id: &str, is_default: bool) -> Value {
+    json!({
+        "method": "Runtime.executionContextCreated",
+        "sessionId": "S1",
+        "params": { "context": {
+            "id": id,
+            "auxData": { "frameId": frame_id, "isDefault": is_default },
+        } },
+    })
+}
+
+/// Run `action_type` on element 5, which is in frame `frame_id`, on a fake
+/// page whose `Runtime.enable` reports contexts for the top frame and frame
+/// F2; functions called on the element return `returned`
+async fn act_in_frame(
+    frame_id: Option<&str>,
+    action_type: &str,
+    params: Value,
+    returned: Value,
+) -> (browsing::error::Result<ActionResult>, Received) {
+    let (client, received) = fake_cdp_with_events(
+        Box::new(move |method, _| match method {
+            "DOM.resolveNode" => Ok(json!({ "object": { "objectId": "input-1" } })),
+            "Runtime.callFunctionOn" => Ok(json!({ "result": { "value": returned } })),
+            _ => Ok(json!({})),
+        }),
+        Box::new(|method, _| match method {
+            "Runtime.enable" => vec![
+                context_created(1, "F1", true),
+                context_created(7, "F2", true),
+                context_created(9, "F2", false),
+            ],
+            _ => vec![],
+        }),
+    )
+    .await;
+    let mut browser = FakePageBrowser { client };
+    let selector_map = HashMap::from([(
+        5,
+        DOMInteractedElement {
+            index: 5,
+            backend_node_id: Some(13),
+            tag: "input".to_string(),
+            text: None,
+            attributes: HashMap::new(),
+            selector: None,
+            bounds: None,
+            form_id: None,
+            frame_id: frame_id.map(str::to_string),
+        },
+    )]);
+    let action = serde_json::from_value(json!({
+        "action_type": action_type,
+        "params": params
+    }))
+    .unwrap();
+    let result = Tools::default()
+        .act(action, &mut browser, Some(&selector_map))
+        .await;
+    (result, received)
+}
+
+/// Input "Ada" into element 5, which is in frame `frame_id`
+async fn input_in_frame(frame_id: Option<&str>) -> Received {
+    let params = json!({ "index": 5, "text": "Ada" });
+    let (result, received) = act_in_frame(frame_id, "input", params, Value::Null).await;
+    result.unwrap();
+    received
+}
+
+fn resolve_params(received: &Received) -> Value {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(method, _, _)| method == "DOM.resolveNode")
+        .map(|(_, params, _)| params.clone())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_iframe_elements_carry_their_frame() {
+    let (client, _) = fake_cdp(Box::new(|method, _| match method {
+        "DOM.getDocument" => Ok(document_with_iframe()),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let state = DOMProcessorImpl::new()
+        .with_cdp_client(client, "S1".to_string())
+        .with_target_id("T1".to_string())
+        .get_serialized_dom()
+        .await
+        .unwrap();
+
+    let by_id = |id: &str| {
+        state
+            .selector_map
+            .values()
+            .find(|e| e.attributes.get("id").map(String::as_str) == Some(id))
+            .unwrap_or_else(|| panic!("no element #{id}: {:?}", state.selector_map))
+    };
+    assert_eq!(by_id("card").frame_id.as_deref(), Some("F2"));
+    assert_eq!(by_id("search").frame_id, None);
+}
+
+#[tokio::test]
+async fn test_iframe_element_is_resolved_in_its_frame_context() {
+    let received = input_in_frame(Some("F2")).await;
+
+    let params = resolve_params(&received);
+    assert_eq!(params["backendNodeId"], 13);
+    assert_eq!(params["executionContextId"], 7);
+    let filled = received.lock().unwrap().iter().any(|(method, params, _)| {
+        method == "Runtime.callFunctionOn" && params["arguments"][0]["value"] == "Ada"
+    });
+    assert!(filled);
+}
+
+#[tokio::test]
+async fn test_top_level_element_uses_the_default_context() {
+    let received = input_in_frame(None).await;
+
+    assert!(
+        resolve_params(&received)
+            .get("executionContextId")
+            .is_none()
+    );
+    assert!(
+        !received
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(method, _, _)| method == "Runtime.enable")
+    );
+}
+
+#[tokio::test]
+async fn test_dropdown_in_iframe_is_read_in_its_frame() {
+    let options = json!([
+        { "value": "us", "text": "United States", "selected": true },
+        { "value": "ca", "text": "Canada", "selected": false },
+    ]);
+    let (result, received) = act_in_frame(
+        Some("F2"),
+        "dropdown_options",
+        json!({ "index": 5 }),
+        options,
+    )
+    .await;
+
+    let result = result.unwrap();
+    assert_eq!(
+        result.extracted_content.as_deref(),
+        Some("1. United States (value: us)\n2. Canada (value: ca)")
+    );
+    let params = resolve_params(&received);
+    assert_eq!(params["backendNodeId"], 13);
+    assert_eq!(params["executionContextId"], 7);
+    assert!(!methods(&received).contains(&"Runtime.evaluate".to_string()));
+}
+
+#[tokio::test]
+async fn test_select_dropdown_selects_in_its_frame() {
+    let selected = json!({ "option": { "value": "ca", "text": "Canada", "selected": true } });
+    let (result, received) = act_in_frame(
+        Some("F2"),
+        "select_dropdown",
+        json!({ "index": 5, "text": "Canada" }),
+        selected,
+    )
+    .await;
+
+    let result = result.unwrap();
+    assert_eq!(
+        result.extracted_content.as_deref(),
+        Some("Selected option: Canada (value: ca)")
+    );
+    assert_eq!(resolve_params(&received)["executionContextId"], 7);
+    let called = received.lock().unwrap().iter().any(|(method, params, _)| {
+        method == "Runtime.callFunctionOn" && params["arguments"][0]["value"] == "Canada"
+    });
+    assert!(called);
+}
+
+#[tokio::test]
+async fn test_dropdown_actions_fail_on_other_elements() {
+    let (result, _) =
+        act_in_frame(None, "dropdown_options", json!({ "index": 5 }), Value::Null).await;
+    assert!(result.unwrap_err().to_string().contains("not a select"));
+
+    let not_select = json!({ "error": "not_select" });
+    let params = json!({ "index": 5, "text": "Canada" });
+    let (result, received) = act_in_frame(None, "select_dropdown", params, not_select).await;
+    assert!(result.unwrap_err().to_string().contains("not a select"));
+    // Nothing else on the page was looked for
+    assert!(!methods(&received).contains(&"Runtime.evaluate".to_string()));
+}
+
+#[test]
+fn test_frame_contexts_follow_runtime_events() {
+    let mut contexts = FrameContexts::new();
+    contexts.apply_event(&context_created(7, "F2", true));
+    contexts.apply_event(&context_created(9, "F2", false));
+    assert_eq!(contexts.get("S1", "F2"), Some(7));
+    assert_eq!(contexts.get("S2", "F2"), None);
+
+    contexts.apply_event(&json!({
+        "method": "Runtime.executionContextDestroyed",
+        "sessionId": "S1",
+        "params": { "executionContextId": 7 },
+    }));
+    assert_eq!(contexts.get("S1", "F2"), None);
+
+    contexts.apply_event(&context_created(8, "F2", true));
+    contexts.apply_event(&json!({
+        "method": "Runtime.executionContextsCleared",
+        "sessionId": "S1",
+        "params": {},
+    }));
+    assert!(contexts.is_empty());
+}
+
+async fn serve_fixture() -> String {
+    serve_http(|request| {
+        HttpResponse::html(if request.path().starts_with("/frame") {
+            FRAME
+        } else {
+            OUTER
+        })
+    })
+    .await
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_input_into_same_origin_iframe() {
+    let url = serve_fixture().await;
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.unwrap();
+    browser.navigate(&url).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let info = browser.get_session_info().await.unwrap();
+    let state = DOMProcessorImpl::new()
+        .with_cdp_client(browser.get_cdp_client().unwrap(), info.session_id.clone())
+        .with_target_id(info.target_id.clone())
+        .get_serialized_dom()
+        .await
+        .unwrap();
+    let card = state
+        .selector_map
+        .values()
+        .find(|e| e.attributes.get("id").map(String::as_str) == Some("card"))
+        .expect("the iframe's input is in the selector map");
+    assert!(card.frame_id.is_some());
+
+    let action = serde_json::from_value(json!({
+        "action_type": "input",
+        "params": { "index": card.index, "text": "Ada Lovelace" }
+    }))
+    .unwrap();
+    Tools::default()
+        .act(action, &mut browser, Some(&state.selector_map))
+        .await
+        .unwrap();
+
+    let page = browser.get_page().unwrap();
+    let frame = "document.getElementById('payment').contentWindow";
+    let value = page
+        .evaluate(&format!("{frame}.document.getElementById('card').value"))
+        .await
+        .unwrap();
+    assert_eq!(value, "Ada Lovelace");
+    let received = page
+        .evaluate(&format!("JSON.stringify({frame}.received)"))
+        .await
+        .unwrap();
+    assert_eq!(received, r#"[{"value":"Ada Lovelace","inFrame":true}]"#);
+    browser.stop().await.unwrap();
+}
@@ -0,0 +1,247 @@
+//! Tests for listing and downloading page images
+
+mod common;
+
+use browsing::actor::{ImageInfo, ImageListOptions, Page};
+use browsing::browser::{Browser, BrowserProfile};
+use browsing::tools::Tools;
+use browsing::tools::handlers::IMAGES_METADATA_KEY;
+use common::{FakePageBrowser, HttpResponse, fake_cdp, serve_http};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const GALLERY: &str = include_str!("fixtures/images/gallery.html");
+
+/// A 1x1 transparent PNG
+const PNG: &[u8] = &[
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1f, 0x15, 0xc4,
+    0x89, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0x00, 0x01, 0x00, 0x00,
+    0x05, 0x00, 0x01, 0x0d, 0x0a, 0x2d, 0xb4, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae,
+    0x42, 0x60, 0x82,
+];
+
+/// What the listing script reports for the gallery fixture
+fn listed_images(base: &str) -> Value {
+    let image = |index: usize, src: String, size: (u32, u32), alt: &str, caption: Option<&str>| {
+        json!({
+            "index": index, "src": src, "alt": alt, "caption": caption,
+            "width": size.0, "height": size.1, "natural_width": 1, "natural_height": 1,
+            "loading": if src.ends_with("lazy.png") { Some("lazy") } else { None },
+        })
+    };
+    json!([
+        image(0, format!("{base}pixel.gif"), (1, 1), "", None),
+        image(1, format!("{base}beacon.gif"), (1, 1), "", None),
+        image(
+            2,
+            format!("{base}hero.png"),
+            (320, 200),
+            "Hero shot",
+            Some("The new kettle, in brushed steel")
+        ),
+        image(
+            3,
+            format!("data:image/png;base64,{}", "A".repeat(200)),
+            (300, 200),
+            "Placeholder",
+            Some("Placeholder")
+        ),
+        image(
+            4,
+            format!("{base}lazy.png"),
+            (240, 160),
+            "Below the fold",
+            Some("Below the fold")
+        ),
+    ])
+}
+
+/// Serve the gallery and a PNG for any other path, recording request heads
+async fn serve_fixture() -> (String, Arc<Mutex<Vec<String>>>) {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&requests);
+    let url = serve_http(move |request| {
+        log.lock().unwrap().push(request.head.clone());
+        if request.path() == "/" {
+            HttpResponse::html(GALLERY)
+        } else {
+            HttpResponse::ok("image/png", PNG)
+        }
+    })
+    .await;
+    (url, requests)
+}
+
+#[tokio::test]
+async fn test_extract_images_skips_pixels_and_data_uris() {
+    let base = "https://shop.example/";
+    let (client, _) = fake_cdp(Box::new(move |method, _| match method {
+        "Runtime.evaluate" => Ok(json!({ "result": { "value": listed_images(base) } })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let mut browser = FakePageBrowser { client };
+    let action =
+        serde_json::from_value(json!({ "action_type": "extract_images", "params": {} })).unwrap();
+
+    let result = Tools::default()
+        .act(action, &mut browser, None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        result.extracted_content.as_deref(),
+        Some(
+            "[2] https://shop.example/hero.png 320x200 (natural 1x1) \"The new kettle, in brushed steel\"\n\
+             [4] https://shop.example/lazy.png 240x160 (natural 1x1) lazy \"Below the fold\""
+        )
+    );
+    assert_eq!(result.long_term_memory.as_deref(), Some("Found 2 images"));
+    let images: Vec<ImageInfo> =
+        serde_json::from_value(result.metadata.unwrap()[IMAGES_METADATA_KEY].clone()).unwrap();
+    assert_eq!(images.len(), 2);
+    assert_eq!(images[1].loading.as_deref(), Some("lazy"));
+}
+
+#[tokio::test]
+async fn test_image_filters() {
+    let base = "https://shop.example/";
+    let (client, _) = fake_cdp(Box::new(move |method, _| match method {
+        "Runtime.evaluate" => Ok(json!({ "result": { "value": listed_images(base) } })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    let large = page
+        .list_images(&ImageListOptions {
+            min_width: 300,
+            min_height: 0,
+            include_data_uris: true,
+        })
+        .await
+        .unwrap();
+
+    let indices: Vec<usize> = large.iter().map(|i| i.index).collect();
+    assert_eq!(indices, vec![2, 3]);
+    assert!(large[1].is_data_uri);
+    assert!(large[1].src.ends_with('…'));
+}
+
+#[tokio::test]
+async fn test_download_largest_images_with_cookies() {
+    let (base, requests) = serve_fixture().await;
+    let page_url = format!("{base}kettles#reviews");
+    let referer = format!("referer: {base}kettles\r\n");
+    let (client, _) = fake_cdp(Box::new(move |method, call| match method {
+        "Runtime.evaluate" if call == 1 => {
+            Ok(json!({ "result": { "value": listed_images(&base) } }))
+        }
+        // The page the images are downloaded from
+        "Runtime.evaluate" => Ok(json!({ "result": { "value": {
+            "url": page_url, "userAgent": "Mozilla/5.0 Kettle"
+        } } })),
+        "Network.getCookies" => Ok(json!({ "cookies": [
+            { "name": "session", "value": "abc", "domain": "localhost", "path": "/" }
+        ] })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut images = page
+        .list_images(&ImageListOptions {
+            include_data_uris: true,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let saved = page
+        .download_images(&mut images, dir.path(), 1, false)
+        .await
+        .unwrap();
+
+    // The data URI is the largest image but is never downloaded
+    assert_eq!(saved, 1);
+    let hero = images.iter().find(|i| i.index == 2).unwrap();
+    let path = hero.saved_path.as_ref().unwrap();
+    assert_eq!(path, &dir.path().join("image-2.png"));
+    assert_eq!(std::fs::read(path).unwrap(), PNG);
+    assert!(
+        images
+            .iter()
+            .filter(|i| i.index != 2)
+            .all(|i| i.saved_path.is_none())
+    );
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].starts_with("GET /hero.png "));
+    assert!(
+        requests[0].contains("cookie: session=abc"),
+        "{}",
+        requests[0]
+    );
+    assert!(requests[0].contains("user-agent: Mozilla/5.0 Kettle"), "{}", requests[0]);
+    assert!(requests[0].contains(&referer), "{}", requests[0]);
+}
+
+#[tokio::test]
+async fn test_download_keeps_existing_files_without_overwrite() {
+    let (base, _) = serve_fixture().await;
+    let (client, _) = fake_cdp(Box::new(move |method, _| match method {
+        "Runtime.evaluate" => Ok(json!({ "result": { "value": listed_images(&base) } })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("image-2.png"), "kept").unwrap();
+    let mut images = page.list_images(&Default::default()).await.unwrap();
+
+    let error = page
+        .download_images(&mut images, dir.path(), 1, false)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("already exists"), "{error}");
+    assert_eq!(
+        std::fs::read(dir.path().join("image-2.png")).unwrap(),
+        b"kept"
+    );
+
+    let saved = page
+        .download_images(&mut images, dir.path(), 1, true)
+        .await
+        .unwrap();
+    assert_eq!(saved, 1);
+    assert_eq!(std::fs::read(dir.path().join("image-2.png")).unwrap(), PNG);
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_gallery_in_chrome() {
+    let (url, _) = serve_fixture().await;
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.unwrap();
+    browser.navigate(&url).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let page = browser.get_page().unwrap();
+
+    let images = page
+        .list_images(&ImageListOptions::default())
+        .await
+        .unwrap();
+
+    let indices: Vec<usize> = images.iter().map(|i| i.index).collect();
+    assert_eq!(indices, vec![2, 4]);
+    assert_eq!(
+        images[0].caption.as_deref(),
+        Some("The new kettle, in brushed steel")
+    );
+    assert_eq!((images[0].width, images[0].height), (320, 200));
+    assert_eq!(images[1].loading.as_deref(), Some("lazy"));
+
+    browser.stop().await.unwrap();
+}
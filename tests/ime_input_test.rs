@@ -0,0 +1,158 @@
+//! Tests for typing text through IME composition
+
+mod common;
+
+use browsing::browser::{Browser, BrowserProfile};
+use browsing::tools::Tools;
+use common::{FakePageBrowser, Received, fake_cdp, methods, serve_html};
+use serde_json::{Value, json};
+use std::time::Duration;
+
+const CONTROLLED_INPUT: &str = include_str!("fixtures/ime/controlled_input.html");
+
+/// Input `params` into element 5 of a fake page
+async fn input(params: Value) -> (String, Received) {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "DOM.resolveNode" => Ok(json!({ "object": { "objectId": "input-1" } })),
+        "DOM.pushNodesByBackendIdsToFrontend" => Ok(json!({ "nodeIds": [12] })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let mut browser = FakePageBrowser { client };
+    let action =
+        serde_json::from_value(json!({ "action_type": "input", "params": params })).unwrap();
+    let result = Tools::default()
+        .act(action, &mut browser, None)
+        .await
+        .unwrap();
+    (result.long_term_memory.unwrap_or_default(), received)
+}
+
+/// Text inserted with `Input.insertText`, and the composition events around it
+fn typed(received: &Received) -> (Vec<String>, Vec<String>) {
+    let received = received.lock().unwrap();
+    let inserted = received
+        .iter()
+        .filter(|(method, _, _)| method == "Input.insertText")
+        .map(|(_, params, _)| params["text"].as_str().unwrap().to_string())
+        .collect();
+    let compositions = received
+        .iter()
+        .filter_map(|(_, params, _)| {
+            let arguments = params["arguments"].as_array()?;
+            let event_type = arguments.first()?["value"].as_str()?;
+            let data = arguments.get(1)?["value"].as_str()?;
+            Some(format!("{event_type}:{data}"))
+        })
+        .collect();
+    (inserted, compositions)
+}
+
+#[tokio::test]
+async fn test_japanese_is_composed_per_character() {
+    let (memory, received) = input(json!({ "index": 5, "text": "東京" })).await;
+
+    let (inserted, compositions) = typed(&received);
+    assert_eq!(inserted, ["東", "京"]);
+    assert_eq!(
+        compositions,
+        [
+            "compositionstart:",
+            "compositionupdate:東",
+            "compositionend:東",
+            "compositionstart:",
+            "compositionupdate:京",
+            "compositionend:京",
+        ]
+    );
+    assert!(memory.contains("IME composition"), "{memory}");
+    let calls = methods(&received);
+    assert!(
+        !calls.contains(&"Runtime.evaluate".to_string()),
+        "{calls:?}"
+    );
+    assert_eq!(
+        calls.last().map(String::as_str),
+        Some("Runtime.releaseObjectGroup")
+    );
+}
+
+#[tokio::test]
+async fn test_emoji_sequences_are_inserted_whole() {
+    let (_, received) =
+        input(json!({ "index": 5, "text": "👍🏽👨\u{200d}👩\u{200d}👧", "ime": true })).await;
+
+    let (inserted, _) = typed(&received);
+    assert_eq!(inserted, ["👍🏽", "👨\u{200d}👩\u{200d}👧"]);
+}
+
+#[tokio::test]
+async fn test_ascii_is_filled_directly() {
+    let (memory, received) = input(json!({ "index": 5, "text": "laptop" })).await;
+
+    assert!(typed(&received).0.is_empty());
+    let filled = received.lock().unwrap().iter().any(|(method, params, _)| {
+        method == "Runtime.callFunctionOn" && params["arguments"][0]["value"] == "laptop"
+    });
+    assert!(filled, "{:?}", methods(&received));
+    assert!(!memory.contains("IME"), "{memory}");
+}
+
+#[tokio::test]
+async fn test_accented_text_and_emoji_are_filled_directly() {
+    let (memory, received) = input(json!({ "index": 5, "text": "café 👍" })).await;
+
+    assert!(typed(&received).0.is_empty());
+    let filled = received.lock().unwrap().iter().any(|(method, params, _)| {
+        method == "Runtime.callFunctionOn" && params["arguments"][0]["value"] == "café 👍"
+    });
+    assert!(filled, "{:?}", methods(&received));
+    assert!(!memory.contains("IME"), "{memory}");
+}
+
+#[tokio::test]
+async fn test_ime_param_overrides_detection() {
+    let (_, received) = input(json!({ "index": 5, "text": "東京", "ime": false })).await;
+    assert!(typed(&received).0.is_empty());
+
+    let (_, received) = input(json!({ "index": 5, "text": "tea", "ime": true })).await;
+    assert_eq!(typed(&received).0, ["t", "e", "a"]);
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_japanese_and_emoji_into_controlled_input() {
+    let url = serve_html(CONTROLLED_INPUT).await;
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.unwrap();
+    browser.navigate(&url).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let page = browser.get_page().unwrap();
+    let field = page.get_elements_by_css_selector("#name").await.unwrap();
+    let text = "こんにちは、世界 👋🏽";
+    let action = serde_json::from_value(json!({
+        "action_type": "input",
+        "params": { "index": field[0].backend_node_id(), "text": text }
+    }))
+    .unwrap();
+
+    Tools::default()
+        .act(action, &mut browser, None)
+        .await
+        .unwrap();
+
+    let state = page.evaluate("window.state.value").await.unwrap();
+    assert_eq!(state, text);
+    let value = page
+        .evaluate("document.getElementById('name').value")
+        .await
+        .unwrap();
+    assert_eq!(value, text);
+    let events = page.evaluate("window.events.join(',')").await.unwrap();
+    assert!(
+        events.contains("compositionstart,input,compositionend"),
+        "{events}"
+    );
+    assert!(events.ends_with("change"), "{events}");
+    browser.stop().await.unwrap();
+}
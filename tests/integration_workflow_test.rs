@@ -27,6 +27,11 @@ mod integration_workflow {
             allowed_domains: None, // Allow all domains
             downloads_path: Some("/tmp/browser_downloads".into()),
             proxy: None,
+            attach_workers: false,
+            stealth: false,
+            stealth_options: Default::default(),
+            disable_cache: false,
+            emulation: Default::default(),
         };
 
         let browser = Box::new(Browser::new(profile));
@@ -0,0 +1,116 @@
+//! Tests for time-lapse screenshots
+
+mod common;
+
+use base64::Engine as _;
+use browsing::actor::Page;
+use browsing::browser::{Browser, BrowserProfile, IntervalScreenshotHandle};
+use browsing::error::BrowsingError;
+use common::{fake_cdp, methods};
+use serde_json::json;
+use std::time::Duration;
+
+const PNG: &[u8] = b"\x89PNG\r\n\x1a\nfake";
+
+fn screenshot() -> serde_json::Value {
+    json!({ "data": base64::engine::general_purpose::STANDARD.encode(PNG) })
+}
+
+#[tokio::test]
+async fn test_takes_requested_number_of_screenshots() {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(screenshot()))).await;
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut handle = IntervalScreenshotHandle::start(
+        Page::new(client, "S1".to_string()),
+        Duration::from_millis(20),
+        3,
+        dir.path().to_path_buf(),
+    );
+    let paths = handle.wait().await.unwrap();
+
+    assert_eq!(paths.len(), 3);
+    assert!(handle.is_finished());
+    assert_eq!(handle.get_latest().as_ref(), paths.last());
+    for path in &paths {
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert!(
+            name.starts_with("screenshot_") && name.ends_with(".png"),
+            "{name}"
+        );
+        assert_eq!(path.parent().unwrap(), dir.path());
+        assert_eq!(std::fs::read(path).unwrap(), PNG);
+    }
+}
+
+#[tokio::test]
+async fn test_stop_returns_saved_paths() {
+    let (client, received) = fake_cdp(Box::new(|_, _| Ok(screenshot()))).await;
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut handle = IntervalScreenshotHandle::start(
+        Page::new(client, "S1".to_string()),
+        Duration::from_millis(50),
+        1000,
+        dir.path().to_path_buf(),
+    );
+    tokio::time::sleep(Duration::from_millis(120)).await;
+    let paths = handle.stop().await.unwrap();
+
+    assert!(!paths.is_empty() && paths.len() < 1000);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), paths.len());
+
+    // Nothing is captured after stopping
+    let captured = methods(&received).len();
+    tokio::time::sleep(Duration::from_millis(120)).await;
+    assert_eq!(methods(&received).len(), captured);
+}
+
+#[tokio::test]
+async fn test_failed_captures_are_skipped() {
+    let (client, _) = fake_cdp(Box::new(|_, call| {
+        if call == 2 {
+            Err("Target closed".to_string())
+        } else {
+            Ok(screenshot())
+        }
+    }))
+    .await;
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut handle = IntervalScreenshotHandle::start(
+        Page::new(client, "S1".to_string()),
+        Duration::from_millis(10),
+        3,
+        dir.path().to_path_buf(),
+    );
+    assert_eq!(handle.wait().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_invalid_arguments_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut browser = Browser::new(BrowserProfile::default());
+
+    let err = browser
+        .start_interval_screenshots(0, 5, dir.path())
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(err, BrowsingError::Validation(_)));
+
+    let err = browser
+        .start_interval_screenshots(100, 0, dir.path())
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(err, BrowsingError::Validation(_)));
+
+    // Valid arguments still need a started browser
+    assert!(
+        browser
+            .start_interval_screenshots(100, 5, dir.path())
+            .await
+            .is_err()
+    );
+}
@@ -0,0 +1,241 @@
+//! Tests for activating elements from the keyboard
+
+mod common;
+
+use browsing::agent::views::ActionResult;
+use browsing::browser::{Browser, BrowserProfile};
+use browsing::config::ToolsConfig;
+use browsing::tools::Tools;
+use browsing::tools::handlers::CLICK_STRATEGY_METADATA_KEY;
+use common::{FakePageBrowser, Received, fake_cdp, serve_html};
+use serde_json::{Value, json};
+use std::time::Duration;
+
+const KEYDOWN_ONLY: &str = include_str!("fixtures/keyboard_activation/keydown_only.html");
+
+/// Fingerprint of a page after `mutations` DOM mutations
+fn fingerprint(mutations: u64) -> Value {
+    json!({ "result": { "value": {
+        "url": "https://shop.example/", "focused": null, "bodyChildren": 2,
+        "scrollX": 0, "scrollY": 0, "mutations": mutations
+    } } })
+}
+
+/// Run `action_type` on element 7 with `role`, on a page whose fingerprints
+/// have `mutations`, in call order
+async fn act(
+    tools: Tools,
+    action_type: &str,
+    role: &'static str,
+    mutations: &'static [u64],
+) -> (ActionResult, Received) {
+    let (client, received) = fake_cdp(Box::new(move |method, call| match method {
+        "Runtime.evaluate" => Ok(fingerprint(mutations[(call - 1).min(mutations.len() - 1)])),
+        "Page.getLayoutMetrics" => {
+            Ok(json!({ "layoutViewport": { "clientWidth": 1280, "clientHeight": 720 } }))
+        }
+        "DOM.getContentQuads" => Ok(json!({ "quads": [[10, 10, 90, 10, 90, 30, 10, 30]] })),
+        "DOM.resolveNode" => Ok(json!({ "object": { "objectId": "widget-1" } })),
+        "Runtime.callFunctionOn" => Ok(json!({ "result": { "value": role } })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let mut browser = FakePageBrowser { client };
+    let action = serde_json::from_value(json!({
+        "action_type": action_type,
+        "params": { "index": 7 }
+    }))
+    .unwrap();
+    let result = tools.act(action, &mut browser, None).await.unwrap();
+    (result, received)
+}
+
+/// Keys pressed, from their `keyDown` events
+fn keys_pressed(received: &Received) -> Vec<String> {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, params, _)| {
+            method == "Input.dispatchKeyEvent" && params["type"] == "keyDown"
+        })
+        .map(|(_, params, _)| params["key"].as_str().unwrap().to_string())
+        .collect()
+}
+
+fn js_clicks(received: &Received) -> usize {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, params, _)| {
+            method == "Runtime.callFunctionOn"
+                && params["functionDeclaration"]
+                    .as_str()
+                    .is_some_and(|f| f.contains("this.click()"))
+        })
+        .count()
+}
+
+fn focused(received: &Received) -> bool {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|(method, params, _)| method == "DOM.focus" && params["backendNodeId"] == 7)
+}
+
+fn strategy(result: &ActionResult) -> Option<&Value> {
+    result.metadata.as_ref()?.get(CLICK_STRATEGY_METADATA_KEY)
+}
+
+fn keyboard_fallback() -> Tools {
+    Tools::from_config(&ToolsConfig {
+        keyboard_fallback: true,
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_button_is_activated_with_enter_when_clicks_change_nothing() {
+    // Mouse click, focus: nothing; Enter adds a node
+    let (result, received) = act(keyboard_fallback(), "click", "button", &[0, 0, 0, 1]).await;
+
+    assert_eq!(strategy(&result), Some(&json!("keyboard_enter")));
+    assert!(focused(&received));
+    assert_eq!(keys_pressed(&received), ["Enter"]);
+    assert_eq!(js_clicks(&received), 0);
+    let memory = result.long_term_memory.unwrap();
+    assert!(
+        memory.ends_with("the button was focused and activated with Enter, which did"),
+        "{memory}"
+    );
+}
+
+#[tokio::test]
+async fn test_click_is_retried_only_once() {
+    // Space would change the page, but the fallback stops after Enter
+    let (result, received) = act(keyboard_fallback(), "click", "button", &[0, 0, 0, 0, 1]).await;
+
+    assert_eq!(strategy(&result), Some(&json!("none")));
+    assert_eq!(keys_pressed(&received), ["Enter"]);
+    assert_eq!(js_clicks(&received), 0);
+}
+
+#[tokio::test]
+async fn test_link_is_only_tried_with_enter() {
+    let (result, received) = act(keyboard_fallback(), "click", "link", &[0]).await;
+
+    assert_eq!(strategy(&result), Some(&json!("none")));
+    assert_eq!(keys_pressed(&received), ["Enter"]);
+    let memory = result.long_term_memory.unwrap();
+    assert!(
+        memory.contains("neither the click nor pressing Enter changed the page"),
+        "{memory}"
+    );
+}
+
+#[tokio::test]
+async fn test_other_roles_are_not_activated_from_the_keyboard() {
+    let (result, received) = act(keyboard_fallback(), "click", "checkbox", &[0]).await;
+
+    assert_eq!(strategy(&result), Some(&json!("none")));
+    assert!(!focused(&received));
+    assert!(keys_pressed(&received).is_empty());
+}
+
+#[tokio::test]
+async fn test_keyboard_fallback_is_off_by_default() {
+    let (result, received) = act(Tools::default(), "click", "button", &[0]).await;
+
+    assert_eq!(strategy(&result), Some(&json!("none")));
+    assert!(!focused(&received));
+    assert!(keys_pressed(&received).is_empty());
+}
+
+#[tokio::test]
+async fn test_activate_action_reports_the_key_that_worked() {
+    // Focus, then Enter changes nothing and Space does
+    let (result, received) = act(Tools::default(), "activate", "button", &[0, 0, 1]).await;
+
+    assert!(result.error.is_none(), "{:?}", result.error);
+    assert_eq!(strategy(&result), Some(&json!("keyboard_space")));
+    assert!(focused(&received));
+    assert_eq!(keys_pressed(&received), ["Enter", " "]);
+    assert_eq!(
+        result.long_term_memory.as_deref(),
+        Some("Activated element 7 (button) with Space")
+    );
+    // The element is never clicked
+    assert!(
+        received
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|(method, _, _)| method != "Input.dispatchMouseEvent")
+    );
+}
+
+#[tokio::test]
+async fn test_activate_action_reports_when_nothing_changed() {
+    let (result, _) = act(Tools::default(), "activate", "checkbox", &[0]).await;
+
+    assert_eq!(strategy(&result), Some(&json!("none")));
+    assert_eq!(
+        result.long_term_memory.as_deref(),
+        Some(
+            "Focused element 7 (checkbox) and pressed Space, but the page did not change (URL, focus, DOM or scroll)"
+        )
+    );
+}
+
+/// Run `action_type` on the keydown-only widget, returning the result and
+/// the text it added to the page
+async fn act_on_fixture(tools: Tools, action_type: &str) -> (ActionResult, String) {
+    let url = serve_html(KEYDOWN_ONLY).await;
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.unwrap();
+    browser.navigate(&url).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let page = browser.get_page().unwrap();
+    let widget = page.get_elements_by_css_selector("#expand").await.unwrap();
+    let action = serde_json::from_value(json!({
+        "action_type": action_type,
+        "params": { "index": widget[0].backend_node_id() }
+    }))
+    .unwrap();
+
+    let result = tools.act(action, &mut browser, None).await.unwrap();
+    let details = page
+        .evaluate("document.getElementById('details')?.textContent ?? ''")
+        .await
+        .unwrap();
+    browser.stop().await.unwrap();
+    (result, details)
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_click_falls_back_to_keyboard_on_keydown_only_widget() {
+    let (result, details) = act_on_fixture(keyboard_fallback(), "click").await;
+
+    assert_eq!(
+        strategy(&result),
+        Some(&json!("keyboard_enter")),
+        "{result:?}"
+    );
+    assert_eq!(details, "Opened with Enter");
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_activate_keydown_only_widget() {
+    let (result, details) = act_on_fixture(Tools::default(), "activate").await;
+
+    assert_eq!(
+        strategy(&result),
+        Some(&json!("keyboard_enter")),
+        "{result:?}"
+    );
+    assert_eq!(details, "Opened with Enter");
+}
@@ -0,0 +1,117 @@
+//! Tests for running the agent in other languages
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::agent::service::Agent;
+use browsing::agent::views::AgentSettings;
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use common::{FakePageBrowser, fake_cdp};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+
+/// Model that remembers a fact, then finishes, recording the messages it was sent
+#[derive(Clone, Default)]
+struct RecordingLLM {
+    messages: Arc<Mutex<Vec<Vec<ChatMessage>>>>,
+}
+
+#[async_trait]
+impl ChatModel for RecordingLLM {
+    fn model(&self) -> &str {
+        "recording"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        let mut recorded = self.messages.lock().unwrap();
+        recorded.push(messages.to_vec());
+        let action = if recorded.len() == 1 {
+            json!({ "action_type": "remember", "params": { "key": "store", "value": "Berlin" } })
+        } else {
+            json!({ "action_type": "done", "params": { "text": "Fertig" } })
+        };
+        Ok(ChatInvokeCompletion::new(
+            json!({ "next_goal": "Weiter", "action": [action] }).to_string(),
+        ))
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+/// System prompt and the state message of the second step, for `language`
+async fn prompts(language: Option<&str>) -> (String, String) {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let llm = RecordingLLM::default();
+    Agent::new(
+        "Find the store hours".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        llm.clone(),
+    )
+    .with_settings(AgentSettings {
+        language: language.map(str::to_string),
+        ..Default::default()
+    })
+    .with_max_steps(3)
+    .run()
+    .await
+    .unwrap();
+    let messages = llm.messages.lock().unwrap();
+    (
+        messages[1][0].content.clone(),
+        messages[1][1].content.clone(),
+    )
+}
+
+#[tokio::test]
+async fn test_japanese_prompt() {
+    let (system, state) = prompts(Some("ja")).await;
+
+    assert!(system.contains("Respond in Japanese:"));
+    assert!(system.contains("the text of done in Japanese"));
+    // Actions are protocol and stay in English
+    assert!(system.contains("navigate:"));
+    assert!(state.starts_with("タスク: Find the store hours\n\n"));
+    assert!(state.contains("作業メモ:\n- store: Berlin\n\n"));
+    assert!(state.contains("ページの状態:\n"));
+    assert!(!state.contains("Page state:"));
+}
+
+#[tokio::test]
+async fn test_german_prompt() {
+    let (system, state) = prompts(Some("de-DE")).await;
+
+    assert!(system.contains("Respond in German:"));
+    assert!(state.starts_with("Aufgabe: Find the store hours\n\n"));
+    assert!(state.contains("Arbeitsgedächtnis:\n- store: Berlin\n\n"));
+    assert!(state.contains("Seitenzustand:\n"));
+}
+
+#[tokio::test]
+async fn test_languages_without_labels_use_english_templates() {
+    let (system, state) = prompts(Some("fr")).await;
+
+    assert!(system.contains("Respond in French:"));
+    assert!(state.starts_with("Task: Find the store hours\n\n"));
+    assert!(state.contains("Working memory:\n- store: Berlin\n\n"));
+}
+
+#[tokio::test]
+async fn test_english_by_default() {
+    let (system, state) = prompts(None).await;
+
+    assert!(!system.contains("Respond in"));
+    assert!(state.starts_with("Task: Find the store hours\n\n"));
+    assert!(state.contains("Page state:\n"));
+}
@@ -0,0 +1,230 @@
+//! Tests for layout metrics and scrolling by the real viewport height
+
+mod common;
+
+use browsing::actor::{FALLBACK_VIEWPORT_SIZE, Page, Size};
+use browsing::tools::Tools;
+use common::{FakePageBrowser, Received, fake_cdp, fake_cdp_with_events};
+use serde_json::{Value, json};
+
+fn layout_metrics() -> serde_json::Value {
+    json!({
+        "layoutViewport": { "pageX": 0, "pageY": 0, "clientWidth": 1600, "clientHeight": 1200 },
+        "visualViewport": {
+            "offsetX": 0, "offsetY": 0, "pageX": 0, "pageY": 0,
+            "clientWidth": 1600, "clientHeight": 1200, "scale": 1
+        },
+        "contentSize": { "x": 0, "y": 0, "width": 1600, "height": 6000 },
+        "cssLayoutViewport": { "pageX": 0, "pageY": 0, "clientWidth": 800, "clientHeight": 600 },
+        "cssVisualViewport": {
+            "offsetX": 0, "offsetY": 0, "pageX": 0, "pageY": 0,
+            "clientWidth": 800, "clientHeight": 600, "scale": 1
+        },
+        "cssContentSize": { "x": 0, "y": 0, "width": 800, "height": 3000 }
+    })
+}
+
+#[tokio::test]
+async fn test_scroll_pages_use_viewport_height() {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Page.getLayoutMetrics" => Ok(layout_metrics()),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let mut browser = FakePageBrowser { client };
+    let action = serde_json::from_value(json!({
+        "action_type": "scroll",
+        "params": { "down": true, "pages": 1.5 }
+    }))
+    .unwrap();
+
+    Tools::default()
+        .act(action, &mut browser, None)
+        .await
+        .unwrap();
+
+    let received = received.lock().unwrap();
+    let (_, wheel, session) = received
+        .iter()
+        .find(|(method, _, _)| method == "Input.dispatchMouseEvent")
+        .unwrap();
+    assert_eq!(wheel["type"], "mouseWheel");
+    assert_eq!(wheel["deltaY"], 900.0);
+    // Dispatched at the center of the viewport, in CSS pixels
+    assert_eq!(
+        (wheel["x"].clone(), wheel["y"].clone()),
+        (json!(400.0), json!(300.0))
+    );
+    assert_eq!(session.as_deref(), Some("S1"));
+}
+
+#[tokio::test]
+async fn test_layout_metrics_and_scroll_position() {
+    let (client, _) = fake_cdp(Box::new(|method, _| match method {
+        "Page.getLayoutMetrics" => Ok(layout_metrics()),
+        "Runtime.evaluate" => Ok(json!({ "result": { "value": [0, 1250.5] } })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    let metrics = page.get_layout_metrics().await.unwrap();
+    assert_eq!(metrics.viewport_height(), 600.0);
+    assert_eq!(metrics.css_content_size.height, 3000.0);
+    assert_eq!(metrics.device_pixel_ratio, 2.0);
+
+    assert_eq!(page.get_scroll_position().await.unwrap(), (0.0, 1250.5));
+}
+
+/// Metrics of an 800x600 viewport, then of a 390x844 one from the second read on
+fn resizing_metrics(call: usize) -> Value {
+    if call == 1 {
+        return layout_metrics();
+    }
+    json!({
+        "cssLayoutViewport": { "pageX": 0, "pageY": 0, "clientWidth": 390, "clientHeight": 844 },
+        "cssVisualViewport": {
+            "offsetX": 0, "offsetY": 0, "pageX": 0, "pageY": 0,
+            "clientWidth": 390, "clientHeight": 844, "scale": 1
+        },
+        "cssContentSize": { "x": 0, "y": 0, "width": 390, "height": 3000 }
+    })
+}
+
+fn metrics_reads(received: &Received) -> usize {
+    common::methods(received)
+        .iter()
+        .filter(|method| *method == "Page.getLayoutMetrics")
+        .count()
+}
+
+#[tokio::test]
+async fn test_viewport_size_is_cached_until_the_frame_resizes() {
+    let (client, received) = fake_cdp_with_events(
+        Box::new(|method, call| match method {
+            "Page.getLayoutMetrics" => Ok(resizing_metrics(call)),
+            _ => Ok(json!({})),
+        }),
+        Box::new(
+            |method, params| match (method, params["session"].as_str()) {
+                ("Page.bringToFront", Some(session)) => vec![
+                    json!({ "method": "Page.frameResized", "params": {}, "sessionId": session }),
+                ],
+                _ => vec![],
+            },
+        ),
+    )
+    .await;
+    let page = Page::new(client.clone(), "S1".to_string());
+    let resize = |session: &'static str| {
+        let client = client.clone();
+        async move {
+            client
+                .send_command_with_session(
+                    "Page.bringToFront",
+                    json!({ "session": session }),
+                    Some(session),
+                )
+                .await
+                .unwrap();
+        }
+    };
+
+    let size = Size {
+        width: 800.0,
+        height: 600.0,
+    };
+    assert_eq!(page.get_viewport_size().await.unwrap(), size);
+    assert_eq!(page.get_viewport_size().await.unwrap(), size);
+    // Another page of the same client shares the cache
+    let same_tab = Page::new(client.clone(), "S1".to_string());
+    assert_eq!(same_tab.get_viewport_size().await.unwrap(), size);
+    assert_eq!(metrics_reads(&received), 1);
+
+    // Another tab resizing leaves this one's size alone
+    resize("OTHER").await;
+    assert_eq!(page.get_viewport_size().await.unwrap(), size);
+    assert_eq!(metrics_reads(&received), 1);
+
+    resize("S1").await;
+    assert_eq!(
+        page.get_viewport_size().await.unwrap(),
+        Size {
+            width: 390.0,
+            height: 844.0
+        }
+    );
+    assert_eq!(metrics_reads(&received), 2);
+}
+
+#[tokio::test]
+async fn test_resizing_the_viewport_forgets_its_size() {
+    let (client, received) = fake_cdp(Box::new(|method, call| match method {
+        "Page.getLayoutMetrics" => Ok(resizing_metrics(call)),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    assert_eq!(page.get_viewport_size().await.unwrap().height, 600.0);
+    // No Page.frameResized is sent, as when the Page domain is off
+    page.set_viewport_size(390, 844).await.unwrap();
+    assert_eq!(page.get_viewport_size().await.unwrap().height, 844.0);
+    assert_eq!(metrics_reads(&received), 2);
+}
+
+/// Scroll down one page with `metrics` answering `Page.getLayoutMetrics`
+async fn scroll_down(
+    metrics: fn(usize) -> Result<Value, String>,
+    times: usize,
+) -> (Vec<Value>, Received) {
+    let (client, received) = fake_cdp(Box::new(move |method, call| match method {
+        "Page.getLayoutMetrics" => metrics(call),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let mut browser = FakePageBrowser { client };
+    for _ in 0..times {
+        let action = serde_json::from_value(json!({
+            "action_type": "scroll",
+            "params": { "down": true }
+        }))
+        .unwrap();
+        Tools::default()
+            .act(action, &mut browser, None)
+            .await
+            .unwrap();
+    }
+    let wheels = received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, _, _)| method == "Input.dispatchMouseEvent")
+        .map(|(_, params, _)| params.clone())
+        .collect();
+    (wheels, received)
+}
+
+#[tokio::test]
+async fn test_repeated_scrolls_read_the_viewport_once() {
+    let (wheels, received) = scroll_down(|call| Ok(resizing_metrics(call)), 3).await;
+
+    assert_eq!(wheels.len(), 3);
+    for wheel in &wheels {
+        assert_eq!(wheel["deltaY"], 600.0);
+        assert_eq!(
+            (wheel["x"].as_f64(), wheel["y"].as_f64()),
+            (Some(400.0), Some(300.0))
+        );
+    }
+    assert_eq!(metrics_reads(&received), 1);
+}
+
+#[tokio::test]
+async fn test_scroll_falls_back_when_metrics_fail() {
+    let (wheels, _) = scroll_down(|_| Err("Page.getLayoutMetrics failed".to_string()), 1).await;
+
+    assert_eq!(wheels.len(), 1);
+    assert_eq!(wheels[0]["deltaY"], FALLBACK_VIEWPORT_SIZE.height);
+    assert_eq!(wheels[0]["y"], FALLBACK_VIEWPORT_SIZE.height / 2.0);
+}
@@ -0,0 +1,202 @@
+//! Tests for loading lazy content before extraction
+
+mod common;
+
+use browsing::actor::{LazyLoadOptions, Page};
+use browsing::browser::{Browser, BrowserProfile};
+use browsing::tools::Tools;
+use common::{FakePageBrowser, Received, Responder, fake_cdp, fake_cdp_with_events, serve_html};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+const GALLERY: &str = include_str!("fixtures/lazy_load/gallery.html");
+
+fn metrics(scroll_y: u32, scroll_height: u32) -> Value {
+    json!({ "result": { "value": {
+        "scrollX": 0, "scrollY": scroll_y, "scrollHeight": scroll_height, "viewportHeight": 800
+    } } })
+}
+
+/// A 2000px page that grows to 3000px once scrolled, reaching the bottom on the fourth step
+///
+/// `Runtime.evaluate` calls alternate between a scroll step and reading the
+/// scroll metrics; then the scroll position is restored and read once more.
+fn growing_page() -> Responder {
+    Box::new(|method, call| match (method, call) {
+        ("Runtime.evaluate", 1) => Ok(metrics(0, 2000)),
+        ("Runtime.evaluate", 3) => Ok(metrics(640, 3000)),
+        ("Runtime.evaluate", 5) => Ok(metrics(1280, 3000)),
+        ("Runtime.evaluate", 7) => Ok(metrics(1920, 3000)),
+        ("Runtime.evaluate", 9) => Ok(metrics(2200, 3000)),
+        ("Runtime.evaluate", 11) => Ok(metrics(0, 3000)),
+        _ => Ok(json!({})),
+    })
+}
+
+/// `Network.*` event for a request of the page
+fn network(method: &str, request_id: &str) -> Value {
+    json!({ "method": method, "params": { "requestId": request_id }, "sessionId": "S1" })
+}
+
+fn expressions(received: &Received) -> Vec<String> {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, _, _)| method == "Runtime.evaluate")
+        .map(|(_, params, _)| params["expression"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_scrolls_to_bottom_and_back() {
+    // Scrolling starts an image request that finishes once scrolled back
+    let (client, received) = fake_cdp_with_events(
+        growing_page(),
+        Box::new(|method, params| {
+            let expression = params["expression"].as_str().unwrap_or_default();
+            match method {
+                "Runtime.evaluate" if expression.contains("scrollBy") => {
+                    vec![network("Network.requestWillBeSent", "img-1")]
+                }
+                "Runtime.evaluate" if expression.contains("scrollTo") => {
+                    vec![network("Network.loadingFinished", "img-1")]
+                }
+                _ => vec![],
+            }
+        }),
+    )
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    let report = page
+        .prepare_page_for_extraction(&LazyLoadOptions::new().with_network_quiet_ms(100))
+        .await
+        .unwrap();
+
+    assert_eq!(report.scroll_steps, 4);
+    assert!(report.reached_bottom);
+    assert!(report.network_quiet);
+    assert!(!report.cancelled);
+    assert_eq!(report.height_growth(), 1000.0);
+    let expressions = expressions(&received);
+    let restore = &expressions[9];
+    assert!(restore.contains("top: 0"), "{restore}");
+    assert!(restore.contains("new Event('resize')"), "{restore}");
+}
+
+#[tokio::test]
+async fn test_stops_at_max_duration() {
+    // An image that never finishes loading keeps the network busy
+    let (client, _) = fake_cdp_with_events(
+        growing_page(),
+        Box::new(|method, _| match method {
+            "Network.enable" => vec![network("Network.requestWillBeSent", "stuck")],
+            _ => vec![],
+        }),
+    )
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    let started = Instant::now();
+    let report = page
+        .prepare_page_for_extraction(
+            &LazyLoadOptions::new()
+                .with_max_duration_ms(1_500)
+                .with_network_quiet_ms(100),
+        )
+        .await
+        .unwrap();
+
+    assert!(report.reached_bottom);
+    assert!(!report.network_quiet);
+    assert!(started.elapsed() < Duration::from_secs(3));
+    assert!(report.to_string().ends_with("; requests still in flight"));
+}
+
+#[tokio::test]
+async fn test_cancel_restores_scroll_position() {
+    // An infinite feed: the bottom is never reached
+    let (client, received) = fake_cdp(Box::new(|method, call| match method {
+        "Runtime.evaluate" if call == 1 => Ok(metrics(300, 2000)),
+        "Runtime.evaluate" => Ok(metrics(300 + call as u32 * 320, 4000 + call as u32 * 800)),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+    let cancel = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&cancel);
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        flag.store(true, Ordering::Relaxed);
+    });
+
+    let started = Instant::now();
+    let report = page
+        .prepare_page_for_extraction(&LazyLoadOptions::new().with_cancel_flag(cancel))
+        .await
+        .unwrap();
+
+    assert!(report.cancelled);
+    assert!(!report.reached_bottom);
+    assert!(report.scroll_steps >= 1);
+    assert!(started.elapsed() < Duration::from_secs(2));
+    let expressions = expressions(&received);
+    let restore = &expressions[expressions.len() - 2];
+    assert!(restore.contains("top: 300"), "{restore}");
+}
+
+#[tokio::test]
+async fn test_extract_reports_height_growth() {
+    let (client, received) = fake_cdp(growing_page()).await;
+    let mut browser = FakePageBrowser { client };
+    let action = serde_json::from_value(json!({
+        "action_type": "extract",
+        "params": { "query": "photo captions", "trigger_lazy_load": true }
+    }))
+    .unwrap();
+
+    let result = Tools::default()
+        .act(action, &mut browser, None)
+        .await
+        .unwrap();
+
+    let memory = result.long_term_memory.unwrap();
+    assert!(
+        memory.ends_with(
+            "before extracting, page height went from 2000px to 3000px (+1000px) after 4 scroll steps"
+        ),
+        "{memory}"
+    );
+    // The content is read after the page was prepared
+    let expressions = expressions(&received);
+    assert!(expressions.last().unwrap().contains("innerText"));
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_intersection_observer_gallery() {
+    let url = serve_html(GALLERY).await;
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.unwrap();
+    browser.navigate(&url).await.unwrap();
+    let page = browser.get_page().unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let before = page.evaluate("document.body.innerText").await.unwrap();
+    assert!(!before.contains("Photo 20 loaded"), "{before}");
+
+    let report = page
+        .prepare_page_for_extraction(&LazyLoadOptions::default())
+        .await
+        .unwrap();
+
+    assert!(report.reached_bottom, "{report}");
+    assert!(report.height_growth() > 0.0, "{report}");
+    let after = page.evaluate("document.body.innerText").await.unwrap();
+    assert!(after.contains("Photo 20 loaded"), "{after}");
+    assert_eq!(page.evaluate("String(window.scrollY)").await.unwrap(), "0");
+
+    browser.stop().await.unwrap();
+}
@@ -0,0 +1,167 @@
+//! Tests for link graph extraction
+
+mod common;
+
+use browsing::browser::{
+    CrawlOptions, CrawledPage, LinkGraph, PageLink, VisitedPage, build_link_graph,
+};
+use common::fixture_site::FixtureSite;
+
+const ORIGIN: &str = "http://fixture.test";
+
+/// Pages of the site in tests/fixtures/research
+fn fixture_page(path: &str) -> Option<&'static str> {
+    Some(match path {
+        "/" => include_str!("fixtures/research/index.html"),
+        "/alpha.html" => include_str!("fixtures/research/alpha.html"),
+        "/beta.html" => include_str!("fixtures/research/beta.html"),
+        "/gamma.html" => include_str!("fixtures/research/gamma.html"),
+        _ => return None,
+    })
+}
+
+async fn graph(options: CrawlOptions) -> LinkGraph {
+    build_link_graph(
+        &mut FixtureSite::new(fixture_page),
+        &format!("{ORIGIN}/"),
+        &options,
+    )
+    .await
+    .unwrap()
+}
+
+fn edges(graph: &LinkGraph) -> Vec<(String, String, String)> {
+    graph
+        .edges
+        .iter()
+        .map(|edge| {
+            (
+                edge.source.replace(ORIGIN, ""),
+                edge.target.replace(ORIGIN, ""),
+                edge.anchor_text.clone(),
+            )
+        })
+        .collect()
+}
+
+fn edge(source: &str, target: &str, text: &str) -> (String, String, String) {
+    (source.to_string(), target.to_string(), text.to_string())
+}
+
+#[tokio::test]
+async fn test_graph_has_every_link_between_the_pages() {
+    let graph = graph(CrawlOptions::default()).await;
+
+    assert_eq!(
+        edges(&graph),
+        [
+            edge("/", "/alpha.html", "Alpha"),
+            edge("/", "/beta.html", "Beta"),
+            edge("/", "/gamma.html", "Gamma"),
+            edge("/", "/retired.html", "Retired"),
+            edge("/alpha.html", "/", "Back to guides"),
+            edge("/beta.html", "/", "Back to guides"),
+            edge("/gamma.html", "/", "Back to guides"),
+        ]
+    );
+    let nodes: Vec<(String, Option<String>)> = graph
+        .nodes
+        .iter()
+        .map(|node| (node.url.replace(ORIGIN, ""), node.title.clone()))
+        .collect();
+    assert_eq!(
+        nodes,
+        [
+            ("/".to_string(), Some("Guides".to_string())),
+            ("/alpha.html".to_string(), Some("Alpha".to_string())),
+            ("/beta.html".to_string(), Some("Beta".to_string())),
+            ("/gamma.html".to_string(), Some("Gamma".to_string())),
+            // Linked, but it does not load
+            ("/retired.html".to_string(), None),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_graph_stops_at_the_crawl_limits() {
+    let graph = graph(CrawlOptions {
+        max_depth: 0,
+        ..CrawlOptions::default()
+    })
+    .await;
+
+    // Only the start page is crawled; the pages it links to are untitled
+    assert_eq!(graph.edges.len(), 4);
+    assert_eq!(graph.nodes.len(), 5);
+    assert!(graph.nodes[1..].iter().all(|node| node.title.is_none()));
+
+    let graph = graph_with_pages(2).await;
+    assert_eq!(
+        edges(&graph)[4..],
+        [edge("/alpha.html", "/", "Back to guides")]
+    );
+}
+
+async fn graph_with_pages(max_pages: usize) -> LinkGraph {
+    graph(CrawlOptions {
+        max_pages,
+        ..CrawlOptions::default()
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_graph_renders_as_dot() {
+    let dot = graph_with_pages(2).await.to_dot();
+
+    assert!(dot.starts_with("digraph links {\n"), "{dot}");
+    assert!(
+        dot.contains(&format!("  \"{ORIGIN}/\" [label=\"Guides\"];\n")),
+        "{dot}"
+    );
+    // Pages not crawled are labelled with their URL
+    assert!(
+        dot.contains(&format!(
+            "  \"{ORIGIN}/beta.html\" [label=\"{ORIGIN}/beta.html\"];\n"
+        )),
+        "{dot}"
+    );
+    assert!(
+        dot.contains(&format!(
+            "  \"{ORIGIN}/alpha.html\" -> \"{ORIGIN}/\" [label=\"Back to guides\"];\n"
+        )),
+        "{dot}"
+    );
+    assert!(dot.ends_with("}\n"));
+}
+
+#[test]
+fn test_links_to_the_same_page_are_one_edge() {
+    let link = |url: &str| PageLink {
+        url: url.to_string(),
+        text: "Alpha".to_string(),
+    };
+    let mut graph = LinkGraph::default();
+    graph.add_page(&CrawledPage {
+        page: VisitedPage {
+            url: format!("{ORIGIN}/"),
+            title: "Guides".to_string(),
+            content_preview: String::new(),
+            links: vec![],
+        },
+        depth: 0,
+        outbound: vec![
+            link(&format!("{ORIGIN}/alpha.html#intro")),
+            link(&format!("{ORIGIN}/alpha.html/")),
+            link(&format!("{ORIGIN}/alpha.html")),
+            link(&format!("{ORIGIN}#top")),
+        ],
+        discovered: vec![],
+    });
+
+    assert_eq!(graph.nodes.len(), 2);
+    assert_eq!(
+        edges(&graph),
+        [edge("/", "/alpha.html", "Alpha"), edge("/", "/", "Alpha")]
+    );
+}
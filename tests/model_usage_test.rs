@@ -0,0 +1,244 @@
+//! Tests for recording which model made each call of a mixed-model run
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::agent::ReportRecord;
+use browsing::agent::service::Agent;
+use browsing::agent::views::{AgentHistoryList, AgentSettings};
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{
+    CallPurpose, ChatInvokeCompletion, ChatInvokeUsage, ChatMessage, ChatModel,
+};
+use browsing::tokens::TokenPricing;
+use common::{FakePageBrowser, fake_cdp};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+const PRODUCT_PAGE: &str = "Espresso grinder\nPrice: 129.00 EUR\nIn stock\n";
+
+/// Model named `provider/model` that answers with `replies` in turn, each
+/// costing `prompt_tokens` and `completion_tokens`
+#[derive(Clone)]
+struct NamedLLM {
+    provider: &'static str,
+    model: &'static str,
+    replies: Arc<Mutex<Vec<String>>>,
+    prompt_tokens: u32,
+    completion_tokens: u32,
+}
+
+impl NamedLLM {
+    fn new(provider: &'static str, model: &'static str, replies: Vec<String>) -> Self {
+        Self {
+            provider,
+            model,
+            replies: Arc::new(Mutex::new(replies)),
+            prompt_tokens: 1000,
+            completion_tokens: 100,
+        }
+    }
+}
+
+#[async_trait]
+impl ChatModel for NamedLLM {
+    fn model(&self) -> &str {
+        self.model
+    }
+
+    fn provider(&self) -> &str {
+        self.provider
+    }
+
+    async fn chat(&self, _messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        let reply = self.replies.lock().unwrap().remove(0);
+        Ok(
+            ChatInvokeCompletion::new(reply).with_usage(ChatInvokeUsage {
+                prompt_tokens: self.prompt_tokens,
+                prompt_cached_tokens: None,
+                prompt_cache_creation_tokens: None,
+                prompt_image_tokens: None,
+                completion_tokens: self.completion_tokens,
+                total_tokens: self.prompt_tokens + self.completion_tokens,
+            }),
+        )
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+fn step(actions: Value) -> String {
+    json!({
+        "evaluation_previous_goal": "Page loaded",
+        "memory": "On the product page",
+        "next_goal": "Read the price",
+        "action": actions
+    })
+    .to_string()
+}
+
+/// Two steps of `gpt-4o`, the first extracting the price twice with `haiku`
+async fn run(settings: AgentSettings) -> AgentHistoryList {
+    let extract = json!({ "action_type": "extract", "params": { "query": "price" } });
+    let planner = NamedLLM::new(
+        "openai",
+        "gpt-4o",
+        vec![
+            step(json!([extract, extract])),
+            step(json!([{ "action_type": "done", "params": { "text": "129.00 EUR" } }])),
+        ],
+    );
+    let mut extractor = NamedLLM::new(
+        "anthropic",
+        "haiku",
+        vec!["129.00 EUR".to_string(), "129.00 EUR".to_string()],
+    );
+    extractor.prompt_tokens = 500;
+    extractor.completion_tokens = 10;
+    let (client, _) = fake_cdp(Box::new(|method, _| match method {
+        "DOM.getDocument" => Ok(json!({
+            "root": { "nodeId": 1, "backendNodeId": 1, "nodeType": 9, "nodeName": "#document" }
+        })),
+        "Runtime.evaluate" => Ok(json!({ "result": { "value": PRODUCT_PAGE } })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    Agent::new(
+        "Find the price".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        planner,
+    )
+    .with_extraction_llm(extractor)
+    .with_settings(settings)
+    .with_max_steps(5)
+    .run()
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_steps_record_each_model_call() {
+    let history = run(AgentSettings::default()).await;
+    assert_eq!(history.history.len(), 2);
+
+    let first = history.history[0].metadata.as_ref().unwrap();
+    let calls: Vec<(&str, &str, CallPurpose)> = first
+        .llm_calls
+        .iter()
+        .map(|call| (call.provider.as_str(), call.model.as_str(), call.purpose))
+        .collect();
+    assert_eq!(
+        calls,
+        [
+            ("openai", "gpt-4o", CallPurpose::ActionSelection),
+            ("anthropic", "haiku", CallPurpose::Extraction),
+            ("anthropic", "haiku", CallPurpose::Extraction),
+        ]
+    );
+    assert_eq!(first.llm_calls[1].usage.as_ref().unwrap().total_tokens, 510);
+    assert_eq!(first.models(), ["openai/gpt-4o", "anthropic/haiku"]);
+    // The step's own usage is still that of the call choosing its actions
+    assert_eq!(first.usage.as_ref().unwrap().prompt_tokens, 1000);
+
+    let second = history.history[1].metadata.as_ref().unwrap();
+    assert_eq!(second.models(), ["openai/gpt-4o"]);
+
+    // The metadata survives a round trip through JSON
+    let json = serde_json::to_value(first).unwrap();
+    assert_eq!(json["llm_calls"][1]["purpose"], "extraction");
+    let parsed: browsing::agent::views::StepMetadata = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed.llm_calls, first.llm_calls);
+}
+
+#[tokio::test]
+async fn test_usage_summary_breaks_usage_down_by_model() {
+    let history = run(AgentSettings {
+        token_pricing: Some(TokenPricing {
+            prompt_per_million: 1.0,
+            completion_per_million: 10.0,
+        }),
+        model_pricing: HashMap::from([(
+            "anthropic/haiku".to_string(),
+            TokenPricing {
+                prompt_per_million: 0.5,
+                completion_per_million: 5.0,
+            },
+        )]),
+        ..Default::default()
+    })
+    .await;
+
+    let usage = history.usage.as_ref().unwrap();
+    assert_eq!(usage.total_tokens, Some(2 * 1100 + 2 * 510));
+    let by_model: Vec<(&str, u32, u32, &[CallPurpose])> = usage
+        .by_model
+        .iter()
+        .map(|m| {
+            (
+                m.model.as_str(),
+                m.calls,
+                m.total_tokens,
+                m.purposes.as_slice(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        by_model,
+        [
+            ("gpt-4o", 2, 2200, &[CallPurpose::ActionSelection][..]),
+            ("haiku", 2, 1020, &[CallPurpose::Extraction][..]),
+        ]
+    );
+    // 1000 + 100 * 10 per million for each planning call, 500 * 0.5 + 10 * 5
+    // for each extraction
+    let costs: Vec<f64> = usage.by_model.iter().map(|m| m.cost.unwrap()).collect();
+    assert!((costs[0] - 0.004).abs() < 1e-9, "{costs:?}");
+    assert!((costs[1] - 0.0006).abs() < 1e-9, "{costs:?}");
+    assert!((usage.cost.unwrap() - 0.0046).abs() < 1e-9);
+
+    // The report lists the models of each step and the breakdown
+    let records: Vec<ReportRecord> = history.report_records();
+    assert_eq!(records[0].models, ["openai/gpt-4o", "anthropic/haiku"]);
+    assert!((records[0].cumulative_cost.unwrap() - 0.0026).abs() < 1e-9);
+    let summary = records.last().unwrap();
+    assert_eq!(summary.models.len(), 2);
+    assert!(
+        summary.models[0].starts_with("openai/gpt-4o: 2 calls, 2200 tokens,"),
+        "{:?}",
+        summary.models
+    );
+    assert!(
+        summary.models[1].ends_with("$0.0006"),
+        "{:?}",
+        summary.models
+    );
+}
+
+#[tokio::test]
+async fn test_models_without_prices_have_no_cost() {
+    let history = run(AgentSettings {
+        token_pricing: Some(TokenPricing {
+            prompt_per_million: 1.0,
+            completion_per_million: 10.0,
+        }),
+        ..Default::default()
+    })
+    .await;
+
+    // The agent's prices are not applied to the extraction model
+    let usage = history.usage.as_ref().unwrap();
+    let costs: Vec<Option<f64>> = usage.by_model.iter().map(|m| m.cost).collect();
+    assert!((costs[0].unwrap() - 0.004).abs() < 1e-9, "{costs:?}");
+    assert_eq!(costs[1], None);
+    let calls = &history.history[0].metadata.as_ref().unwrap().llm_calls;
+    assert!(calls[0].cost.is_some());
+    assert!(calls[1..].iter().all(|call| call.cost.is_none()));
+}
@@ -0,0 +1,234 @@
+//! Tests for answering several extract queries in one model call
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use browsing::tools::Tools;
+use browsing::tools::handlers::extract::EXTRACTED_FIELDS_METADATA_KEY;
+use browsing::tools::views::ActionModel;
+use common::{FakePageBrowser, fake_cdp};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+
+const PRODUCT_PAGE: &str = "Espresso grinder\nPrice: 129.00 EUR\nIn stock\nSKU: EG-2041\n";
+
+/// Model that answers with a JSON object keyed by the query names in its
+/// prompt, from a fixed set of answers
+#[derive(Clone, Default)]
+struct KeyedLLM {
+    prompts: Arc<Mutex<Vec<String>>>,
+    context_window: Option<usize>,
+}
+
+impl KeyedLLM {
+    fn answer(name: &str) -> Value {
+        match name {
+            "price" => json!("129.00 EUR"),
+            "availability" => json!("In stock"),
+            "sku" | "What is the SKU?" => json!("EG-2041"),
+            "rating" => json!("unavailable"),
+            _ => Value::Null,
+        }
+    }
+}
+
+#[async_trait]
+impl ChatModel for KeyedLLM {
+    fn model(&self) -> &str {
+        "keyed"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    fn context_window(&self) -> Option<usize> {
+        self.context_window
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        let prompt = messages[1].content.clone();
+        let queries = prompt
+            .split("Queries:\n")
+            .nth(1)
+            .and_then(|rest| rest.split("\n\nContent:").next())
+            .unwrap_or_default();
+        let mut reply = serde_json::Map::new();
+        for line in queries.lines() {
+            let name: String = serde_json::from_str(
+                line.trim_start_matches("- ")
+                    .split(": ")
+                    .next()
+                    .unwrap_or_default(),
+            )
+            .unwrap();
+            reply.insert(name.clone(), Self::answer(&name));
+        }
+        self.prompts.lock().unwrap().push(prompt);
+        Ok(ChatInvokeCompletion::new(format!(
+            "Here you go:\n```json\n{}\n```",
+            Value::Object(reply)
+        )))
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+/// Model whose reply is not JSON
+struct ProseLLM;
+
+#[async_trait]
+impl ChatModel for ProseLLM {
+    fn model(&self) -> &str {
+        "prose"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, _messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        Ok(ChatInvokeCompletion::new(
+            "The price is 129.00 EUR".to_string(),
+        ))
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+async fn extract(
+    params: Value,
+    llm: &dyn ChatModel,
+) -> Result<browsing::agent::views::ActionResult> {
+    let (client, _) = fake_cdp(Box::new(|method, _| match method {
+        "Runtime.evaluate" => Ok(json!({ "result": { "value": PRODUCT_PAGE } })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let mut browser = FakePageBrowser { client };
+    let action: ActionModel =
+        serde_json::from_value(json!({ "action_type": "extract", "params": params })).unwrap();
+    Tools::new(vec![])
+        .act_with_llm(action, &mut browser, None, Some(llm))
+        .await
+}
+
+#[tokio::test]
+async fn test_named_queries_are_answered_in_one_call() {
+    let llm = KeyedLLM::default();
+    let result = extract(
+        json!({ "queries": {
+            "price": "Price with currency",
+            "availability": "Is it in stock?",
+            "sku": "Product SKU",
+            "rating": "Average customer rating",
+        }}),
+        &llm,
+    )
+    .await
+    .unwrap();
+
+    let prompts = llm.prompts.lock().unwrap();
+    assert_eq!(prompts.len(), 1);
+    assert!(prompts[0].contains("- \"rating\": Average customer rating"));
+    assert!(prompts[0].contains("Price: 129.00 EUR"));
+
+    let fields = &result.metadata.unwrap()[EXTRACTED_FIELDS_METADATA_KEY];
+    assert_eq!(
+        *fields,
+        json!({
+            "price": "129.00 EUR",
+            "availability": "In stock",
+            "sku": "EG-2041",
+            "rating": null,
+        })
+    );
+    assert_eq!(
+        result.long_term_memory.as_deref(),
+        Some("Extracted availability, price, sku from https://example.com; unavailable: rating")
+    );
+    let content = result.extracted_content.unwrap();
+    assert!(content.contains("<queries>\navailability: Is it in stock?\n"));
+    assert!(content.contains("<untrusted_page_content>"));
+    assert!(content.contains("\"sku\": \"EG-2041\""));
+}
+
+#[tokio::test]
+async fn test_query_list_uses_queries_as_names() {
+    let llm = KeyedLLM::default();
+    let result = extract(
+        json!({ "queries": ["What is the SKU?", "Who makes it?"] }),
+        &llm,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        result.metadata.unwrap()[EXTRACTED_FIELDS_METADATA_KEY],
+        json!({ "What is the SKU?": "EG-2041", "Who makes it?": null })
+    );
+    assert_eq!(llm.prompts.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_queries_are_split_when_they_do_not_fit_the_context() {
+    // Room for the page and one answer, but not three
+    let llm = KeyedLLM {
+        context_window: Some(PRODUCT_PAGE.len() / 4 + 300),
+        ..Default::default()
+    };
+    let result = extract(json!({ "queries": ["price", "availability", "sku"] }), &llm)
+        .await
+        .unwrap();
+
+    let prompts = llm.prompts.lock().unwrap();
+    assert_eq!(prompts.len(), 3);
+    assert!(prompts[1].contains("- \"availability\""));
+    assert!(!prompts[1].contains("- \"price\""));
+    assert_eq!(
+        result.metadata.unwrap()[EXTRACTED_FIELDS_METADATA_KEY],
+        json!({ "price": "129.00 EUR", "availability": "In stock", "sku": "EG-2041" })
+    );
+}
+
+#[tokio::test]
+async fn test_reply_must_be_a_json_object() {
+    let error = extract(json!({ "queries": ["price"] }), &ProseLLM)
+        .await
+        .unwrap_err();
+
+    assert!(
+        error
+            .to_string()
+            .contains("did not return a JSON object keyed by query name")
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_queries_are_rejected() {
+    let llm = KeyedLLM::default();
+    for queries in [
+        json!([]),
+        json!("price"),
+        json!([1, 2]),
+        json!({ "price": 1 }),
+    ] {
+        let error = extract(json!({ "queries": queries }), &llm)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("'queries' must be"), "{error}");
+    }
+    assert!(llm.prompts.lock().unwrap().is_empty());
+}
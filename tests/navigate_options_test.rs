@@ -0,0 +1,293 @@
+//! Tests for navigation with a referrer, one-shot headers and wait_until
+
+mod common;
+
+use browsing::actor::{LoadState, NavigateOptions, Page};
+use browsing::tools::Tools;
+use common::{
+    FakePageBrowser, HttpResponse, Received, fake_cdp, fake_cdp_with_events, methods, serve_http,
+};
+use serde_json::{Value, json};
+
+fn sent(received: &Received, method: &str) -> Vec<Value> {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(m, _, _)| m == method)
+        .map(|(_, params, _)| params.clone())
+        .collect()
+}
+
+fn navigated() -> Value {
+    json!({ "frameId": "F1", "loaderId": "L1" })
+}
+
+fn paused(request_id: &str, frame_id: &str) -> Value {
+    json!({
+        "method": "Fetch.requestPaused",
+        "sessionId": "S1",
+        "params": {
+            "requestId": request_id,
+            "frameId": frame_id,
+            "resourceType": "Document",
+            "request": {
+                "url": "https://example.com/",
+                "method": "GET",
+                "headers": { "User-Agent": "Chrome", "Accept": "text/html" }
+            }
+        }
+    })
+}
+
+#[tokio::test]
+async fn test_referrer_is_passed_to_page_navigate() {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Page.navigate" => Ok(navigated()),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    page.goto_with_options(
+        "https://example.com/",
+        &NavigateOptions::new().with_referrer("https://search.example/"),
+    )
+    .await
+    .unwrap();
+
+    let navigate = sent(&received, "Page.navigate");
+    assert_eq!(navigate[0]["url"], "https://example.com/");
+    assert_eq!(navigate[0]["referrer"], "https://search.example/");
+    // No interception without headers
+    assert!(!methods(&received).iter().any(|m| m.starts_with("Fetch.")));
+    assert!(
+        received
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|(_, _, session)| session.as_deref() == Some("S1"))
+    );
+}
+
+#[tokio::test]
+async fn test_headers_are_added_to_main_frame_request_only() {
+    let (client, received) = fake_cdp_with_events(
+        Box::new(|method, _| match method {
+            "Page.getFrameTree" => Ok(json!({ "frameTree": { "frame": { "id": "F1" } } })),
+            "Page.navigate" => Ok(navigated()),
+            _ => Ok(json!({})),
+        }),
+        // An iframe document is paused first, then the main frame
+        Box::new(|method, _| match method {
+            "Page.navigate" => vec![paused("R-iframe", "F2"), paused("R-main", "F1")],
+            _ => vec![],
+        }),
+    )
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    page.goto_with_options(
+        "https://example.com/",
+        &NavigateOptions::new().with_header("X-Gate", "open"),
+    )
+    .await
+    .unwrap();
+
+    let enable = sent(&received, "Fetch.enable");
+    assert_eq!(enable[0]["patterns"][0]["resourceType"], "Document");
+
+    let resumed = sent(&received, "Fetch.continueRequest");
+    assert_eq!(resumed.len(), 2);
+    assert_eq!(resumed[0]["requestId"], "R-iframe");
+    assert!(resumed[0].get("headers").is_none());
+    assert_eq!(resumed[1]["requestId"], "R-main");
+    let headers = resumed[1]["headers"].as_array().unwrap();
+    assert!(headers.contains(&json!({ "name": "X-Gate", "value": "open" })));
+    assert!(headers.contains(&json!({ "name": "User-Agent", "value": "Chrome" })));
+
+    // Interception is removed once the main-frame request went out
+    let methods = methods(&received);
+    let position = |method: &str| methods.iter().position(|m| m == method).unwrap();
+    assert!(position("Fetch.enable") < position("Page.navigate"));
+    assert_eq!(methods.last().map(String::as_str), Some("Fetch.disable"));
+}
+
+#[tokio::test]
+async fn test_interception_is_removed_when_navigation_fails() {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Page.getFrameTree" => Ok(json!({ "frameTree": { "frame": { "id": "F1" } } })),
+        "Page.navigate" => {
+            Ok(json!({ "frameId": "F1", "errorText": "net::ERR_NAME_NOT_RESOLVED" }))
+        }
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    let err = page
+        .goto_with_options(
+            "https://nowhere.invalid/",
+            &NavigateOptions::new().with_header("X-Gate", "open"),
+        )
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("ERR_NAME_NOT_RESOLVED"), "{err}");
+    assert_eq!(sent(&received, "Fetch.disable").len(), 1);
+}
+
+#[tokio::test]
+async fn test_interception_is_removed_when_navigation_is_cancelled() {
+    // The main-frame request is never paused, so the navigation waits for it
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Page.getFrameTree" => Ok(json!({ "frameTree": { "frame": { "id": "F1" } } })),
+        "Page.navigate" => Ok(navigated()),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+    let options = NavigateOptions::new().with_header("X-Gate", "open");
+
+    // As when a step times out in the middle of the navigate action
+    let cancelled = tokio::time::timeout(
+        std::time::Duration::from_millis(200),
+        page.goto_with_options("https://example.com/", &options),
+    )
+    .await;
+    assert!(cancelled.is_err());
+
+    for _ in 0..100 {
+        if !sent(&received, "Fetch.disable").is_empty() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert_eq!(sent(&received, "Fetch.disable").len(), 1);
+
+    // The next navigation with headers intercepts afresh
+    let _ = tokio::time::timeout(
+        std::time::Duration::from_millis(200),
+        page.goto_with_options("https://example.com/", &options),
+    )
+    .await;
+    assert_eq!(sent(&received, "Fetch.enable").len(), 2);
+}
+
+#[tokio::test]
+async fn test_wait_until_overrides_default() {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Page.navigate" => Ok(navigated()),
+        "Runtime.evaluate" => Ok(json!({ "result": { "value": "interactive" } })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    page.goto_with_options(
+        "https://example.com/",
+        &NavigateOptions::new().with_wait_until(LoadState::DomContentLoaded),
+    )
+    .await
+    .unwrap();
+    // The ready state, then the check for a pending redirect by the page
+    assert_eq!(sent(&received, "Runtime.evaluate").len(), 2);
+
+    // Without wait_until nothing is awaited
+    page.goto_with_options("https://example.com/", &NavigateOptions::new())
+        .await
+        .unwrap();
+    assert_eq!(sent(&received, "Runtime.evaluate").len(), 2);
+}
+
+#[tokio::test]
+async fn test_navigate_action_passes_options() {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Page.navigate" => Ok(navigated()),
+        "Runtime.evaluate" => Ok(json!({ "result": { "value": "complete" } })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let mut browser = FakePageBrowser { client };
+
+    let action = serde_json::from_value(json!({
+        "action_type": "navigate",
+        "params": {
+            "url": "https://example.com/",
+            "referrer": "https://search.example/",
+            "wait_until": "domcontentloaded"
+        }
+    }))
+    .unwrap();
+    let result = Tools::default()
+        .act(action, &mut browser, None)
+        .await
+        .unwrap();
+
+    assert!(result.error.is_none());
+    assert_eq!(
+        sent(&received, "Page.navigate")[0]["referrer"],
+        "https://search.example/"
+    );
+
+    let invalid = serde_json::from_value(json!({
+        "action_type": "navigate",
+        "params": { "url": "https://example.com/", "wait_until": "idle" }
+    }))
+    .unwrap();
+    let outcome = Tools::default().act(invalid, &mut browser, None).await;
+    let message = match outcome {
+        Ok(result) => result.error.unwrap_or_default(),
+        Err(e) => e.to_string(),
+    };
+    assert!(message.contains("wait_until"), "{message}");
+}
+
+/// Serve a page that echoes the request headers it received
+async fn serve_header_echo() -> String {
+    serve_http(|request| {
+        let head = request.head.to_lowercase();
+        HttpResponse::html(format!("<html><body><pre>{head}</pre></body></html>"))
+    })
+    .await
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_headers_reach_local_server_once() {
+    use browsing::browser::{Browser, BrowserProfile};
+
+    let url = serve_header_echo().await;
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.expect("Browser should start");
+
+    let options = NavigateOptions::new()
+        .with_referrer("https://search.example/")
+        .with_header("X-Gate", "open")
+        .with_wait_until(LoadState::Load);
+    browser.navigate_with_options(&url, options).await.unwrap();
+    let echoed = browser
+        .get_page()
+        .unwrap()
+        .evaluate("document.body.innerText")
+        .await
+        .unwrap();
+    assert!(echoed.contains("x-gate: open"), "{echoed}");
+    assert!(
+        echoed.contains("referer: https://search.example/"),
+        "{echoed}"
+    );
+
+    // Headers are not sent with the next navigation
+    let plain = NavigateOptions::new().with_wait_until(LoadState::Load);
+    browser.navigate_with_options(&url, plain).await.unwrap();
+    let echoed = browser
+        .get_page()
+        .unwrap()
+        .evaluate("document.body.innerText")
+        .await
+        .unwrap();
+    assert!(!echoed.contains("x-gate"), "{echoed}");
+
+    browser.stop().await.unwrap();
+}
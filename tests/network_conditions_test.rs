@@ -0,0 +1,69 @@
+//! Tests for network throttling of a browser session
+
+mod common;
+
+use browsing::browser::{Browser, BrowserProfile, NetworkConditions};
+use common::{Received, fake_cdp_with_latency};
+use serde_json::json;
+use std::time::Duration;
+
+/// Browser connected to a fake endpoint with one page, target T1 in session S1
+async fn connected_browser() -> (Browser, Received) {
+    let (url, received) = fake_cdp_with_latency(
+        Box::new(|method, _| match method {
+            "Target.getTargets" => Ok(json!({
+                "targetInfos": [{ "targetId": "T1", "type": "page", "url": "about:blank" }]
+            })),
+            "Target.attachToTarget" => Ok(json!({ "sessionId": "S1" })),
+            _ => Ok(json!({})),
+        }),
+        Duration::ZERO,
+    )
+    .await;
+    let mut browser = Browser::new(BrowserProfile::default()).with_cdp_url(url);
+    browser.start().await.unwrap();
+    (browser, received)
+}
+
+#[tokio::test]
+async fn test_network_conditions_round_trip() {
+    let (mut browser, received) = connected_browser().await;
+    assert_eq!(browser.get_network_conditions().await.unwrap(), None);
+
+    browser
+        .set_network_conditions(NetworkConditions::slow_3g())
+        .await
+        .unwrap();
+    assert_eq!(
+        browser.get_network_conditions().await.unwrap(),
+        Some(NetworkConditions::slow_3g())
+    );
+    assert!(!browser.is_offline().await);
+
+    browser
+        .set_network_conditions(NetworkConditions::offline())
+        .await
+        .unwrap();
+    assert!(browser.is_offline().await);
+
+    browser.reset_network_conditions().await.unwrap();
+    assert_eq!(browser.get_network_conditions().await.unwrap(), None);
+    assert!(!browser.is_offline().await);
+
+    let emulated: Vec<_> = received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, _, _)| method == "Network.emulateNetworkConditions")
+        .map(|(_, params, session)| (params["offline"].clone(), session.clone()))
+        .collect();
+    let s1 = Some("S1".to_string());
+    assert_eq!(
+        emulated,
+        [
+            (json!(false), s1.clone()),
+            (json!(true), s1.clone()),
+            (json!(false), s1)
+        ]
+    );
+}
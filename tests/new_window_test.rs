@@ -0,0 +1,220 @@
+//! Tests for detecting windows opened by clicks
+
+mod common;
+
+use browsing::actor::Page;
+use browsing::browser::cdp::CdpClient;
+use browsing::browser::views::TabInfo;
+use browsing::browser::{Browser, BrowserProfile, NewWindowHandling};
+use browsing::error::Result;
+use browsing::tools::Tools;
+use browsing::traits::BrowserClient;
+use common::{Received, fake_cdp_with_events, methods};
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+/// Browser on the fake endpoint that records tab switches and closes
+struct RecordingBrowser {
+    client: Arc<CdpClient>,
+    switched: Vec<String>,
+    closed: Vec<String>,
+}
+
+#[async_trait::async_trait]
+impl BrowserClient for RecordingBrowser {
+    async fn start(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn navigate(&mut self, _url: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_current_url(&self) -> Result<String> {
+        Ok("https://example.com".to_string())
+    }
+
+    async fn create_tab(&mut self, _url: Option<&str>) -> Result<String> {
+        Ok("T1".to_string())
+    }
+
+    async fn switch_to_tab(&mut self, target_id: &str) -> Result<()> {
+        self.switched.push(target_id.to_string());
+        Ok(())
+    }
+
+    async fn close_tab(&mut self, target_id: &str) -> Result<()> {
+        self.closed.push(target_id.to_string());
+        Ok(())
+    }
+
+    async fn get_tabs(&self) -> Result<Vec<TabInfo>> {
+        Ok(vec![])
+    }
+
+    async fn get_target_id_from_tab_id(&self, tab_id: &str) -> Result<String> {
+        Ok(tab_id.to_string())
+    }
+
+    fn get_page(&self) -> Result<Page> {
+        Ok(Page::new(Arc::clone(&self.client), "S1".to_string()))
+    }
+
+    async fn take_screenshot(&self, _path: Option<&str>, _full_page: bool) -> Result<Vec<u8>> {
+        Ok(vec![])
+    }
+
+    async fn get_current_page_title(&self) -> Result<String> {
+        Ok("Example".to_string())
+    }
+
+    fn get_cdp_client(&self) -> Result<Arc<CdpClient>> {
+        Ok(Arc::clone(&self.client))
+    }
+
+    fn get_session_id(&self) -> Result<String> {
+        Ok("S1".to_string())
+    }
+
+    fn get_current_target_id(&self) -> Result<String> {
+        Ok("T1".to_string())
+    }
+}
+
+fn target_created(target_id: &str, target_type: &str) -> Value {
+    json!({
+        "method": "Target.targetCreated",
+        "params": { "targetInfo": { "targetId": target_id, "type": target_type, "url": "" } }
+    })
+}
+
+/// Endpoint with one open tab where releasing the mouse opens a popup if `opens_window`
+async fn browser(opens_window: bool) -> (RecordingBrowser, Received) {
+    let (client, received) = fake_cdp_with_events(
+        Box::new(|method, _| match method {
+            "Page.getLayoutMetrics" => {
+                Ok(json!({ "layoutViewport": { "clientWidth": 1280, "clientHeight": 720 } }))
+            }
+            "DOM.getContentQuads" => Ok(json!({ "quads": [[10, 10, 90, 10, 90, 30, 10, 30]] })),
+            "Target.getTargets" => {
+                Ok(json!({ "targetInfos": [{ "targetId": "T1", "type": "page" }] }))
+            }
+            _ => Ok(json!({})),
+        }),
+        Box::new(move |method, params| match method {
+            // Discovery replays the targets that already exist
+            "Target.setDiscoverTargets" => vec![target_created("T1", "page")],
+            "Input.dispatchMouseEvent" if opens_window && params["type"] == "mouseReleased" => {
+                vec![
+                    target_created("WORKER00", "service_worker"),
+                    target_created("POPUP0042", "page"),
+                ]
+            }
+            _ => vec![],
+        }),
+    )
+    .await;
+    let browser = RecordingBrowser {
+        client,
+        switched: vec![],
+        closed: vec![],
+    };
+    (browser, received)
+}
+
+fn click() -> browsing::tools::views::ActionModel {
+    serde_json::from_value(json!({ "action_type": "click", "params": { "index": 3 } })).unwrap()
+}
+
+#[tokio::test]
+async fn test_click_switches_to_opened_window() {
+    let (mut browser, _) = browser(true).await;
+    let tools = Tools::default().with_new_window_handling(NewWindowHandling::AutoSwitch);
+
+    let result = tools.act(click(), &mut browser, None).await.unwrap();
+
+    assert!(result.error.is_none(), "{:?}", result.error);
+    let memory = result.long_term_memory.unwrap();
+    assert!(
+        memory.contains("opened tab #0042, now switched to"),
+        "{memory}"
+    );
+    assert_eq!(browser.switched, ["POPUP0042"]);
+    assert!(browser.closed.is_empty());
+}
+
+#[tokio::test]
+async fn test_click_closes_opened_window() {
+    let (mut browser, _) = browser(true).await;
+    let tools = Tools::default().with_new_window_handling(NewWindowHandling::AutoClose);
+
+    let result = tools.act(click(), &mut browser, None).await.unwrap();
+
+    let memory = result.long_term_memory.unwrap();
+    assert!(memory.contains("which was closed"), "{memory}");
+    assert_eq!(browser.closed, ["POPUP0042"]);
+    assert!(browser.switched.is_empty());
+}
+
+#[tokio::test]
+async fn test_click_without_new_window() {
+    let (mut browser, _) = browser(false).await;
+    let tools = Tools::default().with_new_window_handling(NewWindowHandling::AutoSwitch);
+
+    let result = tools.act(click(), &mut browser, None).await.unwrap();
+
+    let memory = result.long_term_memory.unwrap();
+    assert!(!memory.contains("opened tab"), "{memory}");
+    assert!(browser.switched.is_empty());
+}
+
+#[tokio::test]
+async fn test_ignore_does_not_watch_targets() {
+    let (mut browser, received) = browser(true).await;
+
+    let result = Tools::default()
+        .act(click(), &mut browser, None)
+        .await
+        .unwrap();
+
+    assert!(!result.long_term_memory.unwrap().contains("opened tab"));
+    assert!(
+        !methods(&received)
+            .iter()
+            .any(|method| method.starts_with("Target."))
+    );
+    assert!(browser.switched.is_empty() && browser.closed.is_empty());
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_wait_for_new_target_from_blank_link() {
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.unwrap();
+    browser
+        .navigate("data:text/html,<a href='about:blank#popup' target='_blank'>Open</a>")
+        .await
+        .unwrap();
+
+    let page = browser.get_page().unwrap();
+    let link = page
+        .get_elements_by_css_selector("a")
+        .await
+        .unwrap()
+        .remove(0);
+    let target_id = browser
+        .wait_for_new_target(
+            link.click(browsing::actor::mouse::MouseButton::Left, 1, None),
+            5_000,
+        )
+        .await
+        .unwrap();
+
+    browser
+        .handle_new_window(&target_id, NewWindowHandling::AutoSwitch)
+        .await
+        .unwrap();
+    assert!(browser.get_current_url().await.unwrap().contains("#popup"));
+
+    browser.stop().await.unwrap();
+}
@@ -0,0 +1,151 @@
+//! Page state size when restricted to element categories
+
+mod common;
+
+use browsing::dom::{
+    DOMProcessorImpl, EMPTY_PAGE_STATE, NO_ELEMENTS_IN_CATEGORIES, NodeCategory,
+    SerializationOptions, SerializedDOMState,
+};
+use browsing::traits::DOMProcessor;
+use common::{document_from_html, fake_cdp};
+use serde_json::json;
+
+const LAPTOP_STORE: &str = include_str!("fixtures/dom/laptop_store.html");
+
+async fn serialize(html: &'static str, categories: &[NodeCategory]) -> SerializedDOMState {
+    let (client, _) = fake_cdp(Box::new(move |method, _| match method {
+        "DOM.getDocument" => Ok(document_from_html(html)),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    DOMProcessorImpl::new()
+        .with_cdp_client(client, "S1".to_string())
+        .with_target_id("T1".to_string())
+        .with_serialization_options(SerializationOptions {
+            node_categories: categories.to_vec(),
+            ..Default::default()
+        })
+        .get_serialized_dom()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_interactive_page_state_is_smaller() {
+    let all = serialize(LAPTOP_STORE, &[]).await;
+    let interactive = serialize(LAPTOP_STORE, &[NodeCategory::Interactive]).await;
+    let (all_text, interactive_text) = (all.page_state(), interactive.page_state());
+
+    println!(
+        "all: {} chars, interactive: {} chars ({:.0}% smaller)",
+        all_text.len(),
+        interactive_text.len(),
+        100.0 * (1.0 - interactive_text.len() as f64 / all_text.len() as f64)
+    );
+    assert!(
+        interactive_text.len() * 10 <= all_text.len() * 6,
+        "{} vs {}",
+        interactive_text.len(),
+        all_text.len()
+    );
+    assert!(interactive_text.contains("Laptop 12 Pro 14"));
+    assert!(interactive_text.contains("Add to cart"));
+    assert!(!interactive_text.contains("Battery life matters"));
+    assert!(!interactive_text.contains("Model 3"));
+}
+
+#[tokio::test]
+async fn test_filtering_keeps_every_index() {
+    let all = serialize(LAPTOP_STORE, &[]).await;
+    assert!(!all.selector_map.is_empty());
+
+    for categories in [
+        &[NodeCategory::Interactive][..],
+        &[NodeCategory::Media],
+        &[NodeCategory::DataContent, NodeCategory::Navigation],
+    ] {
+        let filtered = serialize(LAPTOP_STORE, categories).await;
+        assert_eq!(filtered.selector_map.len(), all.selector_map.len());
+        for (index, element) in &all.selector_map {
+            assert_eq!(
+                filtered.selector_map[index].backend_node_id,
+                element.backend_node_id
+            );
+        }
+    }
+
+    // The last result's button keeps the index it has in the full page state
+    let last = all.selector_map.keys().max().unwrap();
+    let interactive = serialize(LAPTOP_STORE, &[NodeCategory::Interactive]).await;
+    assert!(interactive.page_state().contains(&format!("[{last}]")));
+}
+
+#[tokio::test]
+async fn test_data_content_keeps_text() {
+    let state = serialize(LAPTOP_STORE, &[NodeCategory::DataContent]).await;
+    let text = state.page_state();
+
+    assert!(text.contains("Battery life matters more than peak speed"));
+    assert!(text.contains("Laptops for work and study"));
+    assert!(text.contains("Model 5"));
+    assert!(text.contains("1.6 kg"));
+    assert!(!text.contains("Video review"));
+}
+
+#[tokio::test]
+async fn test_navigation_keeps_landmark_links() {
+    let state = serialize(LAPTOP_STORE, &[NodeCategory::Navigation]).await;
+    let text = state.page_state();
+
+    for link in ["Laptops", "Support", "Docking stations", "Careers"] {
+        assert!(text.contains(link), "missing {link}");
+    }
+    assert!(!text.contains("Laptop 3 Pro 14"));
+    assert!(!text.contains("Battery life matters"));
+}
+
+#[tokio::test]
+async fn test_media_keeps_images_and_video() {
+    let state = serialize(LAPTOP_STORE, &[NodeCategory::Media]).await;
+    let text = state.page_state();
+
+    assert!(text.contains("Laptop 7"));
+    assert!(text.contains("Video review"));
+    assert!(!text.contains("Add to cart"));
+    assert!(!text.contains("Battery life matters"));
+}
+
+#[tokio::test]
+async fn test_all_category_matches_unfiltered() {
+    let all = serialize(LAPTOP_STORE, &[]).await;
+    let explicit = serialize(LAPTOP_STORE, &[NodeCategory::All]).await;
+
+    assert_eq!(explicit.page_state(), all.page_state());
+}
+
+#[tokio::test]
+async fn test_category_without_matches() {
+    let state = serialize(
+        "<html><body><p>Only text here</p></body></html>",
+        &[NodeCategory::Media],
+    )
+    .await;
+
+    assert_eq!(state.page_state(), NO_ELEMENTS_IN_CATEGORIES);
+}
+
+#[tokio::test]
+async fn test_text_only_page_is_not_empty() {
+    // Nothing to interact with, but text for the selected categories
+    let state = serialize(
+        "<html><body><article><h1>Opening hours</h1><p>Mon-Fri 9-17</p></article></body></html>",
+        &[NodeCategory::DataContent],
+    )
+    .await;
+
+    assert!(state.selector_map.is_empty());
+    assert!(!state.is_empty());
+    let text = state.page_state();
+    assert_ne!(text, EMPTY_PAGE_STATE);
+    assert!(text.contains("Mon-Fri 9-17"), "{text}");
+}
@@ -0,0 +1,517 @@
+//! Tests for opening several links in background tabs and extracting from each
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::actor::Page;
+use browsing::agent::views::ActionResult;
+use browsing::browser::cdp::CdpClient;
+use browsing::browser::views::TabInfo;
+use browsing::browser::{Browser, BrowserProfile};
+use browsing::dom::views::DOMInteractedElement;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use browsing::tools::Tools;
+use browsing::tools::handlers::extract::OPEN_AND_EXTRACT_METADATA_KEY;
+use browsing::traits::BrowserClient;
+use common::{HttpResponse, fake_cdp, serve_http};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Pages of the fake site, by URL
+const SITE: &[(&str, &str)] = &[
+    ("https://example.com/", "Home\nSee the docs"),
+    (
+        "https://example.com/docs/a",
+        "Alpha guide\nInstall with cargo",
+    ),
+    (
+        "https://example.com/docs/b",
+        "Beta guide\nConfigure the profile",
+    ),
+    ("https://example.com/docs/c", "Gamma guide\nRun the agent"),
+    ("https://example.com/docs/d", "Delta guide\nRead the logs"),
+    ("https://example.com/docs/broken", "FAIL"),
+];
+
+/// Browser whose tabs are each backed by their own fake endpoint
+#[derive(Default)]
+struct TabsBrowser {
+    tabs: HashMap<String, Arc<CdpClient>>,
+    opened: usize,
+    closed: Vec<String>,
+    most_open: usize,
+}
+
+impl TabsBrowser {
+    async fn page_client(text: &'static str) -> Arc<CdpClient> {
+        // The first evaluation reads document.readyState, the next the page text
+        let (client, _) = fake_cdp(Box::new(move |method, call| match (method, call) {
+            ("Runtime.evaluate", 1) => Ok(json!({ "result": { "value": "complete" } })),
+            ("Runtime.evaluate", _) => Ok(json!({ "result": { "value": text } })),
+            _ => Ok(json!({})),
+        }))
+        .await;
+        client
+    }
+}
+
+#[async_trait]
+impl BrowserClient for TabsBrowser {
+    async fn start(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn navigate(&mut self, _url: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_current_url(&self) -> Result<String> {
+        Ok("https://example.com/".to_string())
+    }
+
+    async fn create_tab(&mut self, url: Option<&str>) -> Result<String> {
+        let url = url.unwrap();
+        let Some((_, text)) = SITE.iter().find(|(page, _)| *page == url) else {
+            return Err(BrowsingError::Browser(format!(
+                "net::ERR_NAME_NOT_RESOLVED at {url}"
+            )));
+        };
+        self.opened += 1;
+        let target_id = format!("TAB{}", self.opened);
+        self.tabs
+            .insert(target_id.clone(), Self::page_client(text).await);
+        self.most_open = self.most_open.max(self.tabs.len());
+        Ok(target_id)
+    }
+
+    async fn switch_to_tab(&mut self, _target_id: &str) -> Result<()> {
+        panic!("open_and_extract must stay on the current tab");
+    }
+
+    async fn close_tab(&mut self, target_id: &str) -> Result<()> {
+        self.tabs.remove(target_id);
+        self.closed.push(target_id.to_string());
+        Ok(())
+    }
+
+    async fn get_tabs(&self) -> Result<Vec<TabInfo>> {
+        Ok(vec![])
+    }
+
+    async fn get_target_id_from_tab_id(&self, tab_id: &str) -> Result<String> {
+        Ok(tab_id.to_string())
+    }
+
+    fn get_page(&self) -> Result<Page> {
+        Err(BrowsingError::Browser(
+            "The current page is not read".to_string(),
+        ))
+    }
+
+    fn get_page_for_target(&self, target_id: &str) -> Result<Page> {
+        Ok(Page::new(
+            Arc::clone(&self.tabs[target_id]),
+            format!("S-{target_id}"),
+        ))
+    }
+
+    async fn take_screenshot(&self, _path: Option<&str>, _full_page: bool) -> Result<Vec<u8>> {
+        Ok(vec![])
+    }
+
+    async fn get_current_page_title(&self) -> Result<String> {
+        Ok("Home".to_string())
+    }
+
+    fn get_cdp_client(&self) -> Result<Arc<CdpClient>> {
+        Err(BrowsingError::Browser("No current session".to_string()))
+    }
+
+    fn get_session_id(&self) -> Result<String> {
+        Ok("S1".to_string())
+    }
+
+    fn get_current_target_id(&self) -> Result<String> {
+        Ok("T1".to_string())
+    }
+}
+
+/// Model that answers with the first line of the content, failing on "FAIL",
+/// and records how many calls overlapped
+#[derive(Default)]
+struct FirstLineLLM {
+    in_flight: AtomicUsize,
+    most_in_flight: AtomicUsize,
+    prompts: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl ChatModel for FirstLineLLM {
+    fn model(&self) -> &str {
+        "first-line"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.most_in_flight.fetch_max(now, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+        let prompt = messages[1].content.clone();
+        self.prompts.lock().unwrap().push(prompt.clone());
+        let content = prompt.split("Content:\n").nth(1).unwrap_or_default();
+        let first_line = content
+            .lines()
+            .find(|line| !line.trim().is_empty() && !line.starts_with('<'))
+            .unwrap_or_default();
+        if first_line == "FAIL" {
+            return Err(BrowsingError::Llm("Rate limited".to_string()));
+        }
+        Ok(ChatInvokeCompletion::new(first_line.to_string()))
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+fn link(index: u32, href: Option<&str>) -> DOMInteractedElement {
+    serde_json::from_value(json!({
+        "index": index,
+        "backend_node_id": index + 100,
+        "tag": if href.is_some() { "a" } else { "button" },
+        "text": null,
+        "attributes": href.map_or(json!({}), |href| json!({ "href": href })),
+        "selector": null,
+    }))
+    .unwrap()
+}
+
+async fn open_and_extract(
+    browser: &mut TabsBrowser,
+    params: Value,
+    llm: Option<&dyn ChatModel>,
+) -> Result<ActionResult> {
+    let selector_map = HashMap::from([
+        (3, link(3, Some("/docs/b"))),
+        (4, link(4, None)),
+        (5, link(5, Some("docs/c"))),
+    ]);
+    let action = serde_json::from_value(json!({
+        "action_type": "open_and_extract",
+        "params": params
+    }))
+    .unwrap();
+    Tools::default()
+        .act_with_llm(action, browser, Some(&selector_map), llm)
+        .await
+}
+
+fn results(result: &ActionResult) -> &Vec<Value> {
+    result.metadata.as_ref().unwrap()[OPEN_AND_EXTRACT_METADATA_KEY]
+        .as_array()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_links_are_opened_extracted_and_closed_in_order() {
+    let llm = FirstLineLLM::default();
+    let mut browser = TabsBrowser::default();
+    let result = open_and_extract(
+        &mut browser,
+        json!({
+            "urls": ["https://example.com/docs/a", "https://example.com/docs/d"],
+            "indices": [3, 5],
+            "query": "Title of the guide",
+            "concurrency": 2
+        }),
+        Some(&llm),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        *results(&result),
+        [
+            json!({ "url": "https://example.com/docs/a", "result": "Alpha guide" }),
+            json!({ "url": "https://example.com/docs/d", "result": "Delta guide" }),
+            json!({ "url": "https://example.com/docs/b", "result": "Beta guide" }),
+            json!({ "url": "https://example.com/docs/c", "result": "Gamma guide" }),
+        ]
+    );
+    // Every tab is closed again, never more than two at once, read concurrently
+    assert_eq!(browser.opened, 4);
+    assert_eq!(browser.closed.len(), 4);
+    assert!(browser.tabs.is_empty());
+    assert_eq!(browser.most_open, 2);
+    assert_eq!(llm.most_in_flight.load(Ordering::SeqCst), 2);
+
+    let content = result.extracted_content.unwrap();
+    assert!(content.contains("<untrusted_page_content"), "{content}");
+    assert_eq!(
+        result.long_term_memory.as_deref(),
+        Some("Extracted Title of the guide from 4 of 4 pages opened")
+    );
+}
+
+#[tokio::test]
+async fn test_failed_pages_do_not_abort_the_batch() {
+    let llm = FirstLineLLM::default();
+    let mut browser = TabsBrowser::default();
+    let result = open_and_extract(
+        &mut browser,
+        json!({
+            "urls": [
+                "https://example.com/docs/broken",
+                "https://missing.example/",
+                "https://example.com/docs/a"
+            ],
+            "indices": [4],
+            "query": "Title of the guide"
+        }),
+        Some(&llm),
+    )
+    .await
+    .unwrap();
+
+    let results = results(&result);
+    assert_eq!(results.len(), 4);
+    assert!(
+        results[0]["error"]
+            .as_str()
+            .unwrap()
+            .contains("LLM extraction failed"),
+        "{results:?}"
+    );
+    assert!(
+        results[1]["error"]
+            .as_str()
+            .unwrap()
+            .contains("ERR_NAME_NOT_RESOLVED")
+    );
+    assert_eq!(results[2]["result"], "Alpha guide");
+    assert_eq!(results[3]["url"], "element 4");
+    assert!(
+        results[3]["error"]
+            .as_str()
+            .unwrap()
+            .contains("Element 4 is not a link")
+    );
+
+    // The tab of the page that failed extraction is closed too
+    assert_eq!(browser.closed.len(), 2);
+    let memory = result.long_term_memory.unwrap();
+    assert!(
+        memory.starts_with("Extracted Title of the guide from 1 of 4 pages opened; failed:"),
+        "{memory}"
+    );
+}
+
+#[tokio::test]
+async fn test_named_queries_are_answered_per_page() {
+    struct PriceLLM;
+
+    #[async_trait]
+    impl ChatModel for PriceLLM {
+        fn model(&self) -> &str {
+            "price"
+        }
+
+        fn provider(&self) -> &str {
+            "test"
+        }
+
+        async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+            let title = if messages[1].content.contains("Alpha") {
+                "Alpha guide"
+            } else {
+                "Beta guide"
+            };
+            Ok(ChatInvokeCompletion::new(
+                json!({ "title": title, "price": "n/a" }).to_string(),
+            ))
+        }
+
+        async fn chat_stream(
+            &self,
+            _messages: &[ChatMessage],
+        ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>>
+        {
+            Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+        }
+    }
+
+    let mut browser = TabsBrowser::default();
+    let result = open_and_extract(
+        &mut browser,
+        json!({
+            "urls": ["https://example.com/docs/a"],
+            "indices": [3],
+            "queries": { "title": "Title", "price": "Price" }
+        }),
+        Some(&PriceLLM),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        results(&result)[0]["result"],
+        json!({ "title": "Alpha guide", "price": null })
+    );
+    assert_eq!(results(&result)[1]["result"]["title"], "Beta guide");
+}
+
+#[tokio::test]
+async fn test_without_a_model_page_text_is_returned() {
+    let mut browser = TabsBrowser::default();
+    let result = open_and_extract(
+        &mut browser,
+        json!({ "urls": ["https://example.com/docs/a"], "query": "Title" }),
+        None,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        results(&result)[0]["result"],
+        "Alpha guide\nInstall with cargo"
+    );
+}
+
+#[tokio::test]
+async fn test_pages_to_open_are_required() {
+    let mut browser = TabsBrowser::default();
+    let err = open_and_extract(&mut browser, json!({ "query": "Title" }), None)
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("'urls' or link 'indices'"),
+        "{err}"
+    );
+
+    let err = open_and_extract(
+        &mut browser,
+        json!({ "urls": ["https://example.com/docs/a"] }),
+        None,
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("Missing 'query'"), "{err}");
+    assert_eq!(browser.opened, 0);
+}
+
+#[tokio::test]
+async fn test_background_tabs_open_behind_and_wait_for_their_page() {
+    // The new tab is still on about:blank when first read; its navigation
+    // commits while the frame tree is requested
+    let (url, received) = common::fake_cdp_url_with_events(
+        Box::new(|method, call| match method {
+            "Target.getTargets" => Ok(json!({
+                "targetInfos": [{ "targetId": "T1", "type": "page", "url": "https://example.com/" }]
+            })),
+            "Target.createTarget" => Ok(json!({ "targetId": "T2" })),
+            "Target.attachToTarget" => Ok(json!({ "sessionId": format!("S{call}") })),
+            "Page.getFrameTree" => Ok(json!({
+                "frameTree": { "frame": { "id": "F2", "url": "about:blank" } }
+            })),
+            _ => Ok(json!({})),
+        }),
+        Box::new(|method, _| match method {
+            "Page.getFrameTree" => vec![json!({
+                "method": "Page.frameNavigated",
+                "sessionId": "S2",
+                "params": { "frame": { "id": "F2", "url": "https://example.com/docs/a" } }
+            })],
+            _ => vec![],
+        }),
+    )
+    .await;
+    let mut browser = Browser::new(BrowserProfile::default()).with_cdp_url(url);
+    browser.start().await.unwrap();
+
+    let target_id = tokio::time::timeout(
+        Duration::from_secs(5),
+        browser.create_background_tab("https://example.com/docs/a"),
+    )
+    .await
+    .expect("the tab's navigation was missed")
+    .unwrap();
+
+    assert_eq!(target_id, "T2");
+    assert_eq!(browser.get_current_target_id().unwrap(), "T1");
+    let created: Vec<Value> = received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, _, _)| method == "Target.createTarget")
+        .map(|(_, params, _)| params.clone())
+        .collect();
+    assert_eq!(
+        created,
+        [json!({ "url": "https://example.com/docs/a", "background": true })]
+    );
+}
+
+/// Serve the research fixture site on localhost
+async fn serve_fixture() -> String {
+    serve_http(|request| match request.path() {
+        "/" => HttpResponse::html(include_str!("fixtures/research/index.html")),
+        "/alpha.html" => HttpResponse::html(include_str!("fixtures/research/alpha.html")),
+        "/beta.html" => HttpResponse::html(include_str!("fixtures/research/beta.html")),
+        "/gamma.html" => HttpResponse::html(include_str!("fixtures/research/gamma.html")),
+        _ => HttpResponse::html("<html><body><h1>Not found</h1></body></html>")
+            .with_status("404 Not Found"),
+    })
+    .await
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_open_and_extract_fixture_site_links() {
+    let url = serve_fixture().await;
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.unwrap();
+    browser.navigate(&url).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let links = browser
+        .get_page()
+        .unwrap()
+        .evaluate("JSON.stringify([...document.querySelectorAll('a')].map(a => a.href))")
+        .await
+        .unwrap();
+    let links: Vec<String> = serde_json::from_str(&links).unwrap();
+    assert_eq!(links.len(), 4);
+    let tabs_before = browser.get_tabs(false).await.unwrap().len();
+
+    let llm = FirstLineLLM::default();
+    let action = serde_json::from_value(json!({
+        "action_type": "open_and_extract",
+        "params": { "urls": links, "query": "Title of the page", "concurrency": 2 }
+    }))
+    .unwrap();
+    let result = Tools::default()
+        .act_with_llm(action, &mut browser, None, Some(&llm))
+        .await
+        .unwrap();
+
+    let answers: Vec<&str> = results(&result)
+        .iter()
+        .map(|r| r["result"].as_str().unwrap_or_default())
+        .collect();
+    assert_eq!(answers, ["Alpha", "Beta", "Gamma", "Not found"]);
+    // Back on the index page, with the opened tabs closed
+    assert_eq!(browser.get_current_url().await.unwrap(), url);
+    assert_eq!(browser.get_tabs(false).await.unwrap().len(), tabs_before);
+    browser.stop().await.unwrap();
+}
@@ -0,0 +1,195 @@
+//! Tests for the agent's overview of its open tabs
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::actor::Page;
+use browsing::agent::service::Agent;
+use browsing::browser::cdp::CdpClient;
+use browsing::browser::views::TabInfo;
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use browsing::traits::BrowserClient;
+use common::{Received, document_from_html, fake_cdp};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+
+const TWO_FORMS: &str = include_str!("fixtures/forms/two_forms.html");
+
+/// Browser with two tabs, T1 (session S1) and T2 (session S2)
+struct TwoTabBrowser {
+    client: Arc<CdpClient>,
+    current: usize,
+}
+
+const TABS: [(&str, &str, &str); 2] = [
+    ("T1", "S1", "https://example.com/"),
+    ("T2", "S2", "https://other.com/"),
+];
+
+#[async_trait]
+impl BrowserClient for TwoTabBrowser {
+    async fn start(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn navigate(&mut self, _url: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_current_url(&self) -> Result<String> {
+        Ok(TABS[self.current].2.to_string())
+    }
+
+    async fn create_tab(&mut self, _url: Option<&str>) -> Result<String> {
+        Err(BrowsingError::Browser("not supported".to_string()))
+    }
+
+    async fn switch_to_tab(&mut self, target_id: &str) -> Result<()> {
+        self.current = TABS.iter().position(|tab| tab.0 == target_id).unwrap();
+        Ok(())
+    }
+
+    async fn close_tab(&mut self, _target_id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_tabs(&self) -> Result<Vec<TabInfo>> {
+        Ok(TABS
+            .iter()
+            .map(|(target_id, _, url)| {
+                serde_json::from_value(json!({
+                    "url": url, "title": "", "target_id": target_id, "parent_target_id": null
+                }))
+                .unwrap()
+            })
+            .collect())
+    }
+
+    async fn get_target_id_from_tab_id(&self, tab_id: &str) -> Result<String> {
+        Ok(tab_id.to_string())
+    }
+
+    fn get_page(&self) -> Result<Page> {
+        Ok(Page::new(
+            Arc::clone(&self.client),
+            TABS[self.current].1.to_string(),
+        ))
+    }
+
+    async fn take_screenshot(&self, _path: Option<&str>, _full_page: bool) -> Result<Vec<u8>> {
+        Ok(vec![])
+    }
+
+    async fn get_current_page_title(&self) -> Result<String> {
+        Ok(String::new())
+    }
+
+    fn get_cdp_client(&self) -> Result<Arc<CdpClient>> {
+        Ok(Arc::clone(&self.client))
+    }
+
+    fn get_session_id(&self) -> Result<String> {
+        Ok(TABS[self.current].1.to_string())
+    }
+
+    fn get_current_target_id(&self) -> Result<String> {
+        Ok(TABS[self.current].0.to_string())
+    }
+}
+
+/// Model that switches to tab T2, then finishes, recording its prompts
+#[derive(Clone, Default)]
+struct SwitchingLLM {
+    prompts: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl ChatModel for SwitchingLLM {
+    fn model(&self) -> &str {
+        "switching"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        let mut prompts = self.prompts.lock().unwrap();
+        prompts.push(messages[1].content.clone());
+        let action = if prompts.len() == 1 {
+            json!({ "action_type": "switch", "params": { "tab_id": "T2" } })
+        } else {
+            json!({ "action_type": "done", "params": { "text": "ok" } })
+        };
+        Ok(ChatInvokeCompletion {
+            completion: json!({ "action": [action] }).to_string(),
+            thinking: None,
+            redacted_thinking: None,
+            usage: None,
+            stop_reason: None,
+        })
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+fn sessions_of(received: &Received, method: &str) -> Vec<String> {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(m, _, _)| m == method)
+        .filter_map(|(_, _, session)| session.clone())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_prompt_has_tab_overview_that_follows_switches() {
+    let document: Value = document_from_html(TWO_FORMS);
+    let (client, received) = fake_cdp(Box::new(move |method, _| match method {
+        "DOM.getDocument" => Ok(document.clone()),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let llm = SwitchingLLM::default();
+    Agent::new(
+        "Compare two shops".to_string(),
+        Box::new(TwoTabBrowser { client, current: 0 }),
+        Box::new(DOMProcessorImpl::new()),
+        llm.clone(),
+    )
+    .with_max_steps(2)
+    .run()
+    .await
+    .unwrap();
+
+    let prompts = llm.prompts.lock().unwrap();
+    assert!(
+        prompts[0].contains(
+            "Open tabs:\n\
+             Tab 1 (current): #T1 (untitled) (https://example.com/) - 7 interactive elements\n\
+             Tab 2: #T2 (untitled) (https://other.com/) - not viewed yet\n\n"
+        ),
+        "{}",
+        prompts[0]
+    );
+    assert!(
+        prompts[1].contains(
+            "Tab 1: #T1 (untitled) (https://example.com/) - 7 interactive elements\n\
+             Tab 2 (current): #T2 (untitled) (https://other.com/) - 7 interactive elements"
+        ),
+        "{}",
+        prompts[1]
+    );
+    // The page state of the second step was read from the tab switched to
+    let mut sessions = sessions_of(&received, "DOM.getDocument");
+    sessions.dedup();
+    assert_eq!(sessions, ["S1", "S2"]);
+}
@@ -0,0 +1,154 @@
+//! Tests for paint timing against a scripted CDP endpoint
+
+mod common;
+
+use browsing::actor::{LoadState, Page};
+use common::{fake_cdp, methods};
+use serde_json::{Value, json};
+
+fn evaluated(value: Value) -> Value {
+    json!({ "result": { "type": "object", "value": value } })
+}
+
+fn painted(lcp: Option<f64>) -> Value {
+    evaluated(json!({
+        "paint": r#"[{"name":"first-paint","startTime":120.5},{"name":"first-contentful-paint","startTime":180.25}]"#,
+        "lcp": lcp
+    }))
+}
+
+fn not_painted() -> Value {
+    evaluated(json!({ "paint": "[]", "lcp": null }))
+}
+
+fn evaluations(received: &common::Received) -> usize {
+    methods(received)
+        .iter()
+        .filter(|m| *m == "Runtime.evaluate")
+        .count()
+}
+
+#[tokio::test]
+async fn test_complete_paint_timing_is_cached() {
+    let (client, received) = fake_cdp(Box::new(|_, _| Ok(painted(Some(400.0))))).await;
+    let page = Page::new(client, "S1".to_string());
+
+    let timing = page.get_paint_timing().await.unwrap();
+    assert_eq!(timing.first_paint_ms, Some(120.5));
+    assert_eq!(timing.first_contentful_paint_ms, Some(180.25));
+    assert_eq!(timing.largest_contentful_paint_ms, Some(400.0));
+
+    assert_eq!(page.get_paint_timing().await.unwrap(), timing);
+    assert_eq!(evaluations(&received), 1);
+}
+
+#[tokio::test]
+async fn test_incomplete_paint_timing_is_measured_again() {
+    let (client, received) = fake_cdp(Box::new(|_, call| {
+        Ok(if call == 1 {
+            painted(None)
+        } else {
+            painted(Some(400.0))
+        })
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    assert!(
+        page.get_paint_timing()
+            .await
+            .unwrap()
+            .largest_contentful_paint_ms
+            .is_none()
+    );
+    assert_eq!(
+        page.get_paint_timing()
+            .await
+            .unwrap()
+            .largest_contentful_paint_ms,
+        Some(400.0)
+    );
+    assert_eq!(evaluations(&received), 2);
+}
+
+#[tokio::test]
+async fn test_wait_for_first_contentful_paint_polls() {
+    let (client, received) = fake_cdp(Box::new(|_, call| {
+        Ok(if call < 3 {
+            not_painted()
+        } else {
+            painted(None)
+        })
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    let fcp = page.wait_for_first_contentful_paint(2000).await.unwrap();
+    assert_eq!(fcp, 180.25);
+    assert_eq!(evaluations(&received), 3);
+    // Evaluated in the page session
+    assert!(
+        received
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|(_, _, session)| session.as_deref() == Some("S1"))
+    );
+}
+
+#[tokio::test]
+async fn test_wait_for_first_contentful_paint_times_out() {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(not_painted()))).await;
+    let page = Page::new(client, "S1".to_string());
+
+    let err = page.wait_for_first_contentful_paint(120).await.unwrap_err();
+    assert!(err.to_string().contains("First Contentful Paint"), "{err}");
+}
+
+#[tokio::test]
+async fn test_load_state_waits_for_content() {
+    // readyState: loading, complete; then paint samples
+    let (client, received) = fake_cdp(Box::new(|_, call| {
+        Ok(match call {
+            1 => evaluated(json!("loading")),
+            2 => evaluated(json!("complete")),
+            3 => not_painted(),
+            _ => painted(Some(400.0)),
+        })
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    page.wait_for_load_state(LoadState::Load, 2000)
+        .await
+        .unwrap();
+    assert_eq!(evaluations(&received), 4);
+}
+
+#[tokio::test]
+async fn test_dom_content_loaded_does_not_wait_for_paint() {
+    let (client, received) = fake_cdp(Box::new(|_, _| Ok(evaluated(json!("interactive"))))).await;
+    let page = Page::new(client, "S1".to_string());
+
+    page.wait_for_load_state(LoadState::DomContentLoaded, 2000)
+        .await
+        .unwrap();
+    assert_eq!(evaluations(&received), 1);
+}
+
+#[tokio::test]
+async fn test_load_state_without_paint_still_loads() {
+    let (client, _) = fake_cdp(Box::new(|_, call| {
+        Ok(if call == 1 {
+            evaluated(json!("complete"))
+        } else {
+            not_painted()
+        })
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    page.wait_for_load_state(LoadState::Load, 150)
+        .await
+        .unwrap();
+}
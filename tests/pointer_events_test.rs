@@ -0,0 +1,163 @@
+//! Tests for dispatching pointer events from mice, pens and touch
+
+mod common;
+
+use browsing::actor::{Page, PointerEventType, PointerType};
+use browsing::tools::Tools;
+use common::{FakePageBrowser, Received, fake_cdp};
+use serde_json::{Value, json};
+
+/// Commands sent to the endpoint, as (method, params)
+fn commands(received: &Received) -> Vec<(String, Value)> {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(method, params, _)| (method.clone(), params.clone()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_pen_stroke() {
+    let (client, received) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let page = Page::new(client, "S1".to_string());
+
+    for (event_type, x, pressure) in [
+        (PointerEventType::Down, 10.0, 0.3),
+        (PointerEventType::Move, 20.0, 0.8),
+        (PointerEventType::Up, 30.0, 0.0),
+    ] {
+        page.dispatch_pointer_event(
+            event_type,
+            x,
+            40.0,
+            2,
+            PointerType::Pen,
+            pressure,
+            20.0,
+            -10.0,
+        )
+        .await
+        .unwrap();
+    }
+
+    let sent = commands(&received);
+    let summary: Vec<(&str, &str, i64)> = sent
+        .iter()
+        .map(|(method, params)| {
+            (
+                method.as_str(),
+                params["type"].as_str().unwrap(),
+                params["buttons"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("Input.dispatchMouseEvent", "mousePressed", 1),
+            ("Input.dispatchMouseEvent", "mouseMoved", 1),
+            ("Input.dispatchMouseEvent", "mouseReleased", 0),
+        ]
+    );
+    let (_, moved) = &sent[1];
+    assert_eq!(moved["pointerType"], "pen");
+    assert_eq!(moved["force"], 0.8);
+    assert_eq!(moved["tiltX"], 20.0);
+    assert_eq!(moved["tiltY"], -10.0);
+    assert!(
+        received
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|(_, _, session)| session.as_deref() == Some("S1"))
+    );
+}
+
+#[tokio::test]
+async fn test_touch_with_two_fingers() {
+    let (client, received) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let page = Page::new(client, "S1".to_string());
+
+    for pointer_id in [1, 2] {
+        page.dispatch_pointer_event(
+            PointerEventType::Down,
+            50.0 * f64::from(pointer_id),
+            100.0,
+            pointer_id,
+            PointerType::Touch,
+            1.0,
+            0.0,
+            0.0,
+        )
+        .await
+        .unwrap();
+    }
+    page.dispatch_pointer_event(
+        PointerEventType::Cancel,
+        0.0,
+        0.0,
+        1,
+        PointerType::Touch,
+        0.0,
+        0.0,
+        0.0,
+    )
+    .await
+    .unwrap();
+
+    let sent = commands(&received);
+    assert!(
+        sent.iter()
+            .all(|(method, _)| method == "Input.dispatchTouchEvent")
+    );
+    assert_eq!(sent[1].1["touchPoints"][0]["id"], 2);
+    assert_eq!(sent[1].1["touchPoints"][0]["x"], 100.0);
+    assert_eq!(
+        sent[2].1,
+        json!({ "type": "touchCancel", "touchPoints": [] })
+    );
+}
+
+#[tokio::test]
+async fn test_click_in_pointer_events_mode() {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Page.getLayoutMetrics" => {
+            Ok(json!({ "layoutViewport": { "clientWidth": 1280, "clientHeight": 720 } }))
+        }
+        "DOM.getContentQuads" => Ok(json!({ "quads": [[10, 10, 90, 10, 90, 30, 10, 30]] })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let mut browser = FakePageBrowser { client };
+    let click = serde_json::from_value(json!({ "action_type": "click", "params": { "index": 3 } }))
+        .unwrap();
+
+    let result = Tools::default()
+        .with_pointer_events_mode(true)
+        .with_click_fallback(false)
+        .act(click, &mut browser, None)
+        .await
+        .unwrap();
+
+    assert!(result.error.is_none(), "{:?}", result.error);
+    let input: Vec<(String, Value)> = commands(&received)
+        .into_iter()
+        .filter(|(method, _)| method.starts_with("Input.") || method == "Runtime.evaluate")
+        .collect();
+    assert_eq!(input.len(), 4, "{input:?}");
+    // pointerover and pointerenter on the element, then a pressed mouse
+    let enter = input[0].1["expression"].as_str().unwrap();
+    assert!(enter.contains("pointerenter"), "{enter}");
+    assert!(
+        enter.contains("\"x\":50.0") && enter.contains("\"y\":20.0"),
+        "{enter}"
+    );
+    let types: Vec<&str> = input[1..]
+        .iter()
+        .map(|(_, params)| params["type"].as_str().unwrap())
+        .collect();
+    assert_eq!(types, ["mouseMoved", "mousePressed", "mouseReleased"]);
+    assert_eq!(input[2].1["force"], 0.5);
+    assert_eq!(input[2].1["pointerType"], "mouse");
+}
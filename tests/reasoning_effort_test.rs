@@ -0,0 +1,176 @@
+//! Tests for the reasoning effort setting and outputs without thinking
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::agent::service::Agent;
+use browsing::agent::views::{AgentHistoryList, AgentOutput, AgentSettings, ReasoningEffort};
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatInvokeUsage, ChatMessage, ChatModel};
+use common::{FakePageBrowser, fake_cdp};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+
+/// Model that answers with `outputs` in turn, each costing `completion_tokens`
+#[derive(Clone)]
+struct ScriptedLLM {
+    outputs: Arc<Mutex<Vec<(Value, u32)>>>,
+    system: Arc<Mutex<String>>,
+}
+
+impl ScriptedLLM {
+    fn new(outputs: Vec<(Value, u32)>) -> Self {
+        Self {
+            outputs: Arc::new(Mutex::new(outputs)),
+            system: Arc::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl ChatModel for ScriptedLLM {
+    fn model(&self) -> &str {
+        "scripted"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        *self.system.lock().unwrap() = messages[0].content.clone();
+        let (output, completion_tokens) = self.outputs.lock().unwrap().remove(0);
+        Ok(ChatInvokeCompletion {
+            completion: output.to_string(),
+            thinking: None,
+            redacted_thinking: None,
+            usage: Some(ChatInvokeUsage {
+                prompt_tokens: 1000,
+                prompt_cached_tokens: None,
+                prompt_cache_creation_tokens: None,
+                prompt_image_tokens: None,
+                completion_tokens,
+                total_tokens: 1000 + completion_tokens,
+            }),
+            stop_reason: None,
+        })
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+fn wait_step(thinking: Option<&str>) -> Value {
+    let mut output = json!({
+        "evaluation_previous_goal": "Page loaded",
+        "memory": "On the home page",
+        "next_goal": "Wait for results",
+        "action": [{ "action_type": "wait", "params": { "seconds": 0 } }]
+    });
+    if let Some(thinking) = thinking {
+        output["thinking"] = json!(thinking);
+    }
+    output
+}
+
+fn done_step() -> Value {
+    json!({ "action": [{ "action_type": "done", "params": { "text": "Found it" } }] })
+}
+
+async fn run(effort: ReasoningEffort, llm: ScriptedLLM) -> AgentHistoryList {
+    let (client, _) = fake_cdp(Box::new(|method, _| match method {
+        "DOM.getDocument" => Ok(json!({
+            "root": { "nodeId": 1, "backendNodeId": 1, "nodeType": 9, "nodeName": "#document" }
+        })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    Agent::new(
+        "Find the price".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        llm,
+    )
+    .with_settings(AgentSettings {
+        reasoning_effort: effort,
+        ..Default::default()
+    })
+    .with_max_steps(3)
+    .run()
+    .await
+    .unwrap()
+}
+
+#[test]
+fn test_output_parses_with_and_without_thinking() {
+    let with =
+        AgentOutput::from_json(&wait_step(Some("Results load slowly.")).to_string()).unwrap();
+    assert_eq!(with.thinking.as_deref(), Some("Results load slowly."));
+
+    let without = AgentOutput::from_json(&wait_step(None).to_string()).unwrap();
+    assert!(without.thinking.is_none());
+    assert_eq!(without.current_state().next_goal, "Wait for results");
+}
+
+#[tokio::test]
+async fn test_every_effort_accepts_outputs_without_thinking() {
+    for effort in [
+        ReasoningEffort::Minimal,
+        ReasoningEffort::Normal,
+        ReasoningEffort::Verbose,
+    ] {
+        let llm = ScriptedLLM::new(vec![
+            (wait_step(Some("The list is still loading.")), 40),
+            (wait_step(None), 20),
+            (done_step(), 30),
+        ]);
+        let history = run(effort, llm).await;
+
+        assert!(history.is_done(), "{effort:?}");
+        let thinking: Vec<Option<String>> = history
+            .history
+            .iter()
+            .map(|step| step.model_output.as_ref().unwrap().thinking.clone())
+            .collect();
+        assert_eq!(
+            thinking,
+            [Some("The list is still loading.".to_string()), None, None],
+            "{effort:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_minimal_effort_drops_thinking_from_the_prompt() {
+    let llm = ScriptedLLM::new(vec![(done_step(), 10)]);
+    run(ReasoningEffort::Minimal, llm.clone()).await;
+    let system = llm.system.lock().unwrap().clone();
+    assert!(!system.contains("\"thinking\""), "{system}");
+
+    let llm = ScriptedLLM::new(vec![(done_step(), 10)]);
+    run(ReasoningEffort::Normal, llm.clone()).await;
+    let system = llm.system.lock().unwrap().clone();
+    assert!(
+        system.contains("Keep thinking to at most 3 sentences."),
+        "{system}"
+    );
+}
+
+#[tokio::test]
+async fn test_usage_reports_completion_tokens_per_step() {
+    let llm = ScriptedLLM::new(vec![
+        (wait_step(Some("Long reasoning.")), 90),
+        (wait_step(None), 20),
+        (done_step(), 10),
+    ]);
+    let history = run(ReasoningEffort::Normal, llm).await;
+
+    let usage = history.usage.unwrap();
+    assert_eq!(usage.completion_tokens, Some(120));
+    assert_eq!(usage.avg_completion_tokens_per_step, Some(40.0));
+}
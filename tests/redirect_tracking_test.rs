@@ -0,0 +1,321 @@
+//! Tests for tracking redirect chains and canonical URLs of navigations
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::actor::{NavigateOptions, Page};
+use browsing::agent::service::Agent;
+use browsing::browser::cdp::CdpClient;
+use browsing::browser::views::TabInfo;
+use browsing::browser::{Browser, BrowserProfile, NavigationRecord, Redirect};
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use browsing::traits::BrowserClient;
+use common::{FakePageBrowser, HttpResponse, fake_cdp, fake_cdp_with_events, serve_http};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+
+fn request_sent(session_id: &str, request_id: &str, url: &str, from: Option<&str>) -> Value {
+    let mut params = json!({ "requestId": request_id, "request": { "url": url } });
+    if let Some(from) = from {
+        params["redirectResponse"] = json!({ "url": from, "status": 302 });
+    }
+    json!({ "method": "Network.requestWillBeSent", "sessionId": session_id, "params": params })
+}
+
+#[tokio::test]
+async fn test_goto_tracked_records_the_redirect_chain() {
+    let (client, received) = fake_cdp_with_events(
+        Box::new(|method, _| match method {
+            "Page.navigate" => Ok(json!({ "frameId": "F1", "loaderId": "L1" })),
+            _ => Ok(json!({})),
+        }),
+        Box::new(|method, _| match method {
+            "Page.navigate" => vec![
+                request_sent("S1", "L1", "https://shop.example/", None),
+                request_sent(
+                    "S1",
+                    "L1",
+                    "https://shop.example/en/",
+                    Some("https://shop.example/"),
+                ),
+                // Another tab's navigation and a subresource are not part of it
+                request_sent("S2", "L1", "https://other.example/", None),
+                request_sent("S1", "R7", "https://shop.example/logo.png", None),
+                request_sent(
+                    "S1",
+                    "L1",
+                    "https://shop.example/en/login",
+                    Some("https://shop.example/en/"),
+                ),
+            ],
+            _ => vec![],
+        }),
+    )
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    let record = page
+        .goto_tracked("https://shop.example/", &NavigateOptions::new())
+        .await
+        .unwrap();
+
+    assert_eq!(record.requested_url, "https://shop.example/");
+    assert_eq!(record.final_url, "https://shop.example/en/login");
+    assert_eq!(
+        record.redirects,
+        [
+            Redirect {
+                url: "https://shop.example/".to_string(),
+                status: 302
+            },
+            Redirect {
+                url: "https://shop.example/en/".to_string(),
+                status: 302
+            },
+        ]
+    );
+    assert_eq!(
+        record.summary().as_deref(),
+        Some(
+            "Requested https://shop.example/, landed on https://shop.example/en/login after 2 redirects"
+        )
+    );
+    let methods = common::methods(&received);
+    assert_eq!(methods, ["Network.enable", "Page.navigate"]);
+}
+
+#[tokio::test]
+async fn test_navigation_without_redirects_has_no_summary() {
+    let (client, _) = fake_cdp_with_events(
+        Box::new(|method, _| match method {
+            "Page.navigate" => Ok(json!({ "frameId": "F1", "loaderId": "L1" })),
+            _ => Ok(json!({})),
+        }),
+        Box::new(|method, _| match method {
+            "Page.navigate" => vec![request_sent("S1", "L1", "https://example.com/", None)],
+            _ => vec![],
+        }),
+    )
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    let record = page
+        .goto_tracked("https://example.com/", &NavigateOptions::new())
+        .await
+        .unwrap();
+
+    assert!(!record.was_redirected());
+    assert_eq!(record.final_url, "https://example.com/");
+    assert_eq!(record.summary(), None);
+}
+
+#[tokio::test]
+async fn test_canonical_url() {
+    let (client, _) = fake_cdp(Box::new(|method, call| match (method, call) {
+        ("Runtime.evaluate", 1) => {
+            Ok(json!({ "result": { "type": "string", "value": "https://example.com/article" } }))
+        }
+        ("Runtime.evaluate", _) => Ok(json!({ "result": { "type": "string", "value": "" } })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let browser = FakePageBrowser { client };
+
+    assert_eq!(
+        browser.get_url(true).await.unwrap(),
+        "https://example.com/article"
+    );
+    // Without a canonical link the current URL is used
+    assert_eq!(browser.get_url(true).await.unwrap(), "https://example.com");
+    assert_eq!(browser.get_url(false).await.unwrap(), "https://example.com");
+}
+
+/// Model that records the messages it is sent and finishes at once
+#[derive(Clone, Default)]
+struct RecordingLLM {
+    messages: Arc<Mutex<Vec<ChatMessage>>>,
+}
+
+#[async_trait]
+impl ChatModel for RecordingLLM {
+    fn model(&self) -> &str {
+        "recording"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        self.messages.lock().unwrap().extend_from_slice(messages);
+        Ok(ChatInvokeCompletion::new(
+            json!({ "action": [{ "action_type": "done", "params": { "text": "ok" } }] })
+                .to_string(),
+        ))
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+/// Fake browser whose last navigation was redirected
+struct RedirectedBrowser {
+    inner: FakePageBrowser,
+    navigation: NavigationRecord,
+}
+
+#[async_trait]
+impl BrowserClient for RedirectedBrowser {
+    async fn start(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn navigate(&mut self, url: &str) -> Result<()> {
+        self.inner.navigate(url).await
+    }
+
+    async fn get_current_url(&self) -> Result<String> {
+        self.inner.get_current_url().await
+    }
+
+    async fn last_navigation(&self) -> Option<NavigationRecord> {
+        Some(self.navigation.clone())
+    }
+
+    async fn create_tab(&mut self, url: Option<&str>) -> Result<String> {
+        self.inner.create_tab(url).await
+    }
+
+    async fn switch_to_tab(&mut self, target_id: &str) -> Result<()> {
+        self.inner.switch_to_tab(target_id).await
+    }
+
+    async fn close_tab(&mut self, target_id: &str) -> Result<()> {
+        self.inner.close_tab(target_id).await
+    }
+
+    async fn get_tabs(&self) -> Result<Vec<TabInfo>> {
+        self.inner.get_tabs().await
+    }
+
+    async fn get_target_id_from_tab_id(&self, tab_id: &str) -> Result<String> {
+        self.inner.get_target_id_from_tab_id(tab_id).await
+    }
+
+    fn get_page(&self) -> Result<Page> {
+        self.inner.get_page()
+    }
+
+    async fn take_screenshot(&self, path: Option<&str>, full_page: bool) -> Result<Vec<u8>> {
+        self.inner.take_screenshot(path, full_page).await
+    }
+
+    async fn get_current_page_title(&self) -> Result<String> {
+        Ok("Example".to_string())
+    }
+
+    fn get_cdp_client(&self) -> Result<Arc<CdpClient>> {
+        self.inner.get_cdp_client()
+    }
+
+    fn get_session_id(&self) -> Result<String> {
+        Ok("S1".to_string())
+    }
+
+    fn get_current_target_id(&self) -> Result<String> {
+        Ok("T1".to_string())
+    }
+}
+
+async fn agent_run(final_url: &str) -> (String, Option<NavigationRecord>) {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let browser = RedirectedBrowser {
+        inner: FakePageBrowser { client },
+        navigation: NavigationRecord {
+            target_id: "T1".to_string(),
+            requested_url: "https://example.org/".to_string(),
+            final_url: final_url.to_string(),
+            redirects: vec![Redirect {
+                url: "https://example.org/".to_string(),
+                status: 301,
+            }],
+            client_redirects: vec![],
+            canonical_url: None,
+        },
+    };
+    let llm = RecordingLLM::default();
+    let history = Agent::new(
+        "Read the front page".to_string(),
+        Box::new(browser),
+        Box::new(DOMProcessorImpl::new()),
+        llm.clone(),
+    )
+    .with_max_steps(1)
+    .run()
+    .await
+    .unwrap();
+
+    let prompt = llm.messages.lock().unwrap()[1].content.clone();
+    (prompt, history.history[0].state.navigation.clone())
+}
+
+#[tokio::test]
+async fn test_agent_is_told_where_a_navigation_landed() {
+    let (prompt, navigation) = agent_run("https://example.com").await;
+
+    assert!(prompt.contains(
+        "Navigation: Requested https://example.org/, landed on https://example.com after 1 redirect\n"
+    ));
+    assert_eq!(navigation.unwrap().final_url, "https://example.com");
+
+    // Once the tab has moved on, the old navigation is no longer mentioned
+    let (prompt, navigation) = agent_run("https://example.com/elsewhere").await;
+    assert!(!prompt.contains("Navigation:"));
+    assert_eq!(navigation, None);
+}
+
+/// Serve `/` redirecting twice to `/final`, which declares a canonical URL
+async fn serve_redirect_chain() -> String {
+    serve_http(|request| match request.path() {
+        "/" => HttpResponse::redirect("/middle"),
+        "/middle" => HttpResponse::redirect("/final"),
+        _ => HttpResponse::html(
+            r#"<html><head><link rel="canonical" href="/article"></head><body>Final</body></html>"#,
+        ),
+    })
+    .await
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_browser_records_redirects() {
+    let base = serve_redirect_chain().await;
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.unwrap();
+
+    browser.navigate(&base).await.unwrap();
+
+    let record = browser.last_navigation().unwrap().clone();
+    assert_eq!(record.requested_url, base);
+    assert_eq!(record.final_url, format!("{base}final"));
+    assert_eq!(record.redirects.len(), 2);
+    assert!(
+        record
+            .redirects
+            .iter()
+            .all(|redirect| redirect.status == 302)
+    );
+    assert_eq!(
+        BrowserClient::get_url(&browser, true).await.unwrap(),
+        format!("{base}article")
+    );
+    assert_eq!(browser.navigation_history().len(), 1);
+
+    browser.stop().await.unwrap();
+}
@@ -0,0 +1,211 @@
+//! Tests for collapsing runs of repeated siblings in the page state
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::agent::service::Agent;
+use browsing::agent::views::AgentSettings;
+use browsing::dom::{DOMProcessorImpl, SerializationOptions, SerializedDOMState};
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use browsing::tools::Tools;
+use browsing::traits::DOMProcessor;
+use common::{FakePageBrowser, document_from_html, fake_cdp};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+
+const COLLAPSED_GOLDEN: &str = include_str!("fixtures/dom/fifty_cards_collapsed.txt");
+
+/// A search results page with `cards` structurally identical result cards
+fn cards_page(cards: usize) -> String {
+    let items: String = (1..=cards)
+        .map(|n| {
+            format!(
+                r#"<li class="card result"><a href="/item/{n}">Item {n}</a><span class="price">${n}.00</span><button type="button">Add</button></li>"#
+            )
+        })
+        .collect();
+    format!(
+        r#"<html><body><form><input type="search" name="q"><button type="submit">Search</button></form><ol class="results">{items}</ol><p>Page 1 of 3</p></body></html>"#
+    )
+}
+
+async fn serialize(options: SerializationOptions) -> SerializedDOMState {
+    let (client, _) = fake_cdp(Box::new(|method, _| match method {
+        "DOM.getDocument" => Ok(document_from_html(&cards_page(50))),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    DOMProcessorImpl::new()
+        .with_cdp_client(client, "S1".to_string())
+        .with_target_id("T1".to_string())
+        .with_serialization_options(options)
+        .get_serialized_dom()
+        .await
+        .unwrap()
+}
+
+fn collapsed() -> SerializationOptions {
+    SerializationOptions {
+        collapse_repeated_siblings: true,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_collapsed_cards_match_golden() {
+    let state = serialize(collapsed()).await;
+
+    assert_eq!(state.text.as_deref().unwrap(), COLLAPSED_GOLDEN.trim_end());
+}
+
+#[tokio::test]
+async fn test_collapsing_reduces_tokens_and_keeps_every_index() {
+    let full = serialize(SerializationOptions::default()).await;
+    let collapsed = serialize(collapsed()).await;
+    let full_text = full.text.unwrap();
+    let collapsed_text = collapsed.text.unwrap();
+
+    // Roughly 4 characters per token
+    let (full_tokens, collapsed_tokens) = (full_text.len() / 4, collapsed_text.len() / 4);
+    assert!(
+        collapsed_tokens * 10 < full_tokens,
+        "{collapsed_tokens} tokens collapsed vs {full_tokens} in full"
+    );
+    assert!(full_text.contains("Item 50"));
+    assert!(!collapsed_text.contains("Item 3"));
+
+    // Search input and button, then a link and a button per card
+    assert_eq!(collapsed.selector_map.len(), 2 + 2 * 50);
+    for (index, element) in &full.selector_map {
+        assert_eq!(
+            collapsed.selector_map[index].backend_node_id,
+            element.backend_node_id
+        );
+    }
+    assert_eq!(collapsed.elements.len(), full.elements.len());
+}
+
+#[tokio::test]
+async fn test_collapsed_item_can_still_be_clicked() {
+    let state = serialize(collapsed()).await;
+    let target = &state.selector_map[&37];
+    assert_eq!(target.tag, "a");
+    assert_eq!(target.attributes["href"], "/item/18");
+    assert!(!state.text.as_deref().unwrap().contains("[37]"));
+
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "DOM.getContentQuads" => Ok(json!({ "quads": [[10, 10, 90, 10, 90, 30, 10, 30]] })),
+        "Page.getLayoutMetrics" => {
+            Ok(json!({ "layoutViewport": { "clientWidth": 1280, "clientHeight": 720 } }))
+        }
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let mut browser = FakePageBrowser { client };
+    let action = serde_json::from_value(json!({
+        "action_type": "click",
+        "params": { "index": 37 }
+    }))
+    .unwrap();
+    let result = Tools::default()
+        .act(action, &mut browser, Some(&state.selector_map))
+        .await
+        .unwrap();
+
+    assert!(result.error.is_none(), "{:?}", result.error);
+    let backend_node_id = target.backend_node_id.unwrap();
+    let received = received.lock().unwrap();
+    assert!(received.iter().any(|(method, params, _)| {
+        method == "DOM.getContentQuads" && params["backendNodeId"] == backend_node_id
+    }));
+    assert!(
+        received
+            .iter()
+            .any(|(method, _, _)| method == "Input.dispatchMouseEvent")
+    );
+}
+
+#[tokio::test]
+async fn test_short_runs_are_not_collapsed() {
+    let (client, _) = fake_cdp(Box::new(|method, _| match method {
+        "DOM.getDocument" => Ok(document_from_html(&cards_page(4))),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let state = DOMProcessorImpl::new()
+        .with_cdp_client(client, "S1".to_string())
+        .with_target_id("T1".to_string())
+        .with_serialization_options(collapsed())
+        .get_serialized_dom()
+        .await
+        .unwrap();
+    let text = state.text.unwrap();
+
+    assert!(text.contains("Item 4"), "{text}");
+    assert!(!text.contains("more similar items"));
+}
+
+/// Model that records the messages it is sent and finishes right away
+#[derive(Clone, Default)]
+struct RecordingLLM {
+    messages: Arc<Mutex<Vec<ChatMessage>>>,
+}
+
+#[async_trait]
+impl ChatModel for RecordingLLM {
+    fn model(&self) -> &str {
+        "recording"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        self.messages.lock().unwrap().extend_from_slice(messages);
+        Ok(ChatInvokeCompletion {
+            completion:
+                json!({ "action": [{ "action_type": "done", "params": { "text": "ok" } }] })
+                    .to_string(),
+            thinking: None,
+            redacted_thinking: None,
+            usage: None,
+            stop_reason: None,
+        })
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn test_agent_settings_collapse_repeated_siblings() {
+    let (client, _) = fake_cdp(Box::new(|method, _| match method {
+        "DOM.getDocument" => Ok(document_from_html(&cards_page(50))),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let llm = RecordingLLM::default();
+    Agent::new(
+        "Add the cheapest item".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        llm.clone(),
+    )
+    .with_settings(AgentSettings {
+        serialization_options: collapsed(),
+        ..Default::default()
+    })
+    .with_max_steps(1)
+    .run()
+    .await
+    .unwrap();
+
+    let prompt = llm.messages.lock().unwrap()[1].content.clone();
+    assert!(prompt.contains("… 48 more similar items"), "{prompt}");
+}
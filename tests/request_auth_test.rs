@@ -0,0 +1,86 @@
+//! Tests for rebuilding request credentials from the browser
+
+mod common;
+
+use browsing::actor::Page;
+use common::fake_cdp;
+use serde_json::{Value, json};
+
+#[tokio::test]
+async fn test_cookie_header_for_url() {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Network.getCookies" => Ok(json!({ "cookies": [
+            { "name": "session", "value": "s3cr3t", "domain": ".example.com", "path": "/", "secure": true },
+            { "name": "api_pref", "value": "v2", "domain": "api.example.com", "path": "/v2", "secure": false },
+        ] })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    let header = page
+        .get_request_cookie_header("https://api.example.com/v2/orders?page=2")
+        .await
+        .unwrap();
+    assert_eq!(header, "api_pref=v2; session=s3cr3t");
+
+    let received = received.lock().unwrap();
+    let (_, params, session) = &received[0];
+    assert_eq!(
+        params["urls"],
+        json!(["https://api.example.com/v2/orders?page=2"])
+    );
+    assert_eq!(session.as_deref(), Some("S1"));
+}
+
+#[tokio::test]
+async fn test_authorization_header_from_session_storage() {
+    let (client, received) = fake_cdp(Box::new(|method, call| match (method, call) {
+        // localStorage holds no token, sessionStorage does
+        ("DOMStorage.getDOMStorageItems", 1) => Ok(json!({ "entries": [["theme", "dark"]] })),
+        ("DOMStorage.getDOMStorageItems", _) => Ok(json!({ "entries": [
+            ["auth", "{\"access_token\":\"at-0123456789\",\"expires_in\":3600}"],
+        ] })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    let header = page
+        .get_authorization_header("https://app.example.com/api/me")
+        .await
+        .unwrap();
+    assert_eq!(header.as_deref(), Some("Bearer at-0123456789"));
+
+    let storage_ids: Vec<Value> = received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, _, _)| method == "DOMStorage.getDOMStorageItems")
+        .map(|(_, params, _)| params["storageId"].clone())
+        .collect();
+    assert_eq!(
+        storage_ids,
+        [
+            json!({ "securityOrigin": "https://app.example.com", "isLocalStorage": true }),
+            json!({ "securityOrigin": "https://app.example.com", "isLocalStorage": false }),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_authorization_header_without_storage() {
+    let (client, _) = fake_cdp(Box::new(|method, _| match method {
+        "DOMStorage.getDOMStorageItems" => Err("Frame not found for the given storage id".into()),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    let header = page
+        .get_authorization_header("https://elsewhere.example.org/")
+        .await
+        .unwrap();
+    assert!(header.is_none());
+    assert!(page.get_authorization_header("not a url").await.is_err());
+}
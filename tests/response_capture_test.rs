@@ -0,0 +1,394 @@
+//! Tests for capturing JSON API responses
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::actor::Page;
+use browsing::agent::service::Agent;
+use browsing::browser::cdp::CdpClient;
+use browsing::browser::views::TabInfo;
+use browsing::browser::{Browser, BrowserProfile, CapturedResponse, ResponseCapture};
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use browsing::tools::Tools;
+use browsing::tools::handlers::CAPTURED_RESPONSES_METADATA_KEY;
+use browsing::traits::BrowserClient;
+use common::{FakePageBrowser, HttpResponse, Received, fake_cdp, fake_cdp_with_events, serve_http};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const PRODUCTS_PAGE: &str = include_str!("fixtures/network/products.html");
+
+fn event(method: &str, request_id: &str, params: Value) -> Value {
+    let mut params = params;
+    params["requestId"] = json!(request_id);
+    json!({ "method": method, "sessionId": "S1", "params": params })
+}
+
+/// A response to `url` that finished loading
+fn response_events(request_id: &str, url: &str, mime_type: &str) -> Vec<Value> {
+    vec![
+        event(
+            "Network.responseReceived",
+            request_id,
+            json!({ "response": {
+                "url": url, "status": 200, "mimeType": mime_type,
+                "headers": {
+                    "content-type": mime_type,
+                    "Set-Cookie": "session=secret",
+                    "Authorization": "Bearer secret",
+                    "X-Total-Count": "2"
+                }
+            } }),
+        ),
+        event("Network.loadingFinished", request_id, json!({})),
+    ]
+}
+
+/// Capture `/api/products` over a fake endpoint that answers `Network.enable`
+/// with a products response, a stylesheet and a failed product request
+async fn capture() -> (Arc<Mutex<ResponseCapture>>, Received) {
+    let (client, received) = fake_cdp_with_events(
+        Box::new(|method, call| match method {
+            "Network.getResponseBody" if call == 1 => Ok(json!({
+                "body": r#"{"items":[{"name":"Lamp"},{"name":"Desk"}]}"#,
+                "base64Encoded": false
+            })),
+            "Network.getResponseBody" => Err("No resource with given identifier".to_string()),
+            _ => Ok(json!({})),
+        }),
+        Box::new(|method, _| {
+            if method != "Network.enable" {
+                return vec![];
+            }
+            let mut events = response_events(
+                "R1",
+                "https://shop.test/api/products?page=1",
+                "application/json",
+            );
+            events.extend(response_events(
+                "R2",
+                "https://shop.test/styles/extra.css",
+                "text/css",
+            ));
+            events.push(event(
+                "Network.responseReceived",
+                "R3",
+                json!({ "response": { "url": "https://shop.test/api/products?page=2", "status": 500 } }),
+            ));
+            events.push(event(
+                "Network.loadingFailed",
+                "R3",
+                json!({ "errorText": "net::ERR_ABORTED" }),
+            ));
+            events
+        }),
+    )
+    .await;
+    let capture = Arc::new(Mutex::new(
+        ResponseCapture::new("/api/products", 10).unwrap(),
+    ));
+    ResponseCapture::spawn(Arc::clone(&capture), client, "S1")
+        .await
+        .unwrap();
+    (capture, received)
+}
+
+/// Wait until `capture` holds `count` responses
+async fn captured(capture: &Mutex<ResponseCapture>, count: usize) -> Vec<CapturedResponse> {
+    for _ in 0..100 {
+        let responses = capture.lock().unwrap().responses();
+        if responses.len() >= count {
+            return responses;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("fewer than {count} responses were captured");
+}
+
+#[tokio::test]
+async fn test_matching_json_bodies_are_captured_without_credentials() {
+    let (capture, received) = capture().await;
+
+    let responses = captured(&capture, 1).await;
+    assert_eq!(responses.len(), 1);
+    let response = &responses[0];
+    assert_eq!(response.url, "https://shop.test/api/products?page=1");
+    assert_eq!(response.body["items"][1]["name"], "Desk");
+    assert_eq!(
+        response.headers.keys().collect::<Vec<_>>(),
+        ["X-Total-Count", "content-type"]
+    );
+
+    // Only the finished products response was read, in the page's session
+    let reads: Vec<_> = received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, _, _)| method == "Network.getResponseBody")
+        .map(|(_, params, session)| (params["requestId"].clone(), session.clone()))
+        .collect();
+    assert_eq!(reads, [(json!("R1"), Some("S1".to_string()))]);
+    assert_eq!(
+        capture.lock().unwrap().summary(),
+        "captured 1 JSON response matching /api/products"
+    );
+}
+
+/// [`FakePageBrowser`] with responses captured
+struct CapturingBrowser {
+    page: FakePageBrowser,
+    capture: Arc<Mutex<ResponseCapture>>,
+}
+
+impl CapturingBrowser {
+    async fn new(responses: &[Value]) -> Self {
+        let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+        let mut capture = ResponseCapture::new("/api/products", 10).unwrap();
+        for (i, body) in responses.iter().enumerate() {
+            let request_id = format!("R{i}");
+            let url = format!("https://shop.test/api/products?page={}", i + 1);
+            for event in response_events(&request_id, &url, "application/json") {
+                if let Some(request) = capture.apply_event(&event) {
+                    let result = json!({ "body": body.to_string(), "base64Encoded": false });
+                    assert!(capture.record_body(&request, &result));
+                }
+            }
+        }
+        Self {
+            page: FakePageBrowser { client },
+            capture: Arc::new(Mutex::new(capture)),
+        }
+    }
+}
+
+#[async_trait]
+impl BrowserClient for CapturingBrowser {
+    async fn start(&mut self) -> Result<()> {
+        self.page.start().await
+    }
+
+    async fn navigate(&mut self, url: &str) -> Result<()> {
+        self.page.navigate(url).await
+    }
+
+    async fn get_current_url(&self) -> Result<String> {
+        self.page.get_current_url().await
+    }
+
+    async fn create_tab(&mut self, url: Option<&str>) -> Result<String> {
+        self.page.create_tab(url).await
+    }
+
+    async fn switch_to_tab(&mut self, target_id: &str) -> Result<()> {
+        self.page.switch_to_tab(target_id).await
+    }
+
+    async fn close_tab(&mut self, target_id: &str) -> Result<()> {
+        self.page.close_tab(target_id).await
+    }
+
+    async fn get_tabs(&self) -> Result<Vec<TabInfo>> {
+        self.page.get_tabs().await
+    }
+
+    async fn get_target_id_from_tab_id(&self, tab_id: &str) -> Result<String> {
+        self.page.get_target_id_from_tab_id(tab_id).await
+    }
+
+    fn get_page(&self) -> Result<Page> {
+        self.page.get_page()
+    }
+
+    async fn take_screenshot(&self, path: Option<&str>, full_page: bool) -> Result<Vec<u8>> {
+        self.page.take_screenshot(path, full_page).await
+    }
+
+    async fn get_current_page_title(&self) -> Result<String> {
+        Ok("Products".to_string())
+    }
+
+    fn get_cdp_client(&self) -> Result<Arc<CdpClient>> {
+        self.page.get_cdp_client()
+    }
+
+    fn get_session_id(&self) -> Result<String> {
+        Ok("S1".to_string())
+    }
+
+    fn get_current_target_id(&self) -> Result<String> {
+        Ok("T1".to_string())
+    }
+
+    fn take_captured_responses(&self) -> Vec<CapturedResponse> {
+        self.capture.lock().unwrap().take_responses()
+    }
+
+    fn response_capture_summary(&self) -> Option<String> {
+        Some(self.capture.lock().unwrap().summary())
+    }
+}
+
+fn get_captured_responses() -> browsing::tools::views::ActionModel {
+    serde_json::from_value(json!({ "action_type": "get_captured_responses", "params": {} }))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_action_returns_captured_bodies_once() {
+    let mut browser = CapturingBrowser::new(&[
+        json!({ "items": [{ "name": "Lamp" }] }),
+        json!({ "items": [{ "name": "Desk" }] }),
+    ])
+    .await;
+    let tools = Tools::default();
+
+    let result = tools
+        .act(get_captured_responses(), &mut browser, None)
+        .await
+        .unwrap();
+
+    let content = result.extracted_content.unwrap();
+    assert!(
+        content.contains(r#"{"items":[{"name":"Desk"}]}"#),
+        "{content}"
+    );
+    assert!(!content.contains("secret"), "{content}");
+    let metadata = result.metadata.unwrap();
+    let responses: Vec<CapturedResponse> =
+        serde_json::from_value(metadata[CAPTURED_RESPONSES_METADATA_KEY].clone()).unwrap();
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0].url, "https://shop.test/api/products?page=1");
+
+    let again = tools
+        .act(get_captured_responses(), &mut browser, None)
+        .await
+        .unwrap();
+    assert!(
+        again
+            .extracted_content
+            .unwrap()
+            .starts_with("No responses captured")
+    );
+}
+
+#[tokio::test]
+async fn test_action_without_capture_is_an_error() {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let mut browser = FakePageBrowser { client };
+
+    let err = Tools::default()
+        .act(get_captured_responses(), &mut browser, None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("No responses are being captured"));
+}
+
+/// Model that finishes at once, recording the messages it was sent
+#[derive(Clone, Default)]
+struct RecordingLLM {
+    messages: Arc<Mutex<Vec<Vec<ChatMessage>>>>,
+}
+
+#[async_trait]
+impl ChatModel for RecordingLLM {
+    fn model(&self) -> &str {
+        "recording"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        self.messages.lock().unwrap().push(messages.to_vec());
+        let action = json!({ "action_type": "done", "params": { "text": "Done" } });
+        Ok(ChatInvokeCompletion::new(
+            json!({ "next_goal": "Finish", "action": [action] }).to_string(),
+        ))
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn test_summary_is_in_the_state_message() {
+    let browser = CapturingBrowser::new(&[json!([1]), json!([2]), json!([3])]).await;
+    let llm = RecordingLLM::default();
+
+    Agent::new(
+        "List the products".to_string(),
+        Box::new(browser),
+        Box::new(DOMProcessorImpl::new()),
+        llm.clone(),
+    )
+    .with_max_steps(1)
+    .run()
+    .await
+    .unwrap();
+
+    let state = llm.messages.lock().unwrap()[0][1].content.clone();
+    assert!(
+        state.contains(
+            "Captured API responses: captured 3 JSON responses matching /api/products \
+             (read them with get_captured_responses)"
+        ),
+        "{state}"
+    );
+}
+
+async fn serve_products_fixture() -> String {
+    serve_http(|request| {
+        let path = request.path();
+        let response = if path.starts_with("/api/products?page=1") {
+            HttpResponse::ok("application/json", r#"{"items":[{"name":"Lamp"}]}"#)
+        } else if path.starts_with("/api/products?page=2") {
+            HttpResponse::ok("application/json", r#"{"items":[{"name":"Desk"}]}"#)
+        } else if path.starts_with("/styles/") {
+            HttpResponse::ok("text/css", "li { color: teal; }")
+        } else {
+            HttpResponse::html(PRODUCTS_PAGE)
+        };
+        response.with_header("Set-Cookie", "session=secret")
+    })
+    .await
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_page_fetches_are_captured() {
+    let url = serve_products_fixture().await;
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.unwrap();
+    browser
+        .capture_responses("/api/products", 10)
+        .await
+        .unwrap();
+    browser.navigate(&url).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    assert_eq!(
+        browser.response_capture_summary().as_deref(),
+        Some("captured 2 JSON responses matching /api/products")
+    );
+    let mut names: Vec<Value> = browser
+        .take_captured_responses()
+        .into_iter()
+        .inspect(|response| {
+            let scrubbed = |name: &String| name.eq_ignore_ascii_case("set-cookie");
+            assert!(!response.headers.keys().any(scrubbed));
+        })
+        .map(|response| response.body["items"][0]["name"].clone())
+        .collect();
+    names.sort_by_key(Value::to_string);
+    assert_eq!(names, [json!("Desk"), json!("Lamp")]);
+    assert!(browser.captured_responses().is_empty());
+    browser.stop().await.unwrap();
+}
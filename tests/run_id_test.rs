@@ -0,0 +1,212 @@
+//! Tests for per-run identifiers
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::actor::EmulationSettings;
+use browsing::agent::RunIdHint;
+use browsing::agent::run_id::RUN_ID_HEADER;
+use browsing::agent::service::Agent;
+use browsing::agent::views::AgentSettings;
+use browsing::browser::{Browser, BrowserProfile};
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use common::{FakePageBrowser, Received, fake_cdp, fake_cdp_url_with_events};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/// Model that finishes the task right away
+#[derive(Clone)]
+struct DoneLLM;
+
+#[async_trait]
+impl ChatModel for DoneLLM {
+    fn model(&self) -> &str {
+        "done"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, _messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        Ok(ChatInvokeCompletion {
+            completion: json!({
+                "action": [{ "action_type": "done", "params": { "text": "Finished", "success": true } }]
+            })
+            .to_string(),
+            thinking: None,
+            redacted_thinking: None,
+            usage: None,
+            stop_reason: None,
+        })
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+/// Formatted log output collected by a test subscriber
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl CapturedLogs {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8_lossy(&self.0.lock().unwrap())
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+#[tokio::test]
+async fn test_run_id_in_history_and_logs() {
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(false)
+        .with_max_level(tracing::Level::INFO)
+        .with_writer(move || writer.clone())
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let mut agent = Agent::new(
+        "Say you are done".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        DoneLLM,
+    )
+    .with_max_steps(1);
+    let run_id = agent.run_id().to_string();
+    assert_eq!(uuid::Uuid::parse_str(&run_id).unwrap().get_version_num(), 7);
+
+    let history = agent.run().await.unwrap();
+
+    let saved = serde_json::to_value(&history).unwrap();
+    assert_eq!(saved["run_id"], json!(run_id));
+    let lines = logs.lines();
+    let done = lines
+        .iter()
+        .find(|line| line.contains("Finished"))
+        .expect("the done action is logged");
+    assert!(
+        done.contains(&format!("agent_run{{run_id={run_id}}}")),
+        "{done}"
+    );
+}
+
+#[tokio::test]
+async fn test_run_id_sent_as_header() {
+    let (client, received) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let settings = AgentSettings {
+        run_id_hint: RunIdHint::Header,
+        ..Default::default()
+    };
+    let mut agent = Agent::new(
+        "Say you are done".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        DoneLLM,
+    )
+    .with_settings(settings)
+    .with_max_steps(1);
+
+    agent.run().await.unwrap();
+
+    let received = received.lock().unwrap();
+    let (_, params, session) = received
+        .iter()
+        .find(|(method, _, _)| method == "Network.setExtraHTTPHeaders")
+        .expect("the run ID header is set");
+    assert_eq!(params["headers"][RUN_ID_HEADER], json!(agent.run_id()));
+    assert_eq!(session.as_deref(), Some("S1"));
+}
+
+/// Extra headers set on each session, in order
+fn headers_set(received: &Received) -> Vec<(String, Value)> {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, _, _)| method == "Network.setExtraHTTPHeaders")
+        .map(|(_, params, session)| (session.clone().unwrap(), params["headers"].clone()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_run_id_header_is_kept_with_emulation_and_in_new_tabs() {
+    let (url, received) = fake_cdp_url_with_events(
+        Box::new(|method, call| match method {
+            // Only the start page at first, the created tab once it exists
+            "Target.getTargets" if call == 1 => Ok(json!({
+                "targetInfos": [{ "targetId": "T1", "type": "page", "url": "about:blank" }]
+            })),
+            "Target.getTargets" => Ok(json!({
+                "targetInfos": [
+                    { "targetId": "T1", "type": "page", "url": "about:blank" },
+                    { "targetId": "T2", "type": "page", "url": "about:blank" }
+                ]
+            })),
+            "Target.attachToTarget" => Ok(json!({ "sessionId": format!("S{call}") })),
+            "Target.createTarget" => Ok(json!({ "targetId": "T2" })),
+            _ => Ok(json!({})),
+        }),
+        Box::new(|_, _| vec![]),
+    )
+    .await;
+    let mut browser = Browser::new(BrowserProfile::new()).with_cdp_url(url);
+    browser.start().await.unwrap();
+
+    browser
+        .set_extra_headers(HashMap::from([(
+            RUN_ID_HEADER.to_string(),
+            "run-1".to_string(),
+        )]))
+        .await
+        .unwrap();
+    browser
+        .set_emulation(EmulationSettings {
+            save_data: true,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let both = json!({ RUN_ID_HEADER: "run-1", "Save-Data": "on" });
+    assert_eq!(
+        headers_set(&received).last().unwrap(),
+        &("S1".to_string(), both)
+    );
+
+    // A new tab has no emulation of its own, but the run ID still goes out
+    let target_id = browser.create_new_tab(None).await.unwrap();
+    let run_id_only = json!({ RUN_ID_HEADER: "run-1" });
+    assert_eq!(
+        headers_set(&received).last().unwrap(),
+        &("S2".to_string(), run_id_only)
+    );
+
+    // Switching attaches a fresh session, which gets the headers again
+    browser.switch_to_tab(&target_id).await.unwrap();
+    let (session, headers) = headers_set(&received).last().unwrap().clone();
+    assert_eq!(session, "S3");
+    assert_eq!(headers, json!({ RUN_ID_HEADER: "run-1" }));
+}
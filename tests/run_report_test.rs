@@ -0,0 +1,242 @@
+//! Tests for per-step run reports
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::agent::report::REPORT_COLUMNS;
+use browsing::agent::service::Agent;
+use browsing::agent::views::{AgentHistoryList, AgentSettings};
+use browsing::agent::{ReportFormat, ReportRecord};
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatInvokeUsage, ChatMessage, ChatModel};
+use browsing::tokens::TokenPricing;
+use common::{FakePageBrowser, fake_cdp};
+use serde_json::{Value, json};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Model that answers with `outputs` in turn, each costing the given tokens
+#[derive(Clone)]
+struct ScriptedLLM {
+    outputs: Arc<Mutex<Vec<(Value, u32, u32)>>>,
+}
+
+#[async_trait]
+impl ChatModel for ScriptedLLM {
+    fn model(&self) -> &str {
+        "scripted"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, _messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        let (output, prompt_tokens, completion_tokens) = self.outputs.lock().unwrap().remove(0);
+        Ok(ChatInvokeCompletion {
+            completion: output.to_string(),
+            thinking: None,
+            redacted_thinking: None,
+            usage: Some(ChatInvokeUsage {
+                prompt_tokens,
+                prompt_cached_tokens: None,
+                prompt_cache_creation_tokens: None,
+                prompt_image_tokens: None,
+                completion_tokens,
+                total_tokens: prompt_tokens + completion_tokens,
+            }),
+            stop_reason: None,
+        })
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+fn step(actions: Value) -> Value {
+    json!({
+        "evaluation_previous_goal": "Page loaded",
+        "memory": "On the home page",
+        "next_goal": "Keep going",
+        "action": actions
+    })
+}
+
+fn artifacts_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("browsing-report-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// Run three steps: a wait, a wait plus an unknown action, then done
+async fn run(settings: AgentSettings) -> AgentHistoryList {
+    let llm = ScriptedLLM {
+        outputs: Arc::new(Mutex::new(vec![
+            (
+                step(json!([{ "action_type": "wait", "params": { "seconds": 0 } }])),
+                1000,
+                50,
+            ),
+            (
+                step(json!([
+                    { "action_type": "wait", "params": { "seconds": 0 } },
+                    { "action_type": "zoom,in", "params": {} }
+                ])),
+                2000,
+                100,
+            ),
+            (
+                json!({ "action": [{ "action_type": "done", "params": { "text": "Done" } }] }),
+                3000,
+                20,
+            ),
+        ])),
+    };
+    let (client, _) = fake_cdp(Box::new(|method, _| match method {
+        "DOM.getDocument" => Ok(json!({
+            "root": { "nodeId": 1, "backendNodeId": 1, "nodeType": 9, "nodeName": "#document" }
+        })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    Agent::new(
+        "Find the price".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        llm,
+    )
+    .with_settings(settings)
+    .with_max_steps(5)
+    .run()
+    .await
+    .unwrap()
+}
+
+/// Parse CSV with quoted cells, as written by the report
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => row.push(std::mem::take(&mut cell)),
+            ('\n', false) => {
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => cell.push(c),
+        }
+    }
+    rows
+}
+
+#[tokio::test]
+async fn test_csv_report_has_a_row_per_step_and_a_summary() {
+    let dir = artifacts_dir("csv");
+    let history = run(AgentSettings {
+        artifacts_dir: Some(dir.clone()),
+        report_format: Some(ReportFormat::Csv),
+        token_pricing: Some(TokenPricing {
+            prompt_per_million: 1.0,
+            completion_per_million: 10.0,
+        }),
+        ..Default::default()
+    })
+    .await;
+
+    // The history points at the report
+    let path = history.report_path.clone().unwrap();
+    assert_eq!(path.parent(), Some(dir.as_path()));
+    assert_eq!(path.extension().unwrap(), "csv");
+    let rows = parse_csv(&std::fs::read_to_string(&path).unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(rows.len(), 1 + 3 + 1);
+    assert_eq!(rows[0], REPORT_COLUMNS);
+    for row in &rows {
+        assert_eq!(row.len(), REPORT_COLUMNS.len(), "{row:?}");
+    }
+    let column = |row: &Vec<String>, name: &str| {
+        row[REPORT_COLUMNS.iter().position(|c| *c == name).unwrap()].clone()
+    };
+
+    let steps = &rows[1..4];
+    let record_types: Vec<String> = steps.iter().map(|r| column(r, "record_type")).collect();
+    assert_eq!(record_types, ["step", "step", "step"]);
+    let numbers: Vec<String> = steps.iter().map(|r| column(r, "step")).collect();
+    assert_eq!(numbers, ["1", "2", "3"]);
+    assert_eq!(column(&steps[1], "url"), "https://example.com");
+    assert_eq!(column(&steps[1], "actions"), "wait;zoom,in");
+    assert_eq!(column(&steps[1], "success"), "true;false");
+    assert!(
+        column(&steps[1], "errors").contains("zoom,in"),
+        "{:?}",
+        steps[1]
+    );
+    assert_eq!(column(&steps[1], "prompt_tokens"), "2000");
+    assert_eq!(column(&steps[1], "completion_tokens"), "100");
+    let start: f64 = column(&steps[0], "start_time").parse().unwrap();
+    let end: f64 = column(&steps[0], "end_time").parse().unwrap();
+    let duration: f64 = column(&steps[0], "duration_seconds").parse().unwrap();
+    assert!((end - start - duration).abs() < 0.01);
+
+    // 1000 + 50 * 10, then + 2000 + 100 * 10, then + 3000 + 20 * 10, per million
+    let costs: Vec<String> = steps.iter().map(|r| column(r, "cumulative_cost")).collect();
+    assert_eq!(costs, ["0.001500", "0.004500", "0.007700"]);
+
+    let summary = &rows[4];
+    assert_eq!(column(summary, "record_type"), "summary");
+    assert_eq!(column(summary, "step"), "3");
+    assert_eq!(column(summary, "prompt_tokens"), "6000");
+    assert_eq!(column(summary, "completion_tokens"), "170");
+    assert_eq!(column(summary, "cumulative_cost"), "0.007700");
+    assert_eq!(column(summary, "errors"), "1 of 4 actions failed");
+}
+
+#[tokio::test]
+async fn test_json_lines_report_round_trips() {
+    let history = run(AgentSettings::default()).await;
+    assert!(history.report_path.is_none());
+
+    let path = std::env::temp_dir().join(format!("browsing-report-{}.jsonl", std::process::id()));
+    history
+        .export_report(&path, ReportFormat::JsonLines)
+        .unwrap();
+    let text = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let records: Vec<ReportRecord> = text
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(records, history.report_records());
+    assert_eq!(records.len(), 4);
+    assert_eq!(records[1].actions, ["wait", "zoom,in"]);
+    assert_eq!(records[2].completion_tokens, Some(20));
+    // Without token prices there is no cost
+    assert!(records.iter().all(|r| r.cumulative_cost.is_none()));
+    assert_eq!(records[3].success, [true]);
+
+    // Every line has every column
+    for line in text.lines() {
+        let object: serde_json::Map<String, Value> = serde_json::from_str(line).unwrap();
+        let keys: Vec<&str> = object.keys().map(String::as_str).collect();
+        let mut expected = REPORT_COLUMNS.to_vec();
+        expected.sort();
+        let mut keys = keys;
+        keys.sort();
+        assert_eq!(keys, expected);
+    }
+}
@@ -0,0 +1,142 @@
+//! Tests for saving pages as MHTML snapshots
+
+mod common;
+
+use browsing::actor::Page;
+use browsing::browser::{Browser, BrowserProfile};
+use browsing::config::ToolsConfig;
+use browsing::tools::handlers::SAVED_PAGE_METADATA_KEY;
+use browsing::tools::{CapabilityProfile, Tools};
+use browsing::traits::BrowserClient;
+use common::{FakePageBrowser, HttpResponse, fake_cdp, methods, serve_http};
+use serde_json::json;
+
+/// What Chrome returns for the fixture page, which links one stylesheet
+const ARTICLE_MHTML: &str = include_str!("fixtures/snapshot/article.mhtml");
+
+async fn fixture_browser() -> (FakePageBrowser, common::Received) {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Page.captureSnapshot" => Ok(json!({ "data": ARTICLE_MHTML })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    (FakePageBrowser { client }, received)
+}
+
+/// Resources embedded in an MHTML document, other than the page itself
+fn embedded_resources(mhtml: &str) -> usize {
+    let boundary = mhtml
+        .split("boundary=\"")
+        .nth(1)
+        .and_then(|rest| rest.split('"').next())
+        .expect("a multipart boundary");
+    let parts = mhtml.matches(&format!("--{boundary}\r\n")).count();
+    parts.saturating_sub(1)
+}
+
+#[tokio::test]
+async fn test_capture_mhtml() {
+    let (browser, received) = fixture_browser().await;
+
+    let mhtml = browser.get_page().unwrap().capture_mhtml().await.unwrap();
+
+    let mhtml = String::from_utf8(mhtml).unwrap();
+    assert!(mhtml.contains("<title>Quarterly report</title>"));
+    assert_eq!(embedded_resources(&mhtml), 1);
+    let received = received.lock().unwrap();
+    assert_eq!(received[0].0, "Page.captureSnapshot");
+    assert_eq!(received[0].1, json!({ "format": "mhtml" }));
+    assert_eq!(received[0].2.as_deref(), Some("S1"));
+}
+
+#[tokio::test]
+async fn test_save_page_action_writes_to_artifacts_dir() {
+    let (mut browser, _) = fixture_browser().await;
+    let dir = tempfile::tempdir().unwrap();
+    let tools = Tools::default().with_artifacts_dir(dir.path());
+    let action = serde_json::from_value(json!({
+        "action_type": "save_page",
+        "params": { "filename": "report" }
+    }))
+    .unwrap();
+
+    let result = tools.act(action, &mut browser, None).await.unwrap();
+
+    let path = dir.path().join("pages").join("report.mhtml");
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(saved, ARTICLE_MHTML);
+    let metadata = &result.metadata.unwrap()[SAVED_PAGE_METADATA_KEY];
+    assert_eq!(metadata["path"], json!(path));
+    assert_eq!(metadata["size"], json!(ARTICLE_MHTML.len()));
+    assert!(
+        result
+            .extracted_content
+            .unwrap()
+            .contains(&format!("({} bytes)", ARTICLE_MHTML.len()))
+    );
+    // The size is a diagnostic, kept out of the model's memory
+    assert_eq!(
+        result.long_term_memory,
+        Some(format!("Saved page to {}", path.display()))
+    );
+    assert_eq!(
+        result.debug_info,
+        Some(format!("{} bytes", ARTICLE_MHTML.len()))
+    );
+}
+
+#[tokio::test]
+async fn test_save_page_refused_without_file_writes() {
+    let (mut browser, received) = fixture_browser().await;
+    let tools = Tools::from_config(&ToolsConfig::from_profile(CapabilityProfile::Safe));
+    let action =
+        serde_json::from_value(json!({ "action_type": "save_page", "params": {} })).unwrap();
+
+    let error = tools.act(action, &mut browser, None).await.unwrap_err();
+
+    assert!(error.to_string().contains("Saving files is disabled"));
+    assert!(methods(&received).is_empty());
+}
+
+#[tokio::test]
+async fn test_save_mhtml_reports_missing_data() {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let page = Page::new(client, "S1".to_string());
+    let dir = tempfile::tempdir().unwrap();
+
+    assert!(
+        page.save_mhtml(&dir.path().join("page.mhtml"))
+            .await
+            .is_err()
+    );
+}
+
+/// Serve the fixture page and its stylesheet
+async fn serve_fixture() -> String {
+    serve_http(|request| {
+        if request.path() == "/style.css" {
+            HttpResponse::ok("text/css", include_str!("fixtures/snapshot/style.css"))
+        } else {
+            HttpResponse::html(include_str!("fixtures/snapshot/article.html"))
+        }
+    })
+    .await
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_capture_fixture_page() {
+    let url = serve_fixture().await;
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.unwrap();
+    browser.navigate(&url).await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    let mhtml = browser.get_page().unwrap().capture_mhtml().await.unwrap();
+
+    let mhtml = String::from_utf8_lossy(&mhtml);
+    assert!(mhtml.contains("Quarterly report"));
+    assert!(embedded_resources(&mhtml) >= 1, "{mhtml}");
+    assert!(mhtml.contains("style.css"));
+    browser.stop().await.unwrap();
+}
@@ -0,0 +1,147 @@
+//! Tests for screenshots decoded straight to disk
+
+mod common;
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use browsing::actor::screenshot::SCREENSHOT_DECODE_CHUNK;
+use browsing::actor::{Page, ScreenshotOptions};
+use browsing::browser::{Browser, BrowserProfile};
+use common::fake_cdp;
+use serde_json::json;
+
+/// A PNG header of `width` x `height` followed by `len` bytes of filler
+fn fake_png(width: u32, height: u32, len: usize) -> Vec<u8> {
+    let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+    png.extend(width.to_be_bytes());
+    png.extend(height.to_be_bytes());
+    png.extend((0..len).map(|i| (i * 31 % 251) as u8));
+    png
+}
+
+#[tokio::test]
+async fn test_file_matches_decoded_screenshot() {
+    // Several decode chunks, with a length that leaves padding at the end
+    let png = fake_png(3840, 21600, SCREENSHOT_DECODE_CHUNK * 2 + 2);
+    let data = STANDARD.encode(&png);
+    assert!(data.len() > SCREENSHOT_DECODE_CHUNK * 2 && data.ends_with('='));
+    let (client, _) = fake_cdp(Box::new(move |_, _| Ok(json!({ "data": data })))).await;
+    let page = Page::new(client, "S1".to_string());
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("shots/full.png");
+
+    let saved = page
+        .screenshot_to_file(&path, &ScreenshotOptions::full_page())
+        .await
+        .unwrap();
+
+    let decoded = STANDARD
+        .decode(
+            page.screenshot_with_options(None, None, true, None)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), decoded);
+    assert_eq!(saved.path, path);
+    assert_eq!(saved.size, png.len() as u64);
+    assert_eq!((saved.width, saved.height), (Some(3840), Some(21600)));
+}
+
+#[tokio::test]
+async fn test_options_are_sent_with_the_capture() {
+    let (client, received) = fake_cdp(Box::new(|_, _| {
+        Ok(json!({ "data": STANDARD.encode(b"\xff\xd8\xff\xd9") }))
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("shot.jpeg");
+
+    let saved = page
+        .screenshot_to_file(
+            &path,
+            &ScreenshotOptions {
+                format: Some("jpeg".to_string()),
+                quality: Some(60),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(saved.size, 4);
+    assert_eq!(saved.width, None);
+    let (method, params, session) = received.lock().unwrap()[0].clone();
+    assert_eq!(method, "Page.captureScreenshot");
+    assert_eq!(params["format"], "jpeg");
+    assert_eq!(params["quality"], 60);
+    assert_eq!(params["captureBeyondViewport"], false);
+    assert_eq!(session.as_deref(), Some("S1"));
+}
+
+#[tokio::test]
+async fn test_invalid_data_leaves_no_file() {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({ "data": "not base64!" })))).await;
+    let page = Page::new(client, "S1".to_string());
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("broken.png");
+
+    let err = page
+        .screenshot_to_file(&path, &ScreenshotOptions::default())
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("decode"), "{err}");
+    assert!(!path.exists());
+}
+
+#[tokio::test]
+async fn test_missing_data_is_an_error() {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let page = Page::new(client, "S1".to_string());
+    let dir = tempfile::tempdir().unwrap();
+
+    let err = page
+        .screenshot_to_file(&dir.path().join("none.png"), &ScreenshotOptions::default())
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("No screenshot data"), "{err}");
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_4k_full_page_capture_to_file() {
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.unwrap();
+    let page = browser.get_page().unwrap();
+    page.set_viewport_size(3840, 2160).await.unwrap();
+    let tall_page = "document.body.style.height = '20000px'; \
+                     document.body.style.background = 'linear-gradient(red, blue)'";
+    page.evaluate(tall_page).await.unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("4k.png");
+
+    let started = std::time::Instant::now();
+    let saved = browser
+        .screenshot_to_file(&path, &ScreenshotOptions::full_page())
+        .await
+        .unwrap();
+    let streamed = started.elapsed();
+
+    let started = std::time::Instant::now();
+    let decoded = browser
+        .take_screenshot(None, true, None, None)
+        .await
+        .unwrap();
+    let buffered = started.elapsed();
+    println!(
+        "{} bytes: streamed in {streamed:?}, decoded in memory in {buffered:?}",
+        saved.size
+    );
+
+    assert_eq!(std::fs::read(&path).unwrap(), decoded);
+    assert_eq!(saved.width, Some(3840));
+    browser.stop().await.unwrap();
+}
@@ -0,0 +1,148 @@
+//! Tests for inner scroll containers in the page state and the scroll action
+
+mod common;
+
+use browsing::dom::serializer::INNER_SCROLL_NOTE;
+use browsing::dom::{DOMProcessorImpl, SerializedDOMState};
+use browsing::tools::Tools;
+use browsing::tools::views::ActionModel;
+use browsing::traits::DOMProcessor;
+use common::{FakePageBrowser, document_from_html, fake_cdp};
+use serde_json::{Value, json};
+
+const INNER_LIST: &str = include_str!("fixtures/scroll/inner_list.html");
+
+/// Backend node ID of the first element in `node` matching `predicate`
+fn find_node(node: &Value, predicate: &dyn Fn(&Value) -> bool) -> Option<u64> {
+    if predicate(node) {
+        return node["backendNodeId"].as_u64();
+    }
+    node["children"]
+        .as_array()?
+        .iter()
+        .find_map(|child| find_node(child, predicate))
+}
+
+/// `DOMSnapshot.captureSnapshot` result laying out the `html` element and the
+/// inbox list, whose 2400px of messages scroll inside its 400px
+fn snapshot(document: &Value, document_scrolls: bool) -> Value {
+    // `overflow: hidden` on a 720px page, or `visible` with content below it
+    let (html_overflow, html_scroll_height) = if document_scrolls {
+        (1, 3000)
+    } else {
+        (4, 720)
+    };
+    let root = &document["root"];
+    let html = find_node(root, &|n| n["localName"] == "html").unwrap();
+    let inbox = find_node(root, &|n| n["attributes"][1] == "inbox").unwrap();
+    json!({
+        "strings": ["block", "visible", "1", "auto", "hidden"],
+        "documents": [{
+            "nodes": { "backendNodeId": [html, inbox] },
+            "layout": {
+                "nodeIndex": [0, 1],
+                "bounds": [[0, 0, 1280, 720], [0, 120, 1280, 400]],
+                "styles": [
+                    [0, 1, 2, html_overflow, html_overflow, html_overflow],
+                    [0, 1, 2, 3, 4, 3]
+                ],
+                "clientRects": [[0, 0, 1280, 720], [0, 0, 1280, 400]],
+                "scrollRects": [[0, 0, 1280, html_scroll_height], [0, 0, 1280, 2400]]
+            }
+        }]
+    })
+}
+
+async fn serialize_fixture(document_scrolls: bool) -> SerializedDOMState {
+    let document = document_from_html(INNER_LIST);
+    let snapshot = snapshot(&document, document_scrolls);
+    let (client, _) = fake_cdp(Box::new(move |method, _| match method {
+        "DOM.getDocument" => Ok(document.clone()),
+        "DOMSnapshot.captureSnapshot" => Ok(snapshot.clone()),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    DOMProcessorImpl::new()
+        .with_cdp_client(client, "S1".to_string())
+        .with_target_id("T1".to_string())
+        .get_serialized_dom()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_inner_scroll_container_is_marked() {
+    let state = serialize_fixture(false).await;
+    let text = state.page_state();
+
+    assert!(text.starts_with(INNER_SCROLL_NOTE), "{text}");
+    let inbox = state.elements.iter().find(|e| e.is_scrollable).unwrap();
+    assert_eq!(inbox.tag, "div");
+    assert!(
+        text.contains(&format!("[scrollable {}]", inbox.index)),
+        "{text}"
+    );
+    assert!(state.selector_map.contains_key(&inbox.index));
+    // Only the list scrolls, not the button or the page
+    assert_eq!(state.elements.iter().filter(|e| e.is_scrollable).count(), 1);
+}
+
+#[tokio::test]
+async fn test_no_note_when_the_document_scrolls() {
+    let state = serialize_fixture(true).await;
+    let text = state.page_state();
+
+    assert!(!text.contains(INNER_SCROLL_NOTE), "{text}");
+    assert!(text.contains("[scrollable "), "{text}");
+}
+
+#[tokio::test]
+async fn test_scroll_container_by_index() {
+    let state = serialize_fixture(false).await;
+    let inbox = state.elements.iter().find(|e| e.is_scrollable).unwrap();
+
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "DOM.resolveNode" => Ok(json!({ "object": { "objectId": "inbox-1" } })),
+        "Runtime.callFunctionOn" => Ok(json!({ "result": { "value": {
+            "top": 2000, "height": 2400, "clientHeight": 400
+        } } })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let mut browser = FakePageBrowser { client };
+    let action: ActionModel = serde_json::from_value(json!({
+        "action_type": "scroll",
+        "params": { "down": true, "pages": 2, "container_index": inbox.index }
+    }))
+    .unwrap();
+
+    let result = Tools::default()
+        .act(action, &mut browser, Some(&state.selector_map))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        result.long_term_memory.as_deref(),
+        Some(
+            format!(
+                "Scrolled container {} down 2 pages; it is at the bottom of its content",
+                inbox.index
+            )
+            .as_str()
+        )
+    );
+    let received = received.lock().unwrap();
+    let call = received
+        .iter()
+        .find(|(method, _, _)| method == "Runtime.callFunctionOn")
+        .map(|(_, params, _)| params)
+        .unwrap();
+    assert_eq!(call["objectId"], "inbox-1");
+    assert_eq!(call["arguments"][0]["value"], 2.0);
+    // The window is not scrolled
+    assert!(
+        !received
+            .iter()
+            .any(|(method, _, _)| method == "Input.dispatchMouseEvent")
+    );
+}
@@ -0,0 +1,245 @@
+//! Tests for scrolling an element into view by index
+
+mod common;
+
+use browsing::agent::views::ActionResult;
+use browsing::browser::{Browser, BrowserProfile};
+use browsing::dom::views::DOMInteractedElement;
+use browsing::error::Result;
+use browsing::tools::{ActionModel, Tools};
+use common::{FakePageBrowser, Received, fake_cdp, methods, serve_html};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::time::Duration;
+
+const CLIPPED_CONTAINER: &str = include_str!("fixtures/scroll/clipped_container.html");
+
+fn element(index: u32, frame_id: Option<&str>) -> DOMInteractedElement {
+    DOMInteractedElement {
+        index,
+        backend_node_id: Some(100 + index),
+        tag: "button".to_string(),
+        text: Some("Load more".to_string()),
+        attributes: HashMap::new(),
+        selector: None,
+        bounds: None,
+        form_id: None,
+        frame_id: frame_id.map(str::to_string),
+    }
+}
+
+/// A 20px-high quad whose top edge is at `y`
+fn quad_at(y: f64) -> Value {
+    json!({ "quads": [[10.0, y, 90.0, y, 90.0, y + 20.0, 10.0, y + 20.0]] })
+}
+
+/// Run `action_type` on a 1280x720 page where the element's quads are
+/// `before` until scrolled into view and `after` from then on, and
+/// `elementFromPoint` at its center finds it if `hit`
+async fn scroll(
+    action_type: &str,
+    params: Value,
+    map: &HashMap<u32, DOMInteractedElement>,
+    before: Value,
+    after: Value,
+    hit: bool,
+) -> (Result<ActionResult>, Received) {
+    let (client, received) = fake_cdp(Box::new(move |method, call| match method {
+        "Page.getLayoutMetrics" => {
+            Ok(json!({ "layoutViewport": { "clientWidth": 1280, "clientHeight": 720 } }))
+        }
+        "DOM.getContentQuads" if call == 1 => Ok(before.clone()),
+        "DOM.getContentQuads" => Ok(after.clone()),
+        "DOM.resolveNode" => Ok(json!({ "object": { "objectId": "target-1" } })),
+        "Runtime.callFunctionOn" => Ok(json!({ "result": { "type": "boolean", "value": hit } })),
+        "Runtime.evaluate" => Ok(json!({ "result": { "value": [0, 0] } })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let mut browser = FakePageBrowser { client };
+    let action: ActionModel =
+        serde_json::from_value(json!({ "action_type": action_type, "params": params })).unwrap();
+    let result = Tools::default()
+        .act(action, &mut browser, Some(map))
+        .await;
+    (result, received)
+}
+
+fn scrolled_into_view(received: &Received) -> Vec<Value> {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, _, _)| method == "DOM.scrollIntoViewIfNeeded")
+        .map(|(_, params, _)| params.clone())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_offscreen_element_is_scrolled_into_view() {
+    let map = HashMap::from([(5, element(5, None))]);
+    let (result, received) = scroll(
+        "scroll_to_element",
+        json!({ "index": 5 }),
+        &map,
+        quad_at(2400.0),
+        quad_at(350.0),
+        true,
+    )
+    .await;
+
+    let result = result.unwrap();
+    assert_eq!(
+        result.long_term_memory.as_deref(),
+        Some("Scrolled element 5 into view")
+    );
+    assert_eq!(
+        scrolled_into_view(&received),
+        [json!({ "backendNodeId": 105 })]
+    );
+}
+
+#[tokio::test]
+async fn test_element_already_in_view_is_reported() {
+    let map = HashMap::from([(5, element(5, None))]);
+    let (result, received) = scroll(
+        "scroll_to_element",
+        json!({ "index": 5 }),
+        &map,
+        quad_at(300.0),
+        quad_at(300.0),
+        true,
+    )
+    .await;
+
+    assert_eq!(
+        result.unwrap().long_term_memory.as_deref(),
+        Some("Element 5 was already in view")
+    );
+    // Sent anyway; it leaves a visible element where it is
+    assert_eq!(scrolled_into_view(&received).len(), 1);
+}
+
+#[tokio::test]
+async fn test_element_clipped_by_a_container_is_scrolled() {
+    // Inside the viewport, but another element is shown at its center
+    let map = HashMap::from([(5, element(5, None))]);
+    let (result, received) = scroll(
+        "scroll_to_element",
+        json!({ "index": 5 }),
+        &map,
+        quad_at(300.0),
+        quad_at(120.0),
+        false,
+    )
+    .await;
+
+    assert_eq!(
+        result.unwrap().long_term_memory.as_deref(),
+        Some("Scrolled element 5 into view")
+    );
+    assert_eq!(
+        scrolled_into_view(&received),
+        [json!({ "backendNodeId": 105 })]
+    );
+}
+
+#[tokio::test]
+async fn test_scroll_with_index_scrolls_to_the_element() {
+    let map = HashMap::from([(7, element(7, Some("F2")))]);
+    let (result, received) = scroll(
+        "scroll",
+        json!({ "down": true, "index": 7 }),
+        &map,
+        quad_at(-500.0),
+        quad_at(100.0),
+        true,
+    )
+    .await;
+
+    let result = result.unwrap();
+    assert_eq!(
+        result.long_term_memory.as_deref(),
+        Some("Scrolled element 7 into view")
+    );
+    assert_eq!(
+        result.debug_info.as_deref(),
+        Some("backend_node_id: 107, frame_id: F2")
+    );
+    assert_eq!(scrolled_into_view(&received).len(), 1);
+    // The page itself was not scrolled by a wheel event
+    assert!(!methods(&received).contains(&"Input.dispatchMouseEvent".to_string()));
+}
+
+#[tokio::test]
+async fn test_unknown_index_fails() {
+    let map = HashMap::from([(5, element(5, None))]);
+    let (result, received) = scroll(
+        "scroll_to_element",
+        json!({ "index": 9 }),
+        &map,
+        quad_at(300.0),
+        quad_at(300.0),
+        true,
+    )
+    .await;
+
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains("Element index 9 not found"), "{error}");
+    assert!(scrolled_into_view(&received).is_empty());
+}
+
+#[tokio::test]
+async fn test_element_without_layout_fails() {
+    let map = HashMap::from([(5, element(5, None))]);
+    let (result, _) = scroll(
+        "scroll_to_element",
+        json!({ "index": 5 }),
+        &map,
+        json!({ "quads": [] }),
+        json!({ "quads": [] }),
+        true,
+    )
+    .await;
+
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains("no size or layout"), "{error}");
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_button_clipped_by_a_scroll_container() {
+    let url = serve_html(CLIPPED_CONTAINER).await;
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.unwrap();
+    browser.navigate(&url).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let page = browser.get_page().unwrap();
+    let button = page.get_elements_by_css_selector("#more").await.unwrap();
+    let mut more = element(1, None);
+    more.backend_node_id = Some(button[0].backend_node_id());
+    let map = HashMap::from([(1, more)]);
+    let action: ActionModel = serde_json::from_value(json!({
+        "action_type": "scroll_to_element",
+        "params": { "index": 1 }
+    }))
+    .unwrap();
+
+    let result = Tools::default()
+        .act(action, &mut browser, Some(&map))
+        .await
+        .unwrap();
+
+    // Its box lies inside the viewport, but the feed clips it
+    assert_eq!(
+        result.long_term_memory.as_deref(),
+        Some("Scrolled element 1 into view")
+    );
+    let scroll_top = page
+        .evaluate("String(document.getElementById('feed').scrollTop)")
+        .await
+        .unwrap();
+    assert_ne!(scroll_top, "0");
+
+    browser.stop().await.unwrap();
+}
@@ -119,6 +119,85 @@ async fn test_file_upload_validation() {
     assert!(result.is_err());
 }
 
+fn action(action_type: &str, params: serde_json::Value) -> browsing::tools::views::ActionModel {
+    serde_json::from_value(json!({ "action_type": action_type, "params": params })).unwrap()
+}
+
+#[tokio::test]
+async fn test_excluded_evaluate_cannot_run() {
+    let mut browser = Browser::new(BrowserProfile::default());
+    let tools = Tools::new(vec!["evaluate".to_string()]);
+
+    let err = tools
+        .act(
+            action("evaluate", json!({ "expression": "document.title" })),
+            &mut browser,
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, BrowsingError::Tool(ref msg) if msg == "Action disabled by configuration: evaluate"),
+        "unexpected error: {err}"
+    );
+
+    // Hidden from the model as well
+    let prompt = tools.registry.registry.get_prompt_description(None);
+    assert!(!prompt.contains("evaluate"));
+
+    // Without the exclusion the request reaches the handler (and fails only
+    // because no browser is running)
+    let err = Tools::new(vec![])
+        .act(
+            action("evaluate", json!({ "expression": "document.title" })),
+            &mut browser,
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert!(!err.to_string().contains("disabled by configuration"));
+}
+
+#[tokio::test]
+async fn test_excluded_custom_action_cannot_run() {
+    use browsing::agent::views::ActionResult;
+    use browsing::tools::views::{ActionContext, ActionHandler, ActionParams};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    struct RecordingHandler(Arc<AtomicBool>);
+
+    #[async_trait::async_trait]
+    impl ActionHandler for RecordingHandler {
+        async fn execute(
+            &self,
+            _params: &ActionParams,
+            _context: &mut ActionContext<'_>,
+        ) -> browsing::error::Result<ActionResult> {
+            self.0.store(true, Ordering::SeqCst);
+            Ok(ActionResult::default())
+        }
+    }
+
+    let ran = Arc::new(AtomicBool::new(false));
+    let mut tools = Tools::new(vec!["export_data".to_string()]);
+    tools.register_custom_action(
+        "export_data".to_string(),
+        "Export user data".to_string(),
+        None,
+        RecordingHandler(Arc::clone(&ran)),
+    );
+
+    let mut browser = Browser::new(BrowserProfile::default());
+    let err = tools
+        .act(action("export_data", json!({})), &mut browser, None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Action disabled by configuration: export_data"));
+    assert!(!ran.load(Ordering::SeqCst));
+    assert!(!tools.registry.registry.get_prompt_description(None).contains("export_data"));
+}
+
 // Helper functions for validation
 fn validate_upload_path(path: &str) -> Result<(), BrowsingError> {
     // Check for directory traversal attempts
@@ -0,0 +1,169 @@
+//! Tests for configurable DOM snapshot styles and color capture
+
+mod common;
+
+use browsing::agent::views::AgentSettings;
+use browsing::dom::enhanced_snapshot::{
+    REQUIRED_COMPUTED_STYLES, build_snapshot_lookup_with_options,
+};
+use browsing::dom::views::EnhancedDOMTreeNode;
+use browsing::dom::{DomService, SnapshotOptions, build_snapshot_lookup};
+use common::{document_from_html, fake_cdp};
+use serde_json::{Value, json};
+
+const PAGE: &str = r#"<html><body><button id="buy">Buy</button></body></html>"#;
+
+/// Snapshot laying out `backend_node_id` with `values`, listed in the order of `styles`
+/// as Chrome does, plus a blended background color and text color opacity
+fn snapshot(styles: &[&str], values: &[(&str, &str)], backend_node_id: u64) -> Value {
+    let mut strings: Vec<String> = vec!["rgb(255, 255, 255)".to_string()];
+    let mut indices = vec![];
+    for style in styles {
+        let (_, value) = values.iter().find(|(name, _)| name == style).unwrap();
+        strings.push(value.to_string());
+        indices.push(strings.len() - 1);
+    }
+    json!({
+        "strings": strings,
+        "documents": [{
+            "nodes": { "backendNodeId": [backend_node_id] },
+            "layout": {
+                "nodeIndex": [0],
+                "bounds": [[10, 10, 80, 24]],
+                "styles": [indices],
+                "blendedBackgroundColors": [0],
+                "textColorOpacities": [0.5]
+            }
+        }]
+    })
+}
+
+const VALUES: &[(&str, &str)] = &[
+    ("cursor", "pointer"),
+    ("background-color", "rgb(0, 0, 255)"),
+    ("display", "inline-block"),
+];
+
+#[test]
+fn test_custom_style_list_round_trips_through_the_parser() {
+    let options =
+        SnapshotOptions::default().with_computed_styles(["cursor", "background-color", "display"]);
+    let params = options.capture_params();
+    assert_eq!(
+        params["computedStyles"],
+        json!(["cursor", "background-color", "display"])
+    );
+
+    let snapshot = snapshot(&["cursor", "background-color", "display"], VALUES, 7);
+    let lookup = build_snapshot_lookup_with_options(&snapshot, 1.0, &options).unwrap();
+    let node = &lookup[&7];
+    let styles = node.computed_styles.as_ref().unwrap();
+    assert_eq!(styles.len(), 3);
+    assert_eq!(styles["cursor"], "pointer");
+    assert_eq!(styles["background-color"], "rgb(0, 0, 255)");
+    assert_eq!(styles["display"], "inline-block");
+    assert_eq!(node.cursor_style.as_deref(), Some("pointer"));
+    // Colors are read whenever the snapshot has them
+    assert_eq!(
+        node.blended_background_color.as_deref(),
+        Some("rgb(255, 255, 255)")
+    );
+    assert_eq!(node.text_color_opacity, Some(0.5));
+}
+
+#[test]
+fn test_default_options_match_the_required_styles() {
+    let options = SnapshotOptions::default();
+    assert_eq!(options.computed_styles, REQUIRED_COMPUTED_STYLES);
+    let params = options.capture_params();
+    assert_eq!(params["includeBlendedBackgroundColors"], false);
+    assert_eq!(params["includeTextColorOpacities"], false);
+
+    let snapshot = snapshot(
+        &["display", "visibility"],
+        &[("display", "none"), ("visibility", "hidden")],
+        7,
+    );
+    let node = &build_snapshot_lookup(&snapshot, 1.0).unwrap()[&7];
+    let styles = node.computed_styles.as_ref().unwrap();
+    assert_eq!(styles["display"], "none");
+    assert_eq!(styles["visibility"], "hidden");
+    // Styles the snapshot has no values for are left out
+    assert!(!styles.contains_key("opacity"));
+}
+
+/// The first node in `node`'s subtree with `id`
+fn find_by_id<'a>(node: &'a EnhancedDOMTreeNode, id: &str) -> Option<&'a EnhancedDOMTreeNode> {
+    if node.attributes.get("id").map(String::as_str) == Some(id) {
+        return Some(node);
+    }
+    node.children_nodes
+        .iter()
+        .flatten()
+        .find_map(|child| find_by_id(child, id))
+}
+
+#[tokio::test]
+async fn test_dom_service_captures_requested_styles_and_colors() {
+    let document = document_from_html(PAGE);
+    fn backend_id(node: &Value) -> Option<u64> {
+        if node["attributes"][1] == "buy" {
+            return node["backendNodeId"].as_u64();
+        }
+        node["children"].as_array()?.iter().find_map(backend_id)
+    }
+    let button = backend_id(&document["root"]).unwrap();
+    let snapshot = snapshot(&["display", "cursor"], VALUES, button);
+    let (client, received) = fake_cdp(Box::new(move |method, _| match method {
+        "DOM.getDocument" => Ok(document.clone()),
+        "DOMSnapshot.captureSnapshot" => Ok(snapshot.clone()),
+        _ => Ok(json!({})),
+    }))
+    .await;
+
+    let tree = DomService::new()
+        .with_cdp_client(client, "S1".to_string())
+        .with_computed_styles(["display", "cursor"])
+        .with_color_capture(true)
+        .get_dom_tree(Some("T1"))
+        .await
+        .unwrap();
+
+    let params = received
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(method, _, _)| method == "DOMSnapshot.captureSnapshot")
+        .map(|(_, params, _)| params.clone())
+        .unwrap();
+    assert_eq!(params["computedStyles"], json!(["display", "cursor"]));
+    assert_eq!(params["includeBlendedBackgroundColors"], true);
+    assert_eq!(params["includeTextColorOpacities"], true);
+
+    let snapshot_node = find_by_id(&tree, "buy")
+        .and_then(|node| node.snapshot_node.as_ref())
+        .unwrap();
+    let styles = snapshot_node.computed_styles.as_ref().unwrap();
+    assert_eq!(styles["display"], "inline-block");
+    assert_eq!(styles["cursor"], "pointer");
+    assert_eq!(
+        snapshot_node.blended_background_color.as_deref(),
+        Some("rgb(255, 255, 255)")
+    );
+}
+
+#[test]
+fn test_unset_snapshot_options_take_defaults() {
+    let options: SnapshotOptions = serde_json::from_value(json!({
+        "include_blended_background_colors": true
+    }))
+    .unwrap();
+
+    assert!(options.include_blended_background_colors);
+    assert!(!options.include_text_color_opacities);
+    assert_eq!(options.computed_styles, REQUIRED_COMPUTED_STYLES);
+    assert_eq!(
+        AgentSettings::default().snapshot_options,
+        SnapshotOptions::default()
+    );
+}
@@ -0,0 +1,130 @@
+//! Tests for headless detection mitigations
+
+mod common;
+
+use browsing::browser::stealth::apply_stealth;
+use browsing::browser::{Browser, BrowserProfile, StealthOptions};
+use common::{fake_cdp, fake_cdp_with_latency, methods};
+use serde_json::json;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_apply_stealth_adds_init_script() {
+    let (client, received) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let options = StealthOptions::default();
+
+    apply_stealth(&client, "S1", &options).await.unwrap();
+
+    let received = received.lock().unwrap();
+    assert_eq!(received[0].0, "Page.addScriptToEvaluateOnNewDocument");
+    assert_eq!(
+        received[0].1["source"].as_str(),
+        options.init_script().as_deref()
+    );
+    assert_eq!(received[0].2.as_deref(), Some("S1"));
+    assert_eq!(received[1].0, "Runtime.evaluate");
+}
+
+#[tokio::test]
+async fn test_apply_stealth_without_page_mitigations() {
+    let (client, received) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let options = StealthOptions {
+        hide_webdriver: false,
+        languages: false,
+        plugins: false,
+        webgl_vendor: false,
+        disable_automation_controlled: true,
+    };
+
+    apply_stealth(&client, "S1", &options).await.unwrap();
+
+    assert!(methods(&received).is_empty());
+}
+
+#[test]
+fn test_stealth_is_off_by_default() {
+    let profile: BrowserProfile = serde_json::from_value(json!({})).unwrap();
+    assert!(!profile.stealth);
+    assert_eq!(profile.stealth_options, StealthOptions::default());
+
+    let profile: BrowserProfile =
+        serde_json::from_value(json!({ "stealth": true, "stealth_options": { "plugins": false } }))
+            .unwrap();
+    assert!(profile.stealth);
+    assert!(!profile.stealth_options.plugins);
+    assert!(profile.stealth_options.hide_webdriver);
+
+    let profile = BrowserProfile::new().with_stealth_options(StealthOptions {
+        webgl_vendor: false,
+        ..Default::default()
+    });
+    assert!(profile.stealth);
+    assert!(!profile.stealth_options.webgl_vendor);
+}
+
+#[tokio::test]
+async fn test_switching_tabs_reapplies_stealth() {
+    let (url, received) = fake_cdp_with_latency(
+        Box::new(|method, call| match method {
+            "Target.getTargets" => Ok(json!({
+                "targetInfos": [
+                    { "targetId": "T1", "type": "page", "url": "about:blank" },
+                    { "targetId": "T2", "type": "page", "url": "about:blank" }
+                ]
+            })),
+            "Target.attachToTarget" => Ok(json!({ "sessionId": format!("S{call}") })),
+            _ => Ok(json!({})),
+        }),
+        Duration::ZERO,
+    )
+    .await;
+    let mut browser = Browser::new(BrowserProfile::new().with_stealth(true)).with_cdp_url(url);
+    browser.start().await.unwrap();
+
+    browser.switch_to_tab("T2").await.unwrap();
+
+    let sessions: Vec<_> = received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, _, _)| method == "Page.addScriptToEvaluateOnNewDocument")
+        .map(|(_, _, session)| session.clone().unwrap())
+        .collect();
+    // The switched-to tab is attached with a new session, which needs the script again
+    assert_eq!(sessions.len(), 2, "{sessions:?}");
+    assert_ne!(sessions[0], sessions[1]);
+}
+
+/// `navigator.webdriver`, as the fixture page sees it, in a browser with `profile`
+async fn webdriver_on_fixture(profile: BrowserProfile) -> String {
+    let mut browser = Browser::new(profile.with_headless(true));
+    browser.start().await.unwrap();
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/forms/two_forms.html");
+    browser
+        .navigate(&format!("file://{}", fixture.display()))
+        .await
+        .unwrap();
+    let webdriver = browser
+        .get_page()
+        .unwrap()
+        .evaluate("String(navigator.webdriver)")
+        .await
+        .unwrap();
+    browser.stop().await.unwrap();
+    webdriver
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_webdriver_is_hidden_with_stealth() {
+    let webdriver = webdriver_on_fixture(BrowserProfile::new().with_stealth(true)).await;
+    assert_eq!(webdriver, "undefined");
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_webdriver_is_unchanged_without_stealth() {
+    let webdriver = webdriver_on_fixture(BrowserProfile::new()).await;
+    assert_eq!(webdriver, "true");
+}
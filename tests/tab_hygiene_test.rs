@@ -0,0 +1,249 @@
+//! Tests for automatic closing of least recently used tabs
+
+use browsing::actor::Page;
+use browsing::agent::tab_hygiene::TabTracker;
+use browsing::agent::views::{ActionResult, AgentSettings};
+use browsing::browser::cdp::CdpClient;
+use browsing::browser::views::TabInfo;
+use browsing::error::{BrowsingError, Result};
+use browsing::traits::BrowserClient;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Mock browser that keeps an ordered list of open tabs
+struct MockTabClient {
+    tabs: Vec<String>,
+    current: String,
+    closed: Vec<String>,
+}
+
+impl MockTabClient {
+    fn new() -> Self {
+        Self {
+            tabs: vec!["tab-0000".to_string()],
+            current: "tab-0000".to_string(),
+            closed: vec![],
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl BrowserClient for MockTabClient {
+    async fn start(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn navigate(&mut self, _url: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_current_url(&self) -> Result<String> {
+        Ok(format!("https://example.com/{}", self.current))
+    }
+
+    async fn create_tab(&mut self, _url: Option<&str>) -> Result<String> {
+        let target_id = format!("tab-{:04}", self.tabs.len() + self.closed.len());
+        self.tabs.push(target_id.clone());
+        self.current = target_id.clone();
+        Ok(target_id)
+    }
+
+    async fn switch_to_tab(&mut self, target_id: &str) -> Result<()> {
+        self.current = target_id.to_string();
+        Ok(())
+    }
+
+    async fn close_tab(&mut self, target_id: &str) -> Result<()> {
+        self.tabs.retain(|t| t != target_id);
+        self.closed.push(target_id.to_string());
+        Ok(())
+    }
+
+    async fn get_tabs(&self) -> Result<Vec<TabInfo>> {
+        Ok(self
+            .tabs
+            .iter()
+            .map(|t| TabInfo {
+                url: format!("https://example.com/{t}"),
+                title: t.clone(),
+                target_id: t.clone(),
+                parent_target_id: None,
+                ..Default::default()
+            })
+            .collect())
+    }
+
+    async fn get_target_id_from_tab_id(&self, tab_id: &str) -> Result<String> {
+        Ok(tab_id.to_string())
+    }
+
+    fn get_page(&self) -> Result<Page> {
+        Err(BrowsingError::Browser("Mock has no page".to_string()))
+    }
+
+    async fn take_screenshot(&self, _path: Option<&str>, _full_page: bool) -> Result<Vec<u8>> {
+        Ok(vec![])
+    }
+
+    async fn get_current_page_title(&self) -> Result<String> {
+        Ok(self.current.clone())
+    }
+
+    fn get_cdp_client(&self) -> Result<Arc<CdpClient>> {
+        Err(BrowsingError::Browser("Mock has no CDP client".to_string()))
+    }
+
+    fn get_session_id(&self) -> Result<String> {
+        Ok("mock-session".to_string())
+    }
+
+    fn get_current_target_id(&self) -> Result<String> {
+        Ok(self.current.clone())
+    }
+}
+
+fn settings(max_open_tabs: Option<u32>, close_after: Option<u32>) -> AgentSettings {
+    AgentSettings {
+        max_open_tabs,
+        close_unused_tabs_after_steps: close_after,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_closes_least_recently_used_tabs_first() {
+    let mut browser = MockTabClient::new();
+    let mut tracker = TabTracker::new();
+    let settings = settings(Some(3), None);
+
+    // Open one new tab per step, five in total
+    for step in 1..=5 {
+        browser.create_tab(None).await.unwrap();
+        tracker
+            .enforce(&mut browser, step, &settings)
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(browser.closed, vec!["tab-0000", "tab-0001", "tab-0002"]);
+    assert_eq!(browser.tabs, vec!["tab-0003", "tab-0004", "tab-0005"]);
+}
+
+#[tokio::test]
+async fn test_revisited_tab_is_kept() {
+    let mut browser = MockTabClient::new();
+    let mut tracker = TabTracker::new();
+    let settings = settings(Some(3), None);
+
+    for step in 1..=2 {
+        browser.create_tab(None).await.unwrap();
+        tracker
+            .enforce(&mut browser, step, &settings)
+            .await
+            .unwrap();
+    }
+
+    // Go back to the first tab, then open two more
+    browser.switch_to_tab("tab-0000").await.unwrap();
+    tracker.enforce(&mut browser, 3, &settings).await.unwrap();
+    for step in 4..=5 {
+        browser.create_tab(None).await.unwrap();
+        tracker
+            .enforce(&mut browser, step, &settings)
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(browser.closed, vec!["tab-0001", "tab-0002"]);
+    assert!(browser.tabs.contains(&"tab-0000".to_string()));
+}
+
+#[tokio::test]
+async fn test_pinned_tab_is_never_closed() {
+    let mut browser = MockTabClient::new();
+    let mut tracker = TabTracker::new();
+    let settings = settings(Some(2), None);
+
+    let pinned = ActionResult {
+        metadata: Some(HashMap::from([(
+            "pinned_tab".to_string(),
+            serde_json::json!("tab-0000"),
+        )])),
+        ..Default::default()
+    };
+    tracker.pin_from_results(&[pinned]);
+
+    for step in 1..=4 {
+        browser.create_tab(None).await.unwrap();
+        tracker
+            .enforce(&mut browser, step, &settings)
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(browser.closed, vec!["tab-0001", "tab-0002", "tab-0003"]);
+    assert_eq!(browser.tabs, vec!["tab-0000", "tab-0004"]);
+}
+
+#[tokio::test]
+async fn test_closes_tabs_unused_for_too_long() {
+    let mut browser = MockTabClient::new();
+    let mut tracker = TabTracker::new();
+    let settings = settings(None, Some(2));
+
+    browser.create_tab(None).await.unwrap();
+    assert!(
+        tracker
+            .enforce(&mut browser, 1, &settings)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert!(
+        tracker
+            .enforce(&mut browser, 2, &settings)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let closed = tracker.enforce(&mut browser, 3, &settings).await.unwrap();
+    assert_eq!(browser.closed, vec!["tab-0000"]);
+    assert_eq!(
+        closed[0].long_term_memory.as_deref(),
+        Some("Closed unused tab #0000 (https://example.com/tab-0000)")
+    );
+}
+
+#[tokio::test]
+async fn test_disabled_by_default() {
+    let mut browser = MockTabClient::new();
+    let mut tracker = TabTracker::new();
+    let settings = AgentSettings::default();
+
+    for step in 1..=5 {
+        browser.create_tab(None).await.unwrap();
+        tracker
+            .enforce(&mut browser, step, &settings)
+            .await
+            .unwrap();
+    }
+
+    assert!(browser.closed.is_empty());
+    assert_eq!(browser.tabs.len(), 6);
+}
+
+#[tokio::test]
+async fn test_tab_touched_at_a_later_step_is_not_idle() {
+    let mut browser = MockTabClient::new();
+    let mut tracker = TabTracker::new();
+    let settings = settings(None, Some(2));
+    browser.create_tab(None).await.unwrap();
+
+    // Step numbers can go back, e.g. when a new run starts counting again
+    tracker.touch("tab-0000", 10);
+    let closed = tracker.enforce(&mut browser, 3, &settings).await.unwrap();
+
+    assert!(closed.is_empty());
+    assert!(browser.closed.is_empty());
+}
@@ -0,0 +1,217 @@
+//! Tests for the step and run timeouts
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::agent::service::Agent;
+use browsing::agent::views::{AgentHistoryList, AgentSettings};
+use browsing::agent::StopReason;
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use common::{FakePageBrowser, fake_cdp};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Model that replies to its n-th call after `replies[n].0` milliseconds
+/// with the actions `replies[n].1`, repeating the last reply
+#[derive(Clone)]
+struct SlowLLM {
+    replies: Vec<(u64, Value)>,
+    calls: Arc<AtomicUsize>,
+}
+
+impl SlowLLM {
+    fn new(replies: Vec<(u64, Value)>) -> Self {
+        Self {
+            replies,
+            calls: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+#[async_trait]
+impl ChatModel for SlowLLM {
+    fn model(&self) -> &str {
+        "slow"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, _messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst);
+        let (delay, actions) = &self.replies[call.min(self.replies.len() - 1)];
+        tokio::time::sleep(Duration::from_millis(*delay)).await;
+        Ok(ChatInvokeCompletion::new(
+            json!({ "next_goal": "Wait for the page", "action": actions }).to_string(),
+        ))
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+fn done() -> Value {
+    json!([{ "action_type": "done", "params": { "text": "Finished" } }])
+}
+
+fn wait(seconds: u64) -> Value {
+    json!({ "action_type": "wait", "params": { "seconds": seconds } })
+}
+
+async fn agent(llm: SlowLLM, settings: AgentSettings) -> Agent<SlowLLM> {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    Agent::new(
+        "Wait for the report to load".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        llm,
+    )
+    .with_settings(settings)
+    .with_max_steps(4)
+}
+
+fn errors(history: &AgentHistoryList, step: usize) -> Vec<String> {
+    history.history[step]
+        .result
+        .iter()
+        .filter_map(|result| result.error.clone())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_slow_model_fails_the_step() {
+    let llm = SlowLLM::new(vec![(3_000, done()), (0, done())]);
+    let settings = AgentSettings {
+        step_timeout: 1,
+        ..Default::default()
+    };
+    let mut agent = agent(llm.clone(), settings).await;
+
+    let started = Instant::now();
+    let history = agent.run().await.unwrap();
+
+    assert!(started.elapsed() < Duration::from_millis(2_500));
+    assert_eq!(
+        errors(&history, 0),
+        ["Step timed out after 1s while waiting for the model"]
+    );
+    assert!(history.history[0].model_output.is_none());
+    // The next step goes on as usual
+    assert_eq!(history.history.len(), 2);
+    assert!(history.is_done());
+    assert_eq!(history.stop_reason, Some(StopReason::Done));
+}
+
+#[tokio::test]
+async fn test_finished_actions_are_kept_and_wait_counts() {
+    let llm = SlowLLM::new(vec![(0, json!([wait(0), wait(5)])), (0, done())]);
+    let settings = AgentSettings {
+        step_timeout: 1,
+        ..Default::default()
+    };
+    let mut agent = agent(llm, settings).await;
+
+    let history = agent.run().await.unwrap();
+
+    let first = &history.history[0];
+    assert_eq!(first.model_output.as_ref().unwrap().action.len(), 2);
+    assert_eq!(first.result.len(), 2);
+    assert_eq!(
+        first.result[0].long_term_memory.as_deref(),
+        Some("Waited for 0 seconds")
+    );
+    assert_eq!(
+        first.result[1].error.as_deref(),
+        Some("Step timed out after 1s while running wait")
+    );
+    // The model's call is still accounted for
+    assert_eq!(first.metadata.as_ref().unwrap().llm_calls.len(), 1);
+    assert!(history.is_done());
+}
+
+#[tokio::test]
+async fn test_repeated_timeouts_count_as_failures() {
+    let llm = SlowLLM::new(vec![(0, json!([wait(5)]))]);
+    let settings = AgentSettings {
+        step_timeout: 1,
+        max_failures: 2,
+        ..Default::default()
+    };
+    let mut agent = agent(llm.clone(), settings).await;
+
+    let error = agent.run().await.unwrap_err().to_string();
+
+    assert!(
+        error.contains("Step timed out after 1s while running wait"),
+        "{error}"
+    );
+    assert!(error.contains("2 consecutive failures"), "{error}");
+    assert_eq!(llm.calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_run_timeout_keeps_the_history() {
+    let llm = SlowLLM::new(vec![(0, json!([wait(5)]))]);
+    let settings = AgentSettings {
+        step_timeout: 0,
+        run_timeout: Some(1),
+        ..Default::default()
+    };
+    let mut agent = agent(llm.clone(), settings).await;
+
+    let started = Instant::now();
+    let history = agent.run().await.unwrap();
+
+    assert!(started.elapsed() < Duration::from_millis(2_500));
+    assert_eq!(history.stop_reason, Some(StopReason::TimedOut));
+    assert_eq!(history.history.len(), 1);
+    assert_eq!(
+        errors(&history, 0),
+        ["Run timed out after 1s while running wait"]
+    );
+    assert!(history.usage.is_some());
+    assert_eq!(llm.calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_waiting_for_the_human_stops_the_clock() {
+    let ask = json!([{ "action_type": "ask_human", "params": { "question": "Which code?" } }]);
+    let llm = SlowLLM::new(vec![(0, ask), (0, done())]);
+    let settings = AgentSettings {
+        step_timeout: 1,
+        run_timeout: Some(1),
+        ..Default::default()
+    };
+    let mut agent = agent(llm, settings).await;
+    let handle = agent.handle();
+    let answering = tokio::spawn(async move {
+        handle.next_question().await;
+        tokio::time::sleep(Duration::from_millis(1_500)).await;
+        assert!(handle.provide_human_input("481516"));
+    });
+
+    let history = agent.run().await.unwrap();
+    answering.await.unwrap();
+
+    assert!(errors(&history, 0).is_empty(), "{:?}", errors(&history, 0));
+    assert_eq!(history.stop_reason, Some(StopReason::Done));
+}
+
+#[test]
+fn test_stop_reason_defaults_for_old_histories() {
+    let history: AgentHistoryList =
+        serde_json::from_value(json!({ "history": [], "usage": null })).unwrap();
+    assert_eq!(history.stop_reason, None);
+
+    let json = serde_json::to_value(StopReason::TimedOut).unwrap();
+    assert_eq!(json, "timed_out");
+}
@@ -374,8 +374,8 @@ fn test_action_result_with_content() {
 fn test_action_result_memory_format() {
     let memories = vec![
         "Navigated to https://example.com",
-        "Clicked element 1 (backend_node_id: 123)",
-        "Input text into element 5 (backend_node_id: 456)",
+        "Clicked element 1",
+        "Input text into element 5",
         "Scrolled down 1 pages",
         "Searched duckduckgo for 'test query'",
     ];
@@ -467,6 +467,9 @@ fn test_selector_map_structure() {
         text: Some("Click me".to_string()),
         attributes: HashMap::new(),
         selector: None,
+        bounds: None,
+        form_id: None,
+        frame_id: None,
     };
 
     assert_eq!(entry.index, 1);
@@ -488,6 +491,9 @@ fn test_selector_map_validation() {
             text: Some("text".to_string()),
             attributes: HashMap::new(),
             selector: None,
+            bounds: None,
+            form_id: None,
+            frame_id: None,
         },
         DOMInteractedElement {
             index: 1,
@@ -496,6 +502,9 @@ fn test_selector_map_validation() {
             text: Some("Submit".to_string()),
             attributes: HashMap::new(),
             selector: None,
+            bounds: None,
+            form_id: None,
+            frame_id: None,
         },
     ];
 
@@ -31,6 +31,7 @@ fn test_registered_action_structure() {
         description: "Test action".to_string(),
         domains: None,
         handler: None,
+        condition: None,
     };
 
     assert_eq!(action.name, "test_action");
@@ -68,3 +69,28 @@ fn test_action_model_set_index() {
     action.set_index(10);
     assert_eq!(action.action_type, "click");
 }
+
+#[tokio::test]
+async fn test_act_refuses_unavailable_action() {
+    use browsing::browser::{Browser, BrowserProfile};
+
+    let mut tools = Tools::new(vec![]);
+    tools.registry.register_action_with_condition(
+        "drag_window".to_string(),
+        "Drag the browser window".to_string(),
+        None,
+        |profile| profile.headless == Some(false),
+    );
+    // Refused before the browser is used, so it does not need to be started
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    let action = ActionModel {
+        action_type: "drag_window".to_string(),
+        params: HashMap::new(),
+    };
+
+    let err = tools.act(action, &mut browser, None).await.unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Action not available in current configuration")
+    );
+}
@@ -96,6 +96,7 @@ fn test_browser_client_tab_info_structure() {
         title: "Example".to_string(),
         target_id: "tab123".to_string(),
         parent_target_id: None,
+        ..Default::default()
     };
 
     assert_eq!(tab_info.url, "https://example.com");
@@ -140,6 +141,7 @@ fn test_dom_processor_serialized_state_structure() {
         markdown: None,
         elements: vec![],
         selector_map: HashMap::new(),
+        truncated_values: vec![],
     };
 
     assert_eq!(state.html, Some("<html></html>".to_string()));
@@ -160,6 +162,9 @@ fn test_dom_processor_selector_map_structure() {
             text: Some("Click".to_string()),
             attributes: HashMap::new(),
             selector: None,
+            bounds: None,
+            form_id: None,
+            frame_id: None,
         },
     );
 
@@ -228,6 +233,7 @@ impl BrowserClient for MockBrowserClient {
             title: "Mock Page".to_string(),
             target_id: "mock-tab-123".to_string(),
             parent_target_id: None,
+            ..Default::default()
         }])
     }
 
@@ -366,6 +372,7 @@ impl DOMProcessor for MockDOMProcessor {
             markdown: None,
             elements: vec![],
             selector_map: HashMap::new(),
+            truncated_values: vec![],
         })
     }
 
@@ -384,6 +391,9 @@ impl DOMProcessor for MockDOMProcessor {
                 text: Some("Click".to_string()),
                 attributes: HashMap::new(),
                 selector: None,
+                bounds: None,
+                form_id: None,
+                frame_id: None,
             },
         );
         Ok(map)
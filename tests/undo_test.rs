@@ -0,0 +1,242 @@
+//! Tests for reverting the last reversible action
+
+mod common;
+
+use browsing::dom::views::DOMInteractedElement;
+use browsing::tools::{ActionModel, Tools};
+use common::{FakePageBrowser, Received, fake_cdp};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+fn element(index: u32, tag: &str, attributes: &[(&str, &str)]) -> DOMInteractedElement {
+    DOMInteractedElement {
+        index,
+        backend_node_id: Some(100 + index),
+        tag: tag.to_string(),
+        text: None,
+        attributes: attributes
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        selector: None,
+        bounds: None,
+        form_id: None,
+        frame_id: None,
+    }
+}
+
+fn selector_map() -> HashMap<u32, DOMInteractedElement> {
+    HashMap::from([
+        (1, element(1, "button", &[("type", "submit")])),
+        (3, element(3, "input", &[("type", "email")])),
+        (6, element(6, "select", &[("name", "country")])),
+    ])
+}
+
+fn action(action_type: &str, params: Value) -> ActionModel {
+    serde_json::from_value(json!({ "action_type": action_type, "params": params })).unwrap()
+}
+
+/// Page whose form control reads as `before` on the first read and `after`
+/// on every later one; the call between them, which changes the control,
+/// selects Canada if it is a dropdown
+fn responder(before: Value, after: Value) -> common::Responder {
+    Box::new(move |method, call| match method {
+        "DOM.resolveNode" => Ok(json!({ "object": { "objectId": "obj-1" } })),
+        "Runtime.callFunctionOn" if call == 1 => Ok(json!({ "result": { "value": before } })),
+        "Runtime.callFunctionOn" if call == 2 => Ok(json!({ "result": { "value": {
+            "option": { "value": "ca", "text": "Canada", "selected": true }
+        } } })),
+        "Runtime.callFunctionOn" => Ok(json!({ "result": { "value": after } })),
+        "DOM.getContentQuads" => Ok(json!({ "quads": [[10, 10, 90, 10, 90, 30, 10, 30]] })),
+        "Page.getLayoutMetrics" => {
+            Ok(json!({ "layoutViewport": { "clientWidth": 1280, "clientHeight": 720 } }))
+        }
+        _ => Ok(json!({})),
+    })
+}
+
+/// Argument of the last `Runtime.callFunctionOn`
+fn last_call_argument(received: &Received) -> Value {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .rev()
+        .find(|(method, _, _)| method == "Runtime.callFunctionOn")
+        .map(|(_, params, _)| params["arguments"][0]["value"].clone())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_revert_input_restores_the_previous_value() {
+    let (client, received) = fake_cdp(responder(
+        json!({ "kind": "text", "value": "ada@example.com" }),
+        json!({ "kind": "text", "value": "typo@example.com" }),
+    ))
+    .await;
+    let mut browser = FakePageBrowser { client };
+    let tools = Tools::default();
+    let map = selector_map();
+
+    tools
+        .act(
+            action("input", json!({ "index": 3, "text": "typo@example.com" })),
+            &mut browser,
+            Some(&map),
+        )
+        .await
+        .unwrap();
+    let reverted = tools
+        .act(action("revert_last", json!({})), &mut browser, Some(&map))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        reverted.long_term_memory.as_deref(),
+        Some(
+            "Reverted input on element 3: restored \"ada@example.com\" (was \"typo@example.com\")"
+        )
+    );
+    assert_eq!(
+        last_call_argument(&received),
+        json!({ "kind": "text", "value": "ada@example.com" })
+    );
+
+    // Nothing is left to revert
+    let error = tools
+        .act(action("revert_last", json!({})), &mut browser, Some(&map))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("Nothing to revert"), "{error}");
+}
+
+#[tokio::test]
+async fn test_revert_input_does_not_report_passwords() {
+    let previous = json!({ "kind": "text", "value": "hunter2", "password": true });
+    let (client, received) = fake_cdp(responder(
+        previous.clone(),
+        json!({ "kind": "text", "value": "typo", "password": true }),
+    ))
+    .await;
+    let mut browser = FakePageBrowser { client };
+    let tools = Tools::default();
+    let map = selector_map();
+
+    tools
+        .act(
+            action("input", json!({ "index": 3, "text": "typo" })),
+            &mut browser,
+            Some(&map),
+        )
+        .await
+        .unwrap();
+    let reverted = tools
+        .act(action("revert_last", json!({})), &mut browser, Some(&map))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        reverted.long_term_memory.as_deref(),
+        Some("Reverted input on element 3: restored [password] (was [password])")
+    );
+    assert_eq!(last_call_argument(&received), previous);
+}
+
+#[tokio::test]
+async fn test_revert_select_restores_the_previous_option() {
+    let united_states = json!({ "kind": "select", "value": "us", "label": "United States" });
+    let (client, received) = fake_cdp(responder(
+        united_states.clone(),
+        json!({ "kind": "select", "value": "ca", "label": "Canada" }),
+    ))
+    .await;
+    let mut browser = FakePageBrowser { client };
+    let tools = Tools::default();
+    let map = selector_map();
+
+    tools
+        .act(
+            action("select_dropdown", json!({ "index": 6, "text": "Canada" })),
+            &mut browser,
+            Some(&map),
+        )
+        .await
+        .unwrap();
+    let reverted = tools
+        .act(action("revert_last", json!({})), &mut browser, Some(&map))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        reverted.long_term_memory.as_deref(),
+        Some("Reverted select_dropdown on element 6: restored \"United States\" (was \"Canada\")")
+    );
+    assert_eq!(last_call_argument(&received), united_states);
+}
+
+#[tokio::test]
+async fn test_clicks_and_navigation_are_not_reversible() {
+    let (client, received) = fake_cdp(responder(
+        json!({ "kind": "text", "value": "" }),
+        json!({ "kind": "text", "value": "ada@example.com" }),
+    ))
+    .await;
+    let mut browser = FakePageBrowser { client };
+    let tools = Tools::default();
+    let map = selector_map();
+
+    tools
+        .act(
+            action("input", json!({ "index": 3, "text": "ada@example.com" })),
+            &mut browser,
+            Some(&map),
+        )
+        .await
+        .unwrap();
+    let clicked = tools
+        .act(
+            action("click", json!({ "index": 1 })),
+            &mut browser,
+            Some(&map),
+        )
+        .await
+        .unwrap();
+    assert!(clicked.error.is_none(), "{:?}", clicked.error);
+
+    received.lock().unwrap().clear();
+    let error = tools
+        .act(action("revert_last", json!({})), &mut browser, Some(&map))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("The last action, click on element 1, cannot be reverted"),
+        "{error}"
+    );
+    // The input before the click is not reverted either
+    assert!(
+        error.contains("nothing before it can be reverted"),
+        "{error}"
+    );
+    assert!(received.lock().unwrap().is_empty());
+
+    tools
+        .act(
+            action("navigate", json!({ "url": "https://example.com/next" })),
+            &mut browser,
+            Some(&map),
+        )
+        .await
+        .unwrap();
+    let error = tools
+        .act(action("revert_last", json!({})), &mut browser, Some(&map))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("navigation to https://example.com/next, cannot be reverted"),
+        "{error}"
+    );
+}
@@ -0,0 +1,122 @@
+//! Tests for version and environment information
+
+use browsing::agent::views::AgentHistoryList;
+use browsing::browser::views::BrowserVersionInfo;
+use browsing::error::{self, BrowsingError};
+use browsing::{BuildInfo, EnvironmentInfo, build_info};
+use serde_json::json;
+
+fn chrome() -> BrowserVersionInfo {
+    BrowserVersionInfo::from_cdp(&json!({
+        "protocolVersion": "1.3",
+        "product": "HeadlessChrome/120.0.6099.71",
+        "revision": "@9729082fe6174c0a371fc66501f5efc5d69d3d2b",
+        "userAgent": "Mozilla/5.0 (X11; Linux x86_64) HeadlessChrome/120.0.6099.71",
+        "jsVersion": "12.0.267.8"
+    }))
+}
+
+#[test]
+fn test_build_info() {
+    let info = build_info();
+    assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+    assert!(!info.git_hash.is_empty());
+    assert_eq!(info.os, std::env::consts::OS);
+
+    let value = serde_json::to_value(&info).unwrap();
+    for key in ["version", "git_hash", "features", "os", "arch"] {
+        assert!(value.get(key).is_some(), "missing {key}");
+    }
+    let restored: BuildInfo = serde_json::from_value(value).unwrap();
+    assert_eq!(restored, info);
+}
+
+#[test]
+fn test_browser_version_info_from_cdp() {
+    let info = chrome();
+    assert_eq!(info.product, "HeadlessChrome/120.0.6099.71");
+    assert_eq!(info.protocol_version, "1.3");
+    assert_eq!(info.js_version, "12.0.267.8");
+    assert!(!info.launched);
+
+    // Launcher details are omitted for connected browsers
+    let value = serde_json::to_value(&info).unwrap();
+    assert!(value.get("executable_path").is_none());
+    assert!(value.get("pid").is_none());
+
+    let launched = BrowserVersionInfo {
+        launched: true,
+        executable_path: Some("/usr/bin/google-chrome".to_string()),
+        pid: Some(4242),
+        headless: Some(true),
+        ..chrome()
+    };
+    let value = serde_json::to_value(&launched).unwrap();
+    assert_eq!(value["executable_path"], "/usr/bin/google-chrome");
+    assert_eq!(value["pid"], 4242);
+    let restored: BrowserVersionInfo = serde_json::from_value(value).unwrap();
+    assert_eq!(restored, launched);
+}
+
+#[test]
+fn test_fingerprint() {
+    let build = BuildInfo {
+        version: "0.1.2".to_string(),
+        git_hash: "3081cb9".to_string(),
+        features: vec![],
+        os: "linux".to_string(),
+        arch: "x86_64".to_string(),
+    };
+    assert_eq!(
+        build.fingerprint(None),
+        "browsing 0.1.2 (3081cb9) linux/x86_64"
+    );
+
+    let environment = EnvironmentInfo {
+        build,
+        browser: Some(chrome()),
+    };
+    assert_eq!(
+        environment.fingerprint(),
+        "browsing 0.1.2 (3081cb9) linux/x86_64; HeadlessChrome/120.0.6099.71 CDP 1.3"
+    );
+}
+
+#[test]
+fn test_history_environment_round_trip() {
+    let history = AgentHistoryList {
+        run_id: None,
+        seed: None,
+        output_version: 0,
+        history: vec![],
+        usage: None,
+        environment: Some(EnvironmentInfo::new(Some(chrome()))),
+        assertions: Default::default(),
+        report_path: None,
+        stop_reason: None,
+    };
+    let json = serde_json::to_string(&history).unwrap();
+    let restored: AgentHistoryList = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.environment, history.environment);
+
+    // Histories saved before environment info was recorded still load
+    let legacy: AgentHistoryList =
+        serde_json::from_value(json!({"history": [], "usage": null})).unwrap();
+    assert!(legacy.environment.is_none());
+}
+
+#[test]
+fn test_debug_errors_fingerprint() {
+    let err = || BrowsingError::Browser("Failed to launch browser".to_string());
+    assert_eq!(err().to_string(), "Browser error: Failed to launch browser");
+
+    error::set_debug_errors(true);
+    assert!(error::debug_errors_enabled());
+    let message = err().to_string();
+    assert!(message.starts_with("Browser error: Failed to launch browser ["));
+    assert!(message.contains(&build_info().fingerprint(None)));
+    assert!(!message.contains('\n'));
+
+    error::set_debug_errors(false);
+    assert_eq!(err().to_string(), "Browser error: Failed to launch browser");
+}
@@ -0,0 +1,119 @@
+//! Tests for downscaled viewport screenshots
+
+mod common;
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use browsing::actor::{Page, PreviewOptions};
+use common::{fake_cdp, methods};
+use serde_json::{Value, json};
+
+fn evaluated(value: Value) -> Value {
+    json!({ "result": { "type": "object", "value": value } })
+}
+
+/// A PNG header of `width` x `height`
+fn fake_png(width: u32, height: u32) -> Vec<u8> {
+    let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+    png.extend(width.to_be_bytes());
+    png.extend(height.to_be_bytes());
+    png
+}
+
+/// A loaded, painted page with a 1600x900 viewport scrolled down 1200px
+async fn loaded_page() -> (Page, common::Received) {
+    let (client, received) = fake_cdp(Box::new(|method, call| match method {
+        "Runtime.evaluate" if call == 1 => Ok(evaluated(json!("complete"))),
+        "Runtime.evaluate" => Ok(evaluated(json!({
+            "paint": r#"[{"name":"first-contentful-paint","startTime":180.25}]"#,
+            "lcp": null
+        }))),
+        "Page.getLayoutMetrics" => Ok(json!({
+            "cssLayoutViewport": { "pageX": 0, "pageY": 1200, "clientWidth": 1600, "clientHeight": 900 },
+            "cssVisualViewport": {
+                "offsetX": 0, "offsetY": 0, "pageX": 0, "pageY": 1200,
+                "clientWidth": 1600, "clientHeight": 900, "scale": 1
+            },
+            "cssContentSize": { "x": 0, "y": 0, "width": 1600, "height": 5000 }
+        })),
+        "Page.captureScreenshot" => Ok(json!({ "data": STANDARD.encode(fake_png(800, 450)) })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    (Page::new(client, "S1".to_string()), received)
+}
+
+fn capture_params(received: &common::Received) -> Value {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(method, _, _)| method == "Page.captureScreenshot")
+        .map(|(_, params, _)| params.clone())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_preview_is_the_viewport_scaled_to_fit() {
+    let (page, received) = loaded_page().await;
+
+    let preview = page
+        .viewport_preview(&PreviewOptions::default())
+        .await
+        .unwrap();
+
+    assert_eq!(preview.mime_type, "image/png");
+    assert_eq!((preview.width, preview.height), (Some(800), Some(450)));
+    assert_eq!(STANDARD.decode(&preview.data).unwrap(), fake_png(800, 450));
+    // 1600x900 fits in 800x600 at half size
+    let params = capture_params(&received);
+    assert_eq!(params["format"], "png");
+    assert_eq!(params["captureBeyondViewport"], false);
+    assert_eq!(
+        params["clip"],
+        json!({ "x": 0.0, "y": 1200.0, "width": 1600.0, "height": 900.0, "scale": 0.5 })
+    );
+
+    // The page was waited for before the capture
+    let methods = methods(&received);
+    let capture = methods
+        .iter()
+        .position(|m| m == "Page.captureScreenshot")
+        .unwrap();
+    assert!(
+        methods[..capture]
+            .iter()
+            .filter(|m| *m == "Runtime.evaluate")
+            .count()
+            >= 2,
+        "{methods:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_preview_size_and_format() {
+    let (page, received) = loaded_page().await;
+
+    let preview = page
+        .viewport_preview(
+            &PreviewOptions::default()
+                .with_max_size(1000, 300)
+                .with_format("jpeg"),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(preview.mime_type, "image/jpeg");
+    let params = capture_params(&received);
+    assert_eq!(params["format"], "jpeg");
+    // The height limits the scale: 300 / 900
+    let scale = params["clip"]["scale"].as_f64().unwrap();
+    assert!((scale - 1.0 / 3.0).abs() < 1e-9, "{scale}");
+
+    // Small viewports are not scaled up
+    let (page, received) = loaded_page().await;
+    page.viewport_preview(&PreviewOptions::default().with_max_size(4000, 4000))
+        .await
+        .unwrap();
+    assert_eq!(capture_params(&received)["clip"]["scale"], 1.0);
+}
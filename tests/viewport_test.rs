@@ -0,0 +1,103 @@
+//! Tests for resizing the viewport before a screenshot
+
+mod common;
+
+use base64::Engine;
+use browsing::actor::Page;
+use browsing::actor::page::VIEWPORT_RESIZE_TIMEOUT_MS;
+use browsing::browser::{Browser, BrowserProfile};
+use common::{fake_cdp, fake_cdp_with_events, methods};
+use serde_json::json;
+use std::time::{Duration, Instant};
+
+/// Width and height from a PNG's IHDR chunk
+fn png_dimensions(png: &[u8]) -> (u32, u32) {
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n", "not a PNG");
+    assert_eq!(&png[12..16], b"IHDR");
+    let width = u32::from_be_bytes(png[16..20].try_into().unwrap());
+    let height = u32::from_be_bytes(png[20..24].try_into().unwrap());
+    (width, height)
+}
+
+/// Start of a PNG of the given size, enough for its header to be read
+fn png_header(width: u32, height: u32) -> String {
+    let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+    png.extend(width.to_be_bytes());
+    png.extend(height.to_be_bytes());
+    png.extend([8, 6, 0, 0, 0]);
+    base64::engine::general_purpose::STANDARD.encode(png)
+}
+
+#[tokio::test]
+async fn test_capture_waits_for_resize_event() {
+    let (client, received) = fake_cdp_with_events(
+        Box::new(|method, _| match method {
+            "Page.captureScreenshot" => Ok(json!({ "data": png_header(412, 915) })),
+            _ => Ok(json!({})),
+        }),
+        Box::new(|method, _| match method {
+            "Emulation.setDeviceMetricsOverride" => vec![
+                json!({ "method": "Page.frameResized", "params": {}, "sessionId": "OTHER" }),
+                json!({ "method": "Page.frameResized", "params": {}, "sessionId": "S1" }),
+            ],
+            _ => vec![],
+        }),
+    )
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    let started = Instant::now();
+    let png = page
+        .set_viewport_and_capture(412, 915, "png", false)
+        .await
+        .unwrap();
+
+    assert!(started.elapsed() < Duration::from_millis(VIEWPORT_RESIZE_TIMEOUT_MS));
+    assert_eq!(png_dimensions(&png), (412, 915));
+    assert_eq!(
+        methods(&received),
+        [
+            "Page.enable",
+            "Emulation.setDeviceMetricsOverride",
+            "Page.captureScreenshot"
+        ]
+    );
+    let received = received.lock().unwrap();
+    assert_eq!(received[1].1["width"], 412);
+    assert_eq!(received[1].1["height"], 915);
+    assert!(received.iter().all(|(_, _, s)| s.as_deref() == Some("S1")));
+}
+
+#[tokio::test]
+async fn test_set_viewport_size_times_out_without_resize_event() {
+    let (client, _) = fake_cdp(Box::new(|_, _| Ok(json!({})))).await;
+    let page = Page::new(client, "S1".to_string());
+
+    let started = Instant::now();
+    page.set_viewport_size(800, 600).await.unwrap();
+
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(VIEWPORT_RESIZE_TIMEOUT_MS));
+    assert!(elapsed < Duration::from_secs(2));
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_screenshot_matches_requested_viewport() {
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.unwrap();
+    browser
+        .navigate("data:text/html,<h1 style='width:3000px'>wide</h1>")
+        .await
+        .unwrap();
+
+    for (width, height) in [(375, 667), (1280, 720)] {
+        let png = browser
+            .set_viewport_and_capture(width, height, "png", false)
+            .await
+            .unwrap();
+        assert_eq!(png_dimensions(&png), (width, height));
+    }
+
+    browser.stop().await.unwrap();
+}
@@ -0,0 +1,215 @@
+//! Tests for waiting for elements to become visible, hidden or attached
+
+mod common;
+
+use browsing::actor::{ElementState, Page, WaitForElementOptions};
+use browsing::browser::cdp::CdpClient;
+use browsing::browser::{Browser, BrowserProfile};
+use common::{fake_cdp, fake_cdp_with_latency, methods};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+fn counts(attached: u64, visible: u64) -> Value {
+    json!({ "result": { "type": "object", "value": { "attached": attached, "visible": visible } } })
+}
+
+/// Page whose checks report `sequence` in order, repeating the last
+async fn scripted_page(sequence: &'static [(u64, u64)]) -> (Page, common::Received) {
+    let (client, received) = fake_cdp(Box::new(move |method, call| match method {
+        "Runtime.evaluate" => {
+            let (attached, visible) = sequence[(call - 1).min(sequence.len() - 1)];
+            Ok(counts(attached, visible))
+        }
+        _ => Ok(json!({})),
+    }))
+    .await;
+    (Page::new(client, "S1".to_string()), received)
+}
+
+#[tokio::test]
+async fn test_waits_until_elements_appear() {
+    let (page, received) = scripted_page(&[(0, 0), (0, 0), (2, 2)]).await;
+
+    let waited = page
+        .wait_for_element(&WaitForElementOptions::selector(".result").with_text("tea"))
+        .await
+        .unwrap();
+
+    assert_eq!(waited.count, 2);
+    assert!(waited.waited_ms >= 200, "{}", waited.waited_ms);
+    assert_eq!(methods(&received), ["Runtime.evaluate"; 3]);
+    let expression = received.lock().unwrap()[0].1["expression"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert!(
+        expression.ends_with(r#"({"selector":".result","text":"tea"})"#),
+        "{expression}"
+    );
+}
+
+#[tokio::test]
+async fn test_states() {
+    // Rendered but hidden: attached, not visible
+    let (page, _) = scripted_page(&[(1, 0)]).await;
+    let attached = page
+        .wait_for_element(
+            &WaitForElementOptions::selector(".spinner").with_state(ElementState::Attached),
+        )
+        .await
+        .unwrap();
+    assert_eq!(attached.count, 1);
+    let hidden = page
+        .wait_for_element(
+            &WaitForElementOptions::selector(".spinner").with_state(ElementState::Hidden),
+        )
+        .await
+        .unwrap();
+    assert_eq!(hidden.count, 1);
+
+    // The spinner goes away
+    let (page, _) = scripted_page(&[(1, 1), (0, 0)]).await;
+    let gone = page
+        .wait_for_element(&WaitForElementOptions::text("Loading").with_state(ElementState::Hidden))
+        .await
+        .unwrap();
+    assert_eq!(gone.count, 0);
+    assert!(gone.waited_ms >= 100);
+
+    assert_eq!(
+        ElementState::from_name("attached"),
+        Some(ElementState::Attached)
+    );
+    assert_eq!(ElementState::from_name("shown"), None);
+}
+
+#[tokio::test]
+async fn test_timeout_and_missing_query() {
+    let (page, received) = scripted_page(&[(0, 0)]).await;
+
+    let error = page
+        .wait_for_element(&WaitForElementOptions::selector("#results li").with_timeout(250))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("Elements matching '#results li' did not become visible within 250ms"),
+        "{error}"
+    );
+
+    let checks = methods(&received).len();
+    let nothing = WaitForElementOptions {
+        text: None,
+        ..WaitForElementOptions::text("")
+    };
+    assert!(page.wait_for_element(&nothing).await.is_err());
+    assert_eq!(methods(&received).len(), checks);
+}
+
+#[tokio::test]
+async fn test_checks_failing_during_a_navigation_are_retried() {
+    let (client, received) = fake_cdp(Box::new(|method, call| match method {
+        "Runtime.evaluate" if call == 1 => Err("Execution context was destroyed.".to_string()),
+        "Runtime.evaluate" if call == 2 => Err("Cannot find context with specified id".to_string()),
+        "Runtime.evaluate" => Ok(counts(1, 1)),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    let waited = page
+        .wait_for_element(&WaitForElementOptions::selector("#checkout"))
+        .await
+        .unwrap();
+
+    assert_eq!(waited.count, 1);
+    assert_eq!(methods(&received), ["Runtime.evaluate"; 3]);
+}
+
+#[tokio::test]
+async fn test_a_stalled_check_ends_at_the_timeout() {
+    let (url, _) =
+        fake_cdp_with_latency(Box::new(|_, _| Ok(counts(1, 1))), Duration::from_secs(10)).await;
+    let mut client = CdpClient::new(url);
+    client.start().await.unwrap();
+    let page = Page::new(Arc::new(client), "S1".to_string());
+
+    let started = Instant::now();
+    let error = page
+        .wait_for_element(&WaitForElementOptions::selector("#checkout").with_timeout(300))
+        .await
+        .unwrap_err()
+        .to_string();
+
+    assert!(
+        error.contains("did not become visible within 300ms"),
+        "{error}"
+    );
+    assert!(
+        started.elapsed() < Duration::from_secs(5),
+        "{:?}",
+        started.elapsed()
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_selector_fails_at_once() {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Runtime.evaluate" => Ok(json!({
+            "result": { "type": "object", "subtype": "error" },
+            "exceptionDetails": {
+                "text": "Uncaught",
+                "exception": { "description": "SyntaxError: Failed to execute 'querySelectorAll' on 'Document': 'li[' is not a valid selector." }
+            }
+        })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let page = Page::new(client, "S1".to_string());
+
+    let error = page
+        .wait_for_element(&WaitForElementOptions::selector("li["))
+        .await
+        .unwrap_err()
+        .to_string();
+
+    assert!(error.contains("is not a valid selector"), "{error}");
+    assert_eq!(methods(&received).len(), 1);
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_delayed_results_in_chrome() {
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.unwrap();
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/wait/delayed_results.html");
+    browser
+        .navigate(&format!("file://{}", fixture.display()))
+        .await
+        .unwrap();
+    let page = browser.get_page().unwrap();
+
+    let results = page
+        .wait_for_element(&WaitForElementOptions::selector("li.result").with_timeout(5_000))
+        .await
+        .unwrap();
+    assert_eq!(results.count, 2);
+    assert!(results.waited_ms > 0);
+
+    let spinner = page
+        .wait_for_element(
+            &WaitForElementOptions::text("Loading results").with_state(ElementState::Hidden),
+        )
+        .await
+        .unwrap();
+    assert_eq!(spinner.count, 1);
+
+    let green = page
+        .wait_for_element(&WaitForElementOptions::text("green tea"))
+        .await
+        .unwrap();
+    assert_eq!(green.count, 1);
+    browser.stop().await.unwrap();
+}
@@ -0,0 +1,266 @@
+//! Tests for waiting on network responses
+
+mod common;
+
+use browsing::actor::Page;
+use browsing::actor::response::WaitForResponseOptions;
+use browsing::browser::cdp::CdpClient;
+use browsing::browser::{Browser, BrowserProfile};
+use browsing::tools::Tools;
+use browsing::tools::handlers::RESPONSE_METADATA_KEY;
+use common::{
+    EventScript, FakePageBrowser, HttpResponse, Received, fake_cdp_with_events, serve_http,
+};
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+const SEARCH_URL: &str = "https://shop.example/api/search?q=tea";
+const SEARCH_BODY: &str = r#"{"results":["Sencha","Assam"]}"#;
+
+fn response_received(session: &str, request_id: &str, url: &str, mime_type: &str) -> Value {
+    json!({
+        "method": "Network.responseReceived",
+        "sessionId": session,
+        "params": {
+            "requestId": request_id,
+            "type": "Fetch",
+            "response": { "url": url, "status": 200, "statusText": "OK", "mimeType": mime_type }
+        }
+    })
+}
+
+/// Network events of a page loading a stylesheet, then searching; the same
+/// search in another tab comes first
+fn search_events() -> EventScript {
+    Box::new(|method, _| {
+        if method != "Network.enable" {
+            return vec![];
+        }
+        vec![
+            response_received("S2", "R0", SEARCH_URL, "application/json"),
+            response_received("S1", "R1", "https://shop.example/style.css", "text/css"),
+            response_received("S1", "R2", SEARCH_URL, "application/json"),
+            json!({ "method": "Network.loadingFinished", "sessionId": "S1", "params": { "requestId": "R2" } }),
+        ]
+    })
+}
+
+async fn search_page() -> (Arc<CdpClient>, Received) {
+    fake_cdp_with_events(
+        Box::new(|method, _| match method {
+            "Network.getResponseBody" => Ok(json!({ "body": SEARCH_BODY, "base64Encoded": false })),
+            _ => Ok(json!({})),
+        }),
+        search_events(),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_wait_for_matching_response() {
+    let (client, received) = search_page().await;
+    let page = Page::new(client, "S1".to_string());
+
+    let response = page.wait_for_response("/api/search", 1000).await.unwrap();
+
+    assert_eq!(response.url, SEARCH_URL);
+    assert_eq!(response.status, 200);
+    assert_eq!(response.mime_type, "application/json");
+    assert_eq!(response.resource_type, "Fetch");
+    assert_eq!(response.body, None);
+    assert_eq!(common::methods(&received), ["Network.enable"]);
+}
+
+#[tokio::test]
+async fn test_wait_for_response_reads_capped_body() {
+    let (client, received) = search_page().await;
+    let page = Page::new(client, "S1".to_string());
+    let options = WaitForResponseOptions {
+        timeout_ms: 1000,
+        include_body: true,
+        max_body_bytes: 12,
+    };
+
+    let response = page
+        .wait_for_response_with_options("*/api/search?q=*", &options)
+        .await
+        .unwrap();
+
+    assert_eq!(response.body.as_deref(), Some(&SEARCH_BODY[..12]));
+    assert!(response.body_truncated);
+    let received = received.lock().unwrap();
+    let (method, params, session) = received.last().unwrap();
+    assert_eq!(method, "Network.getResponseBody");
+    assert_eq!(params, &json!({ "requestId": "R2" }));
+    assert_eq!(session.as_deref(), Some("S1"));
+}
+
+#[tokio::test]
+async fn test_wait_for_response_times_out() {
+    let (client, _) = search_page().await;
+    let page = Page::new(client, "S1".to_string());
+
+    let error = page
+        .wait_for_response(r"/\/api\/cart/", 200)
+        .await
+        .unwrap_err();
+
+    assert!(
+        error
+            .to_string()
+            .contains(r"No response matching '/\/api\/cart/' within 200ms")
+    );
+}
+
+#[tokio::test]
+async fn test_wait_for_response_action() {
+    let (client, _) = search_page().await;
+    let mut browser = FakePageBrowser { client };
+    let action = serde_json::from_value(json!({
+        "action_type": "wait_for_response",
+        "params": { "url": "/api/search", "timeout": 1, "include_body": true }
+    }))
+    .unwrap();
+
+    let result = Tools::default()
+        .act(action, &mut browser, None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        result.long_term_memory.as_deref(),
+        Some(format!("Received response 200 application/json from {SEARCH_URL}").as_str())
+    );
+    let content = result.extracted_content.unwrap();
+    assert!(content.contains("<untrusted_page_content>"));
+    assert!(content.contains(SEARCH_BODY));
+    let metadata = &result.metadata.unwrap()[RESPONSE_METADATA_KEY];
+    assert_eq!(metadata["status"], json!(200));
+    assert_eq!(metadata["body"], json!(SEARCH_BODY));
+}
+
+/// A page whose search response arrives while a click is dispatched
+async fn clicked_search_page() -> (Arc<CdpClient>, Received) {
+    fake_cdp_with_events(
+        Box::new(|_, _| Ok(json!({}))),
+        Box::new(|method, _| {
+            if method != "Input.dispatchMouseEvent" {
+                return vec![];
+            }
+            vec![response_received(
+                "S1",
+                "R1",
+                SEARCH_URL,
+                "application/json",
+            )]
+        }),
+    )
+    .await
+}
+
+async fn click(client: &CdpClient) {
+    client
+        .send_command_with_session("Input.dispatchMouseEvent", json!({}), Some("S1"))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_response_before_the_wait_is_seen_since_the_marker() {
+    let (client, _) = clicked_search_page().await;
+    let page = Page::new(client.clone(), "S1".to_string());
+
+    let mut marker = page.mark_responses().await.unwrap();
+    click(&client).await;
+
+    // A wait that starts after the response arrived misses it
+    assert!(page.wait_for_response("/api/search", 100).await.is_err());
+    let response = page
+        .wait_for_response_since(
+            &mut marker,
+            "/api/search",
+            &WaitForResponseOptions::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.url, SEARCH_URL);
+
+    // The response is used up by the first wait
+    let options = WaitForResponseOptions {
+        timeout_ms: 100,
+        ..Default::default()
+    };
+    assert!(
+        page.wait_for_response_since(&mut marker, "/api/search", &options)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_wait_for_response_action_sees_responses_since_the_mark() {
+    let (client, _) = clicked_search_page().await;
+    let mut browser = FakePageBrowser {
+        client: client.clone(),
+    };
+    let tools = Tools::default();
+    let wait = || {
+        serde_json::from_value(json!({
+            "action_type": "wait_for_response",
+            "params": { "url": "/api/search", "timeout": 1 }
+        }))
+        .unwrap()
+    };
+
+    tools.mark_responses(&browser).await;
+    click(&client).await;
+    let result = tools.act(wait(), &mut browser, None).await.unwrap();
+    assert_eq!(
+        result.long_term_memory.as_deref(),
+        Some(format!("Received response 200 application/json from {SEARCH_URL}").as_str())
+    );
+
+    // Without a marker only later responses count
+    tools.clear_response_marker().await;
+    click(&client).await;
+    assert!(tools.act(wait(), &mut browser, None).await.is_err());
+}
+
+/// Serve the search fixture and its API, answering the API only
+async fn serve_search_fixture() -> String {
+    serve_http(|request| {
+        if request.path().starts_with("/api/search") {
+            HttpResponse::ok("application/json", SEARCH_BODY)
+        } else {
+            HttpResponse::html(include_str!("fixtures/network/delayed_search.html"))
+        }
+    })
+    .await
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_wait_for_delayed_fetch() {
+    let url = serve_search_fixture().await;
+    let mut browser = Browser::new(BrowserProfile::new().with_headless(true));
+    browser.start().await.unwrap();
+    browser.navigate(&url).await.unwrap();
+    let page = browser.get_page().unwrap();
+
+    page.evaluate("document.getElementById('search').click()")
+        .await
+        .unwrap();
+    let options = WaitForResponseOptions {
+        include_body: true,
+        ..Default::default()
+    };
+    let response = page
+        .wait_for_response_with_options("/api/search", &options)
+        .await
+        .unwrap();
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.mime_type, "application/json");
+    assert_eq!(response.body.as_deref(), Some(SEARCH_BODY));
+    browser.stop().await.unwrap();
+}
@@ -0,0 +1,135 @@
+//! Tests for reading web app manifests and telling the model about them
+
+mod common;
+
+use async_trait::async_trait;
+use browsing::agent::service::Agent;
+use browsing::dom::DOMProcessorImpl;
+use browsing::error::{BrowsingError, Result};
+use browsing::llm::base::{ChatInvokeCompletion, ChatMessage, ChatModel};
+use browsing::traits::BrowserClient;
+use common::{FakePageBrowser, fake_cdp, methods};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+
+fn manifest_result() -> Value {
+    json!({
+        "url": "https://shop.example/manifest.webmanifest",
+        "errors": [],
+        "data": json!({
+            "name": "Shop Example",
+            "short_name": "Shop",
+            "start_url": "/app/",
+            "display": "standalone",
+            "theme_color": "#0a84ff",
+            "icons": [{ "src": "/icons/512.png", "sizes": "512x512", "type": "image/png", "purpose": "maskable" }]
+        })
+        .to_string()
+    })
+}
+
+/// Model that records the messages it is sent and finishes at once
+#[derive(Clone, Default)]
+struct RecordingLLM {
+    messages: Arc<Mutex<Vec<ChatMessage>>>,
+}
+
+#[async_trait]
+impl ChatModel for RecordingLLM {
+    fn model(&self) -> &str {
+        "recording"
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatInvokeCompletion<String>> {
+        self.messages.lock().unwrap().extend_from_slice(messages);
+        Ok(ChatInvokeCompletion {
+            completion:
+                json!({ "action": [{ "action_type": "done", "params": { "text": "ok" } }] })
+                    .to_string(),
+            thinking: None,
+            redacted_thinking: None,
+            usage: None,
+            stop_reason: None,
+        })
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+    ) -> Result<Box<dyn futures_util::stream::Stream<Item = Result<String>> + Send + Unpin>> {
+        Err(BrowsingError::Llm("Streaming is not supported".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn test_get_web_app_manifest() {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Page.getAppManifest" => Ok(manifest_result()),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let browser = FakePageBrowser { client };
+
+    let manifest = browser.get_web_app_manifest().await.unwrap().unwrap();
+
+    assert_eq!(manifest.name.as_deref(), Some("Shop Example"));
+    assert_eq!(
+        manifest.start_url.as_deref(),
+        Some("https://shop.example/app/")
+    );
+    assert_eq!(manifest.icons[0].src, "https://shop.example/icons/512.png");
+    assert_eq!(manifest.icons[0].purpose.as_deref(), Some("maskable"));
+    assert!(manifest.screenshots.is_empty());
+    assert_eq!(methods(&received), ["Page.getAppManifest"]);
+    assert_eq!(received.lock().unwrap()[0].2.as_deref(), Some("S1"));
+}
+
+#[tokio::test]
+async fn test_page_without_manifest() {
+    let (client, _) = fake_cdp(Box::new(|method, _| match method {
+        "Page.getAppManifest" => Ok(json!({ "url": "", "errors": [] })),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let browser = FakePageBrowser { client };
+
+    assert_eq!(browser.get_web_app_manifest().await.unwrap(), None);
+}
+
+async fn agent_prompt(manifest: Value) -> String {
+    let (client, _) = fake_cdp(Box::new(move |method, _| match method {
+        "Page.getAppManifest" => Ok(manifest.clone()),
+        _ => Ok(json!({})),
+    }))
+    .await;
+    let llm = RecordingLLM::default();
+    Agent::new(
+        "Order a blender".to_string(),
+        Box::new(FakePageBrowser { client }),
+        Box::new(DOMProcessorImpl::new()),
+        llm.clone(),
+    )
+    .with_max_steps(1)
+    .run()
+    .await
+    .unwrap();
+
+    let messages = llm.messages.lock().unwrap();
+    messages[1].content.clone()
+}
+
+#[tokio::test]
+async fn test_agent_is_told_about_web_apps() {
+    let prompt = agent_prompt(manifest_result()).await;
+    assert!(prompt.contains(
+        "Web app: This site is an installable web app (Shop Example), display standalone. \
+         Its entry point is https://shop.example/app/; start there"
+    ));
+
+    let prompt = agent_prompt(json!({ "url": "", "errors": [] })).await;
+    assert!(!prompt.contains("Web app:"));
+}
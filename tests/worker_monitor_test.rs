@@ -0,0 +1,128 @@
+//! Tests for read-only worker diagnostics
+
+mod common;
+
+use browsing::browser::{Browser, BrowserProfile, TabManager, WorkerMonitor};
+use common::{HttpResponse, fake_cdp, methods, serve_http};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn targets() -> serde_json::Value {
+    json!({
+        "targetInfos": [
+            {"targetId": "P1", "type": "page", "url": "https://example.com/", "title": "Example"},
+            {"targetId": "W1", "type": "service_worker", "url": "https://example.com/sw.js", "title": ""}
+        ]
+    })
+}
+
+#[tokio::test]
+async fn test_switching_to_worker_is_refused() {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Target.getTargets" => Ok(targets()),
+        _ => Ok(json!({})),
+    }))
+    .await;
+
+    let mut tabs = TabManager::new();
+    let err = tabs.switch_to_tab(&client, "W1").await.unwrap_err();
+
+    assert!(err.to_string().contains("service_worker"), "{err}");
+    assert!(tabs.current_target_id().is_none());
+    assert!(!methods(&received).contains(&"Target.attachToTarget".to_string()));
+}
+
+#[tokio::test]
+async fn test_monitor_attaches_to_existing_workers() {
+    let (client, received) = fake_cdp(Box::new(|method, _| match method {
+        "Target.getTargets" => Ok(targets()),
+        "Target.attachToTarget" => Ok(json!({"sessionId": "SW1"})),
+        _ => Ok(json!({})),
+    }))
+    .await;
+
+    let monitor = Arc::new(Mutex::new(WorkerMonitor::new()));
+    let task = WorkerMonitor::spawn(Arc::clone(&monitor), client)
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    task.abort();
+
+    let attached: Vec<_> = received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(method, _, _)| method == "Target.attachToTarget")
+        .map(|(_, params, _)| params["targetId"].clone())
+        .collect();
+    assert_eq!(attached, vec![json!("W1")]);
+}
+
+#[test]
+fn test_attach_workers_is_off_by_default() {
+    assert!(!BrowserProfile::default().attach_workers);
+    assert!(
+        Browser::new(BrowserProfile::default())
+            .worker_console_messages()
+            .is_empty()
+    );
+    assert!(
+        BrowserProfile::new()
+            .with_attach_workers(true)
+            .attach_workers
+    );
+}
+
+/// Serve the service worker fixture site on localhost (a secure context)
+async fn serve_fixture() -> String {
+    serve_http(|request| {
+        if request.path() == "/sw.js" {
+            HttpResponse::ok(
+                "text/javascript",
+                include_str!("fixtures/service_worker/sw.js"),
+            )
+        } else {
+            HttpResponse::html(include_str!("fixtures/service_worker/index.html"))
+        }
+    })
+    .await
+}
+
+#[tokio::test]
+#[ignore] // Requires actual Chrome installation
+async fn test_service_worker_console_is_captured() {
+    use browsing::traits::BrowserClient;
+
+    let url = serve_fixture().await;
+    let profile = BrowserProfile::new()
+        .with_headless(true)
+        .with_attach_workers(true);
+    let mut browser = Browser::new(profile);
+    browser.start().await.expect("Browser should start");
+    BrowserClient::navigate(&mut browser, &url).await.unwrap();
+
+    let mut messages = vec![];
+    for _ in 0..50 {
+        messages = browser.worker_console_messages();
+        if !messages.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(
+        messages
+            .iter()
+            .any(|m| m.text == "fixture service worker active" && m.target_type == "service_worker"),
+        "{messages:?}"
+    );
+
+    // Workers are listed as targets but never as tabs, and actions still go to the page
+    let all = browser.get_tabs(true).await.unwrap();
+    assert!(all.iter().any(|t| t.target_type == "service_worker"));
+    let tabs = browser.get_tabs(false).await.unwrap();
+    assert!(tabs.iter().all(|t| t.target_type == "page"));
+    assert_eq!(browser.get_current_url().await.unwrap(), url);
+
+    browser.stop().await.unwrap();
+}